@@ -0,0 +1,352 @@
+//! Backtesting of the arbitrage strategy against recorded MEV-Share hints:
+//! - hints are read from the history API, or from a store recorded by the
+//!   [HintIndexer](kazuka_mev_share_backend::HintIndexer),
+//! - the hints of a block are processed by the strategy on top of an Anvil fork
+//!   of the state before the block,
+//! - every bundle is executed on the fork after the txs of its hint (but not
+//!   the txs preceding them in the block), and the most profitable bundle of a
+//!   hint is assumed to land.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use alloy::{
+    network::ReceiptResponse,
+    primitives::{Address, Bytes, I256, TxHash, U256},
+    providers::{Provider, ProviderBuilder, ext::AnvilApi},
+    rpc::types::{
+        anvil::Forking,
+        mev::{BundleItem, MevSendBundle, mevshare::EventHistoryParams},
+    },
+    sol,
+};
+use alloy_node_bindings::Anvil;
+use anyhow::{Context, Result, bail};
+use futures_util::TryStreamExt;
+use kazuka_core::{
+    event_sources::mev_share_event_source::MevShareEvent,
+    executors::flashbots_bundle_executor::{AttributedBundle, SubmitBundle},
+    pnl::{PnlEvent, PnlEventKind, PnlLedger},
+    signer::SignerProvider,
+    types::{Strategy, Timestamped},
+};
+use kazuka_mev_share_arbitrage::{
+    discovery::WETH,
+    strategy::STRATEGY_NAME,
+    types::{Action, Event},
+};
+use kazuka_mev_share_backend::{HintQuery, HintStore, SqliteHintStore};
+use kazuka_mev_share_sse::EventClient;
+
+use crate::{arbitrage_strategy, config::Config};
+
+/// Hints of a block are emitted during the slot before it.
+const BLOCK_TIME: Duration = Duration::from_secs(12);
+
+sol! {
+    #[sol(rpc)]
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+    }
+}
+
+/// Options of the `backtest` command.
+#[derive(clap::Args, Debug)]
+pub struct BacktestArgs {
+    /// Archive node HTTP endpoint forked by Anvil.
+    #[arg(long, env = "KAZUKA_FORK_URL")]
+    pub fork_url: String,
+    /// First block of the hints.
+    #[arg(long)]
+    pub from_block: u64,
+    /// Last block of the hints.
+    #[arg(long)]
+    pub to_block: u64,
+    /// SQLite store of the hints recorded by the hint indexer, instead of
+    /// the history API.
+    #[arg(long)]
+    pub hints_db: Option<PathBuf>,
+    /// MEV-Share history endpoint.
+    #[arg(
+        long,
+        env = "KAZUKA_MEV_SHARE_HISTORY",
+        default_value = "https://mev-share.flashbots.net/api/v1/history"
+    )]
+    pub history_url: String,
+}
+
+/// Hint with the block its txs landed in.
+#[derive(Clone, Debug)]
+struct RecordedHint {
+    block: u64,
+    event: MevShareEvent,
+}
+
+/// Outcome of a bundle executed on the fork.
+#[derive(Clone, Copy, Debug)]
+struct Outcome {
+    /// WETH earned by the arbitrage contract (in wei).
+    revenue: U256,
+    /// Gas paid by the backrun txs (in wei).
+    gas_paid: U256,
+}
+
+impl Outcome {
+    fn net(&self) -> I256 {
+        I256::from_raw(self.revenue)
+            .saturating_sub(I256::from_raw(self.gas_paid))
+    }
+}
+
+/// Runs the backtest, see [crate::backtest].
+pub async fn run(mut config: Config, args: BacktestArgs) -> Result<()> {
+    if args.from_block == 0 || args.to_block < args.from_block {
+        bail!("invalid block range");
+    }
+    let archive = ProviderBuilder::new().connect_http(args.fork_url.parse()?);
+    let mut hints = match &args.hints_db {
+        Some(path) => stored_hints(path, &archive, &args).await?,
+        None => history_hints(&args).await?,
+    };
+    hints.sort_by_key(|hint| hint.block);
+    tracing::info!(
+        "Backtesting {} hints of blocks {}..={}",
+        hints.len(),
+        args.from_block,
+        args.to_block
+    );
+
+    let anvil = Anvil::new()
+        .fork(args.fork_url.clone())
+        .fork_block_number(args.from_block - 1)
+        .try_spawn()?;
+    let fork =
+        Arc::new(ProviderBuilder::new().connect_http(anvil.endpoint_url()));
+
+    // Bundles are only executed on the fork, and pools are refreshed for
+    // every block.
+    config.dry_run = false;
+    config.features.simulate = false;
+    config.features.live_pool_states = false;
+    config.features.manage_inventory = false;
+    let signers: Arc<dyn SignerProvider> =
+        Arc::new(config.keys.keyring().await?);
+    let mut strategy = arbitrage_strategy(
+        &config,
+        &config.strategy,
+        fork.clone(),
+        signers,
+    )?;
+    strategy.sync_state().await?;
+
+    let ledger = PnlLedger::new();
+    let contract = config.arb_contract_address()?;
+    for hints in hints.chunk_by(|a, b| a.block == b.block) {
+        let block = hints[0].block;
+        fork.anvil_reset(Some(Forking {
+            json_rpc_url: Some(args.fork_url.clone()),
+            block_number: Some(block - 1),
+        }))
+        .await?;
+        for hint in hints {
+            let actions = strategy
+                .process_event(Event::MevShareEvent(Timestamped::now(
+                    hint.event.clone(),
+                )))
+                .await;
+            let bundles: Vec<_> = actions
+                .into_iter()
+                .filter_map(|action| match action {
+                    Action::SubmitBundle(AttributedBundle {
+                        bundle: SubmitBundle::Mev(bundle),
+                        ..
+                    }) => Some(bundle),
+                    _ => None,
+                })
+                .collect();
+            if bundles.is_empty() {
+                continue;
+            }
+            ledger.record(PnlEvent::now(
+                STRATEGY_NAME,
+                PnlEventKind::Submitted,
+            ));
+
+            let Some(hint_txs) = hint_txs(&archive, &hint.event).await? else {
+                tracing::debug!(hint = ?hint.event.hash, "Hint txs not found");
+                continue;
+            };
+            let mut best: Option<Outcome> = None;
+            for bundle in &bundles {
+                let snapshot = fork.anvil_snapshot().await?;
+                let outcome = execute(
+                    fork.as_ref(),
+                    contract,
+                    &hint_txs,
+                    bundle,
+                )
+                .await;
+                fork.anvil_revert(snapshot).await?;
+                if let Some(outcome) = outcome?
+                    && best.is_none_or(|best| outcome.net() > best.net())
+                {
+                    best = Some(outcome);
+                }
+            }
+            if let Some(outcome) = best {
+                tracing::info!(
+                    block,
+                    hint = ?hint.event.hash,
+                    revenue = %outcome.revenue,
+                    gas_paid = %outcome.gas_paid,
+                    "Bundle would have landed"
+                );
+                ledger.record(PnlEvent::now(
+                    STRATEGY_NAME,
+                    PnlEventKind::Included {
+                        revenue: outcome.revenue,
+                    },
+                ));
+                ledger.record(PnlEvent::now(
+                    STRATEGY_NAME,
+                    PnlEventKind::GasPaid(outcome.gas_paid),
+                ));
+            }
+        }
+    }
+
+    let summary = ledger.strategy(STRATEGY_NAME);
+    tracing::info!(
+        hints = hints.len(),
+        backrun = summary.submissions,
+        landed = summary.inclusions,
+        revenue = %summary.revenue,
+        gas_paid = %summary.gas_paid,
+        net = %summary.net(),
+        "Backtest done"
+    );
+    Ok(())
+}
+
+async fn history_hints(args: &BacktestArgs) -> Result<Vec<RecordedHint>> {
+    let params = EventHistoryParams {
+        block_start: Some(args.from_block),
+        block_end: Some(args.to_block),
+        ..Default::default()
+    };
+    Ok(EventClient::default()
+        .historical_events_stream(&args.history_url, params)
+        .map_ok(|event| RecordedHint {
+            block: event.block,
+            event: event.hint,
+        })
+        .try_collect()
+        .await?)
+}
+
+/// Hints of the store emitted around the blocks. The blocks of hints
+/// recorded from the live stream are looked up from their receipts.
+async fn stored_hints(
+    path: &Path,
+    archive: &impl Provider,
+    args: &BacktestArgs,
+) -> Result<Vec<RecordedHint>> {
+    let timestamp = async |block: u64| -> Result<u64> {
+        let block = archive
+            .get_block_by_number(block.into())
+            .await?
+            .with_context(|| format!("unknown block {block}"))?;
+        Ok(block.header.timestamp)
+    };
+    let query = HintQuery::new()
+        .since(
+            timestamp(args.from_block)
+                .await?
+                .saturating_sub(BLOCK_TIME.as_secs()),
+        )
+        .until(timestamp(args.to_block).await?);
+    let store = SqliteHintStore::open(path)?;
+
+    let mut hints = vec![];
+    for hint in store.query(&query).await? {
+        let block = match hint.block {
+            Some(block) => Some(block),
+            None => archive
+                .get_transaction_receipt(hint.event.hash)
+                .await?
+                .and_then(|receipt| receipt.block_number()),
+        };
+        if let Some(block) = block
+            && (args.from_block..=args.to_block).contains(&block)
+        {
+            hints.push(RecordedHint {
+                block,
+                event: hint.event,
+            });
+        }
+    }
+    Ok(hints)
+}
+
+/// Signed txs of the hint, unless some of them never landed.
+async fn hint_txs(
+    archive: &impl Provider,
+    event: &MevShareEvent,
+) -> Result<Option<Vec<Bytes>>> {
+    let mut hashes: Vec<TxHash> =
+        event.transactions.iter().filter_map(|tx| tx.hash).collect();
+    if hashes.is_empty() {
+        hashes.push(event.hash);
+    }
+    let mut txs = vec![];
+    for hash in hashes {
+        match archive.get_raw_transaction_by_hash(hash).await? {
+            Some(tx) => txs.push(tx),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(txs))
+}
+
+/// Executes the txs of the hint and then the backrun txs of the bundle,
+/// unless the node rejects the former, or the latter revert.
+async fn execute(
+    fork: &impl Provider,
+    contract: Address,
+    hint_txs: &[Bytes],
+    bundle: &MevSendBundle,
+) -> Result<Option<Outcome>> {
+    for tx in hint_txs {
+        match fork.send_raw_transaction(tx).await {
+            Ok(pending) => pending.get_receipt().await?,
+            // E.g. a nonce gap left by the txs of the block before the hint.
+            Err(err) if err.as_error_resp().is_some() => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+    }
+
+    let weth = IERC20::new(WETH, fork);
+    let balance = weth.balanceOf(contract).call().await?;
+    let mut gas_paid = U256::ZERO;
+    for item in &bundle.bundle_body {
+        let BundleItem::Tx { tx, .. } = item else {
+            continue;
+        };
+        let receipt =
+            fork.send_raw_transaction(tx).await?.get_receipt().await?;
+        if !receipt.status() {
+            return Ok(None);
+        }
+        gas_paid += U256::from(receipt.gas_used)
+            * U256::from(receipt.effective_gas_price);
+    }
+    let revenue = weth
+        .balanceOf(contract)
+        .call()
+        .await?
+        .saturating_sub(balance);
+    Ok(Some(Outcome { revenue, gas_paid }))
+}
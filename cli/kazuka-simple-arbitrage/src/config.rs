@@ -0,0 +1,623 @@
+//! Configuration of the CLI, loaded from a TOML file, whose values are
+//! overridden by environment variables and flags, see [Args].
+//!
+//! ```toml
+//! dry_run = true
+//! arb_contract_address = "0x0000000000000000000000000000000000000001"
+//! bundle_store = "bundles.db"
+//!
+//! [endpoints]
+//! wss = "ws://localhost:8546"
+//! relays = ["https://relay.flashbots.net:443", "https://rpc.titanbuilder.xyz"]
+//!
+//! [keys.transactions]
+//! keystore_path = "keys/tx_signer.json"
+//! keystore_password_file = "keys/tx_signer.password"
+//!
+//! [keys.reputation]
+//! keystore_path = "keys/flashbots_signer.json"
+//! keystore_password_file = "keys/flashbots_signer.password"
+//!
+//! [features]
+//! discover_pools = true
+//! bundle_feedback = true
+//!
+//! [telemetry]
+//! metrics_addr = "127.0.0.1:9090"
+//!
+//! [telemetry.logs]
+//! level = "debug"
+//! format = "json"
+//! directory = "logs"
+//!
+//! [telemetry.logs.otlp]
+//! endpoint = "http://tempo:4317"
+//!
+//! [strategy]
+//! sizing = "optimal"
+//! payment_percentage = 90
+//!
+//! # Strategies sharing the hints, the `v2-v3` strategy with the parameters
+//! # of the `strategy` table by default.
+//! [[strategies]]
+//! kind = "v2-v3"
+//!
+//! [[strategies]]
+//! kind = "fee-tier"
+//! params = { payment_percentage = 80 }
+//! ```
+
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use alloy::primitives::Address;
+use anyhow::{Context, Result, bail};
+use kazuka_core::{
+    signer::{KazukaSigner, KeyPurpose, Keyring},
+    telemetry::LogConfig,
+};
+use kazuka_mev_share_arbitrage::config::ArbitrageConfig;
+use serde::Deserialize;
+
+use crate::Args;
+
+/// Configuration of the CLI, see [crate::config].
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether to actually submit bundles or just log them.
+    pub dry_run: bool,
+    /// Address of the arbitrage contract.
+    pub arb_contract_address: Option<Address>,
+    /// SQLite database recording submitted bundles, relay responses and
+    /// outcomes.
+    pub bundle_store: Option<PathBuf>,
+    pub endpoints: EndpointsConfig,
+    pub keys: KeysConfig,
+    pub features: FeaturesConfig,
+    pub telemetry: TelemetryConfig,
+    /// Strategy parameters.
+    pub strategy: ArbitrageConfig,
+    /// Strategies to run, see [Config::strategies].
+    pub strategies: Vec<StrategyConfig>,
+}
+
+/// Kind of a strategy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StrategyKind {
+    /// Arbitrage between Uniswap V2 and V3 pools of a token, see
+    /// [MevShareUniswapV2V3Arbitrage](kazuka_mev_share_arbitrage::strategy::MevShareUniswapV2V3Arbitrage).
+    V2V3,
+    /// Arbitrage between Uniswap V3 pools of different fee tiers, see
+    /// [MevShareUniswapV3FeeTierArbitrage](kazuka_mev_share_arbitrage::fee_tier::MevShareUniswapV3FeeTierArbitrage).
+    FeeTier,
+}
+
+/// Strategy to run, along with its parameters.
+#[derive(Clone, Debug, Deserialize)]
+pub struct StrategyConfig {
+    pub kind: StrategyKind,
+    /// Parameters of the strategy, the `strategy` table by default.
+    #[serde(default)]
+    pub params: Option<ArbitrageConfig>,
+}
+
+/// Nodes and relays to connect to.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct EndpointsConfig {
+    /// Ethereum node WS endpoint.
+    pub wss: Option<String>,
+    /// MEV-Share SSE endpoint the hints are streamed from.
+    pub mev_share_sse: String,
+    /// Relays the bundles are submitted to. The first one also simulates
+    /// bundles and reports their outcomes.
+    pub relays: Vec<String>,
+}
+
+impl Default for EndpointsConfig {
+    fn default() -> Self {
+        Self {
+            wss: None,
+            mev_share_sse: "https://mev-share.flashbots.net".to_string(),
+            relays: vec!["https://relay.flashbots.net:443".to_string()],
+        }
+    }
+}
+
+/// Keys of the signers, see [KeyPurpose].
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct KeysConfig {
+    /// Key signing txs, which holds the funds.
+    pub transactions: KeyConfig,
+    /// Key signing the bundles submitted to the relays.
+    pub reputation: KeyConfig,
+}
+
+impl KeysConfig {
+    /// Loads the keys into a [Keyring].
+    pub async fn keyring(&self) -> Result<Keyring> {
+        Ok(Keyring::new()
+            .with_signer(
+                KeyPurpose::Transactions,
+                self.transactions.signer("tx signer").await?,
+            )
+            .with_signer(
+                KeyPurpose::Reputation,
+                self.reputation.signer("Flashbots signer").await?,
+            ))
+    }
+
+    fn relative_to(mut self, dir: &Path) -> Self {
+        self.transactions = self.transactions.relative_to(dir);
+        self.reputation = self.reputation.relative_to(dir);
+        self
+    }
+}
+
+/// Key of a signer: a private key, an encrypted JSON keystore, or an
+/// account of a Ledger. Private keys are better passed as env vars than as
+/// flags, which leak into the shell history and the process list.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct KeyConfig {
+    /// Hex-encoded private key.
+    pub private_key: Option<String>,
+    /// Encrypted JSON keystore.
+    pub keystore_path: Option<PathBuf>,
+    /// File with the password of the keystore.
+    pub keystore_password_file: Option<PathBuf>,
+    /// Password of the keystore, instead of the `keystore_password_file`.
+    pub keystore_password: Option<String>,
+    /// Index of the account of a Ledger in the Ledger Live derivation path.
+    /// Requires the `ledger` feature.
+    pub ledger_index: Option<usize>,
+}
+
+impl std::fmt::Debug for KeyConfig {
+    /// Keeps the key and the password out of the logs.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyConfig")
+            .field("keystore_path", &self.keystore_path)
+            .field(
+                "keystore_password_file",
+                &self.keystore_password_file,
+            )
+            .field("ledger_index", &self.ledger_index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KeyConfig {
+    async fn signer(&self, name: &str) -> Result<KazukaSigner> {
+        match (
+            &self.private_key,
+            &self.keystore_path,
+            self.ledger_index,
+        ) {
+            (Some(pk), None, None) => Ok(KazukaSigner::from_private_key(pk)?),
+            (None, Some(path), None) => {
+                KazukaSigner::from_keystore(path, self.keystore_password()?)
+                    .with_context(|| {
+                        format!(
+                            "failed to load keystore {}",
+                            path.display()
+                        )
+                    })
+            }
+            (None, None, Some(index)) => Self::ledger_signer(index).await,
+            (None, None, None) => {
+                bail!("missing {name} private key, keystore or Ledger index")
+            }
+            _ => bail!(
+                "only one of a private key, keystore or Ledger index is \
+                 expected for the {name}"
+            ),
+        }
+    }
+
+    /// Password of the keystore, read from the password file without its
+    /// trailing newline, if any.
+    fn keystore_password(&self) -> Result<String> {
+        match (
+            &self.keystore_password,
+            &self.keystore_password_file,
+        ) {
+            (Some(password), _) => Ok(password.clone()),
+            (None, Some(path)) => {
+                let password =
+                    std::fs::read_to_string(path).with_context(|| {
+                        format!("failed to read {}", path.display())
+                    })?;
+                Ok(password.trim_end_matches(['\n', '\r']).to_string())
+            }
+            (None, None) => Ok(String::new()),
+        }
+    }
+
+    #[cfg(feature = "ledger")]
+    async fn ledger_signer(index: usize) -> Result<KazukaSigner> {
+        Ok(KazukaSigner::from_ledger(index, None).await?)
+    }
+
+    #[cfg(not(feature = "ledger"))]
+    async fn ledger_signer(_: usize) -> Result<KazukaSigner> {
+        bail!("Ledger signers require the `ledger` feature")
+    }
+
+    /// Overrides the key, and the password, with the ones of `other` which
+    /// are set.
+    fn with(mut self, other: Self) -> Self {
+        if other.private_key.is_some()
+            || other.keystore_path.is_some()
+            || other.ledger_index.is_some()
+        {
+            self.private_key = other.private_key;
+            self.keystore_path = other.keystore_path;
+            self.ledger_index = other.ledger_index;
+        }
+        if other.keystore_password.is_some()
+            || other.keystore_password_file.is_some()
+        {
+            self.keystore_password = other.keystore_password;
+            self.keystore_password_file = other.keystore_password_file;
+        }
+        self
+    }
+
+    fn relative_to(mut self, dir: &Path) -> Self {
+        for path in [&mut self.keystore_path, &mut self.keystore_password_file]
+            .into_iter()
+            .flatten()
+        {
+            if path.is_relative() {
+                *path = dir.join(&*path);
+            }
+        }
+        self
+    }
+}
+
+/// Optional parts of the strategy, all disabled by default.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FeaturesConfig {
+    pub discover_pools: bool,
+    pub simulate: bool,
+    pub fee_tier_arbitrage: bool,
+    pub triangular_arbitrage: bool,
+    pub live_pool_states: bool,
+    pub bundle_feedback: bool,
+    pub screen_tokens: bool,
+    pub manage_inventory: bool,
+}
+
+/// Logging and metrics settings.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    pub logs: LogConfig,
+    /// Address to serve `/healthz` and `/metrics` on, see [crate::metrics].
+    pub metrics_addr: Option<SocketAddr>,
+}
+
+impl Config {
+    /// Loads the config file, if any, and overrides it with the env vars
+    /// and flags.
+    pub fn load(args: Args) -> Result<Self> {
+        let config = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.with_args(args)
+    }
+
+    /// Loads the config from a TOML file. Relative paths are resolved
+    /// against the directory of the file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let config = Self::from_toml(&contents)?;
+        Ok(config.relative_to(path.parent().unwrap_or(Path::new(""))))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let mut config: Self = toml::from_str(contents)?;
+        config.strategy = config.strategy.validate()?;
+        for strategy in &mut config.strategies {
+            strategy.params = strategy
+                .params
+                .take()
+                .map(|params| params.validate())
+                .transpose()?;
+        }
+        Ok(config)
+    }
+
+    fn relative_to(mut self, dir: &Path) -> Self {
+        self.strategy = self.strategy.relative_to(dir);
+        for strategy in &mut self.strategies {
+            strategy.params =
+                strategy.params.take().map(|params| params.relative_to(dir));
+        }
+        self.keys = self.keys.relative_to(dir);
+        if let Some(bundle_store) = &mut self.bundle_store
+            && bundle_store.is_relative()
+        {
+            *bundle_store = dir.join(&*bundle_store);
+        }
+        if let Some(directory) = &mut self.telemetry.logs.directory
+            && directory.is_relative()
+        {
+            *directory = dir.join(&*directory);
+        }
+        self
+    }
+
+    /// Overrides the config with the env vars and flags that are set.
+    /// Features enabled by flags are added to the ones of the config.
+    fn with_args(mut self, args: Args) -> Result<Self> {
+        self.keys.transactions =
+            self.keys.transactions.with(args.tx_signer_key());
+        self.keys.reputation =
+            self.keys.reputation.with(args.flashbots_signer_key());
+        self.dry_run |= args.dry_run;
+        if let Some(address) = args.arb_contract_address {
+            self.arb_contract_address = Some(address);
+        }
+        if let Some(wss) = args.wss {
+            self.endpoints.wss = Some(wss);
+        }
+        if let Some(mev_share_sse) = args.mev_share_sse {
+            self.endpoints.mev_share_sse = mev_share_sse;
+        }
+        if !args.relays.is_empty() {
+            self.endpoints.relays = args.relays;
+        }
+        if let Some(path) = args.strategy_config {
+            self.strategy = ArbitrageConfig::from_file(path)?;
+        }
+        if let Some(log_level) = args.log_level {
+            self.telemetry.logs.level = log_level;
+        }
+        if let Some(log_format) = args.log_format {
+            self.telemetry.logs.format = log_format;
+        }
+        if let Some(log_dir) = args.log_dir {
+            self.telemetry.logs.directory = Some(log_dir);
+        }
+        if let Some(endpoint) = args.otlp_endpoint {
+            let otlp = self.telemetry.logs.otlp.get_or_insert_default();
+            otlp.endpoint = endpoint;
+        }
+        if let Some(metrics_addr) = args.metrics_addr {
+            self.telemetry.metrics_addr = Some(metrics_addr);
+        }
+
+        let features = &mut self.features;
+        features.discover_pools |= args.discover_pools;
+        features.simulate |= args.simulate;
+        features.fee_tier_arbitrage |= args.fee_tier_arbitrage;
+        features.triangular_arbitrage |= args.triangular_arbitrage;
+        features.live_pool_states |= args.live_pool_states;
+        features.bundle_feedback |= args.bundle_feedback;
+        features.screen_tokens |= args.screen_tokens;
+        features.manage_inventory |= args.manage_inventory;
+
+        if self.endpoints.relays.is_empty() {
+            bail!("at least one relay is required");
+        }
+        Ok(self)
+    }
+
+    /// Strategies to run along with their parameters: the `strategies` of
+    /// the config, or the `v2-v3` strategy otherwise, and the `fee-tier`
+    /// one if enabled by the `fee_tier_arbitrage` feature.
+    pub fn strategies(&self) -> Vec<(StrategyKind, &ArbitrageConfig)> {
+        let mut strategies: Vec<_> = self
+            .strategies
+            .iter()
+            .map(|strategy| {
+                (
+                    strategy.kind,
+                    strategy.params.as_ref().unwrap_or(&self.strategy),
+                )
+            })
+            .collect();
+        if strategies.is_empty() {
+            strategies.push((StrategyKind::V2V3, &self.strategy));
+        }
+        if self.features.fee_tier_arbitrage
+            && !strategies
+                .iter()
+                .any(|(kind, _)| *kind == StrategyKind::FeeTier)
+        {
+            strategies.push((StrategyKind::FeeTier, &self.strategy));
+        }
+        strategies
+    }
+
+    pub fn wss(&self) -> Result<&str> {
+        self.endpoints
+            .wss
+            .as_deref()
+            .context("missing node WS endpoint")
+    }
+
+    pub fn arb_contract_address(&self) -> Result<Address> {
+        self.arb_contract_address
+            .context("missing arbitrage contract address")
+    }
+
+    /// Relay simulating bundles and reporting their outcomes.
+    pub fn primary_relay(&self) -> &str {
+        &self.endpoints.relays[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use kazuka_core::telemetry::LogFormat;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+        arb_contract_address = "0x0000000000000000000000000000000000000001"
+        bundle_store = "bundles.db"
+
+        [endpoints]
+        wss = "ws://localhost:8546"
+
+        [keys.transactions]
+        keystore_path = "keys/tx_signer.json"
+        keystore_password_file = "keys/tx_signer.password"
+
+        [features]
+        discover_pools = true
+
+        [strategy]
+        payment_percentage = 90
+    "#;
+
+    #[test]
+    fn test_from_toml() {
+        let config = Config::from_toml(CONFIG)
+            .unwrap()
+            .relative_to(Path::new("/etc/kazuka"));
+
+        assert_eq!(
+            config.wss().unwrap(),
+            "ws://localhost:8546"
+        );
+        let key = &config.keys.transactions;
+        assert_eq!(
+            key.keystore_path,
+            Some(PathBuf::from(
+                "/etc/kazuka/keys/tx_signer.json"
+            ))
+        );
+        assert_eq!(
+            key.keystore_password_file,
+            Some(PathBuf::from(
+                "/etc/kazuka/keys/tx_signer.password"
+            ))
+        );
+        assert_eq!(
+            config.bundle_store,
+            Some(PathBuf::from("/etc/kazuka/bundles.db"))
+        );
+        assert_eq!(config.strategy.payment_percentage, 90);
+        assert_eq!(
+            config.primary_relay(),
+            "https://relay.flashbots.net:443"
+        );
+        assert!(!config.dry_run);
+    }
+
+    #[test]
+    fn test_args_override_config() {
+        let args = Args::parse_from([
+            "kazuka-simple-arbitrage",
+            "--wss",
+            "ws://node:8546",
+            "--tx-signer-pk",
+            "0x01",
+            "--dry-run",
+            "--simulate",
+            "--log-format",
+            "json",
+        ]);
+        let config =
+            Config::from_toml(CONFIG).unwrap().with_args(args).unwrap();
+
+        assert_eq!(config.wss().unwrap(), "ws://node:8546");
+        let key = &config.keys.transactions;
+        assert_eq!(key.private_key.as_deref(), Some("0x01"));
+        // Keys replace each other, whatever their kind.
+        assert_eq!(key.keystore_path, None);
+        // Unlike the password of the replaced keystore.
+        assert!(key.keystore_password_file.is_some());
+        assert!(config.dry_run);
+        assert!(config.features.discover_pools);
+        assert!(config.features.simulate);
+        assert_eq!(
+            config.telemetry.logs.format,
+            LogFormat::Json
+        );
+    }
+
+    #[test]
+    fn test_strategies() {
+        let config = Config::from_toml(CONFIG).unwrap();
+        let kinds = |config: &Config| {
+            config
+                .strategies()
+                .iter()
+                .map(|(kind, _)| *kind)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kinds(&config), [StrategyKind::V2V3]);
+
+        let config = Config::from_toml(
+            r#"
+            [strategy]
+            payment_percentage = 90
+
+            [[strategies]]
+            kind = "fee-tier"
+            params = { payment_percentage = 80 }
+
+            [[strategies]]
+            kind = "v2-v3"
+            "#,
+        )
+        .unwrap();
+        let strategies = config.strategies();
+        assert_eq!(
+            kinds(&config),
+            [StrategyKind::FeeTier, StrategyKind::V2V3]
+        );
+        assert_eq!(strategies[0].1.payment_percentage, 80);
+        assert_eq!(strategies[1].1.payment_percentage, 90);
+
+        assert!(
+            Config::from_toml(
+                r#"
+                [[strategies]]
+                kind = "v2-v3"
+                params = { payment_percentage = 101 }
+                "#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_keystore_password_file() {
+        let path = std::env::temp_dir().join("kazuka-keystore-password");
+        std::fs::write(&path, "secret\n").unwrap();
+        let key = KeyConfig {
+            keystore_password_file: Some(path),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            key.keystore_password().unwrap(),
+            "secret"
+        );
+        let key = KeyConfig {
+            keystore_password: Some("other".to_string()),
+            ..key
+        };
+        assert_eq!(
+            key.keystore_password().unwrap(),
+            "other"
+        );
+    }
+}
@@ -1,110 +1,402 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
 use alloy::{
+    network::AnyNetwork,
     primitives::Address,
-    providers::{ProviderBuilder, WsConnect},
-    signers::local::PrivateKeySigner,
+    providers::{DynProvider, Provider, ProviderBuilder, WsConnect},
 };
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use kazuka_core::{
     engine::Engine,
-    event_sources::mev_share_event_source::MevShareEventSource,
-    types::{EventSourceMap, ExecutorMap},
+    event_sources::{
+        block_event_source::BlockEventSource,
+        bundle_stats_event_source::{BundleStatsEventSource, BundleTracker},
+        log_event_source::LogEventSource,
+        mev_share_event_source::MevShareEventSource,
+    },
+    executors::{
+        action_dedup::ActionDedup,
+        flashbots_bundle_executor::{
+            AttributedBundle, FlashbotsBundleExecutor,
+        },
+        mempool_executor::MempoolExecutor,
+        nonce_manager::NonceManager,
+    },
+    health::Health,
+    pnl::PnlLedger,
+    signer::{KeyPurpose, SignerProvider},
+    telemetry::{LogFormat, init_logging},
+    types::{EventSourceMap, ExecutorMap, Named, Strategy, Timestamped},
 };
 use kazuka_mev_share_arbitrage::{
-    executor::MevShareExecutor,
+    config::ArbitrageConfig,
+    discovery::PoolDiscoveryConfig,
+    executor::{flashbots_client, mev_share_client},
+    fee_tier::MevShareUniswapV3FeeTierArbitrage,
+    pool_state::PoolStateCache,
     strategy::MevShareUniswapV2V3Arbitrage,
     types::{Action, Event},
 };
-use tracing::Level;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use kazuka_mev_share_backend::{BundleStore, SqliteBundleStore};
+
+use crate::{
+    backtest::BacktestArgs,
+    config::{Config, KeyConfig, StrategyKind},
+    pools::PoolsCommand,
+};
 
-/// CLI options.
+mod backtest;
+mod config;
+mod metrics;
+mod pools;
+
+/// Window in which bundles with the same hash are only submitted once.
+const BUNDLE_DEDUP_WINDOW: Duration = Duration::from_secs(24);
+
+/// CLI options, which override the ones of the `config` file, see
+/// [config::Config].
 #[derive(Parser, Debug)]
 struct Args {
+    /// Runs the strategies against the live hints, unless another command
+    /// is given.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Path to a TOML file with the settings of the CLI.
+    #[arg(long, env = "KAZUKA_CONFIG")]
+    pub config: Option<PathBuf>,
     /// Ethereum node WS endpoint.
-    #[arg(long)]
-    pub wss: String,
-    /// Private key for sending txs.
-    #[arg(long)]
-    pub tx_signer_pk: String,
+    #[arg(long, env = "KAZUKA_WSS")]
+    pub wss: Option<String>,
+    /// MEV-Share SSE endpoint.
+    #[arg(long, env = "KAZUKA_MEV_SHARE_SSE")]
+    pub mev_share_sse: Option<String>,
+    /// Relays to submit bundles to, the first of which simulates them and
+    /// reports their outcomes.
+    #[arg(long = "relay", env = "KAZUKA_RELAYS", value_delimiter = ',')]
+    pub relays: Vec<String>,
+    /// Private key for sending txs. Prefer the env var, or a keystore, as
+    /// flags leak into the shell history and the process list.
+    #[arg(long, env = "KAZUKA_TX_SIGNER_PK", hide_env_values = true)]
+    pub tx_signer_pk: Option<String>,
+    /// Encrypted JSON keystore of the key for sending txs.
+    #[arg(long, env = "KAZUKA_KEYSTORE_PATH", conflicts_with = "tx_signer_pk")]
+    pub keystore_path: Option<PathBuf>,
+    /// File with the password of the `keystore_path`.
+    #[arg(long, env = "KAZUKA_KEYSTORE_PASSWORD_FILE")]
+    pub keystore_password_file: Option<PathBuf>,
+    /// Password of the `keystore_path`, instead of the
+    /// `keystore_password_file`.
+    #[arg(
+        long,
+        env = "KAZUKA_KEYSTORE_PASSWORD",
+        hide_env_values = true,
+        conflicts_with = "keystore_password_file"
+    )]
+    pub keystore_password: Option<String>,
+    /// Index of the Ledger account for sending txs, in the Ledger Live
+    /// derivation path. Requires the `ledger` feature.
+    #[arg(
+        long,
+        env = "KAZUKA_LEDGER_INDEX",
+        conflicts_with_all = ["tx_signer_pk", "keystore_path"]
+    )]
+    pub ledger_index: Option<usize>,
     /// Private key for MEV-Share signer.
-    #[arg(long)]
-    pub flashbots_signer_pk: String,
+    #[arg(long, env = "KAZUKA_FLASHBOTS_SIGNER_PK", hide_env_values = true)]
+    pub flashbots_signer_pk: Option<String>,
+    /// Encrypted JSON keystore of the MEV-Share signer.
+    #[arg(
+        long,
+        env = "KAZUKA_FLASHBOTS_KEYSTORE_PATH",
+        conflicts_with = "flashbots_signer_pk"
+    )]
+    pub flashbots_keystore_path: Option<PathBuf>,
+    /// File with the password of the `flashbots_keystore_path`.
+    #[arg(long, env = "KAZUKA_FLASHBOTS_KEYSTORE_PASSWORD_FILE")]
+    pub flashbots_keystore_password_file: Option<PathBuf>,
     /// Address of the arbitrage contract.
-    #[arg(long)]
-    pub arb_contract_address: String,
+    #[arg(long, env = "KAZUKA_ARB_CONTRACT_ADDRESS")]
+    pub arb_contract_address: Option<Address>,
+    /// Level of the logs, e.g. `info` or `debug`.
+    #[arg(long, env = "KAZUKA_LOG_LEVEL")]
+    pub log_level: Option<String>,
+    /// Format of the logs, `json` or `pretty`.
+    #[arg(long, env = "KAZUKA_LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
+    /// Directory to write the logs to, rotated daily, instead of stdout.
+    #[arg(long, env = "KAZUKA_LOG_DIR")]
+    pub log_dir: Option<PathBuf>,
+    /// gRPC endpoint of an OpenTelemetry collector to export the spans to,
+    /// e.g. `http://localhost:4317`. Requires the `otlp` feature.
+    #[arg(long, env = "KAZUKA_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+    /// Address to serve the health (`/healthz`) and Prometheus metrics
+    /// (`/metrics`) of the bot on, e.g. `127.0.0.1:9090`.
+    #[arg(long, env = "KAZUKA_METRICS_ADDR")]
+    pub metrics_addr: Option<SocketAddr>,
     /// Whether to actually submit bundles or just log them.
     #[arg(long, action)]
     pub dry_run: bool,
+    /// Whether to discover pools from factory logs instead of loading them
+    /// from the bundled CSV.
+    #[arg(long, action)]
+    pub discover_pools: bool,
+    /// Path to a TOML or JSON file with the strategy parameters, instead of
+    /// the `strategy` table of the `config` file.
+    #[arg(long, env = "KAZUKA_STRATEGY_CONFIG")]
+    pub strategy_config: Option<PathBuf>,
+    /// Whether to simulate bundles on the relay, and only submit profitable
+    /// ones.
+    #[arg(long, action)]
+    pub simulate: bool,
+    /// Whether to also arbitrage Uniswap V3 pools of different fee tiers.
+    #[arg(long, action)]
+    pub fee_tier_arbitrage: bool,
+    /// Whether to also arbitrage triangular cycles through the pools of the
+    /// `graph_pools_file` of the config.
+    #[arg(long, action)]
+    pub triangular_arbitrage: bool,
+    /// Whether to keep the states of the pools in memory, updated from their
+    /// logs, instead of fetching them for every hint.
+    #[arg(long, action)]
+    pub live_pool_states: bool,
+    /// Whether to track the outcomes of the submitted bundles, and adapt the
+    /// bids to them.
+    #[arg(long, action)]
+    pub bundle_feedback: bool,
+    /// Whether to screen the tokens of the pools, e.g. for transfer fees,
+    /// before trading them.
+    #[arg(long, action)]
+    pub screen_tokens: bool,
+    /// Whether to cap the backruns to the WETH balance of the arbitrage
+    /// contract, and keep it within the bounds of the config with transfers
+    /// from (and to) the tx signer, which must own the contract.
+    #[arg(long, action)]
+    pub manage_inventory: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Runs the strategy against recorded hints on an Anvil fork, and
+    /// reports the bundles which would have landed, along with their PnL.
+    Backtest(BacktestArgs),
+    /// Manages the pools file of the strategy.
+    Pools {
+        #[command(subcommand)]
+        command: PoolsCommand,
+    },
+}
+
+impl Args {
+    fn tx_signer_key(&self) -> KeyConfig {
+        KeyConfig {
+            private_key: self.tx_signer_pk.clone(),
+            keystore_path: self.keystore_path.clone(),
+            keystore_password_file: self.keystore_password_file.clone(),
+            keystore_password: self.keystore_password.clone(),
+            ledger_index: self.ledger_index,
+        }
+    }
+
+    fn flashbots_signer_key(&self) -> KeyConfig {
+        KeyConfig {
+            private_key: self.flashbots_signer_pk.clone(),
+            keystore_path: self.flashbots_keystore_path.clone(),
+            keystore_password_file: self
+                .flashbots_keystore_password_file
+                .clone(),
+            ..Default::default()
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let target_filter = tracing_subscriber::filter::Targets::new()
-        .with_target("kazuka_simple_arbitrage", Level::INFO)
-        .with_target("kazuka_core", Level::INFO)
-        .with_target("kazuka_mev_share_backend", Level::INFO)
-        .with_target("kazuka_mev_share_sse", Level::INFO)
-        .with_target(
-            "kazuka_mev_share_arbitrage",
-            Level::INFO,
-        );
+    let mut args = Args::parse();
+    let command = args.command.take();
+    let config = Config::load(args)?;
 
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer().with_ansi(true).pretty())
-        .with(target_filter)
-        .init();
+    let _guard = init_logging(
+        &config.telemetry.logs,
+        &["kazuka_simple_arbitrage"],
+    )?;
 
-    let args = Args::parse();
+    tracing::debug!(?config, "Loaded config");
+
+    match command {
+        Some(Command::Backtest(args)) => backtest::run(config, args).await,
+        Some(Command::Pools { command }) => pools::run(config, command).await,
+        None => run(config).await,
+    }
+}
 
-    let ws = WsConnect::new(args.wss);
+/// Runs the strategies of the config against the live hints.
+async fn run(config: Config) -> Result<()> {
+    let health = Health::new();
+    if let Some(addr) = config.telemetry.metrics_addr {
+        metrics::serve(addr, health.clone()).await?;
+    }
+
+    let ws = WsConnect::new(config.wss()?);
 
     tracing::info!("Strating probablistic blind arbitrage strategy...");
 
-    let tx_signer: PrivateKeySigner = args.tx_signer_pk.parse()?;
+    let signers: Arc<dyn SignerProvider> =
+        Arc::new(config.keys.keyring().await?);
+    let tx_signer = signers.signer(KeyPurpose::Transactions)?;
+    let flashbots_signer = signers.signer(KeyPurpose::Reputation)?;
+
     let provider = ProviderBuilder::new()
-        .wallet(tx_signer.clone())
+        .wallet(signers.wallet()?)
         .connect_ws(ws)
         .await?;
 
-    let flashbots_signer: PrivateKeySigner =
-        args.flashbots_signer_pk.parse()?;
-
     let provider = Arc::new(provider);
 
     let mev_share_event_source =
-        MevShareEventSource::new("https://mev-share.flashbots.net".to_string());
+        MevShareEventSource::new(config.endpoints.mev_share_sse.clone());
     let mev_share_event_source = EventSourceMap::new(
         Box::new(mev_share_event_source),
-        Event::MevShareEvent,
-    );
-
-    let arbitrage_contract_address =
-        Address::parse_checksummed(args.arb_contract_address, None)?;
-    let strategy = MevShareUniswapV2V3Arbitrage::new(
-        provider,
-        arbitrage_contract_address,
-        args.dry_run,
+        |event| Event::MevShareEvent(Timestamped::now(event)),
     );
 
-    let mev_share_executor = MevShareExecutor::new(
-        "https://relay.flashbots.net:443".to_string(),
-        args.dry_run,
-        flashbots_signer,
-    );
-    let mev_share_executor = ExecutorMap::new(
-        Box::new(mev_share_executor),
-        |action| match action {
-            Action::SubmitBundle(bundle) => Some(bundle),
-        },
-    );
-
-    let engine: Engine<Event, Action> = Engine::default()
-        .add_event_source(Box::new(mev_share_event_source))
-        .add_strategy(Box::new(strategy))
-        .add_executor(Box::new(mev_share_executor));
+    let features = &config.features;
+    let mut engine: Engine<Event, Action> = Engine::default()
+        .with_health(health)
+        // Hints aren't queued behind blocks and pool updates.
+        .with_priority_events(|event| matches!(event, Event::MevShareEvent(_)))
+        .add_event_source(Box::new(mev_share_event_source));
+    // Strategies share the hints of a single SSE connection.
+    for (kind, params) in config.strategies() {
+        let strategy: Box<dyn Strategy<Event, Action>> = match kind {
+            StrategyKind::V2V3 => Box::new(arbitrage_strategy(
+                &config,
+                params,
+                provider.clone(),
+                signers.clone(),
+            )?),
+            StrategyKind::FeeTier => Box::new(
+                MevShareUniswapV3FeeTierArbitrage::new(
+                    provider.clone(),
+                    tx_signer.address(),
+                    config.dry_run,
+                )
+                .with_config(params.clone())
+                .with_signers(signers.clone()),
+            ),
+        };
+        engine = engine.add_strategy(strategy);
+    }
+    // Bundles are submitted to every relay, and tracked and accounted for on
+    // the primary one, under the strategies that submitted them.
+    let bundle_tracker = BundleTracker::new();
+    let pnl_ledger = PnlLedger::new();
+    let bundle_store = config
+        .bundle_store
+        .as_ref()
+        .map(|path| -> Result<Arc<dyn BundleStore>> {
+            Ok(Arc::new(SqliteBundleStore::open(path)?))
+        })
+        .transpose()?;
+    for (i, relay) in config.endpoints.relays.iter().enumerate() {
+        let mut bundle_executor = FlashbotsBundleExecutor::new(
+            relay.clone(),
+            flashbots_signer.clone(),
+        )
+        .with_dry_run(config.dry_run);
+        if let Some(bundle_store) = &bundle_store {
+            bundle_executor =
+                bundle_executor.with_bundle_store(bundle_store.clone());
+        }
+        if i == 0 {
+            bundle_executor =
+                bundle_executor.with_pnl_ledger(pnl_ledger.clone());
+        }
+        if i == 0 && features.bundle_feedback {
+            bundle_executor =
+                bundle_executor.with_bundle_tracker(bundle_tracker.clone());
+        }
+        // Hints redelivered after reconnecting yield the same bundles.
+        let bundle_executor = ActionDedup::new(
+            Box::new(bundle_executor),
+            |bundle: &AttributedBundle| bundle.bundle.bundle_hash(),
+            BUNDLE_DEDUP_WINDOW,
+        );
+        // Tells apart the logs of the executors of the relays.
+        let bundle_executor = Named::new(
+            format!("FlashbotsBundleExecutor({relay})"),
+            bundle_executor,
+        );
+        engine = engine.add_executor(Box::new(ExecutorMap::new(
+            Box::new(bundle_executor),
+            |action: Action| match action {
+                Action::SubmitBundle(bundle) => Some(bundle),
+                _ => None,
+            },
+        )));
+    }
+    if features.manage_inventory {
+        let mempool_provider = ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .connect_ws(WsConnect::new(config.wss()?))
+            .await?;
+        let mempool_provider = Arc::new(DynProvider::new(mempool_provider));
+        let mempool_executor = MempoolExecutor::new(mempool_provider.clone())
+            .with_nonce_manager(NonceManager::new(mempool_provider))
+            .with_signers(signers.as_ref())?;
+        engine = engine.add_executor(ExecutorMap::for_variant(Box::new(
+            mempool_executor,
+        )));
+    }
+    if features.live_pool_states
+        || features.bundle_feedback
+        || features.manage_inventory
+    {
+        let any_provider = ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .connect_ws(WsConnect::new(config.wss()?))
+            .await?;
+        let any_provider = Arc::new(DynProvider::new(any_provider));
+        if features.manage_inventory {
+            // The inventory is rebalanced on every block.
+            let block_event_source =
+                BlockEventSource::new(any_provider.clone());
+            engine = engine.add_event_source(EventSourceMap::for_variant(
+                Box::new(block_event_source),
+            ));
+        }
+        if features.live_pool_states {
+            let log_event_source = LogEventSource::new(
+                any_provider.clone(),
+                PoolStateCache::log_filter(),
+            );
+            engine = engine.add_event_source(EventSourceMap::for_variant(
+                Box::new(log_event_source),
+            ));
+        }
+        if features.bundle_feedback {
+            // Fee refunds are paid to the searcher identity.
+            let refund_recipient = flashbots_signer.address();
+            let mut bundle_stats_event_source = BundleStatsEventSource::new(
+                flashbots_client(
+                    config.primary_relay().to_string(),
+                    flashbots_signer,
+                ),
+                any_provider,
+                bundle_tracker,
+            )
+            .with_pnl_ledger(pnl_ledger)
+            .with_fee_refunds(refund_recipient);
+            if let Some(bundle_store) = bundle_store {
+                bundle_stats_event_source =
+                    bundle_stats_event_source.with_bundle_store(bundle_store);
+            }
+            engine = engine.add_event_source(EventSourceMap::for_variant(
+                Box::new(bundle_stats_event_source),
+            ));
+        }
+    }
 
     let result = match engine.run().await {
         Ok(mut set) => {
@@ -123,3 +415,44 @@ async fn main() -> Result<()> {
 
     result
 }
+
+/// Backrunning strategy with the given parameters, and the optional parts
+/// enabled by the features of the config.
+fn arbitrage_strategy<P: Provider + 'static>(
+    config: &Config,
+    params: &ArbitrageConfig,
+    provider: Arc<P>,
+    signers: Arc<dyn SignerProvider>,
+) -> Result<MevShareUniswapV2V3Arbitrage<P>> {
+    let features = &config.features;
+    let tx_signer = signers.signer(KeyPurpose::Transactions)?;
+    let mut strategy = MevShareUniswapV2V3Arbitrage::new(
+        provider,
+        config.arb_contract_address()?,
+        config.dry_run,
+    )
+    .with_config(params.clone())
+    .with_signers(signers.clone());
+    if features.simulate {
+        strategy = strategy.with_simulator(mev_share_client(
+            config.primary_relay().to_string(),
+            signers.signer(KeyPurpose::Reputation)?,
+        ));
+    }
+    if features.triangular_arbitrage {
+        strategy = strategy.with_triangular_arbitrage(tx_signer.address());
+    }
+    if features.live_pool_states {
+        strategy = strategy.with_live_pool_states();
+    }
+    if features.discover_pools {
+        strategy = strategy.with_pool_discovery(PoolDiscoveryConfig::default());
+    }
+    if features.screen_tokens {
+        strategy = strategy.with_token_screening();
+    }
+    if features.manage_inventory {
+        strategy = strategy.with_inventory_management(tx_signer.address());
+    }
+    Ok(strategy)
+}
@@ -0,0 +1,142 @@
+//! HTTP endpoint of the bot for liveness checks and dashboards:
+//! - `/healthz` responds with the statuses of the components of the engine, see
+//!   [Health], and `503` if any of them stopped or failed,
+//! - `/metrics` responds with the metrics in the Prometheus format.
+
+use std::{io, net::SocketAddr};
+
+use anyhow::Result;
+use kazuka_core::health::Health;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Installs the Prometheus recorder of the metrics, and serves them along
+/// with the health of the engine in the background.
+pub async fn serve(addr: SocketAddr, health: Health) -> Result<()> {
+    let metrics = PrometheusBuilder::new().install_recorder()?;
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!(addr = %listener.local_addr()?, "Serving health and metrics");
+    tokio::spawn(accept(listener, health, metrics));
+    Ok(())
+}
+
+async fn accept(
+    listener: TcpListener,
+    health: Health,
+    metrics: PrometheusHandle,
+) {
+    while let Ok((socket, addr)) = listener.accept().await {
+        let health = health.clone();
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(err) = respond(socket, &health, &metrics).await {
+                tracing::debug!(%addr, %err, "Metrics connection closed");
+            }
+        });
+    }
+}
+
+async fn respond(
+    mut socket: TcpStream,
+    health: &Health,
+    metrics: &PrometheusHandle,
+) -> io::Result<()> {
+    let mut request = vec![];
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    // E.g. `GET /metrics HTTP/1.1`.
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+    let (status, content_type, body) = match path {
+        "/healthz" => (
+            if health.is_healthy() {
+                "200 OK"
+            } else {
+                "503 Service Unavailable"
+            },
+            "application/json",
+            serde_json::to_string(&health.components())?,
+        ),
+        "/metrics" => (
+            "200 OK",
+            "text/plain; version=0.0.4",
+            metrics.render(),
+        ),
+        _ => (
+            "404 Not Found",
+            "text/plain",
+            "not found".to_string(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+         content-type: {content_type}\r\n\
+         content-length: {}\r\n\
+         connection: close\r\n\r\n\
+         {body}",
+        body.len()
+    );
+    socket.write_all(response.as_bytes()).await?;
+    socket.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use kazuka_core::health::ComponentStatus;
+
+    use super::*;
+
+    async fn get(addr: SocketAddr, path: &str) -> String {
+        let mut socket = TcpStream::connect(addr).await.unwrap();
+        socket
+            .write_all(format!("GET {path} HTTP/1.1\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_healthz() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let health = Health::new();
+        let metrics = PrometheusBuilder::new().build_recorder().handle();
+        tokio::spawn(accept(
+            listener,
+            health.clone(),
+            metrics,
+        ));
+
+        health.set(
+            "event_source_0",
+            ComponentStatus::Running,
+        );
+        let response = get(addr, "/healthz").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(r#"{"event_source_0":"running"}"#));
+
+        health.set(
+            "event_source_0",
+            ComponentStatus::Stopped,
+        );
+        let response = get(addr, "/healthz").await;
+        assert!(response.starts_with("HTTP/1.1 503"));
+
+        let response = get(addr, "/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let response = get(addr, "/unknown").await;
+        assert!(response.starts_with("HTTP/1.1 404"));
+    }
+}
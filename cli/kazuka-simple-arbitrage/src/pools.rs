@@ -0,0 +1,79 @@
+//! Tooling of the pools file of the strategy, see
+//! [pools_file](kazuka_mev_share_arbitrage::config::ArbitrageConfig::pools_file).
+
+use std::{path::PathBuf, sync::Arc};
+
+use alloy::{
+    primitives::U256,
+    providers::{ProviderBuilder, WsConnect},
+};
+use anyhow::Result;
+use clap::Subcommand;
+use kazuka_mev_share_arbitrage::discovery::{
+    PoolDiscovery, PoolDiscoveryConfig, write_pools_csv,
+};
+
+use crate::config::Config;
+
+#[derive(Subcommand, Debug)]
+pub enum PoolsCommand {
+    /// Discovers the V2/V3 WETH pool pairs from the logs of the factories,
+    /// and writes them to the pools file.
+    Fetch(FetchArgs),
+}
+
+/// Options of the `pools fetch` command.
+#[derive(clap::Args, Debug)]
+pub struct FetchArgs {
+    /// File to write the pools to, the pools file of the strategy by
+    /// default.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+    /// Minimum WETH balance (in ether) of both pools of a pair.
+    #[arg(long, default_value_t = 10)]
+    pub min_weth_liquidity: u64,
+    /// First block to scan the factory logs from, the deployment of Uniswap
+    /// V2 by default.
+    #[arg(long)]
+    pub from_block: Option<u64>,
+    /// Maximum number of blocks per `eth_getLogs` request.
+    #[arg(long)]
+    pub blocks_per_request: Option<u64>,
+}
+
+pub async fn run(config: Config, command: PoolsCommand) -> Result<()> {
+    match command {
+        PoolsCommand::Fetch(args) => fetch(config, args).await,
+    }
+}
+
+async fn fetch(config: Config, args: FetchArgs) -> Result<()> {
+    let provider = ProviderBuilder::new()
+        .connect_ws(WsConnect::new(config.wss()?))
+        .await?;
+
+    let mut discovery_config = PoolDiscoveryConfig {
+        v2_forks: config.strategy.v2_forks.clone(),
+        min_weth_liquidity: U256::from(args.min_weth_liquidity)
+            * U256::from(10).pow(U256::from(18)),
+        ..Default::default()
+    };
+    if let Some(from_block) = args.from_block {
+        discovery_config.from_block = from_block;
+    }
+    if let Some(blocks_per_request) = args.blocks_per_request {
+        discovery_config.blocks_per_request = blocks_per_request;
+    }
+    let pools = PoolDiscovery::new(Arc::new(provider), discovery_config)
+        .refresh()
+        .await?;
+
+    let output = args.output.unwrap_or(config.strategy.pools_file);
+    write_pools_csv(&output, &pools)?;
+    tracing::info!(
+        "Wrote {} pools to {}",
+        pools.len(),
+        output.display()
+    );
+    Ok(())
+}
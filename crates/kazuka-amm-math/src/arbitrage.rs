@@ -0,0 +1,179 @@
+//! Arbitrage between constant product pools, buying a token on the first
+//! pool and selling it on the second one, or swapping along a longer path
+//! back to the input token.
+
+use alloy::primitives::{U256, U2048, ruint::UintTryFrom};
+
+use crate::{FEE_DENOMINATOR, v2::Reserves};
+
+/// Profit of swapping the input through both pools, if any.
+pub fn profit(first: &Reserves, second: &Reserves, amount_in: U256) -> U256 {
+    second
+        .amount_out(first.amount_out(amount_in))
+        .saturating_sub(amount_in)
+}
+
+/// Input maximizing the profit of swapping through both pools, zero if there
+/// is no arbitrage.
+///
+/// Both swaps compose into a single constant product swap, whose profit is
+/// maximal where its marginal price is one:
+/// `x = D * (sqrt(g1 * g2 * a1 * a2 * b1 * b2) - D * a1 * a2)
+///   / (g1 * (D * a2 + g2 * b1))`,
+/// where `a` and `b` are the reserves in and out, `g = D - fee` and `D` is
+/// the [FEE_DENOMINATOR].
+pub fn optimal_amount_in(first: &Reserves, second: &Reserves) -> U256 {
+    let d = U2048::from(FEE_DENOMINATOR);
+    let g1 = U2048::from(FEE_DENOMINATOR - first.fee);
+    let g2 = U2048::from(FEE_DENOMINATOR - second.fee);
+    let (a1, b1) = (
+        U2048::from(first.reserve_in),
+        U2048::from(first.reserve_out),
+    );
+    let (a2, b2) = (
+        U2048::from(second.reserve_in),
+        U2048::from(second.reserve_out),
+    );
+
+    let a = a1 * a2;
+    let root = (g1 * g2 * a * b1 * b2).root(2);
+    if root <= d * a {
+        return U256::ZERO;
+    }
+    let denominator = g1 * (a2 * d + g2 * b1);
+    U256::saturating_from(d * (root - d * a) / denominator)
+}
+
+/// Amount received for swapping the input through the pools in order.
+pub fn path_amount_out(path: &[Reserves], amount_in: U256) -> U256 {
+    path.iter().fold(amount_in, |amount, reserves| {
+        reserves.amount_out(amount)
+    })
+}
+
+/// Profit of swapping the input through the pools in order, if any.
+pub fn path_profit(path: &[Reserves], amount_in: U256) -> U256 {
+    path_amount_out(path, amount_in).saturating_sub(amount_in)
+}
+
+/// Input maximizing the profit of swapping through the pools in order, zero
+/// if there is no arbitrage.
+///
+/// All but the last pool are [composed](Reserves::then) into one, which
+/// reduces the path to the two pools of [optimal_amount_in].
+pub fn optimal_path_amount_in(path: &[Reserves]) -> U256 {
+    let Some((last, init)) = path.split_last() else {
+        return U256::ZERO;
+    };
+    let Some((first, rest)) = init.split_first() else {
+        return U256::ZERO;
+    };
+    let first = rest.iter().fold(*first, |composed, reserves| {
+        composed.then(reserves)
+    });
+    optimal_amount_in(&first, last)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::utils::parse_ether;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Maximizes the profit with a ternary search, as it is concave.
+    fn reference_amount_in(path: &[Reserves]) -> U256 {
+        let out = |amount_in| path_amount_out(path, amount_in);
+        let (mut lo, mut hi) = (U256::ZERO, path[0].reserve_in);
+        while hi - lo > U256::from(2) {
+            let third = (hi - lo) / U256::from(3);
+            let (m1, m2) = (lo + third, hi - third);
+            if out(m1) + m2 < out(m2) + m1 {
+                lo = m1;
+            } else {
+                hi = m2;
+            }
+        }
+        [lo, lo + U256::ONE, hi]
+            .into_iter()
+            .max_by_key(|amount_in| path_profit(path, *amount_in))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_optimal_amount_in() {
+        // The token is 10% cheaper on the first pool.
+        let first = Reserves::new(
+            parse_ether("1000").unwrap(),
+            parse_ether("1100").unwrap(),
+            3_000,
+        );
+        let second = Reserves::new(
+            parse_ether("1000").unwrap(),
+            parse_ether("1000").unwrap(),
+            3_000,
+        );
+
+        let optimal = optimal_amount_in(&first, &second);
+        let best = profit(&first, &second, optimal);
+
+        assert!(!best.is_zero());
+        for amount_in in [optimal / U256::from(2), optimal * U256::from(2)] {
+            assert!(profit(&first, &second, amount_in) < best);
+        }
+        assert_eq!(
+            optimal_amount_in(&second, &second),
+            U256::ZERO
+        );
+    }
+
+    fn reserves() -> impl Strategy<Value = Reserves> {
+        (
+            1_000_000_000_000_000..u128::MAX >> 28,
+            1_000_000_000_000_000..u128::MAX >> 28,
+            prop::sample::select(vec![0, 500, 3_000, 10_000]),
+        )
+            .prop_map(|(reserve_in, reserve_out, fee)| {
+                Reserves::new(
+                    U256::from(reserve_in),
+                    U256::from(reserve_out),
+                    fee,
+                )
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_ternary_search(
+            first in reserves(),
+            second in reserves(),
+        ) {
+            let optimal = profit(
+                &first,
+                &second,
+                optimal_amount_in(&first, &second),
+            );
+            let reference = profit(
+                &first,
+                &second,
+                reference_amount_in(&[first, second]),
+            );
+
+            // Both are exact up to the rounding of the swaps.
+            prop_assert!(optimal + U256::from(1_000) >= reference);
+        }
+
+        #[test]
+        fn test_path_matches_ternary_search(
+            path in prop::collection::vec(reserves(), 3..5),
+        ) {
+            let optimal =
+                path_profit(&path, optimal_path_amount_in(&path));
+            let reference =
+                path_profit(&path, reference_amount_in(&path));
+
+            // Also up to the rounding of the composed reserves.
+            prop_assert!(optimal + U256::from(1_000) >= reference);
+        }
+    }
+}
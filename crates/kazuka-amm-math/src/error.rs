@@ -0,0 +1,16 @@
+use alloy::primitives::U160;
+
+/// Inputs the pool contracts would revert on.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MathError {
+    #[error("tick {0} is out of bounds")]
+    TickOutOfBounds(i32),
+    #[error("sqrt price {0} is out of bounds")]
+    SqrtPriceOutOfBounds(U160),
+    #[error("insufficient liquidity")]
+    InsufficientLiquidity,
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("overflow")]
+    Overflow,
+}
@@ -0,0 +1,73 @@
+//! Multiplications and divisions of 256-bit numbers without intermediate
+//! overflows, as the `FullMath` and `UnsafeMath` libraries of Uniswap V3.
+
+use alloy::primitives::{U256, U512, ruint::UintTryFrom};
+
+use crate::error::MathError;
+
+/// `a * b / denominator`, rounded down.
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> Result<U256, MathError> {
+    if denominator.is_zero() {
+        return Err(MathError::DivisionByZero);
+    }
+    let quotient = U512::from(a) * U512::from(b) / U512::from(denominator);
+    U256::uint_try_from(quotient).map_err(|_| MathError::Overflow)
+}
+
+/// `a * b / denominator`, rounded up.
+pub fn mul_div_rounding_up(
+    a: U256,
+    b: U256,
+    denominator: U256,
+) -> Result<U256, MathError> {
+    if denominator.is_zero() {
+        return Err(MathError::DivisionByZero);
+    }
+    let quotient =
+        (U512::from(a) * U512::from(b)).div_ceil(U512::from(denominator));
+    U256::uint_try_from(quotient).map_err(|_| MathError::Overflow)
+}
+
+/// `a / b`, rounded up.
+pub fn div_rounding_up(a: U256, b: U256) -> Result<U256, MathError> {
+    if b.is_zero() {
+        return Err(MathError::DivisionByZero);
+    }
+    Ok(a.div_ceil(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div() {
+        let q128 = U256::ONE << 128;
+
+        // The product overflows 256 bits, but not the result.
+        assert_eq!(
+            mul_div(
+                q128,
+                q128 * U256::from(3),
+                q128 * U256::from(2)
+            ),
+            Ok(q128 + (q128 >> 1))
+        );
+        assert_eq!(
+            mul_div_rounding_up(
+                U256::from(7),
+                U256::from(3),
+                U256::from(2)
+            ),
+            Ok(U256::from(11))
+        );
+        assert_eq!(
+            mul_div(U256::MAX, U256::MAX, U256::ONE),
+            Err(MathError::Overflow)
+        );
+        assert_eq!(
+            div_rounding_up(U256::ONE, U256::ZERO),
+            Err(MathError::DivisionByZero)
+        );
+    }
+}
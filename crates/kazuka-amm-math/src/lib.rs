@@ -0,0 +1,12 @@
+//! Exact swap math of Uniswap V2 and V3 pools, shared by the strategies.
+//!
+//! Fees are in hundredths of a bip, e.g. `3_000` for 0.3%.
+
+pub mod arbitrage;
+pub mod error;
+pub mod full_math;
+pub mod v2;
+pub mod v3;
+
+/// Denominator of the fees, in hundredths of a bip.
+pub const FEE_DENOMINATOR: u32 = 1_000_000;
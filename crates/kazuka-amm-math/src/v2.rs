@@ -0,0 +1,335 @@
+//! Constant product math of Uniswap V2 pairs, as the `UniswapV2Library`,
+//! for any fee.
+
+use alloy::primitives::{U160, U256, U512, ruint::UintTryFrom};
+
+use crate::{FEE_DENOMINATOR, error::MathError};
+
+const BPS: u64 = 10_000;
+
+/// Reserves of a constant product pool in the direction of a swap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Reserves {
+    pub reserve_in: U256,
+    pub reserve_out: U256,
+    /// Fee in hundredths of a bip.
+    pub fee: u32,
+}
+
+impl Reserves {
+    pub fn new(reserve_in: U256, reserve_out: U256, fee: u32) -> Self {
+        Self {
+            reserve_in,
+            reserve_out,
+            fee,
+        }
+    }
+
+    /// Virtual reserves of a V3 pool within the current tick range, which
+    /// swaps token0 for token1 if `zero_for_one`.
+    pub fn from_v3(
+        sqrt_price_x96: U160,
+        liquidity: u128,
+        fee: u32,
+        zero_for_one: bool,
+    ) -> Self {
+        let sqrt_price = U512::from(sqrt_price_x96);
+        if sqrt_price.is_zero() {
+            return Self::new(U256::ZERO, U256::ZERO, fee);
+        }
+        let liquidity = U512::from(liquidity);
+        let reserve0 = (liquidity << 96) / sqrt_price;
+        let reserve1 = (liquidity * sqrt_price) >> 96;
+        let (reserve_in, reserve_out) = if zero_for_one {
+            (reserve0, reserve1)
+        } else {
+            (reserve1, reserve0)
+        };
+        Self::new(
+            U256::saturating_from(reserve_in),
+            U256::saturating_from(reserve_out),
+            fee,
+        )
+    }
+
+    /// Reserves after a trade selling the output token, which lowered its
+    /// price by `impact_bps`.
+    pub fn with_price_impact(self, impact_bps: u64) -> Self {
+        // The price moves by `f`, so the reserves move by `sqrt(f)`, which
+        // is scaled by 1e9.
+        let f = U512::from(BPS - impact_bps.min(BPS - 1))
+            * U512::from(10).pow(U512::from(14));
+        let sqrt_f = f.root(2);
+        let scale = U512::from(1_000_000_000);
+        Self {
+            reserve_in: U256::saturating_from(
+                U512::from(self.reserve_in) * sqrt_f / scale,
+            ),
+            reserve_out: U256::saturating_from(
+                U512::from(self.reserve_out) * scale / sqrt_f,
+            ),
+            fee: self.fee,
+        }
+    }
+
+    /// Virtual reserves of swapping through this pool and then the next
+    /// one, which compose into a single constant product swap:
+    /// `(D * a1 * a2, g2 * b1 * b2) / (D * a2 + g2 * b1)`, with the fee of
+    /// this pool, see [crate::arbitrage::optimal_amount_in] for the notation.
+    ///
+    /// Exact up to the rounding of the reserves and the swaps.
+    pub fn then(self, next: &Self) -> Self {
+        let d = U512::from(FEE_DENOMINATOR);
+        let g2 = U512::from(FEE_DENOMINATOR - next.fee);
+        let (a1, b1) = (
+            U512::from(self.reserve_in),
+            U512::from(self.reserve_out),
+        );
+        let (a2, b2) = (
+            U512::from(next.reserve_in),
+            U512::from(next.reserve_out),
+        );
+        let denominator = d * a2 + g2 * b1;
+        if denominator.is_zero() {
+            return Self::new(U256::ZERO, U256::ZERO, self.fee);
+        }
+        Self::new(
+            U256::saturating_from(d * a1 * a2 / denominator),
+            U256::saturating_from(g2 * b1 * b2 / denominator),
+            self.fee,
+        )
+    }
+
+    /// Reserves of the swap in the opposite direction.
+    pub fn reversed(self) -> Self {
+        Self::new(
+            self.reserve_out,
+            self.reserve_in,
+            self.fee,
+        )
+    }
+
+    /// See [get_amount_out].
+    pub fn amount_out(&self, amount_in: U256) -> U256 {
+        get_amount_out(
+            amount_in,
+            self.reserve_in,
+            self.reserve_out,
+            self.fee,
+        )
+    }
+
+    /// See [get_amount_in].
+    pub fn amount_in(&self, amount_out: U256) -> Result<U256, MathError> {
+        get_amount_in(
+            amount_out,
+            self.reserve_in,
+            self.reserve_out,
+            self.fee,
+        )
+    }
+}
+
+/// Amount received for the input, as `getAmountOut`. Zero if the pool is
+/// empty.
+pub fn get_amount_out(
+    amount_in: U256,
+    reserve_in: U256,
+    reserve_out: U256,
+    fee: u32,
+) -> U256 {
+    let amount_in_with_fee =
+        U512::from(amount_in) * U512::from(FEE_DENOMINATOR - fee);
+    let numerator = amount_in_with_fee * U512::from(reserve_out);
+    let denominator = U512::from(reserve_in) * U512::from(FEE_DENOMINATOR)
+        + amount_in_with_fee;
+    if denominator.is_zero() {
+        return U256::ZERO;
+    }
+    U256::saturating_from(numerator / denominator)
+}
+
+/// Input required to receive the output, as `getAmountIn`.
+pub fn get_amount_in(
+    amount_out: U256,
+    reserve_in: U256,
+    reserve_out: U256,
+    fee: u32,
+) -> Result<U256, MathError> {
+    if reserve_in.is_zero() || amount_out >= reserve_out {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    let numerator = U512::from(reserve_in)
+        * U512::from(amount_out)
+        * U512::from(FEE_DENOMINATOR);
+    let denominator = U512::from(reserve_out - amount_out)
+        * U512::from(FEE_DENOMINATOR - fee);
+    if denominator.is_zero() {
+        return Err(MathError::DivisionByZero);
+    }
+    U256::uint_try_from(numerator / denominator + U512::ONE)
+        .map_err(|_| MathError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// `getAmountOut` of the `UniswapV2Library`, with its 0.3% fee.
+    fn reference_amount_out(
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+    ) -> U256 {
+        let amount_in_with_fee = amount_in * U256::from(997);
+        amount_in_with_fee * reserve_out
+            / (reserve_in * U256::from(1000) + amount_in_with_fee)
+    }
+
+    /// `getAmountIn` of the `UniswapV2Library`, with its 0.3% fee.
+    fn reference_amount_in(
+        amount_out: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+    ) -> U256 {
+        reserve_in * amount_out * U256::from(1000)
+            / ((reserve_out - amount_out) * U256::from(997))
+            + U256::ONE
+    }
+
+    #[test]
+    fn test_amount_out() {
+        let ether = U256::from(10).pow(U256::from(18));
+        let pool = Reserves::new(
+            ether * U256::from(100),
+            ether * U256::from(200),
+            3_000,
+        );
+
+        // 1 * 0.997 * 200 / (100 + 0.997)
+        assert_eq!(
+            pool.amount_out(ether),
+            U256::from(1_974_316_068_794_122_597_u128)
+        );
+        assert_eq!(pool.amount_out(U256::ZERO), U256::ZERO);
+        assert_eq!(
+            pool.amount_in(pool.reserve_out),
+            Err(MathError::InsufficientLiquidity)
+        );
+    }
+
+    #[test]
+    fn test_from_v3() {
+        let liquidity = 1_000_000_000_000_000_000_u128;
+        // Price of 4 token1 per token0.
+        let sqrt_price_x96 = U160::from(2) << 96;
+
+        let pool = Reserves::from_v3(sqrt_price_x96, liquidity, 500, true);
+
+        assert_eq!(
+            pool.reserve_in,
+            U256::from(liquidity / 2)
+        );
+        assert_eq!(
+            pool.reserve_out,
+            U256::from(liquidity * 2)
+        );
+        assert_eq!(
+            Reserves::from_v3(sqrt_price_x96, liquidity, 500, false),
+            pool.reversed()
+        );
+    }
+
+    #[test]
+    fn test_with_price_impact() {
+        let ether = U256::from(10).pow(U256::from(18));
+        let pool = Reserves::new(ether, ether, 3_000);
+
+        // The output token got 1% cheaper.
+        let moved = pool.with_price_impact(100);
+
+        assert!(moved.reserve_in < pool.reserve_in);
+        assert!(moved.reserve_out > pool.reserve_out);
+        let price = moved.reserve_out * U256::from(10_000) / moved.reserve_in;
+        assert_eq!(price, U256::from(10_101));
+    }
+
+    #[test]
+    fn test_then() {
+        let ether = U256::from(10).pow(U256::from(18));
+        let first = Reserves::new(
+            ether * U256::from(100),
+            ether * U256::from(200),
+            3_000,
+        );
+        let second = Reserves::new(
+            ether * U256::from(300),
+            ether * U256::from(100),
+            500,
+        );
+
+        let composed = first.then(&second);
+
+        for amount_in in [ether / U256::from(10), ether, ether * U256::from(10)]
+        {
+            let expected = second.amount_out(first.amount_out(amount_in));
+            let actual = composed.amount_out(amount_in);
+            assert!(actual.abs_diff(expected) <= U256::ONE);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_uniswap_v2_library(
+            reserve_in in 1..u128::MAX >> 16,
+            reserve_out in 2..u128::MAX >> 16,
+            amount_in in 0..u128::MAX >> 16,
+            amount_out_fraction in 0.0..1.0,
+        ) {
+            // Reserves of the pairs fit in 112 bits.
+            let reserve_in = U256::from(reserve_in);
+            let reserve_out = U256::from(reserve_out);
+            let amount_in = U256::from(amount_in);
+            let amount_out = U256::from(
+                (f64::from(reserve_out) * amount_out_fraction) as u128,
+            )
+            .min(reserve_out - U256::ONE);
+
+            prop_assert_eq!(
+                get_amount_out(amount_in, reserve_in, reserve_out, 3_000),
+                reference_amount_out(amount_in, reserve_in, reserve_out)
+            );
+            prop_assert_eq!(
+                get_amount_in(amount_out, reserve_in, reserve_out, 3_000),
+                Ok(reference_amount_in(amount_out, reserve_in, reserve_out))
+            );
+        }
+
+        #[test]
+        fn test_amount_in_round_trip(
+            reserve_in in 1..u128::MAX,
+            reserve_out in 2..u128::MAX,
+            amount_out in 1..u128::MAX,
+            fee in prop::sample::select(vec![0, 500, 3_000, 10_000]),
+        ) {
+            let pool = Reserves::new(
+                U256::from(reserve_in),
+                U256::from(reserve_out),
+                fee,
+            );
+            let amount_out = U256::from(amount_out % reserve_out);
+            let amount_in = pool.amount_in(amount_out).unwrap();
+
+            // The input receives the output, and exceeds the smallest one
+            // that does by at most one.
+            prop_assert!(pool.amount_out(amount_in) >= amount_out);
+            prop_assert!(
+                amount_out.is_zero()
+                    || pool.amount_out(amount_in.saturating_sub(U256::from(2)))
+                    < amount_out
+            );
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Concentrated liquidity math of Uniswap V3 pools, ported from the
+//! libraries of the core contracts.
+
+use alloy::primitives::{I256, U160, U256};
+
+use crate::{
+    error::MathError,
+    v3::{
+        swap_math::{SwapStep, compute_swap_step},
+        tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+    },
+};
+
+pub mod sqrt_price_math;
+pub mod swap_math;
+pub mod tick_math;
+
+/// Sqrt price the swap stops at, the furthest one allowed by the pools.
+fn price_limit(zero_for_one: bool) -> U160 {
+    if zero_for_one {
+        MIN_SQRT_RATIO + U160::ONE
+    } else {
+        MAX_SQRT_RATIO - U160::ONE
+    }
+}
+
+/// Swaps the input within the current tick range, of token0 for token1 if
+/// `zero_for_one`. The input left once the range is exhausted isn't swapped.
+pub fn swap_exact_in(
+    sqrt_price_x96: U160,
+    liquidity: u128,
+    fee: u32,
+    zero_for_one: bool,
+    amount_in: U256,
+) -> Result<SwapStep, MathError> {
+    let amount_remaining =
+        I256::try_from(amount_in).map_err(|_| MathError::Overflow)?;
+    compute_swap_step(
+        sqrt_price_x96,
+        price_limit(zero_for_one),
+        liquidity,
+        amount_remaining,
+        fee,
+    )
+}
+
+/// Swaps for the output within the current tick range, of token0 for
+/// token1 if `zero_for_one`.
+pub fn swap_exact_out(
+    sqrt_price_x96: U160,
+    liquidity: u128,
+    fee: u32,
+    zero_for_one: bool,
+    amount_out: U256,
+) -> Result<SwapStep, MathError> {
+    let amount_remaining =
+        I256::try_from(amount_out).map_err(|_| MathError::Overflow)?;
+    compute_swap_step(
+        sqrt_price_x96,
+        price_limit(zero_for_one),
+        liquidity,
+        -amount_remaining,
+        fee,
+    )
+}
@@ -0,0 +1,319 @@
+//! Prices after swaps and amounts between prices within a tick range, as
+//! the `SqrtPriceMath` library.
+
+use alloy::primitives::{U160, U256, ruint::UintTryFrom, uint};
+
+use crate::{
+    error::MathError,
+    full_math::{div_rounding_up, mul_div, mul_div_rounding_up},
+};
+
+/// `2^96`, the unit of the Q64.96 sqrt prices.
+const Q96: U256 = uint!(79228162514264337593543950336_U256);
+
+fn to_sqrt_price(value: U256) -> Result<U160, MathError> {
+    U160::uint_try_from(value).map_err(|_| MathError::Overflow)
+}
+
+/// Sqrt price after adding or removing an amount of token0, rounded up so
+/// that the pool receives enough.
+fn get_next_sqrt_price_from_amount0_rounding_up(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256, MathError> {
+    if amount.is_zero() {
+        return Ok(sqrt_price);
+    }
+    let numerator1 = U256::from(liquidity) << 96;
+    if add {
+        if let Some(product) = amount.checked_mul(sqrt_price)
+            && let Some(denominator) = numerator1.checked_add(product)
+        {
+            return mul_div_rounding_up(numerator1, sqrt_price, denominator);
+        }
+        // Less precise, but doesn't overflow.
+        let denominator = (numerator1 / sqrt_price)
+            .checked_add(amount)
+            .ok_or(MathError::Overflow)?;
+        div_rounding_up(numerator1, denominator)
+    } else {
+        let product = amount
+            .checked_mul(sqrt_price)
+            .filter(|product| *product < numerator1)
+            .ok_or(MathError::InsufficientLiquidity)?;
+        mul_div_rounding_up(
+            numerator1,
+            sqrt_price,
+            numerator1 - product,
+        )
+    }
+}
+
+/// Sqrt price after adding or removing an amount of token1, rounded down so
+/// that the pool receives enough.
+fn get_next_sqrt_price_from_amount1_rounding_down(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256, MathError> {
+    let liquidity = U256::from(liquidity);
+    let fits_u160 = amount <= U256::from(U160::MAX);
+    if add {
+        let quotient = if fits_u160 {
+            (amount << 96) / liquidity
+        } else {
+            mul_div(amount, Q96, liquidity)?
+        };
+        sqrt_price.checked_add(quotient).ok_or(MathError::Overflow)
+    } else {
+        let quotient = if fits_u160 {
+            div_rounding_up(amount << 96, liquidity)?
+        } else {
+            mul_div_rounding_up(amount, Q96, liquidity)?
+        };
+        if sqrt_price <= quotient {
+            return Err(MathError::InsufficientLiquidity);
+        }
+        Ok(sqrt_price - quotient)
+    }
+}
+
+fn check_pool(sqrt_price_x96: U160, liquidity: u128) -> Result<(), MathError> {
+    if sqrt_price_x96.is_zero() {
+        return Err(MathError::SqrtPriceOutOfBounds(
+            sqrt_price_x96,
+        ));
+    }
+    if liquidity == 0 {
+        return Err(MathError::InsufficientLiquidity);
+    }
+    Ok(())
+}
+
+/// Sqrt price after swapping the input, of token0 if `zero_for_one`.
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x96: U160,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> Result<U160, MathError> {
+    check_pool(sqrt_price_x96, liquidity)?;
+    let sqrt_price = U256::from(sqrt_price_x96);
+    to_sqrt_price(if zero_for_one {
+        get_next_sqrt_price_from_amount0_rounding_up(
+            sqrt_price, liquidity, amount_in, true,
+        )?
+    } else {
+        get_next_sqrt_price_from_amount1_rounding_down(
+            sqrt_price, liquidity, amount_in, true,
+        )?
+    })
+}
+
+/// Sqrt price after swapping for the output, of token1 if `zero_for_one`.
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price_x96: U160,
+    liquidity: u128,
+    amount_out: U256,
+    zero_for_one: bool,
+) -> Result<U160, MathError> {
+    check_pool(sqrt_price_x96, liquidity)?;
+    let sqrt_price = U256::from(sqrt_price_x96);
+    to_sqrt_price(if zero_for_one {
+        get_next_sqrt_price_from_amount1_rounding_down(
+            sqrt_price, liquidity, amount_out, false,
+        )?
+    } else {
+        get_next_sqrt_price_from_amount0_rounding_up(
+            sqrt_price, liquidity, amount_out, false,
+        )?
+    })
+}
+
+fn sorted(a: U160, b: U160) -> (U256, U256) {
+    let (a, b) = if a > b { (b, a) } else { (a, b) };
+    (U256::from(a), U256::from(b))
+}
+
+/// Amount of token0 between the sqrt prices, `liquidity * (1 / sqrt(a) - 1
+/// / sqrt(b))`.
+pub fn get_amount0_delta(
+    sqrt_ratio_a_x96: U160,
+    sqrt_ratio_b_x96: U160,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256, MathError> {
+    let (sqrt_ratio_a, sqrt_ratio_b) =
+        sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    if sqrt_ratio_a.is_zero() {
+        return Err(MathError::SqrtPriceOutOfBounds(
+            U160::ZERO,
+        ));
+    }
+    let numerator1 = U256::from(liquidity) << 96;
+    let numerator2 = sqrt_ratio_b - sqrt_ratio_a;
+    if round_up {
+        div_rounding_up(
+            mul_div_rounding_up(numerator1, numerator2, sqrt_ratio_b)?,
+            sqrt_ratio_a,
+        )
+    } else {
+        Ok(mul_div(numerator1, numerator2, sqrt_ratio_b)? / sqrt_ratio_a)
+    }
+}
+
+/// Amount of token1 between the sqrt prices, `liquidity * (sqrt(b) -
+/// sqrt(a))`.
+pub fn get_amount1_delta(
+    sqrt_ratio_a_x96: U160,
+    sqrt_ratio_b_x96: U160,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256, MathError> {
+    let (sqrt_ratio_a, sqrt_ratio_b) =
+        sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    let liquidity = U256::from(liquidity);
+    if round_up {
+        mul_div_rounding_up(
+            liquidity,
+            sqrt_ratio_b - sqrt_ratio_a,
+            Q96,
+        )
+    } else {
+        mul_div(
+            liquidity,
+            sqrt_ratio_b - sqrt_ratio_a,
+            Q96,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::v3::tick_math::MIN_SQRT_RATIO;
+
+    /// `encodePriceSqrt(121, 100)` of the Uniswap V3 tests, `1.1 * 2^96`.
+    const SQRT_PRICE_1_21: U160 = uint!(87150978765690771352898345369_U160);
+
+    const ETHER: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_amount_deltas() {
+        let one = U160::ONE << 96;
+
+        assert_eq!(
+            get_amount0_delta(one, SQRT_PRICE_1_21, ETHER, true),
+            Ok(U256::from(90_909_090_909_090_910_u128))
+        );
+        assert_eq!(
+            get_amount0_delta(SQRT_PRICE_1_21, one, ETHER, false),
+            Ok(U256::from(90_909_090_909_090_909_u128))
+        );
+        assert_eq!(
+            get_amount1_delta(one, SQRT_PRICE_1_21, ETHER, true),
+            Ok(U256::from(100_000_000_000_000_000_u128))
+        );
+        assert_eq!(
+            get_amount1_delta(one, SQRT_PRICE_1_21, ETHER, false),
+            Ok(U256::from(99_999_999_999_999_999_u128))
+        );
+    }
+
+    #[test]
+    fn test_next_sqrt_price() {
+        let one = U160::ONE << 96;
+        let tenth = U256::from(ETHER / 10);
+
+        assert_eq!(
+            get_next_sqrt_price_from_input(one, ETHER, tenth, false),
+            Ok(SQRT_PRICE_1_21)
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(one, ETHER, tenth, true),
+            Ok(uint!(
+                72025602285694852357767227579_U160
+            ))
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_output(
+                one,
+                ETHER,
+                U256::from(ETHER),
+                true
+            ),
+            Err(MathError::InsufficientLiquidity)
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(one, 0, tenth, true),
+            Err(MathError::InsufficientLiquidity)
+        );
+    }
+
+    fn sqrt_price() -> impl Strategy<Value = U160> {
+        (MIN_SQRT_RATIO.to::<u128>()..u128::MAX).prop_map(U160::from)
+    }
+
+    proptest! {
+        #[test]
+        fn test_input_covers_price_move(
+            sqrt_price in sqrt_price(),
+            liquidity in 1..u128::MAX,
+            amount_in in 0..u128::MAX,
+            zero_for_one: bool,
+        ) {
+            let amount_in = U256::from(amount_in);
+            let Ok(next) = get_next_sqrt_price_from_input(
+                sqrt_price,
+                liquidity,
+                amount_in,
+                zero_for_one,
+            ) else {
+                return Ok(());
+            };
+
+            // The price moves in the direction of the swap, and never more
+            // than the input pays for.
+            let required = if zero_for_one {
+                prop_assert!(next <= sqrt_price);
+                get_amount0_delta(next, sqrt_price, liquidity, true)
+            } else {
+                prop_assert!(next >= sqrt_price);
+                get_amount1_delta(sqrt_price, next, liquidity, true)
+            };
+            prop_assert!(required.unwrap() <= amount_in);
+        }
+
+        #[test]
+        fn test_amount_deltas_match_float(
+            sqrt_price_a in sqrt_price(),
+            sqrt_price_b in sqrt_price(),
+            liquidity in 1u128 << 64..u128::MAX,
+        ) {
+            let (a, b) = sorted(sqrt_price_a, sqrt_price_b);
+            // The difference is exact, even if the prices are close.
+            let difference = f64::from(b - a);
+            let (a, b) = (f64::from(a), f64::from(b));
+            let q96 = 2_f64.powi(96);
+            let liquidity_f64 = liquidity as f64;
+            let amount0 = liquidity_f64 * q96 * difference / b / a;
+            let amount1 = liquidity_f64 * difference / q96;
+
+            let delta0 = f64::from(
+                get_amount0_delta(sqrt_price_a, sqrt_price_b, liquidity, false)
+                    .unwrap(),
+            );
+            let delta1 = f64::from(
+                get_amount1_delta(sqrt_price_a, sqrt_price_b, liquidity, false)
+                    .unwrap(),
+            );
+            prop_assert!((delta0 - amount0).abs() <= amount0 * 1e-9 + 1.0);
+            prop_assert!((delta1 - amount1).abs() <= amount1 * 1e-9 + 1.0);
+        }
+    }
+}
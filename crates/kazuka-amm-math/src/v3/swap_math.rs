@@ -0,0 +1,261 @@
+//! Swaps within a single tick range, as the `SwapMath` library.
+
+use alloy::primitives::{I256, U160, U256};
+
+use crate::{
+    FEE_DENOMINATOR,
+    error::MathError,
+    full_math::{mul_div, mul_div_rounding_up},
+    v3::sqrt_price_math::{
+        get_amount0_delta, get_amount1_delta, get_next_sqrt_price_from_input,
+        get_next_sqrt_price_from_output,
+    },
+};
+
+/// Result of a swap within a tick range, see [compute_swap_step].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapStep {
+    /// Sqrt price after the swap, the target one if it was reached.
+    pub sqrt_price_next_x96: U160,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    /// Fee paid on top of `amount_in`.
+    pub fee_amount: U256,
+}
+
+/// Swaps the remaining amount, an exact input if positive and an exact
+/// output if negative, until the target sqrt price is reached. The swap is
+/// of token0 for token1 if the target price is below the current one.
+pub fn compute_swap_step(
+    sqrt_ratio_current_x96: U160,
+    sqrt_ratio_target_x96: U160,
+    liquidity: u128,
+    amount_remaining: I256,
+    fee_pips: u32,
+) -> Result<SwapStep, MathError> {
+    let zero_for_one = sqrt_ratio_current_x96 >= sqrt_ratio_target_x96;
+    let exact_in = !amount_remaining.is_negative();
+    let amount_remaining_abs = amount_remaining.unsigned_abs();
+    let fee_denominator = U256::from(FEE_DENOMINATOR);
+
+    let mut amount_in = U256::ZERO;
+    let mut amount_out = U256::ZERO;
+    let sqrt_price_next_x96 = if exact_in {
+        let amount_remaining_less_fee = mul_div(
+            amount_remaining_abs,
+            U256::from(FEE_DENOMINATOR - fee_pips),
+            fee_denominator,
+        )?;
+        amount_in = if zero_for_one {
+            get_amount0_delta(
+                sqrt_ratio_target_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                true,
+            )?
+        } else {
+            get_amount1_delta(
+                sqrt_ratio_current_x96,
+                sqrt_ratio_target_x96,
+                liquidity,
+                true,
+            )?
+        };
+        if amount_remaining_less_fee >= amount_in {
+            sqrt_ratio_target_x96
+        } else {
+            get_next_sqrt_price_from_input(
+                sqrt_ratio_current_x96,
+                liquidity,
+                amount_remaining_less_fee,
+                zero_for_one,
+            )?
+        }
+    } else {
+        amount_out = if zero_for_one {
+            get_amount1_delta(
+                sqrt_ratio_target_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                false,
+            )?
+        } else {
+            get_amount0_delta(
+                sqrt_ratio_current_x96,
+                sqrt_ratio_target_x96,
+                liquidity,
+                false,
+            )?
+        };
+        if amount_remaining_abs >= amount_out {
+            sqrt_ratio_target_x96
+        } else {
+            get_next_sqrt_price_from_output(
+                sqrt_ratio_current_x96,
+                liquidity,
+                amount_remaining_abs,
+                zero_for_one,
+            )?
+        }
+    };
+
+    let max = sqrt_ratio_target_x96 == sqrt_price_next_x96;
+    if zero_for_one {
+        if !(max && exact_in) {
+            amount_in = get_amount0_delta(
+                sqrt_price_next_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                true,
+            )?;
+        }
+        if !(max && !exact_in) {
+            amount_out = get_amount1_delta(
+                sqrt_price_next_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                false,
+            )?;
+        }
+    } else {
+        if !(max && exact_in) {
+            amount_in = get_amount1_delta(
+                sqrt_ratio_current_x96,
+                sqrt_price_next_x96,
+                liquidity,
+                true,
+            )?;
+        }
+        if !(max && !exact_in) {
+            amount_out = get_amount0_delta(
+                sqrt_ratio_current_x96,
+                sqrt_price_next_x96,
+                liquidity,
+                false,
+            )?;
+        }
+    }
+
+    // The output can't exceed the remaining amount.
+    if !exact_in && amount_out > amount_remaining_abs {
+        amount_out = amount_remaining_abs;
+    }
+
+    let fee_amount = if exact_in && !max {
+        // The target wasn't reached, so the remainder is the fee.
+        amount_remaining_abs - amount_in
+    } else {
+        mul_div_rounding_up(
+            amount_in,
+            U256::from(fee_pips),
+            U256::from(FEE_DENOMINATOR - fee_pips),
+        )?
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next_x96,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::uint;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::v3::tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO};
+
+    /// `encodePriceSqrt(101, 100)` of the Uniswap V3 tests.
+    const SQRT_PRICE_1_01: U160 = uint!(79623317895830914510639640423_U160);
+
+    const ETHER: i128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_capped_at_price_target() {
+        let one = U160::ONE << 96;
+
+        for amount_remaining in [ETHER, -ETHER] {
+            let step = compute_swap_step(
+                one,
+                SQRT_PRICE_1_01,
+                2 * ETHER as u128,
+                I256::try_from(amount_remaining).unwrap(),
+                600,
+            )
+            .unwrap();
+
+            assert_eq!(
+                step,
+                SwapStep {
+                    sqrt_price_next_x96: SQRT_PRICE_1_01,
+                    amount_in: U256::from(9_975_124_224_178_055_u128),
+                    amount_out: U256::from(9_925_619_580_021_728_u128),
+                    fee_amount: U256::from(5_988_667_735_148_u128),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_input_fully_spent() {
+        let one = U160::ONE << 96;
+        let step = compute_swap_step(
+            one,
+            U160::ONE << 100,
+            2 * ETHER as u128,
+            I256::try_from(ETHER).unwrap(),
+            600,
+        )
+        .unwrap();
+
+        assert_eq!(
+            step.amount_in + step.fee_amount,
+            U256::from(ETHER as u128)
+        );
+        assert_eq!(
+            step.amount_out,
+            U256::from(666_399_946_655_997_866_u128)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_swap_step_bounds(
+            sqrt_price in MIN_SQRT_RATIO.to::<u128>()..u128::MAX,
+            target in MIN_SQRT_RATIO.to::<u128>()..u128::MAX,
+            liquidity: u128,
+            amount_remaining: i128,
+            fee_pips in 1..FEE_DENOMINATOR,
+        ) {
+            let (sqrt_price, target) =
+                (U160::from(sqrt_price), U160::from(target));
+            let amount_remaining = I256::try_from(amount_remaining).unwrap();
+            let Ok(step) = compute_swap_step(
+                sqrt_price,
+                target,
+                liquidity,
+                amount_remaining,
+                fee_pips,
+            ) else {
+                return Ok(());
+            };
+
+            // The price moves towards the target, without passing it.
+            let (low, high) = (sqrt_price.min(target), sqrt_price.max(target));
+            prop_assert!(low <= step.sqrt_price_next_x96);
+            prop_assert!(step.sqrt_price_next_x96 <= high);
+            prop_assert!(step.sqrt_price_next_x96 >= MIN_SQRT_RATIO);
+            prop_assert!(step.sqrt_price_next_x96 < MAX_SQRT_RATIO);
+
+            let remaining = amount_remaining.unsigned_abs();
+            if amount_remaining.is_negative() {
+                prop_assert!(step.amount_out <= remaining);
+            } else {
+                prop_assert!(step.amount_in + step.fee_amount <= remaining);
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Conversions between ticks and sqrt prices, as the `TickMath` library.
+
+use alloy::primitives::{U160, U256, uint};
+
+use crate::error::MathError;
+
+/// Minimum tick, whose price is about 2^-128.
+pub const MIN_TICK: i32 = -887_272;
+/// Maximum tick, whose price is about 2^128.
+pub const MAX_TICK: i32 = -MIN_TICK;
+
+/// Sqrt price of [MIN_TICK], as a Q64.96.
+pub const MIN_SQRT_RATIO: U160 = uint!(4295128739_U160);
+/// Sqrt price of [MAX_TICK], as a Q64.96.
+pub const MAX_SQRT_RATIO: U160 =
+    uint!(1461446703485210103287273052203988822378723970342_U160);
+
+/// `2^128 / sqrt(1.0001)^(2^i)` as Q128.128, for every bit `i` of the ticks.
+const RATIOS: [U256; 20] = [
+    uint!(0xfffcb933bd6fad37aa2d162d1a594001_U256),
+    uint!(0xfff97272373d413259a46990580e213a_U256),
+    uint!(0xfff2e50f5f656932ef12357cf3c7fdcc_U256),
+    uint!(0xffe5caca7e10e4e61c3624eaa0941cd0_U256),
+    uint!(0xffcb9843d60f6159c9db58835c926644_U256),
+    uint!(0xff973b41fa98c081472e6896dfb254c0_U256),
+    uint!(0xff2ea16466c96a3843ec78b326b52861_U256),
+    uint!(0xfe5dee046a99a2a811c461f1969c3053_U256),
+    uint!(0xfcbe86c7900a88aedcffc83b479aa3a4_U256),
+    uint!(0xf987a7253ac413176f2b074cf7815e54_U256),
+    uint!(0xf3392b0822b70005940c7a398e4b70f3_U256),
+    uint!(0xe7159475a2c29b7443b29c7fa6e889d9_U256),
+    uint!(0xd097f3bdfd2022b8845ad8f792aa5825_U256),
+    uint!(0xa9f746462d870fdf8a65dc1f90e061e5_U256),
+    uint!(0x70d869a156d2a1b890bb3df62baf32f7_U256),
+    uint!(0x31be135f97d08fd981231505542fcfa6_U256),
+    uint!(0x9aa508b5b7a84e1c677de54f3e99bc9_U256),
+    uint!(0x5d6af8dedb81196699c329225ee604_U256),
+    uint!(0x2216e584f5fa1ea926041bedfe98_U256),
+    uint!(0x48a170391f7dc42444e8fa2_U256),
+];
+
+/// Sqrt price of the tick, `sqrt(1.0001^tick)` as a Q64.96.
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<U160, MathError> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK.unsigned_abs() {
+        return Err(MathError::TickOutOfBounds(tick));
+    }
+    let mut ratio = if abs_tick & 1 != 0 {
+        RATIOS[0]
+    } else {
+        U256::ONE << 128
+    };
+    for (bit, factor) in RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * factor) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    // Rounds up, so the tick of the price is the tick.
+    let remainder = ratio & U256::from(u32::MAX);
+    let sqrt_price = (ratio >> 32) + U256::from(u8::from(!remainder.is_zero()));
+    Ok(sqrt_price.to::<U160>())
+}
+
+/// Greatest tick, whose sqrt price is at most the given one.
+pub fn get_tick_at_sqrt_ratio(sqrt_price_x96: U160) -> Result<i32, MathError> {
+    if sqrt_price_x96 < MIN_SQRT_RATIO || sqrt_price_x96 >= MAX_SQRT_RATIO {
+        return Err(MathError::SqrtPriceOutOfBounds(
+            sqrt_price_x96,
+        ));
+    }
+    // The sqrt price of `low` is at most the given one, the one of `high`
+    // is above it.
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if get_sqrt_ratio_at_tick(mid)? <= sqrt_price_x96 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_sqrt_ratio_bounds() {
+        assert_eq!(
+            get_sqrt_ratio_at_tick(MIN_TICK),
+            Ok(MIN_SQRT_RATIO)
+        );
+        assert_eq!(
+            get_sqrt_ratio_at_tick(MAX_TICK),
+            Ok(MAX_SQRT_RATIO)
+        );
+        assert_eq!(
+            get_sqrt_ratio_at_tick(0),
+            Ok(U160::ONE << 96)
+        );
+        assert_eq!(
+            get_sqrt_ratio_at_tick(MAX_TICK + 1),
+            Err(MathError::TickOutOfBounds(MAX_TICK + 1))
+        );
+        assert_eq!(
+            get_tick_at_sqrt_ratio(MIN_SQRT_RATIO),
+            Ok(MIN_TICK)
+        );
+        assert_eq!(
+            get_tick_at_sqrt_ratio(MAX_SQRT_RATIO - U160::ONE),
+            Ok(MAX_TICK - 1)
+        );
+        assert!(get_tick_at_sqrt_ratio(MAX_SQRT_RATIO).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_sqrt_ratio_matches_float(tick in MIN_TICK..=MAX_TICK) {
+            let sqrt_price = f64::from(get_sqrt_ratio_at_tick(tick).unwrap());
+            let expected =
+                1.0001_f64.powf(f64::from(tick) / 2.0) * 2_f64.powi(96);
+
+            prop_assert!((sqrt_price - expected).abs() / expected < 1e-9);
+        }
+
+        #[test]
+        fn test_tick_at_sqrt_ratio(tick in MIN_TICK..MAX_TICK, offset: u64) {
+            let sqrt_price = get_sqrt_ratio_at_tick(tick).unwrap();
+            let next = get_sqrt_ratio_at_tick(tick + 1).unwrap();
+            let within = sqrt_price + U160::from(offset) % (next - sqrt_price);
+
+            prop_assert_eq!(get_tick_at_sqrt_ratio(sqrt_price), Ok(tick));
+            prop_assert_eq!(get_tick_at_sqrt_ratio(within), Ok(tick));
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Runs a single strategy across Ethereum mainnet and an L2.
+//!
+//! ```sh
+//! MAINNET_WS_URL=wss://... L2_WS_URL=wss://... \
+//!   cargo run -p kazuka-core --example multi_chain
+//! ```
+
+use std::{env, sync::Arc};
+
+use alloy::{
+    network::AnyNetwork,
+    providers::{DynProvider, ProviderBuilder, WsConnect},
+};
+use async_trait::async_trait;
+use kazuka_core::{
+    engine::Engine,
+    error::KazukaError,
+    event_sources::block_event_source::{BlockEventSource, NewBlock},
+    types::{ChainEvent, ChainEventSource, Strategy},
+};
+
+/// Connects to a node over WebSocket.
+async fn connect(
+    url: String,
+) -> Result<Arc<DynProvider<AnyNetwork>>, KazukaError> {
+    let provider = ProviderBuilder::new()
+        .network::<AnyNetwork>()
+        .connect_ws(WsConnect::new(url))
+        .await?;
+    Ok(Arc::new(DynProvider::new(provider)))
+}
+
+/// Logs new blocks of every chain it receives events from.
+struct BlockLogger;
+
+#[async_trait]
+impl Strategy<ChainEvent<NewBlock>, ()> for BlockLogger {
+    async fn process_event(&mut self, event: ChainEvent<NewBlock>) -> Vec<()> {
+        tracing::info!(
+            chain_id = event.chain_id,
+            number = event.inner.number,
+            "New block"
+        );
+        vec![]
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt::init();
+
+    let mainnet = connect(env::var("MAINNET_WS_URL")?).await?;
+    let l2 = connect(env::var("L2_WS_URL")?).await?;
+
+    // Each event source is bound to its own provider and tags events with
+    // the chain id reported by that provider.
+    let mainnet_blocks = ChainEventSource::for_provider(
+        &mainnet,
+        Box::new(BlockEventSource::new(Arc::clone(
+            &mainnet,
+        ))),
+    )
+    .await?;
+    let l2_blocks = ChainEventSource::for_provider(
+        &l2,
+        Box::new(BlockEventSource::new(Arc::clone(&l2))),
+    )
+    .await?;
+
+    let engine: Engine<ChainEvent<NewBlock>, ()> = Engine::default()
+        .add_event_source(Box::new(mainnet_blocks))
+        .add_event_source(Box::new(l2_blocks))
+        .add_strategy(Box::new(BlockLogger));
+
+    let mut tasks = engine.run().await?;
+    while let Some(result) = tasks.join_next().await {
+        tracing::info!("result: {:?}", result);
+    }
+
+    Ok(())
+}
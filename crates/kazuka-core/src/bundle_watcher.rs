@@ -0,0 +1,221 @@
+use std::{sync::Arc, time::Duration};
+
+use alloy::{
+    network::AnyNetwork,
+    primitives::{BlockNumber, U64},
+    providers::{DynProvider, Provider},
+    rpc::types::mev::BundleStats,
+};
+use kazuka_mev_share::rpc::FlashbotsApiClient;
+use tokio::time::{self, MissedTickBehavior};
+
+use crate::{
+    error::KazukaError,
+    event_sources::bundle_stats_event_source::{
+        TrackedBundle, inclusion_block,
+    },
+};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Final outcome of a submitted bundle.
+#[derive(Clone, Debug)]
+pub enum BundleOutcome {
+    /// One of the bundle transactions landed on-chain.
+    Included { block_number: BlockNumber },
+    /// The bundle was not included by its last valid block, `stats` are the
+    /// last ones reported by the relay.
+    Expired { stats: Option<BundleStats> },
+    /// The outcome couldn't be determined before the timeout.
+    Failed { reason: String },
+}
+
+/// Waits for the outcome of a single submitted bundle, by polling
+/// `flashbots_getBundleStatsV2` and the chain.
+///
+/// Use the [BundleStatsEventSource](crate::event_sources::bundle_stats_event_source::BundleStatsEventSource)
+/// to track the outcomes of many bundles as a stream of events instead.
+///
+/// ```ignore
+/// let bundle_hash = client.send_bundle(bundle.clone()).await?.bundle_hash;
+/// let bundle = TrackedBundle::from_eth_bundle(bundle_hash, &bundle);
+/// match watcher.wait_for_inclusion(&bundle, Duration::from_secs(60)).await {
+///     BundleOutcome::Included { block_number } => { /* ... */ }
+///     outcome => tracing::warn!(?outcome, "Bundle not included"),
+/// }
+/// ```
+pub struct BundleWatcher {
+    client: Arc<dyn FlashbotsApiClient + Send + Sync>,
+    provider: Arc<DynProvider<AnyNetwork>>,
+    poll_interval: Duration,
+}
+
+impl BundleWatcher {
+    pub fn new(
+        client: Arc<dyn FlashbotsApiClient + Send + Sync>,
+        provider: Arc<DynProvider<AnyNetwork>>,
+    ) -> Self {
+        Self {
+            client,
+            provider,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Sets how often the bundle is polled.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Polls until the bundle is included or expired, resolving to
+    /// [BundleOutcome::Failed] if neither happens within `timeout`.
+    pub async fn wait_for_inclusion(
+        &self,
+        bundle: &TrackedBundle,
+        timeout: Duration,
+    ) -> BundleOutcome {
+        let mut last_error = None;
+        let outcome = time::timeout(
+            timeout,
+            self.poll_until_done(bundle, &mut last_error),
+        )
+        .await;
+        outcome.unwrap_or_else(|_| {
+            let reason = match last_error {
+                Some(e) => format!("timed out after {timeout:?}: {e}"),
+                None => format!("timed out after {timeout:?}"),
+            };
+            BundleOutcome::Failed { reason }
+        })
+    }
+
+    async fn poll_until_done(
+        &self,
+        bundle: &TrackedBundle,
+        last_error: &mut Option<KazukaError>,
+    ) -> BundleOutcome {
+        let mut interval = time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut stats = None;
+
+        loop {
+            interval.tick().await;
+            match self.poll(bundle, &mut stats).await {
+                Ok(Some(outcome)) => return outcome,
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        bundle_hash = ?bundle.bundle_hash,
+                        "Error polling bundle: {}",
+                        e
+                    );
+                    *last_error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// Polls the bundle once, returns its outcome if it's final.
+    async fn poll(
+        &self,
+        bundle: &TrackedBundle,
+        stats: &mut Option<BundleStats>,
+    ) -> Result<Option<BundleOutcome>, KazukaError> {
+        let block_number = self.provider.get_block_number().await?;
+        if let Some(block_number) =
+            inclusion_block(&self.provider, bundle).await?
+        {
+            return Ok(Some(BundleOutcome::Included {
+                block_number,
+            }));
+        }
+        if block_number > bundle.max_block {
+            return Ok(Some(BundleOutcome::Expired {
+                stats: stats.take(),
+            }));
+        }
+
+        match self
+            .client
+            .get_bundle_stats(
+                bundle.bundle_hash,
+                U64::from(bundle.target_block),
+            )
+            .await
+        {
+            Ok(new_stats) => *stats = Some(new_stats),
+            // Stats are informational, the chain decides the outcome.
+            Err(e) => tracing::debug!(
+                bundle_hash = ?bundle.bundle_hash,
+                "Error getting bundle stats: {}",
+                e
+            ),
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::B256, providers::ProviderBuilder,
+        transports::mock::Asserter,
+    };
+    use jsonrpsee::http_client::HttpClientBuilder;
+
+    use super::*;
+
+    fn watcher(asserter: Asserter) -> BundleWatcher {
+        let provider = ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .connect_mocked_client(asserter)
+            .erased();
+        // Stats are not requested once the target block has passed.
+        let client = HttpClientBuilder::default()
+            .build("http://127.0.0.1:1")
+            .unwrap();
+        BundleWatcher::new(Arc::new(client), Arc::new(provider))
+            .with_poll_interval(Duration::from_millis(10))
+    }
+
+    fn bundle() -> TrackedBundle {
+        TrackedBundle {
+            bundle_hash: B256::ZERO,
+            target_block: 100,
+            max_block: 100,
+            tx_hashes: vec![B256::repeat_byte(1)],
+            origin: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expired_bundle() {
+        let asserter = Asserter::new();
+        asserter.push_success(&U64::from(101));
+        asserter.push_success(&serde_json::Value::Null);
+
+        let outcome = watcher(asserter)
+            .wait_for_inclusion(&bundle(), Duration::from_secs(1))
+            .await;
+
+        assert!(matches!(
+            outcome,
+            BundleOutcome::Expired { stats: None }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let asserter = Asserter::new();
+        // The provider fails every request.
+        let outcome = watcher(asserter)
+            .wait_for_inclusion(&bundle(), Duration::from_millis(50))
+            .await;
+
+        assert!(matches!(
+            outcome,
+            BundleOutcome::Failed { .. }
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+//! Source of the current time of the time-dependent components (retries,
+//! interval event sources, deduplication windows, staleness checks), so that
+//! they can be tested deterministically:
+//! - [SystemClock] used by default,
+//! - [MockClock] advancing with the paused time of tokio.
+//!
+//! ```ignore
+//! #[tokio::test(start_paused = true)]
+//! async fn test_expiry() {
+//!     let clock = Arc::new(MockClock::new(start));
+//!     let executor = ActionDedup::new(inner, key, Duration::from_secs(12))
+//!         .with_clock(clock.clone());
+//!     tokio::time::advance(Duration::from_secs(12)).await;
+//! }
+//! ```
+
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::future::BoxFuture;
+use tokio::time::Instant;
+
+/// Tells the time, and waits for it to pass.
+pub trait Clock: Debug + Send + Sync {
+    /// Current wall-clock time.
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Current monotonic time.
+    fn instant(&self) -> Instant;
+
+    /// Waits until the duration has elapsed.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// Shared [SystemClock], the default clock of the components.
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// Time of the system, and timers of tokio.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Clock starting at a given wall-clock time, and advancing with the time
+/// of tokio, so that [tokio::time::pause] and [tokio::time::advance] make
+/// it jump forward instantly.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    start: DateTime<Utc>,
+    started_at: Instant,
+}
+
+impl MockClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            started_at: Instant::now(),
+        }
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        let elapsed =
+            TimeDelta::from_std(self.started_at.elapsed()).unwrap_or_default();
+        self.start + elapsed
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_mock_clock() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let clock = MockClock::new(start);
+        let instant = clock.instant();
+        assert_eq!(clock.now(), start);
+
+        clock.sleep(Duration::from_secs(3600)).await;
+        assert_eq!(clock.now(), start + TimeDelta::hours(1));
+        assert_eq!(
+            clock.instant() - instant,
+            Duration::from_secs(3600)
+        );
+    }
+}
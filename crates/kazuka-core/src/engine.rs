@@ -1,17 +1,50 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc, time::Duration};
 
 use tokio::{
-    sync::broadcast::{self, Sender},
+    sync::broadcast::{self, Sender, error::RecvError},
     task::JoinSet,
 };
 use tokio_stream::StreamExt;
+use tracing::{Instrument, field};
 
 use crate::{
     error::KazukaError,
-    types::{EventSource, Executor, Strategy},
+    health::{ComponentStatus, Health},
+    simulation::Simulator,
+    stats::EngineStats,
+    types::{EventSource, Executor, SimulatedAction, Strategy},
 };
 
 const DEFAULT_CHANNEL_CAPACITY: usize = 512;
+/// Number of attempts to get a stream from an event source, which failed
+/// with a retryable error.
+const EVENT_SOURCE_MAX_RETRIES: u32 = 5;
+/// Delay before the first retry, doubling with every further retry.
+const EVENT_SOURCE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Awaits the call of a component, failing with [KazukaError::Timeout] if it
+/// takes longer than the timeout, if any.
+async fn call<T>(
+    call: impl Future<Output = T>,
+    timeout: Option<Duration>,
+    operation: &str,
+) -> Result<T, KazukaError> {
+    match timeout {
+        Some(timeout) => {
+            tokio::time::timeout(timeout, call).await.map_err(|_| {
+                KazukaError::Timeout {
+                    operation: operation.to_string(),
+                    timeout,
+                }
+            })
+        }
+        None => Ok(call.await),
+    }
+}
+
+/// Tells whether an event is latency-critical, see
+/// [Engine::with_priority_events].
+type PriorityFilter<E> = Arc<dyn Fn(&E) -> bool + Send + Sync>;
 
 pub struct Engine<E, A> {
     event_sources: Vec<Box<dyn EventSource<E>>>,
@@ -20,6 +53,19 @@ pub struct Engine<E, A> {
 
     event_channel_capacity: usize,
     action_channel_capacity: usize,
+
+    /// Events sent through the priority lane.
+    priority: Option<PriorityFilter<E>>,
+    /// Maximum duration of a call to `process_event` or `execute`.
+    call_timeout: Option<Duration>,
+    /// Simulates the actions instead of executing them, on dry runs.
+    simulator: Option<Box<dyn Simulator<A>>>,
+
+    /// Statuses of the components, named by kind and index, e.g.
+    /// `event_source_0`.
+    health: Health,
+    /// Activity of the event bus, by the same component names.
+    stats: EngineStats,
 }
 
 impl<E, A> Engine<E, A> {
@@ -30,8 +76,70 @@ impl<E, A> Engine<E, A> {
             executors: vec![],
             event_channel_capacity: DEFAULT_CHANNEL_CAPACITY,
             action_channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            priority: None,
+            call_timeout: None,
+            simulator: None,
+            health: Health::new(),
+            stats: EngineStats::new(),
         }
     }
+
+    /// Reports the statuses of the components to the given [Health].
+    pub fn with_health(mut self, health: Health) -> Self {
+        self.health = health;
+        self
+    }
+
+    pub fn health(&self) -> &Health {
+        &self.health
+    }
+
+    /// Records the activity of the event bus to the given [EngineStats].
+    pub fn with_stats(mut self, stats: EngineStats) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    pub fn stats(&self) -> &EngineStats {
+        &self.stats
+    }
+
+    /// Sends the events matching the filter (e.g. MEV-Share hints) through a
+    /// separate lane, which strategies drain before the other events (e.g.
+    /// block ticks or log backfill), so that latency-critical events don't
+    /// queue behind a backlog. Events of different lanes may therefore be
+    /// processed in a different order than they were emitted in.
+    pub fn with_priority_events(
+        mut self,
+        filter: impl Fn(&E) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.priority = Some(Arc::new(filter));
+        self
+    }
+
+    /// Cancels calls to [Strategy::process_event] and [Executor::execute]
+    /// taking longer than the timeout, e.g. because of a hung RPC request,
+    /// so that they don't stall the loops of the strategies and executors.
+    /// Timed out events produce no actions, and the strategy keeps any
+    /// state it has updated before being cancelled.
+    pub fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = Some(timeout);
+        self
+    }
+
+    /// Runs the engine without submitting anything: the events are
+    /// processed with [Strategy::simulate], and the actions are simulated
+    /// (e.g. with [AnvilSimulator](crate::simulation::AnvilSimulator) or
+    /// [RelaySimulator](crate::simulation::RelaySimulator)) instead of being
+    /// executed. The simulated outcomes are logged along with the ones the
+    /// strategies expected.
+    pub fn with_dry_run(
+        mut self,
+        simulator: impl Simulator<A> + 'static,
+    ) -> Self {
+        self.simulator = Some(Box::new(simulator));
+        self
+    }
 }
 
 impl<E, A> Default for Engine<E, A> {
@@ -66,75 +174,265 @@ where
     pub async fn run(self) -> Result<JoinSet<()>, KazukaError> {
         let (event_sender, _): (Sender<E>, _) =
             broadcast::channel(self.event_channel_capacity);
+        let (priority_sender, _): (Sender<E>, _) =
+            broadcast::channel(self.event_channel_capacity);
         let (action_sender, _): (Sender<A>, _) =
             broadcast::channel(self.action_channel_capacity);
+        let (simulated_sender, _): (Sender<SimulatedAction<A>>, _) =
+            broadcast::channel(self.action_channel_capacity);
+        let dry_run = self.simulator.is_some();
 
         let mut tasks = JoinSet::new();
 
-        for executor in self.executors {
+        if let Some(simulator) = self.simulator {
+            if !self.executors.is_empty() {
+                tracing::info!(
+                    "Dry run, not starting {} executors",
+                    self.executors.len()
+                );
+            }
+            let mut receiver = simulated_sender.subscribe();
+            let call_timeout = self.call_timeout;
+            self.health.set("simulator", ComponentStatus::Running);
+            let task = async move {
+                tracing::info!("Starting simulator...");
+                loop {
+                    let SimulatedAction {
+                        action,
+                        expected_profit,
+                    } = match receiver.recv().await {
+                        Ok(action) => action,
+                        Err(e) => {
+                            tracing::error!("Error receiving action: {}", e);
+                            continue;
+                        }
+                    };
+                    let result = call(
+                        simulator.simulate(&action),
+                        call_timeout,
+                        "Simulating action",
+                    )
+                    .await
+                    .and_then(|result| result);
+                    match result {
+                        Ok(outcome) => {
+                            metrics::counter!(
+                                "kazuka_dry_run_actions_total",
+                                "success" => outcome.success.to_string()
+                            )
+                            .increment(1);
+                            tracing::info!(
+                                ?action,
+                                ?expected_profit,
+                                success = outcome.success,
+                                net_profit = %outcome.net_profit(),
+                                error = ?outcome.error,
+                                "Simulated action"
+                            );
+                        }
+                        Err(e) => tracing::error!(
+                            class = ?e.classify(),
+                            ?action,
+                            "Error simulating action: {}",
+                            e
+                        ),
+                    }
+                }
+            };
+            tasks.spawn(task.instrument(tracing::info_span!("simulator")));
+        }
+
+        let executors = if dry_run { vec![] } else { self.executors };
+        for (i, executor) in executors.into_iter().enumerate() {
             let mut receiver = action_sender.subscribe();
-            tasks.spawn(async move {
+            let id = format!("executor_{i}");
+            let span =
+                tracing::info_span!("executor", %id, name = executor.name());
+            let stats = self.stats.clone();
+            let call_timeout = self.call_timeout;
+            self.health.set(&id, ComponentStatus::Running);
+            let task = async move {
                 tracing::info!("Starting executor...");
                 loop {
                     match receiver.recv().await {
-                        Ok(action) => match executor.execute(action).await {
-                            Ok(()) => {}
-                            Err(e) => {
-                                tracing::error!("Error executing action: {}", e)
+                        Ok(action) => {
+                            stats.record_received(&id);
+                            let result = call(
+                                executor.execute(action),
+                                call_timeout,
+                                "Executing action",
+                            )
+                            .await
+                            .and_then(|result| result);
+                            stats.record_executed(&id, result.is_ok());
+                            if let Err(e) = result {
+                                tracing::error!(
+                                    class = ?e.classify(),
+                                    "Error executing action: {}",
+                                    e
+                                );
                             }
-                        },
+                        }
                         Err(e) => {
+                            if let RecvError::Lagged(skipped) = e {
+                                stats.record_lagged(&id, skipped);
+                            }
                             tracing::error!("Error receiving action: {}", e)
                         }
                     }
                 }
-            });
+            };
+            tasks.spawn(task.instrument(span));
         }
 
-        for mut strategy in self.strategies {
+        for (i, mut strategy) in self.strategies.into_iter().enumerate() {
             let mut event_receiver = event_sender.subscribe();
+            let mut priority_receiver = priority_sender.subscribe();
+            // Closed once all event sources are done.
+            let mut priority_open = self.priority.is_some();
             let action_sender = action_sender.clone();
-            tracing::info!("Syncing strategy's state...");
-            strategy.sync_state().await?;
-            tasks.spawn(async move {
+            let simulated_sender = simulated_sender.clone();
+            let id = format!("strategy_{i}");
+            let name = strategy.name().to_string();
+            let span = tracing::info_span!("strategy", %id, %name);
+            let stats = self.stats.clone();
+            let call_timeout = self.call_timeout;
+            span.in_scope(|| tracing::info!("Syncing strategy's state..."));
+            self.health.set(&id, ComponentStatus::Starting);
+            if let Err(e) = strategy.sync_state().instrument(span.clone()).await
+            {
+                self.health.set(id, ComponentStatus::Failed);
+                return Err(e.context(format!("Syncing strategy {name}")));
+            }
+            self.health.set(&id, ComponentStatus::Running);
+            let task = async move {
                 tracing::info!("Starting strategy...");
                 loop {
-                    match event_receiver.recv().await {
+                    let received = tokio::select! {
+                        biased;
+                        received = priority_receiver.recv(),
+                            if priority_open => received,
+                        received = event_receiver.recv() => received,
+                    };
+                    match received {
                         Ok(event) => {
-                            let actions = strategy.process_event(event).await;
+                            stats.record_received(&id);
+                            let span = tracing::info_span!(
+                                "process_event",
+                                actions = field::Empty
+                            );
+                            let process = async {
+                                if dry_run {
+                                    return strategy.simulate(event).await;
+                                }
+                                strategy
+                                    .process_event(event)
+                                    .await
+                                    .into_iter()
+                                    .map(SimulatedAction::new)
+                                    .collect()
+                            };
+                            let actions = call(
+                                process.instrument(span.clone()),
+                                call_timeout,
+                                "Processing event",
+                            )
+                            .await
+                            .unwrap_or_else(|e| {
+                                tracing::error!(
+                                    class = ?e.classify(),
+                                    "Error processing event: {}",
+                                    e
+                                );
+                                vec![]
+                            });
+                            span.record("actions", actions.len());
+                            let _enter = span.enter();
                             for action in actions {
-                                match action_sender.send(action) {
-                                    Ok(_) => {}
-                                    Err(e) => tracing::error!(
-                                        "Error sending action: {}",
-                                        e
-                                    ),
+                                let sent = if dry_run {
+                                    simulated_sender.send(action).is_ok()
+                                } else {
+                                    action_sender.send(action.action).is_ok()
+                                };
+                                if !sent {
+                                    tracing::error!(
+                                        "Error sending action: no receivers"
+                                    );
                                 }
                             }
                         }
+                        Err(RecvError::Closed) if priority_open => {
+                            priority_open = false;
+                        }
                         Err(e) => {
+                            if let RecvError::Lagged(skipped) = e {
+                                stats.record_lagged(&id, skipped);
+                            }
                             tracing::error!("Error receiving event: {}", e)
                         }
                     }
                 }
-            });
+            };
+            tasks.spawn(task.instrument(span));
         }
 
-        for event_source in self.event_sources {
+        for (i, event_source) in self.event_sources.into_iter().enumerate() {
             let event_sender = event_sender.clone();
-            tasks.spawn(async move {
+            let priority_sender = priority_sender.clone();
+            let priority = self.priority.clone();
+            let id = format!("event_source_{i}");
+            let span = tracing::info_span!(
+                "event_source",
+                %id,
+                name = event_source.name()
+            );
+            let health = self.health.clone();
+            let stats = self.stats.clone();
+            health.set(&id, ComponentStatus::Starting);
+            let task = async move {
                 tracing::info!("Starting event source...");
-                let mut event_stream = event_source
-                    .get_event_stream()
-                    .await
-                    .expect("Event source didn't return event stream");
+                let mut attempt = 0;
+                let mut event_stream = loop {
+                    let e = match event_source.get_event_stream().await {
+                        Ok(event_stream) => break event_stream,
+                        Err(e) => e,
+                    };
+                    if !e.classify().is_retryable()
+                        || attempt >= EVENT_SOURCE_MAX_RETRIES
+                    {
+                        tracing::error!("Error starting event source: {}", e);
+                        health.set(id, ComponentStatus::Failed);
+                        return;
+                    }
+                    tracing::warn!(
+                        attempt = attempt + 1,
+                        "Error starting event source, retrying: {}",
+                        e
+                    );
+                    tokio::time::sleep(
+                        EVENT_SOURCE_RETRY_DELAY.saturating_mul(1 << attempt),
+                    )
+                    .await;
+                    attempt += 1;
+                };
+                health.set(&id, ComponentStatus::Running);
                 while let Some(event) = event_stream.next().await {
-                    match event_sender.send(event) {
+                    let _span = tracing::debug_span!("ingest_event").entered();
+                    stats.record_event(&id);
+                    let sender = match &priority {
+                        Some(is_priority) if is_priority(&event) => {
+                            &priority_sender
+                        }
+                        _ => &event_sender,
+                    };
+                    match sender.send(event) {
                         Ok(_) => {}
                         Err(e) => tracing::error!("Error sending event: {}", e),
                     }
                 }
-            });
+                health.set(id, ComponentStatus::Stopped);
+            };
+            tasks.spawn(task.instrument(span));
         }
 
         Ok(tasks)
@@ -148,12 +446,16 @@ mod tests {
         time::Duration,
     };
 
+    use alloy::primitives::U256;
     use async_trait::async_trait;
     use futures::stream;
     use tokio::time::sleep;
 
     use super::*;
-    use crate::types::{Action, Event, EventStream};
+    use crate::{
+        simulation::SimulationOutcome,
+        types::{Action, Event, EventStream},
+    };
 
     struct MockEventSource {
         events: Vec<Event>,
@@ -196,6 +498,192 @@ mod tests {
         }
     }
 
+    /// Takes a while to process every event.
+    struct SlowStrategy {
+        events: Arc<Mutex<Vec<Event>>>,
+    }
+
+    #[async_trait]
+    impl Strategy<Event, Action> for SlowStrategy {
+        async fn process_event(&mut self, event: Event) -> Vec<Action> {
+            self.events.lock().unwrap().push(event);
+            sleep(Duration::from_millis(20)).await;
+            vec![]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_priority_events_skip_backlog() {
+        let received_events = Arc::new(Mutex::new(vec![]));
+        let engine = Engine::<Event, Action>::new()
+            .add_event_source(Box::new(MockEventSource {
+                events: vec![
+                    Event::NewBlock,
+                    Event::NewBlock,
+                    Event::NewBlock,
+                    Event::Transaction,
+                ],
+            }))
+            .add_strategy(Box::new(SlowStrategy {
+                events: Arc::clone(&received_events),
+            }))
+            .with_priority_events(|event| *event == Event::Transaction);
+
+        let mut tasks = engine.run().await.unwrap();
+        sleep(Duration::from_millis(200)).await;
+        tasks.shutdown().await;
+
+        let received_events = received_events.lock().unwrap().clone();
+        assert_eq!(received_events.len(), 4);
+        // At most the block being processed when the hint arrived comes
+        // before it.
+        let position = received_events
+            .iter()
+            .position(|event| *event == Event::Transaction)
+            .unwrap();
+        assert!(position <= 1);
+    }
+
+    /// Never finishes processing an event, like a hung RPC request.
+    struct HungStrategy {
+        events: Arc<Mutex<Vec<Event>>>,
+    }
+
+    #[async_trait]
+    impl Strategy<Event, Action> for HungStrategy {
+        async fn process_event(&mut self, event: Event) -> Vec<Action> {
+            self.events.lock().unwrap().push(event);
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_timeout() {
+        let received_events = Arc::new(Mutex::new(vec![]));
+        let engine = Engine::<Event, Action>::new()
+            .add_event_source(Box::new(MockEventSource {
+                events: vec![Event::NewBlock, Event::Transaction],
+            }))
+            .add_strategy(Box::new(HungStrategy {
+                events: Arc::clone(&received_events),
+            }))
+            .with_call_timeout(Duration::from_millis(5));
+
+        let mut tasks = engine.run().await.unwrap();
+        sleep(Duration::from_millis(100)).await;
+        tasks.shutdown().await;
+
+        // The second event is processed after the first one timed out.
+        assert_eq!(
+            *received_events.lock().unwrap(),
+            vec![Event::NewBlock, Event::Transaction]
+        );
+    }
+
+    struct UnsyncedStrategy;
+
+    #[async_trait]
+    impl Strategy<Event, Action> for UnsyncedStrategy {
+        async fn sync_state(&mut self) -> Result<(), KazukaError> {
+            Err(KazukaError::ConfigError(
+                "no pools".to_string(),
+            ))
+        }
+
+        async fn process_event(&mut self, _event: Event) -> Vec<Action> {
+            vec![]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_state_error_names_strategy() {
+        let engine = Engine::<Event, Action>::new()
+            .add_strategy(Box::new(UnsyncedStrategy));
+        let health = engine.health().clone();
+
+        let e = engine.run().await.unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Syncing strategy UnsyncedStrategy: Configuration error: no pools"
+        );
+        assert_eq!(
+            health.status("strategy_0"),
+            Some(ComponentStatus::Failed)
+        );
+    }
+
+    /// Expects a profit of 1 wei from every action.
+    struct ExpectingStrategy;
+
+    #[async_trait]
+    impl Strategy<Event, Action> for ExpectingStrategy {
+        async fn process_event(&mut self, _event: Event) -> Vec<Action> {
+            vec![Action::SubmitTxToMempool]
+        }
+
+        async fn simulate(
+            &mut self,
+            event: Event,
+        ) -> Vec<SimulatedAction<Action>> {
+            self.process_event(event)
+                .await
+                .into_iter()
+                .map(|action| {
+                    SimulatedAction::new(action)
+                        .with_expected_profit(U256::from(1))
+                })
+                .collect()
+        }
+    }
+
+    struct MockSimulator {
+        actions: Arc<Mutex<Vec<Action>>>,
+    }
+
+    #[async_trait]
+    impl Simulator<Action> for MockSimulator {
+        async fn simulate(
+            &self,
+            action: &Action,
+        ) -> Result<SimulationOutcome, KazukaError> {
+            self.actions.lock().unwrap().push(action.clone());
+            Ok(SimulationOutcome {
+                success: true,
+                profit: U256::from(1),
+                refund: U256::ZERO,
+                gas_cost: U256::ZERO,
+                error: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let executed_actions = Arc::new(Mutex::new(vec![]));
+        let simulated_actions = Arc::new(Mutex::new(vec![]));
+        let engine = Engine::new()
+            .add_event_source(Box::new(MockEventSource {
+                events: vec![Event::NewBlock, Event::Transaction],
+            }))
+            .add_strategy(Box::new(ExpectingStrategy))
+            .add_executor(Box::new(MockExecutor {
+                actions: Arc::clone(&executed_actions),
+            }))
+            .with_dry_run(MockSimulator {
+                actions: Arc::clone(&simulated_actions),
+            });
+
+        let mut tasks = engine.run().await.unwrap();
+        sleep(Duration::from_millis(100)).await;
+        tasks.shutdown().await;
+
+        assert!(executed_actions.lock().unwrap().is_empty());
+        assert_eq!(
+            *simulated_actions.lock().unwrap(),
+            vec![Action::SubmitTxToMempool; 2]
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_engine_pipeline() {
         let incoming_events = vec![Event::NewBlock, Event::Transaction];
@@ -214,6 +702,8 @@ mod tests {
             }))
             .add_strategy(Box::new(strategy))
             .add_executor(Box::new(executor));
+        let health = engine.health().clone();
+        let stats = engine.stats().clone();
 
         let mut tasks = engine.run().await.expect("Engine failed to run");
 
@@ -230,5 +720,33 @@ mod tests {
             produced_actions[0],
             Action::SubmitTxToMempool
         );
+
+        assert_eq!(
+            health.status("strategy_0"),
+            Some(ComponentStatus::Running)
+        );
+        // The mock stream ends after its events.
+        assert_eq!(
+            health.status("event_source_0"),
+            Some(ComponentStatus::Stopped)
+        );
+
+        let stats = stats.snapshot();
+        assert_eq!(
+            stats.sources["event_source_0"].events,
+            2
+        );
+        assert_eq!(
+            stats.receivers["strategy_0"].received,
+            2
+        );
+        assert_eq!(
+            stats.receivers["executor_0"].received,
+            1
+        );
+        assert_eq!(
+            stats.executors["executor_0"].executed,
+            1
+        );
     }
 }
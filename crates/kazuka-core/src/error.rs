@@ -1,10 +1,348 @@
-use alloy::transports::{RpcError, TransportErrorKind};
+use std::time::Duration;
+
+use alloy::{
+    signers,
+    transports::{RpcError, TransportErrorKind},
+};
+use kazuka_mev_share::{sse::client::SseError, ws::WsError};
 use thiserror::Error;
+use tokio_tungstenite::tungstenite;
+
+/// How an error should be handled by whoever receives it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Temporary failure (e.g. network issue), worth retrying right away.
+    Transient,
+    /// Request was rate limited, worth retrying after backing off.
+    RateLimited,
+    /// Failure that retrying can't fix (e.g. misconfiguration).
+    Fatal,
+    /// Request was rejected as invalid (e.g. a reverting bundle or a nonce
+    /// that is too low), retrying the same request won't help.
+    UserError,
+}
+
+impl ErrorClass {
+    /// Whether the failed operation is worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Transient | Self::RateLimited
+        )
+    }
+}
+
+/// JSON-RPC error code used by nodes and relays for exceeded limits.
+/// See: https://eips.ethereum.org/EIPS/eip-1474#error-codes
+const LIMIT_EXCEEDED_CODE: i64 = -32005;
+
+/// Classifies a JSON-RPC error response.
+fn classify_rpc_code(code: i64, message: &str) -> ErrorClass {
+    if code == LIMIT_EXCEEDED_CODE
+        || code == 429
+        || message.to_lowercase().contains("rate limit")
+    {
+        ErrorClass::RateLimited
+    } else {
+        ErrorClass::UserError
+    }
+}
+
+/// Classifies an HTTP status code.
+fn classify_status(status: u16) -> ErrorClass {
+    match status {
+        429 => ErrorClass::RateLimited,
+        400..=499 => ErrorClass::UserError,
+        _ => ErrorClass::Transient,
+    }
+}
+
+/// Classifies an error of a JSON-RPC request to a node.
+fn classify_rpc_error(error: &RpcError<TransportErrorKind>) -> ErrorClass {
+    match error {
+        RpcError::ErrorResp(payload) => {
+            classify_rpc_code(payload.code, &payload.message)
+        }
+        RpcError::Transport(TransportErrorKind::HttpError(error)) => {
+            classify_status(error.status)
+        }
+        RpcError::Transport(_) | RpcError::NullResp => ErrorClass::Transient,
+        RpcError::LocalUsageError(_) => ErrorClass::UserError,
+        _ => ErrorClass::Fatal,
+    }
+}
 
 #[derive(Error, Debug)]
 pub enum KazukaError {
     #[error("RPC error")]
     RpcError(#[from] RpcError<TransportErrorKind>),
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+    #[error("JSON-RPC client error: {0}")]
+    RpcClientError(#[from] jsonrpsee::core::ClientError),
+    #[error("WebSocket error: {0}")]
+    WebSocketError(Box<tungstenite::Error>),
+    #[error("SSE error: {0}")]
+    SseError(#[from] SseError),
+    #[error("MEV-Share WebSocket error: {0}")]
+    MevShareWsError(#[from] WsError),
+    #[error("Signer error: {0}")]
+    SignerError(#[from] signers::Error),
+    #[error("Contract call error: {0}")]
+    ContractError(#[from] alloy::contract::Error),
+    #[error("Bundle rejected: {0}")]
+    BundleRejected(String),
+    /// Relay responded to a bundle submission with an error.
+    #[error("Bundle submission to {relay} failed{}: {message}", fmt_code(.code))]
+    BundleSubmissionError {
+        relay: String,
+        /// JSON-RPC error code returned by the relay, if any.
+        code: Option<i32>,
+        message: String,
+    },
+    #[error("Can't cancel bundle: {0}")]
+    BundleCancellationError(String),
+    /// Sending to or receiving from a channel failed, e.g. because the
+    /// other side has been dropped or the receiver has lagged behind.
+    #[error("Channel error: {0}")]
+    ChannelError(String),
+    #[error("Configuration error: {0}")]
+    ConfigError(String),
     #[error("CSV error in file {0}:\n\t{1}")]
     CsvError(String, String),
+    #[error("Invalid cron expression {0}:\n\t{1}")]
+    CronError(String, String),
+    /// Call didn't complete in time, e.g. because of a hung RPC request.
+    #[error("{operation} timed out after {timeout:?}")]
+    Timeout {
+        operation: String,
+        timeout: Duration,
+    },
+    /// Error with a description of what was being done when it occurred,
+    /// see [KazukaError::context].
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<KazukaError>,
+    },
+}
+
+fn fmt_code(code: &Option<i32>) -> String {
+    code.map(|code| format!(" (code {code})"))
+        .unwrap_or_default()
+}
+
+impl KazukaError {
+    /// Wraps the error with a description of what was being done when it
+    /// occurred.
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Self::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Classifies the error, so that callers can decide whether to retry.
+    pub fn classify(&self) -> ErrorClass {
+        match self {
+            Self::RpcError(error) => classify_rpc_error(error),
+            Self::HttpError(error) => match error.status() {
+                Some(status) => classify_status(status.as_u16()),
+                None if error.is_decode() || error.is_builder() => {
+                    ErrorClass::Fatal
+                }
+                None => ErrorClass::Transient,
+            },
+            Self::RpcClientError(error) => match error {
+                jsonrpsee::core::ClientError::Call(error) => {
+                    classify_rpc_code(error.code().into(), error.message())
+                }
+                jsonrpsee::core::ClientError::ParseError(_) => {
+                    ErrorClass::Fatal
+                }
+                _ => ErrorClass::Transient,
+            },
+            Self::WebSocketError(_) => ErrorClass::Transient,
+            Self::SseError(error) => match error {
+                SseError::SerdeJsonError(_)
+                | SseError::MaxRetriesExceeded(_)
+                | SseError::NoEndpoints => ErrorClass::Fatal,
+                SseError::Http(_)
+                | SseError::RetryError(_)
+                | SseError::IdleTimeout(_)
+                | SseError::History(_) => ErrorClass::Transient,
+            },
+            Self::MevShareWsError(error) => match error {
+                WsError::SerdeJsonError(_) | WsError::MaxRetriesExceeded(_) => {
+                    ErrorClass::Fatal
+                }
+                WsError::WebSocket(_) => ErrorClass::Transient,
+            },
+            Self::BundleSubmissionError { code, message, .. } => match code {
+                Some(code) => classify_rpc_code((*code).into(), message),
+                None => ErrorClass::Transient,
+            },
+            Self::BundleRejected(_) | Self::BundleCancellationError(_) => {
+                ErrorClass::UserError
+            }
+            Self::ChannelError(_) | Self::Timeout { .. } => {
+                ErrorClass::Transient
+            }
+            Self::ContractError(error) => match error {
+                alloy::contract::Error::TransportError(error) => {
+                    classify_rpc_error(error)
+                }
+                _ => ErrorClass::Fatal,
+            },
+            Self::SignerError(_)
+            | Self::ConfigError(_)
+            | Self::CsvError(_, _)
+            | Self::CronError(_, _) => ErrorClass::Fatal,
+            Self::Context { source, .. } => source.classify(),
+        }
+    }
+
+    /// Returns the underlying error, skipping any [context](Self::context).
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    /// Turns a JSON-RPC client error returned by a relay into a
+    /// [BundleSubmissionError](Self::BundleSubmissionError), keeping the
+    /// error code the relay has responded with.
+    pub fn bundle_submission(
+        relay: impl Into<String>,
+        error: jsonrpsee::core::ClientError,
+    ) -> Self {
+        let (code, message) = match error {
+            jsonrpsee::core::ClientError::Call(error) => (
+                Some(error.code()),
+                error.message().to_string(),
+            ),
+            error => (None, error.to_string()),
+        };
+        Self::BundleSubmissionError {
+            relay: relay.into(),
+            code,
+            message,
+        }
+    }
+}
+
+impl From<tungstenite::Error> for KazukaError {
+    fn from(error: tungstenite::Error) -> Self {
+        Self::WebSocketError(Box::new(error))
+    }
+}
+
+/// Adds [context](KazukaError::context) to errors of results.
+pub trait ResultExt<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, KazukaError>;
+
+    /// Like [context](ResultExt::context), but only builds the context
+    /// on error.
+    fn with_context<C: Into<String>>(
+        self,
+        f: impl FnOnce() -> C,
+    ) -> Result<T, KazukaError>;
+}
+
+impl<T, E: Into<KazukaError>> ResultExt<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T, KazukaError> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context<C: Into<String>>(
+        self,
+        f: impl FnOnce() -> C,
+    ) -> Result<T, KazukaError> {
+        self.map_err(|e| e.into().context(f()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee::types::ErrorObject;
+
+    use super::*;
+
+    #[test]
+    fn test_context() {
+        let result: Result<(), _> = Err(KazukaError::ConfigError(
+            "missing relay URL".to_string(),
+        ));
+        let error = result.context("Starting executor").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Starting executor: Configuration error: missing relay URL"
+        );
+        assert!(matches!(
+            error.root(),
+            KazukaError::ConfigError(_)
+        ));
+    }
+
+    #[test]
+    fn test_classify() {
+        let rate_limited = KazukaError::BundleSubmissionError {
+            relay: "flashbots".to_string(),
+            code: Some(-32005),
+            message: "limit exceeded".to_string(),
+        };
+        assert_eq!(
+            rate_limited.classify(),
+            ErrorClass::RateLimited
+        );
+        assert!(rate_limited.classify().is_retryable());
+
+        let rejected = KazukaError::BundleSubmissionError {
+            relay: "flashbots".to_string(),
+            code: Some(-32000),
+            message: "bundle reverts".to_string(),
+        };
+        assert_eq!(
+            rejected.classify(),
+            ErrorClass::UserError
+        );
+
+        let fatal = KazukaError::ConfigError("missing signer".to_string())
+            .context("Starting executor");
+        assert_eq!(fatal.classify(), ErrorClass::Fatal);
+        assert!(!fatal.classify().is_retryable());
+
+        let timeout = KazukaError::Timeout {
+            operation: "Executing action".to_string(),
+            timeout: Duration::from_secs(5),
+        };
+        assert_eq!(
+            timeout.to_string(),
+            "Executing action timed out after 5s"
+        );
+        assert!(timeout.classify().is_retryable());
+    }
+
+    #[test]
+    fn test_bundle_submission_error_keeps_code() {
+        let error = jsonrpsee::core::ClientError::Call(ErrorObject::owned(
+            -32000,
+            "bundle already known",
+            None::<()>,
+        ));
+        let error = KazukaError::bundle_submission("flashbots", error);
+        assert!(matches!(
+            error,
+            KazukaError::BundleSubmissionError {
+                code: Some(-32000),
+                ..
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Bundle submission to flashbots failed (code -32000): bundle already known"
+        );
+    }
 }
@@ -0,0 +1,836 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use alloy::{
+    network::AnyNetwork,
+    primitives::{Address, B256, BlockNumber, TxHash, U64, U256, keccak256},
+    providers::{DynProvider, Provider},
+    rpc::types::mev::{BundleItem, BundleStats, EthSendBundle, MevSendBundle},
+};
+use async_trait::async_trait;
+use futures::{StreamExt, stream};
+use kazuka_mev_share::{
+    backend::store::{BundleStore, InclusionStatus, StoreError},
+    rpc::FlashbotsApiClient,
+};
+use tokio::time::{self, MissedTickBehavior};
+
+use crate::{
+    error::KazukaError,
+    pnl::{BundleOrigin, PnlEvent, PnlEventKind, PnlLedger},
+    types::{EventSource, EventStream},
+};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Bundle submitted to a relay, whose outcome should be tracked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackedBundle {
+    /// Bundle hash returned by the relay.
+    pub bundle_hash: B256,
+    /// First block the bundle targets.
+    pub target_block: BlockNumber,
+    /// Last block the bundle is valid for.
+    pub max_block: BlockNumber,
+    /// Hashes of our own transactions in the bundle, used to detect
+    /// inclusion.
+    pub tx_hashes: Vec<TxHash>,
+    /// Strategy which submitted the bundle, if known.
+    pub origin: Option<BundleOrigin>,
+}
+
+impl TrackedBundle {
+    /// Creates a [TrackedBundle] from a submitted `mev_sendBundle` request.
+    pub fn from_mev_bundle(bundle_hash: B256, bundle: &MevSendBundle) -> Self {
+        let tx_hashes = bundle
+            .bundle_body
+            .iter()
+            .filter_map(|item| match item {
+                BundleItem::Tx { tx, .. } => Some(keccak256(tx)),
+                _ => None,
+            })
+            .collect();
+        Self {
+            bundle_hash,
+            target_block: bundle.inclusion.block,
+            max_block: bundle
+                .inclusion
+                .max_block
+                .unwrap_or(bundle.inclusion.block),
+            tx_hashes,
+            origin: None,
+        }
+    }
+
+    /// Creates a [TrackedBundle] from a submitted `eth_sendBundle` request.
+    pub fn from_eth_bundle(bundle_hash: B256, bundle: &EthSendBundle) -> Self {
+        Self {
+            bundle_hash,
+            target_block: bundle.block_number,
+            max_block: bundle.block_number,
+            tx_hashes: bundle.txs.iter().map(keccak256).collect(),
+            origin: None,
+        }
+    }
+}
+
+/// Outcome of a [tracked bundle](TrackedBundle).
+#[derive(Clone, Debug)]
+pub enum BundleStatus {
+    /// The relay has simulated the bundle.
+    Simulated {
+        bundle_hash: B256,
+        stats: BundleStats,
+    },
+    /// One of the bundle transactions landed on-chain.
+    Included {
+        bundle_hash: B256,
+        /// Hashes of our own transactions in the bundle.
+        tx_hashes: Vec<TxHash>,
+        block_number: BlockNumber,
+    },
+    /// The bundle was not included by its last valid block.
+    Expired {
+        bundle_hash: B256,
+        /// Hashes of our own transactions in the bundle.
+        tx_hashes: Vec<TxHash>,
+        max_block: BlockNumber,
+        stats: Option<BundleStats>,
+    },
+}
+
+#[derive(Debug)]
+struct TrackedBundleState {
+    bundle: TrackedBundle,
+    stats: Option<BundleStats>,
+}
+
+/// Shared handle, through which executors feed submitted bundles into the
+/// [BundleStatsEventSource].
+#[derive(Clone, Debug, Default)]
+pub struct BundleTracker {
+    bundles: Arc<Mutex<HashMap<B256, TrackedBundleState>>>,
+}
+
+impl BundleTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking the outcome of a submitted bundle.
+    pub fn track(&self, bundle: TrackedBundle) {
+        self.bundles.lock().unwrap().insert(
+            bundle.bundle_hash,
+            TrackedBundleState {
+                bundle,
+                stats: None,
+            },
+        );
+    }
+
+    /// Stops tracking the given bundle.
+    pub fn untrack(&self, bundle_hash: &B256) {
+        self.bundles.lock().unwrap().remove(bundle_hash);
+    }
+
+    /// Number of bundles being tracked.
+    pub fn len(&self) -> usize {
+        self.bundles.lock().unwrap().len()
+    }
+
+    /// Whether no bundles are being tracked.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn snapshot(&self) -> Vec<TrackedBundle> {
+        self.bundles
+            .lock()
+            .unwrap()
+            .values()
+            .map(|state| state.bundle.clone())
+            .collect()
+    }
+
+    /// Records new stats, returns `true` if the bundle got simulated for the
+    /// first time.
+    fn update_stats(&self, bundle_hash: &B256, stats: BundleStats) -> bool {
+        let mut bundles = self.bundles.lock().unwrap();
+        let Some(state) = bundles.get_mut(bundle_hash) else {
+            return false;
+        };
+        let was_simulated = matches!(
+            state.stats,
+            Some(BundleStats::Simulated(_))
+        );
+        let is_simulated = matches!(stats, BundleStats::Simulated(_));
+        state.stats = Some(stats);
+        is_simulated && !was_simulated
+    }
+
+    fn remove(&self, bundle_hash: &B256) -> Option<TrackedBundleState> {
+        self.bundles.lock().unwrap().remove(bundle_hash)
+    }
+}
+
+/// Returns the block our transactions landed in, if any.
+pub(crate) async fn inclusion_block(
+    provider: &DynProvider<AnyNetwork>,
+    bundle: &TrackedBundle,
+) -> Result<Option<BlockNumber>, KazukaError> {
+    landed_block(provider, &bundle.tx_hashes).await
+}
+
+/// Returns the block the first of the transactions landed in, if any.
+async fn landed_block(
+    provider: &DynProvider<AnyNetwork>,
+    tx_hashes: &[TxHash],
+) -> Result<Option<BlockNumber>, KazukaError> {
+    for tx_hash in tx_hashes {
+        let receipt = provider.get_transaction_receipt(*tx_hash).await?;
+        if let Some(block_number) =
+            receipt.and_then(|receipt| receipt.block_number)
+        {
+            return Ok(Some(block_number));
+        }
+    }
+    Ok(None)
+}
+
+/// Gas paid (in wei) by those of the transactions that landed on-chain.
+async fn gas_paid(
+    provider: &DynProvider<AnyNetwork>,
+    tx_hashes: &[TxHash],
+) -> Result<U256, KazukaError> {
+    let mut gas_paid = U256::ZERO;
+    for tx_hash in tx_hashes {
+        if let Some(receipt) =
+            provider.get_transaction_receipt(*tx_hash).await?
+        {
+            gas_paid += U256::from(receipt.gas_used)
+                * U256::from(receipt.effective_gas_price);
+        }
+    }
+    Ok(gas_paid)
+}
+
+/// Fee refunds paid to a recipient, which are split equally among the
+/// bundles included since the previous refund.
+#[derive(Debug)]
+struct FeeRefunds {
+    recipient: Address,
+    /// Total refunds received at the last poll, `None` before the first one.
+    received: Option<U256>,
+    /// Bundles included since the previous refund, along with their
+    /// strategies, in the order of inclusion.
+    inclusions: Vec<(B256, Option<String>)>,
+    /// Refunds received while no bundle had been included, which are split
+    /// on the next refund.
+    unattributed: U256,
+}
+
+/// Share of a fee refund of an included bundle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct RefundShare {
+    bundle_hash: B256,
+    strategy: Option<String>,
+    refund: U256,
+}
+
+impl FeeRefunds {
+    fn new(recipient: Address) -> Self {
+        Self {
+            recipient,
+            received: None,
+            inclusions: vec![],
+            unattributed: U256::ZERO,
+        }
+    }
+
+    fn record_inclusion(
+        &mut self,
+        bundle_hash: B256,
+        strategy: Option<String>,
+    ) {
+        self.inclusions.push((bundle_hash, strategy));
+    }
+
+    /// Shares of the included bundles of the refunds received since the
+    /// previous poll, given the total received so far. The first poll only
+    /// sets the baseline, since earlier refunds are already accounted for.
+    fn split(&mut self, received: U256) -> Vec<RefundShare> {
+        let Some(previous) = self.received.replace(received) else {
+            return vec![];
+        };
+        self.unattributed += received.saturating_sub(previous);
+        if self.unattributed.is_zero() || self.inclusions.is_empty() {
+            return vec![];
+        }
+        let refund = std::mem::take(&mut self.unattributed);
+        let count = U256::from(self.inclusions.len());
+        let share = refund / count;
+        let mut shares: Vec<_> = self
+            .inclusions
+            .drain(..)
+            .map(|(bundle_hash, strategy)| RefundShare {
+                bundle_hash,
+                strategy,
+                refund: share,
+            })
+            .collect();
+        // The rounding remainder goes to the first bundle.
+        shares[0].refund += refund - share * count;
+        shares
+    }
+}
+
+/// Periodically polls `flashbots_getBundleStatsV2` and the chain for bundles
+/// fed through a [BundleTracker], and generates a stream of
+/// [events](BundleStatus) describing their outcome.
+pub struct BundleStatsEventSource {
+    client: Arc<dyn FlashbotsApiClient + Send + Sync>,
+    provider: Arc<DynProvider<AnyNetwork>>,
+    tracker: BundleTracker,
+    poll_interval: Duration,
+    /// Records included bundles and the gas they paid.
+    pnl_ledger: Option<PnlLedger>,
+    /// Fee refunds booked in the ledger and the bundle store.
+    fee_refunds: Option<Mutex<FeeRefunds>>,
+    /// Records the outcomes of tracked bundles.
+    bundle_store: Option<Arc<dyn BundleStore>>,
+}
+
+impl BundleStatsEventSource {
+    pub fn new(
+        client: Arc<dyn FlashbotsApiClient + Send + Sync>,
+        provider: Arc<DynProvider<AnyNetwork>>,
+        tracker: BundleTracker,
+    ) -> Self {
+        Self {
+            client,
+            provider,
+            tracker,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            pnl_ledger: None,
+            fee_refunds: None,
+            bundle_store: None,
+        }
+    }
+
+    /// Sets how often tracked bundles are polled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `poll_interval` is zero.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        assert!(
+            !poll_interval.is_zero(),
+            "Poll interval must be non-zero"
+        );
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Records the inclusions of tracked bundles with an
+    /// [origin](TrackedBundle::origin) in the ledger, earning their expected
+    /// profit, along with the gas paid by their transactions, as events of
+    /// their strategy.
+    pub fn with_pnl_ledger(mut self, pnl_ledger: PnlLedger) -> Self {
+        self.pnl_ledger = Some(pnl_ledger);
+        self
+    }
+
+    /// Also books the fee refunds paid to the recipient, polled with
+    /// `flashbots_getFeeRefundTotalsByRecipient`. Refunds are split equally
+    /// among the bundles included since the previous refund, and recorded
+    /// in the ledger under their strategies, and in the bundle store.
+    pub fn with_fee_refunds(mut self, recipient: Address) -> Self {
+        self.fee_refunds = Some(Mutex::new(FeeRefunds::new(recipient)));
+        self
+    }
+
+    /// Records the outcomes of tracked bundles in the store: included,
+    /// outbid if a transaction they backrun landed without them, or expired.
+    /// Bundles missing from the store are skipped.
+    pub fn with_bundle_store(mut self, store: Arc<dyn BundleStore>) -> Self {
+        self.bundle_store = Some(store);
+        self
+    }
+
+    /// Sets the inclusion status of the bundle in the store, if any. Store
+    /// errors are only logged.
+    async fn store_status(&self, status: &BundleStatus) {
+        let Some(bundle_store) = &self.bundle_store else {
+            return;
+        };
+        let (bundle_hash, inclusion_status) = match status {
+            BundleStatus::Simulated { .. } => return,
+            BundleStatus::Included {
+                bundle_hash,
+                block_number,
+                ..
+            } => (
+                *bundle_hash,
+                InclusionStatus::Included {
+                    block_number: *block_number,
+                },
+            ),
+            BundleStatus::Expired { bundle_hash, .. } => (
+                *bundle_hash,
+                self.expired_status(bundle_store.as_ref(), *bundle_hash)
+                    .await,
+            ),
+        };
+        match bundle_store.set_status(bundle_hash, inclusion_status).await {
+            Ok(()) | Err(StoreError::UnknownBundle(_)) => {}
+            Err(e) => tracing::warn!(
+                ?bundle_hash,
+                "Error recording bundle status: {}",
+                e
+            ),
+        }
+    }
+
+    /// Status of an expired bundle: outbid if one of the transactions it
+    /// backruns landed, expired otherwise.
+    async fn expired_status(
+        &self,
+        bundle_store: &dyn BundleStore,
+        bundle_hash: B256,
+    ) -> InclusionStatus {
+        let backrun_of: Vec<TxHash> = match bundle_store.get(bundle_hash).await
+        {
+            Ok(record) => record
+                .into_iter()
+                .flat_map(|record| record.bundle.bundle_body)
+                .filter_map(|item| match item {
+                    BundleItem::Hash { hash } => Some(hash),
+                    _ => None,
+                })
+                .collect(),
+            Err(e) => {
+                tracing::warn!(
+                    ?bundle_hash,
+                    "Error getting bundle: {}",
+                    e
+                );
+                vec![]
+            }
+        };
+        match landed_block(&self.provider, &backrun_of).await {
+            Ok(Some(block_number)) => InclusionStatus::Outbid { block_number },
+            Ok(None) => InclusionStatus::Expired,
+            Err(e) => {
+                tracing::warn!(
+                    ?bundle_hash,
+                    "Error checking backrun transactions: {}",
+                    e
+                );
+                InclusionStatus::Expired
+            }
+        }
+    }
+
+    /// Books the fee refunds received since the previous poll.
+    async fn poll_fee_refunds(&self) -> Result<(), KazukaError> {
+        let Some(fee_refunds) = &self.fee_refunds else {
+            return Ok(());
+        };
+        let recipient = fee_refunds.lock().unwrap().recipient;
+        let totals = self
+            .client
+            .get_fee_refund_totals_by_recipient(recipient)
+            .await?;
+        let shares = fee_refunds.lock().unwrap().split(totals.received);
+        for share in shares {
+            if let Some(bundle_store) = &self.bundle_store {
+                match bundle_store
+                    .record_refund(share.bundle_hash, share.refund)
+                    .await
+                {
+                    Ok(()) | Err(StoreError::UnknownBundle(_)) => {}
+                    Err(e) => tracing::warn!(
+                        bundle_hash = ?share.bundle_hash,
+                        "Error recording fee refund: {}",
+                        e
+                    ),
+                }
+            }
+            if let (Some(pnl_ledger), Some(strategy)) =
+                (&self.pnl_ledger, share.strategy)
+            {
+                pnl_ledger.record(PnlEvent::now(
+                    strategy,
+                    PnlEventKind::RefundReceived(share.refund),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Polls all tracked bundles once.
+    async fn poll(&self) -> Result<Vec<BundleStatus>, KazukaError> {
+        let block_number = self.provider.get_block_number().await?;
+        let mut statuses = vec![];
+
+        for bundle in self.tracker.snapshot() {
+            let inclusion_block =
+                match inclusion_block(&self.provider, &bundle).await {
+                    Ok(inclusion_block) => inclusion_block,
+                    Err(e) => {
+                        tracing::warn!(
+                            bundle_hash = ?bundle.bundle_hash,
+                            "Error checking bundle inclusion: {}",
+                            e
+                        );
+                        continue;
+                    }
+                };
+            if let Some(block_number) = inclusion_block {
+                self.tracker.remove(&bundle.bundle_hash);
+                if let Some(fee_refunds) = &self.fee_refunds {
+                    fee_refunds.lock().unwrap().record_inclusion(
+                        bundle.bundle_hash,
+                        bundle
+                            .origin
+                            .as_ref()
+                            .map(|origin| origin.strategy.clone()),
+                    );
+                }
+                if let (Some(pnl_ledger), Some(origin)) =
+                    (&self.pnl_ledger, &bundle.origin)
+                {
+                    origin.record(
+                        pnl_ledger,
+                        PnlEventKind::Included {
+                            revenue: origin.expected_profit,
+                        },
+                    );
+                    match gas_paid(&self.provider, &bundle.tx_hashes).await {
+                        Ok(gas_paid) => origin.record(
+                            pnl_ledger,
+                            PnlEventKind::GasPaid(gas_paid),
+                        ),
+                        Err(e) => tracing::warn!(
+                            bundle_hash = ?bundle.bundle_hash,
+                            "Error getting gas paid: {}",
+                            e
+                        ),
+                    }
+                }
+                statuses.push(BundleStatus::Included {
+                    bundle_hash: bundle.bundle_hash,
+                    tx_hashes: bundle.tx_hashes,
+                    block_number,
+                });
+                continue;
+            }
+
+            if block_number > bundle.max_block {
+                let stats = self
+                    .tracker
+                    .remove(&bundle.bundle_hash)
+                    .and_then(|state| state.stats);
+                statuses.push(BundleStatus::Expired {
+                    bundle_hash: bundle.bundle_hash,
+                    tx_hashes: bundle.tx_hashes,
+                    max_block: bundle.max_block,
+                    stats,
+                });
+                continue;
+            }
+
+            match self
+                .client
+                .get_bundle_stats(
+                    bundle.bundle_hash,
+                    U64::from(bundle.target_block),
+                )
+                .await
+            {
+                Ok(stats) => {
+                    if self
+                        .tracker
+                        .update_stats(&bundle.bundle_hash, stats.clone())
+                    {
+                        statuses.push(BundleStatus::Simulated {
+                            bundle_hash: bundle.bundle_hash,
+                            stats,
+                        });
+                    }
+                }
+                Err(e) => tracing::warn!(
+                    bundle_hash = ?bundle.bundle_hash,
+                    "Error getting bundle stats: {}",
+                    e
+                ),
+            }
+        }
+
+        for status in &statuses {
+            self.store_status(status).await;
+        }
+        Ok(statuses)
+    }
+}
+
+#[async_trait]
+impl EventSource<BundleStatus> for BundleStatsEventSource {
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, BundleStatus>, KazukaError> {
+        let mut interval = time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let stream = stream::unfold(
+            interval,
+            move |mut interval| async move {
+                interval.tick().await;
+                let statuses = if self.tracker.is_empty() {
+                    vec![]
+                } else {
+                    self.poll().await.unwrap_or_else(|e| {
+                        tracing::error!("Error polling bundle stats: {}", e);
+                        vec![]
+                    })
+                };
+                // Refunds are paid after the bundles stopped being tracked.
+                if let Err(e) = self.poll_fee_refunds().await {
+                    tracing::warn!("Error polling fee refunds: {}", e);
+                }
+                Some((statuses, interval))
+            },
+        )
+        .flat_map(stream::iter);
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{Bytes, b256},
+        providers::ProviderBuilder,
+        rpc::types::mev::Inclusion,
+        transports::mock::Asserter,
+    };
+    use jsonrpsee::http_client::HttpClientBuilder;
+    use kazuka_mev_share::backend::store::{BundleRecord, InMemoryBundleStore};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_tracked_bundle_from_mev_bundle() {
+        let bundle_hash = b256!(
+            "0xbeefbeefbeef0000000000000000000000000000000000000000000000000000"
+        );
+        let tx = Bytes::from_static(b"tx");
+        let bundle = MevSendBundle {
+            protocol_version: Default::default(),
+            inclusion: Inclusion {
+                block: 100,
+                max_block: Some(130),
+            },
+            bundle_body: vec![
+                BundleItem::Hash { hash: B256::ZERO },
+                BundleItem::Tx {
+                    tx: tx.clone(),
+                    can_revert: false,
+                },
+            ],
+            validity: None,
+            privacy: None,
+        };
+
+        let tracked = TrackedBundle::from_mev_bundle(bundle_hash, &bundle);
+
+        assert_eq!(
+            tracked,
+            TrackedBundle {
+                bundle_hash,
+                target_block: 100,
+                max_block: 130,
+                tx_hashes: vec![keccak256(&tx)],
+                origin: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_bundle_tracker() {
+        let tracker = BundleTracker::new();
+        let bundle_hash = B256::ZERO;
+        tracker.track(TrackedBundle {
+            bundle_hash,
+            target_block: 1,
+            max_block: 1,
+            tx_hashes: vec![],
+            origin: None,
+        });
+        assert_eq!(tracker.len(), 1);
+
+        tracker.untrack(&bundle_hash);
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn test_fee_refunds_split_by_inclusions() {
+        let mut refunds = FeeRefunds::new(Address::ZERO);
+
+        // Refunds received before the first poll are already accounted for.
+        assert!(refunds.split(U256::from(1000)).is_empty());
+        // Refunds without inclusions are carried over.
+        assert!(refunds.split(U256::from(1010)).is_empty());
+
+        let bundle_hashes = [1, 2, 3].map(B256::repeat_byte);
+        refunds.record_inclusion(
+            bundle_hashes[0],
+            Some("arb".to_string()),
+        );
+        refunds.record_inclusion(
+            bundle_hashes[1],
+            Some("arb".to_string()),
+        );
+        refunds.record_inclusion(bundle_hashes[2], None);
+        let share = |bundle_hash, strategy: Option<&str>, refund| RefundShare {
+            bundle_hash,
+            strategy: strategy.map(str::to_string),
+            refund: U256::from(refund),
+        };
+        assert_eq!(
+            refunds.split(U256::from(1110)),
+            [
+                share(bundle_hashes[0], Some("arb"), 38),
+                share(bundle_hashes[1], Some("arb"), 36),
+                share(bundle_hashes[2], None, 36),
+            ]
+        );
+        assert!(refunds.inclusions.is_empty());
+        assert!(refunds.split(U256::from(1110)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_poll_skips_failed_inclusion_check() {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .connect_mocked_client(asserter.clone())
+            .erased();
+        // Stats are not requested once the last block has passed.
+        let client = HttpClientBuilder::default()
+            .build("http://127.0.0.1:1")
+            .unwrap();
+        let tracker = BundleTracker::new();
+        let event_source = BundleStatsEventSource::new(
+            Arc::new(client),
+            Arc::new(provider),
+            tracker.clone(),
+        );
+        let bundle_hash = B256::ZERO;
+        tracker.track(TrackedBundle {
+            bundle_hash,
+            target_block: 100,
+            max_block: 100,
+            tx_hashes: vec![B256::repeat_byte(1)],
+            origin: None,
+        });
+
+        asserter.push_success(&U64::from(101));
+        asserter.push_failure_msg("receipt unavailable");
+        let statuses = event_source.poll().await.unwrap();
+        assert!(statuses.is_empty());
+        assert_eq!(tracker.len(), 1);
+
+        asserter.push_success(&U64::from(101));
+        asserter.push_success(&serde_json::Value::Null);
+        let statuses = event_source.poll().await.unwrap();
+        assert!(matches!(
+            statuses.as_slice(),
+            [BundleStatus::Expired { bundle_hash: expired, .. }]
+                if *expired == bundle_hash
+        ));
+        assert!(tracker.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_backrun_is_outbid() {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .connect_mocked_client(asserter.clone())
+            .erased();
+        let client = HttpClientBuilder::default()
+            .build("http://127.0.0.1:1")
+            .unwrap();
+        let tracker = BundleTracker::new();
+        let bundle_store = Arc::new(InMemoryBundleStore::new());
+        let event_source = BundleStatsEventSource::new(
+            Arc::new(client),
+            Arc::new(provider),
+            tracker.clone(),
+        )
+        .with_bundle_store(bundle_store.clone());
+        let target_tx_hash = B256::repeat_byte(2);
+        let bundle = MevSendBundle {
+            protocol_version: Default::default(),
+            inclusion: Inclusion {
+                block: 100,
+                max_block: None,
+            },
+            bundle_body: vec![
+                BundleItem::Hash {
+                    hash: target_tx_hash,
+                },
+                BundleItem::Tx {
+                    tx: Bytes::from_static(b"tx"),
+                    can_revert: false,
+                },
+            ],
+            validity: None,
+            privacy: None,
+        };
+        let bundle_hash = B256::repeat_byte(1);
+        bundle_store
+            .insert(BundleRecord::new(
+                bundle_hash,
+                bundle.clone(),
+            ))
+            .await
+            .unwrap();
+        tracker.track(TrackedBundle::from_mev_bundle(
+            bundle_hash,
+            &bundle,
+        ));
+
+        asserter.push_success(&U64::from(101));
+        // Our transaction did not land, the one we backrun did.
+        asserter.push_success(&serde_json::Value::Null);
+        asserter.push_success(&json!({
+            "transactionHash": target_tx_hash,
+            "transactionIndex": "0x0",
+            "blockHash": B256::repeat_byte(3),
+            "blockNumber": "0x64",
+            "from": Address::ZERO,
+            "to": Address::ZERO,
+            "contractAddress": null,
+            "gasUsed": "0x5208",
+            "cumulativeGasUsed": "0x5208",
+            "effectiveGasPrice": "0x1",
+            "status": "0x1",
+            "type": "0x2",
+            "logs": [],
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+        }));
+        event_source.poll().await.unwrap();
+
+        assert_eq!(
+            bundle_store.get(bundle_hash).await.unwrap().unwrap().status,
+            InclusionStatus::Outbid { block_number: 100 }
+        );
+    }
+}
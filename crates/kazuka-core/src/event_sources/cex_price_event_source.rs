@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt, stream};
+use serde::Deserialize;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message,
+};
+
+use crate::{
+    error::KazukaError,
+    types::{EventSource, EventStream},
+};
+
+/// Delay before reconnecting after the exchange closes the connection.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Normalized top-of-book price update from a centralized exchange.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CexPriceUpdate {
+    /// Name of the exchange, e.g. `"binance"`.
+    pub exchange: &'static str,
+    /// Exchange-specific symbol, e.g. `"ETHUSDT"`.
+    pub symbol: String,
+    /// Best bid price.
+    pub bid: f64,
+    /// Quantity available at the best bid.
+    pub bid_size: f64,
+    /// Best ask price.
+    pub ask: f64,
+    /// Quantity available at the best ask.
+    pub ask_size: f64,
+    /// Time at which the update was received.
+    pub received_at: DateTime<Utc>,
+}
+
+impl CexPriceUpdate {
+    /// Returns the mid price between the best bid and the best ask.
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+}
+
+/// Describes how to subscribe to an exchange WebSocket ticker and how to
+/// normalize its messages.
+pub trait CexFeed: Send + Sync {
+    /// WebSocket endpoint to connect to.
+    fn url(&self) -> String;
+
+    /// Message to send right after connecting, if the exchange requires an
+    /// explicit subscription.
+    fn subscribe_message(&self) -> Option<String> {
+        None
+    }
+
+    /// Parses a text message into a price update.
+    /// Returns `None` for messages that are not price updates.
+    fn parse(&self, message: &str) -> Option<CexPriceUpdate>;
+}
+
+/// Binance `bookTicker` stream, which pushes best bid/ask updates in
+/// real-time.
+/// See: https://developers.binance.com/docs/binance-spot-api-docs/web-socket-streams#individual-symbol-book-ticker-streams
+pub struct BinanceBookTicker {
+    symbols: Vec<String>,
+}
+
+impl BinanceBookTicker {
+    /// Creates a feed for the given symbols, e.g. `["ETHUSDT", "ETHBTC"]`.
+    pub fn new(symbols: Vec<String>) -> Self {
+        Self { symbols }
+    }
+}
+
+#[derive(Deserialize)]
+struct BinanceCombinedMessage {
+    data: BinanceBookTickerMessage,
+}
+
+#[derive(Deserialize)]
+struct BinanceBookTickerMessage {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "b", with = "alloy::serde::displayfromstr")]
+    bid: f64,
+    #[serde(rename = "B", with = "alloy::serde::displayfromstr")]
+    bid_size: f64,
+    #[serde(rename = "a", with = "alloy::serde::displayfromstr")]
+    ask: f64,
+    #[serde(rename = "A", with = "alloy::serde::displayfromstr")]
+    ask_size: f64,
+}
+
+impl CexFeed for BinanceBookTicker {
+    fn url(&self) -> String {
+        let streams = self
+            .symbols
+            .iter()
+            .map(|symbol| format!("{}@bookTicker", symbol.to_lowercase()))
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("wss://stream.binance.com:9443/stream?streams={streams}")
+    }
+
+    fn parse(&self, message: &str) -> Option<CexPriceUpdate> {
+        let message: BinanceCombinedMessage =
+            serde_json::from_str(message).ok()?;
+        let ticker = message.data;
+        Some(CexPriceUpdate {
+            exchange: "binance",
+            symbol: ticker.symbol,
+            bid: ticker.bid,
+            bid_size: ticker.bid_size,
+            ask: ticker.ask,
+            ask_size: ticker.ask_size,
+            received_at: Utc::now(),
+        })
+    }
+}
+
+/// Connects to an exchange WebSocket ticker and generates a stream of
+/// normalized [price updates](CexPriceUpdate).
+///
+/// Reconnects automatically when the exchange drops the connection.
+pub struct CexPriceEventSource<F> {
+    feed: F,
+}
+
+impl<F: CexFeed> CexPriceEventSource<F> {
+    pub fn new(feed: F) -> Self {
+        Self { feed }
+    }
+
+    async fn connect(&self) -> Result<WsStream, KazukaError> {
+        let (mut socket, _) = connect_async(self.feed.url()).await?;
+        if let Some(message) = self.feed.subscribe_message() {
+            socket.send(Message::text(message)).await?;
+        }
+        Ok(socket)
+    }
+}
+
+#[async_trait]
+impl<F: CexFeed> EventSource<CexPriceUpdate> for CexPriceEventSource<F> {
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, CexPriceUpdate>, KazukaError> {
+        let socket = self.connect().await?;
+
+        let stream = stream::unfold(
+            Some(socket),
+            move |mut socket| async move {
+                loop {
+                    let ws = match socket.as_mut() {
+                        Some(ws) => ws,
+                        None => match self.connect().await {
+                            Ok(ws) => socket.insert(ws),
+                            Err(e) => {
+                                tracing::error!(
+                                    "Error reconnecting to exchange: {}",
+                                    e
+                                );
+                                tokio::time::sleep(RECONNECT_DELAY).await;
+                                continue;
+                            }
+                        },
+                    };
+                    match ws.next().await {
+                        Some(Ok(Message::Text(text))) => {
+                            if let Some(update) = self.feed.parse(text.as_str())
+                            {
+                                return Some((update, socket));
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | None => {
+                            tracing::warn!(
+                                "Exchange closed the connection, reconnecting"
+                            );
+                            socket = None;
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            tracing::error!(
+                                "Error receiving exchange message: {}",
+                                e
+                            );
+                            socket = None;
+                        }
+                    }
+                }
+            },
+        );
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binance_book_ticker_url() {
+        let feed = BinanceBookTicker::new(vec![
+            "ETHUSDT".to_string(),
+            "ETHBTC".to_string(),
+        ]);
+        assert_eq!(
+            feed.url(),
+            "wss://stream.binance.com:9443/stream?streams=ethusdt@bookTicker/ethbtc@bookTicker"
+        );
+    }
+
+    #[test]
+    fn test_binance_book_ticker_parse() {
+        let feed = BinanceBookTicker::new(vec!["ETHUSDT".to_string()]);
+        let message = r#"{
+            "stream": "ethusdt@bookTicker",
+            "data": {
+                "u": 400900217,
+                "s": "ETHUSDT",
+                "b": "2500.10000000",
+                "B": "31.21000000",
+                "a": "2500.20000000",
+                "A": "40.66000000"
+            }
+        }"#;
+
+        let update = feed.parse(message).unwrap();
+
+        assert_eq!(update.exchange, "binance");
+        assert_eq!(update.symbol, "ETHUSDT");
+        assert_eq!(update.bid, 2500.1);
+        assert_eq!(update.ask_size, 40.66);
+        assert!((update.mid() - 2500.15).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_binance_book_ticker_ignores_other_messages() {
+        let feed = BinanceBookTicker::new(vec!["ETHUSDT".to_string()]);
+        assert_eq!(
+            feed.parse(r#"{"result":null,"id":1}"#),
+            None
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::{collections::HashMap, time::Duration};
+
+use alloy::primitives::{Address, BlockNumber, Bytes, U256};
+use async_trait::async_trait;
+use futures::stream;
+use serde::Deserialize;
+use tokio::time::{self, MissedTickBehavior};
+
+use crate::{
+    error::KazukaError,
+    types::{EventSource, EventStream},
+};
+
+/// CoW Protocol API for Ethereum mainnet.
+pub const DEFAULT_COW_API_URL: &str = "https://api.cow.fi/mainnet";
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Solver auction batch from the CoW Protocol API.
+/// See: https://api.cow.fi/docs/#/default/get_api_v1_auction
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CowAuction {
+    /// Auction id, increases with every new auction.
+    pub id: u64,
+    /// Block the auction is valid for.
+    pub block: BlockNumber,
+    /// Orders that can be settled in this auction.
+    pub orders: Vec<CowOrder>,
+    /// Reference prices of the traded tokens, denominated in wei.
+    pub prices: HashMap<Address, U256>,
+}
+
+/// Order from a CoW Protocol solver auction.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CowOrder {
+    /// Unique order identifier.
+    pub uid: Bytes,
+    pub sell_token: Address,
+    pub buy_token: Address,
+    pub sell_amount: U256,
+    pub buy_amount: U256,
+    pub kind: CowOrderKind,
+    pub owner: Address,
+    #[serde(default)]
+    pub receiver: Option<Address>,
+    /// Timestamp until which the order is valid.
+    pub valid_to: u32,
+    pub partially_fillable: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CowOrderKind {
+    Sell,
+    Buy,
+}
+
+/// Polls the CoW Protocol API and generates a stream of
+/// [solver auctions](CowAuction). Each auction is emitted once, when its id
+/// changes.
+pub struct CowAuctionEventSource {
+    api_url: String,
+    poll_interval: Duration,
+    client: reqwest::Client,
+}
+
+impl CowAuctionEventSource {
+    /// Creates an event source for the given CoW Protocol API,
+    /// e.g. [DEFAULT_COW_API_URL].
+    pub fn new(api_url: String) -> Self {
+        Self {
+            api_url,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Sets how often the current auction is polled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `poll_interval` is zero.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        assert!(
+            !poll_interval.is_zero(),
+            "Poll interval must be non-zero"
+        );
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Fetches the current solver auction.
+    pub async fn fetch_auction(&self) -> Result<CowAuction, KazukaError> {
+        let url = format!(
+            "{}/api/v1/auction",
+            self.api_url.trim_end_matches('/')
+        );
+        let auction = self.client.get(url).send().await?.json().await?;
+        Ok(auction)
+    }
+}
+
+#[async_trait]
+impl EventSource<CowAuction> for CowAuctionEventSource {
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, CowAuction>, KazukaError> {
+        let mut interval = time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let stream = stream::unfold(
+            (interval, None),
+            move |(mut interval, last_id)| async move {
+                loop {
+                    interval.tick().await;
+                    match self.fetch_auction().await {
+                        Ok(auction) if Some(auction.id) != last_id => {
+                            let id = Some(auction.id);
+                            return Some((auction, (interval, id)));
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            tracing::error!("Error fetching CoW auction: {}", e)
+                        }
+                    }
+                }
+            },
+        );
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_cow_auction() {
+        let auction = serde_json::json!({
+            "id": 10208009,
+            "block": 21000000,
+            "orders": [{
+                "uid": "0xaa4eb7b4da14b93ce42963ac4085fd8eee4a04170b36454f9f8b91b91f69705387a04752e516548b0d5d4df97384c0b22b64917965b15d24",
+                "sellToken": "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+                "buyToken": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                "sellAmount": "1000000000000000000",
+                "buyAmount": "2500000000",
+                "protocolFees": [],
+                "created": 1700000000,
+                "validTo": 1700000100,
+                "kind": "sell",
+                "receiver": null,
+                "owner": "0x87a04752e516548b0d5d4df97384c0b22b649179",
+                "partiallyFillable": false,
+                "executed": "0",
+                "class": "limit"
+            }],
+            "prices": {
+                "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2": "1000000000000000000"
+            },
+            "surplusCapturingJitOrderOwners": []
+        });
+
+        let auction: CowAuction = serde_json::from_value(auction).unwrap();
+
+        assert_eq!(auction.id, 10208009);
+        assert_eq!(auction.block, 21000000);
+        assert_eq!(auction.orders.len(), 1);
+
+        let order = &auction.orders[0];
+        assert_eq!(order.kind, CowOrderKind::Sell);
+        assert_eq!(
+            order.sell_amount,
+            U256::from(1000000000000000000_u64)
+        );
+        assert_eq!(order.receiver, None);
+        assert_eq!(
+            auction.prices
+                [&address!("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2")],
+            U256::from(1000000000000000000_u64)
+        );
+    }
+}
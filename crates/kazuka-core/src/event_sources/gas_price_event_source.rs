@@ -0,0 +1,109 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use alloy::{
+    network::AnyNetwork,
+    primitives::BlockNumber,
+    providers::{DynProvider, Provider},
+};
+use async_trait::async_trait;
+use futures::{StreamExt, future};
+
+use crate::{
+    error::KazukaError,
+    types::{EventSource, EventStream},
+};
+
+/// Number of blocks used for the base fee moving average by default.
+const DEFAULT_WINDOW: usize = 10;
+
+/// Fee context of a newly mined block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GasPrice {
+    /// Number of the block the fees were observed at.
+    pub block_number: BlockNumber,
+    /// Base fee of the block (in wei).
+    pub base_fee: u128,
+    /// Priority fee suggested by the node (in wei).
+    pub max_priority_fee: u128,
+    /// Simple moving average of the base fee over the last blocks (in wei).
+    pub base_fee_moving_average: u128,
+}
+
+/// Listens for new blocks, and generates a stream of [events](GasPrice)
+/// with the base fee, suggested priority fee and a short moving average of
+/// the base fee.
+///
+/// Blocks without a base fee (pre-London) are skipped.
+pub struct GasPriceEventSource {
+    provider: Arc<DynProvider<AnyNetwork>>,
+    window: usize,
+}
+
+impl GasPriceEventSource {
+    pub fn new(provider: Arc<DynProvider<AnyNetwork>>) -> Self {
+        Self {
+            provider,
+            window: DEFAULT_WINDOW,
+        }
+    }
+
+    /// Sets the number of blocks used for the base fee moving average.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+}
+
+#[async_trait]
+impl EventSource<GasPrice> for GasPriceEventSource {
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, GasPrice>, KazukaError> {
+        let subscription = self.provider.subscribe_blocks().await?;
+
+        let provider = Arc::clone(&self.provider);
+        let window = self.window;
+        let stream = subscription
+            .into_stream()
+            .filter_map(move |header| {
+                let provider = Arc::clone(&provider);
+                async move {
+                    let base_fee = header.base_fee_per_gas?;
+                    let max_priority_fee = provider
+                        .get_max_priority_fee_per_gas()
+                        .await
+                        .inspect_err(|e| {
+                            tracing::error!(
+                                "Error getting max priority fee per gas: {}",
+                                e
+                            )
+                        })
+                        .ok()?;
+                    Some((
+                        header.number,
+                        base_fee as u128,
+                        max_priority_fee,
+                    ))
+                }
+            })
+            .scan(
+                VecDeque::with_capacity(window),
+                move |history, (block_number, base_fee, max_priority_fee)| {
+                    history.push_back(base_fee);
+                    if history.len() > window {
+                        history.pop_front();
+                    }
+                    let base_fee_moving_average =
+                        history.iter().sum::<u128>() / history.len() as u128;
+                    future::ready(Some(GasPrice {
+                        block_number,
+                        base_fee,
+                        max_priority_fee,
+                        base_fee_moving_average,
+                    }))
+                },
+            );
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,199 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use futures::stream;
+use tokio::time::{self, MissedTickBehavior};
+
+use crate::{
+    clock::{Clock, system_clock},
+    error::KazukaError,
+    types::{EventSource, EventStream},
+};
+
+#[derive(Clone, Debug)]
+pub struct Tick {
+    /// Sequence number of the tick, starting from zero.
+    pub index: u64,
+    /// Time at which the tick was scheduled to fire.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Schedule on which [IntervalEventSource] emits ticks.
+#[derive(Clone, Debug)]
+pub enum TickSchedule {
+    /// Fires immediately and then every given period.
+    Interval(Duration),
+    /// Fires according to a cron expression evaluated in UTC.
+    Cron(Box<Schedule>),
+}
+
+/// Emits [ticks](Tick) on a fixed interval or a cron schedule.
+///
+/// Useful for periodic housekeeping, such as refreshing pool lists or
+/// re-pricing inventory, without keeping timers inside a strategy.
+pub struct IntervalEventSource {
+    schedule: TickSchedule,
+    clock: Arc<dyn Clock>,
+}
+
+impl IntervalEventSource {
+    /// Creates an event source that ticks every `period`.
+    ///
+    /// Missed ticks (e.g. when the consumer lags) are skipped rather than
+    /// emitted in a burst.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn new(period: Duration) -> Self {
+        assert!(
+            !period.is_zero(),
+            "Tick period must be non-zero"
+        );
+        Self {
+            schedule: TickSchedule::Interval(period),
+            clock: system_clock(),
+        }
+    }
+
+    /// Creates an event source from a cron expression with seconds, e.g.
+    /// `"0 */5 * * * *"` ticks every five minutes.
+    pub fn from_cron(expression: &str) -> Result<Self, KazukaError> {
+        let schedule = Schedule::from_str(expression).map_err(|e| {
+            KazukaError::CronError(expression.to_string(), e.to_string())
+        })?;
+        Ok(Self {
+            schedule: TickSchedule::Cron(Box::new(schedule)),
+            clock: system_clock(),
+        })
+    }
+
+    /// Sets the clock the ticks are timed and stamped with.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the schedule of this event source.
+    pub fn schedule(&self) -> &TickSchedule {
+        &self.schedule
+    }
+}
+
+#[async_trait]
+impl EventSource<Tick> for IntervalEventSource {
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, Tick>, KazukaError> {
+        match &self.schedule {
+            TickSchedule::Interval(period) => {
+                let mut interval =
+                    time::interval_at(self.clock.instant(), *period);
+                interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                let clock = self.clock.clone();
+                let stream = stream::unfold(
+                    (interval, 0_u64),
+                    move |(mut interval, index)| {
+                        let clock = clock.clone();
+                        async move {
+                            interval.tick().await;
+                            let tick = Tick {
+                                index,
+                                timestamp: clock.now(),
+                            };
+                            Some((tick, (interval, index + 1)))
+                        }
+                    },
+                );
+                Ok(Box::pin(stream))
+            }
+            TickSchedule::Cron(schedule) => {
+                let upcoming = schedule.after_owned(self.clock.now());
+                let clock = self.clock.clone();
+                let stream = stream::unfold(
+                    (upcoming, 0_u64),
+                    move |(mut upcoming, index)| {
+                        let clock = clock.clone();
+                        async move {
+                            let next = upcoming.next()?;
+                            // Fire immediately if we are already late.
+                            let delay = (next - clock.now())
+                                .to_std()
+                                .unwrap_or_default();
+                            clock.sleep(delay).await;
+                            let tick = Tick {
+                                index,
+                                timestamp: next,
+                            };
+                            Some((tick, (upcoming, index + 1)))
+                        }
+                    },
+                );
+                Ok(Box::pin(stream))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::clock::MockClock;
+
+    #[tokio::test]
+    async fn test_interval_event_source_emits_ticks() {
+        let source = IntervalEventSource::new(Duration::from_millis(10));
+        let stream = source
+            .get_event_stream()
+            .await
+            .expect("IntervalEventSource didn't return event stream");
+
+        let ticks: Vec<_> = stream.take(3).collect().await;
+        let indices: Vec<_> = ticks.iter().map(|tick| tick.index).collect();
+
+        assert_eq!(indices, vec![0, 1, 2]);
+        assert!(ticks[0].timestamp <= ticks[2].timestamp);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cron_ticks_follow_clock() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let source = IntervalEventSource::from_cron("0 */5 * * * *")
+            .unwrap()
+            .with_clock(Arc::new(MockClock::new(start)));
+        let stream = source.get_event_stream().await.unwrap();
+
+        let ticks: Vec<_> = stream.take(2).collect().await;
+        let timestamps: Vec<_> = ticks
+            .iter()
+            .map(|tick| tick.timestamp.timestamp())
+            .collect();
+        // 2023-11-14 22:13:20 UTC
+        assert_eq!(
+            timestamps,
+            vec![1_700_000_100, 1_700_000_400]
+        );
+    }
+
+    #[test]
+    fn test_interval_event_source_rejects_invalid_cron() {
+        let result = IntervalEventSource::from_cron("every five minutes");
+        assert!(matches!(
+            result,
+            Err(KazukaError::CronError(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_interval_event_source_parses_cron() {
+        let source = IntervalEventSource::from_cron("0 */5 * * * *").unwrap();
+        assert!(matches!(
+            source.schedule(),
+            TickSchedule::Cron(_)
+        ));
+    }
+}
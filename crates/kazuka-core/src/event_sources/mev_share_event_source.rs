@@ -1,6 +1,16 @@
+use std::{sync::Arc, time::Duration};
+
+use alloy::{
+    consensus::Transaction as _,
+    network::{AnyNetwork, AnyRpcTransaction, TransactionResponse},
+    primitives::{TxHash, U256},
+    providers::{DynProvider, Provider},
+    rpc::types::mev::mevshare::FunctionSelector,
+};
 use async_trait::async_trait;
-use kazuka_mev_share::sse;
-use tokio_stream::StreamExt;
+use futures::{StreamExt, future, stream};
+use kazuka_mev_share::{sse, ws};
+use serde::Serialize;
 
 use crate::{
     error::KazukaError,
@@ -9,19 +19,432 @@ use crate::{
 
 pub type MevShareEvent = kazuka_mev_share::sse::Event;
 
+/// Default number of times the SSE client follows a `retry` directive of
+/// the server before giving up on the current connection.
+const DEFAULT_MAX_RETRIES: u64 = 5;
+
+/// Default delay before reconnecting after the stream has terminated.
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Item of the MEV-Share stream, including connection health updates.
+#[derive(Clone, Debug)]
+pub enum MevShareStreamEvent {
+    /// Connection to the SSE endpoint has been (re-)established.
+    Connected,
+    /// Event received from the live stream.
+    Event(MevShareEvent),
+    /// Past event replayed from the `history` endpoint, which is likely too
+    /// old to be backrun.
+    Replayed(sse::HistoricalEvent),
+    /// Received an event, which could not be decoded, or the connection
+    /// failed mid-stream.
+    StreamError(String),
+    /// Failed to connect to the SSE endpoint. The stream ends after errors,
+    /// which are not [retryable](crate::error::ErrorClass::is_retryable).
+    ConnectionFailed { attempt: u32, error: String },
+    /// The stream has terminated, reconnecting.
+    Disconnected,
+}
+
+/// Resolves full transactions from the node for MEV-Share events, whose
+/// hints only include the transaction hash, filling in the fields omitted
+/// by the hint (calldata, function selector, sender, recipient, value and
+/// fees).
+#[derive(Clone)]
+pub struct EventEnricher {
+    provider: Arc<DynProvider<AnyNetwork>>,
+}
+
+impl EventEnricher {
+    pub fn new(provider: Arc<DynProvider<AnyNetwork>>) -> Self {
+        Self { provider }
+    }
+
+    /// Enriches the transactions of the event. Transactions, which the node
+    /// doesn't know about, are left as is.
+    pub async fn enrich(&self, mut event: MevShareEvent) -> MevShareEvent {
+        // Transaction events may omit the transaction list entirely.
+        if event.transactions.is_empty()
+            && let Some(tx) = self.fetch(event.hash).await
+        {
+            event.transactions.push(sse::EventTransaction {
+                hash: Some(event.hash),
+                calldata: None,
+                function_selector: None,
+                to: None,
+                from: None,
+                value: None,
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                access_list: None,
+            });
+            fill(&mut event.transactions[0], &tx);
+            return event;
+        }
+        for event_tx in &mut event.transactions {
+            if event_tx.calldata.is_some() {
+                continue;
+            }
+            let Some(hash) = event_tx.hash else {
+                continue;
+            };
+            if let Some(tx) = self.fetch(hash).await {
+                fill(event_tx, &tx);
+            }
+        }
+        event
+    }
+
+    async fn fetch(&self, hash: TxHash) -> Option<AnyRpcTransaction> {
+        self.provider
+            .get_transaction_by_hash(hash)
+            .await
+            .inspect_err(|e| {
+                tracing::debug!(
+                    ?hash,
+                    "Error fetching transaction: {}",
+                    e
+                )
+            })
+            .ok()
+            .flatten()
+    }
+}
+
+/// Fills the fields missing from the hint.
+fn fill(event_tx: &mut sse::EventTransaction, tx: &AnyRpcTransaction) {
+    let input = tx.input();
+    if event_tx.function_selector.is_none()
+        && let Some(selector) = input.get(..4)
+    {
+        event_tx.function_selector = Some(FunctionSelector(
+            selector.try_into().unwrap(),
+        ));
+    }
+    event_tx.calldata.get_or_insert_with(|| input.clone());
+    event_tx.to = event_tx.to.or(tx.kind().to().copied());
+    event_tx.from.get_or_insert(TransactionResponse::from(tx));
+    event_tx.value.get_or_insert(tx.value());
+    event_tx
+        .max_fee_per_gas
+        .get_or_insert(U256::from(tx.max_fee_per_gas()));
+    if let Some(fee) = tx.max_priority_fee_per_gas() {
+        event_tx
+            .max_priority_fee_per_gas
+            .get_or_insert(U256::from(fee));
+    }
+}
+
+/// Transport delivering MEV-Share events.
+#[derive(Clone, Debug, Default)]
+pub enum MevShareTransport {
+    /// Server-sent events, as exposed by Flashbots.
+    #[default]
+    Sse,
+    /// WebSocket, for providers exposing hints over WS.
+    WebSocket(ws::WsEventClient),
+}
+
+/// Live connection over one of the [transports](MevShareTransport).
+enum Connection {
+    Sse(sse::EventStream<MevShareEvent>),
+    WebSocket(ws::WsEventStream<MevShareEvent>),
+    /// SSE stream preceded by the replayed history.
+    CatchUp(sse::CatchUpEventStream),
+}
+
+impl Connection {
+    async fn next(&mut self) -> Option<Result<sse::CatchUpEvent, String>> {
+        match self {
+            Self::Sse(events) => events.next().await.map(|event| {
+                event
+                    .map(sse::CatchUpEvent::Live)
+                    .map_err(|e| e.to_string())
+            }),
+            Self::WebSocket(events) => events.next().await.map(|event| {
+                event
+                    .map(sse::CatchUpEvent::Live)
+                    .map_err(|e| e.to_string())
+            }),
+            Self::CatchUp(events) => events
+                .next()
+                .await
+                .map(|event| event.map_err(|e| e.to_string())),
+        }
+    }
+
+    fn reset_retries(&mut self) {
+        match self {
+            Self::Sse(events) => events.reset_retries(),
+            Self::WebSocket(events) => events.reset_retries(),
+            // The live stream is owned by the catch-up stream, its retries
+            // are reset on the next reconnection.
+            Self::CatchUp(_) => {}
+        }
+    }
+}
+
+enum ConnectionState {
+    Connected(Connection),
+    /// The history is replayed until a connection has been established.
+    Disconnected {
+        attempt: u32,
+        replay_history: bool,
+    },
+    Stopped,
+}
+
 /// Streams from MEV-Share SSE endpoint and
 /// generates [events](MevShareEvent), which return tx hash, logs,
 /// and bundled txs.
+///
+/// Events can also be received over WebSocket, see
+/// [with_websocket](MevShareEventSource::with_websocket).
+///
+/// Can replay recent events from the `history` endpoint before switching to
+/// the live stream, so that events are not missed across restarts, see
+/// [with_history](MevShareEventSource::with_history).
+///
+/// Reconnects when the stream terminates. Implements
+/// `EventSource<MevShareStreamEvent>` for strategies interested in the
+/// connection health, and `EventSource<MevShareEvent>`, which only yields
+/// live events and logs errors.
 pub struct MevShareEventSource {
     mev_share_sse_url: String,
+    query: Option<serde_json::Value>,
+    history: Option<sse::HistoryStart>,
+    client: sse::EventClient,
+    reconnect_delay: Duration,
+    enricher: Option<EventEnricher>,
+    transport: MevShareTransport,
 }
 
 impl MevShareEventSource {
     pub fn new(url: String) -> Self {
         Self {
             mev_share_sse_url: url,
+            query: None,
+            history: None,
+            client: sse::EventClient::default()
+                .with_max_retries(DEFAULT_MAX_RETRIES),
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            enricher: None,
+            transport: MevShareTransport::Sse,
         }
     }
+
+    /// Sets how many `retry` directives of the server are followed before
+    /// the connection is considered terminated.
+    pub fn with_max_retries(mut self, max_retries: u64) -> Self {
+        self.client.set_max_retries(max_retries);
+        self
+    }
+
+    /// Drops events, which don't match the filter, before they are
+    /// deserialized and reach strategies.
+    pub fn with_filter(mut self, filter: sse::EventFilter) -> Self {
+        self.client = self.client.with_filter(filter);
+        self
+    }
+
+    /// Notifies the observer about the activity of the SSE stream, see
+    /// [SseMetrics](crate::telemetry::SseMetrics).
+    pub fn with_observer(
+        mut self,
+        observer: Arc<dyn sse::SseObserver>,
+    ) -> Self {
+        self.client = self.client.with_observer(observer);
+        self
+    }
+
+    /// Receives events over WebSocket instead of SSE, e.g.
+    /// `wss://provider.example/mev-share`. The query params and the SSE
+    /// client settings don't apply.
+    pub fn with_websocket(mut self, client: ws::WsEventClient) -> Self {
+        self.transport = MevShareTransport::WebSocket(client);
+        self
+    }
+
+    /// Sets additional query parameters sent to the SSE endpoint, failing
+    /// if they can't be serialized as JSON.
+    pub fn with_query<S: Serialize>(
+        mut self,
+        query: S,
+    ) -> Result<Self, KazukaError> {
+        let query = serde_json::to_value(query).map_err(|e| {
+            KazukaError::ConfigError(format!("Invalid MEV-Share query: {e}"))
+        })?;
+        self.query = Some(query);
+        Ok(self)
+    }
+
+    /// Replays events since the given block or timestamp from the `history`
+    /// endpoint before switching to the live stream, without gaps, see
+    /// [sse::EventClient::events_since]. Replayed events are yielded as
+    /// [MevShareStreamEvent::Replayed].
+    ///
+    /// Only supported by the SSE transport.
+    pub fn with_history(mut self, start: sse::HistoryStart) -> Self {
+        self.history = Some(start);
+        self
+    }
+
+    /// Endpoint to fetch past events from, e.g.
+    /// `https://mev-share.flashbots.net/api/v1/history`.
+    fn history_url(&self) -> String {
+        format!(
+            "{}/api/v1/history",
+            self.mev_share_sse_url.trim_end_matches('/')
+        )
+    }
+
+    /// Connects to the live stream, preceded by the history if
+    /// `replay_history`.
+    async fn connect(
+        &self,
+        replay_history: bool,
+    ) -> Result<Connection, KazukaError> {
+        let url = &self.mev_share_sse_url;
+        let history = self.history.filter(|_| replay_history);
+        let connection = match (&self.transport, &self.query, history) {
+            (MevShareTransport::WebSocket(_), _, Some(_)) => {
+                return Err(KazukaError::ConfigError(
+                    "MEV-Share history can only be replayed over SSE"
+                        .to_string(),
+                ));
+            }
+            (MevShareTransport::WebSocket(client), _, None) => {
+                Connection::WebSocket(client.events(url).await?)
+            }
+            (MevShareTransport::Sse, Some(query), Some(start)) => {
+                Connection::CatchUp(
+                    self.client
+                        .events_since_with_query(
+                            url,
+                            query,
+                            &self.history_url(),
+                            start,
+                        )
+                        .await?,
+                )
+            }
+            (MevShareTransport::Sse, None, Some(start)) => Connection::CatchUp(
+                self.client
+                    .events_since(url, &self.history_url(), start)
+                    .await?,
+            ),
+            (MevShareTransport::Sse, Some(query), None) => Connection::Sse(
+                self.client.subscribe_with_query(url, query).await?,
+            ),
+            (MevShareTransport::Sse, None, None) => {
+                Connection::Sse(self.client.events(url).await?)
+            }
+        };
+        Ok(connection)
+    }
+
+    /// Resolves full transactions of live events from the node, see
+    /// [EventEnricher].
+    pub fn with_enrichment(
+        mut self,
+        provider: Arc<DynProvider<AnyNetwork>>,
+    ) -> Self {
+        self.enricher = Some(EventEnricher::new(provider));
+        self
+    }
+
+    /// Sets the delay between reconnection attempts.
+    pub fn with_reconnect_delay(mut self, reconnect_delay: Duration) -> Self {
+        self.reconnect_delay = reconnect_delay;
+        self
+    }
+}
+
+#[async_trait]
+impl EventSource<MevShareStreamEvent> for MevShareEventSource {
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, MevShareStreamEvent>, KazukaError> {
+        let events = stream::unfold(
+            ConnectionState::Disconnected {
+                attempt: 0,
+                replay_history: true,
+            },
+            move |state| async move {
+                match state {
+                    ConnectionState::Disconnected {
+                        attempt,
+                        replay_history,
+                    } => {
+                        if attempt > 0 {
+                            tokio::time::sleep(self.reconnect_delay).await;
+                        }
+                        match self.connect(replay_history).await {
+                            Ok(events) => Some((
+                                MevShareStreamEvent::Connected,
+                                ConnectionState::Connected(events),
+                            )),
+                            Err(error) => {
+                                let next_state =
+                                    if error.classify().is_retryable() {
+                                        ConnectionState::Disconnected {
+                                            attempt: attempt + 1,
+                                            replay_history,
+                                        }
+                                    } else {
+                                        ConnectionState::Stopped
+                                    };
+                                Some((
+                                    MevShareStreamEvent::ConnectionFailed {
+                                        attempt: attempt + 1,
+                                        error: error.to_string(),
+                                    },
+                                    next_state,
+                                ))
+                            }
+                        }
+                    }
+                    ConnectionState::Connected(mut events) => {
+                        match events.next().await {
+                            Some(Ok(sse::CatchUpEvent::Live(event))) => {
+                                events.reset_retries();
+                                let event = match &self.enricher {
+                                    Some(enricher) => {
+                                        enricher.enrich(event).await
+                                    }
+                                    None => event,
+                                };
+                                Some((
+                                    MevShareStreamEvent::Event(event),
+                                    ConnectionState::Connected(events),
+                                ))
+                            }
+                            Some(Ok(sse::CatchUpEvent::Replayed(event))) => {
+                                Some((
+                                    MevShareStreamEvent::Replayed(event),
+                                    ConnectionState::Connected(events),
+                                ))
+                            }
+                            Some(Err(e)) => Some((
+                                MevShareStreamEvent::StreamError(e),
+                                ConnectionState::Connected(events),
+                            )),
+                            None => Some((
+                                MevShareStreamEvent::Disconnected,
+                                ConnectionState::Disconnected {
+                                    attempt: 1,
+                                    replay_history: false,
+                                },
+                            )),
+                        }
+                    }
+                    ConnectionState::Stopped => None,
+                }
+            },
+        );
+
+        Ok(Box::pin(events))
+    }
 }
 
 #[async_trait]
@@ -29,12 +452,107 @@ impl EventSource<MevShareEvent> for MevShareEventSource {
     async fn get_event_stream(
         &self,
     ) -> Result<EventStream<'_, MevShareEvent>, KazukaError> {
-        let client = sse::EventClient::default();
-        let stream = client
-            .events(&self.mev_share_sse_url)
-            .await
-            .expect("Expected MEV-Share SSE stream")
-            .filter_map(Result::ok);
+        let stream = EventSource::<MevShareStreamEvent>::get_event_stream(self)
+            .await?
+            .filter_map(|event| {
+                future::ready(match event {
+                    MevShareStreamEvent::Event(event) => Some(event),
+                    // Replayed events are too old to be backrun.
+                    MevShareStreamEvent::Replayed(event) => {
+                        tracing::debug!(
+                            "Skipping replayed MEV-Share event {:?}",
+                            event.hint.hash
+                        );
+                        None
+                    }
+                    MevShareStreamEvent::Connected => {
+                        tracing::info!("Connected to MEV-Share");
+                        None
+                    }
+                    MevShareStreamEvent::StreamError(e) => {
+                        tracing::error!("MEV-Share stream error: {}", e);
+                        None
+                    }
+                    MevShareStreamEvent::ConnectionFailed {
+                        attempt,
+                        error,
+                    } => {
+                        tracing::error!(
+                            attempt,
+                            "Error connecting to MEV-Share: {}",
+                            error
+                        );
+                        None
+                    }
+                    MevShareStreamEvent::Disconnected => {
+                        tracing::warn!(
+                            "MEV-Share stream terminated, reconnecting"
+                        );
+                        None
+                    }
+                })
+            });
         Ok(Box::pin(stream))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connection_failure_is_surfaced() {
+        let source = MevShareEventSource::new("http://127.0.0.1:1".to_string())
+            .with_reconnect_delay(Duration::from_millis(10));
+        let events: Vec<MevShareStreamEvent> =
+            EventSource::<MevShareStreamEvent>::get_event_stream(&source)
+                .await
+                .unwrap()
+                .take(2)
+                .collect()
+                .await;
+
+        assert!(matches!(
+            events[..],
+            [
+                MevShareStreamEvent::ConnectionFailed { attempt: 1, .. },
+                MevShareStreamEvent::ConnectionFailed { attempt: 2, .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_invalid_query_is_rejected() {
+        // JSON objects only have string keys.
+        let query = HashMap::from([((1, 2), 3)]);
+
+        let source = MevShareEventSource::new("http://127.0.0.1:1".to_string())
+            .with_query(query);
+
+        assert!(matches!(
+            source,
+            Err(KazukaError::ConfigError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_history_requires_sse() {
+        let source = MevShareEventSource::new("ws://127.0.0.1:1".to_string())
+            .with_websocket(ws::WsEventClient::default())
+            .with_history(sse::HistoryStart::Block(1));
+        let events: Vec<MevShareStreamEvent> =
+            EventSource::<MevShareStreamEvent>::get_event_stream(&source)
+                .await
+                .unwrap()
+                .collect()
+                .await;
+
+        // The stream ends as the config can't be fixed by reconnecting.
+        assert!(matches!(
+            events[..],
+            [MevShareStreamEvent::ConnectionFailed { attempt: 1, .. }]
+        ));
+    }
+}
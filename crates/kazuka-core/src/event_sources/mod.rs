@@ -1,4 +1,10 @@
 pub mod block_event_source;
+pub mod bundle_stats_event_source;
+pub mod cex_price_event_source;
+pub mod cow_auction_event_source;
+pub mod gas_price_event_source;
+pub mod interval_event_source;
 pub mod log_event_source;
 pub mod mempool_event_source;
 pub mod mev_share_event_source;
+pub mod payload_attributes_event_source;
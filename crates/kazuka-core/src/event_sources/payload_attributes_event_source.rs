@@ -0,0 +1,233 @@
+use alloy::primitives::{Address, B256, BlockHash, BlockNumber};
+use async_trait::async_trait;
+use futures::{StreamExt, future};
+use kazuka_mev_share::sse::EventClient;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::KazukaError,
+    types::{EventSource, EventStream},
+};
+
+/// `payload_attributes` event from the beacon node event stream.
+/// See: https://ethereum.github.io/beacon-APIs/#/Events/eventstream
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct PayloadAttributesEvent {
+    /// Fork version of the payload attributes.
+    pub version: String,
+    pub data: PayloadAttributesData,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct PayloadAttributesData {
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub proposer_index: u64,
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub proposal_slot: u64,
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub parent_block_number: BlockNumber,
+    pub parent_block_root: B256,
+    pub parent_block_hash: BlockHash,
+    pub payload_attributes: PayloadAttributes,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct PayloadAttributes {
+    /// Timestamp of the upcoming slot.
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub timestamp: u64,
+    pub prev_randao: B256,
+    pub suggested_fee_recipient: Address,
+}
+
+/// Proposer duty as returned by the relay `/relay/v1/builder/validators`
+/// endpoint. Only the fields we need are deserialized.
+#[derive(Clone, Debug, Deserialize)]
+struct ProposerDuty {
+    #[serde(with = "alloy::serde::displayfromstr")]
+    slot: u64,
+}
+
+#[derive(Serialize)]
+struct EventTopics {
+    topics: &'static str,
+}
+
+/// Upcoming slot, its proposer and the relays the proposer is registered
+/// with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpcomingSlot {
+    pub slot: u64,
+    pub proposer_index: u64,
+    /// Timestamp at which the slot starts.
+    pub timestamp: u64,
+    pub parent_block_number: BlockNumber,
+    pub parent_block_hash: BlockHash,
+    pub fee_recipient: Address,
+    /// Relays (out of the configured ones) that have the proposer of this
+    /// slot registered.
+    pub registered_relays: Vec<String>,
+}
+
+/// Subscribes to `payload_attributes` events of a beacon node and generates
+/// a stream of [events](UpcomingSlot), which allows timing bundle submission
+/// to the slot boundary.
+pub struct PayloadAttributesEventSource {
+    beacon_url: String,
+    relays: Vec<String>,
+    client: reqwest::Client,
+}
+
+impl PayloadAttributesEventSource {
+    pub fn new(beacon_url: String) -> Self {
+        Self {
+            beacon_url,
+            relays: vec![],
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Sets the relays to check proposer registrations against,
+    /// e.g. `https://boost-relay.flashbots.net`.
+    pub fn with_relays(mut self, relays: Vec<String>) -> Self {
+        self.relays = relays;
+        self
+    }
+
+    /// Returns the configured relays that have a proposer registered for the
+    /// given slot.
+    async fn registered_relays(&self, slot: u64) -> Vec<String> {
+        let lookups = self.relays.iter().map(|relay| async move {
+            let url = format!(
+                "{}/relay/v1/builder/validators",
+                relay.trim_end_matches('/')
+            );
+            let duties = async {
+                self.client
+                    .get(url)
+                    .send()
+                    .await?
+                    .json::<Vec<ProposerDuty>>()
+                    .await
+            };
+            match duties.await {
+                Ok(duties) if duties.iter().any(|duty| duty.slot == slot) => {
+                    Some(relay.clone())
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::warn!(
+                        relay = %relay,
+                        "Error fetching proposer duties: {}",
+                        e
+                    );
+                    None
+                }
+            }
+        });
+        future::join_all(lookups)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl EventSource<UpcomingSlot> for PayloadAttributesEventSource {
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, UpcomingSlot>, KazukaError> {
+        let endpoint = format!(
+            "{}/eth/v1/events",
+            self.beacon_url.trim_end_matches('/')
+        );
+        let topics = EventTopics {
+            topics: "payload_attributes",
+        };
+        let stream = EventClient::new(self.client.clone())
+            .subscribe_with_query::<PayloadAttributesEvent, _>(
+                &endpoint, topics,
+            )
+            .await?
+            .filter_map(|event| {
+                future::ready(
+                    event
+                        .inspect_err(|e| {
+                            tracing::error!(
+                                "Error receiving payload attributes: {}",
+                                e
+                            )
+                        })
+                        .ok(),
+                )
+            })
+            .then(move |event| async move {
+                let data = event.data;
+                UpcomingSlot {
+                    slot: data.proposal_slot,
+                    proposer_index: data.proposer_index,
+                    timestamp: data.payload_attributes.timestamp,
+                    parent_block_number: data.parent_block_number,
+                    parent_block_hash: data.parent_block_hash,
+                    fee_recipient: data
+                        .payload_attributes
+                        .suggested_fee_recipient,
+                    registered_relays: self
+                        .registered_relays(data.proposal_slot)
+                        .await,
+                }
+            });
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, b256};
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_payload_attributes_event() {
+        let event = serde_json::json!({
+            "version": "deneb",
+            "data": {
+                "proposer_index": "123",
+                "proposal_slot": "10",
+                "parent_block_number": "9",
+                "parent_block_root": "0xcf8e0d4e9587369b2301d0790347320302cc0943d5a1884560367e8208d920f2",
+                "parent_block_hash": "0x9a2fefd2fdb57f74993c7780ea5b9030d2897b615b89f808011ca5aebed54eaf",
+                "payload_attributes": {
+                    "timestamp": "123456",
+                    "prev_randao": "0xcf8e0d4e9587369b2301d0790347320302cc0943d5a1884560367e8208d920f2",
+                    "suggested_fee_recipient": "0x0000000000000000000000000000000000000000",
+                    "withdrawals": [],
+                    "parent_beacon_block_root": "0xcf8e0d4e9587369b2301d0790347320302cc0943d5a1884560367e8208d920f2"
+                }
+            }
+        });
+
+        let event: PayloadAttributesEvent =
+            serde_json::from_value(event).unwrap();
+
+        assert_eq!(event.data.proposal_slot, 10);
+        assert_eq!(event.data.proposer_index, 123);
+        assert_eq!(event.data.parent_block_number, 9);
+        assert_eq!(
+            event.data.parent_block_hash,
+            b256!(
+                "0x9a2fefd2fdb57f74993c7780ea5b9030d2897b615b89f808011ca5aebed54eaf"
+            )
+        );
+        assert_eq!(
+            event.data.payload_attributes.timestamp,
+            123456
+        );
+        assert_eq!(
+            event.data.payload_attributes.suggested_fee_recipient,
+            address!("0x0000000000000000000000000000000000000000")
+        );
+    }
+}
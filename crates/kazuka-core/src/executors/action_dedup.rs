@@ -0,0 +1,152 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::{
+    clock::{Clock, system_clock},
+    error::KazukaError,
+    types::Executor,
+};
+
+/// Wraps [Executor] and drops actions whose key has already been executed
+/// within the window, e.g. bundles built again from hints redelivered after
+/// the SSE stream reconnected, which would waste the rate limits of the
+/// relays.
+///
+/// ```ignore
+/// let executor = ActionDedup::new(
+///     Box::new(mev_share_executor),
+///     mev_bundle_hash,
+///     Duration::from_secs(12),
+/// );
+/// ```
+pub struct ActionDedup<A, K> {
+    inner: Box<dyn Executor<A>>,
+    key: Box<dyn Fn(&A) -> K + Send + Sync>,
+    window: Duration,
+    /// When the actions were last executed, by key.
+    seen: Mutex<HashMap<K, Instant>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<A, K> ActionDedup<A, K>
+where
+    K: Hash + Eq,
+{
+    pub fn new(
+        inner: Box<dyn Executor<A>>,
+        key: impl Fn(&A) -> K + Send + Sync + 'static,
+        window: Duration,
+    ) -> Self {
+        Self {
+            inner,
+            key: Box::new(key),
+            window,
+            seen: Mutex::new(HashMap::new()),
+            clock: system_clock(),
+        }
+    }
+
+    /// Sets the clock the window is measured with.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Records the key, unless it has been seen within the window.
+    fn first_seen(&self, key: &K) -> bool
+    where
+        K: Clone,
+    {
+        let now = self.clock.instant();
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, at| now.duration_since(*at) < self.window);
+        if seen.contains_key(key) {
+            return false;
+        }
+        seen.insert(key.clone(), now);
+        true
+    }
+}
+
+#[async_trait]
+impl<A, K> Executor<A> for ActionDedup<A, K>
+where
+    A: Send + 'static,
+    K: Hash + Eq + Clone + std::fmt::Debug + Send + Sync + 'static,
+{
+    /// Executes the action, unless it's a duplicate. Actions that failed may
+    /// be retried within the window.
+    async fn execute(&self, action: A) -> Result<(), KazukaError> {
+        let key = (self.key)(&action);
+        if !self.first_seen(&key) {
+            tracing::debug!(?key, "Skipping duplicate action");
+            return Ok(());
+        }
+        let result = self.inner.execute(action).await;
+        if result.is_err() {
+            self.seen.lock().unwrap().remove(&key);
+        }
+        result
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails actions equal to zero.
+    struct MockExecutor {
+        actions: Arc<Mutex<Vec<u32>>>,
+    }
+
+    #[async_trait]
+    impl Executor<u32> for MockExecutor {
+        async fn execute(&self, action: u32) -> Result<(), KazukaError> {
+            self.actions.lock().unwrap().push(action);
+            if action == 0 {
+                return Err(KazukaError::BundleRejected(
+                    "zero".to_string(),
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dedup() {
+        let actions = Arc::new(Mutex::new(vec![]));
+        let executor = ActionDedup::new(
+            Box::new(MockExecutor {
+                actions: Arc::clone(&actions),
+            }),
+            |action: &u32| *action,
+            Duration::from_secs(12),
+        );
+        for action in [1, 2, 1, 0, 0] {
+            let _ = executor.execute(action).await;
+        }
+        // Failed actions aren't deduplicated.
+        assert_eq!(
+            *actions.lock().unwrap(),
+            vec![1, 2, 0, 0]
+        );
+
+        tokio::time::advance(Duration::from_secs(12)).await;
+        executor.execute(1).await.unwrap();
+        assert_eq!(
+            *actions.lock().unwrap(),
+            vec![1, 2, 0, 0, 1]
+        );
+    }
+}
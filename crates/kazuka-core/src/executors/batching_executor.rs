@@ -0,0 +1,180 @@
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+
+use crate::{error::KazukaError, types::Executor};
+
+const DEFAULT_MAX_BATCH_SIZE: usize = 16;
+const DEFAULT_MAX_DELAY: Duration = Duration::from_millis(100);
+
+/// Actions collected since the last flush.
+struct Batch<A> {
+    actions: Vec<A>,
+    /// Number of flushes so far, so that the timer of a batch flushed because
+    /// it was full doesn't flush the next one early.
+    flushes: u64,
+}
+
+impl<A> Batch<A> {
+    fn take(&mut self) -> Vec<A> {
+        self.flushes += 1;
+        mem::take(&mut self.actions)
+    }
+}
+
+/// Wraps [Executor] of batches of actions, e.g. to send several private
+/// txs in a single request, or to coalesce notifications.
+///
+/// Actions are collected until the batch is full, or until the first action
+/// of the batch has been waiting for the maximum delay. The action that
+/// fills the batch returns the result of executing it, while errors of
+/// batches flushed by the timer are only logged.
+pub struct BatchingExecutor<A> {
+    inner: Arc<dyn Executor<Vec<A>>>,
+    batch: Arc<Mutex<Batch<A>>>,
+    max_batch_size: usize,
+    max_delay: Duration,
+}
+
+impl<A> BatchingExecutor<A> {
+    pub fn new(inner: Box<dyn Executor<Vec<A>>>) -> Self {
+        Self {
+            inner: Arc::from(inner),
+            batch: Arc::new(Mutex::new(Batch {
+                actions: vec![],
+                flushes: 0,
+            })),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            max_delay: DEFAULT_MAX_DELAY,
+        }
+    }
+
+    /// Sets the number of actions flushing a batch right away.
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+
+    /// Sets how long the first action of a batch waits for more actions.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+}
+
+#[async_trait]
+impl<A> Executor<A> for BatchingExecutor<A>
+where
+    A: Send + 'static,
+{
+    /// Adds the action to the batch, and executes the batch once it's full.
+    async fn execute(&self, action: A) -> Result<(), KazukaError> {
+        let (full, first, flushes) = {
+            let mut batch = self.batch.lock().unwrap();
+            batch.actions.push(action);
+            let len = batch.actions.len();
+            let full = (len >= self.max_batch_size).then(|| batch.take());
+            (full, len == 1, batch.flushes)
+        };
+        if let Some(actions) = full {
+            tracing::debug!(
+                size = actions.len(),
+                "Executing full batch"
+            );
+            return self.inner.execute(actions).await;
+        }
+
+        if first {
+            let inner = Arc::clone(&self.inner);
+            let batch = Arc::clone(&self.batch);
+            let max_delay = self.max_delay;
+            tokio::spawn(async move {
+                tokio::time::sleep(max_delay).await;
+                let actions = {
+                    let mut batch = batch.lock().unwrap();
+                    if batch.flushes != flushes || batch.actions.is_empty() {
+                        return;
+                    }
+                    batch.take()
+                };
+                tracing::debug!(size = actions.len(), "Executing batch");
+                if let Err(e) = inner.execute(actions).await {
+                    tracing::error!(
+                        class = ?e.classify(),
+                        "Error executing batch: {}",
+                        e
+                    );
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::sleep;
+
+    use super::*;
+
+    struct MockExecutor {
+        batches: Arc<Mutex<Vec<Vec<u32>>>>,
+    }
+
+    #[async_trait]
+    impl Executor<Vec<u32>> for MockExecutor {
+        async fn execute(&self, action: Vec<u32>) -> Result<(), KazukaError> {
+            self.batches.lock().unwrap().push(action);
+            Ok(())
+        }
+    }
+
+    fn batching_executor() -> (
+        BatchingExecutor<u32>,
+        Arc<Mutex<Vec<Vec<u32>>>>,
+    ) {
+        let batches = Arc::new(Mutex::new(vec![]));
+        let executor = BatchingExecutor::new(Box::new(MockExecutor {
+            batches: Arc::clone(&batches),
+        }))
+        .with_max_batch_size(2)
+        .with_max_delay(Duration::from_millis(50));
+        (executor, batches)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_flushes_full_batches() {
+        let (executor, batches) = batching_executor();
+        executor.execute(0).await.unwrap();
+        executor.execute(1).await.unwrap();
+        assert_eq!(
+            *batches.lock().unwrap(),
+            vec![vec![0, 1]]
+        );
+
+        // The timer of the full batch doesn't flush the next one early.
+        sleep(Duration::from_millis(30)).await;
+        executor.execute(2).await.unwrap();
+        sleep(Duration::from_millis(30)).await;
+        assert_eq!(batches.lock().unwrap().len(), 1);
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            *batches.lock().unwrap(),
+            vec![vec![0, 1], vec![2]]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_flushes_after_max_delay() {
+        let (executor, batches) = batching_executor();
+        executor.execute(7).await.unwrap();
+        assert!(batches.lock().unwrap().is_empty());
+
+        sleep(Duration::from_millis(80)).await;
+        assert_eq!(*batches.lock().unwrap(), vec![vec![7]]);
+    }
+}
@@ -0,0 +1,695 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use alloy::{
+    primitives::{B256, BlockNumber, TxHash, keccak256},
+    rpc::types::mev::{BundleItem, EthSendBundle, MevSendBundle},
+    signers::Signer,
+};
+use async_trait::async_trait;
+use jsonrpsee::http_client::HttpClientBuilder;
+use kazuka_mev_share::{
+    backend::store::{self, BundleRecord, BundleStore, RelaySubmission},
+    rpc::{
+        BundleReplacementExt, EthBundleApiClient, FlashbotsApiClient,
+        MevApiClient,
+        middleware::FlashbotsAuthLayer,
+        types::{eth_bundle_hash, mev_bundle_hash},
+    },
+};
+use tower::ServiceBuilder;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    error::KazukaError,
+    event_sources::bundle_stats_event_source::{BundleTracker, TrackedBundle},
+    pnl::{BundleOrigin, PnlEventKind, PnlLedger},
+    types::Executor,
+};
+
+/// Flashbots relay endpoint for Ethereum mainnet.
+pub const FLASHBOTS_RELAY_URL: &str = "https://relay.flashbots.net";
+
+/// Builds a JSON-RPC client for a Flashbots-compatible relay, which signs
+/// every request with the given `signer`.
+pub fn signed_client(
+    url: String,
+    signer: impl Signer + Clone + Send + Sync + 'static,
+) -> impl EthBundleApiClient
++ MevApiClient
++ FlashbotsApiClient
++ Clone
++ Send
++ Sync
++ 'static {
+    let http_middleware =
+        ServiceBuilder::new().layer(FlashbotsAuthLayer::new(signer));
+
+    HttpClientBuilder::default()
+        .set_http_middleware(http_middleware)
+        .build(url)
+        .expect("Failed to build HTTP client")
+}
+
+/// Bundle to submit to a Flashbots-compatible relay.
+#[derive(Clone, Debug)]
+pub enum SubmitBundle {
+    /// Submitted via `eth_sendBundle`.
+    Eth(EthSendBundle),
+    /// Submitted via `mev_sendBundle`.
+    Mev(MevSendBundle),
+}
+
+impl SubmitBundle {
+    /// Hashes of our own signed transactions in the bundle.
+    pub fn tx_hashes(&self) -> Vec<TxHash> {
+        match self {
+            Self::Eth(bundle) => bundle.txs.iter().map(keccak256).collect(),
+            Self::Mev(bundle) => bundle
+                .bundle_body
+                .iter()
+                .filter_map(|item| match item {
+                    BundleItem::Tx { tx, .. } => Some(keccak256(tx)),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Bundle hash the relay will return for the bundle, known before it's
+    /// submitted.
+    pub fn bundle_hash(&self) -> B256 {
+        match self {
+            Self::Eth(bundle) => eth_bundle_hash(bundle),
+            Self::Mev(bundle) => mev_bundle_hash(bundle),
+        }
+    }
+
+    /// Identifies the bundle by its transactions, independently of the
+    /// block it targets.
+    pub fn id(&self) -> B256 {
+        let hashes = match self {
+            Self::Eth(_) => self.tx_hashes(),
+            Self::Mev(bundle) => bundle
+                .bundle_body
+                .iter()
+                .filter_map(|item| match item {
+                    BundleItem::Hash { hash } => Some(*hash),
+                    BundleItem::Tx { tx, .. } => Some(keccak256(tx)),
+                    _ => None,
+                })
+                .collect(),
+        };
+        keccak256(hashes.iter().flat_map(|hash| hash.0).collect::<Vec<_>>())
+    }
+
+    /// Block the bundle targets, the first one for `mev_sendBundle` bundles.
+    pub fn target_block(&self) -> BlockNumber {
+        match self {
+            Self::Eth(bundle) => bundle.block_number,
+            Self::Mev(bundle) => bundle.inclusion.block,
+        }
+    }
+
+    /// Returns the same bundle targeting the given block.
+    pub fn retarget(&self, block: BlockNumber) -> Self {
+        match self {
+            Self::Eth(bundle) => Self::Eth(EthSendBundle {
+                block_number: block,
+                ..bundle.clone()
+            }),
+            Self::Mev(bundle) => {
+                let mut bundle = bundle.clone();
+                bundle.inclusion.block = block;
+                Self::Mev(bundle)
+            }
+        }
+    }
+}
+
+/// Bundle submitted on behalf of a strategy, whose PnL it is booked under.
+#[derive(Clone, Debug)]
+pub struct AttributedBundle {
+    pub bundle: SubmitBundle,
+    pub origin: BundleOrigin,
+}
+
+impl AttributedBundle {
+    pub fn new(bundle: SubmitBundle, origin: BundleOrigin) -> Self {
+        Self { bundle, origin }
+    }
+}
+
+/// Cancels a previously submitted `eth_sendBundle` bundle.
+///
+/// `mev_sendBundle` bundles can't be cancelled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CancelBundle {
+    /// By the replacement UUID the bundle was submitted with.
+    ReplacementUuid(String),
+    /// By the bundle [id](SubmitBundle::id), using the replacement UUID
+    /// assigned at submit time.
+    Id(B256),
+}
+
+/// Submits or cancels a bundle.
+#[derive(Clone, Debug)]
+pub enum BundleAction {
+    Submit(SubmitBundle),
+    Cancel(CancelBundle),
+}
+
+/// Replacement UUID a bundle has been submitted with.
+#[derive(Clone, Debug)]
+struct Replacement {
+    uuid: String,
+    /// Block targeted by the last submission of the bundle.
+    target_block: BlockNumber,
+}
+
+/// Signs bundles with a Flashbots signer and submits them to a relay.
+///
+/// `eth_sendBundle` bundles without a replacement UUID get one assigned, so
+/// that they can be [cancelled](CancelBundle) later. Re-submissions of the
+/// same bundle, e.g. by a
+/// [ResubmittingExecutor](super::resubmitting_executor::ResubmittingExecutor),
+/// reuse its UUID, so that they replace each other.
+pub struct FlashbotsBundleExecutor {
+    /// Relay URL, reported in submission errors.
+    relay: String,
+    eth_client: Box<dyn EthBundleApiClient + Send + Sync>,
+    mev_client: Box<dyn MevApiClient + Send + Sync>,
+    /// Whether to actually submit bundles or just log them.
+    dry_run: bool,
+    /// Receives submitted bundles to track their outcome.
+    bundle_tracker: Option<BundleTracker>,
+    /// Records submitted bundles under their origin.
+    pnl_ledger: Option<PnlLedger>,
+    /// Records submitted `mev_sendBundle` bundles and relay responses.
+    bundle_store: Option<Arc<dyn BundleStore>>,
+    /// Replacement UUIDs of submitted bundles by their ids, until the
+    /// blocks they target have passed.
+    replacement_uuids: Mutex<HashMap<B256, Replacement>>,
+}
+
+impl FlashbotsBundleExecutor {
+    /// Creates an executor for the given relay, e.g. [FLASHBOTS_RELAY_URL].
+    /// Requests are signed with the `signer`, which is used as the searcher
+    /// identity and does not need to hold any funds.
+    pub fn new(
+        url: String,
+        signer: impl Signer + Clone + Send + Sync + 'static,
+    ) -> Self {
+        let client = signed_client(url.clone(), signer);
+        Self::from_client(url, client)
+    }
+
+    /// Creates an executor submitting bundles via the given client, e.g. a
+    /// [MultiRelayClient](kazuka_mev_share::rpc::MultiRelayClient), whose
+    /// relay is named `relay` in submission errors.
+    pub fn from_client(
+        relay: impl Into<String>,
+        client: impl EthBundleApiClient
+        + MevApiClient
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        Self {
+            relay: relay.into(),
+            eth_client: Box::new(client.clone()),
+            mev_client: Box::new(client),
+            dry_run: false,
+            bundle_tracker: None,
+            pnl_ledger: None,
+            bundle_store: None,
+            replacement_uuids: Default::default(),
+        }
+    }
+
+    /// Only logs bundles instead of submitting them.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Feeds every successfully submitted bundle into the given tracker.
+    pub fn with_bundle_tracker(
+        mut self,
+        bundle_tracker: BundleTracker,
+    ) -> Self {
+        self.bundle_tracker = Some(bundle_tracker);
+        self
+    }
+
+    /// Records every successfully submitted [AttributedBundle] in the
+    /// ledger, as a submission of its strategy.
+    pub fn with_pnl_ledger(mut self, pnl_ledger: PnlLedger) -> Self {
+        self.pnl_ledger = Some(pnl_ledger);
+        self
+    }
+
+    /// Records every submitted `mev_sendBundle` bundle in the store, along
+    /// with its origin and the response of the relay, successful or not.
+    /// Executors of several relays may share the store.
+    pub fn with_bundle_store(mut self, store: Arc<dyn BundleStore>) -> Self {
+        self.bundle_store = Some(store);
+        self
+    }
+
+    /// Submits the bundle and returns its hash.
+    pub async fn submit(
+        &self,
+        bundle: SubmitBundle,
+    ) -> Result<B256, KazukaError> {
+        self.submit_with_origin(bundle, None).await
+    }
+
+    /// Submits the bundle of a strategy and returns its hash. The bundle is
+    /// tracked and booked under its origin.
+    pub async fn submit_attributed(
+        &self,
+        bundle: AttributedBundle,
+    ) -> Result<B256, KazukaError> {
+        self.submit_with_origin(bundle.bundle, Some(bundle.origin))
+            .await
+    }
+
+    async fn submit_with_origin(
+        &self,
+        bundle: SubmitBundle,
+        origin: Option<BundleOrigin>,
+    ) -> Result<B256, KazukaError> {
+        let bundle_id = bundle.id();
+        let (bundle_hash, tracked) = match bundle {
+            SubmitBundle::Eth(mut bundle) => {
+                let replacement_uuid = bundle
+                    .replacement_uuid
+                    .get_or_insert_with(|| {
+                        self.replacement_uuid(&bundle_id)
+                            .unwrap_or_else(|| Uuid::new_v4().to_string())
+                    })
+                    .clone();
+                let response =
+                    self.eth_client.send_bundle(bundle.clone()).await.map_err(
+                        |e| KazukaError::bundle_submission(&self.relay, e),
+                    )?;
+                self.insert_replacement_uuid(
+                    bundle_id,
+                    replacement_uuid,
+                    bundle.block_number,
+                );
+                let tracked = TrackedBundle::from_eth_bundle(
+                    response.bundle_hash,
+                    &bundle,
+                );
+                (response.bundle_hash, tracked)
+            }
+            SubmitBundle::Mev(bundle) => {
+                let result = self.mev_client.send_bundle(bundle.clone()).await;
+                self.store_submission(
+                    &bundle,
+                    origin.as_ref(),
+                    result
+                        .as_ref()
+                        .map(|response| response.bundle_hash)
+                        .map_err(|e| e.to_string()),
+                )
+                .await;
+                let response = result.map_err(|e| {
+                    KazukaError::bundle_submission(&self.relay, e)
+                })?;
+                let tracked = TrackedBundle::from_mev_bundle(
+                    response.bundle_hash,
+                    &bundle,
+                );
+                (response.bundle_hash, tracked)
+            }
+        };
+        if let (Some(pnl_ledger), Some(origin)) = (&self.pnl_ledger, &origin) {
+            origin.record(pnl_ledger, PnlEventKind::Submitted);
+        }
+        if let Some(bundle_tracker) = &self.bundle_tracker {
+            bundle_tracker.track(TrackedBundle { origin, ..tracked });
+        }
+        Ok(bundle_hash)
+    }
+
+    /// Records the bundle and the response of the relay in the store, if
+    /// any. Store errors are only logged.
+    async fn store_submission(
+        &self,
+        bundle: &MevSendBundle,
+        origin: Option<&BundleOrigin>,
+        response: Result<B256, String>,
+    ) {
+        let Some(bundle_store) = &self.bundle_store else {
+            return;
+        };
+        let bundle_hash = mev_bundle_hash(bundle);
+        let mut record = BundleRecord::new(bundle_hash, bundle.clone());
+        if let Some(origin) = origin {
+            record = record
+                .with_strategy(origin.strategy.clone())
+                .with_profit(origin.expected_profit);
+        }
+        let (relay_bundle_hash, error) = match response {
+            Ok(bundle_hash) => (Some(bundle_hash), None),
+            Err(e) => (None, Some(e)),
+        };
+        let submission = RelaySubmission {
+            relay: self.relay.clone(),
+            bundle_hash: relay_bundle_hash,
+            error,
+            submitted_at: store::now(),
+        };
+        let result = async {
+            bundle_store.insert_if_absent(record).await?;
+            bundle_store
+                .record_relay_submission(bundle_hash, submission)
+                .await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!(
+                ?bundle_hash,
+                "Error recording bundle: {}",
+                e
+            );
+        }
+    }
+
+    /// Returns the replacement UUID the bundle with the given id was
+    /// submitted with.
+    pub fn replacement_uuid(&self, bundle_id: &B256) -> Option<String> {
+        self.replacement_uuids
+            .lock()
+            .unwrap()
+            .get(bundle_id)
+            .map(|replacement| replacement.uuid.clone())
+    }
+
+    /// Remembers the replacement UUID of a submitted bundle, forgetting the
+    /// bundles targeting blocks before it, which can't be included anymore.
+    fn insert_replacement_uuid(
+        &self,
+        bundle_id: B256,
+        uuid: String,
+        target_block: BlockNumber,
+    ) {
+        let mut replacement_uuids = self.replacement_uuids.lock().unwrap();
+        replacement_uuids
+            .retain(|_, replacement| replacement.target_block >= target_block);
+        replacement_uuids.insert(
+            bundle_id,
+            Replacement { uuid, target_block },
+        );
+    }
+
+    /// Cancels a previously submitted bundle via `eth_cancelBundle`.
+    pub async fn cancel(
+        &self,
+        cancel: CancelBundle,
+    ) -> Result<(), KazukaError> {
+        let (replacement_uuid, bundle_id) = match cancel {
+            CancelBundle::ReplacementUuid(replacement_uuid) => {
+                (replacement_uuid, None)
+            }
+            CancelBundle::Id(bundle_id) => {
+                let replacement_uuid =
+                    self.replacement_uuid(&bundle_id).ok_or_else(|| {
+                        KazukaError::BundleCancellationError(format!(
+                            "no replacement UUID for bundle {bundle_id}"
+                        ))
+                    })?;
+                (replacement_uuid, Some(bundle_id))
+            }
+        };
+        self.eth_client.cancel_by_uuid(&replacement_uuid).await?;
+        // The UUID is kept until the cancellation succeeds, so that a failed
+        // one can be retried.
+        if let Some(bundle_id) = bundle_id {
+            self.replacement_uuids.lock().unwrap().remove(&bundle_id);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Executor<SubmitBundle> for FlashbotsBundleExecutor {
+    /// Sends a bundle to the relay.
+    #[instrument(
+        skip(self),
+        fields(
+            bundle_hash = %action.bundle_hash(),
+            target_block = action.target_block()
+        )
+    )]
+    async fn execute(&self, action: SubmitBundle) -> Result<(), KazukaError> {
+        if self.dry_run {
+            tracing::info!(
+                "Dry run, skipping bundle submission: {:?}",
+                action
+            );
+            return Ok(());
+        }
+        let bundle_hash = self.submit(action).await?;
+        tracing::info!(
+            ?bundle_hash,
+            monotonic_counter.kazuka_bundles_submitted = 1_u64,
+            "Bundle submitted"
+        );
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Executor<AttributedBundle> for FlashbotsBundleExecutor {
+    /// Sends a bundle of a strategy to the relay.
+    #[instrument(
+        skip(self),
+        fields(
+            bundle_hash = %action.bundle.bundle_hash(),
+            target_block = action.bundle.target_block(),
+            strategy = %action.origin.strategy
+        )
+    )]
+    async fn execute(
+        &self,
+        action: AttributedBundle,
+    ) -> Result<(), KazukaError> {
+        if self.dry_run {
+            tracing::info!(
+                "Dry run, skipping bundle submission: {:?}",
+                action
+            );
+            return Ok(());
+        }
+        let bundle_hash = self.submit_attributed(action).await?;
+        tracing::info!(
+            ?bundle_hash,
+            monotonic_counter.kazuka_bundles_submitted = 1_u64,
+            "Bundle submitted"
+        );
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Executor<BundleAction> for FlashbotsBundleExecutor {
+    /// Sends or cancels a bundle.
+    #[instrument(skip(self))]
+    async fn execute(&self, action: BundleAction) -> Result<(), KazukaError> {
+        match action {
+            BundleAction::Submit(bundle) => {
+                Executor::<SubmitBundle>::execute(self, bundle).await
+            }
+            BundleAction::Cancel(cancel) => {
+                if self.dry_run {
+                    tracing::info!(
+                        "Dry run, skipping bundle cancellation: {:?}",
+                        cancel
+                    );
+                    return Ok(());
+                }
+                self.cancel(cancel).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{Bytes, U256},
+        rpc::types::mev::Inclusion,
+        signers::local::PrivateKeySigner,
+    };
+    use kazuka_mev_share::backend::store::InMemoryBundleStore;
+
+    use super::*;
+
+    #[test]
+    fn test_retarget_keeps_bundle_id() {
+        let bundle = SubmitBundle::Eth(EthSendBundle {
+            txs: vec![Bytes::from_static(b"tx")],
+            block_number: 100,
+            ..Default::default()
+        });
+
+        let retargeted = bundle.retarget(101);
+
+        let SubmitBundle::Eth(eth_bundle) = &retargeted else {
+            panic!("Expected eth_sendBundle");
+        };
+        assert_eq!(eth_bundle.block_number, 101);
+        assert_eq!(retargeted.id(), bundle.id());
+        assert_eq!(
+            retargeted.tx_hashes(),
+            vec![keccak256(b"tx")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_unknown_bundle() {
+        let executor = FlashbotsBundleExecutor::new(
+            "http://127.0.0.1:1".to_string(),
+            PrivateKeySigner::random(),
+        );
+
+        let result = executor.cancel(CancelBundle::Id(B256::ZERO)).await;
+
+        assert!(matches!(
+            result,
+            Err(KazukaError::BundleCancellationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_cancellation_keeps_replacement_uuid() {
+        let executor = FlashbotsBundleExecutor::new(
+            "http://127.0.0.1:1".to_string(),
+            PrivateKeySigner::random(),
+        );
+        let bundle_id = B256::repeat_byte(1);
+        executor.insert_replacement_uuid(bundle_id, "uuid".to_string(), 100);
+
+        let result = executor.cancel(CancelBundle::Id(bundle_id)).await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            executor.replacement_uuid(&bundle_id).as_deref(),
+            Some("uuid")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_submission_is_forgotten() {
+        let pnl_ledger = PnlLedger::new();
+        let executor = FlashbotsBundleExecutor::new(
+            "http://127.0.0.1:1".to_string(),
+            PrivateKeySigner::random(),
+        )
+        .with_pnl_ledger(pnl_ledger.clone());
+        let bundle = SubmitBundle::Eth(EthSendBundle {
+            txs: vec![Bytes::from_static(b"tx")],
+            block_number: 100,
+            ..Default::default()
+        });
+
+        assert!(
+            executor
+                .submit_attributed(AttributedBundle::new(
+                    bundle.clone(),
+                    BundleOrigin::new("arb", U256::from(1)),
+                ))
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            executor.replacement_uuid(&bundle.id()),
+            None
+        );
+        assert_eq!(
+            pnl_ledger.strategy("arb").submissions,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_submission_is_recorded() {
+        let bundle_store = Arc::new(InMemoryBundleStore::new());
+        let executor = FlashbotsBundleExecutor::new(
+            "http://127.0.0.1:1".to_string(),
+            PrivateKeySigner::random(),
+        )
+        .with_bundle_store(bundle_store.clone());
+        let bundle = MevSendBundle {
+            protocol_version: Default::default(),
+            inclusion: Inclusion {
+                block: 100,
+                max_block: None,
+            },
+            bundle_body: vec![BundleItem::Tx {
+                tx: Bytes::from_static(b"tx"),
+                can_revert: false,
+            }],
+            validity: None,
+            privacy: None,
+        };
+
+        assert!(
+            executor
+                .submit_attributed(AttributedBundle::new(
+                    SubmitBundle::Mev(bundle.clone()),
+                    BundleOrigin::new("arb", U256::from(1)),
+                ))
+                .await
+                .is_err()
+        );
+
+        let record = bundle_store
+            .get(mev_bundle_hash(&bundle))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.strategy.as_deref(), Some("arb"));
+        assert_eq!(record.profit, Some(U256::from(1)));
+        let [submission] = record.relay_submissions.as_slice() else {
+            panic!("Expected a single relay submission");
+        };
+        assert_eq!(submission.relay, "http://127.0.0.1:1");
+        assert_eq!(submission.bundle_hash, None);
+        assert!(submission.error.is_some());
+    }
+
+    #[test]
+    fn test_past_replacement_uuids_are_pruned() {
+        let executor = FlashbotsBundleExecutor::new(
+            "http://127.0.0.1:1".to_string(),
+            PrivateKeySigner::random(),
+        );
+        let (first, second, third) = (
+            B256::repeat_byte(1),
+            B256::repeat_byte(2),
+            B256::repeat_byte(3),
+        );
+        executor.insert_replacement_uuid(first, "a".to_string(), 100);
+        executor.insert_replacement_uuid(second, "b".to_string(), 101);
+        executor.insert_replacement_uuid(third, "c".to_string(), 101);
+
+        assert_eq!(executor.replacement_uuid(&first), None);
+        assert_eq!(
+            executor.replacement_uuid(&second),
+            Some("b".to_string())
+        );
+        assert_eq!(
+            executor.replacement_uuid(&third),
+            Some("c".to_string())
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::{sync::Arc, time::Duration};
+
+use alloy::{
+    network::AnyNetwork,
+    primitives::{Address, TxHash, U256},
+    providers::{DynProvider, Provider},
+    rpc::types::TransactionRequest,
+    serde::WithOtherFields,
+};
+use tokio::{
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+
+/// Gas used by a plain ETH transfer, which is what a cancellation is.
+const TRANSFER_GAS_LIMIT: u64 = 21_000;
+
+/// Minimum fee bump most nodes require to accept a replacement transaction.
+const MIN_BUMP_PERCENTAGE: u128 = 10;
+
+/// Configures how transactions that are not mined in time get their fees
+/// bumped and, once the fee cap is reached, get cancelled.
+#[derive(Clone, Debug)]
+pub struct GasEscalation {
+    /// Maximum gas price (in wei) replacement transactions are sent with.
+    max_gas_price: u128,
+    /// Number of blocks to wait for the transaction to be mined before
+    /// bumping its fee.
+    blocks_until_bump: u64,
+    /// Fee increase of every replacement, as a percentage (e.g., 12 means
+    /// 12%).
+    bump_percentage: u128,
+    /// How often the chain is polled for the receipt.
+    poll_interval: Duration,
+}
+
+impl GasEscalation {
+    pub fn new(max_gas_price: u128) -> Self {
+        Self {
+            max_gas_price,
+            blocks_until_bump: 3,
+            bump_percentage: 12,
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+
+    /// Sets the number of blocks to wait before bumping the fee.
+    pub fn with_blocks_until_bump(mut self, blocks_until_bump: u64) -> Self {
+        self.blocks_until_bump = blocks_until_bump;
+        self
+    }
+
+    /// Sets the fee increase of every replacement, as a percentage.
+    pub fn with_bump_percentage(mut self, bump_percentage: u128) -> Self {
+        assert!(
+            bump_percentage >= MIN_BUMP_PERCENTAGE,
+            "Replacement transactions need at least a {MIN_BUMP_PERCENTAGE}% fee bump"
+        );
+        self.bump_percentage = bump_percentage;
+        self
+    }
+
+    /// Sets how often the chain is polled for the receipt.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Watches the submitted transaction in the background until it (or one
+    /// of its replacements) is mined, escalating its fee every
+    /// `blocks_until_bump` blocks.
+    ///
+    /// When the bumped fee would exceed the cap, a zero-value self-transfer
+    /// with the same nonce is sent instead, which frees the nonce for the
+    /// cost of a plain transfer.
+    pub(crate) fn watch(
+        self,
+        provider: Arc<DynProvider<AnyNetwork>>,
+        tx: WithOtherFields<TransactionRequest>,
+        tx_hash: TxHash,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move { self.escalate(provider, tx, tx_hash).await })
+    }
+
+    async fn escalate(
+        self,
+        provider: Arc<DynProvider<AnyNetwork>>,
+        mut tx: WithOtherFields<TransactionRequest>,
+        tx_hash: TxHash,
+    ) {
+        let (Some(from), Some(nonce)) = (tx.from, tx.nonce) else {
+            tracing::warn!(
+                ?tx_hash,
+                "Can't escalate a transaction without `from` and nonce"
+            );
+            return;
+        };
+
+        let mut gas_price = tx.gas_price.unwrap_or_default();
+        let mut tx_hashes = vec![tx_hash];
+        let mut bump_at = None;
+        let mut cancelled = false;
+
+        let mut interval = time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            for tx_hash in &tx_hashes {
+                match provider.get_transaction_receipt(*tx_hash).await {
+                    Ok(Some(_)) => {
+                        tracing::info!(
+                            ?tx_hash,
+                            cancelled,
+                            "Transaction mined"
+                        );
+                        return;
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        tracing::warn!(?tx_hash, "Error getting receipt: {}", e)
+                    }
+                }
+            }
+
+            let block_number = match provider.get_block_number().await {
+                Ok(block_number) => block_number,
+                Err(e) => {
+                    tracing::warn!("Error getting block number: {}", e);
+                    continue;
+                }
+            };
+            let bump_at =
+                bump_at.get_or_insert(block_number + self.blocks_until_bump);
+            if block_number < *bump_at {
+                continue;
+            }
+            if cancelled {
+                tracing::error!(
+                    ?from,
+                    nonce,
+                    "Cancellation of the stuck transaction was not mined"
+                );
+                return;
+            }
+
+            gas_price = bump_gas_price(gas_price, self.bump_percentage);
+            let replacement = if gas_price <= self.max_gas_price {
+                tracing::info!(
+                    ?from,
+                    nonce,
+                    gas_price,
+                    "Bumping gas price"
+                );
+                tx.gas_price = Some(gas_price);
+                tx.clone()
+            } else {
+                tracing::warn!(
+                    ?from,
+                    nonce,
+                    gas_price,
+                    "Gas price cap reached, cancelling transaction"
+                );
+                cancelled = true;
+                cancellation(from, nonce, gas_price)
+            };
+
+            match provider.send_transaction(replacement).await {
+                Ok(pending) => tx_hashes.push(*pending.tx_hash()),
+                // Most likely one of the previous transactions got mined.
+                Err(e) => tracing::warn!(
+                    ?from,
+                    nonce,
+                    "Error sending replacement transaction: {}",
+                    e
+                ),
+            }
+            *bump_at = block_number + self.blocks_until_bump;
+        }
+    }
+}
+
+/// Increases the gas price by the given percentage, rounding up.
+fn bump_gas_price(gas_price: u128, bump_percentage: u128) -> u128 {
+    (gas_price * (100 + bump_percentage)).div_ceil(100)
+}
+
+/// Zero-value self-transfer replacing the transaction with the given nonce.
+fn cancellation(
+    from: Address,
+    nonce: u64,
+    gas_price: u128,
+) -> WithOtherFields<TransactionRequest> {
+    WithOtherFields::new(
+        TransactionRequest::default()
+            .from(from)
+            .to(from)
+            .value(U256::ZERO)
+            .nonce(nonce)
+            .gas_limit(TRANSFER_GAS_LIMIT)
+            .gas_price(gas_price),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_gas_price() {
+        assert_eq!(bump_gas_price(100, 12), 112);
+        // Rounds up, so that the bump is never below the percentage.
+        assert_eq!(bump_gas_price(101, 10), 112);
+        assert_eq!(bump_gas_price(0, 10), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bump_percentage_below_minimum() {
+        let _ = GasEscalation::new(1).with_bump_percentage(5);
+    }
+}
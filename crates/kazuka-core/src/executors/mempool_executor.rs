@@ -4,24 +4,73 @@ use std::{
 };
 
 use alloy::{
-    network::{AnyNetwork, TransactionBuilder},
+    consensus::BlobTransactionSidecar,
+    network::{AnyNetwork, TransactionBuilder, TransactionBuilder4844},
     primitives::U128,
-    providers::{DynProvider, Provider},
+    providers::{DynProvider, Provider, ProviderBuilder},
     rpc::types::TransactionRequest,
     serde::WithOtherFields,
 };
 use async_trait::async_trait;
 use tracing::instrument;
 
-use crate::{error::KazukaError, types::Executor};
+use crate::{
+    error::KazukaError,
+    executors::{gas_escalation::GasEscalation, nonce_manager::NonceManager},
+    signer::SignerProvider,
+    types::Executor,
+};
+
+/// Multiple of the current blob base fee used as `max_fee_per_blob_gas`,
+/// which keeps blob transactions includable while the blob base fee rises.
+const BLOB_BASE_FEE_MULTIPLIER: u128 = 2;
 
 pub struct MempoolExecutor {
     provider: Arc<DynProvider<AnyNetwork>>,
+    nonce_manager: Option<NonceManager>,
+    gas_escalation: Option<GasEscalation>,
 }
 
 impl MempoolExecutor {
     pub fn new(provider: Arc<DynProvider<AnyNetwork>>) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            nonce_manager: None,
+            gas_escalation: None,
+        }
+    }
+
+    /// Allocates nonces of transactions, which have `from` set, but no
+    /// nonce, through the given (possibly shared) [NonceManager].
+    pub fn with_nonce_manager(mut self, nonce_manager: NonceManager) -> Self {
+        self.nonce_manager = Some(nonce_manager);
+        self
+    }
+
+    /// Signs transactions (and their replacements) with the wallet of the
+    /// `signers`, instead of the one of the provider, if any. Transactions
+    /// without `from` are sent from the transactions signer.
+    pub fn with_signers(
+        mut self,
+        signers: &dyn SignerProvider,
+    ) -> Result<Self, KazukaError> {
+        let provider = ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .wallet(signers.wallet()?)
+            .connect_provider(self.provider.as_ref().clone());
+        self.provider = Arc::new(DynProvider::new(provider));
+        Ok(self)
+    }
+
+    /// Watches submitted transactions and replaces the ones that are not
+    /// mined in time with bumped fees, see [GasEscalation].
+    /// Only applies to transactions, which have `from` set.
+    pub fn with_gas_escalation(
+        mut self,
+        gas_escalation: GasEscalation,
+    ) -> Self {
+        self.gas_escalation = Some(gas_escalation);
+        self
     }
 }
 
@@ -38,6 +87,10 @@ pub struct GasBidInfo {
 pub struct SubmitTxToMempool {
     pub tx: WithOtherFields<TransactionRequest>,
     pub gas_bid_info: Option<GasBidInfo>,
+    /// Blobs to attach, which turns the transaction into an EIP-4844 blob
+    /// transaction.
+    /// See: https://eips.ethereum.org/EIPS/eip-4844
+    pub blob_sidecar: Option<BlobTransactionSidecar>,
 }
 
 #[async_trait]
@@ -48,12 +101,41 @@ impl Executor<SubmitTxToMempool> for MempoolExecutor {
         &self,
         action: SubmitTxToMempool,
     ) -> Result<(), KazukaError> {
-        let mut tx = action.tx.clone();
+        let mut tx = action.tx;
+        if let Some(blob_sidecar) = action.blob_sidecar {
+            tx.set_blob_sidecar(blob_sidecar);
+        }
+        let allocated_nonce = match (&self.nonce_manager, tx.from, tx.nonce) {
+            (Some(nonce_manager), Some(from), None) => {
+                let nonce = nonce_manager.next_nonce(from).await?;
+                tx.set_nonce(nonce);
+                Some((nonce_manager, from, nonce))
+            }
+            _ => None,
+        };
+
+        let result = self.send(tx, action.gas_bid_info).await;
+        if let Some((nonce_manager, from, nonce)) = allocated_nonce {
+            match result {
+                Ok(()) => nonce_manager.confirm(from, nonce).await,
+                Err(_) => nonce_manager.release(from, nonce).await,
+            }
+        }
+        result
+    }
+}
+
+impl MempoolExecutor {
+    async fn send(
+        &self,
+        mut tx: WithOtherFields<TransactionRequest>,
+        gas_bid_info: Option<GasBidInfo>,
+    ) -> Result<(), KazukaError> {
         // Expected actual gas usage for the transaction.
-        let gas_usage = self.provider.estimate_gas(action.tx).await?;
+        let gas_usage = self.provider.estimate_gas(tx.clone()).await?;
 
         let bid_gas_price: U128;
-        if let Some(gas_bid_info) = action.gas_bid_info {
+        if let Some(gas_bid_info) = gas_bid_info {
             // Gas price at which we'd break even, meaning 100% of profit goes
             // to validator (the entire profit will be spent on gas).
             // This is the maximum gas price we can set without going negative.
@@ -87,8 +169,52 @@ impl Executor<SubmitTxToMempool> for MempoolExecutor {
             bid_gas_price = U128::from(self.provider.get_gas_price().await?);
         }
 
+        if tx.has_blob_sidecar() {
+            self.price_blob_tx(&mut tx, bid_gas_price.to()).await?;
+            // Blob transactions can only be replaced with at least double
+            // the blob fee, so they are not escalated.
+            let _ = self.provider.send_transaction(tx).await?;
+            return Ok(());
+        }
         tx.set_gas_price(bid_gas_price.to());
-        let _ = self.provider.send_transaction(tx).await?;
+
+        let Some(gas_escalation) = &self.gas_escalation else {
+            let _ = self.provider.send_transaction(tx).await?;
+            return Ok(());
+        };
+        // Replacements must reuse the nonce, so it has to be known upfront.
+        if let (Some(from), None) = (tx.from, tx.nonce) {
+            let nonce =
+                self.provider.get_transaction_count(from).pending().await?;
+            tx.set_nonce(nonce);
+        }
+        let pending = self.provider.send_transaction(tx.clone()).await?;
+        gas_escalation.clone().watch(
+            Arc::clone(&self.provider),
+            tx,
+            *pending.tx_hash(),
+        );
+        Ok(())
+    }
+
+    /// Blob transactions are EIP-1559 transactions, so the bid is used as
+    /// both the max fee and the max priority fee, which results in paying
+    /// exactly the bid per unit of gas, just like a legacy gas price.
+    /// Unless set explicitly, the blob gas fee is capped at a multiple of
+    /// the current blob base fee.
+    async fn price_blob_tx(
+        &self,
+        tx: &mut WithOtherFields<TransactionRequest>,
+        bid_gas_price: u128,
+    ) -> Result<(), KazukaError> {
+        tx.set_max_fee_per_gas(bid_gas_price);
+        tx.set_max_priority_fee_per_gas(bid_gas_price);
+        if tx.max_fee_per_blob_gas.is_none() {
+            let blob_base_fee = self.provider.get_blob_base_fee().await?;
+            tx.set_max_fee_per_blob_gas(
+                blob_base_fee.saturating_mul(BLOB_BASE_FEE_MULTIPLIER),
+            );
+        }
         Ok(())
     }
 }
@@ -1 +1,12 @@
+pub mod action_dedup;
+pub mod batching_executor;
+pub mod flashbots_bundle_executor;
+pub mod gas_escalation;
 pub mod mempool_executor;
+pub mod multi_relay_executor;
+pub mod nonce_manager;
+pub mod notification_executor;
+pub mod resubmitting_executor;
+pub mod retry_executor;
+pub mod simulation_gated_executor;
+pub mod timeout_executor;
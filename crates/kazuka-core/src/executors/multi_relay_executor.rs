@@ -0,0 +1,214 @@
+use alloy::{primitives::B256, signers::Signer};
+use async_trait::async_trait;
+use kazuka_mev_share::rpc::MultiRelayClient;
+use tracing::instrument;
+
+use crate::{
+    error::KazukaError,
+    event_sources::bundle_stats_event_source::BundleTracker,
+    executors::flashbots_bundle_executor::{
+        BundleAction, CancelBundle, FLASHBOTS_RELAY_URL,
+        FlashbotsBundleExecutor, SubmitBundle, signed_client,
+    },
+    types::Executor,
+};
+
+/// Relay or builder endpoint accepting bundles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relay {
+    pub name: String,
+    pub url: String,
+}
+
+impl Relay {
+    pub fn new(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            url: url.into(),
+        }
+    }
+
+    /// Well-known Ethereum mainnet relays and builders.
+    ///
+    /// Note that only Flashbots supports `mev_sendBundle`, the others only
+    /// accept `eth_sendBundle`.
+    pub fn mainnet() -> Vec<Self> {
+        vec![
+            Self::new("flashbots", FLASHBOTS_RELAY_URL),
+            Self::new(
+                "beaverbuild",
+                "https://rpc.beaverbuild.org",
+            ),
+            Self::new("titan", "https://rpc.titanbuilder.xyz"),
+            Self::new("rsync", "https://rsync-builder.xyz"),
+        ]
+    }
+}
+
+/// Submits the same bundle concurrently to multiple relays/builders, which
+/// increases the probability of inclusion.
+///
+/// Bundles are broadcast by a [MultiRelayClient], and submitted and
+/// cancelled like by a [FlashbotsBundleExecutor], sharing its replacement
+/// UUIDs across the relays.
+pub struct MultiRelayExecutor {
+    client: MultiRelayClient,
+    executor: FlashbotsBundleExecutor,
+}
+
+impl MultiRelayExecutor {
+    /// Creates an executor for the given relays, all of which receive
+    /// requests signed with the same Flashbots `signer`.
+    pub fn new(
+        relays: Vec<Relay>,
+        signer: impl Signer + Clone + Send + Sync + 'static,
+    ) -> Self {
+        let client = relays.into_iter().fold(
+            MultiRelayClient::new(),
+            |client, relay| {
+                client.with_relay(
+                    relay.name,
+                    signed_client(relay.url, signer.clone()),
+                )
+            },
+        );
+        Self::from_client(client)
+    }
+
+    /// Creates an executor for the relays of the given client, e.g. with
+    /// custom configured relay clients.
+    pub fn from_client(client: MultiRelayClient) -> Self {
+        let relays = client.relays().collect::<Vec<_>>().join(", ");
+        Self {
+            executor: FlashbotsBundleExecutor::from_client(
+                relays,
+                client.clone(),
+            ),
+            client,
+        }
+    }
+
+    /// Feeds every bundle accepted by any relay into the given tracker.
+    pub fn with_bundle_tracker(
+        mut self,
+        bundle_tracker: BundleTracker,
+    ) -> Self {
+        self.executor = self.executor.with_bundle_tracker(bundle_tracker);
+        self
+    }
+
+    /// Names of the configured relays.
+    pub fn relays(&self) -> impl Iterator<Item = &str> {
+        self.client.relays()
+    }
+
+    /// Submits the bundle to all relays concurrently and returns its hash,
+    /// if at least one relay has accepted it.
+    pub async fn submit(
+        &self,
+        bundle: SubmitBundle,
+    ) -> Result<B256, KazukaError> {
+        self.executor
+            .submit(bundle)
+            .await
+            .map_err(|e| broadcast_error(self.relays().count(), e))
+    }
+
+    /// Cancels the bundle at all relays concurrently, succeeding if at least
+    /// one relay has cancelled it.
+    pub async fn cancel(
+        &self,
+        cancel: CancelBundle,
+    ) -> Result<(), KazukaError> {
+        self.executor.cancel(cancel).await
+    }
+}
+
+/// Reports a failed broadcast as rejected by all relays. Errors which
+/// happened before the bundle reached the relays are passed through, along
+/// with their [class](crate::error::ErrorClass).
+fn broadcast_error(relays: usize, error: KazukaError) -> KazukaError {
+    match error {
+        KazukaError::BundleSubmissionError { .. } => {
+            KazukaError::BundleRejected(format!(
+                "rejected by all {relays} relays: {error}"
+            ))
+        }
+        error => error,
+    }
+}
+
+#[async_trait]
+impl Executor<SubmitBundle> for MultiRelayExecutor {
+    /// Sends a bundle to all relays.
+    /// Succeeds if at least one relay has accepted it.
+    #[instrument(skip(self))]
+    async fn execute(&self, action: SubmitBundle) -> Result<(), KazukaError> {
+        let bundle_hash = self.submit(action).await?;
+        tracing::info!(
+            ?bundle_hash,
+            monotonic_counter.kazuka_bundles_submitted = 1_u64,
+            "Bundle accepted"
+        );
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Executor<BundleAction> for MultiRelayExecutor {
+    /// Sends or cancels a bundle at all relays.
+    #[instrument(skip(self))]
+    async fn execute(&self, action: BundleAction) -> Result<(), KazukaError> {
+        match action {
+            BundleAction::Submit(bundle) => {
+                Executor::<SubmitBundle>::execute(self, bundle).await
+            }
+            BundleAction::Cancel(cancel) => self.cancel(cancel).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        rpc::types::mev::EthSendBundle, signers::local::PrivateKeySigner,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fails_when_no_relay_accepts_bundle() {
+        let executor = MultiRelayExecutor::new(
+            vec![Relay::new("unreachable", "http://127.0.0.1:1")],
+            PrivateKeySigner::random(),
+        );
+        let bundle = SubmitBundle::Eth(EthSendBundle::default());
+
+        assert_eq!(
+            executor.relays().collect::<Vec<_>>(),
+            vec!["unreachable"]
+        );
+        assert!(matches!(
+            executor.submit(bundle.clone()).await,
+            Err(KazukaError::BundleRejected(_))
+        ));
+
+        assert!(matches!(
+            Executor::<SubmitBundle>::execute(&executor, bundle).await,
+            Err(KazukaError::BundleRejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_passes_through_local_errors() {
+        let error = broadcast_error(
+            2,
+            KazukaError::ConfigError("missing signer".to_string()),
+        );
+
+        assert!(matches!(
+            error,
+            KazukaError::ConfigError(_)
+        ));
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use alloy::{
+    network::AnyNetwork,
+    primitives::Address,
+    providers::{DynProvider, Provider},
+};
+use tokio::sync::Mutex;
+
+use crate::error::KazukaError;
+
+/// Nonce state of a single signer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct SignerNonces {
+    /// Next nonce to allocate, unless a released one can be reused.
+    next: u64,
+    /// Nonces allocated to transactions that are not sent yet.
+    pending: BTreeSet<u64>,
+    /// Nonces released by failed transactions below `next`, which would
+    /// leave a gap unless reused.
+    released: BTreeSet<u64>,
+}
+
+impl SignerNonces {
+    fn new(next: u64) -> Self {
+        Self {
+            next,
+            ..Default::default()
+        }
+    }
+
+    fn allocate(&mut self) -> u64 {
+        let nonce = self.released.pop_first().unwrap_or_else(|| {
+            let nonce = self.next;
+            self.next += 1;
+            nonce
+        });
+        self.pending.insert(nonce);
+        nonce
+    }
+
+    fn release(&mut self, nonce: u64) {
+        if !self.pending.remove(&nonce) {
+            return;
+        }
+        if nonce + 1 == self.next {
+            self.next = nonce;
+            // Released nonces right below the new `next` are no longer gaps.
+            while self.next > 0 && self.released.remove(&(self.next - 1)) {
+                self.next -= 1;
+            }
+        } else {
+            self.released.insert(nonce);
+        }
+    }
+}
+
+/// Tracks and allocates nonces per signer, so that concurrent strategies
+/// sending transactions from the same wallet do not race on them.
+///
+/// The first nonce of a signer is fetched from the pending block. Nonces of
+/// failed transactions should be [released](NonceManager::release), so they
+/// get reused and do not leave a gap.
+#[derive(Clone)]
+pub struct NonceManager {
+    provider: Arc<DynProvider<AnyNetwork>>,
+    signers: Arc<Mutex<HashMap<Address, SignerNonces>>>,
+}
+
+impl NonceManager {
+    pub fn new(provider: Arc<DynProvider<AnyNetwork>>) -> Self {
+        Self {
+            provider,
+            signers: Default::default(),
+        }
+    }
+
+    /// Allocates the next nonce for the given signer.
+    pub async fn next_nonce(
+        &self,
+        signer: Address,
+    ) -> Result<u64, KazukaError> {
+        let mut signers = self.signers.lock().await;
+        if !signers.contains_key(&signer) {
+            let next = self.fetch_nonce(signer).await?;
+            signers.insert(signer, SignerNonces::new(next));
+        }
+        let nonces = signers.get_mut(&signer).expect("Signer is tracked");
+        Ok(nonces.allocate())
+    }
+
+    /// Marks the nonce as consumed by a transaction accepted by the node,
+    /// after which it can no longer be released.
+    pub async fn confirm(&self, signer: Address, nonce: u64) {
+        if let Some(nonces) = self.signers.lock().await.get_mut(&signer) {
+            nonces.pending.remove(&nonce);
+        }
+    }
+
+    /// Releases the nonce of a transaction that failed to be sent, so that
+    /// it can be reused.
+    pub async fn release(&self, signer: Address, nonce: u64) {
+        if let Some(nonces) = self.signers.lock().await.get_mut(&signer) {
+            nonces.release(nonce);
+        }
+    }
+
+    /// Drops the local state of the given signer and re-fetches its nonce
+    /// from the node, e.g. after transactions were sent bypassing the
+    /// manager or got dropped from the mempool.
+    pub async fn resync(&self, signer: Address) -> Result<u64, KazukaError> {
+        let mut signers = self.signers.lock().await;
+        let next = self.fetch_nonce(signer).await?;
+        signers.insert(signer, SignerNonces::new(next));
+        Ok(next)
+    }
+
+    async fn fetch_nonce(&self, signer: Address) -> Result<u64, KazukaError> {
+        let nonce = self
+            .provider
+            .get_transaction_count(signer)
+            .pending()
+            .await?;
+        Ok(nonce)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_sequential_nonces() {
+        let mut nonces = SignerNonces::new(5);
+        assert_eq!(nonces.allocate(), 5);
+        assert_eq!(nonces.allocate(), 6);
+        assert_eq!(nonces.pending, BTreeSet::from([5, 6]));
+    }
+
+    #[test]
+    fn test_release_last_nonce() {
+        let mut nonces = SignerNonces::new(5);
+        let nonce = nonces.allocate();
+        nonces.release(nonce);
+        assert_eq!(nonces, SignerNonces::new(5));
+    }
+
+    #[test]
+    fn test_reuse_released_gap() {
+        let mut nonces = SignerNonces::new(0);
+        let (first, second, third) = (
+            nonces.allocate(),
+            nonces.allocate(),
+            nonces.allocate(),
+        );
+        // The second tx failed, leaving a gap.
+        nonces.release(second);
+        assert_eq!(nonces.allocate(), second);
+
+        nonces.release(second);
+        nonces.release(third);
+        // Both the gap and the last nonce are gone, nothing left to fill.
+        assert!(nonces.released.is_empty());
+        assert_eq!(nonces.next, 1);
+        assert_eq!(nonces.pending, BTreeSet::from([first]));
+    }
+}
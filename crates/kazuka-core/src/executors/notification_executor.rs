@@ -0,0 +1,199 @@
+use std::fmt;
+
+use alloy::primitives::{B256, BlockNumber};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use tracing::instrument;
+
+use crate::{error::KazukaError, types::Executor};
+
+/// Webhook to post notifications to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Webhook {
+    /// Slack incoming webhook URL.
+    /// See: https://api.slack.com/messaging/webhooks
+    Slack { url: String },
+    /// Discord webhook URL.
+    /// See: https://discord.com/developers/docs/resources/webhook#execute-webhook
+    Discord { url: String },
+    /// Telegram bot, posting to the given chat.
+    /// See: https://core.telegram.org/bots/api#sendmessage
+    Telegram { bot_token: String, chat_id: String },
+}
+
+impl Webhook {
+    fn url(&self) -> String {
+        match self {
+            Self::Slack { url } | Self::Discord { url } => url.clone(),
+            Self::Telegram { bot_token, .. } => {
+                format!("https://api.telegram.org/bot{bot_token}/sendMessage")
+            }
+        }
+    }
+
+    fn payload(&self, message: &str) -> Value {
+        match self {
+            Self::Slack { .. } => json!({ "text": message }),
+            Self::Discord { .. } => json!({ "content": message }),
+            Self::Telegram { chat_id, .. } => {
+                json!({ "chat_id": chat_id, "text": message })
+            }
+        }
+    }
+}
+
+/// Event worth notifying operators about.
+/// Use [ExecutorMap](crate::types::ExecutorMap) to select which actions of a
+/// strategy should be turned into notifications.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Notification {
+    BundleSubmitted {
+        bundle_hash: B256,
+    },
+    BundleLanded {
+        bundle_hash: B256,
+        block_number: BlockNumber,
+    },
+    ErrorThresholdExceeded {
+        errors: u64,
+        threshold: u64,
+    },
+    Message(String),
+}
+
+impl fmt::Display for Notification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BundleSubmitted { bundle_hash } => {
+                write!(f, "Bundle submitted: {bundle_hash}")
+            }
+            Self::BundleLanded {
+                bundle_hash,
+                block_number,
+            } => write!(
+                f,
+                "Bundle {bundle_hash} landed in block {block_number}"
+            ),
+            Self::ErrorThresholdExceeded { errors, threshold } => write!(
+                f,
+                "Error threshold exceeded: {errors} errors (threshold: {threshold})"
+            ),
+            Self::Message(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Posts formatted [notifications](Notification) to a webhook.
+pub struct NotificationExecutor {
+    webhook: Webhook,
+    /// Prepended to every message, e.g. the bot instance name.
+    prefix: Option<String>,
+    client: reqwest::Client,
+}
+
+impl NotificationExecutor {
+    pub fn new(webhook: Webhook) -> Self {
+        Self {
+            webhook,
+            prefix: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Prepends the given prefix to every message.
+    pub fn with_prefix(mut self, prefix: String) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    fn format(&self, notification: &Notification) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("[{prefix}] {notification}"),
+            None => notification.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Executor<Notification> for NotificationExecutor {
+    /// Posts the notification to the webhook.
+    #[instrument(skip(self))]
+    async fn execute(&self, action: Notification) -> Result<(), KazukaError> {
+        let message = self.format(&action);
+        self.client
+            .post(self.webhook.url())
+            .json(&self.webhook.payload(&message))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            // Webhook URLs carry secrets, e.g. the Telegram bot token,
+            // which must not end up in the logs.
+            .map_err(reqwest::Error::without_url)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_payloads() {
+        let slack = Webhook::Slack {
+            url: "https://hooks.slack.com/services/T/B/X".to_string(),
+        };
+        assert_eq!(
+            slack.payload("hi"),
+            json!({ "text": "hi" })
+        );
+
+        let discord = Webhook::Discord {
+            url: "https://discord.com/api/webhooks/1/x".to_string(),
+        };
+        assert_eq!(
+            discord.payload("hi"),
+            json!({ "content": "hi" })
+        );
+
+        let telegram = Webhook::Telegram {
+            bot_token: "123:abc".to_string(),
+            chat_id: "42".to_string(),
+        };
+        assert_eq!(
+            telegram.url(),
+            "https://api.telegram.org/bot123:abc/sendMessage"
+        );
+        assert_eq!(
+            telegram.payload("hi"),
+            json!({ "chat_id": "42", "text": "hi" })
+        );
+    }
+
+    #[test]
+    fn test_format_with_prefix() {
+        let executor =
+            NotificationExecutor::new(Webhook::Slack { url: String::new() })
+                .with_prefix("mainnet".to_string());
+        let notification = Notification::ErrorThresholdExceeded {
+            errors: 12,
+            threshold: 10,
+        };
+        assert_eq!(
+            executor.format(&notification),
+            "[mainnet] Error threshold exceeded: 12 errors (threshold: 10)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors_hide_webhook_url() {
+        let executor = NotificationExecutor::new(Webhook::Slack {
+            url: "http://127.0.0.1:1/services/T/B/secret".to_string(),
+        });
+        let err = executor
+            .execute(Notification::Message("hi".to_string()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KazukaError::HttpError(_)));
+        assert!(!err.to_string().contains("secret"));
+    }
+}
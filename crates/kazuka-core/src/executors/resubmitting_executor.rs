@@ -0,0 +1,382 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use alloy::{
+    network::AnyNetwork,
+    primitives::{B256, BlockNumber, TxHash},
+    providers::{DynProvider, Provider},
+};
+use async_trait::async_trait;
+use tokio::task::AbortHandle;
+use tokio_stream::StreamExt;
+use tracing::instrument;
+
+use crate::{
+    error::KazukaError,
+    executors::flashbots_bundle_executor::{
+        BundleAction, CancelBundle, SubmitBundle,
+    },
+    types::Executor,
+};
+
+/// Number of blocks `eth_sendBundle` bundles are kept alive for, since they
+/// target a single block.
+const DEFAULT_ETH_BUNDLE_WINDOW: u64 = 25;
+
+/// Task re-submitting a bundle.
+#[derive(Debug)]
+struct Resubmission {
+    task: AbortHandle,
+    /// Replacement UUID set by the strategy, if any.
+    replacement_uuid: Option<String>,
+}
+
+/// Shared handle to the bundles kept alive by a [ResubmittingExecutor],
+/// which allows cancelling them.
+#[derive(Clone, Debug, Default)]
+pub struct ResubmissionHandle {
+    tasks: Arc<Mutex<HashMap<B256, Resubmission>>>,
+}
+
+impl ResubmissionHandle {
+    /// Stops re-submitting the bundle with the given [id](SubmitBundle::id).
+    /// Returns `false` if the bundle is not being re-submitted.
+    pub fn cancel(&self, bundle_id: &B256) -> bool {
+        match self.tasks.lock().unwrap().remove(bundle_id) {
+            Some(resubmission) => {
+                resubmission.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops re-submitting the bundle submitted with the given replacement
+    /// UUID. Returns `false` if the bundle is not being re-submitted.
+    pub fn cancel_replacement_uuid(&self, replacement_uuid: &str) -> bool {
+        let mut tasks = self.tasks.lock().unwrap();
+        let bundle_id = tasks.iter().find_map(|(bundle_id, resubmission)| {
+            (resubmission.replacement_uuid.as_deref() == Some(replacement_uuid))
+                .then_some(*bundle_id)
+        });
+        match bundle_id.and_then(|bundle_id| tasks.remove(&bundle_id)) {
+            Some(resubmission) => {
+                resubmission.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops re-submitting all bundles.
+    pub fn cancel_all(&self) {
+        for (_, resubmission) in self.tasks.lock().unwrap().drain() {
+            resubmission.task.abort();
+        }
+    }
+
+    /// Number of bundles being re-submitted.
+    pub fn len(&self) -> usize {
+        self.tasks.lock().unwrap().len()
+    }
+
+    /// Whether no bundles are being re-submitted.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Keeps bundles alive across their inclusion window: on every new block
+/// the bundle is re-targeted to the next block and re-submitted through the
+/// inner executor, until one of its transactions lands on-chain, the window
+/// expires or the bundle is [cancelled](ResubmissionHandle::cancel).
+///
+/// The window of `mev_sendBundle` bundles ends at `max_block`, the window of
+/// `eth_sendBundle` bundles is configured separately.
+///
+/// [Cancelling](BundleAction::Cancel) a bundle stops re-submitting it, and
+/// cancels it through the inner executor.
+pub struct ResubmittingExecutor {
+    inner: Arc<dyn Executor<BundleAction>>,
+    provider: Arc<DynProvider<AnyNetwork>>,
+    eth_bundle_window: u64,
+    handle: ResubmissionHandle,
+}
+
+impl ResubmittingExecutor {
+    pub fn new(
+        inner: Arc<dyn Executor<BundleAction>>,
+        provider: Arc<DynProvider<AnyNetwork>>,
+    ) -> Self {
+        Self {
+            inner,
+            provider,
+            eth_bundle_window: DEFAULT_ETH_BUNDLE_WINDOW,
+            handle: ResubmissionHandle::default(),
+        }
+    }
+
+    /// Sets the number of blocks `eth_sendBundle` bundles are kept alive
+    /// for.
+    pub fn with_eth_bundle_window(mut self, eth_bundle_window: u64) -> Self {
+        self.eth_bundle_window = eth_bundle_window;
+        self
+    }
+
+    /// Returns a handle, through which bundles can be cancelled.
+    pub fn handle(&self) -> ResubmissionHandle {
+        self.handle.clone()
+    }
+
+    /// Last block the bundle can be included in.
+    fn last_block(&self, bundle: &SubmitBundle) -> BlockNumber {
+        match bundle {
+            SubmitBundle::Eth(bundle) => {
+                bundle.block_number + self.eth_bundle_window
+            }
+            SubmitBundle::Mev(bundle) => {
+                bundle.inclusion.max_block.unwrap_or(bundle.inclusion.block)
+            }
+        }
+    }
+}
+
+/// Returns `true` if any of the transactions has been mined.
+async fn is_included(
+    provider: &DynProvider<AnyNetwork>,
+    tx_hashes: &[TxHash],
+) -> Result<bool, KazukaError> {
+    for tx_hash in tx_hashes {
+        if provider.get_transaction_receipt(*tx_hash).await?.is_some() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+async fn keep_alive(
+    inner: Arc<dyn Executor<BundleAction>>,
+    provider: Arc<DynProvider<AnyNetwork>>,
+    bundle: SubmitBundle,
+    last_block: BlockNumber,
+) {
+    let tx_hashes = bundle.tx_hashes();
+    let mut blocks = match provider.subscribe_blocks().await {
+        Ok(subscription) => subscription.into_stream(),
+        Err(e) => {
+            tracing::error!("Error subscribing to blocks: {}", e);
+            return;
+        }
+    };
+
+    while let Some(header) = blocks.next().await {
+        match is_included(&provider, &tx_hashes).await {
+            Ok(true) => {
+                tracing::info!(block = header.number, "Bundle included");
+                return;
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Error checking bundle inclusion: {}", e),
+        }
+
+        let target_block = header.number + 1;
+        if target_block > last_block {
+            tracing::info!(last_block, "Bundle expired");
+            return;
+        }
+        let action = BundleAction::Submit(bundle.retarget(target_block));
+        if let Err(e) = inner.execute(action).await {
+            tracing::warn!(
+                target_block,
+                "Error re-submitting bundle: {}",
+                e
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl Executor<SubmitBundle> for ResubmittingExecutor {
+    /// Submits the bundle and keeps re-submitting it in the background.
+    #[instrument(skip(self))]
+    async fn execute(&self, action: SubmitBundle) -> Result<(), KazukaError> {
+        self.inner
+            .execute(BundleAction::Submit(action.clone()))
+            .await?;
+
+        let bundle_id = action.id();
+        let replacement_uuid = match &action {
+            SubmitBundle::Eth(bundle) => bundle.replacement_uuid.clone(),
+            SubmitBundle::Mev(_) => None,
+        };
+        let last_block = self.last_block(&action);
+        let inner = Arc::clone(&self.inner);
+        let provider = Arc::clone(&self.provider);
+        let tasks = Arc::clone(&self.handle.tasks);
+
+        // The lock is held until the task is registered, so that a task
+        // finishing right away can't remove its entry before it is inserted.
+        let mut registered = self.handle.tasks.lock().unwrap();
+        let task = tokio::spawn(async move {
+            keep_alive(inner, provider, action, last_block).await;
+            // A re-submission of the same bundle may have replaced the task,
+            // in which case the entry belongs to the newer task.
+            let mut tasks = tasks.lock().unwrap();
+            if tasks.get(&bundle_id).is_some_and(|resubmission| {
+                resubmission.task.id() == tokio::task::id()
+            }) {
+                tasks.remove(&bundle_id);
+            }
+        });
+
+        // Re-submitting the same bundle again replaces the previous task.
+        let resubmission = Resubmission {
+            task: task.abort_handle(),
+            replacement_uuid,
+        };
+        if let Some(previous) = registered.insert(bundle_id, resubmission) {
+            previous.task.abort();
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Executor<BundleAction> for ResubmittingExecutor {
+    /// Submits and keeps re-submitting a bundle, or stops re-submitting and
+    /// cancels it.
+    #[instrument(skip(self))]
+    async fn execute(&self, action: BundleAction) -> Result<(), KazukaError> {
+        match action {
+            BundleAction::Submit(bundle) => {
+                Executor::<SubmitBundle>::execute(self, bundle).await
+            }
+            BundleAction::Cancel(cancel) => {
+                let stopped = match &cancel {
+                    CancelBundle::Id(bundle_id) => {
+                        self.handle.cancel(bundle_id)
+                    }
+                    CancelBundle::ReplacementUuid(replacement_uuid) => {
+                        self.handle.cancel_replacement_uuid(replacement_uuid)
+                    }
+                };
+                tracing::debug!(
+                    stopped,
+                    "Cancelling re-submitted bundle"
+                );
+                self.inner.execute(BundleAction::Cancel(cancel)).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::Bytes, providers::ProviderBuilder,
+        rpc::types::mev::EthSendBundle, transports::mock::Asserter,
+    };
+
+    use super::*;
+
+    /// Records the actions it receives.
+    #[derive(Default)]
+    struct RecordingExecutor {
+        actions: Mutex<Vec<BundleAction>>,
+    }
+
+    #[async_trait]
+    impl Executor<BundleAction> for RecordingExecutor {
+        async fn execute(
+            &self,
+            action: BundleAction,
+        ) -> Result<(), KazukaError> {
+            self.actions.lock().unwrap().push(action);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_resubmission() {
+        let inner = Arc::new(RecordingExecutor::default());
+        let provider = ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .connect_mocked_client(Asserter::new())
+            .erased();
+        let executor = ResubmittingExecutor::new(
+            Arc::clone(&inner) as Arc<dyn Executor<BundleAction>>,
+            Arc::new(provider),
+        );
+        let handle = executor.handle();
+
+        // Stands in for the task re-submitting a bundle.
+        let bundle = SubmitBundle::Eth(EthSendBundle {
+            txs: vec![Bytes::from_static(b"tx")],
+            block_number: 100,
+            replacement_uuid: Some("uuid".to_string()),
+            ..Default::default()
+        });
+        let task = tokio::spawn(std::future::pending::<()>());
+        handle.tasks.lock().unwrap().insert(
+            bundle.id(),
+            Resubmission {
+                task: task.abort_handle(),
+                replacement_uuid: Some("uuid".to_string()),
+            },
+        );
+
+        let cancel = CancelBundle::ReplacementUuid("uuid".to_string());
+        Executor::<BundleAction>::execute(
+            &executor,
+            BundleAction::Cancel(cancel.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert!(handle.is_empty());
+        assert!(task.await.unwrap_err().is_cancelled());
+        let actions = inner.actions.lock().unwrap();
+        assert!(matches!(
+            actions.as_slice(),
+            [BundleAction::Cancel(forwarded)] if *forwarded == cancel
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_finished_task_removes_its_entry() {
+        let inner = Arc::new(RecordingExecutor::default());
+        // The mocked transport can't subscribe to blocks, so the task
+        // keeping the bundle alive finishes right away.
+        let provider = ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .connect_mocked_client(Asserter::new())
+            .erased();
+        let executor = ResubmittingExecutor::new(
+            Arc::clone(&inner) as Arc<dyn Executor<BundleAction>>,
+            Arc::new(provider),
+        );
+        let handle = executor.handle();
+        let bundle = SubmitBundle::Eth(EthSendBundle {
+            txs: vec![Bytes::from_static(b"tx")],
+            block_number: 100,
+            ..Default::default()
+        });
+
+        Executor::<SubmitBundle>::execute(&executor, bundle)
+            .await
+            .unwrap();
+
+        tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            async {
+                while !handle.is_empty() {
+                    tokio::task::yield_now().await;
+                }
+            },
+        )
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{
+    clock::{Clock, system_clock},
+    error::{ErrorClass, KazukaError},
+    types::Executor,
+};
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(200);
+const DEFAULT_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Wraps [Executor] and retries actions that failed with a
+/// [retryable](ErrorClass::is_retryable) error, backing off exponentially.
+/// Rate limited actions back off longer than ones that failed transiently.
+pub struct RetryExecutor<A> {
+    inner: Box<dyn Executor<A>>,
+    max_retries: u32,
+    backoff: Duration,
+    rate_limit_backoff: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl<A> RetryExecutor<A> {
+    pub fn new(inner: Box<dyn Executor<A>>) -> Self {
+        Self {
+            inner,
+            max_retries: DEFAULT_MAX_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+            rate_limit_backoff: DEFAULT_RATE_LIMIT_BACKOFF,
+            clock: system_clock(),
+        }
+    }
+
+    /// Sets the maximum number of retries of a single action.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry after a transient error,
+    /// which doubles with every further retry.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the delay before the first retry after being rate limited,
+    /// which doubles with every further retry.
+    pub fn with_rate_limit_backoff(
+        mut self,
+        rate_limit_backoff: Duration,
+    ) -> Self {
+        self.rate_limit_backoff = rate_limit_backoff;
+        self
+    }
+
+    /// Sets the clock the backoff delays are waited on.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn delay(&self, class: ErrorClass, attempt: u32) -> Duration {
+        let backoff = match class {
+            ErrorClass::RateLimited => self.rate_limit_backoff,
+            _ => self.backoff,
+        };
+        backoff.saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+#[async_trait]
+impl<A> Executor<A> for RetryExecutor<A>
+where
+    A: Clone + std::fmt::Debug + Send + Sync + 'static,
+{
+    /// Executes the action, retrying retryable failures.
+    #[instrument(skip(self))]
+    async fn execute(&self, action: A) -> Result<(), KazukaError> {
+        let mut attempt = 0;
+        loop {
+            let error = match self.inner.execute(action.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            let class = error.classify();
+            if !class.is_retryable() || attempt >= self.max_retries {
+                return Err(error);
+            }
+            let delay = self.delay(class, attempt);
+            tracing::warn!(
+                attempt = attempt + 1,
+                ?class,
+                ?delay,
+                "Retrying action: {}",
+                error
+            );
+            self.clock.sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::clock::MockClock;
+
+    /// Fails with the given error a number of times before succeeding.
+    struct FlakyExecutor {
+        failures: u32,
+        error: fn() -> KazukaError,
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl Executor<()> for FlakyExecutor {
+        async fn execute(&self, _action: ()) -> Result<(), KazukaError> {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst);
+            if calls < self.failures {
+                return Err((self.error)());
+            }
+            Ok(())
+        }
+    }
+
+    fn retry_executor(
+        failures: u32,
+        error: fn() -> KazukaError,
+    ) -> (RetryExecutor<()>, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let inner = FlakyExecutor {
+            failures,
+            error,
+            calls: Arc::clone(&calls),
+        };
+        let executor = RetryExecutor::new(Box::new(inner))
+            .with_backoff(Duration::from_millis(1))
+            .with_rate_limit_backoff(Duration::from_millis(1));
+        (executor, calls)
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let (executor, calls) = retry_executor(2, || {
+            KazukaError::ChannelError("lagged".to_string())
+        });
+        executor.execute(()).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let (executor, calls) = retry_executor(10, || {
+            KazukaError::ChannelError("lagged".to_string())
+        });
+        assert!(executor.execute(()).await.is_err());
+        assert_eq!(
+            calls.load(Ordering::SeqCst),
+            DEFAULT_MAX_RETRIES + 1
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_backs_off_exponentially() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let inner = FlakyExecutor {
+            failures: 3,
+            error: || KazukaError::ChannelError("lagged".to_string()),
+            calls: Arc::clone(&calls),
+        };
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let executor = RetryExecutor::new(Box::new(inner))
+            .with_backoff(Duration::from_secs(1))
+            .with_clock(clock.clone());
+
+        let start = clock.now();
+        executor.execute(()).await.unwrap();
+        // 1s + 2s + 4s
+        assert_eq!(
+            (clock.now() - start).to_std().unwrap(),
+            Duration::from_secs(7)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_user_errors() {
+        let (executor, calls) = retry_executor(1, || {
+            KazukaError::BundleRejected("bundle reverts".to_string())
+        });
+        assert!(executor.execute(()).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
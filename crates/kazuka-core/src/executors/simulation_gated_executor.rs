@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use alloy::{
+    network::AnyNetwork, primitives::U256, providers::DynProvider,
+    signers::Signer,
+};
+use async_trait::async_trait;
+use tracing::instrument;
+
+pub use crate::simulation::SimulationOutcome;
+use crate::{
+    error::KazukaError,
+    executors::flashbots_bundle_executor::SubmitBundle,
+    simulation::{RelaySimulator, Simulator},
+    types::Executor,
+};
+
+/// Simulates each bundle via `mev_simBundle` / `eth_callBundle` and only
+/// forwards it to the inner executor if the simulation succeeds and the
+/// estimated profit exceeds the threshold.
+pub struct SimulationGatedExecutor {
+    inner: Box<dyn Executor<SubmitBundle>>,
+    simulator: RelaySimulator,
+    /// Minimum net profit (in wei) for a bundle to be submitted.
+    min_profit: U256,
+}
+
+impl SimulationGatedExecutor {
+    /// Wraps the `inner` executor, simulating bundles against the given
+    /// relay (e.g. `https://relay.flashbots.net`) with requests signed by
+    /// the `signer`. The provider is used to estimate the gas cost.
+    pub fn new(
+        inner: Box<dyn Executor<SubmitBundle>>,
+        url: String,
+        signer: impl Signer + Clone + Send + Sync + 'static,
+        provider: Arc<DynProvider<AnyNetwork>>,
+    ) -> Self {
+        Self {
+            inner,
+            simulator: RelaySimulator::new(url, signer, provider),
+            min_profit: U256::ZERO,
+        }
+    }
+
+    /// Sets the minimum net profit (in wei) for a bundle to be submitted.
+    pub fn with_min_profit(mut self, min_profit: U256) -> Self {
+        self.min_profit = min_profit;
+        self
+    }
+
+    /// Sets the percentage of the refundable value paid back to users.
+    pub fn with_refund_percent(mut self, refund_percent: u64) -> Self {
+        self.simulator = self.simulator.with_refund_percent(refund_percent);
+        self
+    }
+
+    /// Simulates the bundle without submitting it.
+    pub async fn simulate(
+        &self,
+        bundle: &SubmitBundle,
+    ) -> Result<SimulationOutcome, KazukaError> {
+        self.simulator.simulate(bundle).await
+    }
+}
+
+#[async_trait]
+impl Executor<SubmitBundle> for SimulationGatedExecutor {
+    /// Simulates the bundle and forwards it if it is profitable.
+    #[instrument(skip(self))]
+    async fn execute(&self, action: SubmitBundle) -> Result<(), KazukaError> {
+        let outcome = self.simulate(&action).await?;
+        if !outcome.success {
+            tracing::info!(
+                error = ?outcome.error,
+                "Bundle simulation failed, skipping"
+            );
+            return Ok(());
+        }
+        let net_profit = outcome.net_profit();
+        if net_profit < self.min_profit {
+            tracing::info!(
+                %net_profit,
+                min_profit = %self.min_profit,
+                "Bundle is not profitable enough, skipping"
+            );
+            return Ok(());
+        }
+        tracing::info!(%net_profit, "Bundle simulated, submitting");
+        self.inner.execute(action).await
+    }
+}
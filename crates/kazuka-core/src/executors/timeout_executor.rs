@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{error::KazukaError, types::Executor};
+
+/// Wraps [Executor] and fails actions that take longer than the timeout,
+/// e.g. because of a hung RPC request, with [KazukaError::Timeout].
+///
+/// Timed out actions are cancelled, so they may have been only partially
+/// executed, e.g. a bundle sent to some of the relays.
+pub struct TimeoutExecutor<A> {
+    inner: Box<dyn Executor<A>>,
+    timeout: Duration,
+}
+
+impl<A> TimeoutExecutor<A> {
+    pub fn new(inner: Box<dyn Executor<A>>, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+}
+
+#[async_trait]
+impl<A> Executor<A> for TimeoutExecutor<A>
+where
+    A: std::fmt::Debug + Send + Sync + 'static,
+{
+    #[instrument(skip(self))]
+    async fn execute(&self, action: A) -> Result<(), KazukaError> {
+        tokio::time::timeout(self.timeout, self.inner.execute(action))
+            .await
+            .map_err(|_| KazukaError::Timeout {
+                operation: format!(
+                    "Executing action with {}",
+                    self.inner.name()
+                ),
+                timeout: self.timeout,
+            })?
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorClass;
+
+    /// Takes the given time to execute an action.
+    struct SlowExecutor(Duration);
+
+    #[async_trait]
+    impl Executor<()> for SlowExecutor {
+        async fn execute(&self, _action: ()) -> Result<(), KazukaError> {
+            tokio::time::sleep(self.0).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let executor = TimeoutExecutor::new(
+            Box::new(SlowExecutor(Duration::from_millis(1))),
+            Duration::from_millis(100),
+        );
+        executor.execute(()).await.unwrap();
+
+        let executor = TimeoutExecutor::new(
+            Box::new(SlowExecutor(Duration::from_secs(10))),
+            Duration::from_millis(10),
+        );
+        let e = executor.execute(()).await.unwrap_err();
+        assert!(matches!(e, KazukaError::Timeout { .. }));
+        assert_eq!(e.classify(), ErrorClass::Transient);
+        assert_eq!(
+            e.to_string(),
+            "Executing action with SlowExecutor timed out after 10ms"
+        );
+    }
+}
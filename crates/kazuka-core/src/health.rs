@@ -0,0 +1,108 @@
+//! Health of the components of the [Engine](crate::engine::Engine), e.g. for
+//! liveness checks.
+//!
+//! ```ignore
+//! let health = Health::new();
+//! let engine = Engine::new().with_health(health.clone());
+//! let tasks = engine.run().await?;
+//! assert!(health.is_healthy());
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+/// Status of a component of the engine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentStatus {
+    /// Syncing its state, or connecting.
+    Starting,
+    Running,
+    /// Event source whose stream has ended.
+    Stopped,
+    /// Failed to start.
+    Failed,
+}
+
+impl ComponentStatus {
+    pub fn is_healthy(&self) -> bool {
+        matches!(
+            self,
+            ComponentStatus::Starting | ComponentStatus::Running
+        )
+    }
+}
+
+/// Statuses of the components by name, shared by the engine and its
+/// observers.
+#[derive(Clone, Debug, Default)]
+pub struct Health {
+    components: Arc<Mutex<BTreeMap<String, ComponentStatus>>>,
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, component: impl Into<String>, status: ComponentStatus) {
+        self.components
+            .lock()
+            .unwrap()
+            .insert(component.into(), status);
+    }
+
+    pub fn status(&self, component: &str) -> Option<ComponentStatus> {
+        self.components.lock().unwrap().get(component).copied()
+    }
+
+    /// Snapshot of the statuses of all components.
+    pub fn components(&self) -> BTreeMap<String, ComponentStatus> {
+        self.components.lock().unwrap().clone()
+    }
+
+    /// Whether all components are starting or running.
+    pub fn is_healthy(&self) -> bool {
+        self.components
+            .lock()
+            .unwrap()
+            .values()
+            .all(ComponentStatus::is_healthy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health() {
+        let health = Health::new();
+        assert!(health.is_healthy());
+
+        health.set("strategy_0", ComponentStatus::Starting);
+        health.clone().set(
+            "event_source_0",
+            ComponentStatus::Running,
+        );
+        assert!(health.is_healthy());
+
+        health.set(
+            "event_source_0",
+            ComponentStatus::Stopped,
+        );
+        assert!(!health.is_healthy());
+        assert_eq!(
+            health.status("event_source_0"),
+            Some(ComponentStatus::Stopped)
+        );
+        assert_eq!(
+            serde_json::to_string(&health.components()).unwrap(),
+            r#"{"event_source_0":"stopped","strategy_0":"starting"}"#
+        );
+    }
+}
@@ -1,6 +1,14 @@
+pub mod bundle_watcher;
+pub mod clock;
 pub mod engine;
 pub mod error;
 pub mod event_sources;
 pub mod executors;
+pub mod health;
+pub mod pnl;
+pub mod signer;
+pub mod simulation;
+pub mod stats;
+pub mod strategies;
 pub mod telemetry;
 pub mod types;
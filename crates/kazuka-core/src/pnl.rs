@@ -0,0 +1,243 @@
+//! Profit and loss accounting.
+//!
+//! Executors and bundle status pollers feed a [PnlLedger] with
+//! [events](PnlEvent), which keeps running PnL per strategy and per day and
+//! exports it as metrics. Bundles are booked under their [BundleOrigin].
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use alloy::primitives::{I256, U256};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Something that affects the PnL of a strategy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PnlEventKind {
+    /// A bundle or transaction has been submitted.
+    Submitted,
+    /// A bundle or transaction has been included, earning the given revenue
+    /// (in wei).
+    Included { revenue: U256 },
+    /// Gas paid (in wei), including tips paid to the builder.
+    GasPaid(U256),
+    /// Refund received (in wei), e.g. a MEV-Share or gas refund.
+    RefundReceived(U256),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PnlEvent {
+    /// Name of the strategy the event belongs to.
+    pub strategy: String,
+    pub timestamp: DateTime<Utc>,
+    pub kind: PnlEventKind,
+}
+
+impl PnlEvent {
+    /// Creates an event happening now.
+    pub fn now(strategy: impl Into<String>, kind: PnlEventKind) -> Self {
+        Self {
+            strategy: strategy.into(),
+            timestamp: Utc::now(),
+            kind,
+        }
+    }
+}
+
+/// Running PnL over a period.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PnlSummary {
+    pub submissions: u64,
+    pub inclusions: u64,
+    /// Total revenue (in wei).
+    pub revenue: U256,
+    /// Total gas paid (in wei).
+    pub gas_paid: U256,
+    /// Total refunds received (in wei).
+    pub refunds: U256,
+}
+
+impl PnlSummary {
+    /// Net profit (in wei), which is negative on loss.
+    pub fn net(&self) -> I256 {
+        to_signed(self.revenue.saturating_add(self.refunds))
+            .saturating_sub(to_signed(self.gas_paid))
+    }
+
+    /// Share of submissions that got included.
+    pub fn inclusion_rate(&self) -> f64 {
+        if self.submissions == 0 {
+            return 0.0;
+        }
+        self.inclusions as f64 / self.submissions as f64
+    }
+
+    fn apply(&mut self, kind: &PnlEventKind) {
+        match kind {
+            PnlEventKind::Submitted => self.submissions += 1,
+            PnlEventKind::Included { revenue } => {
+                self.inclusions += 1;
+                self.revenue = self.revenue.saturating_add(*revenue);
+            }
+            PnlEventKind::GasPaid(gas_paid) => {
+                self.gas_paid = self.gas_paid.saturating_add(*gas_paid);
+            }
+            PnlEventKind::RefundReceived(refund) => {
+                self.refunds = self.refunds.saturating_add(*refund);
+            }
+        }
+    }
+}
+
+fn to_signed(value: U256) -> I256 {
+    I256::try_from(value).unwrap_or(I256::MAX)
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    strategies: HashMap<String, PnlSummary>,
+    days: HashMap<(String, NaiveDate), PnlSummary>,
+}
+
+/// Shared ledger, which accumulates [PnL events](PnlEvent).
+#[derive(Clone, Debug, Default)]
+pub struct PnlLedger {
+    ledger: Arc<Mutex<Ledger>>,
+}
+
+impl PnlLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the event and updates the PnL metrics of its strategy.
+    pub fn record(&self, event: PnlEvent) {
+        let mut ledger = self.ledger.lock().unwrap();
+        let day = event.timestamp.date_naive();
+        ledger
+            .days
+            .entry((event.strategy.clone(), day))
+            .or_default()
+            .apply(&event.kind);
+        let summary =
+            ledger.strategies.entry(event.strategy.clone()).or_default();
+        summary.apply(&event.kind);
+        record_metrics(&event, summary);
+    }
+
+    /// Running PnL of the strategy since start.
+    pub fn strategy(&self, strategy: &str) -> PnlSummary {
+        let ledger = self.ledger.lock().unwrap();
+        ledger.strategies.get(strategy).cloned().unwrap_or_default()
+    }
+
+    /// PnL of the strategy on the given (UTC) day.
+    pub fn day(&self, strategy: &str, day: NaiveDate) -> PnlSummary {
+        let ledger = self.ledger.lock().unwrap();
+        ledger
+            .days
+            .get(&(strategy.to_string(), day))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Running PnL of all strategies.
+    pub fn strategies(&self) -> HashMap<String, PnlSummary> {
+        self.ledger.lock().unwrap().strategies.clone()
+    }
+}
+
+/// Strategy a bundle has been submitted by, which its PnL is booked under.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BundleOrigin {
+    pub strategy: String,
+    /// Revenue (in wei) the strategy expects if the bundle is included.
+    pub expected_profit: U256,
+}
+
+impl BundleOrigin {
+    pub fn new(strategy: impl Into<String>, expected_profit: U256) -> Self {
+        Self {
+            strategy: strategy.into(),
+            expected_profit,
+        }
+    }
+
+    /// Records an event of the strategy happening now.
+    pub(crate) fn record(&self, ledger: &PnlLedger, kind: PnlEventKind) {
+        ledger.record(PnlEvent::now(
+            self.strategy.clone(),
+            kind,
+        ));
+    }
+}
+
+fn record_metrics(event: &PnlEvent, summary: &PnlSummary) {
+    let strategy = event.strategy.clone();
+    match event.kind {
+        PnlEventKind::Submitted => {
+            metrics::counter!("kazuka_pnl_submissions_total", "strategy" => strategy.clone())
+                .increment(1);
+        }
+        PnlEventKind::Included { .. } => {
+            metrics::counter!("kazuka_pnl_inclusions_total", "strategy" => strategy.clone())
+                .increment(1);
+        }
+        PnlEventKind::GasPaid(_) | PnlEventKind::RefundReceived(_) => {}
+    }
+    let net = summary.net();
+    let net = i128::try_from(net).unwrap_or(if net.is_negative() {
+        i128::MIN
+    } else {
+        i128::MAX
+    });
+    metrics::gauge!("kazuka_pnl_net_wei", "strategy" => strategy)
+        .set(net as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pnl_ledger() {
+        let ledger = PnlLedger::new();
+        let events = [
+            PnlEventKind::Submitted,
+            PnlEventKind::Submitted,
+            PnlEventKind::Included {
+                revenue: U256::from(1000),
+            },
+            PnlEventKind::GasPaid(U256::from(300)),
+            PnlEventKind::RefundReceived(U256::from(50)),
+        ];
+        for kind in events {
+            ledger.record(PnlEvent::now("arb", kind));
+        }
+        ledger.record(PnlEvent::now(
+            "liquidations",
+            PnlEventKind::GasPaid(U256::from(100)),
+        ));
+
+        let arb = ledger.strategy("arb");
+        assert_eq!(arb.submissions, 2);
+        assert_eq!(arb.inclusions, 1);
+        assert_eq!(
+            arb.net(),
+            I256::try_from(750i64).unwrap()
+        );
+        assert_eq!(arb.inclusion_rate(), 0.5);
+        assert_eq!(
+            ledger.day("arb", Utc::now().date_naive()),
+            arb
+        );
+
+        let liquidations = ledger.strategy("liquidations");
+        assert_eq!(
+            liquidations.net(),
+            I256::try_from(-100i64).unwrap()
+        );
+        assert_eq!(ledger.strategies().len(), 2);
+    }
+}
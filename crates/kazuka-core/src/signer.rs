@@ -0,0 +1,268 @@
+//! Keys of the strategies and executors, by purpose:
+//! - [KeyPurpose::Reputation] signs requests to relays, which builds the
+//!   reputation of the searcher,
+//! - [KeyPurpose::Transactions] signs transactions, and holds the funds,
+//! - [KeyPurpose::Cancellation] signs bundle cancellations.
+//!
+//! Keys are loaded from private keys, encrypted keystores, or any other
+//! (e.g. remote, hardware) alloy signer, see [KazukaSigner].
+
+use std::{collections::HashMap, fmt, path::Path, str::FromStr, sync::Arc};
+
+use alloy::{
+    consensus::SignableTransaction,
+    network::{EthereumWallet, FullSigner, TxSigner},
+    primitives::{Address, B256, ChainId, Signature},
+    signers::{self, Signer, local::PrivateKeySigner},
+};
+use async_trait::async_trait;
+
+use crate::error::KazukaError;
+
+/// What a key is used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyPurpose {
+    /// Signs requests to relays, e.g. the Flashbots signature of bundles.
+    Reputation,
+    /// Signs transactions.
+    Transactions,
+    /// Signs bundle cancellations. Relays only accept cancellations signed
+    /// by the key that submitted the bundle, so it defaults to the
+    /// reputation key.
+    Cancellation,
+}
+
+/// Cheaply clonable signer, which wraps any alloy signer (e.g. a local key,
+/// an AWS KMS key or a Ledger), so that signers of different kinds can be
+/// used interchangeably.
+#[derive(Clone)]
+pub struct KazukaSigner {
+    inner: Arc<dyn FullSigner<Signature> + Send + Sync>,
+}
+
+impl KazukaSigner {
+    pub fn new(
+        signer: impl FullSigner<Signature> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner: Arc::new(signer),
+        }
+    }
+
+    /// Signer of a hex-encoded private key.
+    pub fn from_private_key(private_key: &str) -> Result<Self, KazukaError> {
+        let signer = PrivateKeySigner::from_str(private_key)
+            .map_err(signers::Error::other)?;
+        Ok(Self::new(signer))
+    }
+
+    /// Signer of a key in an encrypted JSON keystore.
+    pub fn from_keystore(
+        path: impl AsRef<Path>,
+        password: impl AsRef<[u8]>,
+    ) -> Result<Self, KazukaError> {
+        let signer = PrivateKeySigner::decrypt_keystore(path, password)
+            .map_err(signers::Error::other)?;
+        Ok(Self::new(signer))
+    }
+
+    /// Signer of the account of a Ledger at the index of the Ledger Live
+    /// derivation path.
+    #[cfg(feature = "ledger")]
+    pub async fn from_ledger(
+        index: usize,
+        chain_id: Option<ChainId>,
+    ) -> Result<Self, KazukaError> {
+        use alloy::signers::ledger::{HDPath, LedgerSigner};
+
+        let signer = LedgerSigner::new(HDPath::LedgerLive(index), chain_id)
+            .await
+            .map_err(signers::Error::other)?;
+        Ok(Self::new(signer))
+    }
+
+    pub fn address(&self) -> Address {
+        Signer::address(self.inner.as_ref())
+    }
+}
+
+impl fmt::Debug for KazukaSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KazukaSigner")
+            .field("address", &self.address())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Signer for KazukaSigner {
+    async fn sign_hash(&self, hash: &B256) -> signers::Result<Signature> {
+        self.inner.sign_hash(hash).await
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> signers::Result<Signature> {
+        self.inner.sign_message(message).await
+    }
+
+    fn address(&self) -> Address {
+        KazukaSigner::address(self)
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        self.inner.chain_id()
+    }
+
+    /// Only applies to signers which aren't shared yet, as the wrapped
+    /// signer is shared by the clones.
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        match Arc::get_mut(&mut self.inner) {
+            Some(inner) => inner.set_chain_id(chain_id),
+            None => tracing::warn!(
+                address = ?self.address(),
+                "Can't set the chain ID of a shared signer"
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl TxSigner<Signature> for KazukaSigner {
+    fn address(&self) -> Address {
+        KazukaSigner::address(self)
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<Signature>,
+    ) -> signers::Result<Signature> {
+        self.inner.sign_transaction(tx).await
+    }
+}
+
+/// Provides the keys of the strategies and executors, by purpose.
+pub trait SignerProvider: Send + Sync {
+    /// Signer of the given purpose.
+    fn signer(&self, purpose: KeyPurpose) -> Result<KazukaSigner, KazukaError>;
+
+    /// Wallet signing transactions with the
+    /// [transactions](KeyPurpose::Transactions) signer.
+    fn wallet(&self) -> Result<EthereumWallet, KazukaError> {
+        Ok(EthereumWallet::new(
+            self.signer(KeyPurpose::Transactions)?,
+        ))
+    }
+}
+
+/// A single key used for every purpose.
+impl SignerProvider for KazukaSigner {
+    fn signer(&self, _: KeyPurpose) -> Result<KazukaSigner, KazukaError> {
+        Ok(self.clone())
+    }
+}
+
+/// Keys by purpose, falling back to the default key for the purposes
+/// without one.
+#[derive(Clone, Debug, Default)]
+pub struct Keyring {
+    default: Option<KazukaSigner>,
+    signers: HashMap<KeyPurpose, KazukaSigner>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the key of the purposes without one.
+    pub fn with_default(mut self, signer: KazukaSigner) -> Self {
+        self.default = Some(signer);
+        self
+    }
+
+    /// Sets the key of the purpose.
+    pub fn with_signer(
+        mut self,
+        purpose: KeyPurpose,
+        signer: KazukaSigner,
+    ) -> Self {
+        self.signers.insert(purpose, signer);
+        self
+    }
+}
+
+impl SignerProvider for Keyring {
+    fn signer(&self, purpose: KeyPurpose) -> Result<KazukaSigner, KazukaError> {
+        let signer = match purpose {
+            KeyPurpose::Cancellation => self
+                .signers
+                .get(&KeyPurpose::Cancellation)
+                .or_else(|| self.signers.get(&KeyPurpose::Reputation)),
+            purpose => self.signers.get(&purpose),
+        };
+        signer.or(self.default.as_ref()).cloned().ok_or_else(|| {
+            KazukaError::ConfigError(format!(
+                "no {:?} signer configured",
+                purpose
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random() -> KazukaSigner {
+        KazukaSigner::new(PrivateKeySigner::random())
+    }
+
+    #[test]
+    fn test_keyring_fallbacks() {
+        let default = random();
+        let reputation = random();
+        let keyring = Keyring::new().with_default(default.clone()).with_signer(
+            KeyPurpose::Reputation,
+            reputation.clone(),
+        );
+
+        let address = |purpose| keyring.signer(purpose).unwrap().address();
+        assert_eq!(
+            address(KeyPurpose::Reputation),
+            reputation.address()
+        );
+        assert_eq!(
+            address(KeyPurpose::Cancellation),
+            reputation.address()
+        );
+        assert_eq!(
+            address(KeyPurpose::Transactions),
+            default.address()
+        );
+    }
+
+    #[test]
+    fn test_keyring_missing_signer() {
+        let keyring =
+            Keyring::new().with_signer(KeyPurpose::Reputation, random());
+
+        assert!(matches!(
+            keyring.signer(KeyPurpose::Transactions),
+            Err(KazukaError::ConfigError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sign_message() {
+        let signer = KazukaSigner::from_private_key(
+            "0x0123456789012345678901234567890123456789012345678901234567890123",
+        )
+        .unwrap();
+
+        let signature = signer.sign_message(b"kazuka").await.unwrap();
+        assert_eq!(
+            signature.recover_address_from_msg(b"kazuka").unwrap(),
+            signer.address()
+        );
+        assert!(KazukaSigner::from_private_key("0x01zz").is_err());
+    }
+}
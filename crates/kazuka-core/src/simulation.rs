@@ -0,0 +1,376 @@
+//! Simulation of actions without submitting them, e.g. by the dry runs of
+//! the engine, see [Engine::with_dry_run](crate::engine::Engine::with_dry_run):
+//! - [RelaySimulator] simulating bundles with `mev_simBundle` /
+//!   `eth_callBundle` on a relay,
+//! - [AnvilSimulator] executing bundles on an Anvil node (e.g. a mainnet fork),
+//!   and reverting them.
+
+use std::sync::Arc;
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    network::{AnyNetwork, ReceiptResponse},
+    primitives::{Bytes, U256, keccak256},
+    providers::{DynProvider, Provider, ext::AnvilApi},
+    rpc::types::mev::{
+        BundleItem, EthCallBundle, EthSendBundle, MevSendBundle,
+        SimBundleOverrides,
+    },
+    signers::Signer,
+};
+use async_trait::async_trait;
+use kazuka_mev_share::rpc::{EthBundleApiClient, MevApiClient};
+
+use crate::{
+    error::KazukaError,
+    executors::flashbots_bundle_executor::{SubmitBundle, signed_client},
+};
+
+/// Default percentage of the refundable value paid back to the users whose
+/// transactions are backrun, as configured by MEV-Share.
+const DEFAULT_REFUND_PERCENT: u64 = 90;
+
+/// Simulated outcome of a bundle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulationOutcome {
+    /// Whether all transactions of the bundle executed successfully.
+    pub success: bool,
+    /// Value the bundle pays to the block builder (in wei).
+    pub profit: U256,
+    /// Part of the profit refunded to users (in wei).
+    pub refund: U256,
+    /// Base fee burned by the bundle (in wei).
+    pub gas_cost: U256,
+    /// Error or revert reason, if the simulation has failed.
+    pub error: Option<String>,
+}
+
+impl SimulationOutcome {
+    /// Profit that is left after paying refunds and gas.
+    pub fn net_profit(&self) -> U256 {
+        self.profit
+            .saturating_sub(self.refund)
+            .saturating_sub(self.gas_cost)
+    }
+
+    fn failed(error: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            profit: U256::ZERO,
+            refund: U256::ZERO,
+            gas_cost: U256::ZERO,
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Simulates actions without submitting them.
+#[async_trait]
+pub trait Simulator<A>: Send + Sync {
+    async fn simulate(
+        &self,
+        action: &A,
+    ) -> Result<SimulationOutcome, KazukaError>;
+}
+
+/// Simulates bundles via `mev_simBundle` / `eth_callBundle` on a relay.
+pub struct RelaySimulator {
+    eth_client: Box<dyn EthBundleApiClient + Send + Sync>,
+    mev_client: Box<dyn MevApiClient + Send + Sync>,
+    provider: Arc<DynProvider<AnyNetwork>>,
+    /// Percentage of the refundable value paid back to users.
+    refund_percent: u64,
+}
+
+impl RelaySimulator {
+    /// Simulates bundles against the given relay (e.g.
+    /// `https://relay.flashbots.net`) with requests signed by the `signer`.
+    /// The provider is used to estimate the gas cost.
+    pub fn new(
+        url: String,
+        signer: impl Signer + Clone + Send + Sync + 'static,
+        provider: Arc<DynProvider<AnyNetwork>>,
+    ) -> Self {
+        let client = signed_client(url, signer);
+        Self {
+            eth_client: Box::new(client.clone()),
+            mev_client: Box::new(client),
+            provider,
+            refund_percent: DEFAULT_REFUND_PERCENT,
+        }
+    }
+
+    /// Sets the percentage of the refundable value paid back to users.
+    pub fn with_refund_percent(mut self, refund_percent: u64) -> Self {
+        assert!(
+            refund_percent <= 100,
+            "Refund percent must be <= 100"
+        );
+        self.refund_percent = refund_percent;
+        self
+    }
+
+    async fn simulate_mev(
+        &self,
+        bundle: &MevSendBundle,
+        base_fee: u64,
+    ) -> Result<SimulationOutcome, KazukaError> {
+        let response = self
+            .mev_client
+            .sim_bundle(
+                bundle.clone(),
+                SimBundleOverrides::default(),
+            )
+            .await?;
+        Ok(SimulationOutcome {
+            success: response.success,
+            profit: response.profit,
+            refund: response.refundable_value * U256::from(self.refund_percent)
+                / U256::from(100),
+            gas_cost: U256::from(response.gas_used) * U256::from(base_fee),
+            error: response.error.or(response.exec_error),
+        })
+    }
+
+    async fn simulate_eth(
+        &self,
+        bundle: &EthSendBundle,
+        base_fee: u64,
+    ) -> Result<SimulationOutcome, KazukaError> {
+        let request = EthCallBundle {
+            txs: bundle.txs.clone(),
+            block_number: bundle.block_number,
+            ..Default::default()
+        };
+        let response = self.eth_client.call_bundle(request).await?;
+        let revert = response
+            .results
+            .into_iter()
+            .find_map(|result| result.revert);
+        Ok(SimulationOutcome {
+            success: revert.is_none(),
+            profit: response.coinbase_diff,
+            refund: U256::ZERO,
+            gas_cost: U256::from(response.total_gas_used)
+                * U256::from(base_fee),
+            error: revert,
+        })
+    }
+}
+
+#[async_trait]
+impl Simulator<SubmitBundle> for RelaySimulator {
+    async fn simulate(
+        &self,
+        bundle: &SubmitBundle,
+    ) -> Result<SimulationOutcome, KazukaError> {
+        let base_fee = self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Latest)
+            .await?
+            .and_then(|block| block.header.base_fee_per_gas)
+            .unwrap_or_default();
+        match bundle {
+            SubmitBundle::Mev(bundle) => {
+                self.simulate_mev(bundle, base_fee).await
+            }
+            SubmitBundle::Eth(bundle) => {
+                self.simulate_eth(bundle, base_fee).await
+            }
+        }
+    }
+}
+
+/// Signed transactions of the bundle in order, along with whether they may
+/// revert. Fails on hash items, i.e. private transactions, which only the
+/// relay knows.
+fn bundle_txs(bundle: &SubmitBundle) -> Result<Vec<(Bytes, bool)>, String> {
+    fn mev_txs(
+        bundle: &MevSendBundle,
+        txs: &mut Vec<(Bytes, bool)>,
+    ) -> Result<(), String> {
+        for item in &bundle.bundle_body {
+            match item {
+                BundleItem::Tx { tx, can_revert } => {
+                    txs.push((tx.clone(), *can_revert))
+                }
+                BundleItem::Bundle { bundle } => mev_txs(bundle, txs)?,
+                BundleItem::Hash { hash } => {
+                    return Err(format!(
+                        "can't simulate private transaction {hash}"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    match bundle {
+        SubmitBundle::Eth(bundle) => Ok(bundle
+            .txs
+            .iter()
+            .map(|tx| {
+                let can_revert =
+                    bundle.reverting_tx_hashes.contains(&keccak256(tx));
+                (tx.clone(), can_revert)
+            })
+            .collect()),
+        SubmitBundle::Mev(bundle) => {
+            let mut txs = vec![];
+            mev_txs(bundle, &mut txs)?;
+            Ok(txs)
+        }
+    }
+}
+
+/// Simulates bundles by executing them on an Anvil node (e.g. a mainnet
+/// fork) with auto mining, reverting its state after every simulation.
+///
+/// Bundles backrunning private transactions, e.g. MEV-Share hints, can only
+/// be simulated by the relay, see [RelaySimulator].
+pub struct AnvilSimulator {
+    provider: DynProvider<AnyNetwork>,
+}
+
+impl AnvilSimulator {
+    pub fn new(provider: DynProvider<AnyNetwork>) -> Self {
+        Self { provider }
+    }
+
+    async fn execute(
+        &self,
+        txs: Vec<(Bytes, bool)>,
+    ) -> Result<SimulationOutcome, KazukaError> {
+        let Some(block) = self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Latest)
+            .await?
+        else {
+            return Ok(SimulationOutcome::failed(
+                "no latest block",
+            ));
+        };
+        let coinbase = block.header.beneficiary;
+        let base_fee = block.header.base_fee_per_gas.unwrap_or_default();
+        let balance = self.provider.get_balance(coinbase).await?;
+        let mut outcome = SimulationOutcome {
+            success: true,
+            profit: U256::ZERO,
+            refund: U256::ZERO,
+            gas_cost: U256::ZERO,
+            error: None,
+        };
+        let mut gas_used = 0;
+        for (tx, can_revert) in txs {
+            let pending = match self.provider.send_raw_transaction(&tx).await {
+                Ok(pending) => pending,
+                // Rejected by the node, e.g. because of a wrong nonce.
+                Err(e) if e.as_error_resp().is_some() => {
+                    return Ok(SimulationOutcome::failed(e.to_string()));
+                }
+                Err(e) => return Err(e.into()),
+            };
+            // Auto mining includes the transaction right away.
+            let receipt = self
+                .provider
+                .get_transaction_receipt(*pending.tx_hash())
+                .await?;
+            let Some(receipt) = receipt else {
+                return Ok(SimulationOutcome::failed(format!(
+                    "transaction {} wasn't mined",
+                    pending.tx_hash()
+                )));
+            };
+            gas_used += receipt.gas_used();
+            if !receipt.status() && !can_revert {
+                outcome.success = false;
+                outcome.error = Some(format!(
+                    "transaction {} reverted",
+                    pending.tx_hash()
+                ));
+                break;
+            }
+        }
+        outcome.profit = self
+            .provider
+            .get_balance(coinbase)
+            .await?
+            .saturating_sub(balance);
+        outcome.gas_cost = U256::from(gas_used) * U256::from(base_fee);
+        Ok(outcome)
+    }
+}
+
+#[async_trait]
+impl Simulator<SubmitBundle> for AnvilSimulator {
+    async fn simulate(
+        &self,
+        bundle: &SubmitBundle,
+    ) -> Result<SimulationOutcome, KazukaError> {
+        let txs = match bundle_txs(bundle) {
+            Ok(txs) => txs,
+            Err(e) => return Ok(SimulationOutcome::failed(e)),
+        };
+        let snapshot = self.provider.anvil_snapshot().await?;
+        let outcome = self.execute(txs).await;
+        self.provider.anvil_revert(snapshot).await?;
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::B256, rpc::types::mev::Inclusion};
+
+    use super::*;
+
+    #[test]
+    fn test_net_profit() {
+        let outcome = SimulationOutcome {
+            success: true,
+            profit: U256::from(1000),
+            refund: U256::from(600),
+            gas_cost: U256::from(300),
+            error: None,
+        };
+        assert_eq!(outcome.net_profit(), U256::from(100));
+
+        let outcome = SimulationOutcome {
+            gas_cost: U256::from(500),
+            ..outcome
+        };
+        assert_eq!(outcome.net_profit(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_bundle_txs() {
+        let tx = Bytes::from_static(&[1]);
+        let bundle = SubmitBundle::Eth(EthSendBundle {
+            txs: vec![tx.clone(), Bytes::from_static(&[2])],
+            reverting_tx_hashes: vec![keccak256(&tx)],
+            ..Default::default()
+        });
+        assert_eq!(
+            bundle_txs(&bundle).unwrap(),
+            vec![(tx.clone(), true), (Bytes::from_static(&[2]), false)]
+        );
+
+        let bundle = SubmitBundle::Mev(MevSendBundle {
+            protocol_version: Default::default(),
+            inclusion: Inclusion {
+                block: 1,
+                max_block: None,
+            },
+            bundle_body: vec![
+                BundleItem::Hash { hash: B256::ZERO },
+                BundleItem::Tx {
+                    tx,
+                    can_revert: false,
+                },
+            ],
+            validity: None,
+            privacy: None,
+        });
+        assert!(bundle_txs(&bundle).is_err());
+    }
+}
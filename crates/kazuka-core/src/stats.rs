@@ -0,0 +1,150 @@
+//! Statistics of the event bus of the [Engine](crate::engine::Engine), e.g.
+//! to find out why a strategy isn't seeing events:
+//! - events sent by every event source, and when the last one was sent,
+//! - messages received and skipped by every strategy and executor, which skip
+//!   the oldest messages when they fall behind by more than the capacity of
+//!   their channel,
+//! - actions executed by every executor.
+//!
+//! The statistics are also exported as metrics.
+//!
+//! ```ignore
+//! let engine = Engine::new().add_strategy(strategy);
+//! let stats = engine.stats().clone();
+//! let tasks = engine.run().await?;
+//! println!("{:?}", stats.snapshot().receivers["strategy_0"]);
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+/// Activity of an event source.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SourceStats {
+    /// Events sent to the strategies.
+    pub events: u64,
+    pub last_event_at: Option<SystemTime>,
+}
+
+/// Activity of a strategy or executor receiving from the event bus.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReceiverStats {
+    /// Events or actions received.
+    pub received: u64,
+    /// Events or actions skipped after falling behind.
+    pub lagged: u64,
+    pub last_received_at: Option<SystemTime>,
+}
+
+/// Actions handled by an executor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExecutorStats {
+    pub executed: u64,
+    pub failed: u64,
+}
+
+/// Snapshot of the [EngineStats], by component name, e.g. `strategy_0`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EngineStatsSnapshot {
+    pub sources: BTreeMap<String, SourceStats>,
+    pub receivers: BTreeMap<String, ReceiverStats>,
+    pub executors: BTreeMap<String, ExecutorStats>,
+}
+
+/// Statistics of the event bus, shared by the engine and its observers.
+#[derive(Clone, Debug, Default)]
+pub struct EngineStats {
+    inner: Arc<Mutex<EngineStatsSnapshot>>,
+}
+
+impl EngineStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an event sent by the event source.
+    pub fn record_event(&self, source: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let stats = inner.sources.entry(source.to_string()).or_default();
+        stats.events += 1;
+        stats.last_event_at = Some(SystemTime::now());
+        metrics::counter!("kazuka_engine_events_total", "source" => source.to_string())
+            .increment(1);
+    }
+
+    /// Records an event or action received by the strategy or executor.
+    pub fn record_received(&self, receiver: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let stats = inner.receivers.entry(receiver.to_string()).or_default();
+        stats.received += 1;
+        stats.last_received_at = Some(SystemTime::now());
+        metrics::counter!("kazuka_engine_received_total", "receiver" => receiver.to_string())
+            .increment(1);
+    }
+
+    /// Records events or actions skipped by the strategy or executor.
+    pub fn record_lagged(&self, receiver: &str, skipped: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .receivers
+            .entry(receiver.to_string())
+            .or_default()
+            .lagged += skipped;
+        metrics::counter!("kazuka_engine_lagged_total", "receiver" => receiver.to_string())
+            .increment(skipped);
+    }
+
+    /// Records the outcome of an action executed by the executor.
+    pub fn record_executed(&self, executor: &str, ok: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        let stats = inner.executors.entry(executor.to_string()).or_default();
+        if ok {
+            stats.executed += 1;
+        } else {
+            stats.failed += 1;
+        }
+        let status = if ok { "ok" } else { "error" };
+        metrics::counter!("kazuka_engine_actions_total", "executor" => executor.to_string(), "status" => status)
+            .increment(1);
+    }
+
+    pub fn snapshot(&self) -> EngineStatsSnapshot {
+        self.inner.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_stats() {
+        let stats = EngineStats::new();
+        stats.record_event("event_source_0");
+        stats.clone().record_event("event_source_0");
+        stats.record_received("strategy_0");
+        stats.record_lagged("strategy_0", 3);
+        stats.record_executed("executor_0", true);
+        stats.record_executed("executor_0", false);
+
+        let snapshot = stats.snapshot();
+        let source = snapshot.sources["event_source_0"];
+        assert_eq!(source.events, 2);
+        assert!(source.last_event_at.is_some());
+        let receiver = snapshot.receivers["strategy_0"];
+        assert_eq!(
+            (receiver.received, receiver.lagged),
+            (1, 3)
+        );
+        assert_eq!(
+            snapshot.executors["executor_0"],
+            ExecutorStats {
+                executed: 1,
+                failed: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,464 @@
+//! Strategy backrunning MEV-Share hints, which only needs to be told what
+//! transaction to append to a hinted one.
+//!
+//! [BackrunStrategy] takes care of:
+//! - the intake of the hints, skipping the ones without logs,
+//! - matching the pools of the hints against the targets of the evaluator,
+//! - trying the backrun sizes concurrently, up to a number of bundles per hint,
+//! - assembling the bundles and their submission window,
+//!
+//! while its [OpportunityEvaluator] builds the backrun transactions.
+
+use std::{borrow::Borrow, sync::Arc};
+
+use alloy::{
+    network::{AnyNetwork, Network},
+    primitives::{Address, B256, BlockNumber, Bytes, U256},
+    providers::Provider,
+    rpc::types::mev::{MevSendBundle, ProtocolVersion},
+};
+use async_trait::async_trait;
+use futures::{StreamExt, future, stream};
+use kazuka_mev_share::{
+    rpc::{bundle::BundleBuilder, protocol::BundleValidationError},
+    sse,
+};
+
+use crate::{
+    error::KazukaError,
+    executors::flashbots_bundle_executor::{AttributedBundle, SubmitBundle},
+    pnl::BundleOrigin,
+    types::Strategy,
+};
+
+/// Maximum number of backrun txs of an opportunity built concurrently.
+const MAX_CONCURRENT_TXS: usize = 8;
+
+/// A hinted transaction touching a pool targeted by the evaluator.
+#[derive(Clone, Copy, Debug)]
+pub struct Opportunity<'a> {
+    pub hint: &'a sse::Event,
+    pub pool: Address,
+    /// Latest block, the bundles target the next one.
+    pub block_number: BlockNumber,
+}
+
+/// Decides which hints to backrun, and how.
+#[async_trait]
+pub trait OpportunityEvaluator: Send + Sync {
+    /// Loads the state of the evaluator, e.g. the pools it targets.
+    async fn sync_state(&mut self) -> Result<(), KazukaError> {
+        Ok(())
+    }
+
+    /// Whether hints touching the pool are worth backrunning.
+    async fn is_target(&mut self, pool: Address) -> bool;
+
+    /// Sizes of the backruns of the opportunity, most promising first.
+    /// Defaults to the [sizes](BackrunConfig::sizes) of the strategy.
+    async fn sizes(
+        &mut self,
+        _opportunity: &Opportunity<'_>,
+        sizes: &[U256],
+    ) -> Result<Vec<U256>, KazukaError> {
+        Ok(sizes.to_vec())
+    }
+
+    /// Signed transaction backrunning the opportunity with the size, or
+    /// `None` if the size isn't worth a bundle. Txs of the sizes of an
+    /// opportunity are built concurrently.
+    async fn backrun_tx(
+        &self,
+        opportunity: &Opportunity<'_>,
+        size: U256,
+    ) -> Result<Option<Bytes>, KazukaError>;
+
+    /// Profit (in wei) expected from the backrun of the opportunity with the
+    /// size, zero if unknown.
+    fn expected_profit(
+        &self,
+        _opportunity: &Opportunity<'_>,
+        _size: U256,
+    ) -> U256 {
+        U256::ZERO
+    }
+
+    /// Called with every backrun tx which is bundled, e.g. to track the
+    /// outcome of its bundle.
+    fn on_backrun(
+        &mut self,
+        _opportunity: &Opportunity<'_>,
+        _size: U256,
+        _tx: &Bytes,
+    ) {
+    }
+}
+
+/// Parameters of the bundles of a [BackrunStrategy].
+#[derive(Clone, Debug)]
+pub struct BackrunConfig {
+    /// Sizes (in wei) of the backruns, unless the evaluator picks its own.
+    pub sizes: Vec<U256>,
+    /// Maximum number of bundles submitted for a single hint.
+    pub max_bundles_per_event: usize,
+    /// Number of blocks the bundles are valid for.
+    pub inclusion_window: u64,
+    /// Protocol version of the bundles, which must be accepted by the relay.
+    pub protocol_version: ProtocolVersion,
+}
+
+impl Default for BackrunConfig {
+    /// Every power of ten from 1e5 to 1e18 wei, valid for 30 blocks.
+    fn default() -> Self {
+        Self {
+            sizes: (5..=18)
+                .map(|exp| U256::from(10).pow(U256::from(exp)))
+                .collect(),
+            max_bundles_per_event: 14,
+            inclusion_window: 30,
+            protocol_version: ProtocolVersion::V0_1,
+        }
+    }
+}
+
+/// Bundle backrunning a hint, with the profit the evaluator expects from it.
+#[derive(Clone, Debug)]
+pub struct BackrunBundle {
+    pub bundle: MevSendBundle,
+    /// Profit (in wei), zero if unknown.
+    pub expected_profit: U256,
+}
+
+impl BackrunBundle {
+    /// The bundle, submitted on behalf of the strategy.
+    pub fn attributed(self, strategy: impl Into<String>) -> AttributedBundle {
+        AttributedBundle::new(
+            SubmitBundle::Mev(self.bundle),
+            BundleOrigin::new(strategy, self.expected_profit),
+        )
+    }
+}
+
+impl Borrow<MevSendBundle> for BackrunBundle {
+    fn borrow(&self) -> &MevSendBundle {
+        &self.bundle
+    }
+}
+
+/// Bundle of the hinted transaction followed by the backrun, valid from the
+/// block after `block_number` for `inclusion_window` blocks.
+pub fn backrun_bundle(
+    block_number: BlockNumber,
+    inclusion_window: u64,
+    protocol_version: ProtocolVersion,
+    tx_hash: B256,
+    tx: Bytes,
+) -> Result<MevSendBundle, BundleValidationError> {
+    BundleBuilder::for_block(block_number + 1)
+        // Set a large validity window to ensure builder gets a chance to
+        // include bundle.
+        .max_block(block_number + inclusion_window)
+        .protocol_version(protocol_version)
+        .backrun_of(tx_hash)
+        .tx(tx)
+        .build()
+}
+
+/// Backruns the MEV-Share hints with the transactions of the evaluator.
+pub struct BackrunStrategy<E: OpportunityEvaluator, N: Network = AnyNetwork> {
+    /// Fetches the latest block the bundles are targeted after.
+    provider: Arc<dyn Provider<N>>,
+    evaluator: E,
+    config: BackrunConfig,
+}
+
+impl<E: OpportunityEvaluator, N: Network> BackrunStrategy<E, N> {
+    pub fn new(provider: Arc<dyn Provider<N>>, evaluator: E) -> Self {
+        Self {
+            provider,
+            evaluator,
+            config: BackrunConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: BackrunConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+
+    pub fn evaluator_mut(&mut self) -> &mut E {
+        &mut self.evaluator
+    }
+
+    /// Bundle parameters, which can be adapted between hints.
+    pub fn config_mut(&mut self) -> &mut BackrunConfig {
+        &mut self.config
+    }
+
+    /// Pools of the hint targeted by the evaluator, in order of appearance.
+    async fn targets(&mut self, hint: &sse::Event) -> Vec<Address> {
+        let mut targets = vec![];
+        for log in &hint.logs {
+            if !targets.contains(&log.address)
+                && self.evaluator.is_target(log.address).await
+            {
+                targets.push(log.address);
+            }
+        }
+        targets
+    }
+
+    /// Bundles backrunning the hint, when `block_number` is the latest
+    /// block.
+    pub async fn backrun(
+        &mut self,
+        hint: &sse::Event,
+        block_number: BlockNumber,
+    ) -> Vec<BackrunBundle> {
+        let mut bundles = vec![];
+        for pool in self.targets(hint).await {
+            if bundles.len() >= self.config.max_bundles_per_event {
+                break;
+            }
+            let opportunity = Opportunity {
+                hint,
+                pool,
+                block_number,
+            };
+            let sizes = match self
+                .evaluator
+                .sizes(&opportunity, &self.config.sizes)
+                .await
+            {
+                Ok(sizes) => sizes,
+                Err(e) => {
+                    tracing::error!(?pool, "Error sizing backruns: {:?}", e);
+                    continue;
+                }
+            };
+            let remaining = self
+                .config
+                .max_bundles_per_event
+                .saturating_sub(bundles.len());
+            let evaluator = &self.evaluator;
+            let txs: Vec<(U256, Bytes)> = stream::iter(sizes)
+                .map(|size| async move {
+                    (
+                        size,
+                        evaluator.backrun_tx(&opportunity, size).await,
+                    )
+                })
+                .buffered(MAX_CONCURRENT_TXS)
+                .filter_map(|(size, tx)| {
+                    future::ready(match tx {
+                        Ok(tx) => tx.map(|tx| (size, tx)),
+                        Err(e) => {
+                            tracing::error!(
+                                ?pool,
+                                "Error building backrun of size {}: {:?}",
+                                size,
+                                e
+                            );
+                            None
+                        }
+                    })
+                })
+                .take(remaining)
+                .collect()
+                .await;
+            for (size, tx) in txs {
+                match backrun_bundle(
+                    block_number,
+                    self.config.inclusion_window,
+                    self.config.protocol_version.clone(),
+                    hint.hash,
+                    tx.clone(),
+                ) {
+                    Ok(bundle) => {
+                        self.evaluator.on_backrun(&opportunity, size, &tx);
+                        bundles.push(BackrunBundle {
+                            bundle,
+                            expected_profit: self
+                                .evaluator
+                                .expected_profit(&opportunity, size),
+                        });
+                    }
+                    Err(err) => {
+                        tracing::error!("Skipping invalid bundle: {}", err)
+                    }
+                }
+            }
+        }
+        bundles
+    }
+}
+
+#[async_trait]
+impl<E: OpportunityEvaluator, N: Network> Strategy<sse::Event, BackrunBundle>
+    for BackrunStrategy<E, N>
+{
+    async fn sync_state(&mut self) -> Result<(), KazukaError> {
+        self.evaluator.sync_state().await
+    }
+
+    async fn process_event(&mut self, hint: sse::Event) -> Vec<BackrunBundle> {
+        if hint.logs.is_empty() {
+            return vec![];
+        }
+        let block_number = match self.provider.get_block_number().await {
+            Ok(block_number) => block_number,
+            Err(e) => {
+                tracing::error!("Error fetching block number: {}", e);
+                return vec![];
+            }
+        };
+        self.backrun(&hint, block_number).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::U64,
+        providers::ProviderBuilder,
+        rpc::types::mev::{BundleItem, mevshare::EventTransactionLog},
+        transports::mock::Asserter,
+    };
+
+    use super::*;
+
+    /// Backruns every size of the targeted pool but the second one.
+    struct StubEvaluator {
+        target: Address,
+        /// Sizes of the bundled backruns.
+        backruns: Vec<U256>,
+    }
+
+    #[async_trait]
+    impl OpportunityEvaluator for StubEvaluator {
+        async fn is_target(&mut self, pool: Address) -> bool {
+            pool == self.target
+        }
+
+        async fn backrun_tx(
+            &self,
+            _opportunity: &Opportunity<'_>,
+            size: U256,
+        ) -> Result<Option<Bytes>, KazukaError> {
+            Ok((size != U256::from(2))
+                .then(|| Bytes::from(size.to_be_bytes_vec())))
+        }
+
+        fn expected_profit(
+            &self,
+            _opportunity: &Opportunity<'_>,
+            size: U256,
+        ) -> U256 {
+            size * U256::from(10)
+        }
+
+        fn on_backrun(
+            &mut self,
+            _opportunity: &Opportunity<'_>,
+            size: U256,
+            _tx: &Bytes,
+        ) {
+            self.backruns.push(size);
+        }
+    }
+
+    fn hint(pools: &[Address]) -> sse::Event {
+        sse::Event {
+            hash: B256::repeat_byte(0xaa),
+            logs: pools
+                .iter()
+                .map(|pool| EventTransactionLog {
+                    address: *pool,
+                    topics: vec![],
+                })
+                .collect(),
+            transactions: vec![],
+        }
+    }
+
+    fn strategy(asserter: Asserter) -> BackrunStrategy<StubEvaluator> {
+        let provider: Arc<dyn Provider<AnyNetwork>> = Arc::new(
+            ProviderBuilder::new()
+                .network::<AnyNetwork>()
+                .connect_mocked_client(asserter),
+        );
+        BackrunStrategy::new(
+            provider,
+            StubEvaluator {
+                target: Address::repeat_byte(1),
+                backruns: vec![],
+            },
+        )
+        .with_config(BackrunConfig {
+            sizes: (1..=4).map(U256::from).collect(),
+            max_bundles_per_event: 2,
+            inclusion_window: 3,
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_backrun_targets() {
+        let asserter = Asserter::new();
+        asserter.push_success(&U64::from(100));
+        let mut strategy = strategy(asserter);
+        let target = Address::repeat_byte(1);
+
+        let bundles = strategy
+            .process_event(hint(&[
+                Address::repeat_byte(2),
+                target,
+                target,
+            ]))
+            .await;
+
+        assert_eq!(bundles.len(), 2);
+        assert_eq!(
+            strategy.evaluator().backruns,
+            [U256::from(1), U256::from(3)]
+        );
+        for (backrun, size) in bundles.iter().zip([1, 3]) {
+            assert_eq!(
+                backrun.expected_profit,
+                U256::from(size * 10)
+            );
+            let bundle = &backrun.bundle;
+            assert_eq!(bundle.inclusion.block, 101);
+            assert_eq!(bundle.inclusion.max_block, Some(103));
+            assert_eq!(
+                bundle.bundle_body,
+                [
+                    BundleItem::Hash {
+                        hash: B256::repeat_byte(0xaa)
+                    },
+                    BundleItem::Tx {
+                        tx: Bytes::from(U256::from(size).to_be_bytes_vec()),
+                        can_revert: false,
+                    },
+                ]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_skip_untargeted_hints() {
+        let mut strategy = strategy(Asserter::new());
+
+        assert!(strategy.process_event(hint(&[])).await.is_empty());
+        assert!(
+            strategy
+                .backrun(&hint(&[Address::repeat_byte(2)]), 100)
+                .await
+                .is_empty()
+        );
+    }
+}
@@ -1 +1,463 @@
+//! Telemetry of the bots:
+//! - logging setup shared by the binaries, see [init_logging],
+//! - export of the spans to an OpenTelemetry collector over OTLP, see
+//!   [OtlpConfig] (requires the `otlp` feature),
+//! - metrics of the data sources, see [SseMetrics].
 
+use std::{path::PathBuf, str::FromStr};
+
+use kazuka_mev_share::sse::{ConnectionState, SseObserver};
+use serde::Deserialize;
+use tracing::Level;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    Layer, Registry, filter::Targets, fmt::writer::BoxMakeWriter,
+    layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+use crate::error::KazukaError;
+
+/// Targets of the kazuka crates, whose logs are kept at the configured
+/// level. Logs of other crates are dropped.
+pub const TARGETS: &[&str] = &[
+    "kazuka_core",
+    "kazuka_mev_share",
+    "kazuka_mev_share_arbitrage",
+    "kazuka_mev_share_backend",
+    "kazuka_mev_share_rpc_api",
+    "kazuka_mev_share_sse",
+    "kazuka_liquidation",
+];
+
+/// Format of the logs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Multi-line human-readable logs.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = KazukaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(KazukaError::ConfigError(format!(
+                "invalid log format {s}, expected json or pretty"
+            ))),
+        }
+    }
+}
+
+/// How often a new log file is started.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+impl From<LogRotation> for Rotation {
+    fn from(rotation: LogRotation) -> Self {
+        match rotation {
+            LogRotation::Minutely => Rotation::MINUTELY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+/// Logging settings.
+///
+/// ```toml
+/// level = "debug"
+/// format = "json"
+/// directory = "logs"
+/// rotation = "hourly"
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Level of the logs of the [TARGETS], e.g. `info` or `debug`.
+    pub level: String,
+    pub format: LogFormat,
+    /// Whether to color the pretty logs written to stdout.
+    pub ansi: bool,
+    /// Directory to write the logs to instead of stdout.
+    pub directory: Option<PathBuf>,
+    /// Prefix of the names of the log files.
+    pub file_prefix: String,
+    pub rotation: LogRotation,
+    /// Exports the spans to an OpenTelemetry collector.
+    pub otlp: Option<OtlpConfig>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::default(),
+            ansi: true,
+            directory: None,
+            file_prefix: "kazuka.log".to_string(),
+            rotation: LogRotation::default(),
+            otlp: None,
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_level(mut self, level: impl Into<String>) -> Self {
+        self.level = level.into();
+        self
+    }
+
+    pub fn with_format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+
+    /// Writes the logs to rotated files in the directory.
+    pub fn with_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: LogRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_otlp(mut self, otlp: OtlpConfig) -> Self {
+        self.otlp = Some(otlp);
+        self
+    }
+
+    pub fn level(&self) -> Result<Level, KazukaError> {
+        self.level.parse().map_err(|_| {
+            KazukaError::ConfigError(format!(
+                "invalid log level {}",
+                self.level
+            ))
+        })
+    }
+
+    /// Filter keeping the logs of the [TARGETS] and the extra targets, e.g.
+    /// the binary itself, at the configured level.
+    pub fn targets(&self, extra: &[&str]) -> Result<Targets, KazukaError> {
+        let level = self.level()?;
+        Ok(Targets::new().with_targets(
+            TARGETS
+                .iter()
+                .chain(extra)
+                .map(|target| (target.to_string(), level)),
+        ))
+    }
+}
+
+/// OTLP export of the spans, e.g. to Grafana Tempo, and of the metrics
+/// recorded as fields of the logs, e.g.
+/// `monotonic_counter.kazuka_bundles_submitted = 1`.
+///
+/// ```toml
+/// endpoint = "http://tempo:4317"
+/// service_name = "kazuka-mainnet"
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct OtlpConfig {
+    /// gRPC endpoint of the collector.
+    pub endpoint: String,
+    /// `service.name` of the exported spans and metrics.
+    pub service_name: String,
+    /// Whether to export the metrics as well as the spans.
+    pub metrics: bool,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://localhost:4317".to_string(),
+            service_name: "kazuka".to_string(),
+            metrics: true,
+        }
+    }
+}
+
+/// Keeps the exporters of the logs running, see [init_logging].
+#[must_use]
+#[derive(Default)]
+pub struct TelemetryGuard {
+    _file: Option<WorkerGuard>,
+    #[cfg(feature = "otlp")]
+    otlp: Option<otlp::Providers>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(providers) = self.otlp.take() {
+            providers.shutdown();
+        }
+    }
+}
+
+type BoxLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Installs the global subscriber of the logs, see [LogConfig::targets].
+///
+/// Logs written to files, and spans exported over OTLP, are flushed in the
+/// background until the returned guard is dropped, so it should be held
+/// until exit.
+///
+/// ```ignore
+/// let _guard = init_logging(&config, &["kazuka_simple_arbitrage"])?;
+/// ```
+pub fn init_logging(
+    config: &LogConfig,
+    extra_targets: &[&str],
+) -> Result<TelemetryGuard, KazukaError> {
+    let targets = config.targets(extra_targets)?;
+    let mut guard = TelemetryGuard::default();
+    let writer = match &config.directory {
+        Some(directory) => {
+            let appender = RollingFileAppender::new(
+                config.rotation.into(),
+                directory,
+                &config.file_prefix,
+            );
+            let (writer, file) = tracing_appender::non_blocking(appender);
+            guard._file = Some(file);
+            BoxMakeWriter::new(writer)
+        }
+        None => BoxMakeWriter::new(std::io::stdout),
+    };
+    // Colors would end up as escape codes in the files.
+    let ansi = config.ansi && config.directory.is_none();
+    let mut layers: Vec<BoxLayer> = vec![match config.format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_ansi(ansi)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_writer(writer)
+            .boxed(),
+    }];
+
+    match &config.otlp {
+        #[cfg(feature = "otlp")]
+        Some(otlp_config) => {
+            let (providers, otlp_layers) = otlp::init(otlp_config)?;
+            guard.otlp = Some(providers);
+            layers.extend(otlp_layers);
+        }
+        #[cfg(not(feature = "otlp"))]
+        Some(_) => {
+            return Err(KazukaError::ConfigError(
+                "OTLP export requires the otlp feature".to_string(),
+            ));
+        }
+        None => {}
+    }
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .with(targets)
+        .try_init()
+        .map_err(|err| KazukaError::ConfigError(err.to_string()))?;
+    Ok(guard)
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use opentelemetry::{global, trace::TracerProvider};
+    use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{
+        Resource, metrics::SdkMeterProvider, trace::SdkTracerProvider,
+    };
+    use tracing_opentelemetry::{MetricsLayer, OpenTelemetryLayer};
+    use tracing_subscriber::Layer;
+
+    use super::{BoxLayer, OtlpConfig};
+    use crate::error::KazukaError;
+
+    /// Providers batching the spans and metrics to the collector.
+    pub(super) struct Providers {
+        tracer: SdkTracerProvider,
+        meter: Option<SdkMeterProvider>,
+    }
+
+    impl Providers {
+        /// Flushes the pending spans and metrics.
+        pub(super) fn shutdown(self) {
+            if let Err(err) = self.tracer.shutdown() {
+                eprintln!("Failed to flush the spans: {err}");
+            }
+            if let Some(meter) = self.meter
+                && let Err(err) = meter.shutdown()
+            {
+                eprintln!("Failed to flush the metrics: {err}");
+            }
+        }
+    }
+
+    pub(super) fn init(
+        config: &OtlpConfig,
+    ) -> Result<(Providers, Vec<BoxLayer>), KazukaError> {
+        let error = |err: &dyn std::fmt::Display| {
+            KazukaError::ConfigError(format!("OTLP exporter: {err}"))
+        };
+        let resource = Resource::builder()
+            .with_service_name(config.service_name.clone())
+            .build();
+
+        let exporter = SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(&config.endpoint)
+            .build()
+            .map_err(|err| error(&err))?;
+        let tracer = SdkTracerProvider::builder()
+            .with_resource(resource.clone())
+            .with_batch_exporter(exporter)
+            .build();
+        global::set_tracer_provider(tracer.clone());
+        let mut layers =
+            vec![OpenTelemetryLayer::new(tracer.tracer("kazuka")).boxed()];
+
+        let meter = if config.metrics {
+            let exporter = MetricExporter::builder()
+                .with_tonic()
+                .with_endpoint(&config.endpoint)
+                .build()
+                .map_err(|err| error(&err))?;
+            let meter = SdkMeterProvider::builder()
+                .with_resource(resource)
+                .with_periodic_exporter(exporter)
+                .build();
+            global::set_meter_provider(meter.clone());
+            layers.push(MetricsLayer::new(meter.clone()).boxed());
+            Some(meter)
+        } else {
+            None
+        };
+
+        Ok((Providers { tracer, meter }, layers))
+    }
+}
+
+/// [SseObserver] exporting SSE stream activity as metrics, labeled by
+/// endpoint.
+///
+/// ```ignore
+/// let source = MevShareEventSource::new(url)
+///     .with_observer(Arc::new(SseMetrics));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SseMetrics;
+
+impl SseObserver for SseMetrics {
+    fn on_bytes(&self, endpoint: &str, bytes: usize) {
+        metrics::counter!("kazuka_sse_bytes_total", "endpoint" => endpoint.to_string())
+            .increment(bytes as u64);
+    }
+
+    fn on_event(&self, endpoint: &str) {
+        metrics::counter!("kazuka_sse_events_total", "endpoint" => endpoint.to_string())
+            .increment(1);
+    }
+
+    fn on_decode_error(&self, endpoint: &str) {
+        metrics::counter!("kazuka_sse_decode_errors_total", "endpoint" => endpoint.to_string())
+            .increment(1);
+    }
+
+    fn on_reconnect(&self, endpoint: &str, _retry: u64) {
+        metrics::counter!("kazuka_sse_reconnects_total", "endpoint" => endpoint.to_string())
+            .increment(1);
+    }
+
+    fn on_dropped(&self, endpoint: &str) {
+        metrics::counter!("kazuka_sse_dropped_total", "endpoint" => endpoint.to_string())
+            .increment(1);
+    }
+
+    fn on_state_change(&self, endpoint: &str, state: ConnectionState) {
+        let connected = matches!(state, ConnectionState::Connected);
+        metrics::gauge!("kazuka_sse_connected", "endpoint" => endpoint.to_string())
+            .set(if connected { 1.0 } else { 0.0 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_config() {
+        let config: LogConfig = serde_json::from_str(
+            r#"{"level": "debug", "format": "json", "rotation": "hourly"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            LogConfig::new()
+                .with_level("debug")
+                .with_format(LogFormat::Json)
+                .with_rotation(LogRotation::Hourly)
+        );
+        assert_eq!(
+            "json".parse::<LogFormat>().unwrap(),
+            LogFormat::Json
+        );
+        assert!("yaml".parse::<LogFormat>().is_err());
+        assert!(LogConfig::new().with_level("loud").level().is_err());
+    }
+
+    #[test]
+    fn test_otlp_config() {
+        let config: LogConfig = serde_json::from_str(
+            r#"{"otlp": {"endpoint": "http://tempo:4317"}}"#,
+        )
+        .unwrap();
+        let otlp = config.otlp.unwrap();
+        assert_eq!(otlp.endpoint, "http://tempo:4317");
+        assert_eq!(otlp.service_name, "kazuka");
+        assert!(otlp.metrics);
+    }
+
+    #[test]
+    fn test_targets() {
+        let targets = LogConfig::new()
+            .with_level("debug")
+            .targets(&["kazuka_simple_arbitrage"])
+            .unwrap();
+        assert!(targets.would_enable("kazuka_core::engine", &Level::DEBUG));
+        assert!(targets.would_enable("kazuka_simple_arbitrage", &Level::DEBUG));
+        assert!(!targets.would_enable("kazuka_core", &Level::TRACE));
+        assert!(!targets.would_enable("hyper::client", &Level::ERROR));
+    }
+}
@@ -1,10 +1,27 @@
-use std::pin::Pin;
+use std::{pin::Pin, time::Duration};
 
+use alloy::{
+    network::AnyNetwork,
+    primitives::{ChainId, U256},
+    providers::{DynProvider, Provider},
+};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use futures::Stream;
 use tokio_stream::StreamExt;
 
-use crate::error::KazukaError;
+use crate::{
+    clock::{Clock, SystemClock},
+    error::KazukaError,
+};
+
+/// Name of the type without its module path and generic parameters, e.g.
+/// `MempoolExecutor` for `kazuka_core::executors::MempoolExecutor<P>`.
+pub fn short_type_name<T: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
 
 /// A stream of events emitted by a [EventSource](EventSource).
 pub type EventStream<'a, E> = Pin<Box<dyn Stream<Item = E> + Send + 'a>>;
@@ -15,6 +32,12 @@ pub type EventStream<'a, E> = Pin<Box<dyn Stream<Item = E> + Send + 'a>>;
 pub trait EventSource<E>: Send + Sync {
     async fn get_event_stream(&self)
     -> Result<EventStream<'_, E>, KazukaError>;
+
+    /// Name of the event source in the logs and errors of the engine, the
+    /// name of its type by default.
+    fn name(&self) -> &str {
+        short_type_name::<Self>()
+    }
 }
 
 /// Wraps [EventSource](EventSource) and
@@ -45,12 +68,132 @@ where
         let stream = stream.map(f);
         Ok(Box::pin(stream))
     }
+
+    fn name(&self) -> &str {
+        self.event_source.name()
+    }
+}
+
+impl<E1, E2> EventSourceMap<E1, fn(E1) -> E2>
+where
+    E1: Send + Sync + 'static,
+    E2: Variant<E1> + Send + Sync + 'static,
+{
+    /// Wraps the events into their variant of the application-level event
+    /// enum, see [variants](crate::variants).
+    pub fn for_variant(
+        event_source: Box<dyn EventSource<E1>>,
+    ) -> Box<dyn EventSource<E2>> {
+        Box::new(Self::new(event_source, E2::from_inner))
+    }
+}
+
+/// Event tagged with the id of the chain it originated from.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ChainEvent<E> {
+    pub chain_id: ChainId,
+    pub inner: E,
+}
+
+/// Event stamped with the time it was received at, so that strategies can
+/// tell how long it has been queued for.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Timestamped<E> {
+    pub received_at: DateTime<Utc>,
+    pub inner: E,
+}
+
+impl<E> Timestamped<E> {
+    /// Stamps the event with the current time.
+    pub fn now(inner: E) -> Self {
+        Self::now_on(&SystemClock, inner)
+    }
+
+    /// Stamps the event with the current time of the clock.
+    pub fn now_on(clock: &dyn Clock, inner: E) -> Self {
+        Self {
+            received_at: clock.now(),
+            inner,
+        }
+    }
+
+    /// Time elapsed since the event was received.
+    pub fn age(&self) -> Duration {
+        self.age_on(&SystemClock)
+    }
+
+    /// Time elapsed since the event was received, according to the clock.
+    pub fn age_on(&self, clock: &dyn Clock) -> Duration {
+        (clock.now() - self.received_at)
+            .to_std()
+            .unwrap_or_default()
+    }
+}
+
+/// Wraps [EventSource](EventSource) bound to a specific chain and tags
+/// outgoing events with the chain id, so that a single strategy can consume
+/// events from several chains.
+pub struct ChainEventSource<E> {
+    chain_id: ChainId,
+    event_source: Box<dyn EventSource<E>>,
+}
+
+impl<E> ChainEventSource<E> {
+    pub fn new(
+        chain_id: ChainId,
+        event_source: Box<dyn EventSource<E>>,
+    ) -> Self {
+        Self {
+            chain_id,
+            event_source,
+        }
+    }
+
+    /// Creates a [ChainEventSource](ChainEventSource) tagging events with
+    /// the chain id reported by the given provider.
+    pub async fn for_provider(
+        provider: &DynProvider<AnyNetwork>,
+        event_source: Box<dyn EventSource<E>>,
+    ) -> Result<Self, KazukaError> {
+        let chain_id = provider.get_chain_id().await?;
+        Ok(Self::new(chain_id, event_source))
+    }
+
+    /// The chain id outgoing events are tagged with.
+    pub fn chain_id(&self) -> ChainId {
+        self.chain_id
+    }
+}
+
+#[async_trait]
+impl<E> EventSource<ChainEvent<E>> for ChainEventSource<E>
+where
+    E: Send + Sync + 'static,
+{
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, ChainEvent<E>>, KazukaError> {
+        let stream = self.event_source.get_event_stream().await?;
+        let chain_id = self.chain_id;
+        let stream = stream.map(move |inner| ChainEvent { chain_id, inner });
+        Ok(Box::pin(stream))
+    }
+
+    fn name(&self) -> &str {
+        self.event_source.name()
+    }
 }
 
 /// Executes actions returned by [Strategy](Strategy).
 #[async_trait]
 pub trait Executor<A>: Send + Sync {
     async fn execute(&self, action: A) -> Result<(), KazukaError>;
+
+    /// Name of the executor in the logs and errors of the engine, the name
+    /// of its type by default.
+    fn name(&self) -> &str {
+        short_type_name::<Self>()
+    }
 }
 
 /// Wraps [Executor](Executor) and maps incoming actions to a different type.
@@ -79,6 +222,88 @@ where
             None => Ok(()),
         }
     }
+
+    fn name(&self) -> &str {
+        self.executor.name()
+    }
+}
+
+impl<A1, A2> ExecutorMap<A2, fn(A1) -> Option<A2>>
+where
+    A1: Variant<A2> + Send + Sync + 'static,
+    A2: Send + Sync + 'static,
+{
+    /// Executes the actions of its variant of the application-level action
+    /// enum, and ignores the other ones, see [variants](crate::variants).
+    pub fn for_variant(
+        executor: Box<dyn Executor<A2>>,
+    ) -> Box<dyn Executor<A1>> {
+        Box::new(Self::new(executor, A1::into_inner))
+    }
+}
+
+/// Variant of an application-level event or action enum wrapping the
+/// events or actions of a component, implemented by
+/// [variants](crate::variants).
+pub trait Variant<T>: Sized {
+    fn from_inner(inner: T) -> Self;
+
+    /// Returns the wrapped value, if `self` is this variant.
+    fn into_inner(self) -> Option<T>;
+}
+
+/// Defines an application-level event or action enum, and implements
+/// [Variant] for the type of every variant, so that components are plugged
+/// into the engine with [EventSourceMap::for_variant] and
+/// [ExecutorMap::for_variant]. Types of the variants must be distinct.
+///
+/// ```ignore
+/// kazuka_core::variants! {
+///     #[derive(Clone, Debug)]
+///     pub enum Action {
+///         SubmitBundle(MevSendBundle),
+///         SubmitTx(SubmitTxToMempool),
+///     }
+/// }
+///
+/// let engine = Engine::<Event, Action>::new()
+///     .add_executor(ExecutorMap::for_variant(Box::new(executor)));
+/// ```
+#[macro_export]
+macro_rules! variants {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident($ty:ty)
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant($ty),
+            )*
+        }
+
+        $(
+            impl $crate::types::Variant<$ty> for $name {
+                fn from_inner(inner: $ty) -> Self {
+                    Self::$variant(inner)
+                }
+
+                fn into_inner(self) -> Option<$ty> {
+                    match self {
+                        Self::$variant(inner) => Some(inner),
+                        #[allow(unreachable_patterns)]
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
 }
 
 /// Contains the core logic required for each MEV opportunity.
@@ -94,6 +319,132 @@ pub trait Strategy<E, A>: Send + Sync {
 
     /// Processes an event, and return an action if needed.
     async fn process_event(&mut self, event: E) -> Vec<A>;
+
+    /// Processes an event on a dry run of the engine, see
+    /// [Engine::with_dry_run](crate::engine::Engine::with_dry_run), along
+    /// with the outcomes the strategy expects from the actions, which are
+    /// then compared against simulations. Processes the event as usual and
+    /// expects nothing by default.
+    async fn simulate(&mut self, event: E) -> Vec<SimulatedAction<A>>
+    where
+        E: Send + 'static,
+        A: Send + 'static,
+    {
+        self.process_event(event)
+            .await
+            .into_iter()
+            .map(SimulatedAction::new)
+            .collect()
+    }
+
+    /// Name of the strategy in the logs and errors of the engine, the name
+    /// of its type by default.
+    fn name(&self) -> &str {
+        short_type_name::<Self>()
+    }
+}
+
+/// Action of a [Strategy] on a dry run, along with the outcome the strategy
+/// expects from it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulatedAction<A> {
+    pub action: A,
+    /// Net profit (in wei) the strategy estimated, e.g. from the reserves
+    /// of the pools, if any.
+    pub expected_profit: Option<U256>,
+}
+
+impl<A> SimulatedAction<A> {
+    pub fn new(action: A) -> Self {
+        Self {
+            action,
+            expected_profit: None,
+        }
+    }
+
+    pub fn with_expected_profit(mut self, expected_profit: U256) -> Self {
+        self.expected_profit = Some(expected_profit);
+        self
+    }
+}
+
+/// Wraps an [EventSource], [Strategy] or [Executor] and overrides its
+/// name, e.g. to tell apart executors of the same type.
+///
+/// ```ignore
+/// let engine = Engine::new()
+///     .add_executor(Box::new(Named::new("flashbots", flashbots)))
+///     .add_executor(Box::new(Named::new("titan", titan)));
+/// ```
+pub struct Named<T> {
+    name: String,
+    inner: T,
+}
+
+impl<T> Named<T> {
+    pub fn new(name: impl Into<String>, inner: T) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+        }
+    }
+}
+
+#[async_trait]
+impl<E, T> EventSource<E> for Named<T>
+where
+    T: EventSource<E>,
+{
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, E>, KazukaError> {
+        self.inner.get_event_stream().await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[async_trait]
+impl<E, A, T> Strategy<E, A> for Named<T>
+where
+    E: Send + 'static,
+    T: Strategy<E, A>,
+{
+    async fn sync_state(&mut self) -> Result<(), KazukaError> {
+        self.inner.sync_state().await
+    }
+
+    async fn process_event(&mut self, event: E) -> Vec<A> {
+        self.inner.process_event(event).await
+    }
+
+    async fn simulate(&mut self, event: E) -> Vec<SimulatedAction<A>>
+    where
+        A: Send + 'static,
+    {
+        self.inner.simulate(event).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[async_trait]
+impl<A, T> Executor<A> for Named<T>
+where
+    A: Send + 'static,
+    T: Executor<A>,
+{
+    async fn execute(&self, action: A) -> Result<(), KazukaError> {
+        self.inner.execute(action).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -151,6 +502,35 @@ mod tests {
         )
     }
 
+    // ChainEventSource
+
+    #[tokio::test]
+    async fn test_chain_event_source() {
+        let src: Box<dyn EventSource<Event>> = Box::new(MockEventSource);
+        let chain_event_source = ChainEventSource::new(10, src);
+
+        let stream = chain_event_source
+            .get_event_stream()
+            .await
+            .expect("ChainEventSource didn't return event stream");
+
+        let events: Vec<_> = stream.collect().await;
+
+        assert_eq!(
+            events,
+            vec![
+                ChainEvent {
+                    chain_id: 10,
+                    inner: Event::NewBlock
+                },
+                ChainEvent {
+                    chain_id: 10,
+                    inner: Event::Transaction
+                },
+            ]
+        )
+    }
+
     // ExecutorMap
 
     struct MockExecutor {
@@ -184,4 +564,87 @@ mod tests {
         assert_eq!(result.len(), 1);
         assert_eq!(result[0], Action::SubmitTxToMempool);
     }
+
+    // variants
+
+    crate::variants! {
+        /// Application-level event.
+        #[derive(PartialEq, Debug)]
+        enum AppEvent {
+            Core(Event),
+            Tick(u64),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_variants() {
+        let src: Box<dyn EventSource<Event>> = Box::new(MockEventSource);
+        let map: Box<dyn EventSource<AppEvent>> =
+            EventSourceMap::for_variant(src);
+        let events: Vec<_> =
+            map.get_event_stream().await.unwrap().collect().await;
+        assert_eq!(
+            events,
+            vec![
+                AppEvent::Core(Event::NewBlock),
+                AppEvent::Core(Event::Transaction)
+            ]
+        );
+
+        let actions = Arc::new(Mutex::new(vec![]));
+        let executor: Box<dyn Executor<Action>> = Box::new(MockExecutor {
+            actions: Arc::clone(&actions),
+        });
+        let map: Box<dyn Executor<AppAction>> =
+            ExecutorMap::for_variant(executor);
+        map.execute(AppAction::Core(
+            Action::SubmitTxToMempool,
+        ))
+        .await
+        .unwrap();
+        map.execute(AppAction::Log("ignored")).await.unwrap();
+        assert_eq!(
+            *actions.lock().unwrap(),
+            vec![Action::SubmitTxToMempool]
+        );
+    }
+
+    crate::variants! {
+        enum AppAction {
+            Core(Action),
+            Log(&'static str),
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        let executor = MockExecutor {
+            actions: Arc::new(Mutex::new(vec![])),
+        };
+        assert_eq!(
+            Executor::name(&executor),
+            "MockExecutor"
+        );
+        let map = ExecutorMap::new(Box::new(executor), |action: Action| {
+            Some(action)
+        });
+        assert_eq!(map.name(), "MockExecutor");
+        assert_eq!(
+            EventSource::name(&Named::new("blocks", MockEventSource)),
+            "blocks"
+        );
+        assert_eq!(
+            short_type_name::<ChainEventSource<Option<Event>>>(),
+            "ChainEventSource"
+        );
+    }
+
+    #[test]
+    fn test_timestamped_age() {
+        let mut event = Timestamped::now(Event::NewBlock);
+        assert!(event.age() < Duration::from_secs(1));
+
+        event.received_at -= chrono::TimeDelta::seconds(30);
+        assert!(event.age() >= Duration::from_secs(30));
+    }
 }
@@ -3,38 +3,26 @@ use std::{sync::Arc, time::Duration};
 use alloy::{
     consensus::Transaction,
     eips::BlockId,
-    network::{AnyNetwork, TransactionBuilder},
-    primitives::U256,
-    providers::{DynProvider, Provider, ProviderBuilder, WsConnect},
+    network::TransactionBuilder,
+    primitives::{U256, bytes},
+    providers::Provider,
     rpc::types::TransactionRequest,
     serde::WithOtherFields,
 };
-use alloy_node_bindings::{Anvil, AnvilInstance};
 use futures::StreamExt;
 use kazuka_core::{
     event_sources::{
         block_event_source::BlockEventSource,
+        gas_price_event_source::GasPriceEventSource,
         mempool_event_source::MempoolEventSource,
+        mev_share_event_source::{EventEnricher, MevShareEvent},
     },
     executors::mempool_executor::{MempoolExecutor, SubmitTxToMempool},
     types::{EventSource, Executor},
 };
+use kazuka_test_utils::spawn_anvil;
 use tokio::time::sleep;
 
-/// Spawns Anvil and instantiates a WebSocket provider.
-pub async fn spawn_anvil() -> (DynProvider<AnyNetwork>, AnvilInstance) {
-    let anvil = Anvil::new().block_time(1).spawn();
-    let ws = WsConnect::new(anvil.ws_endpoint_url());
-    let provider = ProviderBuilder::new()
-        .network::<AnyNetwork>()
-        .connect_ws(ws)
-        .await
-        .unwrap();
-
-    let provider = DynProvider::new(provider);
-    (provider, anvil)
-}
-
 /// Test that block event source correctly emits blocks.
 #[tokio::test]
 async fn test_block_event_source_emits_blocks() {
@@ -52,6 +40,32 @@ async fn test_block_event_source_emits_blocks() {
     assert_eq!(block_a.hash, block_b.header.hash);
 }
 
+/// Test that gas price event source emits base fees of new blocks.
+#[tokio::test]
+async fn test_gas_price_event_source_emits_fees() {
+    let (provider, _anvil) = spawn_anvil().await;
+    let provider = Arc::new(provider);
+    let gas_price_event_source =
+        GasPriceEventSource::new(Arc::clone(&provider)).with_window(2);
+    let gas_price_stream =
+        gas_price_event_source.get_event_stream().await.unwrap();
+    let gas_price = gas_price_stream.into_future().await.0.unwrap();
+    let block = provider
+        .get_block(BlockId::number(gas_price.block_number))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        Some(gas_price.base_fee as u64),
+        block.header.base_fee_per_gas
+    );
+    assert_eq!(
+        gas_price.base_fee_moving_average,
+        gas_price.base_fee
+    );
+}
+
 /// Test that mempool event source correctly emits blocks.
 #[tokio::test]
 async fn test_mempool_event_source_emits_txs() {
@@ -102,6 +116,7 @@ async fn test_mempool_executor_sends_tx() {
     let action = SubmitTxToMempool {
         tx: WithOtherFields::new(tx),
         gas_bid_info: None,
+        blob_sidecar: None,
     };
 
     mempool_executor.execute(action).await.unwrap();
@@ -112,3 +127,47 @@ async fn test_mempool_executor_sends_tx() {
     let count = provider.get_transaction_count(alice_address).await.unwrap();
     assert_eq!(count, 1);
 }
+
+/// Test that the event enricher resolves transactions from the node.
+#[tokio::test]
+async fn test_event_enricher_resolves_txs() {
+    let (provider, _anvil) = spawn_anvil().await;
+    let provider = Arc::new(provider);
+
+    let alice_address = provider.get_accounts().await.unwrap()[0];
+    let bob_address = provider.get_accounts().await.unwrap()[1];
+
+    let tx = TransactionRequest::default()
+        .with_from(alice_address)
+        .with_to(bob_address)
+        .with_value(U256::from(42))
+        .with_input(bytes!("a9059cbb0000"));
+    let tx_hash = *provider
+        .send_transaction(WithOtherFields::new(tx))
+        .await
+        .unwrap()
+        .tx_hash();
+
+    let enricher = EventEnricher::new(Arc::clone(&provider));
+    let event = enricher
+        .enrich(MevShareEvent {
+            hash: tx_hash,
+            logs: vec![],
+            transactions: vec![],
+        })
+        .await;
+
+    let tx = &event.transactions[0];
+    assert_eq!(tx.hash, Some(tx_hash));
+    assert_eq!(
+        tx.calldata,
+        Some(bytes!("a9059cbb0000"))
+    );
+    assert_eq!(
+        tx.function_selector.as_ref().map(|selector| selector.0),
+        Some([0xa9, 0x05, 0x9c, 0xbb])
+    );
+    assert_eq!(tx.from, Some(alice_address));
+    assert_eq!(tx.to, Some(bob_address));
+    assert_eq!(tx.value, Some(U256::from(42)));
+}
@@ -0,0 +1,223 @@
+//! Statistics of submitted bundles per strategy, computed from the
+//! [BundleStore].
+//!
+//! ```ignore
+//! let stats = strategy_stats_from(&store, &BundleQuery::new().blocks(from, to)).await?;
+//! println!("{}", to_json(&stats)?);
+//! ```
+
+use std::collections::BTreeMap;
+
+use alloy::{primitives::U256, rpc::types::mev::BundleItem};
+use serde::Serialize;
+
+use crate::store::{
+    BundleQuery, BundleRecord, BundleStore, InclusionStatus, StoreError,
+};
+
+/// Strategy name of records without one.
+pub const UNKNOWN_STRATEGY: &str = "unknown";
+
+/// Statistics of the bundles submitted by a strategy.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrategyStats {
+    pub strategy: String,
+    pub submitted: usize,
+    pub rejected: usize,
+    pub pending: usize,
+    pub included: usize,
+    pub expired: usize,
+    pub outbid: usize,
+    /// Share of the resolved (included, expired or outbid) bundles, which
+    /// have been included.
+    pub inclusion_rate: f64,
+    /// Expected profit per included bundle.
+    pub average_profit: U256,
+    /// Refunds received for included bundles.
+    pub total_refund: U256,
+    /// Share of the refunds in profits and refunds of included bundles.
+    pub refund_share: f64,
+    /// Share of the resolved backruns, whose target transaction has been
+    /// captured by someone else.
+    pub outbid_rate: f64,
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        return 0.0;
+    }
+    numerator as f64 / denominator as f64
+}
+
+fn is_backrun(record: &BundleRecord) -> bool {
+    record
+        .bundle
+        .bundle_body
+        .iter()
+        .any(|item| matches!(item, BundleItem::Hash { .. }))
+}
+
+/// Computes the statistics of every strategy, sorted by strategy name.
+pub fn strategy_stats(records: &[BundleRecord]) -> Vec<StrategyStats> {
+    let mut by_strategy: BTreeMap<&str, Vec<&BundleRecord>> = BTreeMap::new();
+    for record in records {
+        let strategy = record.strategy.as_deref().unwrap_or(UNKNOWN_STRATEGY);
+        by_strategy.entry(strategy).or_default().push(record);
+    }
+    by_strategy
+        .into_iter()
+        .map(|(strategy, records)| stats(strategy, &records))
+        .collect()
+}
+
+fn stats(strategy: &str, records: &[&BundleRecord]) -> StrategyStats {
+    let mut stats = StrategyStats {
+        strategy: strategy.to_string(),
+        submitted: records.len(),
+        ..Default::default()
+    };
+    let mut total_profit = U256::ZERO;
+    let mut resolved_backruns = 0;
+    let mut outbid_backruns = 0;
+    for record in records {
+        match record.status {
+            InclusionStatus::Pending => stats.pending += 1,
+            InclusionStatus::Rejected => stats.rejected += 1,
+            InclusionStatus::Included { .. } => {
+                stats.included += 1;
+                total_profit += record.profit.unwrap_or_default();
+                stats.total_refund += record.refund.unwrap_or_default();
+            }
+            InclusionStatus::Expired => stats.expired += 1,
+            InclusionStatus::Outbid { .. } => stats.outbid += 1,
+        }
+        let resolved = !matches!(
+            record.status,
+            InclusionStatus::Pending | InclusionStatus::Rejected
+        );
+        if resolved && is_backrun(record) {
+            resolved_backruns += 1;
+            if matches!(
+                record.status,
+                InclusionStatus::Outbid { .. }
+            ) {
+                outbid_backruns += 1;
+            }
+        }
+    }
+
+    let resolved = stats.included + stats.expired + stats.outbid;
+    stats.inclusion_rate = ratio(stats.included, resolved);
+    stats.outbid_rate = ratio(outbid_backruns, resolved_backruns);
+    if stats.included > 0 {
+        stats.average_profit = total_profit / U256::from(stats.included);
+    }
+    let total = total_profit + stats.total_refund;
+    if !total.is_zero() {
+        stats.refund_share = f64::from(stats.total_refund) / f64::from(total);
+    }
+    stats
+}
+
+/// Computes the statistics of the records matching the query.
+pub async fn strategy_stats_from(
+    store: &dyn BundleStore,
+    query: &BundleQuery,
+) -> Result<Vec<StrategyStats>, StoreError> {
+    Ok(strategy_stats(
+        &store.query(query).await?,
+    ))
+}
+
+/// Exports the statistics as a JSON array.
+pub fn to_json(stats: &[StrategyStats]) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(stats)
+}
+
+/// Exports the statistics as CSV with a header row.
+#[cfg(feature = "csv")]
+pub fn to_csv(stats: &[StrategyStats]) -> Result<String, csv::Error> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for stats in stats {
+        writer.serialize(stats)?;
+    }
+    let csv = writer.into_inner().map_err(|err| err.into_error())?;
+    Ok(String::from_utf8(csv).expect("CSV is valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{B256, Bytes},
+        rpc::types::mev::{Inclusion, MevSendBundle},
+    };
+
+    use super::*;
+
+    fn record(status: InclusionStatus, profit: u64) -> BundleRecord {
+        let bundle = MevSendBundle {
+            protocol_version: Default::default(),
+            inclusion: Inclusion {
+                block: 1,
+                max_block: None,
+            },
+            bundle_body: vec![
+                BundleItem::Hash { hash: B256::ZERO },
+                BundleItem::Tx {
+                    tx: Bytes::from_static(b"tx"),
+                    can_revert: false,
+                },
+            ],
+            validity: None,
+            privacy: None,
+        };
+        let mut record = BundleRecord::new(B256::ZERO, bundle)
+            .with_strategy("arbitrage")
+            .with_profit(U256::from(profit));
+        record.status = status;
+        record
+    }
+
+    #[test]
+    fn test_strategy_stats() {
+        let mut included = record(
+            InclusionStatus::Included { block_number: 1 },
+            300,
+        );
+        included.refund = Some(U256::from(100));
+        let mut not_backrun = record(
+            InclusionStatus::Outbid { block_number: 1 },
+            0,
+        );
+        not_backrun.bundle.bundle_body.remove(0);
+        let records = [
+            included,
+            record(
+                InclusionStatus::Included { block_number: 2 },
+                100,
+            ),
+            record(InclusionStatus::Expired, 0),
+            record(
+                InclusionStatus::Outbid { block_number: 1 },
+                0,
+            ),
+            record(InclusionStatus::Rejected, 0),
+            // Bundles which aren't backruns don't count towards the outbid
+            // rate.
+            not_backrun,
+        ];
+
+        let stats = strategy_stats(&records);
+
+        assert_eq!(stats.len(), 1);
+        let stats = &stats[0];
+        assert_eq!(stats.strategy, "arbitrage");
+        assert_eq!(stats.submitted, 6);
+        assert_eq!(stats.outbid, 2);
+        assert_eq!(stats.inclusion_rate, 0.4);
+        assert_eq!(stats.average_profit, U256::from(200));
+        assert_eq!(stats.refund_share, 0.2);
+        assert_eq!(stats.outbid_rate, 0.25);
+    }
+}
@@ -0,0 +1,505 @@
+//! Indexer ingesting MEV-Share hints into a [HintStore], for backtesting and
+//! statistics over past hints.
+//!
+//! ```ignore
+//! let store = Arc::new(InMemoryHintStore::new());
+//! let indexer = HintIndexer::new(
+//!     EventClient::default(),
+//!     "https://mev-share.flashbots.net",
+//!     "https://mev-share.flashbots.net/api/v1/history",
+//!     store.clone(),
+//! );
+//! tokio::spawn(indexer.run());
+//!
+//! let day_ago = now() - 24 * 60 * 60;
+//! let hints = store.query(&HintQuery::new().address(pool).since(day_ago)).await?;
+//! ```
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use alloy::{
+    primitives::{Address, TxHash},
+    rpc::types::mev::mevshare::EventHistoryParams,
+};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use kazuka_mev_share_sse::{Event, EventClient};
+use serde::{Deserialize, Serialize};
+
+use crate::store::{StoreError, now};
+
+/// How far back the history is replayed into an empty store.
+const DEFAULT_BACKFILL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Error of the [HintIndexer].
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("failed to connect to the event stream: {0}")]
+    Connect(#[from] reqwest::Error),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+    #[error("event stream ended")]
+    StreamEnded,
+}
+
+/// Hint with the time it was emitted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedHint {
+    /// Block of the hint, only known for hints from the history.
+    pub block: Option<u64>,
+    /// Unix timestamp in seconds.
+    pub timestamp: u64,
+    pub event: Event,
+}
+
+impl IndexedHint {
+    /// Contracts touched by the hint: addresses of its logs and the
+    /// recipients of its transactions.
+    pub fn addresses(&self) -> BTreeSet<Address> {
+        let logs = self.event.logs.iter().map(|log| log.address);
+        let txs = self.event.transactions.iter().filter_map(|tx| tx.to);
+        logs.chain(txs).collect()
+    }
+}
+
+/// Filter of [HintStore::query], matching everything by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HintQuery {
+    /// Only hints touching the address.
+    pub address: Option<Address>,
+    /// Minimal unix timestamp in seconds.
+    pub since: Option<u64>,
+    /// Maximal unix timestamp in seconds.
+    pub until: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl HintQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn address(mut self, address: Address) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    pub fn since(mut self, timestamp: u64) -> Self {
+        self.since = Some(timestamp);
+        self
+    }
+
+    pub fn until(mut self, timestamp: u64) -> Self {
+        self.until = Some(timestamp);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn matches(&self, hint: &IndexedHint) -> bool {
+        self.address
+            .is_none_or(|address| hint.addresses().contains(&address))
+            && self.since.is_none_or(|since| hint.timestamp >= since)
+            && self.until.is_none_or(|until| hint.timestamp <= until)
+    }
+}
+
+/// Storage of [IndexedHint]s.
+#[async_trait]
+pub trait HintStore: Send + Sync {
+    /// Inserts the hint, keeping the existing one with the same hash.
+    async fn insert(&self, hint: IndexedHint) -> Result<(), StoreError>;
+
+    /// Matching hints, most recent first.
+    async fn query(
+        &self,
+        query: &HintQuery,
+    ) -> Result<Vec<IndexedHint>, StoreError>;
+
+    /// Timestamp of the most recent hint.
+    async fn latest_timestamp(&self) -> Result<Option<u64>, StoreError>;
+}
+
+/// [HintStore] keeping hints in memory, e.g. for tests.
+#[derive(Debug, Default)]
+pub struct InMemoryHintStore {
+    hints: Mutex<HashMap<TxHash, IndexedHint>>,
+}
+
+impl InMemoryHintStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl HintStore for InMemoryHintStore {
+    async fn insert(&self, hint: IndexedHint) -> Result<(), StoreError> {
+        self.hints
+            .lock()
+            .unwrap()
+            .entry(hint.event.hash)
+            .or_insert(hint);
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        query: &HintQuery,
+    ) -> Result<Vec<IndexedHint>, StoreError> {
+        let mut hints: Vec<_> = self
+            .hints
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|hint| query.matches(hint))
+            .cloned()
+            .collect();
+        hints.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        hints.truncate(query.limit.unwrap_or(usize::MAX));
+        Ok(hints)
+    }
+
+    async fn latest_timestamp(&self) -> Result<Option<u64>, StoreError> {
+        Ok(self
+            .hints
+            .lock()
+            .unwrap()
+            .values()
+            .map(|hint| hint.timestamp)
+            .max())
+    }
+}
+
+/// Continuously ingests the MEV-Share event stream into a [HintStore].
+///
+/// On start, the history is replayed from the most recent stored hint (or
+/// the backfill period for an empty store), while live events are already
+/// being received, so that no hints are missed in between.
+pub struct HintIndexer {
+    client: EventClient,
+    endpoint: String,
+    history_endpoint: String,
+    store: Arc<dyn HintStore>,
+    backfill: Duration,
+}
+
+impl HintIndexer {
+    pub fn new(
+        client: EventClient,
+        endpoint: impl Into<String>,
+        history_endpoint: impl Into<String>,
+        store: Arc<dyn HintStore>,
+    ) -> Self {
+        Self {
+            client,
+            endpoint: endpoint.into(),
+            history_endpoint: history_endpoint.into(),
+            store,
+            backfill: DEFAULT_BACKFILL,
+        }
+    }
+
+    /// Sets how far back the history is replayed into an empty store.
+    pub fn with_backfill(mut self, backfill: Duration) -> Self {
+        self.backfill = backfill;
+        self
+    }
+
+    /// Indexes hints until the live stream ends or the store fails.
+    pub async fn run(self) -> Result<(), IndexerError> {
+        let mut live = self.client.events(&self.endpoint).await?;
+
+        let since = match self.store.latest_timestamp().await? {
+            Some(timestamp) => timestamp,
+            None => now().saturating_sub(self.backfill.as_secs()),
+        };
+        let params = EventHistoryParams {
+            timestamp_start: Some(since),
+            ..Default::default()
+        };
+        let mut history = self
+            .client
+            .historical_events_stream(&self.history_endpoint, params);
+        let mut replayed = 0;
+        while let Some(event) = history.next().await {
+            match event {
+                Ok(event) => {
+                    self.store
+                        .insert(IndexedHint {
+                            block: Some(event.block),
+                            timestamp: event.timestamp,
+                            event: event.hint,
+                        })
+                        .await?;
+                    replayed += 1;
+                }
+                Err(err) => {
+                    tracing::warn!("Error replaying hint history: {}", err);
+                    break;
+                }
+            }
+        }
+        tracing::info!(replayed, since, "replayed hint history");
+
+        while let Some(event) = live.next().await {
+            match event {
+                Ok(event) => {
+                    self.store
+                        .insert(IndexedHint {
+                            block: None,
+                            timestamp: now(),
+                            event,
+                        })
+                        .await?
+                }
+                Err(err) => tracing::warn!("Error receiving hint: {}", err),
+            }
+        }
+        Err(IndexerError::StreamEnded)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteHintStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::path::Path;
+
+    use rusqlite::{Connection, params};
+
+    use super::*;
+
+    const SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS hints (
+            hash TEXT PRIMARY KEY,
+            block INTEGER,
+            timestamp INTEGER NOT NULL,
+            event TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS hints_timestamp ON hints (timestamp);
+        CREATE TABLE IF NOT EXISTS hint_addresses (
+            hash TEXT NOT NULL,
+            address TEXT NOT NULL,
+            PRIMARY KEY (address, hash)
+        );
+    ";
+
+    /// [HintStore] persisting hints in a SQLite database, indexed by the
+    /// addresses they touch.
+    #[derive(Debug, Clone)]
+    pub struct SqliteHintStore {
+        conn: Arc<Mutex<Connection>>,
+    }
+
+    impl SqliteHintStore {
+        /// Opens (or creates) the database at the given path.
+        pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+            Self::from_connection(Connection::open(path)?)
+        }
+
+        /// Opens a database, which is lost once the store is dropped.
+        pub fn in_memory() -> Result<Self, StoreError> {
+            Self::from_connection(Connection::open_in_memory()?)
+        }
+
+        fn from_connection(conn: Connection) -> Result<Self, StoreError> {
+            conn.execute_batch(SCHEMA)?;
+            Ok(Self {
+                conn: Arc::new(Mutex::new(conn)),
+            })
+        }
+
+        /// Runs the blocking SQLite call off the async runtime.
+        async fn with_conn<T: Send + 'static>(
+            &self,
+            f: impl FnOnce(&mut Connection) -> Result<T, StoreError>
+            + Send
+            + 'static,
+        ) -> Result<T, StoreError> {
+            let conn = Arc::clone(&self.conn);
+            tokio::task::spawn_blocking(move || f(&mut conn.lock().unwrap()))
+                .await
+                .expect("SQLite task panicked")
+        }
+    }
+
+    #[async_trait]
+    impl HintStore for SqliteHintStore {
+        async fn insert(&self, hint: IndexedHint) -> Result<(), StoreError> {
+            self.with_conn(move |conn| {
+                let hash = hint.event.hash.to_string();
+                let tx = conn.transaction()?;
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO hints (hash, block, timestamp, event)
+                        VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        hash,
+                        hint.block,
+                        hint.timestamp,
+                        serde_json::to_string(&hint.event)?,
+                    ],
+                )?;
+                if inserted > 0 {
+                    for address in hint.addresses() {
+                        tx.execute(
+                            "INSERT OR IGNORE INTO hint_addresses (hash, address)
+                                VALUES (?1, ?2)",
+                            params![hash, address.to_string()],
+                        )?;
+                    }
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+        }
+
+        async fn query(
+            &self,
+            query: &HintQuery,
+        ) -> Result<Vec<IndexedHint>, StoreError> {
+            let query = query.clone();
+            self.with_conn(move |conn| {
+                let mut statement = conn.prepare(
+                    "SELECT block, timestamp, event FROM hints
+                        WHERE (?1 IS NULL OR hash IN (
+                            SELECT hash FROM hint_addresses WHERE address = ?1
+                        ))
+                        AND (?2 IS NULL OR timestamp >= ?2)
+                        AND (?3 IS NULL OR timestamp <= ?3)
+                        ORDER BY timestamp DESC
+                        LIMIT ?4",
+                )?;
+                let limit = query.limit.map_or(-1, |limit| limit as i64);
+                let rows = statement.query_map(
+                    params![
+                        query.address.map(|address| address.to_string()),
+                        query.since,
+                        query.until,
+                        limit,
+                    ],
+                    |row| {
+                        Ok((
+                            row.get::<_, Option<u64>>(0)?,
+                            row.get::<_, u64>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    },
+                )?;
+                rows.map(|row| {
+                    let (block, timestamp, event) = row?;
+                    Ok(IndexedHint {
+                        block,
+                        timestamp,
+                        event: serde_json::from_str(&event)?,
+                    })
+                })
+                .collect()
+            })
+            .await
+        }
+
+        async fn latest_timestamp(&self) -> Result<Option<u64>, StoreError> {
+            self.with_conn(|conn| {
+                Ok(conn.query_row(
+                    "SELECT MAX(timestamp) FROM hints",
+                    [],
+                    |row| row.get(0),
+                )?)
+            })
+            .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::B256, rpc::types::mev::mevshare::EventTransactionLog,
+    };
+    use kazuka_mev_share_sse::EventTransaction;
+
+    use super::*;
+
+    fn hint(hash: u8, pool: Address, timestamp: u64) -> IndexedHint {
+        IndexedHint {
+            block: None,
+            timestamp,
+            event: Event {
+                hash: B256::with_last_byte(hash),
+                logs: vec![EventTransactionLog {
+                    address: pool,
+                    topics: vec![],
+                }],
+                transactions: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_hint_addresses() {
+        let pool = Address::repeat_byte(1);
+        let router = Address::repeat_byte(2);
+        let mut hint = hint(1, pool, 0);
+        hint.event.transactions.push(EventTransaction {
+            hash: None,
+            calldata: None,
+            function_selector: None,
+            to: Some(router),
+            from: None,
+            value: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+        });
+        assert_eq!(
+            hint.addresses(),
+            BTreeSet::from([pool, router])
+        );
+    }
+
+    async fn test_query_hints(store: impl HintStore) {
+        let pool = Address::repeat_byte(1);
+        store.insert(hint(1, pool, 100)).await.unwrap();
+        store.insert(hint(2, pool, 200)).await.unwrap();
+        store
+            .insert(hint(3, Address::repeat_byte(2), 300))
+            .await
+            .unwrap();
+
+        let hints = store
+            .query(&HintQuery::new().address(pool).since(150))
+            .await
+            .unwrap();
+        assert_eq!(hints.len(), 1);
+        assert_eq!(
+            hints[0].event.hash,
+            B256::with_last_byte(2)
+        );
+        assert_eq!(
+            store.latest_timestamp().await.unwrap(),
+            Some(300)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store() {
+        test_query_hints(InMemoryHintStore::new()).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_store() {
+        test_query_hints(SqliteHintStore::in_memory().unwrap()).await;
+    }
+}
@@ -1,2 +1,45 @@
-mod rpc;
-mod service;
+//! Reference MEV-Share matchmaker.
+//!
+//! The [Matchmaker] accepts private transactions and bundles over JSON-RPC
+//! ([MatchmakerRpc]), simulates bundles against an Anvil node
+//! ([AnvilSimulator]) and shares hints over SSE, see
+//! [EventServer](kazuka_mev_share_sse::server::EventServer).
+//!
+//! ```ignore
+//! let publisher = EventPublisher::default();
+//! let events = EventServer::bind("127.0.0.1:8080", publisher.clone()).await?;
+//! let matchmaker = Matchmaker::new(AnvilSimulator::new(provider), publisher);
+//! let server = Server::builder().build("127.0.0.1:8545").await?;
+//! let handle = server.start(MatchmakerRpc::new(Arc::new(matchmaker)).into_module());
+//! ```
+
+pub mod analytics;
+pub use analytics::{StrategyStats, strategy_stats};
+
+pub mod indexer;
+#[cfg(feature = "sqlite")]
+pub use indexer::SqliteHintStore;
+pub use indexer::{
+    HintIndexer, HintQuery, HintStore, InMemoryHintStore, IndexedHint,
+    IndexerError,
+};
+
+pub mod rpc;
+pub use rpc::MatchmakerRpc;
+
+pub mod service;
+pub use service::{
+    AnvilSimulator, BundleTx, Matchmaker, MatchmakerError, SimulationResult,
+    Simulator, TxSimulation,
+};
+
+#[cfg(feature = "test-util")]
+pub mod sim_server;
+
+pub mod store;
+#[cfg(feature = "sqlite")]
+pub use store::SqliteBundleStore;
+pub use store::{
+    BundleQuery, BundleRecord, BundleStore, InMemoryBundleStore,
+    InclusionStatus, RelaySubmission, StatusKind, StoreError,
+};
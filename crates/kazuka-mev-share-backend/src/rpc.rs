@@ -1 +1,274 @@
+//! JSON-RPC API of the [Matchmaker].
 
+use std::sync::Arc;
+
+use alloy::{
+    primitives::{B256, Bytes, keccak256},
+    rpc::types::mev::{
+        BundleItem, EthCallBundle, EthCallBundleResponse, EthCancelBundle,
+        EthCancelPrivateTransaction, EthSendBundle, EthSendPrivateTransaction,
+        Inclusion, MevSendBundle, SimBundleOverrides, SimBundleResponse,
+    },
+};
+use async_trait::async_trait;
+use jsonrpsee::{
+    RpcModule,
+    core::RpcResult,
+    types::{
+        ErrorObjectOwned,
+        error::{INVALID_PARAMS_CODE, METHOD_NOT_FOUND_CODE},
+    },
+};
+use kazuka_mev_share_rpc_api::{
+    EthBundleApiServer, MevApiServer,
+    types::{BundleHash, SendBundleResponse},
+};
+
+use crate::service::{Matchmaker, MatchmakerError};
+
+/// Error code of failed simulations and provider errors.
+const SERVER_ERROR_CODE: i32 = -32000;
+
+/// Serves `mev_*` and `eth_*` bundle methods of a [Matchmaker].
+#[derive(Clone)]
+pub struct MatchmakerRpc {
+    matchmaker: Arc<Matchmaker>,
+}
+
+impl MatchmakerRpc {
+    pub fn new(matchmaker: Arc<Matchmaker>) -> Self {
+        Self { matchmaker }
+    }
+
+    /// Merges both APIs into a single module.
+    pub fn into_module(self) -> RpcModule<()> {
+        let mut module = RpcModule::new(());
+        module
+            .merge(MevApiServer::into_rpc(self.clone()))
+            .expect("No conflicting methods");
+        module
+            .merge(EthBundleApiServer::into_rpc(self))
+            .expect("No conflicting methods");
+        module
+    }
+}
+
+pub(crate) fn rpc_error(err: MatchmakerError) -> ErrorObjectOwned {
+    let code = match err {
+        MatchmakerError::InvalidBundle(_)
+        | MatchmakerError::UnknownTransaction(_) => INVALID_PARAMS_CODE,
+        _ => SERVER_ERROR_CODE,
+    };
+    ErrorObjectOwned::owned(code, err.to_string(), None::<()>)
+}
+
+pub(crate) fn unsupported(method: &str) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        METHOD_NOT_FOUND_CODE,
+        format!("{method} is not supported by the matchmaker"),
+        None::<()>,
+    )
+}
+
+#[async_trait]
+impl MevApiServer for MatchmakerRpc {
+    async fn send_bundle(
+        &self,
+        request: MevSendBundle,
+    ) -> RpcResult<SendBundleResponse> {
+        let bundle_hash = self
+            .matchmaker
+            .submit_bundle(request)
+            .await
+            .map_err(rpc_error)?;
+        Ok(SendBundleResponse { bundle_hash })
+    }
+
+    /// Overrides are ignored, bundles are simulated on top of the latest
+    /// block.
+    async fn sim_bundle(
+        &self,
+        bundle: MevSendBundle,
+        _sim_overrides: SimBundleOverrides,
+    ) -> RpcResult<SimBundleResponse> {
+        let result = self
+            .matchmaker
+            .simulate_bundle(&bundle)
+            .await
+            .map_err(rpc_error)?;
+        Ok(SimBundleResponse {
+            success: result.success,
+            error: result.error,
+            state_block: result.state_block,
+            mev_gas_price: result.mev_gas_price(),
+            profit: result.coinbase_diff,
+            refundable_value: result.coinbase_diff,
+            gas_used: result.gas_used,
+            logs: None,
+            exec_error: None,
+            revert: None,
+        })
+    }
+}
+
+#[async_trait]
+impl EthBundleApiServer for MatchmakerRpc {
+    /// Accepted as a `mev_sendBundle` bundle of signed transactions.
+    async fn send_bundle(
+        &self,
+        request: EthSendBundle,
+    ) -> RpcResult<BundleHash> {
+        let bundle_body = request
+            .txs
+            .into_iter()
+            .map(|tx| {
+                let can_revert =
+                    request.reverting_tx_hashes.contains(&keccak256(&tx));
+                BundleItem::Tx { tx, can_revert }
+            })
+            .collect();
+        let bundle = MevSendBundle {
+            protocol_version: Default::default(),
+            inclusion: Inclusion {
+                block: request.block_number,
+                max_block: None,
+            },
+            bundle_body,
+            validity: None,
+            privacy: None,
+        };
+        let bundle_hash = self
+            .matchmaker
+            .submit_bundle(bundle)
+            .await
+            .map_err(rpc_error)?;
+        Ok(BundleHash { bundle_hash })
+    }
+
+    async fn call_bundle(
+        &self,
+        _request: EthCallBundle,
+    ) -> RpcResult<EthCallBundleResponse> {
+        Err(unsupported("eth_callBundle"))
+    }
+
+    async fn cancel_bundle(&self, _request: EthCancelBundle) -> RpcResult<()> {
+        Err(unsupported("eth_cancelBundle"))
+    }
+
+    async fn send_private_transaction(
+        &self,
+        request: EthSendPrivateTransaction,
+    ) -> RpcResult<B256> {
+        Ok(self.matchmaker.submit_transaction(request.tx).await)
+    }
+
+    async fn send_private_raw_transaction(
+        &self,
+        bytes: Bytes,
+    ) -> RpcResult<B256> {
+        Ok(self.matchmaker.submit_transaction(bytes).await)
+    }
+
+    async fn cancel_private_transaction(
+        &self,
+        _request: EthCancelPrivateTransaction,
+    ) -> RpcResult<bool> {
+        Err(unsupported(
+            "eth_cancelPrivateTransaction",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures_util::StreamExt;
+    use jsonrpsee::{http_client::HttpClientBuilder, server::Server};
+    use kazuka_mev_share_rpc_api::{
+        EthBundleApiClient, MevApiClient, bundle::BundleBuilder,
+    };
+    use kazuka_mev_share_sse::{
+        EventClient,
+        server::{EventPublisher, EventServer},
+    };
+
+    use super::*;
+    use crate::service::{BundleTx, SimulationResult, Simulator};
+
+    struct StubSimulator;
+
+    #[async_trait]
+    impl Simulator for StubSimulator {
+        async fn simulate(
+            &self,
+            txs: &[BundleTx],
+        ) -> Result<SimulationResult, MatchmakerError> {
+            Ok(SimulationResult {
+                success: true,
+                error: None,
+                state_block: 1,
+                gas_used: 21_000 * txs.len() as u64,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_matchmaker_rpc() -> anyhow::Result<()> {
+        let publisher = EventPublisher::default();
+        let events =
+            EventServer::bind("127.0.0.1:0", publisher.clone()).await?;
+        let matchmaker = Arc::new(Matchmaker::new(
+            StubSimulator,
+            publisher,
+        ));
+        let server = Server::builder().build("127.0.0.1:0").await?;
+        let url = format!("http://{}", server.local_addr()?);
+        let handle = server.start(MatchmakerRpc::new(matchmaker).into_module());
+
+        let mut hints = EventClient::default()
+            .events(&events.url("/api/v1/mev-share"))
+            .await?;
+        let client = HttpClientBuilder::default()
+            .request_timeout(Duration::from_secs(5))
+            .build(url)?;
+
+        let tx_hash = client
+            .send_private_raw_transaction(Bytes::from_static(b"tx"))
+            .await?;
+        assert_eq!(
+            hints.next().await.unwrap()?.hash,
+            tx_hash
+        );
+
+        let bundle = BundleBuilder::for_block(1)
+            .backrun_of(tx_hash)
+            .tx(Bytes::from_static(b"backrun"))
+            .build()?;
+        let simulation = MevApiClient::sim_bundle(
+            &client,
+            bundle.clone(),
+            SimBundleOverrides::default(),
+        )
+        .await?;
+        assert!(simulation.success);
+        assert_eq!(simulation.gas_used, 42_000);
+
+        let response = MevApiClient::send_bundle(&client, bundle).await?;
+        assert_ne!(response.bundle_hash, B256::ZERO);
+
+        let unsupported = EthBundleApiClient::cancel_bundle(
+            &client,
+            EthCancelBundle {
+                replacement_uuid: String::new(),
+            },
+        )
+        .await;
+        assert!(unsupported.is_err());
+
+        handle.stop()?;
+        Ok(())
+    }
+}
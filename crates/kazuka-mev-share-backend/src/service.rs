@@ -1 +1,509 @@
+//! Matchmaker accepting private transactions and bundles.
 
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    network::ReceiptResponse,
+    primitives::{Address, B256, Bytes, TxHash, U256, keccak256},
+    providers::{
+        DynProvider, PendingTransactionError, Provider, ext::AnvilApi,
+    },
+    rpc::types::mev::{
+        BundleItem, MevSendBundle, mevshare::EventTransactionLog,
+    },
+    transports::TransportError,
+};
+use async_trait::async_trait;
+use kazuka_mev_share_rpc_api::{
+    protocol::{BundleValidationError, validate_bundle},
+    types::mev_bundle_hash,
+};
+use kazuka_mev_share_sse::{Event, server::EventPublisher};
+use serde::{Deserialize, Serialize};
+
+use crate::store::{BundleRecord, BundleStore, InclusionStatus, StoreError};
+
+/// Error of the [Matchmaker].
+#[derive(Debug, thiserror::Error)]
+pub enum MatchmakerError {
+    #[error("invalid bundle: {0}")]
+    InvalidBundle(#[from] BundleValidationError),
+    #[error("unknown transaction {0}")]
+    UnknownTransaction(TxHash),
+    #[error("no latest block")]
+    NoLatestBlock,
+    #[error("bundle simulation failed: {0}")]
+    SimulationFailed(String),
+    #[error(transparent)]
+    Provider(#[from] TransportError),
+    #[error(transparent)]
+    PendingTransaction(#[from] PendingTransactionError),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
+
+/// Signed transaction of a bundle, with hash items resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleTx {
+    pub tx: Bytes,
+    pub can_revert: bool,
+}
+
+/// Result of simulating a bundle on top of the latest block.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResult {
+    pub success: bool,
+    /// Why the simulation failed.
+    pub error: Option<String>,
+    /// Block the bundle was simulated on top of.
+    pub state_block: u64,
+    pub gas_used: u64,
+    /// Increase of the block builder's balance, i.e. priority fees and
+    /// direct payments.
+    pub coinbase_diff: U256,
+    /// Results of the executed transactions.
+    pub txs: Vec<TxSimulation>,
+}
+
+impl SimulationResult {
+    /// Logs emitted by the transactions, as shared by the hints.
+    pub fn logs(&self) -> Vec<EventTransactionLog> {
+        self.txs.iter().flat_map(|tx| tx.logs.clone()).collect()
+    }
+
+    /// Coinbase payment per unit of gas.
+    pub fn mev_gas_price(&self) -> U256 {
+        if self.gas_used == 0 {
+            return U256::ZERO;
+        }
+        self.coinbase_diff / U256::from(self.gas_used)
+    }
+}
+
+/// Result of a single simulated transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxSimulation {
+    pub tx_hash: TxHash,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub gas_used: u64,
+    pub effective_gas_price: u128,
+    pub coinbase_diff: U256,
+    pub reverted: bool,
+    /// Logs emitted by the transaction, without their data.
+    #[serde(default)]
+    pub logs: Vec<EventTransactionLog>,
+}
+
+/// Executes bundles without committing them.
+#[async_trait]
+pub trait Simulator: Send + Sync {
+    /// Executes the transactions in order on top of the latest block.
+    async fn simulate(
+        &self,
+        txs: &[BundleTx],
+    ) -> Result<SimulationResult, MatchmakerError>;
+}
+
+/// Simulates bundles against an Anvil node (e.g. a mainnet fork) with auto
+/// mining, reverting its state after every simulation.
+#[derive(Debug, Clone)]
+pub struct AnvilSimulator {
+    provider: DynProvider,
+}
+
+impl AnvilSimulator {
+    pub fn new(provider: DynProvider) -> Self {
+        Self { provider }
+    }
+
+    async fn execute(
+        &self,
+        txs: &[BundleTx],
+        state_block: u64,
+    ) -> Result<SimulationResult, MatchmakerError> {
+        let coinbase = self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Latest)
+            .await?
+            .ok_or(MatchmakerError::NoLatestBlock)?
+            .header
+            .beneficiary;
+        let mut balance = self.provider.get_balance(coinbase).await?;
+        let mut result = SimulationResult {
+            success: true,
+            state_block,
+            ..Default::default()
+        };
+        for BundleTx { tx, can_revert } in txs {
+            let pending = match self.provider.send_raw_transaction(tx).await {
+                Ok(pending) => pending,
+                // Rejected by the node, e.g. because of a wrong nonce.
+                Err(err) if err.as_error_resp().is_some() => {
+                    result.success = false;
+                    result.error = Some(err.to_string());
+                    return Ok(result);
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let receipt = pending.get_receipt().await?;
+            // Every transaction is mined in its own block.
+            let new_balance = self.provider.get_balance(coinbase).await?;
+            let coinbase_diff = new_balance.saturating_sub(balance);
+            balance = new_balance;
+
+            result.gas_used += receipt.gas_used;
+            result.coinbase_diff += coinbase_diff;
+            result.txs.push(TxSimulation {
+                tx_hash: receipt.transaction_hash,
+                from: receipt.from,
+                to: receipt.to,
+                gas_used: receipt.gas_used,
+                effective_gas_price: receipt.effective_gas_price,
+                coinbase_diff,
+                reverted: !receipt.status(),
+                logs: receipt
+                    .logs()
+                    .iter()
+                    .map(|log| EventTransactionLog {
+                        address: log.address(),
+                        topics: log.topics().to_vec(),
+                    })
+                    .collect(),
+            });
+            if !receipt.status() && !can_revert {
+                result.success = false;
+                result.error = Some(format!(
+                    "transaction {} reverted",
+                    receipt.transaction_hash
+                ));
+                return Ok(result);
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl Simulator for AnvilSimulator {
+    async fn simulate(
+        &self,
+        txs: &[BundleTx],
+    ) -> Result<SimulationResult, MatchmakerError> {
+        let state_block = self.provider.get_block_number().await?;
+        let snapshot = self.provider.anvil_snapshot().await?;
+        let result = self.execute(txs, state_block).await;
+        self.provider.anvil_revert(snapshot).await?;
+        result
+    }
+}
+
+/// Reference MEV-Share matchmaker: stores private transactions and shares
+/// their hashes as hints, and accepts bundles backrunning them, which are
+/// validated and simulated before being stored.
+///
+/// Bundles are not forwarded to builders.
+pub struct Matchmaker {
+    simulator: Arc<dyn Simulator>,
+    publisher: EventPublisher,
+    transactions: Mutex<HashMap<TxHash, Bytes>>,
+    bundles: Mutex<HashMap<B256, MevSendBundle>>,
+    store: Option<Arc<dyn BundleStore>>,
+}
+
+impl Matchmaker {
+    pub fn new(
+        simulator: impl Simulator + 'static,
+        publisher: EventPublisher,
+    ) -> Self {
+        Self {
+            simulator: Arc::new(simulator),
+            publisher,
+            transactions: Mutex::new(HashMap::new()),
+            bundles: Mutex::new(HashMap::new()),
+            store: None,
+        }
+    }
+
+    /// Records every submitted bundle, including rejected ones, in the
+    /// store.
+    pub fn with_store(mut self, store: Arc<dyn BundleStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Publisher of the hint events.
+    pub fn publisher(&self) -> &EventPublisher {
+        &self.publisher
+    }
+
+    /// Accepted bundle with the given hash.
+    pub fn bundle(&self, bundle_hash: &B256) -> Option<MevSendBundle> {
+        self.bundles.lock().unwrap().get(bundle_hash).cloned()
+    }
+
+    /// Stores a private transaction, sharing its hash and the logs it emits
+    /// on top of the latest block, which tell the pools it touches.
+    pub async fn submit_transaction(&self, tx: Bytes) -> TxHash {
+        let hash = keccak256(&tx);
+        self.transactions.lock().unwrap().insert(hash, tx.clone());
+        let txs = [BundleTx {
+            tx,
+            can_revert: true,
+        }];
+        let logs = match self.simulator.simulate(&txs).await {
+            Ok(result) => result.logs(),
+            Err(err) => {
+                tracing::debug!(
+                    ?hash,
+                    "error simulating transaction: {err}"
+                );
+                vec![]
+            }
+        };
+        self.publish(hash, logs);
+        hash
+    }
+
+    /// Validates and simulates the bundle, storing it if it succeeds.
+    ///
+    /// Bundles with privacy hints are shared by their hash, and their logs
+    /// if hinted, so that they can be backrun.
+    pub async fn submit_bundle(
+        &self,
+        bundle: MevSendBundle,
+    ) -> Result<B256, MatchmakerError> {
+        let bundle_hash = mev_bundle_hash(&bundle);
+        let result = match self.simulate_bundle(&bundle).await {
+            Ok(result) => result,
+            Err(err) => {
+                self.record(bundle_hash, &bundle, None).await?;
+                return Err(err);
+            }
+        };
+        self.record(
+            bundle_hash,
+            &bundle,
+            Some(result.clone()),
+        )
+        .await?;
+        if !result.success {
+            return Err(MatchmakerError::SimulationFailed(
+                result.error.unwrap_or_default(),
+            ));
+        }
+        // Whether the bundle is shared, and whether with its logs.
+        let share_logs = bundle
+            .privacy
+            .as_ref()
+            .and_then(|privacy| privacy.hints.as_ref())
+            .map(|hints| hints.has_logs());
+        self.bundles.lock().unwrap().insert(bundle_hash, bundle);
+        if let Some(share_logs) = share_logs {
+            let logs = if share_logs { result.logs() } else { vec![] };
+            self.publish(bundle_hash, logs);
+        }
+        tracing::debug!(?bundle_hash, "accepted bundle");
+        Ok(bundle_hash)
+    }
+
+    /// Validates and simulates the bundle without storing it.
+    pub async fn simulate_bundle(
+        &self,
+        bundle: &MevSendBundle,
+    ) -> Result<SimulationResult, MatchmakerError> {
+        validate_bundle(bundle)?;
+        let mut txs = vec![];
+        self.resolve(bundle, &mut txs)?;
+        self.simulator.simulate(&txs).await
+    }
+
+    /// Flattens the bundle into its signed transactions.
+    fn resolve(
+        &self,
+        bundle: &MevSendBundle,
+        txs: &mut Vec<BundleTx>,
+    ) -> Result<(), MatchmakerError> {
+        for item in &bundle.bundle_body {
+            match item {
+                BundleItem::Hash { hash } => {
+                    let tx = self
+                        .transactions
+                        .lock()
+                        .unwrap()
+                        .get(hash)
+                        .cloned()
+                        .ok_or(MatchmakerError::UnknownTransaction(
+                            *hash,
+                        ))?;
+                    txs.push(BundleTx {
+                        tx,
+                        can_revert: false,
+                    });
+                }
+                BundleItem::Tx { tx, can_revert } => txs.push(BundleTx {
+                    tx: tx.clone(),
+                    can_revert: *can_revert,
+                }),
+                BundleItem::Bundle { bundle } => self.resolve(bundle, txs)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores the bundle as pending if it has been simulated successfully,
+    /// otherwise as rejected.
+    async fn record(
+        &self,
+        bundle_hash: B256,
+        bundle: &MevSendBundle,
+        simulation: Option<SimulationResult>,
+    ) -> Result<(), StoreError> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let mut record = BundleRecord::new(bundle_hash, bundle.clone());
+        if !simulation.as_ref().is_some_and(|result| result.success) {
+            record.status = InclusionStatus::Rejected;
+        }
+        record.simulation = simulation;
+        store.insert(record).await
+    }
+
+    fn publish(&self, hash: B256, logs: Vec<EventTransactionLog>) {
+        self.publisher.publish(Event {
+            hash,
+            logs,
+            transactions: vec![],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kazuka_mev_share_rpc_api::bundle::BundleBuilder;
+
+    use super::*;
+    use crate::store::InMemoryBundleStore;
+
+    /// Pool every transaction swaps through.
+    const POOL: Address = Address::repeat_byte(0xaa);
+
+    /// Fails bundles containing the `revert` transaction, every transaction
+    /// emits a log of the [POOL].
+    struct StubSimulator;
+
+    #[async_trait]
+    impl Simulator for StubSimulator {
+        async fn simulate(
+            &self,
+            txs: &[BundleTx],
+        ) -> Result<SimulationResult, MatchmakerError> {
+            let success = txs.iter().all(|tx| tx.tx.as_ref() != b"revert");
+            Ok(SimulationResult {
+                success,
+                error: (!success).then(|| "reverted".to_string()),
+                state_block: 1,
+                gas_used: 21_000 * txs.len() as u64,
+                txs: txs
+                    .iter()
+                    .map(|tx| TxSimulation {
+                        tx_hash: keccak256(&tx.tx),
+                        from: Address::ZERO,
+                        to: Some(POOL),
+                        gas_used: 21_000,
+                        effective_gas_price: 1,
+                        coinbase_diff: U256::ZERO,
+                        reverted: false,
+                        logs: vec![EventTransactionLog {
+                            address: POOL,
+                            topics: vec![],
+                        }],
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backrun_shared_transaction() {
+        let publisher = EventPublisher::default();
+        let mut events = publisher.subscribe();
+        let matchmaker = Matchmaker::new(StubSimulator, publisher);
+
+        let tx_hash = matchmaker
+            .submit_transaction(Bytes::from_static(b"tx"))
+            .await;
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.hash, tx_hash);
+        // The hint tells the pool the transaction swaps through.
+        assert_eq!(
+            event.logs,
+            vec![EventTransactionLog {
+                address: POOL,
+                topics: vec![],
+            }]
+        );
+
+        let bundle = BundleBuilder::for_block(1)
+            .backrun_of(tx_hash)
+            .tx(Bytes::from_static(b"backrun"))
+            .build()
+            .unwrap();
+        let bundle_hash =
+            matchmaker.submit_bundle(bundle.clone()).await.unwrap();
+        assert_eq!(bundle_hash, mev_bundle_hash(&bundle));
+        assert!(matchmaker.bundle(&bundle_hash).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_record_submitted_bundles() {
+        let store = Arc::new(InMemoryBundleStore::new());
+        let matchmaker =
+            Matchmaker::new(StubSimulator, EventPublisher::default())
+                .with_store(store.clone());
+
+        let reverting = BundleBuilder::for_block(1)
+            .tx(Bytes::from_static(b"revert"))
+            .build()
+            .unwrap();
+        let bundle_hash = mev_bundle_hash(&reverting);
+        assert!(matchmaker.submit_bundle(reverting).await.is_err());
+
+        let record = store.get(bundle_hash).await.unwrap().unwrap();
+        assert_eq!(record.status, InclusionStatus::Rejected);
+        assert!(!record.simulation.unwrap().success);
+    }
+
+    #[tokio::test]
+    async fn test_reject_bundles() {
+        let matchmaker =
+            Matchmaker::new(StubSimulator, EventPublisher::default());
+
+        let unknown = BundleBuilder::for_block(1)
+            .backrun_of(B256::ZERO)
+            .tx(Bytes::from_static(b"backrun"))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            matchmaker.submit_bundle(unknown).await,
+            Err(MatchmakerError::UnknownTransaction(_))
+        ));
+
+        let reverting = BundleBuilder::for_block(1)
+            .tx(Bytes::from_static(b"revert"))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            matchmaker.submit_bundle(reverting).await,
+            Err(MatchmakerError::SimulationFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,268 @@
+//! Bundle simulation server for tests, executing bundles against an Anvil
+//! node instead of returning canned responses.
+//!
+//! ```ignore
+//! let anvil = Anvil::new().fork(fork_url).spawn();
+//! let provider = ProviderBuilder::new().connect_http(anvil.endpoint_url()).erased();
+//! let server = Server::builder().build("127.0.0.1:0").await?;
+//! let handle = server.start(SimulationServer::new(provider).into_module());
+//! ```
+
+use alloy::{
+    primitives::{B256, Bytes, U256},
+    providers::DynProvider,
+    rpc::types::mev::{
+        BundleItem, EthCallBundle, EthCallBundleResponse,
+        EthCallBundleTransactionResult, EthCancelBundle,
+        EthCancelPrivateTransaction, EthSendBundle, EthSendPrivateTransaction,
+        MevSendBundle, SimBundleOverrides, SimBundleResponse,
+    },
+};
+use async_trait::async_trait;
+use jsonrpsee::{RpcModule, core::RpcResult, types::ErrorObjectOwned};
+use kazuka_mev_share_rpc_api::{
+    EthBundleApiServer, MevApiServer,
+    protocol::validate_bundle,
+    types::{BundleHash, SendBundleResponse, eth_bundle_hash},
+};
+
+use crate::{
+    rpc::{rpc_error, unsupported},
+    service::{
+        AnvilSimulator, BundleTx, MatchmakerError, SimulationResult, Simulator,
+    },
+};
+
+/// Serves `mev_simBundle` and `eth_callBundle` by executing bundles with an
+/// [AnvilSimulator], all other methods fail.
+///
+/// Bundles are simulated on top of the latest block, overrides and target
+/// blocks are ignored.
+#[derive(Clone)]
+pub struct SimulationServer {
+    simulator: AnvilSimulator,
+}
+
+impl SimulationServer {
+    pub fn new(provider: DynProvider) -> Self {
+        Self {
+            simulator: AnvilSimulator::new(provider),
+        }
+    }
+
+    /// Merges both APIs into a single module.
+    pub fn into_module(self) -> RpcModule<()> {
+        let mut module = RpcModule::new(());
+        module
+            .merge(MevApiServer::into_rpc(self.clone()))
+            .expect("No conflicting methods");
+        module
+            .merge(EthBundleApiServer::into_rpc(self))
+            .expect("No conflicting methods");
+        module
+    }
+}
+
+/// Flattens the bundle, failing on hash items, which can't be resolved
+/// without a matchmaker.
+fn bundle_txs(
+    bundle: &MevSendBundle,
+    txs: &mut Vec<BundleTx>,
+) -> Result<(), ErrorObjectOwned> {
+    for item in &bundle.bundle_body {
+        match item {
+            BundleItem::Hash { hash } => {
+                return Err(rpc_error(
+                    MatchmakerError::UnknownTransaction(*hash),
+                ));
+            }
+            BundleItem::Tx { tx, can_revert } => txs.push(BundleTx {
+                tx: tx.clone(),
+                can_revert: *can_revert,
+            }),
+            BundleItem::Bundle { bundle } => bundle_txs(bundle, txs)?,
+        }
+    }
+    Ok(())
+}
+
+fn call_bundle_response(
+    bundle_hash: B256,
+    result: SimulationResult,
+) -> EthCallBundleResponse {
+    let results = result
+        .txs
+        .iter()
+        .map(|tx| EthCallBundleTransactionResult {
+            coinbase_diff: tx.coinbase_diff,
+            eth_sent_to_coinbase: U256::ZERO,
+            from_address: tx.from,
+            gas_fees: U256::from(tx.effective_gas_price)
+                * U256::from(tx.gas_used),
+            gas_price: U256::from(tx.effective_gas_price),
+            gas_used: tx.gas_used,
+            to_address: tx.to,
+            tx_hash: tx.tx_hash,
+            value: None,
+            revert: tx.reverted.then(|| "execution reverted".to_string()),
+        })
+        .collect::<Vec<_>>();
+    EthCallBundleResponse {
+        bundle_hash,
+        bundle_gas_price: result.mev_gas_price(),
+        coinbase_diff: result.coinbase_diff,
+        eth_sent_to_coinbase: U256::ZERO,
+        gas_fees: results.iter().map(|tx| tx.gas_fees).sum(),
+        total_gas_used: result.gas_used,
+        results,
+        state_block_number: result.state_block,
+    }
+}
+
+#[async_trait]
+impl MevApiServer for SimulationServer {
+    async fn send_bundle(
+        &self,
+        _request: MevSendBundle,
+    ) -> RpcResult<SendBundleResponse> {
+        Err(unsupported("mev_sendBundle"))
+    }
+
+    async fn sim_bundle(
+        &self,
+        bundle: MevSendBundle,
+        _sim_overrides: SimBundleOverrides,
+    ) -> RpcResult<SimBundleResponse> {
+        validate_bundle(&bundle)
+            .map_err(|err| rpc_error(MatchmakerError::InvalidBundle(err)))?;
+        let mut txs = vec![];
+        bundle_txs(&bundle, &mut txs)?;
+        let result = self.simulator.simulate(&txs).await.map_err(rpc_error)?;
+        Ok(SimBundleResponse {
+            success: result.success,
+            mev_gas_price: result.mev_gas_price(),
+            profit: result.coinbase_diff,
+            refundable_value: result.coinbase_diff,
+            error: result.error,
+            state_block: result.state_block,
+            gas_used: result.gas_used,
+            logs: None,
+            exec_error: None,
+            revert: None,
+        })
+    }
+}
+
+#[async_trait]
+impl EthBundleApiServer for SimulationServer {
+    async fn send_bundle(
+        &self,
+        _request: EthSendBundle,
+    ) -> RpcResult<BundleHash> {
+        Err(unsupported("eth_sendBundle"))
+    }
+
+    /// Failed transactions are reported in the results instead of failing
+    /// the call, as the relays do.
+    async fn call_bundle(
+        &self,
+        request: EthCallBundle,
+    ) -> RpcResult<EthCallBundleResponse> {
+        let bundle_hash = eth_bundle_hash(&EthSendBundle {
+            txs: request.txs.clone(),
+            ..Default::default()
+        });
+        let txs = request
+            .txs
+            .into_iter()
+            .map(|tx| BundleTx {
+                tx,
+                can_revert: true,
+            })
+            .collect::<Vec<_>>();
+        let result = self.simulator.simulate(&txs).await.map_err(rpc_error)?;
+        if let Some(error) = result.error.clone()
+            && result.txs.len() < txs.len()
+        {
+            // The node rejected one of the transactions.
+            return Err(rpc_error(
+                MatchmakerError::SimulationFailed(error),
+            ));
+        }
+        Ok(call_bundle_response(
+            bundle_hash,
+            result,
+        ))
+    }
+
+    async fn cancel_bundle(&self, _request: EthCancelBundle) -> RpcResult<()> {
+        Err(unsupported("eth_cancelBundle"))
+    }
+
+    async fn send_private_transaction(
+        &self,
+        _request: EthSendPrivateTransaction,
+    ) -> RpcResult<B256> {
+        Err(unsupported(
+            "eth_sendPrivateTransaction",
+        ))
+    }
+
+    async fn send_private_raw_transaction(
+        &self,
+        _bytes: Bytes,
+    ) -> RpcResult<B256> {
+        Err(unsupported(
+            "eth_sendPrivateRawTransaction",
+        ))
+    }
+
+    async fn cancel_private_transaction(
+        &self,
+        _request: EthCancelPrivateTransaction,
+    ) -> RpcResult<bool> {
+        Err(unsupported(
+            "eth_cancelPrivateTransaction",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, keccak256};
+
+    use super::*;
+    use crate::service::TxSimulation;
+
+    #[test]
+    fn test_call_bundle_response() {
+        let tx = TxSimulation {
+            tx_hash: keccak256(b"tx"),
+            from: Address::ZERO,
+            to: None,
+            gas_used: 21_000,
+            effective_gas_price: 2,
+            coinbase_diff: U256::from(21_000),
+            reverted: true,
+            logs: vec![],
+        };
+        let result = SimulationResult {
+            success: true,
+            error: None,
+            state_block: 100,
+            gas_used: 42_000,
+            coinbase_diff: U256::from(42_000),
+            txs: vec![tx.clone(), tx],
+        };
+
+        let response = call_bundle_response(B256::ZERO, result);
+
+        assert_eq!(response.bundle_gas_price, U256::from(1));
+        assert_eq!(response.gas_fees, U256::from(84_000));
+        assert_eq!(response.state_block_number, 100);
+        assert_eq!(
+            response.results[0].revert.as_deref(),
+            Some("execution reverted")
+        );
+    }
+}
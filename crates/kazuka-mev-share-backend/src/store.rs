@@ -0,0 +1,709 @@
+//! Audit trail of submitted bundles, from submission to their final
+//! inclusion status.
+//!
+//! ```ignore
+//! let store = SqliteBundleStore::open("bundles.db")?;
+//! let included = store
+//!     .query(&BundleQuery::new().status(StatusKind::Included).limit(10))
+//!     .await?;
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use alloy::{
+    primitives::{B256, U256},
+    rpc::types::mev::MevSendBundle,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::service::SimulationResult;
+
+/// Error of a [BundleStore].
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("unknown bundle {0}")]
+    UnknownBundle(B256),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+/// Where a bundle is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum InclusionStatus {
+    /// Submitted, waiting for inclusion.
+    Pending,
+    /// Failed validation or simulation.
+    Rejected,
+    /// Landed on-chain.
+    #[serde(rename_all = "camelCase")]
+    Included { block_number: u64 },
+    /// Not included by its last valid block.
+    Expired,
+    /// Not included, while the backrun transaction landed in someone else's
+    /// bundle.
+    #[serde(rename_all = "camelCase")]
+    Outbid { block_number: u64 },
+}
+
+/// [InclusionStatus] without its data, to filter by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusKind {
+    Pending,
+    Rejected,
+    Included,
+    Expired,
+    Outbid,
+}
+
+impl InclusionStatus {
+    pub fn kind(&self) -> StatusKind {
+        match self {
+            Self::Pending => StatusKind::Pending,
+            Self::Rejected => StatusKind::Rejected,
+            Self::Included { .. } => StatusKind::Included,
+            Self::Expired => StatusKind::Expired,
+            Self::Outbid { .. } => StatusKind::Outbid,
+        }
+    }
+}
+
+impl StatusKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Rejected => "rejected",
+            Self::Included => "included",
+            Self::Expired => "expired",
+            Self::Outbid => "outbid",
+        }
+    }
+}
+
+/// Response of a relay the bundle has been submitted to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelaySubmission {
+    pub relay: String,
+    /// Bundle hash returned by the relay.
+    pub bundle_hash: Option<B256>,
+    /// Error returned by the relay.
+    pub error: Option<String>,
+    /// Unix timestamp in seconds.
+    pub submitted_at: u64,
+}
+
+/// Everything known about a submitted bundle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRecord {
+    pub bundle_hash: B256,
+    pub bundle: MevSendBundle,
+    /// Strategy which submitted the bundle.
+    #[serde(default)]
+    pub strategy: Option<String>,
+    /// Profit expected by the strategy.
+    #[serde(default)]
+    pub profit: Option<U256>,
+    /// Refund received from the matchmaker once included.
+    #[serde(default)]
+    pub refund: Option<U256>,
+    /// First block the bundle targets.
+    pub target_block: u64,
+    /// Last block the bundle is valid for.
+    pub max_block: u64,
+    /// Unix timestamp in seconds.
+    pub submitted_at: u64,
+    pub simulation: Option<SimulationResult>,
+    pub relay_submissions: Vec<RelaySubmission>,
+    pub status: InclusionStatus,
+}
+
+impl BundleRecord {
+    /// Creates a pending record, submitted now.
+    pub fn new(bundle_hash: B256, bundle: MevSendBundle) -> Self {
+        Self {
+            bundle_hash,
+            target_block: bundle.inclusion.block,
+            max_block: bundle
+                .inclusion
+                .max_block
+                .unwrap_or(bundle.inclusion.block),
+            bundle,
+            strategy: None,
+            profit: None,
+            refund: None,
+            submitted_at: now(),
+            simulation: None,
+            relay_submissions: vec![],
+            status: InclusionStatus::Pending,
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: impl Into<String>) -> Self {
+        self.strategy = Some(strategy.into());
+        self
+    }
+
+    pub fn with_profit(mut self, profit: U256) -> Self {
+        self.profit = Some(profit);
+        self
+    }
+}
+
+/// Filter of [BundleStore::query], matching everything by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BundleQuery {
+    pub status: Option<StatusKind>,
+    /// Minimal target block.
+    pub from_block: Option<u64>,
+    /// Maximal target block.
+    pub to_block: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl BundleQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(mut self, status: StatusKind) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Only bundles targeting blocks in the given range.
+    pub fn blocks(mut self, from_block: u64, to_block: u64) -> Self {
+        self.from_block = Some(from_block);
+        self.to_block = Some(to_block);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn matches(&self, record: &BundleRecord) -> bool {
+        self.status
+            .is_none_or(|status| record.status.kind() == status)
+            && self
+                .from_block
+                .is_none_or(|block| record.target_block >= block)
+            && self
+                .to_block
+                .is_none_or(|block| record.target_block <= block)
+    }
+}
+
+/// Storage of [BundleRecord]s.
+#[async_trait]
+pub trait BundleStore: Send + Sync {
+    /// Inserts the record, replacing the one with the same hash.
+    async fn insert(&self, record: BundleRecord) -> Result<(), StoreError>;
+
+    /// Inserts the record, unless there is one with the same hash, e.g. when
+    /// the bundle is submitted to several relays.
+    async fn insert_if_absent(
+        &self,
+        record: BundleRecord,
+    ) -> Result<(), StoreError>;
+
+    async fn record_simulation(
+        &self,
+        bundle_hash: B256,
+        simulation: SimulationResult,
+    ) -> Result<(), StoreError>;
+
+    async fn record_relay_submission(
+        &self,
+        bundle_hash: B256,
+        submission: RelaySubmission,
+    ) -> Result<(), StoreError>;
+
+    async fn set_status(
+        &self,
+        bundle_hash: B256,
+        status: InclusionStatus,
+    ) -> Result<(), StoreError>;
+
+    /// Adds a refund received for the bundle to its [BundleRecord::refund].
+    async fn record_refund(
+        &self,
+        bundle_hash: B256,
+        refund: U256,
+    ) -> Result<(), StoreError>;
+
+    async fn get(
+        &self,
+        bundle_hash: B256,
+    ) -> Result<Option<BundleRecord>, StoreError>;
+
+    /// Matching records, most recently submitted first.
+    async fn query(
+        &self,
+        query: &BundleQuery,
+    ) -> Result<Vec<BundleRecord>, StoreError>;
+}
+
+/// [BundleStore] keeping records in memory, e.g. for tests.
+#[derive(Debug, Default)]
+pub struct InMemoryBundleStore {
+    records: Mutex<HashMap<B256, BundleRecord>>,
+}
+
+impl InMemoryBundleStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(
+        &self,
+        bundle_hash: B256,
+        f: impl FnOnce(&mut BundleRecord),
+    ) -> Result<(), StoreError> {
+        let mut records = self.records.lock().unwrap();
+        let record = records
+            .get_mut(&bundle_hash)
+            .ok_or(StoreError::UnknownBundle(bundle_hash))?;
+        f(record);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BundleStore for InMemoryBundleStore {
+    async fn insert(&self, record: BundleRecord) -> Result<(), StoreError> {
+        self.records
+            .lock()
+            .unwrap()
+            .insert(record.bundle_hash, record);
+        Ok(())
+    }
+
+    async fn insert_if_absent(
+        &self,
+        record: BundleRecord,
+    ) -> Result<(), StoreError> {
+        self.records
+            .lock()
+            .unwrap()
+            .entry(record.bundle_hash)
+            .or_insert(record);
+        Ok(())
+    }
+
+    async fn record_simulation(
+        &self,
+        bundle_hash: B256,
+        simulation: SimulationResult,
+    ) -> Result<(), StoreError> {
+        self.update(bundle_hash, |record| {
+            record.simulation = Some(simulation)
+        })
+    }
+
+    async fn record_relay_submission(
+        &self,
+        bundle_hash: B256,
+        submission: RelaySubmission,
+    ) -> Result<(), StoreError> {
+        self.update(bundle_hash, |record| {
+            record.relay_submissions.push(submission)
+        })
+    }
+
+    async fn set_status(
+        &self,
+        bundle_hash: B256,
+        status: InclusionStatus,
+    ) -> Result<(), StoreError> {
+        self.update(bundle_hash, |record| {
+            record.status = status
+        })
+    }
+
+    async fn record_refund(
+        &self,
+        bundle_hash: B256,
+        refund: U256,
+    ) -> Result<(), StoreError> {
+        self.update(bundle_hash, |record| {
+            *record.refund.get_or_insert_default() += refund
+        })
+    }
+
+    async fn get(
+        &self,
+        bundle_hash: B256,
+    ) -> Result<Option<BundleRecord>, StoreError> {
+        Ok(self.records.lock().unwrap().get(&bundle_hash).cloned())
+    }
+
+    async fn query(
+        &self,
+        query: &BundleQuery,
+    ) -> Result<Vec<BundleRecord>, StoreError> {
+        let mut records: Vec<_> = self
+            .records
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|record| query.matches(record))
+            .cloned()
+            .collect();
+        records.sort_by(|a, b| b.submitted_at.cmp(&a.submitted_at));
+        records.truncate(query.limit.unwrap_or(usize::MAX));
+        Ok(records)
+    }
+}
+
+/// Current unix timestamp in seconds.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteBundleStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+
+    use rusqlite::{Connection, OptionalExtension, params};
+
+    use super::*;
+
+    const SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS bundles (
+            bundle_hash TEXT PRIMARY KEY,
+            target_block INTEGER NOT NULL,
+            max_block INTEGER NOT NULL,
+            submitted_at INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            record TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS bundles_target_block
+            ON bundles (target_block);
+    ";
+
+    /// [BundleStore] persisting records in a SQLite database.
+    ///
+    /// Records are stored as JSON, along with the columns they are queried
+    /// by.
+    #[derive(Debug, Clone)]
+    pub struct SqliteBundleStore {
+        conn: Arc<Mutex<Connection>>,
+    }
+
+    impl SqliteBundleStore {
+        /// Opens (or creates) the database at the given path.
+        pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+            Self::from_connection(Connection::open(path)?)
+        }
+
+        /// Opens a database, which is lost once the store is dropped.
+        pub fn in_memory() -> Result<Self, StoreError> {
+            Self::from_connection(Connection::open_in_memory()?)
+        }
+
+        fn from_connection(conn: Connection) -> Result<Self, StoreError> {
+            conn.execute_batch(SCHEMA)?;
+            Ok(Self {
+                conn: Arc::new(Mutex::new(conn)),
+            })
+        }
+
+        /// Runs the blocking SQLite call off the async runtime.
+        async fn with_conn<T: Send + 'static>(
+            &self,
+            f: impl FnOnce(&Connection) -> Result<T, StoreError> + Send + 'static,
+        ) -> Result<T, StoreError> {
+            let conn = Arc::clone(&self.conn);
+            tokio::task::spawn_blocking(move || f(&conn.lock().unwrap()))
+                .await
+                .expect("SQLite task panicked")
+        }
+
+        async fn update(
+            &self,
+            bundle_hash: B256,
+            f: impl FnOnce(&mut BundleRecord) + Send + 'static,
+        ) -> Result<(), StoreError> {
+            self.with_conn(move |conn| {
+                let mut record = select(conn, bundle_hash)?
+                    .ok_or(StoreError::UnknownBundle(bundle_hash))?;
+                f(&mut record);
+                upsert(conn, &record)
+            })
+            .await
+        }
+    }
+
+    fn select(
+        conn: &Connection,
+        bundle_hash: B256,
+    ) -> Result<Option<BundleRecord>, StoreError> {
+        let record: Option<String> = conn
+            .query_row(
+                "SELECT record FROM bundles WHERE bundle_hash = ?1",
+                params![bundle_hash.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(record
+            .map(|record| serde_json::from_str(&record))
+            .transpose()?)
+    }
+
+    fn upsert(
+        conn: &Connection,
+        record: &BundleRecord,
+    ) -> Result<(), StoreError> {
+        write(conn, "INSERT OR REPLACE", record)
+    }
+
+    /// Writes the record with the given `INSERT` statement.
+    fn write(
+        conn: &Connection,
+        insert: &str,
+        record: &BundleRecord,
+    ) -> Result<(), StoreError> {
+        conn.execute(
+            &format!(
+                "{insert} INTO bundles
+                (bundle_hash, target_block, max_block, submitted_at, status, record)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+            ),
+            params![
+                record.bundle_hash.to_string(),
+                record.target_block,
+                record.max_block,
+                record.submitted_at,
+                record.status.kind().name(),
+                serde_json::to_string(record)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    #[async_trait]
+    impl BundleStore for SqliteBundleStore {
+        async fn insert(&self, record: BundleRecord) -> Result<(), StoreError> {
+            self.with_conn(move |conn| upsert(conn, &record)).await
+        }
+
+        async fn insert_if_absent(
+            &self,
+            record: BundleRecord,
+        ) -> Result<(), StoreError> {
+            self.with_conn(move |conn| write(conn, "INSERT OR IGNORE", &record))
+                .await
+        }
+
+        async fn record_simulation(
+            &self,
+            bundle_hash: B256,
+            simulation: SimulationResult,
+        ) -> Result<(), StoreError> {
+            self.update(bundle_hash, |record| {
+                record.simulation = Some(simulation)
+            })
+            .await
+        }
+
+        async fn record_relay_submission(
+            &self,
+            bundle_hash: B256,
+            submission: RelaySubmission,
+        ) -> Result<(), StoreError> {
+            self.update(bundle_hash, |record| {
+                record.relay_submissions.push(submission)
+            })
+            .await
+        }
+
+        async fn set_status(
+            &self,
+            bundle_hash: B256,
+            status: InclusionStatus,
+        ) -> Result<(), StoreError> {
+            self.update(bundle_hash, move |record| {
+                record.status = status
+            })
+            .await
+        }
+
+        async fn record_refund(
+            &self,
+            bundle_hash: B256,
+            refund: U256,
+        ) -> Result<(), StoreError> {
+            self.update(bundle_hash, move |record| {
+                *record.refund.get_or_insert_default() += refund
+            })
+            .await
+        }
+
+        async fn get(
+            &self,
+            bundle_hash: B256,
+        ) -> Result<Option<BundleRecord>, StoreError> {
+            self.with_conn(move |conn| select(conn, bundle_hash)).await
+        }
+
+        async fn query(
+            &self,
+            query: &BundleQuery,
+        ) -> Result<Vec<BundleRecord>, StoreError> {
+            let query = query.clone();
+            self.with_conn(move |conn| {
+                let mut statement = conn.prepare(
+                    "SELECT record FROM bundles
+                        WHERE (?1 IS NULL OR status = ?1)
+                        AND (?2 IS NULL OR target_block >= ?2)
+                        AND (?3 IS NULL OR target_block <= ?3)
+                        ORDER BY submitted_at DESC
+                        LIMIT ?4",
+                )?;
+                let limit = query.limit.map_or(-1, |limit| limit as i64);
+                let records = statement.query_map(
+                    params![
+                        query.status.map(|status| status.name()),
+                        query.from_block,
+                        query.to_block,
+                        limit,
+                    ],
+                    |row| row.get::<_, String>(0),
+                )?;
+                records
+                    .map(|record| Ok(serde_json::from_str(&record?)?))
+                    .collect()
+            })
+            .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::Bytes,
+        rpc::types::mev::{BundleItem, Inclusion},
+    };
+
+    use super::*;
+
+    fn record(target_block: u64) -> BundleRecord {
+        let bundle = MevSendBundle {
+            protocol_version: Default::default(),
+            inclusion: Inclusion {
+                block: target_block,
+                max_block: None,
+            },
+            bundle_body: vec![BundleItem::Tx {
+                tx: Bytes::from_static(b"tx"),
+                can_revert: false,
+            }],
+            validity: None,
+            privacy: None,
+        };
+        BundleRecord::new(
+            B256::with_last_byte(target_block as u8),
+            bundle,
+        )
+    }
+
+    async fn test_lifecycle(store: impl BundleStore) {
+        store.insert(record(1)).await.unwrap();
+        store.insert(record(2)).await.unwrap();
+        let bundle_hash = B256::with_last_byte(2);
+        // Records of bundles submitted to another relay are kept.
+        store
+            .insert_if_absent(record(2).with_strategy("arbitrage"))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get(bundle_hash).await.unwrap().unwrap().strategy,
+            None
+        );
+        store
+            .record_relay_submission(
+                bundle_hash,
+                RelaySubmission {
+                    relay: "flashbots".to_string(),
+                    bundle_hash: Some(bundle_hash),
+                    error: None,
+                    submitted_at: now(),
+                },
+            )
+            .await
+            .unwrap();
+        store
+            .set_status(
+                bundle_hash,
+                InclusionStatus::Included { block_number: 2 },
+            )
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            store
+                .record_refund(bundle_hash, U256::from(10))
+                .await
+                .unwrap();
+        }
+
+        let included = store
+            .query(&BundleQuery::new().status(StatusKind::Included))
+            .await
+            .unwrap();
+        assert_eq!(included.len(), 1);
+        assert_eq!(included[0].relay_submissions.len(), 1);
+        assert_eq!(included[0].refund, Some(U256::from(20)));
+        assert_eq!(
+            store
+                .query(&BundleQuery::new().blocks(1, 1))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            store
+                .query(&BundleQuery::new().limit(1))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(matches!(
+            store.set_status(B256::ZERO, InclusionStatus::Expired).await,
+            Err(StoreError::UnknownBundle(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store() {
+        test_lifecycle(InMemoryBundleStore::new()).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_store() {
+        test_lifecycle(SqliteBundleStore::in_memory().unwrap()).await;
+    }
+}
@@ -0,0 +1,383 @@
+//! Client submitting bundles directly to block builders exposing a
+//! Flashbots-compatible `eth_sendBundle`, like MEV-Boost relays fan out to
+//! several builders. The extensions and quirks of every builder, e.g. the
+//! refund fields of beaverbuild, are encoded as [RequestAdapter]s.
+//!
+//! ```ignore
+//! let builders = [
+//!     BuilderClient::http(Builder::Beaverbuild, "https://rpc.beaverbuild.org")?,
+//!     BuilderClient::http(Builder::Titan, "https://rpc.titanbuilder.xyz")?,
+//! ];
+//! let refund = BundleRefund::new(90).with_tx_hashes(vec![target_tx_hash]);
+//! let responses =
+//!     broadcast_bundle(&builders, bundle, Some(refund)).await;
+//! ```
+
+use std::fmt;
+
+use alloy::{
+    primitives::{Address, B256, hex, keccak256},
+    rpc::types::mev::EthSendBundle,
+};
+use jsonrpsee::{
+    core::{ClientError, client::ClientT},
+    http_client::{HttpClient, HttpClientBuilder},
+    rpc_params,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    RelayResponse, builders::Builder, multi_relay::join_relays,
+    types::BundleHash,
+};
+
+/// Parameters of an `eth_sendBundle` request, as sent to the builder.
+pub type BundleParams = Map<String, Value>;
+
+/// Refund of part of the value of a bundle to the sender of a transaction,
+/// which some builders support as an extension of `eth_sendBundle`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleRefund {
+    /// Percentage of the value of the bundle refunded.
+    pub percent: u8,
+    /// Recipient of the refund, the sender of the first refunded
+    /// transaction by default.
+    pub recipient: Option<Address>,
+    /// Transactions whose senders are refunded, the first transaction of
+    /// the bundle by default.
+    pub tx_hashes: Vec<B256>,
+}
+
+impl BundleRefund {
+    /// # Panics
+    ///
+    /// If the percentage is over 100.
+    pub fn new(percent: u8) -> Self {
+        assert!(
+            percent <= 100,
+            "Refund percent must be <= 100"
+        );
+        Self {
+            percent,
+            recipient: None,
+            tx_hashes: vec![],
+        }
+    }
+
+    pub fn with_recipient(mut self, recipient: Address) -> Self {
+        self.recipient = Some(recipient);
+        self
+    }
+
+    pub fn with_tx_hashes(mut self, tx_hashes: Vec<B256>) -> Self {
+        self.tx_hashes = tx_hashes;
+        self
+    }
+}
+
+/// Rewrites `eth_sendBundle` requests into the dialect of a builder.
+pub trait RequestAdapter: fmt::Debug + Send + Sync {
+    fn adapt(&self, params: &mut BundleParams, refund: Option<&BundleRefund>);
+}
+
+/// Builders following the Flashbots API, which don't support refunds.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardAdapter;
+
+impl RequestAdapter for StandardAdapter {
+    fn adapt(&self, _params: &mut BundleParams, refund: Option<&BundleRefund>) {
+        if refund.is_some() {
+            tracing::warn!("Builder doesn't support refunds, ignoring");
+        }
+    }
+}
+
+/// beaverbuild, refunding the senders of `refundTransactionHashes`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BeaverbuildAdapter;
+
+impl RequestAdapter for BeaverbuildAdapter {
+    fn adapt(&self, params: &mut BundleParams, refund: Option<&BundleRefund>) {
+        let Some(refund) = refund else {
+            return;
+        };
+        params.insert(
+            "refundPercent".to_string(),
+            refund.percent.into(),
+        );
+        if let Some(recipient) = refund.recipient {
+            params.insert(
+                "refundRecipient".to_string(),
+                recipient.to_string().into(),
+            );
+        }
+        if !refund.tx_hashes.is_empty() {
+            params.insert(
+                "refundTransactionHashes".to_string(),
+                refund
+                    .tx_hashes
+                    .iter()
+                    .map(|hash| Value::from(hash.to_string()))
+                    .collect(),
+            );
+        }
+    }
+}
+
+/// Titan, refunding the sender of the transaction at `refundIndex`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TitanAdapter;
+
+impl RequestAdapter for TitanAdapter {
+    fn adapt(&self, params: &mut BundleParams, refund: Option<&BundleRefund>) {
+        let Some(refund) = refund else {
+            return;
+        };
+        params.insert(
+            "refundPercent".to_string(),
+            refund.percent.into(),
+        );
+        if let Some(recipient) = refund.recipient {
+            params.insert(
+                "refundRecipient".to_string(),
+                recipient.to_string().into(),
+            );
+        }
+        // Only a single transaction can be refunded.
+        let Some(tx_hash) = refund.tx_hashes.first() else {
+            return;
+        };
+        match tx_index(params, tx_hash) {
+            Some(index) => {
+                params.insert("refundIndex".to_string(), index.into());
+            }
+            None => tracing::warn!(
+                %tx_hash,
+                "Refunded transaction isn't in the bundle, ignoring"
+            ),
+        }
+    }
+}
+
+/// Index of the transaction with the hash in the `txs` of the request.
+fn tx_index(params: &BundleParams, tx_hash: &B256) -> Option<usize> {
+    params.get("txs")?.as_array()?.iter().position(|tx| {
+        tx.as_str()
+            .and_then(|tx| hex::decode(tx).ok())
+            .is_some_and(|tx| keccak256(tx) == *tx_hash)
+    })
+}
+
+/// Adapter of the builder's dialect of `eth_sendBundle`.
+pub fn adapter_for(builder: Builder) -> Box<dyn RequestAdapter> {
+    match builder {
+        Builder::Beaverbuild => Box::new(BeaverbuildAdapter),
+        Builder::Titan => Box::new(TitanAdapter),
+        _ => Box::new(StandardAdapter),
+    }
+}
+
+/// Client of a single builder, see the [module](self) docs.
+pub struct BuilderClient<C = HttpClient> {
+    name: String,
+    client: C,
+    adapter: Box<dyn RequestAdapter>,
+}
+
+impl BuilderClient {
+    /// Client of the builder at the URL, sending unsigned requests.
+    pub fn http(
+        builder: Builder,
+        url: impl AsRef<str>,
+    ) -> Result<Self, ClientError> {
+        let client = HttpClientBuilder::default().build(url)?;
+        Ok(Self::new(builder, client))
+    }
+}
+
+impl<C> BuilderClient<C> {
+    /// Wraps the client, e.g. built with an
+    /// [FlashbotsAuthLayer](crate::middleware::FlashbotsAuthLayer) for builders
+    /// accepting signed requests, adapting the requests to the builder.
+    pub fn new(builder: Builder, client: C) -> Self {
+        Self {
+            name: builder.name().to_string(),
+            client,
+            adapter: adapter_for(builder),
+        }
+    }
+
+    /// Wraps the client of a builder which isn't well-known.
+    pub fn custom(
+        name: impl Into<String>,
+        client: C,
+        adapter: impl RequestAdapter + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            client,
+            adapter: Box::new(adapter),
+        }
+    }
+
+    /// Name of the builder.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Parameters of the `eth_sendBundle` request of the bundle, in the
+    /// dialect of the builder.
+    pub fn params(
+        &self,
+        bundle: &EthSendBundle,
+        refund: Option<&BundleRefund>,
+    ) -> Result<BundleParams, ClientError> {
+        let Value::Object(mut params) = serde_json::to_value(bundle)? else {
+            return Err(ClientError::Custom(
+                "Bundle isn't serialized as an object".to_string(),
+            ));
+        };
+        // Some builders reject null fields.
+        params.retain(|_, value| !value.is_null());
+        self.adapter.adapt(&mut params, refund);
+        Ok(params)
+    }
+}
+
+impl<C> BuilderClient<C>
+where
+    C: ClientT + Send + Sync,
+{
+    /// Sends the bundle via `eth_sendBundle`, refunding part of its value
+    /// if the builder supports it.
+    pub async fn send_bundle(
+        &self,
+        bundle: &EthSendBundle,
+        refund: Option<&BundleRefund>,
+    ) -> Result<BundleHash, ClientError> {
+        let params = self.params(bundle, refund)?;
+        self.client
+            .request("eth_sendBundle", rpc_params![params])
+            .await
+    }
+}
+
+impl<C> fmt::Debug for BuilderClient<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BuilderClient")
+            .field("name", &self.name)
+            .field("adapter", &self.adapter)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sends the bundle to all builders, returning the result of every one.
+pub async fn broadcast_bundle<C>(
+    builders: &[BuilderClient<C>],
+    bundle: EthSendBundle,
+    refund: Option<BundleRefund>,
+) -> Vec<RelayResponse<BundleHash>>
+where
+    C: ClientT + Send + Sync,
+{
+    join_relays(builders.iter().map(|builder| {
+        (
+            builder.name().to_string(),
+            builder.send_bundle(&bundle, refund.as_ref()),
+        )
+    }))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use alloy::primitives::{Bytes, address};
+    use jsonrpsee::{RpcModule, server::Server};
+
+    use super::*;
+
+    fn bundle() -> EthSendBundle {
+        EthSendBundle {
+            txs: vec![Bytes::from_static(&[1]), Bytes::from_static(&[2])],
+            block_number: 1,
+            ..Default::default()
+        }
+    }
+
+    fn refund() -> BundleRefund {
+        BundleRefund::new(90)
+            .with_recipient(address!(
+                "0x1111111111111111111111111111111111111111"
+            ))
+            .with_tx_hashes(vec![keccak256([2])])
+    }
+
+    #[test]
+    fn test_beaverbuild_refund() {
+        let client = BuilderClient::new(Builder::Beaverbuild, ());
+        let params = client.params(&bundle(), Some(&refund())).unwrap();
+        assert_eq!(params["refundPercent"], 90);
+        assert_eq!(
+            params["refundRecipient"],
+            "0x1111111111111111111111111111111111111111"
+        );
+        assert_eq!(
+            params["refundTransactionHashes"][0],
+            keccak256([2]).to_string()
+        );
+        assert!(params.values().all(|value| !value.is_null()));
+    }
+
+    #[test]
+    fn test_titan_refund_index() {
+        let client = BuilderClient::new(Builder::Titan, ());
+        let params = client.params(&bundle(), Some(&refund())).unwrap();
+        assert_eq!(params["refundPercent"], 90);
+        assert_eq!(params["refundIndex"], 1);
+        assert!(!params.contains_key("refundTransactionHashes"));
+    }
+
+    #[test]
+    fn test_standard_ignores_refund() {
+        let client = BuilderClient::new(Builder::Flashbots, ());
+        let params = client.params(&bundle(), Some(&refund())).unwrap();
+        assert!(!params.contains_key("refundPercent"));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_bundle() -> anyhow::Result<()> {
+        let received = Arc::new(Mutex::new(vec![]));
+        let mut module = RpcModule::new(Arc::clone(&received));
+        module.register_method(
+            "eth_sendBundle",
+            |params, received, _| {
+                let (params,): (BundleParams,) = params.parse()?;
+                received.lock().unwrap().push(params);
+                Ok::<_, jsonrpsee::types::ErrorObjectOwned>(BundleHash {
+                    bundle_hash: B256::repeat_byte(0x11),
+                })
+            },
+        )?;
+        let server = Server::builder().build("127.0.0.1:0").await?;
+        let url = format!("http://{}", server.local_addr()?);
+        tokio::spawn(server.start(module).stopped());
+
+        let builders = [
+            BuilderClient::http(Builder::Beaverbuild, &url)?,
+            BuilderClient::http(Builder::Titan, &url)?,
+        ];
+        let responses =
+            broadcast_bundle(&builders, bundle(), Some(refund())).await;
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[1].relay, "Titan");
+        assert!(responses.iter().all(|response| response.result.is_ok()));
+
+        let received = received.lock().unwrap();
+        assert!(received[0].contains_key("refundTransactionHashes"));
+        assert!(received[1].contains_key("refundIndex"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,302 @@
+//! Builders bundles can be shared with, see `Privacy::builders`.
+//!
+//! ```ignore
+//! let registry = BuilderRegistry::new();
+//! let privacy = Privacy {
+//!     hints: None,
+//!     builders: Some(registry.builder_names([Builder::Flashbots, Builder::Titan]).await?),
+//! };
+//! ```
+
+#[cfg(feature = "client")]
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "client")]
+use tokio::sync::RwLock;
+
+/// Registrations of the builders accepting MEV-Share bundles.
+pub const BUILDER_REGISTRATIONS_URL: &str = "https://raw.githubusercontent.com/flashbots/dowg/main/builder-registrations.json";
+
+/// How long fetched registrations are cached.
+#[cfg(feature = "client")]
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Well-known builders, named as in the builder registrations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Builder {
+    Flashbots,
+    Beaverbuild,
+    Titan,
+    Rsync,
+    Builder0x69,
+    F1b,
+    BuildAi,
+    JetBuilder,
+}
+
+impl Builder {
+    pub const ALL: [Builder; 8] = [
+        Self::Flashbots,
+        Self::Beaverbuild,
+        Self::Titan,
+        Self::Rsync,
+        Self::Builder0x69,
+        Self::F1b,
+        Self::BuildAi,
+        Self::JetBuilder,
+    ];
+
+    /// Name of the builder used in `Privacy::builders`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Flashbots => "flashbots",
+            Self::Beaverbuild => "beaverbuild.org",
+            Self::Titan => "Titan",
+            Self::Rsync => "rsync",
+            Self::Builder0x69 => "builder0x69",
+            Self::F1b => "f1b.io",
+            Self::BuildAi => "BuildAI",
+            Self::JetBuilder => "JetBuilder",
+        }
+    }
+}
+
+impl fmt::Display for Builder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Builder {
+    type Err = UnknownBuilder;
+
+    /// Parses the builder name, ignoring case.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|builder| builder.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| UnknownBuilder(name.to_string()))
+    }
+}
+
+/// Builder name which is neither well-known nor registered.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown builder {0}")]
+pub struct UnknownBuilder(pub String);
+
+/// Entry of the builder registrations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuilderRegistration {
+    /// Name used in `Privacy::builders`.
+    pub name: String,
+    /// RPC endpoint of the builder.
+    pub rpc: String,
+    /// APIs supported by the builder, e.g. `v0.1/mev_sendBundle`.
+    #[serde(default)]
+    pub supported_apis: Vec<String>,
+}
+
+/// Error of a [BuilderRegistry].
+#[cfg(feature = "client")]
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    #[error("failed to fetch builder registrations: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error(transparent)]
+    UnknownBuilder(#[from] UnknownBuilder),
+}
+
+#[cfg(feature = "client")]
+#[derive(Debug)]
+struct Cached {
+    registrations: Vec<BuilderRegistration>,
+    fetched_at: Instant,
+}
+
+/// Fetches and caches the builder registrations, to validate builder names
+/// before sharing bundles with them.
+#[cfg(feature = "client")]
+#[derive(Debug)]
+pub struct BuilderRegistry {
+    url: String,
+    ttl: Duration,
+    client: reqwest::Client,
+    cached: RwLock<Option<Cached>>,
+}
+
+#[cfg(feature = "client")]
+impl Default for BuilderRegistry {
+    fn default() -> Self {
+        Self {
+            url: BUILDER_REGISTRATIONS_URL.to_string(),
+            ttl: DEFAULT_TTL,
+            client: reqwest::Client::new(),
+            cached: RwLock::new(None),
+        }
+    }
+}
+
+#[cfg(feature = "client")]
+impl BuilderRegistry {
+    /// Creates a registry fetching the [Flashbots
+    /// registrations](BUILDER_REGISTRATIONS_URL).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with fixed registrations, which are never
+    /// fetched.
+    pub fn from_registrations(registrations: Vec<BuilderRegistration>) -> Self {
+        Self {
+            ttl: Duration::MAX,
+            cached: RwLock::new(Some(Cached {
+                registrations,
+                fetched_at: Instant::now(),
+            })),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the URL the registrations are fetched from.
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Sets how long fetched registrations are cached.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns the registrations, fetching them if the cache is stale.
+    pub async fn registrations(
+        &self,
+    ) -> Result<Vec<BuilderRegistration>, RegistryError> {
+        if let Some(cached) = self.cached.read().await.as_ref()
+            && cached.fetched_at.elapsed() < self.ttl
+        {
+            return Ok(cached.registrations.clone());
+        }
+
+        let mut cached = self.cached.write().await;
+        // Another task may have fetched them in the meantime.
+        if let Some(cached) = cached.as_ref()
+            && cached.fetched_at.elapsed() < self.ttl
+        {
+            return Ok(cached.registrations.clone());
+        }
+        let registrations: Vec<BuilderRegistration> = self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        tracing::debug!(
+            count = registrations.len(),
+            "fetched builder registrations"
+        );
+        *cached = Some(Cached {
+            registrations: registrations.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(registrations)
+    }
+
+    /// Checks that the builder is registered, returning its exact name.
+    pub async fn validate(&self, name: &str) -> Result<String, RegistryError> {
+        self.registrations()
+            .await?
+            .into_iter()
+            .find(|registration| registration.name.eq_ignore_ascii_case(name))
+            .map(|registration| registration.name)
+            .ok_or_else(|| UnknownBuilder(name.to_string()).into())
+    }
+
+    /// Names of the given builders for `Privacy::builders`, failing if any
+    /// of them isn't registered (anymore).
+    pub async fn builder_names(
+        &self,
+        builders: impl IntoIterator<Item = Builder>,
+    ) -> Result<Vec<String>, RegistryError> {
+        let registered: HashSet<String> = self
+            .registrations()
+            .await?
+            .into_iter()
+            .map(|registration| registration.name)
+            .collect();
+        builders
+            .into_iter()
+            .map(|builder| {
+                let name = builder.name().to_string();
+                if registered.contains(&name) {
+                    Ok(name)
+                } else {
+                    Err(UnknownBuilder(name).into())
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_names() {
+        for builder in Builder::ALL {
+            assert_eq!(builder.name().parse(), Ok(builder));
+        }
+        assert_eq!("TITAN".parse(), Ok(Builder::Titan));
+        assert_eq!(
+            "flashbot".parse::<Builder>(),
+            Err(UnknownBuilder("flashbot".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_registration_serde() {
+        let json = r#"[{"name":"flashbots","rpc":"https://relay.flashbots.net","supported-apis":["v0.1/mev_sendBundle"]}]"#;
+        let registrations: Vec<BuilderRegistration> =
+            serde_json::from_str(json).unwrap();
+        assert_eq!(registrations[0].name, "flashbots");
+        assert_eq!(
+            registrations[0].supported_apis,
+            ["v0.1/mev_sendBundle"]
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_registry_validates_builders() {
+        let registry =
+            BuilderRegistry::from_registrations(vec![BuilderRegistration {
+                name: "flashbots".to_string(),
+                rpc: "https://relay.flashbots.net".to_string(),
+                supported_apis: vec![],
+            }]);
+
+        assert_eq!(
+            registry.validate("Flashbots").await.unwrap(),
+            "flashbots"
+        );
+        assert!(registry.validate("flashbot").await.is_err());
+        assert_eq!(
+            registry.builder_names([Builder::Flashbots]).await.unwrap(),
+            ["flashbots"]
+        );
+        assert!(matches!(
+            registry.builder_names([Builder::Titan]).await,
+            Err(RegistryError::UnknownBuilder(_))
+        ));
+    }
+}
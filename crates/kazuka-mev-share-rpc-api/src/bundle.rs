@@ -0,0 +1,269 @@
+//! Fluent construction of bundles.
+
+use alloy::{
+    primitives::{Address, B256, BlockNumber, Bytes, keccak256},
+    rpc::types::mev::{
+        BundleItem, EthSendBundle, Inclusion, MevSendBundle, Privacy,
+        PrivacyHint, ProtocolVersion, Refund, RefundConfig, Validity,
+    },
+};
+
+use crate::{
+    builders::Builder,
+    protocol::{BundleValidationError, validate_bundle},
+};
+
+/// Bundle can't be sent via `eth_sendBundle`, because it contains more than
+/// signed transactions.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("eth_sendBundle bundles can only contain signed transactions")]
+pub struct NotAnEthBundle;
+
+/// Builds [MevSendBundle]s and [EthSendBundle]s.
+///
+/// ```
+/// # use alloy::primitives::{Address, B256, Bytes};
+/// # use alloy::rpc::types::mev::PrivacyHint;
+/// # use kazuka_mev_share_rpc_api::bundle::BundleBuilder;
+/// # let (tx_hash, signed_tx) = (B256::ZERO, Bytes::from_static(b"tx"));
+/// let bundle = BundleBuilder::for_block(100)
+///     .backrun_of(tx_hash)
+///     .tx(signed_tx)
+///     .allow_revert()
+///     .refund_to(Address::ZERO, 90)
+///     .privacy(PrivacyHint::default().hash())
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct BundleBuilder {
+    protocol_version: ProtocolVersion,
+    inclusion: Inclusion,
+    body: Vec<BundleItem>,
+    refunds: Vec<Refund>,
+    refund_configs: Vec<RefundConfig>,
+    hints: Option<PrivacyHint>,
+    builders: Option<Vec<String>>,
+}
+
+impl BundleBuilder {
+    /// Starts a bundle targeting the given block.
+    pub fn for_block(block: BlockNumber) -> Self {
+        Self {
+            protocol_version: ProtocolVersion::V0_1,
+            inclusion: Inclusion {
+                block,
+                max_block: None,
+            },
+            body: vec![],
+            refunds: vec![],
+            refund_configs: vec![],
+            hints: None,
+            builders: None,
+        }
+    }
+
+    /// Keeps the bundle valid until the given block.
+    pub fn max_block(mut self, max_block: BlockNumber) -> Self {
+        self.inclusion.max_block = Some(max_block);
+        self
+    }
+
+    /// Sets the protocol version, `v0.1` by default.
+    pub fn protocol_version(
+        mut self,
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        self.protocol_version = protocol_version;
+        self
+    }
+
+    /// Adds the transaction with the given hash, e.g. a transaction shared
+    /// by the matchmaker to backrun.
+    pub fn backrun_of(mut self, tx_hash: B256) -> Self {
+        self.body.push(BundleItem::Hash { hash: tx_hash });
+        self
+    }
+
+    /// Adds a signed transaction, which must not revert.
+    pub fn tx(mut self, tx: impl Into<Bytes>) -> Self {
+        self.body.push(BundleItem::Tx {
+            tx: tx.into(),
+            can_revert: false,
+        });
+        self
+    }
+
+    /// Allows the last added transaction to revert.
+    ///
+    /// # Panics
+    ///
+    /// If the last added item isn't a signed transaction.
+    pub fn allow_revert(mut self) -> Self {
+        match self.body.last_mut() {
+            Some(BundleItem::Tx { can_revert, .. }) => *can_revert = true,
+            _ => panic!("allow_revert must follow a transaction"),
+        }
+        self
+    }
+
+    /// Adds a nested bundle.
+    pub fn bundle(mut self, bundle: MevSendBundle) -> Self {
+        self.body.push(BundleItem::Bundle { bundle });
+        self
+    }
+
+    /// Refunds `percent` of the bundle's value to the given address.
+    pub fn refund_to(mut self, address: Address, percent: u64) -> Self {
+        self.refund_configs.push(RefundConfig { address, percent });
+        self
+    }
+
+    /// Refunds `percent` of the bundle's value to the sender of the body
+    /// item at `body_idx`.
+    pub fn refund(mut self, body_idx: u64, percent: u64) -> Self {
+        self.refunds.push(Refund { body_idx, percent });
+        self
+    }
+
+    /// Sets the hints shared about the bundle.
+    pub fn privacy(mut self, hints: PrivacyHint) -> Self {
+        self.hints = Some(hints);
+        self
+    }
+
+    /// Only shares the bundle with the given builders.
+    pub fn builders(
+        mut self,
+        builders: impl IntoIterator<Item = Builder>,
+    ) -> Self {
+        self.builders = Some(
+            builders
+                .into_iter()
+                .map(|builder| builder.name().to_string())
+                .collect(),
+        );
+        self
+    }
+
+    /// Builds a `mev_sendBundle` bundle, which is
+    /// [validated](validate_bundle).
+    pub fn build(self) -> Result<MevSendBundle, BundleValidationError> {
+        let validity = (!self.refunds.is_empty()
+            || !self.refund_configs.is_empty())
+        .then(|| Validity {
+            refund: (!self.refunds.is_empty()).then_some(self.refunds),
+            refund_config: (!self.refund_configs.is_empty())
+                .then_some(self.refund_configs),
+        });
+        let privacy =
+            (self.hints.is_some() || self.builders.is_some()).then(|| {
+                Privacy {
+                    hints: self.hints,
+                    builders: self.builders,
+                }
+            });
+        let bundle = MevSendBundle {
+            protocol_version: self.protocol_version,
+            inclusion: self.inclusion,
+            bundle_body: self.body,
+            validity,
+            privacy,
+        };
+        validate_bundle(&bundle)?;
+        Ok(bundle)
+    }
+
+    /// Builds an `eth_sendBundle` bundle, failing if it contains more than
+    /// signed transactions. Refunds, privacy settings and the max block
+    /// don't apply to it.
+    pub fn build_eth(self) -> Result<EthSendBundle, NotAnEthBundle> {
+        let mut txs = vec![];
+        let mut reverting_tx_hashes = vec![];
+        for item in self.body {
+            let BundleItem::Tx { tx, can_revert } = item else {
+                return Err(NotAnEthBundle);
+            };
+            if can_revert {
+                reverting_tx_hashes.push(keccak256(&tx));
+            }
+            txs.push(tx);
+        }
+        Ok(EthSendBundle {
+            txs,
+            block_number: self.inclusion.block,
+            reverting_tx_hashes,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_mev_bundle() {
+        let tx = Bytes::from_static(b"tx");
+        let bundle = BundleBuilder::for_block(100)
+            .max_block(105)
+            .backrun_of(B256::ZERO)
+            .tx(tx.clone())
+            .allow_revert()
+            .refund_to(Address::ZERO, 90)
+            .builders([Builder::Flashbots])
+            .build()
+            .unwrap();
+
+        assert_eq!(bundle.inclusion.max_block, Some(105));
+        assert_eq!(
+            bundle.bundle_body[1],
+            BundleItem::Tx {
+                tx,
+                can_revert: true,
+            }
+        );
+        let validity = bundle.validity.unwrap();
+        assert_eq!(validity.refund, None);
+        assert_eq!(
+            validity.refund_config.unwrap()[0].percent,
+            90
+        );
+        let privacy = bundle.privacy.unwrap();
+        assert_eq!(privacy.hints, None);
+        assert_eq!(
+            privacy.builders,
+            Some(vec!["flashbots".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_build_validates_bundle() {
+        let result = BundleBuilder::for_block(100).build();
+        assert!(matches!(
+            result,
+            Err(BundleValidationError::EmptyBody)
+        ));
+    }
+
+    #[test]
+    fn test_build_eth_bundle() {
+        let tx = Bytes::from_static(b"tx");
+        let bundle = BundleBuilder::for_block(100)
+            .tx(tx.clone())
+            .allow_revert()
+            .build_eth()
+            .unwrap();
+        assert_eq!(bundle.txs, [tx.clone()]);
+        assert_eq!(
+            bundle.reverting_tx_hashes,
+            [keccak256(&tx)]
+        );
+
+        let result = BundleBuilder::for_block(100)
+            .backrun_of(B256::ZERO)
+            .tx(tx)
+            .build_eth();
+        assert!(matches!(result, Err(NotAnEthBundle)));
+    }
+}
@@ -0,0 +1,450 @@
+//! High-level MEV-Share client with signing, timeouts and retries
+//! pre-configured.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), jsonrpsee::core::ClientError> {
+//! use alloy::signers::local::PrivateKeySigner;
+//! use kazuka_mev_share_rpc_api::{MevShareClient, clients::FlashbotsApiClient};
+//!
+//! let client = MevShareClient::builder()
+//!     .url("https://relay.flashbots.net")
+//!     .signer(PrivateKeySigner::random())
+//!     .build()?;
+//! let stats = client.get_user_stats(Default::default()).await?;
+//!
+//! // Builders accepting bundles over WebSocket.
+//! let ws_client = MevShareClient::builder()
+//!     .url("wss://builder.example/ws")
+//!     .build_ws()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use alloy::{
+    primitives::{Address, B256, Bytes, U64},
+    rpc::types::mev::{
+        BundleStats, EthCallBundle, EthCallBundleResponse, EthCancelBundle,
+        EthCancelPrivateTransaction, EthSendBundle, EthSendPrivateTransaction,
+        MevSendBundle, ProtocolVersion, SimBundleOverrides, SimBundleResponse,
+        UserStats,
+    },
+    signers::Signer,
+};
+use async_trait::async_trait;
+use http::{HeaderMap, HeaderName, HeaderValue};
+use jsonrpsee::{
+    core::ClientError, http_client::HttpClientBuilder,
+    ws_client::WsClientBuilder,
+};
+use tower::ServiceBuilder;
+
+use crate::{
+    EthBundleApiClient, FlashbotsApiClient, MevApiClient,
+    middleware::{FlashbotsAuthLayer, MetricsLayer, RetryLayer},
+    protocol::validate_bundle,
+    types::{
+        BundleHash, SendBundleResponse,
+        flashbots::{FeeRefundRecipient, FeeRefundTotals},
+    },
+};
+
+/// Default timeout of a single request.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Default number of retries of a failed request.
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// All client traits combined, so that the facade can hold any of them.
+trait Inner:
+    EthBundleApiClient + MevApiClient + FlashbotsApiClient + Send + Sync
+{
+}
+
+impl<T> Inner for T where
+    T: EthBundleApiClient + MevApiClient + FlashbotsApiClient + Send + Sync
+{
+}
+
+/// Client for the MEV-Share and Flashbots APIs, which signs every request
+/// with the `X-Flashbots-Signature` header, retries failed requests (see
+/// [RetryLayer]) and records metrics of every call (see [MetricsLayer]).
+///
+/// Implements [EthBundleApiClient], [MevApiClient] and
+/// [FlashbotsApiClient]. Cloning is cheap.
+///
+/// `mev_sendBundle` bundles are [validated](validate_bundle) before they are
+/// sent.
+#[derive(Clone)]
+pub struct MevShareClient {
+    inner: Arc<dyn Inner>,
+    url: String,
+    protocol_version: Option<ProtocolVersion>,
+}
+
+impl MevShareClient {
+    pub fn builder() -> MevShareClientBuilder {
+        MevShareClientBuilder::default()
+    }
+
+    /// URL of the relay.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Bundle protocol version accepted by the relay, if configured.
+    pub fn protocol_version(&self) -> Option<&ProtocolVersion> {
+        self.protocol_version.as_ref()
+    }
+
+    fn check_bundle(&self, bundle: &MevSendBundle) -> Result<(), ClientError> {
+        if let Some(version) = &self.protocol_version
+            && bundle.protocol_version != *version
+        {
+            return Err(ClientError::Custom(format!(
+                "Relay accepts protocol version {version:?}, got {:?}",
+                bundle.protocol_version
+            )));
+        }
+        validate_bundle(bundle).map_err(|err| {
+            ClientError::Custom(format!("Invalid bundle: {err}"))
+        })
+    }
+}
+
+impl fmt::Debug for MevShareClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MevShareClient")
+            .field("url", &self.url)
+            .field(
+                "protocol_version",
+                &self.protocol_version,
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder of a [MevShareClient].
+#[derive(Debug, Clone)]
+pub struct MevShareClientBuilder<S = ()> {
+    url: Option<String>,
+    signer: S,
+    headers: HeaderMap,
+    request_timeout: Duration,
+    max_retries: u32,
+    protocol_version: Option<ProtocolVersion>,
+}
+
+impl Default for MevShareClientBuilder {
+    fn default() -> Self {
+        Self {
+            url: None,
+            signer: (),
+            headers: HeaderMap::new(),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+            protocol_version: None,
+        }
+    }
+}
+
+impl<S> MevShareClientBuilder<S> {
+    /// Sets the URL of the relay, e.g. `https://relay.flashbots.net`.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Sets the signer used to sign the requests.
+    pub fn signer<T>(self, signer: T) -> MevShareClientBuilder<T> {
+        MevShareClientBuilder {
+            url: self.url,
+            signer,
+            headers: self.headers,
+            request_timeout: self.request_timeout,
+            max_retries: self.max_retries,
+            protocol_version: self.protocol_version,
+        }
+    }
+
+    /// Adds a header sent with every request, or with the WebSocket
+    /// handshake, e.g. an API key.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets the timeout of a single request attempt.
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Sets the maximum number of retries of a failed request, `0` disables
+    /// retries.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the bundle protocol version accepted by the relay, bundles of
+    /// other versions are rejected without sending them. See
+    /// [negotiate_protocol_version](crate::protocol::negotiate_protocol_version)
+    /// to find it out.
+    pub fn protocol_version(
+        mut self,
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        self.protocol_version = Some(protocol_version);
+        self
+    }
+
+    /// Connects to a relay accepting bundles over a persistent WebSocket
+    /// connection (`ws://` or `wss://`), which saves the TLS and HTTP
+    /// overhead of every submission.
+    ///
+    /// Messages can't carry per-request signatures, so the connection is
+    /// only authenticated by the [headers](Self::header) sent with the
+    /// handshake, and the signer is not used. Requests are not retried and
+    /// the connection is not re-established once closed.
+    pub async fn build_ws(self) -> Result<MevShareClient, ClientError> {
+        let url = required_url(self.url)?;
+        let client = WsClientBuilder::default()
+            .set_headers(self.headers)
+            .request_timeout(self.request_timeout)
+            .build(&url)
+            .await?;
+        Ok(MevShareClient {
+            inner: Arc::new(client),
+            url,
+            protocol_version: self.protocol_version,
+        })
+    }
+}
+
+fn required_url(url: Option<String>) -> Result<String, ClientError> {
+    url.ok_or_else(|| {
+        ClientError::Custom("MEV-Share client URL is not set".to_string())
+    })
+}
+
+impl<S> MevShareClientBuilder<S>
+where
+    S: Signer + Clone + Send + Sync + 'static,
+{
+    /// Builds the client, failing if the URL is missing or invalid.
+    pub fn build(self) -> Result<MevShareClient, ClientError> {
+        let url = required_url(self.url)?;
+        // Every retry is signed again.
+        let http_middleware = ServiceBuilder::new()
+            .layer(MetricsLayer::new())
+            .layer(RetryLayer::new().with_max_retries(self.max_retries))
+            .layer(FlashbotsAuthLayer::new(self.signer));
+        let client = HttpClientBuilder::default()
+            .set_headers(self.headers)
+            .set_http_middleware(http_middleware)
+            .request_timeout(self.request_timeout)
+            .build(&url)?;
+        Ok(MevShareClient {
+            inner: Arc::new(client),
+            url,
+            protocol_version: self.protocol_version,
+        })
+    }
+}
+
+#[async_trait]
+impl EthBundleApiClient for MevShareClient {
+    async fn send_bundle(
+        &self,
+        request: EthSendBundle,
+    ) -> Result<BundleHash, ClientError> {
+        EthBundleApiClient::send_bundle(self.inner.as_ref(), request).await
+    }
+
+    async fn call_bundle(
+        &self,
+        request: EthCallBundle,
+    ) -> Result<EthCallBundleResponse, ClientError> {
+        self.inner.call_bundle(request).await
+    }
+
+    async fn cancel_bundle(
+        &self,
+        request: EthCancelBundle,
+    ) -> Result<(), ClientError> {
+        self.inner.cancel_bundle(request).await
+    }
+
+    async fn send_private_transaction(
+        &self,
+        request: EthSendPrivateTransaction,
+    ) -> Result<B256, ClientError> {
+        self.inner.send_private_transaction(request).await
+    }
+
+    async fn send_private_raw_transaction(
+        &self,
+        bytes: Bytes,
+    ) -> Result<B256, ClientError> {
+        self.inner.send_private_raw_transaction(bytes).await
+    }
+
+    async fn cancel_private_transaction(
+        &self,
+        request: EthCancelPrivateTransaction,
+    ) -> Result<bool, ClientError> {
+        self.inner.cancel_private_transaction(request).await
+    }
+}
+
+#[async_trait]
+impl MevApiClient for MevShareClient {
+    async fn send_bundle(
+        &self,
+        request: MevSendBundle,
+    ) -> Result<SendBundleResponse, ClientError> {
+        self.check_bundle(&request)?;
+        MevApiClient::send_bundle(self.inner.as_ref(), request).await
+    }
+
+    async fn sim_bundle(
+        &self,
+        bundle: MevSendBundle,
+        sim_overrides: SimBundleOverrides,
+    ) -> Result<SimBundleResponse, ClientError> {
+        self.inner.sim_bundle(bundle, sim_overrides).await
+    }
+}
+
+#[async_trait]
+impl FlashbotsApiClient for MevShareClient {
+    async fn get_user_stats(
+        &self,
+        block_number: U64,
+    ) -> Result<UserStats, ClientError> {
+        self.inner.get_user_stats(block_number).await
+    }
+
+    async fn get_bundle_stats(
+        &self,
+        bundle_hash: B256,
+        block_number: U64,
+    ) -> Result<BundleStats, ClientError> {
+        self.inner.get_bundle_stats(bundle_hash, block_number).await
+    }
+
+    async fn get_fee_refund_totals_by_recipient(
+        &self,
+        recipient: Address,
+    ) -> Result<FeeRefundTotals, ClientError> {
+        self.inner
+            .get_fee_refund_totals_by_recipient(recipient)
+            .await
+    }
+
+    async fn set_fee_refund_recipient(
+        &self,
+        from: Address,
+        to: Address,
+    ) -> Result<FeeRefundRecipient, ClientError> {
+        self.inner.set_fee_refund_recipient(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use alloy::{primitives::bytes, signers::local::PrivateKeySigner};
+    use jsonrpsee::{RpcModule, server::Server};
+    #[cfg(test)]
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_build_requires_url() {
+        let result = MevShareClient::builder()
+            .signer(PrivateKeySigner::random())
+            .build();
+        assert!(matches!(
+            result,
+            Err(ClientError::Custom(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request() -> anyhow::Result<()> {
+        let hash = B256::repeat_byte(0x42);
+        let mut module = RpcModule::new(());
+        module.register_method(
+            "eth_sendPrivateRawTransaction",
+            move |_, _, _| hash,
+        )?;
+        let server = Server::builder().build("127.0.0.1:0").await?;
+        let addr = server.local_addr()?;
+        tokio::spawn(server.start(module).stopped());
+
+        let client = MevShareClient::builder()
+            .url(format!("http://{addr}"))
+            .signer(PrivateKeySigner::random())
+            .build()?;
+        let result =
+            client.send_private_raw_transaction(bytes!("0x01")).await?;
+        assert_eq!(result, hash);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_websocket_request() -> anyhow::Result<()> {
+        let mut module = RpcModule::new(());
+        module.register_method(
+            "eth_sendPrivateRawTransaction",
+            |_, _, _| B256::ZERO,
+        )?;
+        // The server accepts both HTTP and WebSocket connections.
+        let server = Server::builder().build("127.0.0.1:0").await?;
+        let addr = server.local_addr()?;
+        tokio::spawn(server.start(module).stopped());
+
+        let client = MevShareClient::builder()
+            .url(format!("ws://{addr}"))
+            .header(
+                HeaderName::from_static("x-api-key"),
+                HeaderValue::from_static("secret"),
+            )
+            .build_ws()
+            .await?;
+        let result =
+            client.send_private_raw_transaction(bytes!("0x01")).await?;
+        assert_eq!(result, B256::ZERO);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retries_transport_errors() -> anyhow::Result<()> {
+        // Reserves a port, which nothing listens on.
+        let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+
+        let client = MevShareClient::builder()
+            .url(format!("http://{addr}"))
+            .signer(PrivateKeySigner::random())
+            .max_retries(2)
+            .build()?;
+        let started = std::time::Instant::now();
+        let result = client.get_user_stats(U64::from(1)).await;
+
+        assert!(matches!(
+            result,
+            Err(ClientError::Transport(_))
+        ));
+        // Backed off 100ms and 200ms.
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        Ok(())
+    }
+}
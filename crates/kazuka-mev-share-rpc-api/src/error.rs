@@ -0,0 +1,196 @@
+//! Typed errors returned by relays.
+
+use std::time::Duration;
+
+use jsonrpsee::{core::ClientError, types::ErrorObject};
+
+/// JSON-RPC error code for exceeded limits.
+/// See: https://eips.ethereum.org/EIPS/eip-1474#error-codes
+const LIMIT_EXCEEDED_CODE: i32 = -32005;
+/// JSON-RPC error code for invalid method parameters.
+const INVALID_PARAMS_CODE: i32 = -32602;
+
+/// Error returned by a relay, decoded from the error shapes of Flashbots and
+/// other builders.
+///
+/// Client methods return the error as [ClientError::Call], use
+/// [ClientErrorExt::relay_error] to decode it.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RelayError {
+    /// Bundle exceeds the size or transaction count limit of the relay.
+    #[error("Bundle too large: {0}")]
+    BundleTooLarge(String),
+    /// Bundle failed the simulation, e.g. because a transaction reverted.
+    #[error("Bundle simulation failed: {0}")]
+    SimulationFailed(String),
+    /// Too many requests, `retry_after` is the delay suggested by the relay.
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// Missing or invalid `X-Flashbots-Signature` header.
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    /// Request parameters were rejected.
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+    /// Any other error.
+    #[error("Relay error (code {code}): {message}")]
+    Other {
+        code: i32,
+        message: String,
+        /// Additional error data as JSON, if any.
+        data: Option<String>,
+    },
+}
+
+impl RelayError {
+    /// Decodes the JSON-RPC error object returned by a relay.
+    pub fn from_error_object(error: &ErrorObject<'_>) -> Self {
+        let code = error.code();
+        let message = error.message().to_string();
+        let lowercase = message.to_lowercase();
+        let data = error.data().map(|data| data.get().to_string());
+
+        if code == LIMIT_EXCEEDED_CODE
+            || code == 429
+            || lowercase.contains("rate limit")
+            || lowercase.contains("too many requests")
+        {
+            let retry_after = data.as_deref().and_then(retry_after);
+            Self::RateLimited {
+                message,
+                retry_after,
+            }
+        } else if lowercase.contains("too large")
+            || lowercase.contains("too many txs")
+            || lowercase.contains("too many transactions")
+        {
+            Self::BundleTooLarge(message)
+        } else if lowercase.contains("signature") {
+            Self::InvalidSignature(message)
+        } else if lowercase.contains("simulation")
+            || lowercase.contains("sim failed")
+            || lowercase.contains("revert")
+        {
+            Self::SimulationFailed(message)
+        } else if code == INVALID_PARAMS_CODE {
+            Self::InvalidParams(message)
+        } else {
+            Self::Other {
+                code,
+                message,
+                data,
+            }
+        }
+    }
+
+    /// Whether the request may succeed if sent again later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::RateLimited { .. })
+    }
+}
+
+/// Delay from error data like `{"retryAfter": 2}` (in seconds).
+fn retry_after(data: &str) -> Option<Duration> {
+    let data: serde_json::Value = serde_json::from_str(data).ok()?;
+    let seconds = data
+        .get("retryAfter")
+        .or_else(|| data.get("retry_after"))?
+        .as_u64()?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// Decodes [RelayError]s from errors returned by client methods.
+pub trait ClientErrorExt {
+    /// The error returned by the relay, `None` if the request failed
+    /// before reaching it or the response couldn't be parsed.
+    fn relay_error(&self) -> Option<RelayError>;
+}
+
+impl ClientErrorExt for ClientError {
+    fn relay_error(&self) -> Option<RelayError> {
+        match self {
+            ClientError::Call(error) => {
+                Some(RelayError::from_error_object(error))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee::types::ErrorObjectOwned;
+    #[cfg(test)]
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn call_error(
+        code: i32,
+        message: &str,
+        data: Option<serde_json::Value>,
+    ) -> ClientError {
+        ClientError::Call(ErrorObjectOwned::owned(
+            code, message, data,
+        ))
+    }
+
+    #[test]
+    fn test_relay_error_decoding() {
+        let error = call_error(
+            -32005,
+            "rate limit exceeded",
+            Some(serde_json::json!({ "retryAfter": 2 })),
+        );
+        assert_eq!(
+            error.relay_error(),
+            Some(RelayError::RateLimited {
+                message: "rate limit exceeded".to_string(),
+                retry_after: Some(Duration::from_secs(2)),
+            })
+        );
+        assert!(error.relay_error().unwrap().is_retryable());
+
+        let error = call_error(-32000, "bundle too large", None);
+        assert!(matches!(
+            error.relay_error(),
+            Some(RelayError::BundleTooLarge(_))
+        ));
+
+        let error = call_error(
+            -32000,
+            "no valid signature in X-Flashbots-Signature header",
+            None,
+        );
+        assert!(matches!(
+            error.relay_error(),
+            Some(RelayError::InvalidSignature(_))
+        ));
+
+        let error = call_error(-32000, "bundle reverted", None);
+        assert!(matches!(
+            error.relay_error(),
+            Some(RelayError::SimulationFailed(_))
+        ));
+
+        let error = call_error(-32602, "invalid block number", None);
+        assert!(matches!(
+            error.relay_error(),
+            Some(RelayError::InvalidParams(_))
+        ));
+
+        let error = call_error(-32000, "internal error", None);
+        assert!(matches!(
+            error.relay_error(),
+            Some(RelayError::Other { code: -32000, .. })
+        ));
+
+        assert_eq!(
+            ClientError::RequestTimeout.relay_error(),
+            None
+        );
+    }
+}
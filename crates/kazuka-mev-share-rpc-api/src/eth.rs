@@ -1,6 +1,6 @@
 #[cfg(feature = "client")]
 use alloy::rpc::types::mev::{
-    EthCallBundle, EthCallBundleTransactionResult, EthCancelBundle,
+    EthCallBundle, EthCallBundleResponse, EthCancelBundle,
     EthCancelPrivateTransaction, EthSendBundle, EthSendPrivateTransaction,
 };
 use alloy::{
@@ -13,6 +13,8 @@ use jsonrpsee::{core::ClientError, proc_macros::rpc};
 use tracing::instrument;
 
 use crate::types::BundleHash;
+#[cfg(feature = "client")]
+use crate::types::ReplaceableBundle;
 
 /// jsonrpsee generated code.
 ///
@@ -20,8 +22,7 @@ use crate::types::BundleHash;
 /// replaced by the `EthBundleApiClient` trait.
 mod rpc {
     use alloy::rpc::types::mev::{
-        EthCallBundle, EthCallBundleTransactionResult, EthCancelBundle,
-        EthSendBundle,
+        EthCallBundle, EthCallBundleResponse, EthCancelBundle, EthSendBundle,
     };
     use jsonrpsee::core::RpcResult;
 
@@ -48,7 +49,7 @@ mod rpc {
         async fn call_bundle(
             &self,
             request: EthCallBundle,
-        ) -> RpcResult<EthCallBundleTransactionResult>;
+        ) -> RpcResult<EthCallBundleResponse>;
 
         /// The `eth_cancelBundle` is used to prevent a submitted bundle from
         /// being included on-chain.
@@ -113,10 +114,11 @@ pub trait EthBundleApiClient {
 
     /// The `eth_callBundle` is used to simulate a bundle against a specific
     /// block, including simulating a bundle at the top of the next block.
+    /// The response contains the results of all transactions of the bundle.
     async fn call_bundle(
         &self,
         request: EthCallBundle,
-    ) -> Result<EthCallBundleTransactionResult, ClientError>;
+    ) -> Result<EthCallBundleResponse, ClientError>;
 
     /// The `eth_cancelBundle` is used to prevent a submitted bundle from
     /// being included on-chain.
@@ -177,7 +179,7 @@ where
     async fn call_bundle(
         &self,
         request: EthCallBundle,
-    ) -> Result<EthCallBundleTransactionResult, ClientError> {
+    ) -> Result<EthCallBundleResponse, ClientError> {
         rpc::EthBundleApiClient::call_bundle(self, request).await
     }
 
@@ -214,6 +216,58 @@ where
     }
 }
 
+/// Replacing and cancelling bundles by their replacement UUID.
+///
+/// A bundle sent with the same replacement UUID as a previous one replaces
+/// it, and both can be cancelled via `eth_cancelBundle` until the target
+/// block.
+///
+/// See: https://docs.flashbots.net/flashbots-auction/advanced/bundle-cancellations
+#[cfg(feature = "client")]
+#[async_trait]
+pub trait BundleReplacementExt: EthBundleApiClient {
+    /// Sends the bundle with a new replacement UUID, unless it already has
+    /// one.
+    async fn send_replaceable_bundle(
+        &self,
+        mut request: EthSendBundle,
+    ) -> Result<ReplaceableBundle, ClientError> {
+        let replacement_uuid = request
+            .replacement_uuid
+            .get_or_insert_with(|| uuid::Uuid::new_v4().to_string())
+            .clone();
+        let response = self.send_bundle(request).await?;
+        Ok(ReplaceableBundle {
+            bundle_hash: response.bundle_hash,
+            replacement_uuid,
+        })
+    }
+
+    /// Replaces the bundle sent with the given replacement UUID.
+    async fn replace_bundle(
+        &self,
+        replacement_uuid: &str,
+        mut request: EthSendBundle,
+    ) -> Result<BundleHash, ClientError> {
+        request.replacement_uuid = Some(replacement_uuid.to_string());
+        self.send_bundle(request).await
+    }
+
+    /// Cancels the bundle sent with the given replacement UUID.
+    async fn cancel_by_uuid(
+        &self,
+        replacement_uuid: &str,
+    ) -> Result<(), ClientError> {
+        self.cancel_bundle(EthCancelBundle {
+            replacement_uuid: replacement_uuid.to_string(),
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "client")]
+impl<T> BundleReplacementExt for T where T: EthBundleApiClient + Sync + ?Sized {}
+
 #[cfg(all(test, feature = "client"))]
 mod tests {
     use std::net::SocketAddr;
@@ -221,8 +275,8 @@ mod tests {
     use alloy::{
         primitives::{U256, address, b256, bytes},
         rpc::types::mev::{
-            EthCallBundle, EthCallBundleTransactionResult, EthCancelBundle,
-            EthSendBundle,
+            EthCallBundle, EthCallBundleResponse,
+            EthCallBundleTransactionResult, EthCancelBundle, EthSendBundle,
         },
     };
     use async_trait::async_trait;
@@ -266,7 +320,7 @@ mod tests {
         async fn call_bundle(
             &self,
             request: EthCallBundle,
-        ) -> RpcResult<EthCallBundleTransactionResult>;
+        ) -> RpcResult<EthCallBundleResponse>;
 
         #[method(name = "cancelBundle")]
         async fn cancel_bundle(
@@ -310,9 +364,9 @@ mod tests {
 
         async fn call_bundle(
             &self,
-            _request: EthCallBundle,
-        ) -> RpcResult<EthCallBundleTransactionResult> {
-            Ok(EthCallBundleTransactionResult {
+            request: EthCallBundle,
+        ) -> RpcResult<EthCallBundleResponse> {
+            let result = EthCallBundleTransactionResult {
                 coinbase_diff: U256::from(10000000000063000u64),
                 eth_sent_to_coinbase: U256::from(10000000000000000u64),
                 from_address: address!(
@@ -329,6 +383,19 @@ mod tests {
                 ),
                 value: Some(bytes!("0x")),
                 revert: None,
+            };
+            let results = vec![result; request.txs.len()];
+            Ok(EthCallBundleResponse {
+                bundle_hash: b256!(
+                    "0xbeefbeefbeef0000000000000000000000000000000000000000000000000000"
+                ),
+                bundle_gas_price: U256::from(476190476193u64),
+                coinbase_diff: U256::from(20000000000126000u64),
+                eth_sent_to_coinbase: U256::from(20000000000000000u64),
+                gas_fees: U256::from(126000u64),
+                total_gas_used: 21000 * results.len() as u64,
+                results,
+                state_block_number: 5221585,
             })
         }
 
@@ -366,7 +433,7 @@ mod tests {
     }
 
     async fn start_mock_server() -> anyhow::Result<SocketAddr> {
-        let server = Server::builder().build("127.0.0.1:3001").await?;
+        let server = Server::builder().build("127.0.0.1:0").await?;
         let addr = server.local_addr()?;
 
         let handle = server.start(EthBundleApiMockServiceImpl.into_rpc());
@@ -421,4 +488,63 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_call_bundle_returns_all_results() -> anyhow::Result<()> {
+        init_tracing();
+
+        let server_addr = start_mock_server().await?;
+
+        let client = HttpClientBuilder::default()
+            .build(format!("http://{server_addr}"))?;
+
+        let client = Client {
+            inner: Box::new(client),
+        };
+
+        let tx = bytes!(
+            "0x02f86b0180843b9aca00852ecc889a0082520894c87037874aed04e51c29f582394217a0a2b89d808080c080a0a463985c616dd8ee17d7ef9112af4e6e06a27b071525b42182fe7b0b5c8b4925a00af5ca177ffef2ff28449292505d41be578bebb77110dfc09361d2fb56998260"
+        );
+        let request = EthCallBundle {
+            txs: vec![tx.clone(), tx],
+            block_number: 0x1,
+            ..Default::default()
+        };
+
+        let response = client.inner.call_bundle(request).await?;
+        assert_eq!(response.results.len(), 2);
+        assert_eq!(response.total_gas_used, 42000);
+        assert_eq!(response.state_block_number, 5221585);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replaceable_bundle() -> anyhow::Result<()> {
+        let server_addr = start_mock_server().await?;
+        let client = HttpClientBuilder::default()
+            .build(format!("http://{server_addr}"))?;
+
+        let bundle = client
+            .send_replaceable_bundle(EthSendBundle::default())
+            .await?;
+        assert!(uuid::Uuid::parse_str(&bundle.replacement_uuid).is_ok());
+
+        let request = EthSendBundle {
+            replacement_uuid: Some("uuid".to_string()),
+            ..Default::default()
+        };
+        let bundle = client.send_replaceable_bundle(request).await?;
+        assert_eq!(bundle.replacement_uuid, "uuid");
+
+        client
+            .replace_bundle(
+                &bundle.replacement_uuid,
+                EthSendBundle::default(),
+            )
+            .await?;
+        client.cancel_by_uuid(&bundle.replacement_uuid).await?;
+
+        Ok(())
+    }
 }
@@ -1,12 +1,17 @@
 use alloy::{
-    primitives::{B256, U64},
+    primitives::{Address, B256, U64},
     rpc::types::mev::{BundleStats, UserStats},
 };
 use async_trait::async_trait;
 use jsonrpsee::{core::ClientError, proc_macros::rpc};
 use tracing::instrument;
 
-use crate::types::{GetBundleStatsRequest, GetUserStatsRequest};
+#[cfg(feature = "client")]
+use crate::types::flashbots::SearcherReputation;
+use crate::types::flashbots::{
+    FeeRefundRecipient, FeeRefundTotals, GetBundleStatsRequest,
+    GetUserStatsRequest,
+};
 
 /// Generates a client using jsonrpsee proc macros.
 ///
@@ -20,11 +25,11 @@ mod rpc {
     use super::*;
 
     /// Flashbots RPC interface.
-    #[cfg_attr(not(feature = "server"), rpc(client, namespace = "mev"))]
-    #[cfg_attr(not(feature = "client"), rpc(server, namespace = "mev"))]
+    #[cfg_attr(not(feature = "server"), rpc(client, namespace = "flashbots"))]
+    #[cfg_attr(not(feature = "client"), rpc(server, namespace = "flashbots"))]
     #[cfg_attr(
         all(feature = "client", feature = "server"),
-        rpc(client, server, namespace = "mev")
+        rpc(client, server, namespace = "flashbots")
     )]
     #[async_trait]
     pub trait FlashbotsApi {
@@ -41,6 +46,21 @@ mod rpc {
             &self,
             request: GetBundleStatsRequest,
         ) -> RpcResult<BundleStats>;
+
+        /// See [`super::FlashbotsApiClient::get_fee_refund_totals_by_recipient`]
+        #[method(name = "getFeeRefundTotalsByRecipient")]
+        async fn get_fee_refund_totals_by_recipient(
+            &self,
+            recipient: Address,
+        ) -> RpcResult<FeeRefundTotals>;
+
+        /// See [`super::FlashbotsApiClient::set_fee_refund_recipient`]
+        #[method(name = "setFeeRefundRecipient")]
+        async fn set_fee_refund_recipient(
+            &self,
+            from: Address,
+            to: Address,
+        ) -> RpcResult<FeeRefundRecipient>;
     }
 }
 
@@ -83,6 +103,33 @@ pub trait FlashbotsApiClient {
         bundle_hash: B256,
         block_number: U64,
     ) -> Result<BundleStats, ClientError>;
+
+    /// Returns the total amount of fee refunds that have been earned by the
+    /// recipient, both pending and already paid out.
+    async fn get_fee_refund_totals_by_recipient(
+        &self,
+        recipient: Address,
+    ) -> Result<FeeRefundTotals, ClientError>;
+
+    /// Delegates the fee refunds of `from` to `to`, future refunds are sent
+    /// to `to` instead.
+    ///
+    /// The request must be signed by `from`.
+    async fn set_fee_refund_recipient(
+        &self,
+        from: Address,
+        to: Address,
+    ) -> Result<FeeRefundRecipient, ClientError>;
+
+    /// Returns the reputation of the signing searcher, derived from its
+    /// [user stats](Self::get_user_stats).
+    async fn get_searcher_reputation(
+        &self,
+        block_number: U64,
+    ) -> Result<SearcherReputation, ClientError> {
+        let stats = self.get_user_stats(block_number).await?;
+        Ok(stats.into())
+    }
 }
 
 #[cfg(feature = "client")]
@@ -114,4 +161,26 @@ where
         })
         .await
     }
+
+    /// See [`FlashbotsApiClient::get_fee_refund_totals_by_recipient`]
+    #[instrument(skip(self))]
+    async fn get_fee_refund_totals_by_recipient(
+        &self,
+        recipient: Address,
+    ) -> Result<FeeRefundTotals, ClientError> {
+        rpc::FlashbotsApiClient::get_fee_refund_totals_by_recipient(
+            self, recipient,
+        )
+        .await
+    }
+
+    /// See [`FlashbotsApiClient::set_fee_refund_recipient`]
+    #[instrument(skip(self))]
+    async fn set_fee_refund_recipient(
+        &self,
+        from: Address,
+        to: Address,
+    ) -> Result<FeeRefundRecipient, ClientError> {
+        rpc::FlashbotsApiClient::set_fee_refund_recipient(self, from, to).await
+    }
 }
@@ -1,9 +1,22 @@
 //! MEV-Share RPC interface definitions.
 
+#[cfg(feature = "client")]
+mod builder_client;
+pub mod builders;
+pub mod bundle;
+#[cfg(feature = "client")]
+mod client;
+#[cfg(feature = "client")]
+pub mod error;
 mod eth;
 mod flashbots;
 mod mev;
 pub mod middleware;
+#[cfg(feature = "client")]
+mod multi_relay;
+pub mod protocol;
+#[cfg(feature = "client")]
+pub mod relay_data;
 pub mod types;
 
 #[cfg(feature = "client")]
@@ -21,7 +34,19 @@ pub mod servers {
 #[cfg(feature = "client")]
 pub mod clients {
     pub use crate::{
-        eth::EthBundleApiClient, flashbots::FlashbotsApiClient,
-        mev::MevApiClient,
+        builder_client::{
+            BeaverbuildAdapter, BuilderClient, BundleParams, BundleRefund,
+            RequestAdapter, StandardAdapter, TitanAdapter, adapter_for,
+            broadcast_bundle,
+        },
+        client::{MevShareClient, MevShareClientBuilder},
+        error::{ClientErrorExt, RelayError},
+        eth::{BundleReplacementExt, EthBundleApiClient},
+        flashbots::FlashbotsApiClient,
+        mev::{
+            MevApiClient, SimBundleExt, block_overrides,
+            pending_block_overrides,
+        },
+        multi_relay::{MultiRelayClient, RelayResponse},
     };
 }
@@ -1,7 +1,9 @@
-#[cfg(feature = "client")]
-use alloy::rpc::types::mev::MevSendBundle;
 use alloy::rpc::types::mev::{SimBundleOverrides, SimBundleResponse};
+#[cfg(feature = "client")]
+use alloy::{eips::BlockId, rpc::types::mev::MevSendBundle};
 use async_trait::async_trait;
+#[cfg(feature = "client")]
+use futures_util::{StreamExt, stream};
 use jsonrpsee::{core::ClientError, proc_macros::rpc};
 use tracing::instrument;
 
@@ -97,14 +99,95 @@ where
     }
 }
 
+/// Overrides simulating a bundle on top of the pending block.
+#[cfg(feature = "client")]
+pub fn pending_block_overrides() -> SimBundleOverrides {
+    SimBundleOverrides {
+        parent_block: Some(BlockId::pending()),
+        ..Default::default()
+    }
+}
+
+/// Overrides simulating a bundle in the block following `parent_block`,
+/// mined `timestamp_shift` seconds after its parent (the relay assumes 12
+/// seconds by default).
+#[cfg(feature = "client")]
+pub fn block_overrides(
+    parent_block: u64,
+    parent_timestamp: u64,
+    timestamp_shift: u64,
+) -> SimBundleOverrides {
+    SimBundleOverrides {
+        parent_block: Some(BlockId::number(parent_block)),
+        block_number: Some(parent_block + 1),
+        timestamp: Some(parent_timestamp + timestamp_shift),
+        ..Default::default()
+    }
+}
+
+/// Shortcuts of `mev_simBundle` for the common simulation targets, and
+/// simulation of many candidate bundles at once.
+#[cfg(feature = "client")]
+#[async_trait]
+pub trait SimBundleExt: MevApiClient {
+    /// Simulates the bundle on top of the pending block.
+    async fn sim_bundle_on_pending(
+        &self,
+        bundle: MevSendBundle,
+    ) -> Result<SimBundleResponse, ClientError> {
+        self.sim_bundle(bundle, pending_block_overrides()).await
+    }
+
+    /// Simulates the bundle in the block following `parent_block`, see
+    /// [block_overrides].
+    async fn sim_bundle_at(
+        &self,
+        bundle: MevSendBundle,
+        parent_block: u64,
+        parent_timestamp: u64,
+        timestamp_shift: u64,
+    ) -> Result<SimBundleResponse, ClientError> {
+        let overrides = block_overrides(
+            parent_block,
+            parent_timestamp,
+            timestamp_shift,
+        );
+        self.sim_bundle(bundle, overrides).await
+    }
+
+    /// Simulates the bundles with the same overrides, running at most
+    /// `max_concurrency` simulations at a time. The results are in the
+    /// order of the bundles.
+    async fn sim_bundles(
+        &self,
+        bundles: Vec<MevSendBundle>,
+        sim_overrides: SimBundleOverrides,
+        max_concurrency: usize,
+    ) -> Vec<Result<SimBundleResponse, ClientError>> {
+        stream::iter(bundles)
+            .map(|bundle| self.sim_bundle(bundle, sim_overrides.clone()))
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+#[cfg(feature = "client")]
+impl<T> SimBundleExt for T where T: MevApiClient + Sync + ?Sized {}
+
 #[cfg(all(test, feature = "client"))]
 mod tests {
-    use std::net::SocketAddr;
+    use std::{
+        net::SocketAddr,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
 
     use alloy::{
+        eips::BlockNumberOrTag,
         primitives::{U256, b256},
         rpc::types::mev::{
-            MevSendBundle, SimBundleOverrides, SimBundleResponse,
+            Inclusion, MevSendBundle, SimBundleOverrides, SimBundleResponse,
         },
         signers::local::PrivateKeySigner,
     };
@@ -120,7 +203,7 @@ mod tests {
     };
 
     use super::*;
-    use crate::middleware::AuthLayer;
+    use crate::middleware::FlashbotsAuthLayer;
 
     const DEFAULT_FILTER_LEVEL: &str = "trace";
 
@@ -203,7 +286,7 @@ mod tests {
         let server_addr = start_mock_server().await?;
         let signer = PrivateKeySigner::random();
         let http_middleware =
-            ServiceBuilder::new().layer(AuthLayer::new(signer));
+            ServiceBuilder::new().layer(FlashbotsAuthLayer::new(signer));
 
         let client = HttpClientBuilder::default()
             .set_http_middleware(http_middleware)
@@ -232,4 +315,95 @@ mod tests {
 
         Ok(())
     }
+
+    /// Simulator tracking the number of concurrent simulations, and echoing
+    /// the target block of the bundle as the state block.
+    #[derive(Default)]
+    struct ConcurrencyTracker {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl MevApiClient for ConcurrencyTracker {
+        async fn send_bundle(
+            &self,
+            _request: MevSendBundle,
+        ) -> Result<SendBundleResponse, ClientError> {
+            unimplemented!()
+        }
+
+        async fn sim_bundle(
+            &self,
+            bundle: MevSendBundle,
+            _sim_overrides: SimBundleOverrides,
+        ) -> Result<SimBundleResponse, ClientError> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(SimBundleResponse {
+                success: true,
+                error: None,
+                state_block: bundle.inclusion.block,
+                mev_gas_price: U256::ZERO,
+                profit: U256::ZERO,
+                refundable_value: U256::ZERO,
+                gas_used: 0,
+                logs: None,
+                exec_error: None,
+                revert: None,
+            })
+        }
+    }
+
+    fn bundle(block: u64) -> MevSendBundle {
+        MevSendBundle {
+            protocol_version: Default::default(),
+            inclusion: Inclusion {
+                block,
+                max_block: None,
+            },
+            bundle_body: vec![],
+            validity: None,
+            privacy: None,
+        }
+    }
+
+    #[test]
+    fn test_overrides_presets() {
+        assert_eq!(
+            pending_block_overrides().parent_block,
+            Some(BlockId::Number(
+                BlockNumberOrTag::Pending
+            ))
+        );
+
+        let overrides = block_overrides(100, 1_700_000_000, 24);
+        assert_eq!(
+            overrides.parent_block,
+            Some(BlockId::number(100))
+        );
+        assert_eq!(overrides.block_number, Some(101));
+        assert_eq!(overrides.timestamp, Some(1_700_000_024));
+        assert_eq!(overrides.coinbase, None);
+    }
+
+    #[tokio::test]
+    async fn test_sim_bundles() {
+        let simulator = ConcurrencyTracker::default();
+        let bundles = (0..10).map(bundle).collect();
+        let responses = simulator
+            .sim_bundles(bundles, pending_block_overrides(), 3)
+            .await;
+
+        assert_eq!(responses.len(), 10);
+        for (block, response) in (0..10).zip(responses) {
+            assert_eq!(response.unwrap().state_block, block);
+        }
+        assert_eq!(
+            simulator.max_in_flight.load(Ordering::SeqCst),
+            3
+        );
+    }
 }
@@ -1,40 +1,287 @@
-use std::task::{Context, Poll};
+//! Authentication of the requests to Flashbots-compatible relays and
+//! builders, which sign the body of every request and pass the signature
+//! in a header, e.g. `X-Flashbots-Signature: <address>:<signature>`.
+//!
+//! - [FlashbotsAuthLayer] signs requests with a
+//!   [Signer](alloy::signers::Signer),
+//! - [AuthScheme] configures the header and the signed message,
+//! - [PassThrough] configures the requests forwarded unsigned.
+//!
+//! Requests failing to be signed fail with an [AuthError], wrapped into a
+//! transport error of the client.
+//!
+//! ```ignore
+//! let layer = FlashbotsAuthLayer::new(signer)
+//!     .with_pass_through(PassThrough::default().with_method("eth_chainId"));
+//! let client = HttpClientBuilder::default()
+//!     .set_http_middleware(ServiceBuilder::new().layer(layer))
+//!     .build(url)?;
+//! ```
+//!
+//! See: https://docs.flashbots.net/flashbots-protect/nonce-management#authentication
+
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
 
 use alloy::{
-    primitives::{B256, keccak256},
+    primitives::{Address, B256, Signature, keccak256},
     transports::BoxFuture,
 };
 use futures_util::FutureExt;
-use http::{HeaderName, HeaderValue, Request};
+use http::{
+    HeaderName, HeaderValue, Request, header::InvalidHeaderValue,
+    request::Parts,
+};
 use http_body_util::Full;
-use hyper::body::Bytes;
 use jsonrpsee::http_client::{
     HttpBody, HttpRequest, transport::Error as TransportError,
 };
 use tower::{Layer, Service};
 use tracing::instrument;
 
-// To authenticate your request, Flashbots endpoints require you to
-// sign the payload and include the signed payload in the X-Flashbots-Signature
-// header of your request.
-// See: https://docs.flashbots.net/flashbots-protect/nonce-management#authentication
+use super::{collect_body, rate_limit::methods};
 
 static FLASHBOTS_HEADER: HeaderName =
     HeaderName::from_static("x-flashbots-signature");
 
+/// Message, which is signed (as an EIP-191 personal message) to
+/// authenticate a request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignatureMessage {
+    /// Hex string of the keccak256 hash of the body, e.g. `0x1c9a...`,
+    /// as required by Flashbots.
+    #[default]
+    KeccakHex,
+    /// Raw request body.
+    RawBody,
+}
+
+/// Format of the signer address in the signature header.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AddressFormat {
+    /// Lowercase hex, e.g. `0xab5801a7...`.
+    #[default]
+    Lowercase,
+    /// EIP-55 checksummed hex, e.g. `0xAb5801a7...`.
+    Checksummed,
+}
+
+/// How requests are signed: the header carrying `<address>:<signature>`,
+/// the signed message and the address format.
+///
+/// Defaults to the [Flashbots scheme](AuthScheme::flashbots).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthScheme {
+    header: HeaderName,
+    message: SignatureMessage,
+    address_format: AddressFormat,
+}
+
+impl Default for AuthScheme {
+    fn default() -> Self {
+        Self::flashbots()
+    }
+}
+
+impl AuthScheme {
+    /// `X-Flashbots-Signature` header with the signed keccak hash hex of
+    /// the body, used by Flashbots and most builders accepting bundles.
+    pub fn flashbots() -> Self {
+        Self {
+            header: FLASHBOTS_HEADER.clone(),
+            message: SignatureMessage::KeccakHex,
+            address_format: AddressFormat::Lowercase,
+        }
+    }
+
+    /// `X-Auction-Signature` header with the signed keccak hash hex of the
+    /// body.
+    pub fn auction() -> Self {
+        Self::flashbots().with_header(HeaderName::from_static(
+            "x-auction-signature",
+        ))
+    }
+
+    /// Sets the name of the signature header.
+    pub fn with_header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Sets the message, which is signed.
+    pub fn with_message(mut self, message: SignatureMessage) -> Self {
+        self.message = message;
+        self
+    }
+
+    /// Sets the format of the signer address.
+    pub fn with_address_format(
+        mut self,
+        address_format: AddressFormat,
+    ) -> Self {
+        self.address_format = address_format;
+        self
+    }
+
+    /// Name of the signature header.
+    pub fn header(&self) -> &HeaderName {
+        &self.header
+    }
+
+    /// Message, which is signed for the body.
+    pub(super) fn message(&self, body: &[u8]) -> Vec<u8> {
+        match self.message {
+            SignatureMessage::KeccakHex => {
+                format!("0x{:x}", B256::from(keccak256(body))).into_bytes()
+            }
+            SignatureMessage::RawBody => body.to_vec(),
+        }
+    }
+
+    /// `<address>:<signature>`, where the signature is `0x`-prefixed hex.
+    fn header_value(&self, address: Address, signature: Signature) -> String {
+        match self.address_format {
+            AddressFormat::Lowercase => format!("{address:?}:{signature}"),
+            AddressFormat::Checksummed => format!("{address}:{signature}"),
+        }
+    }
+}
+
+/// Requests which are forwarded without being signed.
+///
+/// By default, requests which aren't JSON `POST` requests, or which are
+/// already signed, are passed through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PassThrough {
+    non_post: bool,
+    non_json: bool,
+    signed: bool,
+    methods: HashSet<String>,
+}
+
+impl Default for PassThrough {
+    fn default() -> Self {
+        Self {
+            non_post: true,
+            non_json: true,
+            signed: true,
+            methods: HashSet::new(),
+        }
+    }
+}
+
+impl PassThrough {
+    /// Signs every request.
+    pub fn none() -> Self {
+        Self {
+            non_post: false,
+            non_json: false,
+            signed: false,
+            methods: HashSet::new(),
+        }
+    }
+
+    /// Sets whether requests which aren't `POST` requests are passed
+    /// through.
+    pub fn with_non_post(mut self, non_post: bool) -> Self {
+        self.non_post = non_post;
+        self
+    }
+
+    /// Sets whether requests whose content type isn't JSON are passed
+    /// through.
+    pub fn with_non_json(mut self, non_json: bool) -> Self {
+        self.non_json = non_json;
+        self
+    }
+
+    /// Sets whether requests already carrying the signature header are
+    /// passed through, otherwise their header is replaced.
+    pub fn with_signed(mut self, signed: bool) -> Self {
+        self.signed = signed;
+        self
+    }
+
+    /// Passes through calls of the JSON-RPC method, which doesn't require
+    /// authentication, e.g. `eth_chainId`. Batches are passed through if
+    /// all their calls are.
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.methods.insert(method.into());
+        self
+    }
+
+    /// Whether the request is passed through because of its head.
+    fn matches_head(&self, parts: &Parts, header: &HeaderName) -> bool {
+        let is_json = parts
+            .headers
+            .get(http::header::CONTENT_TYPE)
+            .is_some_and(|v| v == HeaderValue::from_static("application/json"));
+        let is_signed = parts.headers.contains_key(header);
+        let is_post = parts.method == http::Method::POST;
+
+        tracing::debug!(is_json, is_signed, method = ?parts.method);
+
+        (self.non_json && !is_json)
+            || (self.signed && is_signed)
+            || (self.non_post && !is_post)
+    }
+
+    /// Whether the request is passed through because of the methods it
+    /// calls.
+    fn matches_body(&self, body: &[u8]) -> bool {
+        if self.methods.is_empty() {
+            return false;
+        }
+        let methods = methods(body);
+        !methods.is_empty()
+            && methods.iter().all(|method| self.methods.contains(method))
+    }
+}
+
+/// Default timeout of signing a request, which matters for remote signers.
+const DEFAULT_SIGN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Failed to authenticate a request.
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("Failed to sign request: {0}")]
+    Signing(#[from] alloy::signers::Error),
+    #[error("Signing request timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("Invalid signature header: {0}")]
+    InvalidHeader(#[from] InvalidHeaderValue),
+}
+
+impl From<AuthError> for TransportError {
+    fn from(err: AuthError) -> Self {
+        TransportError::Http(err.into())
+    }
+}
+
+/// Signs requests, see [FlashbotsAuthLayer].
 #[derive(Clone)]
-pub struct AuthService<Service, Signer> {
+pub struct FlashbotsAuthService<Service, Signer> {
     service: Service,
     signer: Signer,
+    scheme: AuthScheme,
+    pass_through: Arc<PassThrough>,
+    sign_timeout: Option<Duration>,
 }
 
-impl<S, Signer> Service<HttpRequest> for AuthService<S, Signer>
+/// Former name of [FlashbotsAuthService].
+#[deprecated(note = "renamed to `FlashbotsAuthService`")]
+pub type AuthService<Service, Signer> = FlashbotsAuthService<Service, Signer>;
+
+impl<S, Signer> Service<HttpRequest> for FlashbotsAuthService<S, Signer>
 where
     Signer: alloy::signers::Signer + Clone + Send + Sync + 'static,
     S: Service<HttpRequest> + Clone + Send + 'static,
     S::Future: Send,
     S::Error: Into<TransportError>,
-    // S::Error: Into<BoxError>,
 {
     type Response = S::Response;
     type Error = TransportError;
@@ -49,38 +296,15 @@ where
 
     #[instrument(skip(self, request))]
     fn call(&mut self, request: HttpRequest) -> Self::Future {
-        use http_body_util::BodyExt;
-
         let service_clone = self.service.clone();
         // Even though the original service is ready, the clone might not be.
         // See: https://docs.rs/tower/latest/tower/trait.Service.html#be-careful-when-cloning-inner-services
         // Here is how we take the service that is ready.
         let mut service = std::mem::replace(&mut self.service, service_clone);
 
-        // If method is not POST, return an error.
         let (mut parts, body) = request.into_parts();
 
-        let is_json = parts
-            .headers
-            .get(http::header::CONTENT_TYPE)
-            .map(|v| v == HeaderValue::from_static("application/json"))
-            .unwrap_or(false);
-
-        let has_flashbots_header =
-            parts.headers.contains_key(FLASHBOTS_HEADER.clone());
-
-        tracing::debug!(
-            ?is_json,
-            ?has_flashbots_header,
-            method = ?parts.method,
-        );
-
-        // If content-type is not JSON,
-        // or flashbots header already exists, just pass through the request.
-        if !is_json
-            || has_flashbots_header
-            || parts.method != http::Method::POST
-        {
+        if self.pass_through.matches_head(&parts, self.scheme.header()) {
             tracing::debug!("pass through");
             return async move {
                 let request = Request::from_parts(parts, body);
@@ -90,26 +314,37 @@ where
         }
 
         let signer = self.signer.clone();
+        let scheme = self.scheme.clone();
+        let pass_through = Arc::clone(&self.pass_through);
+        let sign_timeout = self.sign_timeout;
 
         async move {
-            let body_bytes: Bytes = body
-                .collect()
-                .await
-                .expect("Failed to collect body")
-                .to_bytes();
+            let body_bytes = collect_body(body).await?;
+
+            if pass_through.matches_body(&body_bytes) {
+                tracing::debug!("pass through");
+                let body = HttpBody::new(Full::new(body_bytes));
+                return service
+                    .call(HttpRequest::from_parts(parts, body))
+                    .await
+                    .map_err(Into::into);
+            }
 
-            let message = format!(
-                "0x{:x}",
-                B256::from(keccak256(body_bytes.as_ref()))
-            );
-            let message_bytes = message.clone().into_bytes();
-            let signature = signer
-                .sign_message(&message_bytes)
+            let message_bytes = scheme.message(body_bytes.as_ref());
+            let message = String::from_utf8_lossy(&message_bytes).into_owned();
+            let signature = match sign_timeout {
+                Some(sign_timeout) => tokio::time::timeout(
+                    sign_timeout,
+                    signer.sign_message(&message_bytes),
+                )
                 .await
-                .expect("Failed to sign message");
-            let header_str = format!("{:?}:0x{}", signer.address(), signature);
+                .map_err(|_| AuthError::Timeout(sign_timeout))?,
+                None => signer.sign_message(&message_bytes).await,
+            }
+            .map_err(AuthError::Signing)?;
+            let header_str = scheme.header_value(signer.address(), signature);
             let header_val = HeaderValue::from_str(&header_str)
-                .expect("Flashbots header contains invalid characters");
+                .map_err(AuthError::InvalidHeader)?;
 
             tracing::debug!(
                 message,
@@ -120,7 +355,7 @@ where
                 "inserting flashbots header"
             );
 
-            parts.headers.insert(FLASHBOTS_HEADER.clone(), header_val);
+            parts.headers.insert(scheme.header().clone(), header_val);
 
             let body = HttpBody::new(Full::new(body_bytes));
 
@@ -134,26 +369,71 @@ where
     }
 }
 
-/// Layer that applies [`AuthService`]
+/// Layer that applies [`FlashbotsAuthService`]
 /// which adds a request header with a signed payload.
-#[derive(Clone, Default)]
-pub struct AuthLayer<Signer> {
+#[derive(Clone)]
+pub struct FlashbotsAuthLayer<Signer> {
     signer: Signer,
+    scheme: AuthScheme,
+    pass_through: Arc<PassThrough>,
+    sign_timeout: Option<Duration>,
 }
 
-impl<Signer> AuthLayer<Signer> {
+/// Former name of [FlashbotsAuthLayer].
+#[deprecated(note = "renamed to `FlashbotsAuthLayer`")]
+pub type AuthLayer<Signer> = FlashbotsAuthLayer<Signer>;
+
+impl<Signer: Default> Default for FlashbotsAuthLayer<Signer> {
+    fn default() -> Self {
+        Self::new(Signer::default())
+    }
+}
+
+impl<Signer> FlashbotsAuthLayer<Signer> {
+    /// Creates the layer signing with the given signer, which has to be
+    /// cheap to clone. Wrap remote signers, e.g. AWS KMS or Ledger, into a
+    /// [SharedSigner](super::SharedSigner).
     pub fn new(signer: Signer) -> Self {
-        Self { signer }
+        Self {
+            signer,
+            scheme: AuthScheme::default(),
+            pass_through: Arc::new(PassThrough::default()),
+            sign_timeout: Some(DEFAULT_SIGN_TIMEOUT),
+        }
+    }
+
+    /// Sets the timeout of signing a request, `None` waits indefinitely.
+    /// Requests failing to be signed in time fail with
+    /// [AuthError::Timeout].
+    pub fn with_sign_timeout(mut self, sign_timeout: Option<Duration>) -> Self {
+        self.sign_timeout = sign_timeout;
+        self
+    }
+
+    /// Sets how requests are signed, e.g. [AuthScheme::auction] for relays
+    /// expecting the `X-Auction-Signature` header.
+    pub fn with_scheme(mut self, scheme: AuthScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Sets which requests are forwarded without being signed.
+    pub fn with_pass_through(mut self, pass_through: PassThrough) -> Self {
+        self.pass_through = Arc::new(pass_through);
+        self
     }
 }
 
-impl<Signer: Clone, S> Layer<S> for AuthLayer<Signer> {
-    type Service = AuthService<S, Signer>;
+impl<Signer: Clone, S> Layer<S> for FlashbotsAuthLayer<Signer> {
+    type Service = FlashbotsAuthService<S, Signer>;
 
     fn layer(&self, service: S) -> Self::Service {
-        AuthService {
+        FlashbotsAuthService {
             service,
             signer: self.signer.clone(),
+            scheme: self.scheme.clone(),
+            pass_through: Arc::clone(&self.pass_through),
+            sign_timeout: self.sign_timeout,
         }
     }
 }
@@ -161,6 +441,7 @@ impl<Signer: Clone, S> Layer<S> for AuthLayer<Signer> {
 #[cfg(test)]
 mod tests {
     use alloy::signers::local::PrivateKeySigner;
+    use hyper::body::Bytes;
     #[cfg(test)]
     use pretty_assertions::assert_eq;
     use tower::service_fn;
@@ -169,6 +450,7 @@ mod tests {
     };
 
     use super::*;
+    use crate::middleware::tests::failing_body;
 
     const DEFAULT_FILTER_LEVEL: &str = "trace";
 
@@ -192,7 +474,13 @@ mod tests {
         });
 
         let signer = PrivateKeySigner::random();
-        let mut auth_service = AuthService { service, signer };
+        let mut auth_service = FlashbotsAuthService {
+            service,
+            signer,
+            scheme: AuthScheme::default(),
+            pass_through: Default::default(),
+            sign_timeout: None,
+        };
 
         let request = Request::builder()
             .method(http::Method::POST)
@@ -205,6 +493,51 @@ mod tests {
         auth_service.call(HttpRequest::from(request)).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_auth_service_uses_configured_scheme() {
+        init_tracing();
+
+        let signer = PrivateKeySigner::random();
+        let address = signer.address();
+        let body = b"{\"key\":\"value\"}";
+
+        let service = service_fn(move |request: HttpRequest| async move {
+            let value = request
+                .headers()
+                .get("x-auction-signature")
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string();
+            let (header_address, signature) = value.split_once(':').unwrap();
+            assert_eq!(header_address, address.to_string());
+            // The raw body is signed.
+            let signature: Signature = signature.parse().unwrap();
+            assert_eq!(
+                signature.recover_address_from_msg(body).unwrap(),
+                address
+            );
+            Ok::<_, TransportError>(())
+        });
+
+        let scheme = AuthScheme::auction()
+            .with_message(SignatureMessage::RawBody)
+            .with_address_format(AddressFormat::Checksummed);
+        let mut auth_service = FlashbotsAuthLayer::new(signer)
+            .with_scheme(scheme)
+            .layer(service);
+
+        let request = Request::builder()
+            .method(http::Method::POST)
+            .header("content-type", "application/json")
+            .body(HttpBody::new(Full::new(
+                Bytes::from_static(body),
+            )))
+            .unwrap();
+
+        auth_service.call(HttpRequest::from(request)).await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_auth_service_passes_through_non_post_request() {
         init_tracing();
@@ -216,7 +549,13 @@ mod tests {
         });
 
         let signer = PrivateKeySigner::random();
-        let mut auth_service = AuthService { service, signer };
+        let mut auth_service = FlashbotsAuthService {
+            service,
+            signer,
+            scheme: AuthScheme::default(),
+            pass_through: Default::default(),
+            sign_timeout: None,
+        };
 
         let request = Request::builder()
             .method(http::Method::GET)
@@ -243,7 +582,13 @@ mod tests {
         });
 
         let signer = PrivateKeySigner::random();
-        let mut auth_service = AuthService { service, signer };
+        let mut auth_service = FlashbotsAuthService {
+            service,
+            signer,
+            scheme: AuthScheme::default(),
+            pass_through: Default::default(),
+            sign_timeout: None,
+        };
 
         let request = Request::builder()
             .method(http::Method::POST)
@@ -266,7 +611,13 @@ mod tests {
         });
 
         let signer = PrivateKeySigner::random();
-        let mut auth_service = AuthService { service, signer };
+        let mut auth_service = FlashbotsAuthService {
+            service,
+            signer,
+            scheme: AuthScheme::default(),
+            pass_through: Default::default(),
+            sign_timeout: None,
+        };
 
         let request = Request::builder()
             .method(http::Method::POST)
@@ -279,4 +630,90 @@ mod tests {
 
         auth_service.call(HttpRequest::from(request)).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_auth_service_passes_through_configured_methods() {
+        init_tracing();
+
+        let signed = Arc::new(std::sync::Mutex::new(vec![]));
+        let recorded = Arc::clone(&signed);
+        let service = service_fn(move |request: HttpRequest| {
+            recorded
+                .lock()
+                .unwrap()
+                .push(request.headers().contains_key(FLASHBOTS_HEADER.clone()));
+            async { Ok::<_, TransportError>(()) }
+        });
+
+        let pass_through = PassThrough::default().with_method("eth_chainId");
+        let mut auth_service =
+            FlashbotsAuthLayer::new(PrivateKeySigner::random())
+                .with_pass_through(pass_through)
+                .layer(service);
+
+        for body in [
+            r#"{"method":"eth_chainId"}"#,
+            r#"[{"method":"eth_chainId"},{"method":"mev_sendBundle"}]"#,
+        ] {
+            let request = Request::builder()
+                .method(http::Method::POST)
+                .header("content-type", "application/json")
+                .body(HttpBody::new(Full::new(
+                    Bytes::from_static(body.as_bytes()),
+                )))
+                .unwrap();
+            auth_service.call(request).await.unwrap();
+        }
+
+        // Only the batch calling an authenticated method is signed.
+        assert_eq!(*signed.lock().unwrap(), [false, true]);
+    }
+
+    #[tokio::test]
+    async fn test_auth_service_signs_everything_without_pass_through() {
+        init_tracing();
+
+        let service = service_fn(|request: HttpRequest| async move {
+            assert_eq!(request.method(), http::Method::GET);
+            assert!(request.headers().contains_key(FLASHBOTS_HEADER.clone()));
+            Ok::<_, TransportError>(())
+        });
+
+        let mut auth_service =
+            FlashbotsAuthLayer::new(PrivateKeySigner::random())
+                .with_pass_through(PassThrough::none())
+                .layer(service);
+
+        let request = Request::builder()
+            .method(http::Method::GET)
+            .body(HttpBody::new(Full::new(
+                Bytes::from_static(b"{}"),
+            )))
+            .unwrap();
+
+        auth_service.call(request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_auth_service_returns_body_error() {
+        init_tracing();
+
+        let service =
+            service_fn(|_: HttpRequest| async { Ok::<_, TransportError>(()) });
+
+        let mut auth_service =
+            FlashbotsAuthLayer::new(PrivateKeySigner::random()).layer(service);
+
+        let request = Request::builder()
+            .method(http::Method::POST)
+            .header("content-type", "application/json")
+            .body(failing_body())
+            .unwrap();
+
+        let result = auth_service.call(request).await;
+        assert!(matches!(
+            result,
+            Err(TransportError::Http(_))
+        ));
+    }
 }
@@ -0,0 +1,298 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use alloy::transports::BoxFuture;
+use futures_util::FutureExt;
+use http::{Response, StatusCode};
+use http_body_util::Full;
+use hyper::body::{Body, Bytes, Frame, SizeHint};
+use jsonrpsee::http_client::{
+    HttpBody, HttpRequest, transport::Error as TransportError,
+};
+use tower::{Layer, Service};
+
+use super::{collect_body, rate_limit::methods};
+
+/// Records every JSON-RPC call: method, payload size, latency, HTTP status
+/// and error codes returned by the relay, see [MetricsLayer].
+#[derive(Clone, Debug)]
+pub struct MetricsService<S> {
+    service: S,
+}
+
+/// Label of the called method, `batch` for batches.
+fn method_label(body: &[u8]) -> String {
+    let mut methods = methods(body);
+    match methods.len() {
+        0 => "unknown".to_string(),
+        1 => methods.remove(0),
+        _ => "batch".to_string(),
+    }
+}
+
+/// JSON-RPC error codes in the response or batch response.
+fn error_codes(body: &[u8]) -> Vec<(i64, String)> {
+    let error = |value: &serde_json::Value| {
+        let error = value.get("error")?;
+        let code = error.get("code")?.as_i64()?;
+        let message = error.get("message").and_then(|m| m.as_str());
+        Some((
+            code,
+            message.unwrap_or_default().to_string(),
+        ))
+    };
+    match serde_json::from_slice(body) {
+        Ok(serde_json::Value::Array(responses)) => {
+            responses.iter().filter_map(error).collect()
+        }
+        Ok(response) => error(&response).into_iter().collect(),
+        Err(_) => vec![],
+    }
+}
+
+impl<S, B> Service<HttpRequest> for MetricsService<S>
+where
+    S: Service<HttpRequest, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<TransportError>,
+    B: Send + 'static,
+{
+    type Response = Response<ObservedBody<B>>;
+    type Error = TransportError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: HttpRequest) -> Self::Future {
+        let service_clone = self.service.clone();
+        // Takes the service that is ready, see FlashbotsAuthService::call.
+        let mut service = std::mem::replace(&mut self.service, service_clone);
+
+        async move {
+            let (parts, body) = request.into_parts();
+            let body_bytes = match collect_body(body).await {
+                Ok(body_bytes) => body_bytes,
+                Err(err) => {
+                    tracing::warn!(%err, "Failed to read RPC request");
+                    metrics::counter!("kazuka_rpc_requests_total", "method" => "unknown", "status" => "error")
+                        .increment(1);
+                    return Err(err);
+                }
+            };
+            let method = method_label(&body_bytes);
+            let size = body_bytes.len();
+            metrics::histogram!("kazuka_rpc_request_size_bytes", "method" => method.clone())
+                .record(size as f64);
+
+            let started = Instant::now();
+            let body = HttpBody::new(Full::new(body_bytes));
+            let result = service
+                .call(HttpRequest::from_parts(parts, body))
+                .await
+                .map_err(Into::into);
+            let latency = started.elapsed();
+            metrics::histogram!("kazuka_rpc_request_duration_seconds", "method" => method.clone())
+                .record(latency.as_secs_f64());
+
+            let response = match result {
+                Ok(response) => response,
+                Err(err) => {
+                    tracing::warn!(%method, size, ?latency, %err, "RPC request failed");
+                    metrics::counter!("kazuka_rpc_requests_total", "method" => method, "status" => "error")
+                        .increment(1);
+                    return Err(err);
+                }
+            };
+
+            let status = response.status();
+            tracing::debug!(
+                %method,
+                size,
+                ?latency,
+                status = status.as_u16(),
+                "RPC request"
+            );
+            metrics::counter!("kazuka_rpc_requests_total", "method" => method.clone(), "status" => status.as_str().to_string())
+                .increment(1);
+
+            Ok(response.map(|body| ObservedBody {
+                body,
+                method,
+                status,
+                data: vec![],
+            }))
+        }
+        .boxed()
+    }
+}
+
+/// Response body, which records the JSON-RPC error codes returned by the
+/// relay once it has been read completely.
+#[derive(Debug)]
+pub struct ObservedBody<B> {
+    body: B,
+    method: String,
+    status: StatusCode,
+    data: Vec<u8>,
+}
+
+impl<B> ObservedBody<B> {
+    fn record_errors(&mut self) {
+        for (code, message) in error_codes(&self.data) {
+            tracing::warn!(
+                method = %self.method,
+                code,
+                %message,
+                status = self.status.as_u16(),
+                "relay returned an error"
+            );
+            metrics::counter!("kazuka_rpc_errors_total", "method" => self.method.clone(), "code" => code.to_string())
+                .increment(1);
+        }
+        self.data = vec![];
+    }
+}
+
+impl<B> Body for ObservedBody<B>
+where
+    B: Body<Data = Bytes> + Unpin,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        let frame = Pin::new(&mut this.body).poll_frame(cx);
+        match &frame {
+            Poll::Ready(Some(Ok(frame))) => {
+                if let Some(data) = frame.data_ref() {
+                    this.data.extend_from_slice(data);
+                }
+            }
+            Poll::Ready(None) => this.record_errors(),
+            _ => {}
+        }
+        frame
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+/// Layer that applies [`MetricsService`], which logs every JSON-RPC call
+/// and exports it as metrics, labeled by method:
+///
+/// - `kazuka_rpc_requests_total` by HTTP status, `error` on transport errors
+/// - `kazuka_rpc_request_duration_seconds`
+/// - `kazuka_rpc_request_size_bytes`
+/// - `kazuka_rpc_errors_total` by JSON-RPC error code
+///
+/// Place it outside of the [RetryLayer](super::RetryLayer) to observe
+/// calls, or inside of it to observe every attempt.
+#[derive(Clone, Debug, Default)]
+pub struct MetricsLayer;
+
+impl MetricsLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        MetricsService { service }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Request;
+    use http_body_util::BodyExt;
+    use tower::{ServiceExt, service_fn};
+
+    use super::*;
+    use crate::middleware::tests::failing_body;
+
+    #[test]
+    fn test_error_codes() {
+        let response = br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"bundle rejected"}}"#;
+        assert_eq!(
+            error_codes(response),
+            [(-32000, "bundle rejected".to_string())]
+        );
+        let batch =
+            br#"[{"id":1,"result":"0x1"},{"id":2,"error":{"code":429}}]"#;
+        assert_eq!(
+            error_codes(batch),
+            [(429, String::new())]
+        );
+        assert!(error_codes(br#"{"id":1,"result":null}"#).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_passes_response_through() {
+        let service = service_fn(|_: HttpRequest| async {
+            let body = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601}}"#;
+            Ok::<_, TransportError>(
+                Response::builder()
+                    .status(StatusCode::OK)
+                    .body(Full::new(Bytes::from_static(
+                        body.as_bytes(),
+                    )))
+                    .unwrap(),
+            )
+        });
+        let request = Request::builder()
+            .method(http::Method::POST)
+            .body(HttpBody::new(Full::new(
+                Bytes::from_static(br#"{"method":"mev_sendBundle"}"#),
+            )))
+            .unwrap();
+
+        let response = MetricsLayer::new()
+            .layer(service)
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(
+            body.as_ref(),
+            br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601}}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body_error() {
+        let service = service_fn(|_: HttpRequest| async {
+            Ok::<_, TransportError>(Response::new(Full::new(Bytes::new())))
+        });
+        let request = Request::builder()
+            .method(http::Method::POST)
+            .body(failing_body())
+            .unwrap();
+        let result = MetricsLayer::new().layer(service).oneshot(request).await;
+        assert!(matches!(
+            result,
+            Err(TransportError::Http(_))
+        ));
+    }
+}
@@ -1,2 +1,63 @@
+use http_body_util::BodyExt;
+use hyper::body::Bytes;
+use jsonrpsee::http_client::{HttpBody, transport::Error as TransportError};
+
 pub mod auth;
+pub mod metrics;
+pub mod rate_limit;
+pub mod retry;
+pub mod signer;
+#[cfg(feature = "server")]
+pub mod verify;
+#[allow(deprecated)]
 pub use auth::AuthLayer;
+pub use auth::{
+    AddressFormat, AuthError, AuthScheme, FlashbotsAuthLayer, PassThrough,
+    SignatureMessage,
+};
+pub use metrics::MetricsLayer;
+pub use rate_limit::RateLimitLayer;
+pub use retry::RetryLayer;
+pub use signer::SharedSigner;
+#[cfg(feature = "server")]
+pub use verify::{AuthenticatedSigner, VerifyLayer};
+
+/// Buffers the body of a request, so that it can be inspected or sent
+/// again, failing with a transport error instead of panicking.
+pub(crate) async fn collect_body(
+    body: HttpBody,
+) -> Result<Bytes, TransportError> {
+    let body = body
+        .collect()
+        .await
+        .map_err(|err| TransportError::Http(err.into()))?;
+    Ok(body.to_bytes())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io;
+
+    use futures_util::stream;
+    use http_body_util::StreamBody;
+    use hyper::body::Frame;
+
+    use super::*;
+
+    /// Body failing to be read, e.g. because the connection was reset.
+    pub(crate) fn failing_body() -> HttpBody {
+        HttpBody::new(StreamBody::new(stream::once(async {
+            Err::<Frame<Bytes>, _>(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "connection reset",
+            ))
+        })))
+    }
+
+    #[tokio::test]
+    async fn test_collect_body_error() {
+        let err = collect_body(failing_body()).await.unwrap_err();
+        assert!(matches!(err, TransportError::Http(_)));
+        assert!(err.to_string().contains("connection reset"));
+    }
+}
@@ -0,0 +1,309 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use alloy::transports::BoxFuture;
+use futures_util::FutureExt;
+use http_body_util::Full;
+use jsonrpsee::http_client::{
+    HttpBody, HttpRequest, transport::Error as TransportError,
+};
+use tokio::{sync::Mutex, time::Instant};
+use tower::{Layer, Service};
+
+use super::collect_body;
+
+/// Token bucket, which allows `capacity` requests per `period`.
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    period: Duration,
+    /// Waiters acquire the lock in FIFO order, which queues requests fairly.
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(requests: u32, period: Duration) -> Self {
+        assert!(
+            requests > 0 && !period.is_zero(),
+            "Rate limit must allow at least one request per non-zero period"
+        );
+        Self {
+            capacity: requests as f64,
+            period,
+            state: Mutex::new(BucketState {
+                tokens: requests as f64,
+                updated_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    async fn acquire(&self) {
+        let mut state = self.state.lock().await;
+        let refill_rate = self.capacity / self.period.as_secs_f64();
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.updated_at).as_secs_f64();
+        state.tokens =
+            (state.tokens + elapsed * refill_rate).min(self.capacity);
+        state.updated_at = now;
+
+        if state.tokens < 1.0 {
+            let wait = (1.0 - state.tokens) / refill_rate;
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+            state.tokens = 1.0;
+            state.updated_at = Instant::now();
+        }
+        state.tokens -= 1.0;
+    }
+}
+
+/// Limits the rate of JSON-RPC requests per method, see [RateLimitLayer].
+#[derive(Clone, Debug)]
+pub struct RateLimitService<S> {
+    service: S,
+    limits: Arc<Limits>,
+}
+
+#[derive(Debug, Default)]
+struct Limits {
+    methods: HashMap<String, Bucket>,
+    default: Option<Bucket>,
+}
+
+impl Limits {
+    fn bucket(&self, method: &str) -> Option<&Bucket> {
+        self.methods.get(method).or(self.default.as_ref())
+    }
+}
+
+/// Names of the methods called by the JSON-RPC request or batch.
+pub(super) fn methods(body: &[u8]) -> Vec<String> {
+    let method = |value: &serde_json::Value| {
+        value.get("method")?.as_str().map(str::to_string)
+    };
+    match serde_json::from_slice(body) {
+        Ok(serde_json::Value::Array(calls)) => {
+            calls.iter().filter_map(method).collect()
+        }
+        Ok(call) => method(&call).into_iter().collect(),
+        Err(_) => vec![],
+    }
+}
+
+impl<S> Service<HttpRequest> for RateLimitService<S>
+where
+    S: Service<HttpRequest> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<TransportError>,
+{
+    type Response = S::Response;
+    type Error = TransportError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: HttpRequest) -> Self::Future {
+        let service_clone = self.service.clone();
+        // Takes the service that is ready, see FlashbotsAuthService::call.
+        let mut service = std::mem::replace(&mut self.service, service_clone);
+        let limits = Arc::clone(&self.limits);
+
+        async move {
+            let (parts, body) = request.into_parts();
+            let body_bytes = collect_body(body).await?;
+
+            for method in methods(&body_bytes) {
+                if let Some(bucket) = limits.bucket(&method) {
+                    tracing::trace!(%method, "acquiring rate limit");
+                    bucket.acquire().await;
+                }
+            }
+
+            let body = HttpBody::new(Full::new(body_bytes));
+            service
+                .call(HttpRequest::from_parts(parts, body))
+                .await
+                .map_err(Into::into)
+        }
+        .boxed()
+    }
+}
+
+/// Layer that applies [`RateLimitService`], which limits the rate of
+/// requests per JSON-RPC method, e.g. to avoid getting the signing key
+/// rate-limited by the relay on a burst of opportunities.
+///
+/// Requests over budget wait in FIFO order. Budgets are shared by all
+/// services created by the layer and its clones. Calls of a batch are
+/// counted separately.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use kazuka_mev_share_rpc_api::middleware::RateLimitLayer;
+/// let layer = RateLimitLayer::new()
+///     .with_method_limit("mev_sendBundle", 10, Duration::from_secs(1))
+///     .with_method_limit("mev_simBundle", 50, Duration::from_secs(1));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RateLimitLayer {
+    limits: Arc<Limits>,
+}
+
+impl RateLimitLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows at most `requests` calls of the method per `period`.
+    ///
+    /// # Panics
+    ///
+    /// If called on a clone of a layer, which is already in use, or with a
+    /// zero budget.
+    pub fn with_method_limit(
+        mut self,
+        method: impl Into<String>,
+        requests: u32,
+        period: Duration,
+    ) -> Self {
+        self.limits_mut().methods.insert(
+            method.into(),
+            Bucket::new(requests, period),
+        );
+        self
+    }
+
+    /// Allows at most `requests` calls per `period` of all methods without
+    /// their own limit, which share the budget.
+    pub fn with_default_limit(
+        mut self,
+        requests: u32,
+        period: Duration,
+    ) -> Self {
+        self.limits_mut().default = Some(Bucket::new(requests, period));
+        self
+    }
+
+    fn limits_mut(&mut self) -> &mut Limits {
+        Arc::get_mut(&mut self.limits)
+            .expect("Rate limits can't be changed once the layer is shared")
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RateLimitService {
+            service,
+            limits: Arc::clone(&self.limits),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use futures_util::future::join_all;
+    use http::Request;
+    use hyper::body::Bytes;
+    use tower::{ServiceExt, service_fn};
+
+    use super::*;
+    use crate::middleware::tests::failing_body;
+
+    fn request(body: &'static str) -> HttpRequest {
+        Request::builder()
+            .method(http::Method::POST)
+            .header("content-type", "application/json")
+            .body(HttpBody::new(Full::new(
+                Bytes::from_static(body.as_bytes()),
+            )))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_methods() {
+        assert_eq!(
+            methods(br#"{"jsonrpc":"2.0","id":1,"method":"mev_sendBundle"}"#),
+            ["mev_sendBundle"]
+        );
+        assert_eq!(
+            methods(
+                br#"[{"method":"eth_callBundle"},{"method":"mev_simBundle"}]"#
+            ),
+            ["eth_callBundle", "mev_simBundle"]
+        );
+        assert!(methods(b"not json").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limits_per_method() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let service = service_fn(move |_: HttpRequest| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Ok::<_, TransportError>(()) }
+        });
+        let layer = RateLimitLayer::new().with_method_limit(
+            "mev_sendBundle",
+            2,
+            Duration::from_millis(200),
+        );
+        let service = layer.layer(service);
+
+        let started = Instant::now();
+        let unlimited = (0..5).map(|_| {
+            service.clone().oneshot(request(
+                r#"{"method":"eth_callBundle"}"#,
+            ))
+        });
+        join_all(unlimited).await;
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        let limited = (0..4).map(|_| {
+            service.clone().oneshot(request(
+                r#"{"method":"mev_sendBundle"}"#,
+            ))
+        });
+        join_all(limited).await;
+        // 2 requests are sent at once, the other 2 after refilling.
+        assert!(started.elapsed() >= Duration::from_millis(180));
+        assert_eq!(calls.load(Ordering::SeqCst), 9);
+    }
+
+    #[tokio::test]
+    async fn test_body_error() {
+        let service =
+            service_fn(|_: HttpRequest| async { Ok::<_, TransportError>(()) });
+        let request = Request::builder()
+            .method(http::Method::POST)
+            .body(failing_body())
+            .unwrap();
+        let result = RateLimitLayer::new()
+            .with_default_limit(1, Duration::from_secs(1))
+            .layer(service)
+            .oneshot(request)
+            .await;
+        assert!(matches!(
+            result,
+            Err(TransportError::Http(_))
+        ));
+    }
+}
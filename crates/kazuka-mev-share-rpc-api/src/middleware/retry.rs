@@ -0,0 +1,334 @@
+use std::{
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use alloy::transports::BoxFuture;
+use futures_util::FutureExt;
+use http::{Request, Response, StatusCode, header::RETRY_AFTER};
+use http_body_util::Full;
+use jsonrpsee::http_client::{
+    HttpBody, HttpRequest, transport::Error as TransportError,
+};
+use tower::{Layer, Service};
+
+use super::collect_body;
+
+/// Default number of retries of a failed request.
+const DEFAULT_MAX_RETRIES: u32 = 3;
+/// Default delay before the first retry, doubled on every following one.
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// Upper bound of the delay requested by a `Retry-After` header.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Retries requests failing with transport errors, `429 Too Many Requests`
+/// or `5xx` responses, backing off exponentially or as long as requested by
+/// the `Retry-After` header.
+///
+/// Other responses, e.g. `401`/`403` on invalid signatures, are never
+/// retried. Every attempt is passed to the inner service as a fresh request,
+/// so an inner [FlashbotsAuthService](super::auth::FlashbotsAuthService)
+/// re-signs it:
+///
+/// ```ignore
+/// ServiceBuilder::new()
+///     .layer(RetryLayer::new())
+///     .layer(FlashbotsAuthLayer::new(signer));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryService<S> {
+    service: S,
+    max_retries: u32,
+    initial_backoff: Duration,
+}
+
+/// Whether the response status may change if retried.
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Delay requested by the `Retry-After` header in seconds. HTTP dates are
+/// ignored.
+fn retry_after<B>(response: &Response<B>) -> Option<Duration> {
+    let seconds = response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+impl<S, B> Service<HttpRequest> for RetryService<S>
+where
+    S: Service<HttpRequest, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<TransportError>,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = TransportError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: HttpRequest) -> Self::Future {
+        let service_clone = self.service.clone();
+        // Takes the service that is ready, see FlashbotsAuthService::call.
+        let mut service = std::mem::replace(&mut self.service, service_clone);
+        let max_retries = self.max_retries;
+        let mut backoff = self.initial_backoff;
+
+        async move {
+            // The body is buffered, so that it can be sent again.
+            let (parts, body) = request.into_parts();
+            let body_bytes = collect_body(body).await?;
+
+            let mut attempt = 0;
+            loop {
+                let mut request = Request::builder()
+                    .method(parts.method.clone())
+                    .uri(parts.uri.clone())
+                    .version(parts.version)
+                    .body(HttpBody::new(Full::new(
+                        body_bytes.clone(),
+                    )))
+                    .map_err(|err| TransportError::Http(err.into()))?;
+                *request.headers_mut() = parts.headers.clone();
+
+                let result = std::future::poll_fn(|cx| service.poll_ready(cx))
+                    .await
+                    .map_err(Into::into);
+                let result = match result {
+                    Ok(()) => service.call(request).await.map_err(Into::into),
+                    Err(err) => Err(err),
+                };
+
+                let delay = match &result {
+                    Ok(response) if is_retryable(response.status()) => {
+                        retry_after(response).unwrap_or(backoff)
+                    }
+                    Ok(_) => return result,
+                    Err(_) => backoff,
+                };
+                if attempt >= max_retries {
+                    return result;
+                }
+                attempt += 1;
+                tracing::debug!(
+                    attempt,
+                    ?delay,
+                    status = ?result.as_ref().map(|response| response.status()),
+                    "retrying request"
+                );
+                tokio::time::sleep(delay).await;
+                backoff *= 2;
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Layer that applies [`RetryService`].
+#[derive(Clone, Debug)]
+pub struct RetryLayer {
+    max_retries: u32,
+    initial_backoff: Duration,
+}
+
+impl Default for RetryLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryLayer {
+    pub fn new() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+        }
+    }
+
+    /// Sets the maximum number of retries, `0` disables retries.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry, which doubles with every
+    /// further retry.
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+}
+
+impl<S> Layer<S> for RetryLayer {
+    type Service = RetryService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RetryService {
+            service,
+            max_retries: self.max_retries,
+            initial_backoff: self.initial_backoff,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, Ordering},
+    };
+
+    use alloy::signers::local::PrivateKeySigner;
+    use hyper::body::Bytes;
+    use tower::{ServiceBuilder, ServiceExt, service_fn};
+
+    use super::*;
+    use crate::middleware::{FlashbotsAuthLayer, tests::failing_body};
+
+    fn request() -> HttpRequest {
+        Request::builder()
+            .method(http::Method::POST)
+            .header("content-type", "application/json")
+            .body(HttpBody::new(Full::new(
+                Bytes::from_static(b"{\"key\":\"value\"}"),
+            )))
+            .unwrap()
+    }
+
+    /// Service answering with the given statuses in order, recording the
+    /// signature headers of the requests.
+    fn scripted(
+        statuses: Vec<(StatusCode, Option<&'static str>)>,
+    ) -> (
+        impl Service<
+            HttpRequest,
+            Response = Response<()>,
+            Error = TransportError,
+            Future = impl Send,
+        > + Clone
+        + Send
+        + 'static,
+        Arc<Mutex<Vec<Option<String>>>>,
+    ) {
+        let signatures = Arc::new(Mutex::new(vec![]));
+        let calls = Arc::new(AtomicU32::new(0));
+        let recorded = Arc::clone(&signatures);
+        let service = service_fn(move |request: HttpRequest| {
+            let call = calls.fetch_add(1, Ordering::SeqCst) as usize;
+            let (status, retry_after) = statuses[call.min(statuses.len() - 1)];
+            recorded.lock().unwrap().push(
+                request
+                    .headers()
+                    .get("x-flashbots-signature")
+                    .map(|value| value.to_str().unwrap().to_string()),
+            );
+            async move {
+                let mut response = Response::builder().status(status);
+                if let Some(retry_after) = retry_after {
+                    response = response.header(RETRY_AFTER, retry_after);
+                }
+                Ok::<_, TransportError>(response.body(()).unwrap())
+            }
+        });
+        (service, signatures)
+    }
+
+    #[tokio::test]
+    async fn test_retries_and_re_signs() {
+        let (service, signatures) = scripted(vec![
+            (StatusCode::TOO_MANY_REQUESTS, Some("0")),
+            (StatusCode::BAD_GATEWAY, None),
+            (StatusCode::OK, None),
+        ]);
+        let service = ServiceBuilder::new()
+            .layer(
+                RetryLayer::new()
+                    .with_initial_backoff(Duration::from_millis(1)),
+            )
+            .layer(FlashbotsAuthLayer::new(
+                PrivateKeySigner::random(),
+            ))
+            .service(service);
+
+        let response = service.oneshot(request()).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let signatures = signatures.lock().unwrap();
+        assert_eq!(signatures.len(), 3);
+        assert!(signatures.iter().all(Option::is_some));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_and_skips_client_errors() {
+        let (service, signatures) = scripted(vec![(
+            StatusCode::SERVICE_UNAVAILABLE,
+            None,
+        )]);
+        let service = RetryLayer::new()
+            .with_max_retries(2)
+            .with_initial_backoff(Duration::from_millis(1))
+            .layer(service);
+        let response = service.oneshot(request()).await.unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(signatures.lock().unwrap().len(), 3);
+
+        // Invalid signature.
+        let (service, signatures) =
+            scripted(vec![(StatusCode::FORBIDDEN, None)]);
+        let service = RetryLayer::new().layer(service);
+        let response = service.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(signatures.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_body_error() {
+        let (service, signatures) = scripted(vec![(StatusCode::OK, None)]);
+        let request = Request::builder()
+            .method(http::Method::POST)
+            .body(failing_body())
+            .unwrap();
+        let result = RetryLayer::new().layer(service).oneshot(request).await;
+        assert!(matches!(
+            result,
+            Err(TransportError::Http(_))
+        ));
+        assert!(signatures.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let response = Response::builder()
+            .header(RETRY_AFTER, "120")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            retry_after(&response),
+            Some(MAX_RETRY_AFTER)
+        );
+
+        let response = Response::builder()
+            .header(
+                RETRY_AFTER,
+                "Wed, 21 Oct 2015 07:28:00 GMT",
+            )
+            .body(())
+            .unwrap();
+        assert_eq!(retry_after(&response), None);
+    }
+}
@@ -0,0 +1,167 @@
+use std::{fmt, sync::Arc};
+
+use alloy::{
+    primitives::{Address, B256, ChainId, Signature},
+    signers::{Result, Signer},
+};
+use async_trait::async_trait;
+
+/// Cheaply cloneable handle to a signer, e.g. a remote signer like AWS KMS,
+/// a Ledger or a signing service, which can't or shouldn't be cloned.
+///
+/// The address is fetched once on creation, so that it is never requested
+/// from the remote signer while signing requests.
+///
+/// ```ignore
+/// let signer = AwsSigner::new(client, key_id, Some(1)).await?;
+/// let layer = FlashbotsAuthLayer::new(SharedSigner::new(signer))
+///     .with_sign_timeout(Some(Duration::from_secs(2)));
+/// ```
+#[derive(Clone)]
+pub struct SharedSigner {
+    signer: Arc<dyn Signer + Send + Sync>,
+    address: Address,
+    chain_id: Option<ChainId>,
+}
+
+impl SharedSigner {
+    pub fn new(signer: impl Signer + Send + Sync + 'static) -> Self {
+        Self {
+            address: signer.address(),
+            chain_id: signer.chain_id(),
+            signer: Arc::new(signer),
+        }
+    }
+}
+
+impl fmt::Debug for SharedSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedSigner")
+            .field("address", &self.address)
+            .field("chain_id", &self.chain_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Signer for SharedSigner {
+    async fn sign_hash(&self, hash: &B256) -> Result<Signature> {
+        self.signer.sign_hash(hash).await
+    }
+
+    /// Delegates to the signer, since hardware wallets sign messages
+    /// differently from hashes.
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        self.signer.sign_message(message).await
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id
+    }
+
+    /// Only affects this handle, the chain id isn't used to sign messages.
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        self.chain_id = chain_id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    use alloy::signers::local::PrivateKeySigner;
+    use http::Request;
+    use http_body_util::Full;
+    use hyper::body::Bytes;
+    use jsonrpsee::http_client::{
+        HttpBody, HttpRequest, transport::Error as TransportError,
+    };
+    use tower::{Layer, ServiceExt, service_fn};
+
+    use super::*;
+    use crate::middleware::FlashbotsAuthLayer;
+
+    /// Remote signer taking `delay` to sign, counting address lookups.
+    #[derive(Debug)]
+    struct RemoteSigner {
+        signer: PrivateKeySigner,
+        delay: Duration,
+        address_lookups: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl Signer for RemoteSigner {
+        async fn sign_hash(&self, hash: &B256) -> Result<Signature> {
+            tokio::time::sleep(self.delay).await;
+            self.signer.sign_hash(hash).await
+        }
+
+        fn address(&self) -> Address {
+            self.address_lookups.fetch_add(1, Ordering::SeqCst);
+            self.signer.address()
+        }
+
+        fn chain_id(&self) -> Option<ChainId> {
+            None
+        }
+
+        fn set_chain_id(&mut self, _chain_id: Option<ChainId>) {}
+    }
+
+    fn request() -> HttpRequest {
+        Request::builder()
+            .method(http::Method::POST)
+            .header("content-type", "application/json")
+            .body(HttpBody::new(Full::new(
+                Bytes::from_static(b"{\"key\":\"value\"}"),
+            )))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer() {
+        let address_lookups = Arc::new(AtomicU32::new(0));
+        let signer = SharedSigner::new(RemoteSigner {
+            signer: PrivateKeySigner::random(),
+            delay: Duration::from_millis(10),
+            address_lookups: Arc::clone(&address_lookups),
+        });
+        let service = service_fn(|request: HttpRequest| async move {
+            assert!(request.headers().contains_key("x-flashbots-signature"));
+            Ok::<_, TransportError>(())
+        });
+        let layer = FlashbotsAuthLayer::new(signer);
+
+        for _ in 0..3 {
+            layer.layer(service).oneshot(request()).await.unwrap();
+        }
+        assert_eq!(
+            address_lookups.load(Ordering::SeqCst),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_timeout() {
+        let signer = SharedSigner::new(RemoteSigner {
+            signer: PrivateKeySigner::random(),
+            delay: Duration::from_secs(10),
+            address_lookups: Default::default(),
+        });
+        let service =
+            service_fn(|_: HttpRequest| async { Ok::<_, TransportError>(()) });
+        let layer = FlashbotsAuthLayer::new(signer)
+            .with_sign_timeout(Some(Duration::from_millis(10)));
+
+        // Requests aren't sent unsigned.
+        let result = layer.layer(service).oneshot(request()).await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use alloy::{
+    primitives::{Address, Signature},
+    transports::BoxFuture,
+};
+use futures_util::FutureExt;
+use http::{HeaderValue, StatusCode, header::CONTENT_TYPE};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use jsonrpsee::{
+    core::BoxError,
+    server::{HttpBody, HttpRequest, HttpResponse},
+};
+use tower::{Layer, Service};
+
+use super::AuthScheme;
+
+/// JSON-RPC error code of rejected requests.
+const INVALID_REQUEST_CODE: i32 = -32600;
+
+/// Address of the signer of an authenticated request, which is inserted
+/// into the request extensions by the [VerifyService].
+///
+/// Available in method handlers, e.g. via the `Extensions` argument of
+/// [RpcModule::register_method](jsonrpsee::RpcModule::register_method).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AuthenticatedSigner(pub Address);
+
+/// Why a request has been rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VerifyError {
+    #[error("missing signature header")]
+    MissingHeader,
+    #[error("signature header must be <address>:<signature>")]
+    MalformedHeader,
+    #[error("signature doesn't match the address")]
+    InvalidSignature,
+    #[error("signer {0} is not allowed")]
+    NotAllowed(Address),
+}
+
+#[derive(Clone, Debug, Default)]
+struct Verifier {
+    scheme: AuthScheme,
+    allowlist: Option<HashSet<Address>>,
+}
+
+impl Verifier {
+    /// Recovers the signer of the body, checking it against the allowlist.
+    fn verify(
+        &self,
+        header: Option<&HeaderValue>,
+        body: &[u8],
+    ) -> Result<Address, VerifyError> {
+        let header = header
+            .ok_or(VerifyError::MissingHeader)?
+            .to_str()
+            .map_err(|_| VerifyError::MalformedHeader)?;
+        let (address, signature) =
+            header.split_once(':').ok_or(VerifyError::MalformedHeader)?;
+        let address: Address =
+            address.parse().map_err(|_| VerifyError::MalformedHeader)?;
+        let signature: Signature = signature
+            .parse()
+            .map_err(|_| VerifyError::MalformedHeader)?;
+
+        let recovered = signature
+            .recover_address_from_msg(self.scheme.message(body))
+            .map_err(|_| VerifyError::InvalidSignature)?;
+        if recovered != address {
+            return Err(VerifyError::InvalidSignature);
+        }
+        if let Some(allowlist) = &self.allowlist
+            && !allowlist.contains(&address)
+        {
+            return Err(VerifyError::NotAllowed(address));
+        }
+        Ok(address)
+    }
+}
+
+/// Verifies the signature header of incoming requests, see [VerifyLayer].
+#[derive(Clone, Debug)]
+pub struct VerifyService<S> {
+    service: S,
+    verifier: Arc<Verifier>,
+}
+
+/// JSON-RPC error response to a rejected request.
+fn reject(body: &[u8], err: &VerifyError) -> HttpResponse {
+    let id = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|request| request.get("id").cloned())
+        .unwrap_or_default();
+    let response = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": INVALID_REQUEST_CODE, "message": err.to_string() },
+    });
+    HttpResponse::builder()
+        .status(StatusCode::FORBIDDEN)
+        .header(CONTENT_TYPE, "application/json")
+        .body(HttpBody::new(Full::new(Bytes::from(
+            response.to_string(),
+        ))))
+        .expect("Failed to build response")
+}
+
+impl<S> Service<HttpRequest> for VerifyService<S>
+where
+    S: Service<HttpRequest, Response = HttpResponse> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError>,
+{
+    type Response = HttpResponse;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: HttpRequest) -> Self::Future {
+        let service_clone = self.service.clone();
+        // Takes the service that is ready, see FlashbotsAuthService::call.
+        let mut service = std::mem::replace(&mut self.service, service_clone);
+        let verifier = Arc::clone(&self.verifier);
+
+        async move {
+            let (mut parts, body) = request.into_parts();
+            let body_bytes = body.collect().await?.to_bytes();
+
+            let header = parts.headers.get(verifier.scheme.header());
+            match verifier.verify(header, &body_bytes) {
+                Ok(address) => {
+                    tracing::trace!(%address, "authenticated request");
+                    parts.extensions.insert(AuthenticatedSigner(address));
+                }
+                Err(err) => {
+                    tracing::debug!(%err, "rejected request");
+                    return Ok(reject(&body_bytes, &err));
+                }
+            }
+
+            let body = HttpBody::new(Full::new(body_bytes));
+            service
+                .call(HttpRequest::from_parts(parts, body))
+                .await
+                .map_err(Into::into)
+        }
+        .boxed()
+    }
+}
+
+/// Server layer that applies [`VerifyService`], which rejects requests
+/// without a valid signature header (see
+/// [FlashbotsAuthLayer](super::FlashbotsAuthLayer)) and
+/// makes the [AuthenticatedSigner] available to method handlers.
+///
+/// ```ignore
+/// let server = Server::builder()
+///     .set_http_middleware(
+///         ServiceBuilder::new().layer(VerifyLayer::new().with_allowlist([searcher])),
+///     )
+///     .build(addr)
+///     .await?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct VerifyLayer {
+    verifier: Verifier,
+}
+
+impl VerifyLayer {
+    /// Accepts requests signed by any signer using the Flashbots scheme.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how requests are expected to be signed.
+    pub fn with_scheme(mut self, scheme: AuthScheme) -> Self {
+        self.verifier.scheme = scheme;
+        self
+    }
+
+    /// Only accepts requests signed by the given signers.
+    pub fn with_allowlist(
+        mut self,
+        allowlist: impl IntoIterator<Item = Address>,
+    ) -> Self {
+        self.verifier.allowlist = Some(allowlist.into_iter().collect());
+        self
+    }
+}
+
+impl<S> Layer<S> for VerifyLayer {
+    type Service = VerifyService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        VerifyService {
+            service,
+            verifier: Arc::new(self.verifier.clone()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use alloy::signers::{Signer, local::PrivateKeySigner};
+    use jsonrpsee::{
+        RpcModule, core::client::ClientT, http_client::HttpClientBuilder,
+        rpc_params, server::Server,
+    };
+    use tower::ServiceBuilder;
+
+    use super::*;
+    use crate::middleware::FlashbotsAuthLayer;
+
+    async fn start_server(layer: VerifyLayer) -> anyhow::Result<String> {
+        let mut module = RpcModule::new(());
+        module.register_method("mev_whoami", |_, _, extensions| {
+            extensions
+                .get::<AuthenticatedSigner>()
+                .map(|signer| signer.0)
+        })?;
+        let server = Server::builder()
+            .set_http_middleware(ServiceBuilder::new().layer(layer))
+            .build("127.0.0.1:0")
+            .await?;
+        let addr = server.local_addr()?;
+        tokio::spawn(server.start(module).stopped());
+        Ok(format!("http://{addr}"))
+    }
+
+    #[tokio::test]
+    async fn test_verifies_signatures() -> anyhow::Result<()> {
+        let signer = PrivateKeySigner::random();
+        let stranger = PrivateKeySigner::random();
+        let url =
+            start_server(VerifyLayer::new().with_allowlist([signer.address()]))
+                .await?;
+
+        let client = HttpClientBuilder::default()
+            .set_http_middleware(
+                ServiceBuilder::new()
+                    .layer(FlashbotsAuthLayer::new(signer.clone())),
+            )
+            .build(&url)?;
+        let address: Option<Address> =
+            client.request("mev_whoami", rpc_params![]).await?;
+        assert_eq!(address, Some(signer.address()));
+
+        let client = HttpClientBuilder::default()
+            .set_http_middleware(
+                ServiceBuilder::new().layer(FlashbotsAuthLayer::new(stranger)),
+            )
+            .build(&url)?;
+        let result: Result<Option<Address>, _> =
+            client.request("mev_whoami", rpc_params![]).await;
+        assert!(result.is_err());
+
+        let client = HttpClientBuilder::default().build(&url)?;
+        let result: Result<Option<Address>, _> =
+            client.request("mev_whoami", rpc_params![]).await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reject_keeps_request_id() -> anyhow::Result<()> {
+        let response = reject(
+            br#"{"jsonrpc":"2.0","id":7,"method":"mev_sendBundle"}"#,
+            &VerifyError::MissingHeader,
+        );
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let body = response.into_body().collect().await?.to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(body["id"], 7);
+        assert_eq!(
+            body["error"]["code"],
+            INVALID_REQUEST_CODE
+        );
+
+        Ok(())
+    }
+}
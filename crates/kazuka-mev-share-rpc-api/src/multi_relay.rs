@@ -0,0 +1,357 @@
+//! Client submitting bundles to several relays at once.
+
+use std::{fmt, sync::Arc};
+
+use alloy::{
+    primitives::{B256, Bytes},
+    rpc::types::mev::{
+        EthCallBundle, EthCallBundleResponse, EthCancelBundle,
+        EthCancelPrivateTransaction, EthSendBundle, EthSendPrivateTransaction,
+        MevSendBundle, SimBundleOverrides, SimBundleResponse,
+    },
+    transports::BoxFuture,
+};
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use jsonrpsee::core::ClientError;
+
+use crate::{
+    EthBundleApiClient, MevApiClient,
+    types::{BundleHash, SendBundleResponse},
+};
+
+/// Bundle APIs of a single relay.
+trait RelayClient: EthBundleApiClient + MevApiClient + Send + Sync {}
+
+impl<T> RelayClient for T where
+    T: EthBundleApiClient + MevApiClient + Send + Sync
+{
+}
+
+/// Result of a request broadcast to a single relay.
+#[derive(Debug)]
+pub struct RelayResponse<T> {
+    /// Name of the relay.
+    pub relay: String,
+    pub result: Result<T, ClientError>,
+}
+
+/// Client wrapping the clients of several relays.
+///
+/// Implements [EthBundleApiClient] and [MevApiClient] by broadcasting
+/// submissions and cancellations to all relays, returning the first
+/// successful response, or the first error if all relays failed. Use the
+/// `broadcast_*` methods to get the result of every relay. Simulations are
+/// only sent to the preferred relay.
+///
+/// ```ignore
+/// let client = MultiRelayClient::new()
+///     .with_relay("flashbots", flashbots_client)
+///     .with_relay("titan", titan_client)
+///     .with_preferred("flashbots");
+/// let responses = client.broadcast_eth_bundle(bundle).await;
+/// ```
+#[derive(Clone, Default)]
+pub struct MultiRelayClient {
+    relays: Vec<(String, Arc<dyn RelayClient>)>,
+    preferred: usize,
+}
+
+impl MultiRelayClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a relay. The first one is preferred unless
+    /// [set otherwise](Self::with_preferred).
+    pub fn with_relay(
+        mut self,
+        name: impl Into<String>,
+        client: impl EthBundleApiClient + MevApiClient + Send + Sync + 'static,
+    ) -> Self {
+        self.relays.push((name.into(), Arc::new(client)));
+        self
+    }
+
+    /// Routes simulations to the relay with the given name.
+    ///
+    /// # Panics
+    ///
+    /// If no relay with the name has been added.
+    pub fn with_preferred(mut self, name: &str) -> Self {
+        self.preferred = self
+            .relays
+            .iter()
+            .position(|(relay, _)| relay == name)
+            .unwrap_or_else(|| panic!("Unknown relay {name}"));
+        self
+    }
+
+    /// Names of the relays.
+    pub fn relays(&self) -> impl Iterator<Item = &str> {
+        self.relays.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Sends the `eth_sendBundle` request to all relays.
+    pub async fn broadcast_eth_bundle(
+        &self,
+        request: EthSendBundle,
+    ) -> Vec<RelayResponse<BundleHash>> {
+        self.broadcast(|client| {
+            EthBundleApiClient::send_bundle(client, request.clone())
+        })
+        .await
+    }
+
+    /// Sends the `mev_sendBundle` request to all relays.
+    pub async fn broadcast_mev_bundle(
+        &self,
+        request: MevSendBundle,
+    ) -> Vec<RelayResponse<SendBundleResponse>> {
+        self.broadcast(|client| {
+            MevApiClient::send_bundle(client, request.clone())
+        })
+        .await
+    }
+
+    /// Sends the private transaction to all relays.
+    pub async fn broadcast_private_transaction(
+        &self,
+        request: EthSendPrivateTransaction,
+    ) -> Vec<RelayResponse<B256>> {
+        self.broadcast(|client| {
+            client.send_private_transaction(request.clone())
+        })
+        .await
+    }
+
+    async fn broadcast<'a, T>(
+        &'a self,
+        request: impl Fn(
+            &'a dyn RelayClient,
+        ) -> BoxFuture<'a, Result<T, ClientError>>,
+    ) -> Vec<RelayResponse<T>> {
+        join_relays(
+            self.relays.iter().map(|(relay, client)| {
+                (relay.clone(), request(client.as_ref()))
+            }),
+        )
+        .await
+    }
+
+    /// Broadcasts the request, returning the first successful response.
+    async fn broadcast_any<'a, T>(
+        &'a self,
+        request: impl Fn(
+            &'a dyn RelayClient,
+        ) -> BoxFuture<'a, Result<T, ClientError>>,
+    ) -> Result<T, ClientError> {
+        let mut first_error = None;
+        for response in self.broadcast(request).await {
+            match response.result {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        Err(first_error.unwrap_or_else(no_relays))
+    }
+
+    fn preferred(&self) -> Result<&dyn RelayClient, ClientError> {
+        self.relays
+            .get(self.preferred)
+            .map(|(_, client)| client.as_ref())
+            .ok_or_else(no_relays)
+    }
+}
+
+/// Awaits the requests sent to the named relays concurrently, returning the
+/// result of every one.
+pub(crate) async fn join_relays<T, F>(
+    requests: impl IntoIterator<Item = (String, F)>,
+) -> Vec<RelayResponse<T>>
+where
+    F: Future<Output = Result<T, ClientError>>,
+{
+    let (relays, requests): (Vec<_>, Vec<_>) = requests.into_iter().unzip();
+    let results = join_all(requests).await;
+    relays
+        .into_iter()
+        .zip(results)
+        .map(|(relay, result)| {
+            if let Err(err) = &result {
+                tracing::warn!(%relay, %err, "relay request failed");
+            }
+            RelayResponse { relay, result }
+        })
+        .collect()
+}
+
+fn no_relays() -> ClientError {
+    ClientError::Custom("No relays configured".to_string())
+}
+
+impl fmt::Debug for MultiRelayClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiRelayClient")
+            .field(
+                "relays",
+                &self.relays().collect::<Vec<_>>(),
+            )
+            .field("preferred", &self.preferred)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl EthBundleApiClient for MultiRelayClient {
+    async fn send_bundle(
+        &self,
+        request: EthSendBundle,
+    ) -> Result<BundleHash, ClientError> {
+        self.broadcast_any(|client| {
+            EthBundleApiClient::send_bundle(client, request.clone())
+        })
+        .await
+    }
+
+    async fn call_bundle(
+        &self,
+        request: EthCallBundle,
+    ) -> Result<EthCallBundleResponse, ClientError> {
+        self.preferred()?.call_bundle(request).await
+    }
+
+    async fn cancel_bundle(
+        &self,
+        request: EthCancelBundle,
+    ) -> Result<(), ClientError> {
+        self.broadcast_any(|client| client.cancel_bundle(request.clone()))
+            .await
+    }
+
+    async fn send_private_transaction(
+        &self,
+        request: EthSendPrivateTransaction,
+    ) -> Result<B256, ClientError> {
+        self.broadcast_any(|client| {
+            client.send_private_transaction(request.clone())
+        })
+        .await
+    }
+
+    async fn send_private_raw_transaction(
+        &self,
+        bytes: Bytes,
+    ) -> Result<B256, ClientError> {
+        self.broadcast_any(|client| {
+            client.send_private_raw_transaction(bytes.clone())
+        })
+        .await
+    }
+
+    async fn cancel_private_transaction(
+        &self,
+        request: EthCancelPrivateTransaction,
+    ) -> Result<bool, ClientError> {
+        self.broadcast_any(|client| {
+            client.cancel_private_transaction(request.clone())
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl MevApiClient for MultiRelayClient {
+    async fn send_bundle(
+        &self,
+        request: MevSendBundle,
+    ) -> Result<SendBundleResponse, ClientError> {
+        self.broadcast_any(|client| {
+            MevApiClient::send_bundle(client, request.clone())
+        })
+        .await
+    }
+
+    async fn sim_bundle(
+        &self,
+        bundle: MevSendBundle,
+        sim_overrides: SimBundleOverrides,
+    ) -> Result<SimBundleResponse, ClientError> {
+        self.preferred()?.sim_bundle(bundle, sim_overrides).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use jsonrpsee::{
+        RpcModule, http_client::HttpClientBuilder, server::Server,
+    };
+
+    use super::*;
+
+    async fn start_relay(bundle_hash: B256) -> anyhow::Result<String> {
+        let mut module = RpcModule::new(());
+        module.register_method("eth_sendBundle", move |_, _, _| {
+            BundleHash { bundle_hash }
+        })?;
+        let server = Server::builder().build("127.0.0.1:0").await?;
+        let addr = server.local_addr()?;
+        tokio::spawn(server.start(module).stopped());
+        Ok(format!("http://{addr}"))
+    }
+
+    #[tokio::test]
+    async fn test_broadcasts_to_all_relays() -> anyhow::Result<()> {
+        let bundle_hash = B256::repeat_byte(0x11);
+        let live = start_relay(bundle_hash).await?;
+        // Reserves a port, which nothing listens on.
+        let dead = format!(
+            "http://{}",
+            TcpListener::bind("127.0.0.1:0")?.local_addr()?
+        );
+
+        let client = MultiRelayClient::new()
+            .with_relay(
+                "dead",
+                HttpClientBuilder::default().build(&dead)?,
+            )
+            .with_relay(
+                "live",
+                HttpClientBuilder::default().build(&live)?,
+            );
+
+        let responses =
+            client.broadcast_eth_bundle(EthSendBundle::default()).await;
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].relay, "dead");
+        assert!(responses[0].result.is_err());
+        assert_eq!(
+            responses[1].result.as_ref().unwrap().bundle_hash,
+            bundle_hash
+        );
+
+        let response =
+            EthBundleApiClient::send_bundle(&client, EthSendBundle::default())
+                .await?;
+        assert_eq!(response.bundle_hash, bundle_hash);
+
+        // Simulations go to the preferred relay only.
+        let result = client.call_bundle(EthCallBundle::default()).await;
+        assert!(matches!(
+            result,
+            Err(ClientError::Transport(_))
+        ));
+        let client = client.with_preferred("live");
+        let result = client.call_bundle(EthCallBundle::default()).await;
+        assert!(matches!(
+            result,
+            Err(ClientError::Call(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+//! Versions of the `mev_sendBundle` bundle protocol.
+//!
+//! See: https://docs.flashbots.net/flashbots-mev-share/searchers/understanding-bundles
+
+#[cfg(feature = "client")]
+use alloy::rpc::types::mev::SimBundleOverrides;
+use alloy::rpc::types::mev::{
+    BundleItem, Inclusion, MevSendBundle, ProtocolVersion,
+};
+#[cfg(feature = "client")]
+use jsonrpsee::core::ClientError;
+
+#[cfg(feature = "client")]
+use crate::MevApiClient;
+
+/// Supported protocol versions, newest first.
+pub const PROTOCOL_VERSIONS: [ProtocolVersion; 2] =
+    [ProtocolVersion::V0_1, ProtocolVersion::Beta1];
+
+/// Maximum number of blocks a bundle can be valid for.
+pub const MAX_BLOCK_RANGE: u64 = 30;
+
+/// Maximum nesting depth of bundles.
+pub const MAX_NESTING_DEPTH: usize = 1;
+
+/// Why a bundle is invalid for its protocol version.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BundleValidationError {
+    #[error("bundle body is empty")]
+    EmptyBody,
+    #[error("max block {max_block} is before block {block}")]
+    InvalidInclusion { block: u64, max_block: u64 },
+    #[error(
+        "bundle is valid for {0} blocks, at most {MAX_BLOCK_RANGE} allowed"
+    )]
+    BlockRangeTooLarge(u64),
+    #[error("bundles are nested deeper than {MAX_NESTING_DEPTH} level")]
+    NestingTooDeep,
+    #[error("nested bundle has version {found:?}, expected {expected:?}")]
+    VersionMismatch {
+        expected: ProtocolVersion,
+        found: ProtocolVersion,
+    },
+    #[error("refund of body item {0}, which doesn't exist")]
+    InvalidRefundIndex(u64),
+    #[error("refunds add up to {0}%, at most 100% allowed")]
+    RefundTooHigh(u64),
+    #[error("{field} is not supported by protocol version {version:?}")]
+    UnsupportedField {
+        field: &'static str,
+        version: ProtocolVersion,
+    },
+}
+
+/// Checks the bundle and its nested bundles against the rules of its
+/// protocol version, so that invalid bundles are caught before they are
+/// sent to the relay.
+pub fn validate_bundle(
+    bundle: &MevSendBundle,
+) -> Result<(), BundleValidationError> {
+    validate(bundle, 0)
+}
+
+fn validate(
+    bundle: &MevSendBundle,
+    depth: usize,
+) -> Result<(), BundleValidationError> {
+    if depth > MAX_NESTING_DEPTH {
+        return Err(BundleValidationError::NestingTooDeep);
+    }
+    if bundle.bundle_body.is_empty() {
+        return Err(BundleValidationError::EmptyBody);
+    }
+    validate_inclusion(&bundle.inclusion)?;
+
+    if let Some(validity) = &bundle.validity {
+        // `refundConfig` has been introduced in v0.1.
+        if validity.refund_config.is_some()
+            && bundle.protocol_version == ProtocolVersion::Beta1
+        {
+            return Err(
+                BundleValidationError::UnsupportedField {
+                    field: "validity.refundConfig",
+                    version: bundle.protocol_version.clone(),
+                },
+            );
+        }
+        let refunds = validity.refund.iter().flatten();
+        for refund in refunds.clone() {
+            if refund.body_idx >= bundle.bundle_body.len() as u64 {
+                return Err(
+                    BundleValidationError::InvalidRefundIndex(refund.body_idx),
+                );
+            }
+        }
+        let total: u64 = refunds.map(|refund| refund.percent).sum();
+        if total > 100 {
+            return Err(BundleValidationError::RefundTooHigh(
+                total,
+            ));
+        }
+        let total: u64 = validity
+            .refund_config
+            .iter()
+            .flatten()
+            .map(|config| config.percent)
+            .sum();
+        if total > 100 {
+            return Err(BundleValidationError::RefundTooHigh(
+                total,
+            ));
+        }
+    }
+
+    for item in &bundle.bundle_body {
+        if let BundleItem::Bundle { bundle: nested } = item {
+            if nested.protocol_version != bundle.protocol_version {
+                return Err(BundleValidationError::VersionMismatch {
+                    expected: bundle.protocol_version.clone(),
+                    found: nested.protocol_version.clone(),
+                });
+            }
+            validate(nested, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_inclusion(
+    inclusion: &Inclusion,
+) -> Result<(), BundleValidationError> {
+    let Some(max_block) = inclusion.max_block else {
+        return Ok(());
+    };
+    if max_block < inclusion.block {
+        return Err(
+            BundleValidationError::InvalidInclusion {
+                block: inclusion.block,
+                max_block,
+            },
+        );
+    }
+    let range = max_block - inclusion.block;
+    if range > MAX_BLOCK_RANGE {
+        return Err(BundleValidationError::BlockRangeTooLarge(range));
+    }
+    Ok(())
+}
+
+/// Finds the newest protocol version the relay accepts, by simulating an
+/// empty bundle of every [supported](PROTOCOL_VERSIONS) version.
+///
+/// A version is considered unsupported if the relay rejects it with an
+/// error mentioning the version, any other response (including failed
+/// simulations) means that it has been accepted.
+#[cfg(feature = "client")]
+pub async fn negotiate_protocol_version(
+    client: &(dyn MevApiClient + Send + Sync),
+) -> Result<ProtocolVersion, ClientError> {
+    for version in PROTOCOL_VERSIONS {
+        let bundle = MevSendBundle {
+            protocol_version: version.clone(),
+            inclusion: Inclusion::default(),
+            bundle_body: vec![],
+            validity: None,
+            privacy: None,
+        };
+        match client
+            .sim_bundle(bundle, SimBundleOverrides::default())
+            .await
+        {
+            Err(ClientError::Call(error))
+                if error.message().to_lowercase().contains("version") =>
+            {
+                tracing::debug!(?version, %error, "protocol version rejected");
+            }
+            Err(ClientError::Call(_)) | Ok(_) => return Ok(version),
+            Err(err) => return Err(err),
+        }
+    }
+    Err(ClientError::Custom(
+        "Relay supports none of the protocol versions".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{B256, Bytes},
+        rpc::types::mev::{Refund, RefundConfig, Validity},
+    };
+
+    use super::*;
+
+    fn bundle(version: ProtocolVersion) -> MevSendBundle {
+        MevSendBundle {
+            protocol_version: version,
+            inclusion: Inclusion {
+                block: 100,
+                max_block: Some(110),
+            },
+            bundle_body: vec![
+                BundleItem::Hash { hash: B256::ZERO },
+                BundleItem::Tx {
+                    tx: Bytes::from_static(b"tx"),
+                    can_revert: false,
+                },
+            ],
+            validity: None,
+            privacy: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_bundle() {
+        assert_eq!(
+            validate_bundle(&bundle(ProtocolVersion::V0_1)),
+            Ok(())
+        );
+
+        let mut invalid = bundle(ProtocolVersion::V0_1);
+        invalid.inclusion.max_block = Some(200);
+        assert_eq!(
+            validate_bundle(&invalid),
+            Err(BundleValidationError::BlockRangeTooLarge(100))
+        );
+
+        let mut invalid = bundle(ProtocolVersion::V0_1);
+        invalid.validity = Some(Validity {
+            refund: Some(vec![Refund {
+                body_idx: 2,
+                percent: 50,
+            }]),
+            refund_config: None,
+        });
+        assert_eq!(
+            validate_bundle(&invalid),
+            Err(BundleValidationError::InvalidRefundIndex(2))
+        );
+
+        let mut nested = bundle(ProtocolVersion::V0_1);
+        nested.bundle_body.push(BundleItem::Bundle {
+            bundle: bundle(ProtocolVersion::Beta1),
+        });
+        assert!(matches!(
+            validate_bundle(&nested),
+            Err(BundleValidationError::VersionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_refund_config_requires_v0_1() {
+        let validity = Validity {
+            refund: None,
+            refund_config: Some(vec![RefundConfig {
+                address: Default::default(),
+                percent: 100,
+            }]),
+        };
+        let mut beta = bundle(ProtocolVersion::Beta1);
+        beta.validity = Some(validity.clone());
+        assert!(matches!(
+            validate_bundle(&beta),
+            Err(BundleValidationError::UnsupportedField { .. })
+        ));
+
+        let mut v0_1 = bundle(ProtocolVersion::V0_1);
+        v0_1.validity = Some(validity);
+        assert_eq!(validate_bundle(&v0_1), Ok(()));
+    }
+}
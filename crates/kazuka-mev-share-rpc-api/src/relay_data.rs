@@ -0,0 +1,158 @@
+//! Client of the public data API of MEV-Boost relays, telling which bids
+//! the relay received for a block and which one won it, so that submitted
+//! bundles can be correlated with the blocks that were actually proposed.
+//!
+//! ```ignore
+//! let relay = RelayDataClient::new(FLASHBOTS_RELAY_URL);
+//! if let Some(winner) = relay.delivered_payload(block_number).await? {
+//!     let ours = winner.builder_pubkey == our_builder_pubkey;
+//! }
+//! ```
+
+use crate::types::relay::{
+    BidTrace, BlocksReceivedQuery, PayloadsDeliveredQuery, ReceivedBlock,
+};
+
+/// Relay of Flashbots.
+pub const FLASHBOTS_RELAY_URL: &str = "https://boost-relay.flashbots.net";
+
+const PAYLOADS_DELIVERED_PATH: &str =
+    "/relay/v1/data/bidtraces/proposer_payload_delivered";
+const BLOCKS_RECEIVED_PATH: &str =
+    "/relay/v1/data/bidtraces/builder_blocks_received";
+
+/// Client of the data API of a relay.
+#[derive(Debug, Clone)]
+pub struct RelayDataClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl RelayDataClient {
+    /// Creates a client of the relay at the URL, e.g.
+    /// [FLASHBOTS_RELAY_URL].
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Sets the HTTP client, e.g. with a timeout.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// URL of the relay.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Payloads the relay delivered to proposers, i.e. the winning bids,
+    /// most recent first.
+    pub async fn proposer_payloads_delivered(
+        &self,
+        query: &PayloadsDeliveredQuery,
+    ) -> Result<Vec<BidTrace>, reqwest::Error> {
+        self.get(PAYLOADS_DELIVERED_PATH, query).await
+    }
+
+    /// Blocks the relay received from builders, including the ones that
+    /// lost.
+    pub async fn builder_blocks_received(
+        &self,
+        query: &BlocksReceivedQuery,
+    ) -> Result<Vec<ReceivedBlock>, reqwest::Error> {
+        self.get(BLOCKS_RECEIVED_PATH, query).await
+    }
+
+    /// Bid which won the block, if the relay delivered its payload.
+    pub async fn delivered_payload(
+        &self,
+        block_number: u64,
+    ) -> Result<Option<BidTrace>, reqwest::Error> {
+        let query = PayloadsDeliveredQuery {
+            block_number: Some(block_number),
+            ..Default::default()
+        };
+        Ok(self
+            .proposer_payloads_delivered(&query)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Blocks the relay received for the block number.
+    pub async fn received_blocks(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<ReceivedBlock>, reqwest::Error> {
+        let query = BlocksReceivedQuery {
+            block_number: Some(block_number),
+            ..Default::default()
+        };
+        self.builder_blocks_received(&query).await
+    }
+
+    fn request(
+        &self,
+        path: &str,
+        query: &impl serde::Serialize,
+    ) -> reqwest::RequestBuilder {
+        self.client.get(format!("{}{path}", self.url)).query(query)
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &impl serde::Serialize,
+    ) -> Result<T, reqwest::Error> {
+        self.request(path, query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+
+    use super::*;
+    use crate::types::relay::OrderBy;
+
+    #[test]
+    fn test_query_url() {
+        let client = RelayDataClient::new(format!("{FLASHBOTS_RELAY_URL}/"));
+        let query = PayloadsDeliveredQuery {
+            block_number: Some(18_000_000),
+            limit: Some(10),
+            order_by: Some(OrderBy::ValueDescending),
+            ..Default::default()
+        };
+        let request = client
+            .request(PAYLOADS_DELIVERED_PATH, &query)
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            "https://boost-relay.flashbots.net/relay/v1/data/bidtraces/proposer_payload_delivered?limit=10&block_number=18000000&order_by=-value"
+        );
+
+        let query = BlocksReceivedQuery {
+            block_hash: Some(B256::ZERO),
+            ..Default::default()
+        };
+        let request = client
+            .request(BLOCKS_RECEIVED_PATH, &query)
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().query(),
+            Some(format!("block_hash={}", B256::ZERO).as_str())
+        );
+    }
+}
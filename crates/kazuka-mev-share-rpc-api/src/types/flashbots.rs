@@ -0,0 +1,112 @@
+//! Flashbots RPC type bindings.
+
+use alloy::{
+    primitives::{Address, B256, U64, U256},
+    rpc::types::mev::UserStats,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetUserStatsRequest {
+    pub block_number: U64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBundleStatsRequest {
+    pub bundle_hash: B256,
+    pub block_number: U64,
+}
+
+/// Fee refunds of a recipient, returned by
+/// `flashbots_getFeeRefundTotalsByRecipient`.
+///
+/// See: https://docs.flashbots.net/flashbots-auction/advanced/rpc-endpoint#flashbots_getfeerefundtotalsbyrecipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeRefundTotals {
+    /// Refunds (in wei) which haven't been paid out yet.
+    pub pending: U256,
+    /// Refunds (in wei) which have been paid out.
+    pub received: U256,
+    /// Last block the totals account for.
+    pub max_block_number: U64,
+}
+
+/// Delegation of fee refunds, returned by `flashbots_setFeeRefundRecipient`.
+///
+/// See: https://docs.flashbots.net/flashbots-auction/advanced/rpc-endpoint#flashbots_setfeerefundrecipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeRefundRecipient {
+    /// Address whose refunds are delegated.
+    pub from: Address,
+    /// Address receiving the refunds.
+    pub to: Address,
+}
+
+/// Reputation of a searcher, derived from its [UserStats].
+///
+/// Bundles of high priority searchers are simulated before others, the
+/// priority is based on the miner payments per unit of simulated gas.
+///
+/// See: https://docs.flashbots.net/flashbots-auction/advanced/reputation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearcherReputation {
+    pub is_high_priority: bool,
+    /// Miner payments per unit of gas simulated over all time (in wei).
+    pub all_time_gas_price: U256,
+    /// Miner payments per unit of gas simulated over the last 7 days
+    /// (in wei).
+    pub last_7d_gas_price: U256,
+}
+
+fn gas_price(payments: U256, gas: U256) -> U256 {
+    payments.checked_div(gas).unwrap_or_default()
+}
+
+impl From<UserStats> for SearcherReputation {
+    fn from(stats: UserStats) -> Self {
+        Self {
+            is_high_priority: stats.is_high_priority,
+            all_time_gas_price: gas_price(
+                stats.all_time_miner_payments,
+                stats.all_time_gas_simulated,
+            ),
+            last_7d_gas_price: gas_price(
+                stats.last_7d_miner_payments,
+                stats.last_7d_gas_simulated,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_refund_totals_serde() {
+        let json = r#"{"pending":"0x17812ea4fbbe314","received":"0x0","maxBlockNumber":"0x10f2a45"}"#;
+        let totals: FeeRefundTotals = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            totals.pending,
+            U256::from(0x17812ea4fbbe314_u64)
+        );
+        assert_eq!(totals.received, U256::ZERO);
+        assert_eq!(
+            totals.max_block_number,
+            U64::from(0x10f2a45)
+        );
+    }
+
+    #[test]
+    fn test_reputation_without_simulated_gas() {
+        let reputation = SearcherReputation::from(UserStats::default());
+        assert!(!reputation.is_high_priority);
+        assert_eq!(
+            reputation.all_time_gas_price,
+            U256::ZERO
+        );
+    }
+}
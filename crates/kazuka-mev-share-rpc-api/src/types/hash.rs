@@ -0,0 +1,92 @@
+//! Local computation of the bundle hashes returned by relays.
+
+use alloy::{
+    primitives::{B256, keccak256},
+    rpc::types::mev::{BundleItem, EthSendBundle, MevSendBundle},
+};
+
+/// Hash of an `eth_sendBundle` bundle, as returned by the relay: keccak256
+/// of the concatenated hashes of its transactions.
+pub fn eth_bundle_hash(bundle: &EthSendBundle) -> B256 {
+    hash_of_hashes(bundle.txs.iter().map(keccak256))
+}
+
+/// Hash of a `mev_sendBundle` bundle, as returned by the matchmaker:
+/// keccak256 of the concatenated hashes of its body items, which are the
+/// transaction hashes, the hashes of matched transactions and the hashes of
+/// nested bundles.
+pub fn mev_bundle_hash(bundle: &MevSendBundle) -> B256 {
+    hash_of_hashes(
+        bundle.bundle_body.iter().map(|item| match item {
+            BundleItem::Hash { hash } => *hash,
+            BundleItem::Tx { tx, .. } => keccak256(tx),
+            BundleItem::Bundle { bundle } => mev_bundle_hash(bundle),
+        }),
+    )
+}
+
+fn hash_of_hashes(hashes: impl Iterator<Item = B256>) -> B256 {
+    let bytes: Vec<u8> = hashes.flat_map(|hash| hash.0).collect();
+    keccak256(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{Bytes, b256},
+        rpc::types::mev::Inclusion,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_eth_bundle_hash() {
+        let tx = Bytes::from_static(b"tx");
+        let bundle = EthSendBundle {
+            txs: vec![tx.clone()],
+            ..Default::default()
+        };
+        assert_eq!(
+            eth_bundle_hash(&bundle),
+            keccak256(keccak256(&tx))
+        );
+    }
+
+    #[test]
+    fn test_mev_bundle_hash() {
+        let backrun = Bytes::from_static(b"backrun");
+        let tx_hash = b256!(
+            "0x669b4704a7d993a946cdd6e2f95233f308ce0c4649d2e04944e8299efcaa098a"
+        );
+        let bundle = |bundle_body| MevSendBundle {
+            protocol_version: Default::default(),
+            inclusion: Inclusion::default(),
+            bundle_body,
+            validity: None,
+            privacy: None,
+        };
+        let inner = bundle(vec![
+            BundleItem::Hash { hash: tx_hash },
+            BundleItem::Tx {
+                tx: backrun.clone(),
+                can_revert: false,
+            },
+        ]);
+
+        let mut expected = tx_hash.to_vec();
+        expected.extend_from_slice(keccak256(&backrun).as_slice());
+        assert_eq!(
+            mev_bundle_hash(&inner),
+            keccak256(&expected)
+        );
+
+        // Nested bundles contribute their own hash.
+        let outer = bundle(vec![BundleItem::Bundle {
+            bundle: inner.clone(),
+        }]);
+        assert_eq!(
+            mev_bundle_hash(&outer),
+            keccak256(mev_bundle_hash(&inner))
+        );
+    }
+}
@@ -1,8 +1,15 @@
 //! MEV-share bundle type bindings.
 
-use alloy::primitives::{B256, U64};
+use alloy::primitives::B256;
 use serde::{Deserialize, Serialize};
 
+pub mod flashbots;
+mod hash;
+pub mod relay;
+
+pub use flashbots::{GetBundleStatsRequest, GetUserStatsRequest};
+pub use hash::{eth_bundle_hash, mev_bundle_hash};
+
 /// Response from the matchmaker after sending a bundle.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -19,15 +26,14 @@ pub struct BundleHash {
     pub bundle_hash: B256,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GetUserStatsRequest {
-    pub block_number: U64,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GetBundleStatsRequest {
+/// Bundle sent via `eth_sendBundle` with a replacement UUID, which can be
+/// used to replace or cancel it later.
+///
+/// See: https://docs.flashbots.net/flashbots-auction/advanced/bundle-cancellations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplaceableBundle {
+    /// Hash of the bundle bodies.
     pub bundle_hash: B256,
-    pub block_number: U64,
+    /// UUID the bundle has been sent with.
+    pub replacement_uuid: String,
 }
@@ -0,0 +1,132 @@
+//! MEV-Boost relay data API type bindings.
+//!
+//! See: https://flashbots.github.io/relay-specs/
+
+use alloy::primitives::{Address, B256, FixedBytes, U256};
+use serde::{Deserialize, Serialize};
+
+/// BLS public key of a validator or a builder.
+pub type BlsPublicKey = FixedBytes<48>;
+
+/// Bid of a builder for a slot, with its numbers encoded as decimal strings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BidTrace {
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub slot: u64,
+    pub parent_hash: B256,
+    pub block_hash: B256,
+    pub builder_pubkey: BlsPublicKey,
+    pub proposer_pubkey: BlsPublicKey,
+    pub proposer_fee_recipient: Address,
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub gas_limit: u64,
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub gas_used: u64,
+    /// Value paid to the proposer (in wei).
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub value: U256,
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub num_tx: u64,
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub block_number: u64,
+}
+
+/// Block submitted by a builder to the relay.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceivedBlock {
+    #[serde(flatten)]
+    pub trace: BidTrace,
+    /// When the relay received the block (in seconds).
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub timestamp: u64,
+    /// When the relay received the block (in milliseconds).
+    #[serde(with = "alloy::serde::displayfromstr")]
+    pub timestamp_ms: u64,
+    /// Whether the block was accepted before being simulated.
+    #[serde(default)]
+    pub optimistic_submission: bool,
+}
+
+/// Order of the delivered payloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderBy {
+    #[serde(rename = "value")]
+    ValueAscending,
+    #[serde(rename = "-value")]
+    ValueDescending,
+}
+
+/// Filters of `/relay/v1/data/bidtraces/proposer_payload_delivered`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PayloadsDeliveredQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    /// Latest slot to return, for pagination.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proposer_pubkey: Option<BlsPublicKey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builder_pubkey: Option<BlsPublicKey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_by: Option<OrderBy>,
+}
+
+/// Filters of `/relay/v1/data/bidtraces/builder_blocks_received`, at least
+/// one of the slot, the block hash or number, or the builder must be set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BlocksReceivedQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builder_pubkey: Option<BlsPublicKey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_received_block_serde() {
+        let json = r#"{
+            "slot": "7000000",
+            "parent_hash": "0x1111111111111111111111111111111111111111111111111111111111111111",
+            "block_hash": "0x2222222222222222222222222222222222222222222222222222222222222222",
+            "builder_pubkey": "0xa1dead01e65f0a0eee7b5170223f20c8f0cbf122eac3324d61afbdb33a8885ff8cab2ef514ac2c7698ae0d6289ef27fc",
+            "proposer_pubkey": "0x8aa2de6b3b4f9e6e4b6a5a4f1a3e9cdd1c2b8d6a8f1e2d3c4b5a69788796a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8",
+            "proposer_fee_recipient": "0x388c818ca8b9251b393131c08a736a67ccb19297",
+            "gas_limit": "30000000",
+            "gas_used": "12345678",
+            "value": "52612338932112873",
+            "num_tx": "130",
+            "block_number": "18000000",
+            "timestamp": "1695000000",
+            "timestamp_ms": "1695000000123",
+            "optimistic_submission": true
+        }"#;
+        let block: ReceivedBlock = serde_json::from_str(json).unwrap();
+        assert_eq!(block.trace.slot, 7_000_000);
+        assert_eq!(
+            block.trace.value,
+            U256::from(52_612_338_932_112_873_u64)
+        );
+        assert_eq!(block.trace.block_number, 18_000_000);
+        assert_eq!(block.timestamp_ms, 1_695_000_000_123);
+        assert!(block.optimistic_submission);
+
+        let trace: BidTrace = serde_json::from_str(json).unwrap();
+        assert_eq!(trace, block.trace);
+    }
+}
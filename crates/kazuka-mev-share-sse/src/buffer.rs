@@ -0,0 +1,185 @@
+//! Bounded buffering of SSE events for slow consumers.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll},
+};
+
+use futures_util::{Stream, StreamExt, task::AtomicWaker};
+use serde::de::DeserializeOwned;
+use tokio::{sync::Notify, task::JoinHandle};
+
+use crate::{
+    client::{EventStream, SseError},
+    observer::{Observers, SseStats},
+};
+
+/// What to do when the consumer of a [BufferedEventStream] falls behind and
+/// the buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LagPolicy {
+    /// Keeps reading from the connection, dropping the oldest buffered
+    /// events. Dropped events are counted in [SseStats::dropped].
+    #[default]
+    DropOldest,
+    /// Stops reading from the connection until the consumer catches up,
+    /// which applies backpressure to the TCP stream.
+    Backpressure,
+}
+
+struct Buffer<T> {
+    items: Mutex<VecDeque<Result<T, SseError>>>,
+    capacity: usize,
+    policy: LagPolicy,
+    /// Set once the underlying stream has finished.
+    closed: AtomicBool,
+    /// Wakes the consumer when items are available.
+    consumer: AtomicWaker,
+    /// Wakes the reader when space is available.
+    space: Notify,
+}
+
+impl<T> Buffer<T> {
+    /// Buffers the item, returning it back if the buffer is full and the
+    /// reader has to wait.
+    fn push(
+        &self,
+        item: Result<T, SseError>,
+        observers: &Observers,
+        endpoint: &str,
+    ) -> Option<Result<T, SseError>> {
+        let mut items = self.items.lock().unwrap();
+        if items.len() >= self.capacity {
+            match self.policy {
+                LagPolicy::Backpressure => return Some(item),
+                LagPolicy::DropOldest => {
+                    items.pop_front();
+                    observers.notify(|observer| observer.on_dropped(endpoint));
+                }
+            }
+        }
+        items.push_back(item);
+        drop(items);
+        self.consumer.wake();
+        None
+    }
+}
+
+/// [EventStream], which is read by a background task into a bounded
+/// buffer, so that slow consumers don't stall the connection or grow memory
+/// unboundedly. See [EventStream::buffered].
+#[must_use = "streams do nothing unless polled"]
+pub struct BufferedEventStream<T> {
+    buffer: Arc<Buffer<T>>,
+    observers: Observers,
+    task: JoinHandle<()>,
+}
+
+impl<T> BufferedEventStream<T> {
+    /// Counters and the connection state of the underlying stream.
+    pub fn stats(&self) -> SseStats {
+        self.observers.stats.stats()
+    }
+
+    /// Number of currently buffered events.
+    pub fn len(&self) -> usize {
+        self.buffer.items.lock().unwrap().len()
+    }
+
+    /// Whether no events are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> fmt::Debug for BufferedEventStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferedEventStream")
+            .field("capacity", &self.buffer.capacity)
+            .field("policy", &self.buffer.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Stream for BufferedEventStream<T> {
+    type Item = Result<T, SseError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let buffer = &self.buffer;
+        // Registers before checking, so that no wake-up is missed.
+        buffer.consumer.register(cx.waker());
+        if let Some(item) = buffer.items.lock().unwrap().pop_front() {
+            buffer.space.notify_one();
+            return Poll::Ready(Some(item));
+        }
+        if buffer.closed.load(Ordering::Acquire) {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for BufferedEventStream<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl<T> EventStream<T>
+where
+    T: DeserializeOwned + fmt::Debug + Send + 'static,
+{
+    /// Reads the stream in a background task into a buffer of the given
+    /// capacity, handling a lagging consumer according to the policy.
+    ///
+    /// Must be called within a tokio runtime.
+    pub fn buffered(
+        mut self,
+        capacity: usize,
+        policy: LagPolicy,
+    ) -> BufferedEventStream<T> {
+        assert!(
+            capacity > 0,
+            "Buffer capacity must be > 0"
+        );
+        let buffer = Arc::new(Buffer {
+            items: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            policy,
+            closed: AtomicBool::new(false),
+            consumer: AtomicWaker::new(),
+            space: Notify::new(),
+        });
+        let observers = self.observers().clone();
+
+        let reader = Arc::clone(&buffer);
+        let reader_observers = observers.clone();
+        let task = tokio::spawn(async move {
+            while let Some(mut item) = self.next().await {
+                while let Some(rejected) =
+                    reader.push(item, &reader_observers, self.endpoint())
+                {
+                    item = rejected;
+                    reader.space.notified().await;
+                }
+            }
+            reader.closed.store(true, Ordering::Release);
+            reader.consumer.wake();
+        });
+
+        BufferedEventStream {
+            buffer,
+            observers,
+            task,
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use core::fmt;
 use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use alloy::rpc::types::mev::mevshare::{
@@ -16,12 +19,16 @@ use futures_util::{
     ready,
     stream::{IntoAsyncRead, MapErr, MapOk},
 };
-use http::{HeaderValue, header};
+use http::{HeaderMap, HeaderName, HeaderValue, header};
 use pin_project_lite::pin_project;
 use serde::{Serialize, de::DeserializeOwned};
 use tracing::{instrument, trace};
 
-use crate::Event;
+use crate::{
+    Event, HistoricalEvent, RawEvent,
+    filter::EventFilter,
+    observer::{ConnectionState, Observers, SseObserver, SseStats},
+};
 
 /// The client for SSE.
 ///
@@ -31,6 +38,36 @@ use crate::Event;
 pub struct EventClient {
     reqwest_client: reqwest::Client,
     max_retries: Option<u64>,
+    backoff: Option<Backoff>,
+    idle_timeout: Option<Duration>,
+    pub(crate) fail_back_interval: Option<Duration>,
+    pub(crate) failover_errors: Option<u32>,
+    pub(crate) history_rate_limit: Option<Duration>,
+    filter: Option<EventFilter>,
+    observer: Option<Arc<dyn SseObserver>>,
+    headers: HeaderMap,
+    auth: Option<Auth>,
+}
+
+/// Credentials sent with every request.
+#[derive(Clone)]
+enum Auth {
+    Bearer(String),
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bearer(_) => f.write_str("Bearer(..)"),
+            Self::Basic { username, .. } => {
+                f.debug_struct("Basic").field("username", username).finish()
+            }
+        }
+    }
 }
 
 impl Default for EventClient {
@@ -50,6 +87,15 @@ impl EventClient {
         Self {
             reqwest_client: client,
             max_retries: None,
+            backoff: None,
+            idle_timeout: None,
+            fail_back_interval: None,
+            failover_errors: None,
+            history_rate_limit: None,
+            filter: None,
+            observer: None,
+            headers: HeaderMap::new(),
+            auth: None,
         }
     }
 
@@ -69,6 +115,121 @@ impl EventClient {
         self.max_retries
     }
 
+    /// Returns the underlying HTTP client.
+    pub fn reqwest_client(&self) -> &reqwest::Client {
+        &self.reqwest_client
+    }
+
+    /// Sends the header with every request, including reconnections and
+    /// history requests. The value is marked as sensitive, so that it is
+    /// not logged.
+    ///
+    /// Clone the client to use different headers for different
+    /// subscriptions:
+    ///
+    /// ```
+    /// use http::{HeaderName, HeaderValue};
+    /// use kazuka_mev_share_sse::EventClient;
+    /// let client = EventClient::default();
+    /// let private = client.clone().with_header(
+    ///     HeaderName::from_static("x-api-key"),
+    ///     HeaderValue::from_static("secret"),
+    /// );
+    /// ```
+    pub fn with_header(
+        mut self,
+        name: HeaderName,
+        mut value: HeaderValue,
+    ) -> Self {
+        value.set_sensitive(true);
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sends the headers with every request, see [Self::with_header].
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Authenticates every request with the bearer token, e.g. an API key.
+    pub fn with_bearer_auth(mut self, token: impl Into<String>) -> Self {
+        self.auth = Some(Auth::Bearer(token.into()));
+        self
+    }
+
+    /// Authenticates every request with HTTP basic auth.
+    pub fn with_basic_auth(
+        mut self,
+        username: impl Into<String>,
+        password: Option<impl Into<String>>,
+    ) -> Self {
+        self.auth = Some(Auth::Basic {
+            username: username.into(),
+            password: password.map(Into::into),
+        });
+        self
+    }
+
+    /// Starts a GET request with the configured headers and credentials.
+    pub(crate) fn get(
+        &self,
+        url: impl reqwest::IntoUrl,
+    ) -> reqwest::RequestBuilder {
+        let builder =
+            self.reqwest_client.get(url).headers(self.headers.clone());
+        match &self.auth {
+            Some(Auth::Bearer(token)) => builder.bearer_auth(token),
+            Some(Auth::Basic { username, password }) => {
+                builder.basic_auth(username, password.as_ref())
+            }
+            None => builder,
+        }
+    }
+
+    /// Enables automatic reconnection of streams, which have terminated or
+    /// failed with a transport error, waiting according to the given
+    /// [Backoff] between attempts.
+    ///
+    /// Without it streams only reconnect when the server sends an explicit
+    /// SSE `retry` directive.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = Some(backoff);
+        self
+    }
+
+    /// Returns the reconnection backoff, if automatic reconnection is
+    /// enabled.
+    pub fn backoff(&self) -> Option<&Backoff> {
+        self.backoff.as_ref()
+    }
+
+    /// Drops MEV-share events, which don't match the filter, before
+    /// deserializing them.
+    pub fn with_filter(mut self, filter: EventFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Notifies the observer about the activity of all streams of this
+    /// client, e.g. to export metrics.
+    ///
+    /// Every stream also keeps its own [stats](EventStream::stats).
+    pub fn with_observer(mut self, observer: Arc<dyn SseObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Considers connections stale and reconnects them, if neither events
+    /// nor keep-alive comments have been received for the given time.
+    ///
+    /// Reconnects right away, unless [backoff](Self::with_backoff) is
+    /// configured.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
     /// Subscribe to the MEV-share SSE endpoint.
     ///
     /// This connects to the endpoint and returns a stream of `T` items.
@@ -80,22 +241,7 @@ impl EventClient {
         &self,
         endpoint: &str,
     ) -> reqwest::Result<EventStream<T>> {
-        let stream = ActiveEventStream::<T>::connect(
-            &self.reqwest_client,
-            endpoint,
-            None::<()>,
-        )
-        .await?;
-
-        let endpoint = endpoint.to_string();
-        let inner = EventStreamInner {
-            num_retries: 0,
-            endpoint,
-            event_client: self.clone(),
-            query: None,
-        };
-        let state = Some(State::Active(Box::pin(stream)));
-        Ok(EventStream { inner, state })
+        self.connect_stream(endpoint, None).await
     }
 
     /// Subscribe to the MEV-share SSE endpoint with additional query params.
@@ -117,21 +263,43 @@ impl EventClient {
     ) -> reqwest::Result<EventStream<T>> {
         let query =
             Some(serde_json::to_value(query).expect("Serialization failed"));
-        let stream = ActiveEventStream::<T>::connect(
-            &self.reqwest_client,
-            endpoint,
-            query.as_ref(),
-        )
-        .await?;
-        let endpoint = endpoint.to_string();
+        self.connect_stream(endpoint, query).await
+    }
+
+    async fn connect_stream<T: DeserializeOwned + fmt::Debug>(
+        &self,
+        endpoint: &str,
+        query: Option<serde_json::Value>,
+    ) -> reqwest::Result<EventStream<T>> {
         let inner = EventStreamInner {
             num_retries: 0,
-            endpoint,
+            reconnect_attempt: 0,
+            reconnecting_since: None,
+            last_event_id: None,
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+            endpoint: endpoint.to_string(),
             event_client: self.clone(),
-            query: None,
+            query,
+            observers: Observers::new(self.observer.clone()),
         };
+        let stream = inner.open().await?;
         let state = Some(State::Active(Box::pin(stream)));
-        Ok(EventStream { inner, state })
+        Ok(EventStream {
+            inner,
+            state,
+            idle_timer: None,
+        })
+    }
+
+    /// Subscribe to a stream of [RawEvent]s, which keep the undecoded event
+    /// data along with its best-effort decoding as `T`, so that events with
+    /// unexpected payloads are still delivered. Only data, which is not
+    /// valid JSON, results in errors.
+    pub async fn subscribe_raw<T: DeserializeOwned + fmt::Debug>(
+        &self,
+        endpoint: &str,
+    ) -> reqwest::Result<EventStream<RawEvent<T>>> {
+        self.subscribe(endpoint).await
     }
 
     /// Subscribe to a stream of [Event]s.
@@ -151,13 +319,19 @@ impl EventClient {
         endpoint: &str,
         params: EventHistoryParams,
     ) -> reqwest::Result<Vec<EventHistory>> {
-        self.reqwest_client
-            .get(endpoint)
-            .query(&params)
-            .send()
-            .await?
-            .json()
-            .await
+        self.get(endpoint).query(&params).send().await?.json().await
+    }
+
+    /// Gets past events, decoding their hints as [Event]s, which allows
+    /// replaying them through the same code path as the live stream.
+    ///
+    /// Such as `https://mev-share.flashbots.net/api/v1/history`.
+    pub async fn historical_events(
+        &self,
+        endpoint: &str,
+        params: EventHistoryParams,
+    ) -> reqwest::Result<Vec<HistoricalEvent>> {
+        self.get(endpoint).query(&params).send().await?.json().await
     }
 
     /// Gets information about the event history endpoint
@@ -167,15 +341,76 @@ impl EventClient {
         &self,
         endpoint: &str,
     ) -> reqwest::Result<Vec<EventHistoryInfo>> {
-        self.reqwest_client.get(endpoint).send().await?.json().await
+        self.get(endpoint).send().await?.json().await
+    }
+}
+
+/// Exponential backoff between automatic reconnection attempts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backoff {
+    /// Delay before the first reconnection attempt.
+    pub initial_delay: Duration,
+    /// Upper bound of the delay between attempts.
+    pub max_delay: Duration,
+    /// Factor the delay is multiplied by after every attempt.
+    pub multiplier: f64,
+    /// Randomization factor in `[0, 1]`: the actual delay is picked from
+    /// `[delay * (1 - jitter), delay * (1 + jitter)]`, so that clients
+    /// disconnected at the same time don't reconnect at the same time.
+    pub jitter: f64,
+    /// Gives up reconnecting once this much time has passed since the
+    /// stream got disconnected. Retries forever if not set (unless limited
+    /// by [EventClient::with_max_retries]).
+    pub max_elapsed_time: Option<Duration>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_elapsed_time: None,
+        }
     }
 }
 
+impl Backoff {
+    /// Delay before the given (zero-based) reconnection attempt, without
+    /// jitter.
+    pub fn base_delay(&self, attempt: u32) -> Duration {
+        let delay = self.initial_delay.as_secs_f64()
+            * self.multiplier.powi(attempt.min(i32::MAX as u32) as i32);
+        Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()))
+    }
+
+    /// Delay before the given (zero-based) reconnection attempt, with
+    /// jitter applied.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 + jitter * (2.0 * random_unit() - 1.0);
+        self.base_delay(attempt).mul_f64(factor)
+    }
+}
+
+/// Returns a pseudo-random number in `[0, 1)`, good enough for jitter.
+fn random_unit() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    hasher.write_u128(now.as_nanos());
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
 /// A stream of SSE items.
 #[must_use = "streams do nothing unless polled"]
 pub struct EventStream<T: fmt::Debug> {
     inner: EventStreamInner,
     state: Option<State<T>>,
+    /// Fires when the connection has been idle for too long.
+    idle_timer: Option<Pin<Box<tokio::time::Sleep>>>,
 }
 
 impl<T: fmt::Debug> EventStream<T> {
@@ -184,10 +419,73 @@ impl<T: fmt::Debug> EventStream<T> {
         &self.inner.endpoint
     }
 
+    /// The query params sent on every (re-)connection.
+    pub fn query(&self) -> Option<&serde_json::Value> {
+        self.inner.query.as_ref()
+    }
+
     /// Resets all retry attempts.
     pub fn reset_retries(&mut self) {
         self.inner.num_retries = 0;
     }
+
+    /// When the last data (an event or a keep-alive comment) has been
+    /// received, or the stream has (re-)connected if nothing has been
+    /// received since.
+    pub fn last_event_at(&self) -> Instant {
+        self.inner.last_event_at()
+    }
+
+    /// Polls the idle timer, returning `true` once the connection has been
+    /// idle for longer than the configured idle timeout.
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> bool {
+        let Some(idle_timeout) = self.inner.event_client.idle_timeout else {
+            return false;
+        };
+        loop {
+            let deadline = tokio::time::Instant::from(
+                self.inner.last_event_at() + idle_timeout,
+            );
+            let timer = self.idle_timer.get_or_insert_with(|| {
+                Box::pin(tokio::time::sleep_until(deadline))
+            });
+            if timer.deadline() != deadline {
+                timer.as_mut().reset(deadline);
+            }
+            if timer.as_mut().poll(cx).is_pending() {
+                return false;
+            }
+            if self.inner.last_event_at().elapsed() >= idle_timeout {
+                return true;
+            }
+        }
+    }
+
+    /// Id of the last received event, which is sent in the `Last-Event-ID`
+    /// header on reconnection, if the server assigns event ids.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.inner.last_event_id.as_deref()
+    }
+
+    /// Number of automatic reconnection attempts since the stream got
+    /// disconnected, `0` while connected.
+    pub fn reconnect_attempts(&self) -> u32 {
+        self.inner.reconnect_attempt
+    }
+
+    /// Whether the stream is currently waiting to reconnect.
+    pub fn is_reconnecting(&self) -> bool {
+        matches!(self.state, Some(State::Retry(_)))
+    }
+
+    /// Counters and the connection state of this stream.
+    pub fn stats(&self) -> SseStats {
+        self.inner.observers.stats.stats()
+    }
+
+    pub(crate) fn observers(&self) -> &Observers {
+        &self.inner.observers
+    }
 }
 
 impl<T: DeserializeOwned + fmt::Debug> EventStream<T> {
@@ -214,6 +512,23 @@ impl<T: DeserializeOwned + fmt::Debug> EventStream<T> {
         self.state = Some(State::Active(Box::pin(stream)));
         Ok(())
     }
+
+    /// Retries the stream by establishing a new connection using the given
+    /// endpoint and query params, which are also used by later retries.
+    #[instrument(
+        name = "MEV-share SSE retrying with new endpoint and query",
+        skip(self, endpoint, query)
+    )]
+    pub async fn retry_with_query<S: Serialize>(
+        &mut self,
+        endpoint: impl Into<String>,
+        query: S,
+    ) -> Result<(), SseError> {
+        self.inner.query = Some(
+            serde_json::to_value(query).map_err(SseError::SerdeJsonError)?,
+        );
+        self.retry_with(endpoint).await
+    }
 }
 
 impl<T: fmt::Debug> fmt::Debug for EventStream<T> {
@@ -265,10 +580,20 @@ impl<T: DeserializeOwned + fmt::Debug> Stream for EventStream<T> {
                             continue;
                         }
                         Poll::Ready(Err(err)) => {
+                            this.state = match &err {
+                                SseError::RetryError(_) => {
+                                    this.inner.reconnect().map(State::Retry)
+                                }
+                                _ => None,
+                            }
+                            .or(Some(State::End));
+                            if matches!(this.state, Some(State::End)) {
+                                this.inner.set_state(ConnectionState::Closed);
+                            }
                             tracing::debug!(
-                                "failed to retry, stopping, returning error"
+                                reconnecting = this.is_reconnecting(),
+                                "failed to retry, returning error"
                             );
-                            this.state = Some(State::End);
                             return Poll::Ready(Some(Err(err)));
                         }
                         Poll::Pending => {
@@ -283,7 +608,15 @@ impl<T: DeserializeOwned + fmt::Debug> Stream for EventStream<T> {
                     tracing::debug!("state = active");
                     match stream.as_mut().poll_next(cx) {
                         Poll::Ready(None) => {
+                            if let Some(future) = this.inner.reconnect() {
+                                tracing::debug!(
+                                    "active stream finished, reconnecting"
+                                );
+                                this.state = Some(State::Retry(future));
+                                continue;
+                            }
                             tracing::debug!("active stream finished, stopping");
+                            this.inner.set_state(ConnectionState::Closed);
                             this.state = Some(State::End);
                             return Poll::Ready(None);
                         }
@@ -292,31 +625,72 @@ impl<T: DeserializeOwned + fmt::Debug> Stream for EventStream<T> {
 
                             match event_or_retry {
                                 // Got an event - return it.
-                                EventOrRetry::Event(event) => {
-                                    tracing::debug!(?event, "got event");
+                                EventOrRetry::Event(event, id) => {
+                                    tracing::debug!(?event, ?id, "got event");
+                                    this.inner.observers.notify(|observer| {
+                                        observer.on_event(&this.inner.endpoint)
+                                    });
+                                    this.inner.reconnected();
+                                    if id.is_some() {
+                                        this.inner.last_event_id = id;
+                                    }
                                     result = Poll::Ready(Some(Ok(event)));
                                 }
                                 // Got a retry -
                                 // start retrying after the duration.
+                                EventOrRetry::Skip => {
+                                    this.state = Some(State::Active(stream));
+                                    continue;
+                                }
                                 EventOrRetry::Retry(duration) => {
                                     tracing::debug!("got retry");
-                                    let mut client = this.inner.clone();
-                                    let future = Box::pin(async move {
-                                        tokio::time::sleep(duration).await;
-                                        client.retry().await
-                                    });
+                                    let future =
+                                        this.inner.retry_after(duration);
                                     this.state = Some(State::Retry(future));
                                     continue;
                                 }
                             }
                         }
                         Poll::Ready(Some(Err(err))) => {
-                            // Got an error, end the stream.
                             tracing::warn!(?err, "active stream error");
+                            if matches!(err, SseError::SerdeJsonError(_)) {
+                                this.inner.observers.notify(|observer| {
+                                    observer
+                                        .on_decode_error(&this.inner.endpoint)
+                                });
+                            }
+                            // Transport errors break the connection.
+                            if matches!(err, SseError::Http(_))
+                                && let Some(future) = this.inner.reconnect()
+                            {
+                                this.state = Some(State::Retry(future));
+                                return Poll::Ready(Some(Err(err)));
+                            }
                             result = Poll::Ready(Some(Err(err)));
                         }
                         Poll::Pending => {
                             tracing::debug!("active stream pending");
+                            if this.poll_idle(cx) {
+                                let idle_timeout = this
+                                    .inner
+                                    .event_client
+                                    .idle_timeout
+                                    .unwrap_or_default();
+                                tracing::warn!(
+                                    ?idle_timeout,
+                                    "stream is stale, reconnecting"
+                                );
+                                let future = this
+                                    .inner
+                                    .reconnect()
+                                    .unwrap_or_else(|| {
+                                        this.inner.retry_after(Duration::ZERO)
+                                    });
+                                this.state = Some(State::Retry(future));
+                                return Poll::Ready(Some(Err(
+                                    SseError::IdleTimeout(idle_timeout),
+                                )));
+                            }
                         }
                     }
                     this.state = Some(State::Active(stream));
@@ -329,12 +703,15 @@ impl<T: DeserializeOwned + fmt::Debug> Stream for EventStream<T> {
     }
 }
 
+type RetryFuture<T> =
+    BoxFuture<'static, Result<ActiveEventStream<T>, SseError>>;
+
 /// State machine for [EventStream].
 enum State<T: fmt::Debug> {
     /// Stream has finished.
     End,
     /// Waiting for retry future to resolve.
-    Retry(BoxFuture<'static, Result<ActiveEventStream<T>, SseError>>),
+    Retry(RetryFuture<T>),
     /// Active, connected stream.
     Active(Pin<Box<ActiveEventStream<T>>>),
 }
@@ -344,12 +721,22 @@ enum State<T: fmt::Debug> {
 pub struct EventStreamInner {
     /// Number of retries.
     num_retries: u64,
+    /// Number of automatic reconnection attempts since the last event.
+    reconnect_attempt: u32,
+    /// When the stream got disconnected, if it is being reconnected.
+    reconnecting_since: Option<Instant>,
+    /// Id of the last received event.
+    last_event_id: Option<String>,
+    /// When data has been received last, shared with the connection.
+    last_activity: Arc<Mutex<Instant>>,
     /// Endpoint to connect to.
     endpoint: String,
     /// Client to use for connecting.
     event_client: EventClient,
-    /// Query parameters..
+    /// Query parameters, sent again on every retry.
     query: Option<serde_json::Value>,
+    /// Observers notified about the activity of the stream.
+    observers: Observers,
 }
 
 impl EventStreamInner {
@@ -358,6 +745,12 @@ impl EventStreamInner {
     async fn retry<T: DeserializeOwned + fmt::Debug>(
         &mut self,
     ) -> Result<ActiveEventStream<T>, SseError> {
+        self.count_retry()?;
+        self.connect().await
+    }
+
+    /// Counts a retry attempt, failing if all retries are exhausted.
+    fn count_retry(&mut self) -> Result<(), SseError> {
         self.num_retries += 1;
 
         if let Some(max_retries) = self.event_client.max_retries
@@ -371,19 +764,95 @@ impl EventStreamInner {
             retries = self.num_retries,
             "retrying SSE stream"
         );
-        ActiveEventStream::connect(
-            &self.event_client.reqwest_client,
-            &self.endpoint,
-            self.query.as_ref(),
-        )
-        .map_err(SseError::RetryError)
-        .await
+        self.observers.notify(|observer| {
+            observer.on_reconnect(&self.endpoint, self.num_retries);
+            observer.on_state_change(
+                &self.endpoint,
+                ConnectionState::Reconnecting,
+            );
+        });
+        Ok(())
+    }
+
+    /// Opens a new connection to the endpoint.
+    async fn open<T: DeserializeOwned + fmt::Debug>(
+        &self,
+    ) -> reqwest::Result<ActiveEventStream<T>> {
+        let stream = ActiveEventStream::connect(self).await?;
+        self.set_state(ConnectionState::Connected);
+        Ok(stream)
+    }
+
+    async fn connect<T: DeserializeOwned + fmt::Debug>(
+        &self,
+    ) -> Result<ActiveEventStream<T>, SseError> {
+        self.open().map_err(SseError::RetryError).await
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        self.observers
+            .notify(|observer| observer.on_state_change(&self.endpoint, state));
+    }
+
+    /// Returns a future retrying the stream after the given delay.
+    /// The retry is counted right away, so that it is not lost with the
+    /// clone moved into the future.
+    fn retry_after<T: DeserializeOwned + fmt::Debug>(
+        &mut self,
+        delay: Duration,
+    ) -> RetryFuture<T> {
+        let counted = self.count_retry();
+        let inner = self.clone();
+        Box::pin(async move {
+            counted?;
+            tokio::time::sleep(delay).await;
+            inner.connect().await
+        })
+    }
+
+    /// Returns a future reconnecting the stream after the backoff delay, or
+    /// `None` if automatic reconnection is disabled or has timed out.
+    fn reconnect<T: DeserializeOwned + fmt::Debug>(
+        &mut self,
+    ) -> Option<RetryFuture<T>> {
+        let backoff = self.event_client.backoff.clone()?;
+        let since = *self.reconnecting_since.get_or_insert_with(Instant::now);
+        if let Some(max_elapsed_time) = backoff.max_elapsed_time
+            && since.elapsed() >= max_elapsed_time
+        {
+            tracing::debug!(
+                ?max_elapsed_time,
+                "giving up reconnecting"
+            );
+            return None;
+        }
+        let delay = backoff.delay(self.reconnect_attempt);
+        self.reconnect_attempt += 1;
+        tracing::debug!(
+            attempt = self.reconnect_attempt,
+            ?delay,
+            "reconnecting SSE stream"
+        );
+        Some(self.retry_after(delay))
+    }
+
+    fn last_event_at(&self) -> Instant {
+        *self.last_activity.lock().unwrap()
+    }
+
+    /// Resets the reconnection backoff once the stream is healthy again.
+    fn reconnected(&mut self) {
+        self.reconnect_attempt = 0;
+        self.reconnecting_since = None;
     }
 }
 
 type ToIoError = fn(reqwest::Error) -> std::io::Error;
-type ToEventOrRetry<T> =
-    fn(async_sse::Event) -> serde_json::Result<EventOrRetry<T>>;
+type ToEventOrRetry<T> = Box<
+    dyn Fn(async_sse::Event) -> serde_json::Result<EventOrRetry<T>>
+        + Send
+        + Sync,
+>;
 
 type RequestStream = Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>;
 
@@ -394,7 +863,10 @@ type SseDecoderStream<T> = MapOk<
 
 enum EventOrRetry<T: fmt::Debug> {
     Retry(Duration),
-    Event(T),
+    /// Event along with the last event id seen on the connection.
+    Event(T, Option<String>),
+    /// Event dropped by the [EventFilter].
+    Skip,
 }
 
 pin_project! {
@@ -432,14 +904,17 @@ where
     T: DeserializeOwned + fmt::Debug,
 {
     /// Connects to the SSE endpoint and returns a new [ActiveEventStream].
-    #[instrument(name = "MEV-share SSE connecting", skip(client, query))]
-    async fn connect<S: Serialize>(
-        client: &reqwest::Client,
-        endpoint: &str,
-        query: Option<S>,
+    #[instrument(
+        name = "MEV-share SSE connecting",
+        skip_all,
+        fields(endpoint = %inner.endpoint)
+    )]
+    async fn connect(
+        inner: &EventStreamInner,
     ) -> reqwest::Result<ActiveEventStream<T>> {
-        let mut builder = client
-            .get(endpoint)
+        let mut builder = inner
+            .event_client
+            .get(&inner.endpoint)
             .header(
                 header::ACCEPT,
                 HeaderValue::from_static("text/event-stream"),
@@ -449,29 +924,55 @@ where
                 HeaderValue::from_static("no-cache"),
             );
 
-        if let Some(query) = query {
-            builder = builder.query(&query);
+        if let Some(query) = &inner.query {
+            builder = builder.query(query);
+        }
+        // Lets the server resume the stream without gaps.
+        if let Some(last_event_id) = &inner.last_event_id {
+            builder = builder.header("Last-Event-ID", last_event_id);
         }
 
         let response = builder.send().await?;
+        let last_activity = Arc::clone(&inner.last_activity);
+        *last_activity.lock().unwrap() = Instant::now();
+        let filter = inner.event_client.filter.clone();
 
         // Converts reqwest errors to io::Error.
         let to_io_error: ToIoError = std::io::Error::other;
 
         // Converts SSE events to [EventOrRetry].
-        let to_event_or_retry: ToEventOrRetry<_> = |event| match event {
-            async_sse::Event::Message(message) => {
-                trace!(message = ?String::from_utf8_lossy(message.data()), "received message");
-                serde_json::from_slice::<T>(message.data())
-                    .map(EventOrRetry::Event)
-            }
-            async_sse::Event::Retry(duration) => {
-                trace!(?duration, "receive retry");
-                Ok(EventOrRetry::Retry(duration))
+        let to_event_or_retry: ToEventOrRetry<_> = Box::new(move |event| {
+            match event {
+                async_sse::Event::Message(message) => {
+                    trace!(message = ?String::from_utf8_lossy(message.data()), "received message");
+                    if let Some(filter) = &filter
+                        && !filter.matches_json(message.data())
+                    {
+                        return Ok(EventOrRetry::Skip);
+                    }
+                    serde_json::from_slice::<T>(message.data()).map(|event| {
+                        EventOrRetry::Event(event, message.id().clone())
+                    })
+                }
+                async_sse::Event::Retry(duration) => {
+                    trace!(?duration, "receive retry");
+                    Ok(EventOrRetry::Retry(duration))
+                }
             }
-        };
-
-        let event_stream: RequestStream = Box::pin(response.bytes_stream());
+        });
+
+        // Any received data, including keep-alive comments, which are
+        // dropped by the decoder, proves that the connection is alive.
+        let observers = inner.observers.clone();
+        let endpoint = inner.endpoint.clone();
+        let event_stream: RequestStream = Box::pin(
+            response.bytes_stream().inspect_ok(move |bytes| {
+                *last_activity.lock().unwrap() = Instant::now();
+                observers.notify(|observer| {
+                    observer.on_bytes(&endpoint, bytes.len())
+                });
+            }),
+        );
         let reader = event_stream.map_err(to_io_error).into_async_read();
         let stream = async_sse::decode(reader).map_ok(to_event_or_retry);
 
@@ -494,4 +995,14 @@ pub enum SseError {
     /// Exceeded all retries.
     #[error("Exceeded all retries: {0}")]
     MaxRetriesExceeded(u64),
+    /// Nothing has been received for the given time, the connection is
+    /// considered stale.
+    #[error("No data received for {0:?}, connection is stale")]
+    IdleTimeout(Duration),
+    /// Failed to fetch past events.
+    #[error("Failed to fetch event history: {0}")]
+    History(reqwest::Error),
+    /// No endpoints to connect to.
+    #[error("No endpoints to connect to")]
+    NoEndpoints,
 }
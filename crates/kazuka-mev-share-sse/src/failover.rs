@@ -0,0 +1,313 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    pin::{Pin, pin},
+    time::Duration,
+};
+
+use alloy::primitives::TxHash;
+use futures_util::{
+    Stream, StreamExt,
+    future::{Either, select},
+    stream,
+};
+use tokio::time::Instant;
+
+use crate::{
+    Event,
+    client::{EventClient, EventStream, SseError},
+};
+
+/// Default interval between attempts to fail back to a preferred endpoint.
+const DEFAULT_FAIL_BACK_INTERVAL: Duration = Duration::from_secs(30);
+/// Default number of consecutive connection errors of an endpoint, after
+/// which it is failed over.
+const DEFAULT_FAILOVER_ERRORS: u32 = 3;
+/// Delay before trying all endpoints again once none is reachable.
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// Number of recent event hashes remembered for deduplication.
+const SEEN_CAPACITY: usize = 10_000;
+
+/// A stream of [Event]s, which fails over between endpoints.
+pub type FailoverEventStream =
+    Pin<Box<dyn Stream<Item = Result<Event, SseError>> + Send>>;
+
+/// Remembers recently seen event hashes.
+#[derive(Default)]
+pub(crate) struct SeenHashes {
+    hashes: HashSet<TxHash>,
+    order: VecDeque<TxHash>,
+}
+
+impl SeenHashes {
+    /// Returns `true` if the hash hasn't been seen yet.
+    pub(crate) fn insert(&mut self, hash: TxHash) -> bool {
+        if !self.hashes.insert(hash) {
+            return false;
+        }
+        self.order.push_back(hash);
+        if self.order.len() > SEEN_CAPACITY
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.hashes.remove(&oldest);
+        }
+        true
+    }
+}
+
+struct Failover {
+    client: EventClient,
+    /// Endpoints, most preferred first.
+    endpoints: Vec<String>,
+    fail_back_interval: Duration,
+    /// Number of consecutive connection errors, after which the endpoint is
+    /// failed over.
+    failover_errors: u32,
+    /// Index of the connected endpoint and its stream.
+    active: Option<(usize, EventStream<Event>)>,
+    /// Index of the endpoint, which has been connected last.
+    last: Option<usize>,
+    last_fail_back: Instant,
+    /// Consecutive connection errors of the connected endpoint.
+    errors: u32,
+    seen: SeenHashes,
+}
+
+/// Whether the error means that the endpoint is unreachable or has broken
+/// the connection, as opposed to e.g. a malformed event.
+fn is_connection_error(error: &SseError) -> bool {
+    matches!(
+        error,
+        SseError::Http(_)
+            | SseError::RetryError(_)
+            | SseError::IdleTimeout(_)
+            | SseError::MaxRetriesExceeded(_)
+    )
+}
+
+impl Failover {
+    /// Connects to the first reachable endpoint among the given ones.
+    async fn connect(
+        &mut self,
+        candidates: impl IntoIterator<Item = usize>,
+    ) -> Result<(), SseError> {
+        let mut last_error = None;
+        for index in candidates {
+            let endpoint = &self.endpoints[index];
+            match self.client.events(endpoint).await {
+                Ok(stream) => {
+                    tracing::info!(%endpoint, "connected to SSE endpoint");
+                    self.active = Some((index, stream));
+                    self.last = Some(index);
+                    self.errors = 0;
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::warn!(%endpoint, ?e, "SSE endpoint unreachable");
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) => Err(SseError::RetryError(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Endpoints to try after the last one has disconnected: the following
+    /// ones first, so that an endpoint that keeps terminating the stream
+    /// doesn't starve the others.
+    fn fail_over_order(&self) -> Vec<usize> {
+        let start = self.last.map_or(0, |last| last + 1);
+        (0..self.endpoints.len())
+            .map(|offset| (start + offset) % self.endpoints.len())
+            .collect()
+    }
+
+    /// Time left until the next attempt to fail back, if connected to a
+    /// fallback.
+    fn fail_back_in(&self) -> Option<Duration> {
+        match self.active {
+            Some((index, _)) if index > 0 => Some(
+                self.fail_back_interval
+                    .saturating_sub(self.last_fail_back.elapsed()),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Tries to switch back to a more preferred endpoint.
+    async fn fail_back(&mut self) {
+        let Some((index, _)) = self.active else {
+            return;
+        };
+        self.last_fail_back = Instant::now();
+        for preferred in 0..index {
+            let endpoint = &self.endpoints[preferred];
+            if let Ok(stream) = self.client.events(endpoint).await {
+                tracing::info!(%endpoint, "failed back to SSE endpoint");
+                self.active = Some((preferred, stream));
+                self.last = Some(preferred);
+                self.errors = 0;
+                return;
+            }
+        }
+    }
+
+    async fn next(&mut self) -> Result<Event, SseError> {
+        loop {
+            if self.active.is_none() {
+                let order = self.fail_over_order();
+                if let Err(e) = self.connect(order).await {
+                    let delay = self
+                        .client
+                        .backoff()
+                        .map_or(DEFAULT_RECONNECT_DELAY, |backoff| {
+                            backoff.initial_delay
+                        });
+                    tokio::time::sleep(delay).await;
+                    return Err(e);
+                }
+            }
+
+            // Fails back on time even if the fallback is quiet.
+            let fail_back_in = self.fail_back_in();
+            let Some((index, stream)) = &mut self.active else {
+                continue;
+            };
+            let index = *index;
+            let fail_back = pin!(async move {
+                match fail_back_in {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => std::future::pending().await,
+                }
+            });
+            let item = match select(stream.next(), fail_back).await {
+                Either::Left((item, _)) => Some(item),
+                Either::Right(_) => None,
+            };
+            let Some(item) = item else {
+                self.fail_back().await;
+                continue;
+            };
+
+            let endpoint = &self.endpoints[index];
+            match item {
+                Some(Ok(event)) => {
+                    self.errors = 0;
+                    if self.seen.insert(event.hash) {
+                        return Ok(event);
+                    }
+                }
+                Some(Err(e)) => {
+                    // The stream keeps reconnecting to the same endpoint
+                    // on its own, so an unreachable one is failed over.
+                    if is_connection_error(&e) {
+                        self.errors += 1;
+                        if self.errors >= self.failover_errors {
+                            tracing::warn!(
+                                %endpoint,
+                                errors = self.errors,
+                                "SSE endpoint keeps failing, failing over"
+                            );
+                            self.active = None;
+                        }
+                    }
+                    return Err(e);
+                }
+                None => {
+                    tracing::warn!(
+                        %endpoint,
+                        "SSE stream terminated, failing over"
+                    );
+                    self.active = None;
+                }
+            }
+        }
+    }
+}
+
+impl EventClient {
+    /// Sets the interval between attempts to fail back to a more preferred
+    /// endpoint, see [EventClient::events_with_failover].
+    pub fn with_fail_back_interval(
+        mut self,
+        fail_back_interval: Duration,
+    ) -> Self {
+        self.fail_back_interval = Some(fail_back_interval);
+        self
+    }
+
+    /// Sets the number of consecutive connection errors of an endpoint,
+    /// after which it is failed over, see
+    /// [EventClient::events_with_failover].
+    pub fn with_failover_errors(mut self, failover_errors: u32) -> Self {
+        self.failover_errors = Some(failover_errors.max(1));
+        self
+    }
+
+    /// Subscribes to a stream of [Event]s from a prioritized list of
+    /// endpoints, e.g. the Flashbots endpoint followed by a self-hosted
+    /// mirror.
+    ///
+    /// Connects to the first reachable endpoint and fails over to the
+    /// others once its stream terminates, or fails to reconnect several
+    /// times in a row (see [EventClient::with_failover_errors]), the
+    /// errors being passed on. While connected to a fallback,
+    /// periodically tries to fail back to the more preferred endpoints.
+    /// Events are deduplicated by hash across the switches.
+    ///
+    /// Fails if none of the endpoints is reachable initially.
+    pub async fn events_with_failover(
+        &self,
+        endpoints: Vec<String>,
+    ) -> Result<FailoverEventStream, SseError> {
+        let mut failover = Failover {
+            client: self.clone(),
+            endpoints,
+            fail_back_interval: self
+                .fail_back_interval
+                .unwrap_or(DEFAULT_FAIL_BACK_INTERVAL),
+            failover_errors: self
+                .failover_errors
+                .unwrap_or(DEFAULT_FAILOVER_ERRORS),
+            active: None,
+            last: None,
+            last_fail_back: Instant::now(),
+            errors: 0,
+            seen: SeenHashes::default(),
+        };
+        failover.connect(0..failover.endpoints.len()).await?;
+        if failover.active.is_none() {
+            return Err(SseError::NoEndpoints);
+        }
+
+        let stream = stream::unfold(failover, |mut failover| async move {
+            let item = failover.next().await;
+            Some((item, failover))
+        });
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+
+    use super::*;
+
+    #[test]
+    fn test_seen_hashes_are_bounded() {
+        let mut seen = SeenHashes::default();
+        assert!(seen.insert(B256::ZERO));
+        assert!(!seen.insert(B256::ZERO));
+
+        for i in 1..=SEEN_CAPACITY as u64 {
+            assert!(seen.insert(B256::left_padding_from(
+                &i.to_be_bytes()
+            )));
+        }
+        assert_eq!(seen.order.len(), SEEN_CAPACITY);
+        // The oldest hash has been forgotten.
+        assert!(seen.insert(B256::ZERO));
+    }
+}
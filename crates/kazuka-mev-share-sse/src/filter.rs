@@ -0,0 +1,183 @@
+//! Client-side filtering of MEV-share events.
+
+use std::collections::HashSet;
+
+use alloy::{
+    primitives::{Address, U256},
+    rpc::types::mev::mevshare::FunctionSelector,
+};
+use serde::Deserialize;
+
+/// Filters MEV-share events on the client, before they reach strategies.
+///
+/// Events are matched against a lightweight view of their JSON, so that
+/// non-matching events are dropped without deserializing the full
+/// [Event](crate::Event). An event matches if it satisfies all of the
+/// configured conditions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    to_addresses: Option<HashSet<Address>>,
+    function_selectors: Option<HashSet<FunctionSelector>>,
+    min_value: Option<U256>,
+    logs_only: bool,
+}
+
+impl EventFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keeps events with a transaction sent to, or a log emitted by,
+    /// one of the given addresses.
+    pub fn with_to_addresses(
+        mut self,
+        addresses: impl IntoIterator<Item = Address>,
+    ) -> Self {
+        self.to_addresses = Some(addresses.into_iter().collect());
+        self
+    }
+
+    /// Only keeps events with a transaction calling one of the given
+    /// functions.
+    pub fn with_function_selectors(
+        mut self,
+        selectors: impl IntoIterator<Item = FunctionSelector>,
+    ) -> Self {
+        self.function_selectors = Some(selectors.into_iter().collect());
+        self
+    }
+
+    /// Only keeps events with a transaction transferring at least the given
+    /// value (in wei). Note that the value is only shared by some users.
+    pub fn with_min_value(mut self, min_value: U256) -> Self {
+        self.min_value = Some(min_value);
+        self
+    }
+
+    /// Only keeps events, which share logs.
+    pub fn logs_only(mut self) -> Self {
+        self.logs_only = true;
+        self
+    }
+
+    /// Whether the raw JSON event matches the filter.
+    /// Events, which can't be decoded, are let through to surface the
+    /// decoding error.
+    pub fn matches_json(&self, data: &[u8]) -> bool {
+        match serde_json::from_slice::<RawEvent>(data) {
+            Ok(event) => self.matches(&event),
+            Err(_) => true,
+        }
+    }
+
+    fn matches(&self, event: &RawEvent) -> bool {
+        let txs = event.txs.as_deref().unwrap_or_default();
+        let logs = event.logs.as_deref().unwrap_or_default();
+
+        if self.logs_only && logs.is_empty() {
+            return false;
+        }
+        if let Some(addresses) = &self.to_addresses {
+            let to = txs.iter().filter_map(|tx| tx.to);
+            let emitters = logs.iter().map(|log| log.address);
+            if !to
+                .chain(emitters)
+                .any(|address| addresses.contains(&address))
+            {
+                return false;
+            }
+        }
+        if let Some(selectors) = &self.function_selectors
+            && !txs.iter().any(|tx| {
+                tx.function_selector
+                    .as_ref()
+                    .is_some_and(|selector| selectors.contains(selector))
+            })
+        {
+            return false;
+        }
+        if let Some(min_value) = self.min_value
+            && !txs
+                .iter()
+                .any(|tx| tx.value.is_some_and(|value| value >= min_value))
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// Fields of an [Event](crate::Event) needed for filtering.
+#[derive(Deserialize)]
+struct RawEvent {
+    #[serde(default)]
+    logs: Option<Vec<RawLog>>,
+    #[serde(default)]
+    txs: Option<Vec<RawTransaction>>,
+}
+
+#[derive(Deserialize)]
+struct RawLog {
+    address: Address,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTransaction {
+    #[serde(default)]
+    to: Option<Address>,
+    #[serde(default)]
+    function_selector: Option<FunctionSelector>,
+    #[serde(default)]
+    value: Option<U256>,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+    use serde_json::json;
+
+    use super::*;
+
+    fn event() -> Vec<u8> {
+        json!({
+            "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+            "logs": null,
+            "txs": [{
+                "to": "0x57e114b691db790c35207b2e685d4a43181e6061",
+                "functionSelector": "0xa9059cbb",
+                "value": "0x64"
+            }]
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let event = event();
+        assert!(EventFilter::new().matches_json(&event));
+        assert!(
+            EventFilter::new()
+                .with_to_addresses([address!(
+                    "0x57e114b691db790c35207b2e685d4a43181e6061"
+                )])
+                .with_function_selectors([FunctionSelector([
+                    0xa9, 0x05, 0x9c, 0xbb
+                ])])
+                .with_min_value(U256::from(100))
+                .matches_json(&event)
+        );
+        assert!(
+            !EventFilter::new()
+                .with_to_addresses([Address::ZERO])
+                .matches_json(&event)
+        );
+        assert!(
+            !EventFilter::new()
+                .with_min_value(U256::from(101))
+                .matches_json(&event)
+        );
+        assert!(!EventFilter::new().logs_only().matches_json(&event));
+    }
+}
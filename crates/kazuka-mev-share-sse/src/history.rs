@@ -0,0 +1,301 @@
+use std::{
+    future,
+    marker::PhantomData,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use alloy::rpc::types::mev::mevshare::{
+    EventHistory, EventHistoryInfo, EventHistoryParams,
+};
+use futures_util::{Stream, StreamExt, stream};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    Event, HistoricalEvent,
+    client::{EventClient, EventStream, SseError},
+    failover::SeenHashes,
+};
+
+/// A stream of past events, fetched page by page.
+pub type EventHistoryStream<T = EventHistory> =
+    Pin<Box<dyn Stream<Item = reqwest::Result<T>> + Send>>;
+
+/// A stream of replayed past events followed by live ones.
+pub type CatchUpEventStream =
+    Pin<Box<dyn Stream<Item = Result<CatchUpEvent, SseError>> + Send>>;
+
+/// Event of a [CatchUpEventStream], telling replayed events from live ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CatchUpEvent {
+    /// Past event replayed from the `history` endpoint.
+    Replayed(HistoricalEvent),
+    /// Event received from the live stream.
+    Live(Event),
+}
+
+impl CatchUpEvent {
+    pub fn hint(&self) -> &Event {
+        match self {
+            Self::Replayed(event) => &event.hint,
+            Self::Live(event) => event,
+        }
+    }
+
+    pub fn into_hint(self) -> Event {
+        match self {
+            Self::Replayed(event) => event.hint,
+            Self::Live(event) => event,
+        }
+    }
+
+    pub fn is_replayed(&self) -> bool {
+        matches!(self, Self::Replayed(_))
+    }
+}
+
+/// Point to replay the event history from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryStart {
+    Block(u64),
+    /// Unix timestamp (in seconds).
+    Timestamp(u64),
+}
+
+enum PaginationState {
+    Start,
+    Page { offset: u64, limit: u64 },
+    Done,
+}
+
+struct Pagination<T> {
+    client: EventClient,
+    endpoint: String,
+    params: EventHistoryParams,
+    /// Minimum interval between page requests.
+    rate_limit: Option<Duration>,
+    last_request: Option<Instant>,
+    state: PaginationState,
+    _event: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Pagination<T> {
+    /// Waits until the next page can be requested without exceeding the
+    /// rate limit.
+    async fn throttle(&mut self) {
+        if let (Some(rate_limit), Some(last_request)) =
+            (self.rate_limit, self.last_request)
+        {
+            let elapsed = last_request.elapsed();
+            if elapsed < rate_limit {
+                tokio::time::sleep(rate_limit - elapsed).await;
+            }
+        }
+        self.last_request = Some(Instant::now());
+    }
+
+    async fn info(&mut self) -> reqwest::Result<EventHistoryInfo> {
+        self.throttle().await;
+        let url = format!(
+            "{}/info",
+            self.endpoint.trim_end_matches('/')
+        );
+        self.client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    /// Fetches the next page, returning its events.
+    async fn fetch(
+        &self,
+        params: EventHistoryParams,
+    ) -> reqwest::Result<Vec<T>> {
+        self.client
+            .get(&self.endpoint)
+            .query(&params)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    async fn next_page(&mut self) -> Vec<reqwest::Result<T>> {
+        loop {
+            match self.state {
+                PaginationState::Start => {
+                    let max_limit = match self.info().await {
+                        Ok(info) => info.max_limit,
+                        Err(e) => {
+                            self.state = PaginationState::Done;
+                            return vec![Err(e)];
+                        }
+                    };
+                    let limit = self
+                        .params
+                        .limit
+                        .map_or(max_limit, |limit| limit.min(max_limit))
+                        .max(1);
+                    self.state = PaginationState::Page {
+                        offset: self.params.offset.unwrap_or_default(),
+                        limit,
+                    };
+                }
+                PaginationState::Page { offset, limit } => {
+                    self.throttle().await;
+                    let params = EventHistoryParams {
+                        limit: Some(limit),
+                        offset: Some(offset),
+                        ..self.params.clone()
+                    };
+                    let page = match self.fetch(params).await {
+                        Ok(page) => page,
+                        Err(e) => {
+                            self.state = PaginationState::Done;
+                            return vec![Err(e)];
+                        }
+                    };
+                    self.state = if (page.len() as u64) < limit {
+                        PaginationState::Done
+                    } else {
+                        PaginationState::Page {
+                            offset: offset + page.len() as u64,
+                            limit,
+                        }
+                    };
+                    return page.into_iter().map(Ok).collect();
+                }
+                PaginationState::Done => return vec![],
+            }
+        }
+    }
+}
+
+impl EventClient {
+    /// Sets the minimum interval between page requests of
+    /// [EventClient::event_history_stream].
+    pub fn with_history_rate_limit(mut self, interval: Duration) -> Self {
+        self.history_rate_limit = Some(interval);
+        self
+    }
+
+    /// Streams past events matching the params, such as from
+    /// `https://mev-share.flashbots.net/api/v1/history`, transparently
+    /// paginating through them.
+    ///
+    /// Pages are as large as allowed by the `info` endpoint (or `limit`, if
+    /// smaller) and start at `offset`. The stream ends after the last page
+    /// or the first error.
+    pub fn event_history_stream(
+        &self,
+        endpoint: &str,
+        params: EventHistoryParams,
+    ) -> EventHistoryStream {
+        self.paginate(endpoint, params)
+    }
+
+    /// Like [EventClient::event_history_stream], but decodes the hints as
+    /// [Event]s, see [EventClient::historical_events].
+    pub fn historical_events_stream(
+        &self,
+        endpoint: &str,
+        params: EventHistoryParams,
+    ) -> EventHistoryStream<HistoricalEvent> {
+        self.paginate(endpoint, params)
+    }
+
+    /// Replays past events starting at the given point from the `history`
+    /// endpoint, then switches to the live stream, without gaps in between:
+    /// the live stream is subscribed to before the replay, and events
+    /// received both ways are only yielded once.
+    ///
+    /// Such as `https://mev-share.flashbots.net` with
+    /// `https://mev-share.flashbots.net/api/v1/history`.
+    pub async fn events_since(
+        &self,
+        endpoint: &str,
+        history_endpoint: &str,
+        start: HistoryStart,
+    ) -> reqwest::Result<CatchUpEventStream> {
+        let live = self.events(endpoint).await?;
+        Ok(self.catch_up(live, history_endpoint, start))
+    }
+
+    /// Like [EventClient::events_since], but subscribes to the live stream
+    /// with additional query params, see [EventClient::subscribe_with_query].
+    pub async fn events_since_with_query<S: Serialize>(
+        &self,
+        endpoint: &str,
+        query: S,
+        history_endpoint: &str,
+        start: HistoryStart,
+    ) -> reqwest::Result<CatchUpEventStream> {
+        let live = self.subscribe_with_query(endpoint, query).await?;
+        Ok(self.catch_up(live, history_endpoint, start))
+    }
+
+    /// Replays the history before the already subscribed live stream.
+    fn catch_up(
+        &self,
+        live: EventStream<Event>,
+        history_endpoint: &str,
+        start: HistoryStart,
+    ) -> CatchUpEventStream {
+        let params = match start {
+            HistoryStart::Block(block) => EventHistoryParams {
+                block_start: Some(block),
+                ..Default::default()
+            },
+            HistoryStart::Timestamp(timestamp) => EventHistoryParams {
+                timestamp_start: Some(timestamp),
+                ..Default::default()
+            },
+        };
+        let history = self
+            .historical_events_stream(history_endpoint, params)
+            .map(|event| {
+                event.map(CatchUpEvent::Replayed).map_err(SseError::History)
+            });
+        let live = live.map(|event| event.map(CatchUpEvent::Live));
+
+        let mut seen = SeenHashes::default();
+        let events = history.chain(live).filter(move |event| {
+            future::ready(match event {
+                Ok(event) => seen.insert(event.hint().hash),
+                Err(_) => true,
+            })
+        });
+        Box::pin(events)
+    }
+
+    fn paginate<T: DeserializeOwned + Send + 'static>(
+        &self,
+        endpoint: &str,
+        params: EventHistoryParams,
+    ) -> EventHistoryStream<T> {
+        let pagination = Pagination {
+            client: self.clone(),
+            endpoint: endpoint.to_string(),
+            params,
+            rate_limit: self.history_rate_limit,
+            last_request: None,
+            state: PaginationState::Start,
+            _event: PhantomData,
+        };
+        let pages = stream::unfold(
+            pagination,
+            |mut pagination| async move {
+                if matches!(pagination.state, PaginationState::Done) {
+                    return None;
+                }
+                let page = pagination.next_page().await;
+                Some((page, pagination))
+            },
+        );
+        Box::pin(pages.flat_map(stream::iter))
+    }
+}
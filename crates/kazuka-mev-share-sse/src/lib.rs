@@ -1,7 +1,28 @@
 pub mod types;
 pub use types::*;
 
+pub mod buffer;
+pub use buffer::{BufferedEventStream, LagPolicy};
+
 pub mod client;
-pub use client::EventClient;
+pub use client::{Backoff, EventClient};
+
+pub mod failover;
+pub use failover::FailoverEventStream;
+
+pub mod filter;
+pub use filter::EventFilter;
+
+pub mod history;
+pub use history::{
+    CatchUpEvent, CatchUpEventStream, EventHistoryStream, HistoryStart,
+};
+
+#[cfg(feature = "test-util")]
+pub mod mock;
+
+pub mod observer;
+pub use observer::{ConnectionState, SseObserver, SseStats};
 
+#[cfg(feature = "server")]
 pub mod server;
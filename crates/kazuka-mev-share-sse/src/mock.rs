@@ -0,0 +1,295 @@
+//! Scripted SSE server for testing SSE clients and strategies.
+//!
+//! Every connection to the [MockSseServer] is answered with the next
+//! [script](MockStep) in order, which lets tests simulate retries,
+//! disconnects and malformed payloads:
+//!
+//! ```no_run
+//! # async fn run() -> std::io::Result<()> {
+//! use kazuka_mev_share_sse::mock::{MockSseServer, MockStep};
+//! use serde_json::json;
+//!
+//! let event = json!({ "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05" });
+//! let server = MockSseServer::builder()
+//!     .connection([MockStep::event(&event), MockStep::Disconnect])
+//!     .connection([MockStep::malformed("{"), MockStep::event(&event)])
+//!     .start()
+//!     .await?;
+//! let endpoint = server.url("/api/v1/mev-share");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::{JoinHandle, JoinSet},
+};
+
+const RESPONSE_HEADERS: &[u8] = b"HTTP/1.1 200 OK\r\n\
+    content-type: text/event-stream\r\n\
+    cache-control: no-cache\r\n\
+    connection: close\r\n\r\n";
+
+const UNAVAILABLE: &[u8] = b"HTTP/1.1 503 Service Unavailable\r\n\
+    content-length: 0\r\n\
+    connection: close\r\n\r\n";
+
+/// Step of a scripted SSE connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockStep {
+    /// Sends an event with the given data.
+    Event { id: Option<String>, data: String },
+    /// Sends a `retry` directive.
+    Retry(Duration),
+    /// Sends a keep-alive comment.
+    Comment(String),
+    /// Waits before the next step.
+    Delay(Duration),
+    /// Keeps the connection open without sending anything, until the server
+    /// is dropped.
+    Hang,
+    /// Closes the connection.
+    Disconnect,
+}
+
+impl MockStep {
+    /// Sends the value as JSON event data.
+    pub fn event(event: &impl Serialize) -> Self {
+        Self::Event {
+            id: None,
+            data: serde_json::to_string(event).expect("Serialization failed"),
+        }
+    }
+
+    /// Sends the value as JSON event data with the given event id.
+    pub fn event_with_id(
+        id: impl Into<String>,
+        event: &impl Serialize,
+    ) -> Self {
+        Self::Event {
+            id: Some(id.into()),
+            data: serde_json::to_string(event).expect("Serialization failed"),
+        }
+    }
+
+    /// Sends the data as is, e.g. invalid JSON.
+    pub fn malformed(data: impl Into<String>) -> Self {
+        Self::Event {
+            id: None,
+            data: data.into(),
+        }
+    }
+
+    fn encode(&self) -> Option<String> {
+        match self {
+            Self::Event { id, data } => {
+                let id = id
+                    .as_ref()
+                    .map(|id| format!("id: {id}\n"))
+                    .unwrap_or_default();
+                Some(format!("{id}data: {data}\n\n"))
+            }
+            Self::Retry(duration) => Some(format!(
+                "retry: {}\n\n",
+                duration.as_millis()
+            )),
+            Self::Comment(comment) => Some(format!(": {comment}\n\n")),
+            Self::Delay(_) | Self::Hang | Self::Disconnect => None,
+        }
+    }
+}
+
+/// Request received by the [MockSseServer].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockRequest {
+    /// Path and query, e.g. `/events?txs=true`.
+    pub target: String,
+    /// Headers, with lowercase names.
+    pub headers: Vec<(String, String)>,
+}
+
+impl MockRequest {
+    /// Value of the header with the given (case-insensitive) name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn parse(request: &str) -> Self {
+        let mut lines = request.lines();
+        let target = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or_default()
+            .to_string();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| {
+                (
+                    name.trim().to_ascii_lowercase(),
+                    value.trim().to_string(),
+                )
+            })
+            .collect();
+        Self { target, headers }
+    }
+}
+
+/// Builder of a [MockSseServer].
+#[derive(Debug, Default)]
+pub struct MockSseServerBuilder {
+    connections: Vec<Vec<MockStep>>,
+}
+
+impl MockSseServerBuilder {
+    /// Adds a script for the next connection.
+    pub fn connection(
+        mut self,
+        steps: impl IntoIterator<Item = MockStep>,
+    ) -> Self {
+        self.connections.push(steps.into_iter().collect());
+        self
+    }
+
+    /// Binds to a random local port and starts serving.
+    pub async fn start(self) -> io::Result<MockSseServer> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let requests = Arc::new(Mutex::new(vec![]));
+        let task = tokio::spawn(serve(
+            listener,
+            self.connections,
+            Arc::clone(&requests),
+        ));
+        Ok(MockSseServer {
+            addr,
+            requests,
+            task,
+        })
+    }
+}
+
+/// Local HTTP server, which answers every connection with the next scripted
+/// SSE stream, and with `503 Service Unavailable` once all scripts have
+/// been played. Stops when dropped.
+#[derive(Debug)]
+pub struct MockSseServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    task: JoinHandle<()>,
+}
+
+impl MockSseServer {
+    pub fn builder() -> MockSseServerBuilder {
+        MockSseServerBuilder::default()
+    }
+
+    /// Address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// URL of the given path, e.g. `/api/v1/mev-share`. Any path is served.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+
+    /// Requests received so far.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockSseServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(
+    listener: TcpListener,
+    connections: Vec<Vec<MockStep>>,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+) {
+    // Aborting the server drops the set, which aborts all connections.
+    let mut tasks = JoinSet::new();
+    let mut connections = connections.into_iter();
+    while let Ok((socket, _)) = listener.accept().await {
+        let steps = connections.next();
+        let requests = Arc::clone(&requests);
+        tasks.spawn(async move {
+            if let Err(err) = handle(socket, steps, requests).await {
+                tracing::debug!(%err, "mock SSE connection failed");
+            }
+        });
+    }
+}
+
+async fn handle(
+    mut socket: TcpStream,
+    steps: Option<Vec<MockStep>>,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+) -> io::Result<()> {
+    let mut request = vec![];
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    requests.lock().unwrap().push(MockRequest::parse(
+        &String::from_utf8_lossy(&request),
+    ));
+
+    let Some(steps) = steps else {
+        return socket.write_all(UNAVAILABLE).await;
+    };
+    socket.write_all(RESPONSE_HEADERS).await?;
+    for step in steps {
+        match step {
+            MockStep::Delay(duration) => tokio::time::sleep(duration).await,
+            MockStep::Hang => std::future::pending::<()>().await,
+            MockStep::Disconnect => break,
+            step => {
+                if let Some(data) = step.encode() {
+                    socket.write_all(data.as_bytes()).await?;
+                }
+            }
+        }
+    }
+    socket.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_steps() {
+        let event = serde_json::json!({ "hash": "0x01" });
+        assert_eq!(
+            MockStep::event_with_id("7", &event).encode().unwrap(),
+            "id: 7\ndata: {\"hash\":\"0x01\"}\n\n"
+        );
+        assert_eq!(
+            MockStep::Retry(Duration::from_millis(1500))
+                .encode()
+                .unwrap(),
+            "retry: 1500\n\n"
+        );
+        assert_eq!(MockStep::Disconnect.encode(), None);
+    }
+}
@@ -0,0 +1,141 @@
+//! Hooks for observing SSE streams, e.g. to export metrics.
+
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicU8, AtomicU64, Ordering},
+    },
+};
+
+/// State of the connection of an [EventStream](crate::client::EventStream).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Connected and receiving events.
+    Connected,
+    /// Disconnected, waiting to reconnect.
+    Reconnecting,
+    /// Stream has finished and won't reconnect.
+    Closed,
+}
+
+/// Receives notifications about the activity of SSE streams.
+///
+/// All methods do nothing by default.
+/// See [EventClient::with_observer](crate::EventClient::with_observer).
+pub trait SseObserver: fmt::Debug + Send + Sync {
+    /// Called for every chunk of bytes read from the connection.
+    fn on_bytes(&self, _endpoint: &str, _bytes: usize) {}
+
+    /// Called for every received event, which has been deserialized.
+    fn on_event(&self, _endpoint: &str) {}
+
+    /// Called when an event couldn't be deserialized.
+    fn on_decode_error(&self, _endpoint: &str) {}
+
+    /// Called for every reconnection attempt, `retry` is the total number
+    /// of retries of the stream.
+    fn on_reconnect(&self, _endpoint: &str, _retry: u64) {}
+
+    /// Called when the connection state changes.
+    fn on_state_change(&self, _endpoint: &str, _state: ConnectionState) {}
+
+    /// Called when a buffered event has been dropped, because the consumer
+    /// is lagging, see [LagPolicy](crate::buffer::LagPolicy).
+    fn on_dropped(&self, _endpoint: &str) {}
+}
+
+/// Counters of a single [EventStream](crate::client::EventStream).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SseStats {
+    pub events: u64,
+    pub bytes: u64,
+    pub decode_errors: u64,
+    pub reconnects: u64,
+    /// Events dropped from the buffer because the consumer is lagging.
+    pub dropped: u64,
+    pub state: ConnectionState,
+}
+
+/// [SseObserver] keeping [SseStats] of a stream.
+#[derive(Debug, Default)]
+pub(crate) struct StatsObserver {
+    events: AtomicU64,
+    bytes: AtomicU64,
+    decode_errors: AtomicU64,
+    reconnects: AtomicU64,
+    dropped: AtomicU64,
+    state: AtomicU8,
+}
+
+impl StatsObserver {
+    pub(crate) fn stats(&self) -> SseStats {
+        SseStats {
+            events: self.events.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            decode_errors: self.decode_errors.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            state: match self.state.load(Ordering::Relaxed) {
+                0 => ConnectionState::Connected,
+                1 => ConnectionState::Reconnecting,
+                _ => ConnectionState::Closed,
+            },
+        }
+    }
+}
+
+impl SseObserver for StatsObserver {
+    fn on_bytes(&self, _endpoint: &str, bytes: usize) {
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn on_event(&self, _endpoint: &str) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_decode_error(&self, _endpoint: &str) {
+        self.decode_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_reconnect(&self, _endpoint: &str, _retry: u64) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_state_change(&self, _endpoint: &str, state: ConnectionState) {
+        let state = match state {
+            ConnectionState::Connected => 0,
+            ConnectionState::Reconnecting => 1,
+            ConnectionState::Closed => 2,
+        };
+        self.state.store(state, Ordering::Relaxed);
+    }
+
+    fn on_dropped(&self, _endpoint: &str) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Observers notified about a single stream: its own [StatsObserver] and
+/// the one configured on the client, if any.
+#[derive(Debug, Clone)]
+pub(crate) struct Observers {
+    pub(crate) stats: Arc<StatsObserver>,
+    pub(crate) client: Option<Arc<dyn SseObserver>>,
+}
+
+impl Observers {
+    pub(crate) fn new(client: Option<Arc<dyn SseObserver>>) -> Self {
+        Self {
+            stats: Arc::default(),
+            client,
+        }
+    }
+
+    pub(crate) fn notify(&self, f: impl Fn(&dyn SseObserver)) {
+        f(self.stats.as_ref());
+        if let Some(observer) = &self.client {
+            f(observer.as_ref());
+        }
+    }
+}
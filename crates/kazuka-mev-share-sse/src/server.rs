@@ -1 +1,192 @@
+//! SSE server streaming MEV-Share [events](Event) to
+//! [EventClient](crate::EventClient)s.
+//!
+//! Events published through an [EventPublisher] are sent to every connected
+//! client, regardless of the requested path:
+//!
+//! ```no_run
+//! # async fn run(event: kazuka_mev_share_sse::Event) -> std::io::Result<()> {
+//! use kazuka_mev_share_sse::server::{EventPublisher, EventServer};
+//!
+//! let publisher = EventPublisher::default();
+//! let server = EventServer::bind("127.0.0.1:0", publisher.clone()).await?;
+//! let endpoint = server.url("/api/v1/mev-share");
+//! publisher.publish(event);
+//! # Ok(())
+//! # }
+//! ```
 
+use std::{io, net::SocketAddr, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::broadcast::{self, error::RecvError},
+    task::{JoinHandle, JoinSet},
+    time::{self, MissedTickBehavior},
+};
+
+use crate::Event;
+
+const RESPONSE_HEADERS: &[u8] = b"HTTP/1.1 200 OK\r\n\
+    content-type: text/event-stream\r\n\
+    cache-control: no-cache\r\n\
+    connection: close\r\n\r\n";
+
+/// Number of events buffered for slow clients, before they start missing
+/// events.
+const DEFAULT_CAPACITY: usize = 1024;
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Publishes events to all clients connected to an [EventServer].
+#[derive(Debug, Clone)]
+pub struct EventPublisher {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventPublisher {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl EventPublisher {
+    /// Creates a publisher buffering up to `capacity` events per client.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Sends the event to all connected clients, returns their number.
+    pub fn publish(&self, event: Event) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Receives published events without going through SSE.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+/// HTTP server streaming published events as SSE. Stops when dropped.
+#[derive(Debug)]
+pub struct EventServer {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl EventServer {
+    /// Binds to the given address and starts serving the events of the
+    /// publisher.
+    pub async fn bind(
+        addr: impl ToSocketAddrs,
+        publisher: EventPublisher,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let task = tokio::spawn(serve(listener, publisher));
+        Ok(Self { addr, task })
+    }
+
+    /// Address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// URL of the given path, e.g. `/api/v1/mev-share`. Any path is served.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+}
+
+impl Drop for EventServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(listener: TcpListener, publisher: EventPublisher) {
+    // Aborting the server drops the set, which aborts all connections.
+    let mut tasks = JoinSet::new();
+    while let Ok((socket, addr)) = listener.accept().await {
+        let events = publisher.subscribe();
+        tasks.spawn(async move {
+            if let Err(err) = handle(socket, events).await {
+                tracing::debug!(%addr, %err, "SSE connection closed");
+            }
+        });
+    }
+}
+
+async fn handle(
+    mut socket: TcpStream,
+    mut events: broadcast::Receiver<Event>,
+) -> io::Result<()> {
+    let mut request = vec![];
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    socket.write_all(RESPONSE_HEADERS).await?;
+
+    let mut keep_alive = time::interval(KEEP_ALIVE_INTERVAL);
+    keep_alive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        let data = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => format!(
+                    "data: {}\n\n",
+                    serde_json::to_string(&event)
+                        .expect("Serialization failed")
+                ),
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "SSE client is lagging behind");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = keep_alive.tick() => ": keep-alive\n\n".to_string(),
+        };
+        socket.write_all(data.as_bytes()).await?;
+    }
+    socket.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::EventClient;
+
+    #[tokio::test]
+    async fn test_stream_published_events() {
+        let publisher = EventPublisher::default();
+        let server = EventServer::bind("127.0.0.1:0", publisher.clone())
+            .await
+            .unwrap();
+        let mut stream = EventClient::default()
+            .events(&server.url("/"))
+            .await
+            .unwrap();
+
+        let event = Event {
+            hash: B256::repeat_byte(1),
+            logs: vec![],
+            transactions: vec![],
+        };
+        // Clients are subscribed before the response headers are sent.
+        assert_eq!(publisher.publish(event.clone()), 1);
+
+        assert_eq!(
+            stream.next().await.unwrap().unwrap(),
+            event
+        );
+    }
+}
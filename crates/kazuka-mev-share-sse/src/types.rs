@@ -28,24 +28,64 @@ use alloy::{
     rpc::types::mev::mevshare::{EventTransactionLog, FunctionSelector},
 };
 use num_traits::Num;
-use serde::{Deserialize, Deserializer, Serialize, de::Error};
+use serde::{
+    Deserialize, Deserializer, Serialize,
+    de::{DeserializeOwned, Error},
+};
 
 /// SSE event from the MEV-share endpoint.
 /// See: https://docs.flashbots.net/flashbots-mev-share/searchers/event-stream#event-scheme
+///
+/// Unknown fields are ignored, and logs and transactions, which can't be
+/// decoded (e.g. new hint types), are skipped, so that schema additions
+/// don't break the stream.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Event {
     /// Transaction or bundle hash.
     pub hash: TxHash,
     /// Event logs emitted by executing the transaction.
-    #[serde(with = "null_sequence")]
+    #[serde(default, with = "lenient_sequence")]
     pub logs: Vec<EventTransactionLog>,
 
     /// Transactions from the event. If the event itself is a transaction, txs
     /// will only have one entry. Bundle events may have more.
-    #[serde(rename = "txs", with = "null_sequence")]
+    #[serde(default, rename = "txs", with = "lenient_sequence")]
     pub transactions: Vec<EventTransaction>,
 }
 
+/// Undecoded SSE event along with its best-effort decoding, see
+/// [EventClient::subscribe_raw](crate::EventClient::subscribe_raw).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawEvent<T = Event> {
+    /// Event data as received.
+    pub value: serde_json::Value,
+    /// Event decoded from the data, `None` if it doesn't match `T`.
+    pub event: Option<T>,
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for RawEvent<T> {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let event = T::deserialize(&value).ok();
+        Ok(Self { value, event })
+    }
+}
+
+/// Past event returned by the `history` endpoint, with its hint decoded as
+/// a regular [Event].
+/// See: https://docs.flashbots.net/flashbots-mev-share/searchers/event-stream#event-history
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct HistoricalEvent {
+    /// Block number of the event's block.
+    pub block: u64,
+    /// Timestamp when the event was emitted.
+    pub timestamp: u64,
+    /// Event, as it was broadcast via the SSE event stream.
+    pub hint: Event,
+}
+
 /// Transaction from the MEV-share event.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct EventTransaction {
@@ -114,8 +154,9 @@ pub struct AccessListEntry {
     pub storage_keys: Vec<U256>,
 }
 
-/// Deserializes missing or null sequences as empty vectors.
-mod null_sequence {
+/// Deserializes missing or null sequences as empty vectors, skipping
+/// elements, which can't be decoded.
+mod lenient_sequence {
     use serde::{
         Deserialize, Deserializer, Serialize, Serializer, de::DeserializeOwned,
     };
@@ -127,8 +168,19 @@ mod null_sequence {
         D: Deserializer<'de>,
         T: DeserializeOwned,
     {
-        let s =
-            Option::<Vec<T>>::deserialize(deserializer)?.unwrap_or_default();
+        let values =
+            Option::<Vec<serde_json::Value>>::deserialize(deserializer)?
+                .unwrap_or_default();
+        let s = values
+            .into_iter()
+            .filter_map(|value| match T::deserialize(value) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    tracing::debug!(%err, "skipping undecodable element");
+                    None
+                }
+            })
+            .collect();
         Ok(s)
     }
 
@@ -1,6 +1,23 @@
-use alloy::primitives::{U256, address, b256, bytes};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use alloy::{
+    primitives::{U256, address, b256, bytes},
+    rpc::types::mev::mevshare::EventHistoryParams,
+};
 use futures_util::StreamExt;
-use kazuka_mev_share_sse::{Event, EventClient, EventTransaction};
+use http::{HeaderName, HeaderValue};
+use kazuka_mev_share_sse::{
+    Backoff, ConnectionState, Event, EventClient, EventFilter,
+    EventTransaction, HistoryStart, LagPolicy, SseObserver,
+    client::SseError,
+    mock::{MockSseServer, MockStep},
+};
 #[cfg(test)]
 use pretty_assertions::assert_eq;
 use serde_json::json;
@@ -9,7 +26,7 @@ use tracing_subscriber::{
 };
 use wiremock::{
     Mock, MockServer, ResponseTemplate,
-    matchers::{method, path},
+    matchers::{header, method, path, query_param},
 };
 
 const DEFAULT_FILTER_LEVEL: &str = "trace";
@@ -277,3 +294,935 @@ async fn test_subscribe_mev_events_complex() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_historical_events() -> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let history = json!([{
+        "block": 18000000,
+        "timestamp": 1692900000,
+        "hint": {
+            "txs": null,
+            "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+            "logs": null,
+            "gasUsed": "0x5208",
+            "mevGasPrice": "0x3b9aca00"
+        }
+    }]);
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/history"))
+        .and(query_param("blockStart", "18000000"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(history))
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/api/v1/history", mock_server.uri());
+    let params = EventHistoryParams {
+        block_start: Some(18000000),
+        ..Default::default()
+    };
+    let events = EventClient::default()
+        .historical_events(&endpoint, params)
+        .await?;
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].block, 18000000);
+    assert_eq!(
+        events[0].hint.hash,
+        b256!(
+            "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05"
+        )
+    );
+    assert!(events[0].hint.transactions.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reconnects_after_stream_ends() -> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let event = json!({
+        "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        "logs": null,
+        "txs": null
+    });
+
+    // Every connection yields a single event and terminates.
+    Mock::given(method("GET"))
+        .and(path("/mev-share/events"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(format!("data: {event}\n\n")),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/mev-share/events", mock_server.uri());
+    let client = EventClient::default().with_backoff(Backoff {
+        initial_delay: Duration::from_millis(10),
+        ..Default::default()
+    });
+    let events: Vec<_> =
+        client.events(&endpoint).await?.take(3).collect().await;
+
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().all(Result::is_ok));
+    assert_eq!(
+        mock_server.received_requests().await.unwrap().len(),
+        3
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_backoff_delay() {
+    let backoff = Backoff {
+        initial_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(5),
+        multiplier: 2.0,
+        jitter: 0.5,
+        max_elapsed_time: None,
+    };
+    assert_eq!(
+        backoff.base_delay(0),
+        Duration::from_secs(1)
+    );
+    assert_eq!(
+        backoff.base_delay(2),
+        Duration::from_secs(4)
+    );
+    assert_eq!(
+        backoff.base_delay(10),
+        Duration::from_secs(5)
+    );
+
+    let delay = backoff.delay(1);
+    assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(3));
+}
+
+#[tokio::test]
+async fn test_query_is_kept_across_reconnects() -> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let event = json!({
+        "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        "logs": null,
+        "txs": null
+    });
+
+    Mock::given(method("GET"))
+        .and(path("/mev-share/events"))
+        .and(query_param("txs", "true"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(format!("data: {event}\n\n")),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/mev-share/events", mock_server.uri());
+    let client = EventClient::default().with_backoff(Backoff {
+        initial_delay: Duration::from_millis(10),
+        ..Default::default()
+    });
+    let stream = client
+        .subscribe_with_query::<Event, _>(&endpoint, json!({ "txs": true }))
+        .await?;
+    assert_eq!(
+        stream.query(),
+        Some(&json!({ "txs": true }))
+    );
+
+    let events: Vec<_> = stream.take(2).collect().await;
+    assert!(events.iter().all(Result::is_ok));
+
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(
+        requests
+            .iter()
+            .all(|request| request.url.query() == Some("txs=true"))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_headers_are_sent_on_reconnect() -> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let event = json!({
+        "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        "logs": null,
+        "txs": null
+    });
+
+    Mock::given(method("GET"))
+        .and(path("/mev-share/events"))
+        .and(header("authorization", "Bearer secret"))
+        .and(header("x-api-key", "key"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(format!("data: {event}\n\n")),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/mev-share/events", mock_server.uri());
+    let client = EventClient::default()
+        .with_backoff(Backoff {
+            initial_delay: Duration::from_millis(10),
+            ..Default::default()
+        })
+        .with_bearer_auth("secret")
+        .with_header(
+            HeaderName::from_static("x-api-key"),
+            HeaderValue::from_static("key"),
+        );
+    let events: Vec<_> =
+        client.events(&endpoint).await?.take(2).collect().await;
+    assert!(events.iter().all(Result::is_ok));
+    assert_eq!(
+        mock_server.received_requests().await.unwrap().len(),
+        2
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_last_event_id_is_sent_on_reconnect() -> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let event = json!({
+        "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        "logs": null,
+        "txs": null
+    });
+
+    Mock::given(method("GET"))
+        .and(path("/mev-share/events"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(format!("id: 42\ndata: {event}\n\n")),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/mev-share/events", mock_server.uri());
+    let client = EventClient::default().with_backoff(Backoff {
+        initial_delay: Duration::from_millis(10),
+        ..Default::default()
+    });
+    let mut stream = client.events(&endpoint).await?;
+    assert_eq!(stream.last_event_id(), None);
+
+    stream.next().await.unwrap()?;
+    assert_eq!(stream.last_event_id(), Some("42"));
+    stream.next().await.unwrap()?;
+
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].headers.get("last-event-id").is_none());
+    assert_eq!(
+        requests[1].headers.get("last-event-id").unwrap(),
+        "42"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stale_connection_is_reconnected() -> anyhow::Result<()> {
+    init_tracing();
+
+    // Responds with SSE headers and then stays silent, like a half-dead
+    // connection.
+    let server = MockSseServer::builder()
+        .connection([MockStep::Hang])
+        .connection([MockStep::Hang])
+        .start()
+        .await?;
+    let endpoint = server.url("/events");
+
+    let client =
+        EventClient::default().with_idle_timeout(Duration::from_millis(100));
+    let mut stream = client.events(&endpoint).await?;
+
+    let error = stream.next().await.unwrap().unwrap_err();
+    assert!(matches!(
+        error,
+        SseError::IdleTimeout(_)
+    ));
+    assert!(stream.is_reconnecting());
+
+    // The reconnected stream goes stale again.
+    let error = stream.next().await.unwrap().unwrap_err();
+    assert!(matches!(
+        error,
+        SseError::IdleTimeout(_)
+    ));
+    assert_eq!(server.requests().len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_server_scripts_connections() -> anyhow::Result<()> {
+    init_tracing();
+
+    let event = json!({
+        "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        "logs": null,
+        "txs": null
+    });
+    let server = MockSseServer::builder()
+        .connection([
+            MockStep::event_with_id("1", &event),
+            MockStep::Disconnect,
+        ])
+        .connection([
+            MockStep::malformed("{"),
+            MockStep::Comment("keep-alive".to_string()),
+            MockStep::event(&event),
+        ])
+        .start()
+        .await?;
+
+    let client = EventClient::default().with_backoff(Backoff {
+        initial_delay: Duration::from_millis(10),
+        ..Default::default()
+    });
+    let items: Vec<_> = client
+        .events(&server.url("/events"))
+        .await?
+        .take(3)
+        .collect()
+        .await;
+    assert!(items[0].is_ok());
+    assert!(matches!(
+        items[1],
+        Err(SseError::SerdeJsonError(_))
+    ));
+    assert!(items[2].is_ok());
+
+    let requests = server.requests();
+    assert_eq!(requests[0].target, "/events");
+    assert_eq!(
+        requests[1].header("last-event-id"),
+        Some("1")
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_buffered_stream_lag_policies() -> anyhow::Result<()> {
+    init_tracing();
+
+    let event = |hash: &str| {
+        MockStep::event(&json!({
+            "hash": hash,
+            "logs": null,
+            "txs": null
+        }))
+    };
+    let script = [
+        event(
+            "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        ),
+        event(
+            "0x1dbb30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd06",
+        ),
+        event(
+            "0x2dbb30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd07",
+        ),
+    ];
+    let server = MockSseServer::builder()
+        .connection(script.clone())
+        .connection(script)
+        .start()
+        .await?;
+    let endpoint = server.url("/events");
+    let client = EventClient::default();
+
+    // The consumer only starts reading after all events have been received.
+    let stream = client
+        .events(&endpoint)
+        .await?
+        .buffered(1, LagPolicy::DropOldest);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(stream.stats().dropped, 2);
+    let events: Vec<_> = stream.collect().await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].as_ref().unwrap().hash,
+        b256!(
+            "0x2dbb30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd07"
+        )
+    );
+
+    let stream = client
+        .events(&endpoint)
+        .await?
+        .buffered(1, LagPolicy::Backpressure);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(stream.len(), 1);
+    let events: Vec<_> = stream.collect().await;
+    assert_eq!(events.len(), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failover_deduplicates_events() -> anyhow::Result<()> {
+    init_tracing();
+
+    let event = |hash: &str| {
+        json!({
+            "hash": hash,
+            "logs": null,
+            "txs": null
+        })
+    };
+    let first = event(
+        "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+    );
+    let second = event(
+        "0x1dbb30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd06",
+    );
+
+    // The primary endpoint terminates after the first event, the mirror
+    // has seen it as well.
+    let primary = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(format!("data: {first}\n\n")),
+        )
+        .mount(&primary)
+        .await;
+    let mirror = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(format!(
+                    "data: {first}\n\ndata: {second}\n\n"
+                )),
+        )
+        .mount(&mirror)
+        .await;
+
+    let endpoints = vec![
+        "http://127.0.0.1:1".to_string(),
+        primary.uri(),
+        mirror.uri(),
+    ];
+    let events: Vec<_> = EventClient::default()
+        .events_with_failover(endpoints)
+        .await?
+        .take(2)
+        .collect()
+        .await;
+
+    let hashes: Vec<_> = events
+        .into_iter()
+        .map(|event| event.unwrap().hash)
+        .collect();
+    assert_eq!(
+        hashes,
+        vec![
+            b256!(
+                "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05"
+            ),
+            b256!(
+                "0x1dbb30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd06"
+            ),
+        ]
+    );
+    assert_eq!(
+        primary.received_requests().await.unwrap().len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failover_on_unreachable_endpoint() -> anyhow::Result<()> {
+    init_tracing();
+
+    let first_hash = b256!(
+        "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05"
+    );
+    let second_hash = b256!(
+        "0x1dbb30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd06"
+    );
+    let event = |hash| json!({ "hash": hash, "logs": null, "txs": null });
+
+    let primary = MockSseServer::builder()
+        .connection([MockStep::event(&event(first_hash)), MockStep::Hang])
+        .start()
+        .await?;
+    let mirror = MockSseServer::builder()
+        .connection([
+            MockStep::event(&event(first_hash)),
+            MockStep::event(&event(second_hash)),
+            MockStep::Hang,
+        ])
+        .start()
+        .await?;
+
+    let client = EventClient::default()
+        .with_backoff(Backoff {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(10),
+            multiplier: 1.0,
+            jitter: 0.0,
+            max_elapsed_time: None,
+        })
+        .with_failover_errors(2);
+    let mut stream = client
+        .events_with_failover(vec![
+            primary.url("/events"),
+            mirror.url("/events"),
+        ])
+        .await?;
+    assert_eq!(
+        stream.next().await.unwrap()?.hash,
+        first_hash
+    );
+
+    // The primary goes down and refuses connections, while the stream of
+    // the endpoint keeps reconnecting to it.
+    drop(primary);
+    let mut errors = 0;
+    let event = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match stream.next().await.unwrap() {
+                Ok(event) => return event,
+                Err(error) => {
+                    assert!(matches!(
+                        error,
+                        SseError::RetryError(_) | SseError::Http(_)
+                    ));
+                    errors += 1;
+                }
+            }
+        }
+    })
+    .await?;
+
+    assert_eq!(event.hash, second_hash);
+    assert_eq!(errors, 2);
+    assert_eq!(mirror.requests().len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_filtered_events_are_dropped() -> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let transfer = json!({
+        "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        "logs": null,
+        "txs": [{ "to": "0x57e114b691db790c35207b2e685d4a43181e6061" }]
+    });
+    let other = json!({
+        "hash": "0x1dbb30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd06",
+        "logs": null,
+        "txs": [{ "to": "0x0000000000000000000000000000000000000001" }]
+    });
+
+    Mock::given(method("GET"))
+        .and(path("/mev-share/events"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(format!(
+                    "data: {other}\n\ndata: {transfer}\n\ndata: {other}\n\n"
+                )),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/mev-share/events", mock_server.uri());
+    let filter = EventFilter::new().with_to_addresses([address!(
+        "0x57e114b691db790c35207b2e685d4a43181e6061"
+    )]);
+    let events: Vec<_> = EventClient::default()
+        .with_filter(filter)
+        .events(&endpoint)
+        .await?
+        .collect()
+        .await;
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].as_ref().unwrap().hash,
+        b256!(
+            "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05"
+        )
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_raw_events_tolerate_unknown_payloads() -> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let event = json!({
+        "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        "logs": [{ "kind": "new-hint" }],
+        "txs": [{ "to": "0x57e114b691db790c35207b2e685d4a43181e6061" }],
+        "newField": 1
+    });
+    let unknown = json!({ "type": "announcement" });
+
+    Mock::given(method("GET"))
+        .and(path("/mev-share/events"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(format!(
+                    "data: {event}\n\ndata: {unknown}\n\n"
+                )),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/mev-share/events", mock_server.uri());
+    let events: Vec<_> = EventClient::default()
+        .subscribe_raw::<Event>(&endpoint)
+        .await?
+        .collect()
+        .await;
+    assert_eq!(events.len(), 2);
+
+    let raw = events[0].as_ref().unwrap();
+    assert_eq!(raw.value, event);
+    let decoded = raw.event.as_ref().unwrap();
+    assert!(decoded.logs.is_empty());
+    assert_eq!(
+        decoded.transactions[0].to,
+        Some(address!(
+            "0x57e114b691db790c35207b2e685d4a43181e6061"
+        ))
+    );
+
+    let raw = events[1].as_ref().unwrap();
+    assert_eq!(raw.value, unknown);
+    assert_eq!(raw.event, None);
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct CountingObserver {
+    events: AtomicUsize,
+    decode_errors: AtomicUsize,
+}
+
+impl SseObserver for CountingObserver {
+    fn on_event(&self, _endpoint: &str) {
+        self.events.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_decode_error(&self, _endpoint: &str) {
+        self.decode_errors.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn test_stream_stats_and_observer() -> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let event = json!({
+        "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        "logs": null,
+        "txs": null
+    });
+    let body = format!("data: {event}\n\ndata: not json\n\ndata: {event}\n\n");
+
+    Mock::given(method("GET"))
+        .and(path("/mev-share/events"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(body.clone()),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/mev-share/events", mock_server.uri());
+    let observer = Arc::new(CountingObserver::default());
+    let mut stream = EventClient::default()
+        .with_observer(observer.clone())
+        .events(&endpoint)
+        .await?;
+    assert_eq!(
+        stream.stats().state,
+        ConnectionState::Connected
+    );
+
+    let items: Vec<_> = stream.by_ref().collect().await;
+    assert_eq!(items.len(), 3);
+
+    let stats = stream.stats();
+    assert_eq!(stats.events, 2);
+    assert_eq!(stats.decode_errors, 1);
+    assert_eq!(stats.bytes, body.len() as u64);
+    assert_eq!(stats.reconnects, 0);
+    assert_eq!(stats.state, ConnectionState::Closed);
+
+    assert_eq!(
+        observer.events.load(Ordering::SeqCst),
+        2
+    );
+    assert_eq!(
+        observer.decode_errors.load(Ordering::SeqCst),
+        1
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_event_history_stream_paginates() -> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let event = |block: u64| {
+        json!({
+            "block": block,
+            "timestamp": 1692900000,
+            "hint": {
+                "txs": null,
+                "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+                "logs": null
+            }
+        })
+    };
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/history/info"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({
+                "count": 3,
+                "minBlock": 18000000,
+                "maxBlock": 18000002,
+                "minTimestamp": 1692900000,
+                "maxTimestamp": 1692900000,
+                "maxLimit": 2
+            })),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/history"))
+        .and(query_param("limit", "2"))
+        .and(query_param("offset", "0"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!([
+                event(18000000),
+                event(18000001)
+            ])),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/history"))
+        .and(query_param("limit", "2"))
+        .and(query_param("offset", "2"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!([event(18000002)])),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/api/v1/history", mock_server.uri());
+    let events: Vec<_> = EventClient::default()
+        .with_history_rate_limit(Duration::from_millis(10))
+        .event_history_stream(&endpoint, EventHistoryParams::default())
+        .collect()
+        .await;
+
+    let blocks = events
+        .into_iter()
+        .map(|event| event.map(|event| event.block))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        blocks,
+        vec![18000000, 18000001, 18000002]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_event_history_stream_fails_on_error_status() -> anyhow::Result<()>
+{
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/history/info"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({
+                "count": 1,
+                "minBlock": 18000000,
+                "maxBlock": 18000000,
+                "minTimestamp": 1692900000,
+                "maxTimestamp": 1692900000,
+                "maxLimit": 2
+            })),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/history"))
+        .respond_with(
+            ResponseTemplate::new(503).set_body_string("upstream timeout"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = format!("{}/api/v1/history", mock_server.uri());
+    let events: Vec<_> = EventClient::default()
+        .event_history_stream(&endpoint, EventHistoryParams::default())
+        .collect()
+        .await;
+
+    assert_eq!(events.len(), 1);
+    let error = events.into_iter().next().unwrap().unwrap_err();
+    assert_eq!(
+        error.status(),
+        Some(http::StatusCode::SERVICE_UNAVAILABLE)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_events_since_replays_history_without_duplicates()
+-> anyhow::Result<()> {
+    init_tracing();
+
+    let mock_server = MockServer::start().await;
+
+    let replayed = json!({
+        "hash": "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05",
+        "logs": null,
+        "txs": null
+    });
+    let live = json!({
+        "hash": "0x1dbb30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd06",
+        "logs": null,
+        "txs": null
+    });
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/history/info"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({
+                "count": 1,
+                "minBlock": 18000000,
+                "maxBlock": 18000000,
+                "minTimestamp": 1692900000,
+                "maxTimestamp": 1692900000,
+                "maxLimit": 500
+            })),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/history"))
+        .and(query_param("blockStart", "18000000"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!([{
+                "block": 18000000,
+                "timestamp": 1692900000,
+                "hint": replayed
+            }])),
+        )
+        .mount(&mock_server)
+        .await;
+    // The live stream overlaps with the history.
+    Mock::given(method("GET"))
+        .and(path("/events"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(format!(
+                    "data: {replayed}\n\ndata: {live}\n\n"
+                )),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let events: Vec<_> = EventClient::default()
+        .events_since(
+            &format!("{}/events", mock_server.uri()),
+            &format!("{}/api/v1/history", mock_server.uri()),
+            HistoryStart::Block(18000000),
+        )
+        .await?
+        .collect()
+        .await;
+
+    let hashes = events
+        .into_iter()
+        .map(|event| {
+            event.map(|event| (event.hint().hash, event.is_replayed()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        hashes,
+        vec![
+            (
+                b256!(
+                    "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05"
+                ),
+                true
+            ),
+            (
+                b256!(
+                    "0x1dbb30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd06"
+                ),
+                false
+            ),
+        ]
+    );
+
+    Ok(())
+}
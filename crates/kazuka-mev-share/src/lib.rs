@@ -6,3 +6,5 @@ pub use kazuka_mev_share_backend as backend;
 pub use kazuka_mev_share_rpc_api as rpc;
 #[doc(inline)]
 pub use kazuka_mev_share_sse as sse;
+
+pub mod ws;
@@ -0,0 +1,329 @@
+//! MEV-Share events over WebSocket, for providers exposing hints over WS
+//! instead of SSE.
+//!
+//! Yields the same [Event] type as the [SSE client](crate::sse::EventClient).
+
+use std::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::{
+    SinkExt, Stream, StreamExt,
+    future::{self, BoxFuture},
+};
+use serde::de::DeserializeOwned;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async, tungstenite,
+    tungstenite::Message,
+};
+
+use crate::sse::Event;
+
+/// Default delay before reconnecting after the connection has been closed.
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// The client for MEV-Share events over WebSocket.
+#[derive(Debug, Clone)]
+pub struct WsEventClient {
+    max_retries: Option<u64>,
+    reconnect_delay: Duration,
+    subscribe_message: Option<serde_json::Value>,
+}
+
+impl Default for WsEventClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WsEventClient {
+    pub fn new() -> Self {
+        Self {
+            max_retries: None,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            subscribe_message: None,
+        }
+    }
+
+    /// Sets the maximum number of reconnection attempts.
+    pub fn with_max_retries(mut self, max_retries: u64) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Sets the delay before reconnecting after the connection has been
+    /// closed.
+    pub fn with_reconnect_delay(mut self, reconnect_delay: Duration) -> Self {
+        self.reconnect_delay = reconnect_delay;
+        self
+    }
+
+    /// Sends the message after every (re-)connection, e.g. a JSON-RPC
+    /// `eth_subscribe` request, if the provider requires an explicit
+    /// subscription.
+    pub fn with_subscribe_message(
+        mut self,
+        message: serde_json::Value,
+    ) -> Self {
+        self.subscribe_message = Some(message);
+        self
+    }
+
+    /// Subscribe to the WebSocket endpoint.
+    ///
+    /// This connects to the endpoint and returns a stream of `T` items.
+    pub async fn subscribe<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+    ) -> Result<WsEventStream<T>, WsError> {
+        let socket = connect(self.clone(), endpoint.to_string()).await?;
+        Ok(WsEventStream {
+            client: self.clone(),
+            endpoint: endpoint.to_string(),
+            num_retries: 0,
+            state: Some(State::Connected(Box::new(socket))),
+            _event: PhantomData,
+        })
+    }
+
+    /// Subscribe to a stream of [Event]s.
+    /// This is a convenience function for [WsEventClient::subscribe].
+    pub async fn events(
+        &self,
+        endpoint: &str,
+    ) -> Result<WsEventStream<Event>, WsError> {
+        self.subscribe(endpoint).await
+    }
+}
+
+async fn connect(
+    client: WsEventClient,
+    endpoint: String,
+) -> Result<WsStream, WsError> {
+    let (mut socket, _) = connect_async(endpoint.as_str()).await?;
+    if let Some(message) = &client.subscribe_message {
+        socket.send(Message::text(message.to_string())).await?;
+    }
+    tracing::debug!(%endpoint, "connected to MEV-Share WebSocket");
+    Ok(socket)
+}
+
+/// Decodes the message, unwrapping JSON-RPC subscription notifications.
+/// Returns `None` for messages, which are not events, such as the response
+/// to the subscription request.
+fn decode<T: DeserializeOwned>(
+    data: &[u8],
+) -> Option<Result<T, serde_json::Error>> {
+    let mut value: serde_json::Value = match serde_json::from_slice(data) {
+        Ok(value) => value,
+        Err(err) => return Some(Err(err)),
+    };
+    if value.get("method").is_some() {
+        value = value.pointer_mut("/params/result")?.take();
+    } else if value.get("id").is_some() && value.get("result").is_some() {
+        return None;
+    }
+    Some(serde_json::from_value(value))
+}
+
+/// A stream of MEV-Share items over WebSocket, which reconnects when the
+/// connection is closed.
+#[must_use = "streams do nothing unless polled"]
+pub struct WsEventStream<T> {
+    client: WsEventClient,
+    endpoint: String,
+    num_retries: u64,
+    state: Option<State>,
+    _event: PhantomData<fn() -> T>,
+}
+
+enum State {
+    /// Stream has finished.
+    End,
+    /// Waiting for the connection to be re-established.
+    Connecting(BoxFuture<'static, Result<WsStream, WsError>>),
+    /// Active, connected stream.
+    Connected(Box<WsStream>),
+}
+
+impl<T> WsEventStream<T> {
+    /// The endpoint this stream is connected to.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Resets all retry attempts.
+    pub fn reset_retries(&mut self) {
+        self.num_retries = 0;
+    }
+
+    /// Returns a future reconnecting after the configured delay, which
+    /// fails if all retries are exhausted.
+    fn reconnect(&mut self) -> State {
+        self.num_retries += 1;
+        if let Some(max_retries) = self.client.max_retries
+            && self.num_retries > max_retries
+        {
+            return State::Connecting(Box::pin(future::ready(Err(
+                WsError::MaxRetriesExceeded(max_retries),
+            ))));
+        }
+        tracing::debug!(
+            retries = self.num_retries,
+            "reconnecting MEV-Share WebSocket"
+        );
+        let client = self.client.clone();
+        let endpoint = self.endpoint.clone();
+        State::Connecting(Box::pin(async move {
+            tokio::time::sleep(client.reconnect_delay).await;
+            connect(client, endpoint).await
+        }))
+    }
+}
+
+impl<T> fmt::Debug for WsEventStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsEventStream")
+            .field("endpoint", &self.endpoint)
+            .field("num_retries", &self.num_retries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: DeserializeOwned> Stream for WsEventStream<T> {
+    type Item = Result<T, WsError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let state = this
+                .state
+                .take()
+                .expect("WsEventStream polled after completion");
+            match state {
+                State::End => {
+                    this.state = Some(State::End);
+                    return Poll::Ready(None);
+                }
+                State::Connecting(mut future) => match future.as_mut().poll(cx)
+                {
+                    Poll::Ready(Ok(socket)) => {
+                        this.state = Some(State::Connected(Box::new(socket)));
+                    }
+                    Poll::Ready(Err(err)) => {
+                        this.state = Some(match err {
+                            WsError::MaxRetriesExceeded(_) => State::End,
+                            _ => this.reconnect(),
+                        });
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                    Poll::Pending => {
+                        this.state = Some(State::Connecting(future));
+                        return Poll::Pending;
+                    }
+                },
+                State::Connected(mut socket) => {
+                    match socket.poll_next_unpin(cx) {
+                        Poll::Ready(Some(Ok(message))) => {
+                            this.state = Some(State::Connected(socket));
+                            let decoded = match &message {
+                                Message::Text(text) => decode(text.as_bytes()),
+                                Message::Binary(data) => decode(data),
+                                Message::Close(_) => {
+                                    tracing::debug!(
+                                        "WebSocket closed, reconnecting"
+                                    );
+                                    this.state = Some(this.reconnect());
+                                    continue;
+                                }
+                                // Pings are answered by the socket.
+                                _ => None,
+                            };
+                            if let Some(decoded) = decoded {
+                                return Poll::Ready(Some(
+                                    decoded.map_err(WsError::SerdeJsonError),
+                                ));
+                            }
+                        }
+                        Poll::Ready(Some(Err(err))) => {
+                            tracing::warn!(
+                                ?err,
+                                "WebSocket error, reconnecting"
+                            );
+                            this.state = Some(this.reconnect());
+                            return Poll::Ready(Some(Err(err.into())));
+                        }
+                        Poll::Ready(None) => {
+                            this.state = Some(this.reconnect());
+                        }
+                        Poll::Pending => {
+                            this.state = Some(State::Connected(socket));
+                            return Poll::Pending;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Error variants that can occur while handling a WebSocket subscription.
+#[derive(Debug, thiserror::Error)]
+pub enum WsError {
+    /// Failed to deserialize the event data.
+    #[error("Failed to deserialize event: {0}")]
+    SerdeJsonError(serde_json::Error),
+    /// WebSocket transport error.
+    #[error("WebSocket error: {0}")]
+    WebSocket(#[from] tungstenite::Error),
+    /// Exceeded all retries.
+    #[error("Exceeded all retries: {0}")]
+    MaxRetriesExceeded(u64),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_subscription_messages() {
+        let hash = "0xabda30c14d8a2e520028117013a68904f28eac159cdb0bca64763e80ba2edd05";
+        let event = serde_json::json!({ "hash": hash });
+
+        let plain = event.to_string();
+        assert_eq!(
+            decode::<Event>(plain.as_bytes()).unwrap().unwrap().hash,
+            hash.parse().unwrap()
+        );
+
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "mev_subscription",
+            "params": { "subscription": "0x1", "result": event }
+        })
+        .to_string();
+        assert_eq!(
+            decode::<Event>(notification.as_bytes())
+                .unwrap()
+                .unwrap()
+                .hash,
+            hash.parse().unwrap()
+        );
+
+        let response =
+            serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": "0x1" })
+                .to_string();
+        assert!(decode::<Event>(response.as_bytes()).is_none());
+        assert!(decode::<Event>(b"not json").unwrap().is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use alloy::{
+    network::AnyNetwork,
+    providers::{DynProvider, ProviderBuilder, WsConnect},
+};
+use alloy_node_bindings::{Anvil, AnvilInstance};
+
+/// Spawns Anvil mining a block every second, and connects to it over
+/// WebSocket. Anvil is killed when the returned instance is dropped.
+pub async fn spawn_anvil() -> (DynProvider<AnyNetwork>, AnvilInstance) {
+    connect(Anvil::new().block_time(1).spawn()).await
+}
+
+/// Spawns Anvil forking the node at the given block, and connects to it
+/// over WebSocket.
+pub async fn spawn_anvil_fork(
+    fork_url: impl Into<String>,
+    block: u64,
+) -> (DynProvider<AnyNetwork>, AnvilInstance) {
+    connect(Anvil::new().fork(fork_url).fork_block_number(block).spawn()).await
+}
+
+async fn connect(
+    anvil: AnvilInstance,
+) -> (DynProvider<AnyNetwork>, AnvilInstance) {
+    let ws = WsConnect::new(anvil.ws_endpoint_url());
+    let provider = ProviderBuilder::new()
+        .network::<AnyNetwork>()
+        .connect_ws(ws)
+        .await
+        .expect("Failed to connect to Anvil");
+    (DynProvider::new(provider), anvil)
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{StreamExt, stream};
+use kazuka_core::{
+    error::KazukaError,
+    types::{EventSource, EventStream},
+};
+
+/// [EventSource] emitting a script of events, each after a delay since the
+/// previous one. Delays are measured with [tokio::time], so tests can skip
+/// them with [tokio::time::pause].
+#[derive(Clone, Debug)]
+pub struct ScriptedEventSource<E> {
+    script: Vec<(Duration, E)>,
+    /// Whether to keep the stream open after the script, like a live source.
+    keep_open: bool,
+}
+
+impl<E> Default for ScriptedEventSource<E> {
+    fn default() -> Self {
+        Self {
+            script: vec![],
+            keep_open: false,
+        }
+    }
+}
+
+impl<E> ScriptedEventSource<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emits the event right after the previous one.
+    pub fn emit(self, event: E) -> Self {
+        self.emit_after(Duration::ZERO, event)
+    }
+
+    /// Emits the event once the delay has passed since the previous one.
+    pub fn emit_after(mut self, delay: Duration, event: E) -> Self {
+        self.script.push((delay, event));
+        self
+    }
+
+    /// Keeps the stream open after the script, instead of ending it, which
+    /// the engine reports as a stopped event source.
+    pub fn keep_open(mut self) -> Self {
+        self.keep_open = true;
+        self
+    }
+}
+
+#[async_trait]
+impl<E> EventSource<E> for ScriptedEventSource<E>
+where
+    E: Clone + Send + Sync + 'static,
+{
+    async fn get_event_stream(
+        &self,
+    ) -> Result<EventStream<'_, E>, KazukaError> {
+        let events = stream::unfold(
+            self.script.clone().into_iter(),
+            |mut script| async move {
+                let (delay, event) = script.next()?;
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+                Some((event, script))
+            },
+        );
+        if self.keep_open {
+            Ok(Box::pin(
+                events.chain(stream::pending()),
+            ))
+        } else {
+            Ok(Box::pin(events))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scripted_event_source() {
+        let source = ScriptedEventSource::new()
+            .emit(1)
+            .emit_after(Duration::from_secs(12), 2);
+
+        let start = Instant::now();
+        let events: Vec<_> =
+            source.get_event_stream().await.unwrap().collect().await;
+        assert_eq!(events, vec![1, 2]);
+        assert_eq!(start.elapsed(), Duration::from_secs(12));
+    }
+}
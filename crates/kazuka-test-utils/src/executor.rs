@@ -0,0 +1,177 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use kazuka_core::{error::KazukaError, types::Executor};
+use tokio::sync::Notify;
+
+/// [Executor] recording the actions it receives into [CapturedActions].
+pub struct CapturingExecutor<A> {
+    actions: CapturedActions<A>,
+}
+
+impl<A> CapturingExecutor<A> {
+    /// Returns the executor along with the handle to its actions.
+    pub fn new() -> (Self, CapturedActions<A>) {
+        let actions = CapturedActions {
+            actions: Arc::new(Mutex::new(vec![])),
+            notify: Arc::new(Notify::new()),
+        };
+        (
+            Self {
+                actions: actions.clone(),
+            },
+            actions,
+        )
+    }
+}
+
+#[async_trait]
+impl<A> Executor<A> for CapturingExecutor<A>
+where
+    A: Send + 'static,
+{
+    async fn execute(&self, action: A) -> Result<(), KazukaError> {
+        self.actions.actions.lock().unwrap().push(action);
+        self.actions.notify.notify_waiters();
+        Ok(())
+    }
+}
+
+/// Actions received by a [CapturingExecutor], with assertions on them.
+pub struct CapturedActions<A> {
+    actions: Arc<Mutex<Vec<A>>>,
+    notify: Arc<Notify>,
+}
+
+impl<A> Clone for CapturedActions<A> {
+    fn clone(&self) -> Self {
+        Self {
+            actions: Arc::clone(&self.actions),
+            notify: Arc::clone(&self.notify),
+        }
+    }
+}
+
+impl<A> CapturedActions<A>
+where
+    A: Clone + Debug + PartialEq,
+{
+    /// Actions received so far.
+    pub fn actions(&self) -> Vec<A> {
+        self.actions.lock().unwrap().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Waits until at least `count` actions have been received, and returns
+    /// them.
+    ///
+    /// # Panics
+    ///
+    /// If fewer actions have been received before the timeout.
+    pub async fn wait_for(&self, count: usize, timeout: Duration) -> Vec<A> {
+        let wait = async {
+            loop {
+                let notified = self.notify.notified();
+                if self.len() >= count {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        if tokio::time::timeout(timeout, wait).await.is_err() {
+            panic!(
+                "expected {count} actions within {timeout:?}, got {:?}",
+                self.actions()
+            );
+        }
+        self.actions()
+    }
+
+    /// Asserts that no action is received within the duration, e.g. after
+    /// an event that shouldn't trigger the strategy.
+    pub async fn assert_none_within(&self, duration: Duration) {
+        let before = self.len();
+        tokio::time::sleep(duration).await;
+        let actions = self.actions();
+        assert_eq!(
+            actions.len(),
+            before,
+            "unexpected actions: {:?}",
+            &actions[before..]
+        );
+    }
+
+    /// Asserts that the actions received so far are the expected ones, in
+    /// order.
+    pub fn assert_eq(&self, expected: &[A]) {
+        assert_eq!(self.actions(), expected);
+    }
+
+    /// Asserts that every action received so far satisfies the predicate.
+    pub fn assert_all(&self, predicate: impl Fn(&A) -> bool) {
+        for action in self.actions() {
+            assert!(
+                predicate(&action),
+                "unexpected action: {action:?}"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kazuka_core::{
+        engine::Engine,
+        types::{Action, Event, Strategy},
+    };
+
+    use super::*;
+    use crate::ScriptedEventSource;
+
+    /// Submits a tx to the mempool for every tx.
+    struct MockStrategy;
+
+    #[async_trait]
+    impl Strategy<Event, Action> for MockStrategy {
+        async fn process_event(&mut self, event: Event) -> Vec<Action> {
+            match event {
+                Event::Transaction => vec![Action::SubmitTxToMempool],
+                Event::NewBlock => vec![],
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_engine_harness() {
+        let (executor, actions) = CapturingExecutor::new();
+        let engine = Engine::new()
+            .add_event_source(Box::new(
+                ScriptedEventSource::new()
+                    .emit(Event::NewBlock)
+                    .emit_after(
+                        Duration::from_millis(10),
+                        Event::Transaction,
+                    )
+                    .keep_open(),
+            ))
+            .add_strategy(Box::new(MockStrategy))
+            .add_executor(Box::new(executor));
+        let mut tasks = engine.run().await.unwrap();
+
+        actions.wait_for(1, Duration::from_secs(1)).await;
+        actions.assert_none_within(Duration::from_millis(20)).await;
+        actions.assert_eq(&[Action::SubmitTxToMempool]);
+        tasks.shutdown().await;
+    }
+}
@@ -0,0 +1,28 @@
+//! Scaffolding of the tests of strategies and engines:
+//! - [ScriptedEventSource] emitting events at given times, which can be
+//!   fast-forwarded with [tokio::time::pause],
+//! - [CapturingExecutor] recording the actions, with assertions on them,
+//! - [spawn_anvil] and [spawn_anvil_fork] fixtures.
+//!
+//! ```ignore
+//! let (executor, actions) = CapturingExecutor::new();
+//! let engine = Engine::new()
+//!     .add_event_source(Box::new(
+//!         ScriptedEventSource::new()
+//!             .emit(Event::NewBlock)
+//!             .emit_after(Duration::from_secs(12), Event::NewBlock),
+//!     ))
+//!     .add_strategy(Box::new(strategy))
+//!     .add_executor(Box::new(executor));
+//! let _tasks = engine.run().await?;
+//! actions.wait_for(1, Duration::from_secs(1)).await;
+//! actions.assert_eq(&[Action::SubmitTxToMempool]);
+//! ```
+
+pub mod anvil;
+pub mod event_source;
+pub mod executor;
+
+pub use anvil::{spawn_anvil, spawn_anvil_fork};
+pub use event_source::ScriptedEventSource;
+pub use executor::{CapturedActions, CapturingExecutor};
@@ -0,0 +1,278 @@
+//! Borrowers of the lending markets, found from the logs of the markets,
+//! with their last known health factors.
+
+use std::collections::HashMap;
+
+use alloy::{
+    primitives::{Address, U256},
+    rpc::types::Log,
+    sol_types::SolEvent,
+};
+
+use crate::markets::{HEALTH_FACTOR_ONE, IAaveV3Pool, IComet, Market};
+
+/// An account of a market, with the assets it supplied and borrowed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Borrower {
+    pub market: Market,
+    pub account: Address,
+    /// Assets supplied as collateral (Aave V3 only).
+    pub collaterals: Vec<Address>,
+    /// Assets borrowed (Aave V3 only).
+    pub debts: Vec<Address>,
+    /// Last known health factor, scaled by 1e18, if fetched yet.
+    pub health_factor: Option<U256>,
+}
+
+impl Borrower {
+    fn new(market: Market, account: Address) -> Self {
+        Self {
+            market,
+            account,
+            collaterals: vec![],
+            debts: vec![],
+            health_factor: None,
+        }
+    }
+
+    /// Whether the account has borrowed anything. Accounts of Compound V3
+    /// markets are only known from their withdrawals, which may be
+    /// borrows.
+    pub fn has_debt(&self) -> bool {
+        match self.market {
+            Market::AaveV3(_) => !self.debts.is_empty(),
+            Market::CompoundV3(_) => true,
+        }
+    }
+
+    pub fn is_liquidatable(&self) -> bool {
+        self.health_factor
+            .is_some_and(|health_factor| health_factor < HEALTH_FACTOR_ONE)
+    }
+}
+
+/// Borrowers by their market and account.
+#[derive(Debug, Default)]
+pub struct BorrowerBook {
+    borrowers: HashMap<(Address, Address), Borrower>,
+}
+
+impl BorrowerBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.borrowers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.borrowers.is_empty()
+    }
+
+    pub fn get(&self, market: Market, account: Address) -> Option<&Borrower> {
+        self.borrowers.get(&(market.address(), account))
+    }
+
+    fn entry(&mut self, market: Market, account: Address) -> &mut Borrower {
+        self.borrowers
+            .entry((market.address(), account))
+            .or_insert_with(|| Borrower::new(market, account))
+    }
+
+    /// Records the account revealed by a log of the market, see
+    /// [Market::borrower_signatures]. Returns whether the log was relevant.
+    pub fn add_log(&mut self, market: Market, log: &Log) -> bool {
+        let Some(&topic0) = log.topic0() else {
+            return false;
+        };
+        match market {
+            Market::AaveV3(_)
+                if topic0 == IAaveV3Pool::Supply::SIGNATURE_HASH =>
+            {
+                let Ok(supply) = log.log_decode::<IAaveV3Pool::Supply>() else {
+                    return false;
+                };
+                let supply = supply.inner.data;
+                push_unique(
+                    &mut self.entry(market, supply.onBehalfOf).collaterals,
+                    supply.reserve,
+                );
+                true
+            }
+            Market::AaveV3(_)
+                if topic0 == IAaveV3Pool::Borrow::SIGNATURE_HASH =>
+            {
+                let Ok(borrow) = log.log_decode::<IAaveV3Pool::Borrow>() else {
+                    return false;
+                };
+                let borrow = borrow.inner.data;
+                push_unique(
+                    &mut self.entry(market, borrow.onBehalfOf).debts,
+                    borrow.reserve,
+                );
+                true
+            }
+            Market::CompoundV3(_)
+                if topic0 == IComet::Withdraw::SIGNATURE_HASH =>
+            {
+                let Ok(withdraw) = log.log_decode::<IComet::Withdraw>() else {
+                    return false;
+                };
+                self.entry(market, withdraw.inner.data.src);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn set_health_factor(
+        &mut self,
+        market: Market,
+        account: Address,
+        health_factor: U256,
+    ) {
+        if let Some(borrower) =
+            self.borrowers.get_mut(&(market.address(), account))
+        {
+            borrower.health_factor = Some(health_factor);
+        }
+    }
+
+    /// Accounts with debt, whose health factors are worth tracking.
+    pub fn borrowers(&self) -> impl Iterator<Item = &Borrower> {
+        self.borrowers
+            .values()
+            .filter(|borrower| borrower.has_debt())
+    }
+
+    /// Borrowers with a health factor below the threshold, riskiest first.
+    /// Borrowers, whose health factor isn't known yet, are included last.
+    pub fn at_risk(&self, threshold: U256) -> Vec<&Borrower> {
+        let mut borrowers: Vec<&Borrower> = self
+            .borrowers()
+            .filter(|borrower| {
+                borrower
+                    .health_factor
+                    .is_none_or(|health_factor| health_factor < threshold)
+            })
+            .collect();
+        borrowers.sort_by_key(|borrower| {
+            borrower.health_factor.unwrap_or(U256::MAX)
+        });
+        borrowers
+    }
+
+    /// Borrowers, which can be liquidated right away.
+    pub fn liquidatable(&self) -> Vec<&Borrower> {
+        self.at_risk(HEALTH_FACTOR_ONE)
+            .into_iter()
+            .filter(|borrower| borrower.is_liquidatable())
+            .collect()
+    }
+}
+
+fn push_unique(assets: &mut Vec<Address>, asset: Address) {
+    if !assets.contains(&asset) {
+        assets.push(asset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markets::{AAVE_V3_POOL, COMET_USDC};
+
+    fn log(market: Market, event: &impl SolEvent) -> Log {
+        Log {
+            inner: alloy::primitives::Log {
+                address: market.address(),
+                data: event.encode_log_data(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn borrow(account: Address, reserve: Address) -> IAaveV3Pool::Borrow {
+        IAaveV3Pool::Borrow {
+            reserve,
+            user: account,
+            onBehalfOf: account,
+            amount: U256::from(1),
+            interestRateMode: 2,
+            borrowRate: U256::ZERO,
+            referralCode: 0,
+        }
+    }
+
+    #[test]
+    fn test_add_log() {
+        let aave = Market::AaveV3(AAVE_V3_POOL);
+        let comet = Market::CompoundV3(COMET_USDC);
+        let account = Address::repeat_byte(1);
+        let weth = Address::repeat_byte(0xee);
+        let usdc = Address::repeat_byte(0xcc);
+        let mut book = BorrowerBook::new();
+
+        let supply = IAaveV3Pool::Supply {
+            reserve: weth,
+            user: account,
+            onBehalfOf: account,
+            amount: U256::from(1),
+            referralCode: 0,
+        };
+        assert!(book.add_log(aave, &log(aave, &supply)));
+        // Suppliers without debt aren't borrowers.
+        assert_eq!(book.borrowers().count(), 0);
+
+        assert!(book.add_log(aave, &log(aave, &borrow(account, usdc))));
+        assert!(book.add_log(aave, &log(aave, &borrow(account, usdc))));
+        let withdraw = IComet::Withdraw {
+            src: account,
+            to: account,
+            amount: U256::from(1),
+        };
+        assert!(book.add_log(comet, &log(comet, &withdraw)));
+        // Logs of another protocol are ignored.
+        assert!(!book.add_log(comet, &log(comet, &supply)));
+
+        assert_eq!(book.len(), 2);
+        let borrower = book.get(aave, account).unwrap();
+        assert_eq!(borrower.collaterals, [weth]);
+        assert_eq!(borrower.debts, [usdc]);
+    }
+
+    #[test]
+    fn test_at_risk() {
+        let aave = Market::AaveV3(AAVE_V3_POOL);
+        let mut book = BorrowerBook::new();
+        let accounts: Vec<_> = (1..=4).map(Address::repeat_byte).collect();
+        for account in &accounts {
+            book.add_log(
+                aave,
+                &log(aave, &borrow(*account, Address::ZERO)),
+            );
+        }
+        let ether = U256::from(10).pow(U256::from(18));
+        book.set_health_factor(aave, accounts[0], ether * U256::from(2));
+        book.set_health_factor(aave, accounts[1], ether / U256::from(2));
+        book.set_health_factor(aave, accounts[2], ether);
+
+        let at_risk: Vec<_> = book
+            .at_risk(ether * U256::from(105) / U256::from(100))
+            .into_iter()
+            .map(|borrower| borrower.account)
+            .collect();
+        let liquidatable: Vec<_> = book
+            .liquidatable()
+            .into_iter()
+            .map(|borrower| borrower.account)
+            .collect();
+
+        assert_eq!(
+            at_risk,
+            [accounts[1], accounts[2], accounts[3]]
+        );
+        assert_eq!(liquidatable, [accounts[1]]);
+    }
+}
@@ -0,0 +1,52 @@
+//! Parameters of the liquidation strategy.
+
+use alloy::primitives::{Address, BlockNumber, U256};
+use serde::Deserialize;
+
+use crate::markets::{AAVE_V3_POOL, COMET_USDC, Market};
+
+/// Parameters of the
+/// [LiquidationStrategy](crate::strategy::LiquidationStrategy). Missing
+/// fields take their default values.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LiquidationConfig {
+    /// Markets whose borrowers are monitored.
+    pub markets: Vec<Market>,
+    /// Price oracles (e.g. Chainlink aggregators), whose updates can make
+    /// borrowers liquidatable.
+    pub oracles: Vec<Address>,
+    /// Block the borrowers are searched from.
+    pub from_block: BlockNumber,
+    /// Number of blocks of logs fetched per request.
+    pub blocks_per_request: u64,
+    /// Health factor (scaled by 1e18), below which borrowers are refreshed
+    /// every block and liquidated after oracle updates.
+    pub watch_health_factor: U256,
+    /// Number of blocks between refreshes of every borrower.
+    pub full_refresh_interval: u64,
+    /// Number of blocks the backrun bundles are valid for.
+    pub inclusion_window: u64,
+    /// Maximum number of bundles submitted for a single event.
+    pub max_bundles_per_event: usize,
+}
+
+impl Default for LiquidationConfig {
+    /// Aave V3 and Compound V3 USDC markets on Ethereum mainnet, watching
+    /// borrowers below a health factor of 1.05.
+    fn default() -> Self {
+        Self {
+            markets: vec![
+                Market::AaveV3(AAVE_V3_POOL),
+                Market::CompoundV3(COMET_USDC),
+            ],
+            oracles: vec![],
+            from_block: 16_291_127,
+            blocks_per_request: 10_000,
+            watch_health_factor: U256::from(1_050_000_000_000_000_000u64),
+            full_refresh_interval: 300,
+            inclusion_window: 3,
+            max_bundles_per_event: 10,
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod borrowers;
+pub mod config;
+pub mod markets;
+pub mod strategy;
+pub mod types;
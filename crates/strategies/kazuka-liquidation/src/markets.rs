@@ -0,0 +1,210 @@
+//! Lending markets, whose borrowers can be liquidated.
+
+use std::sync::Arc;
+
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{Address, B256, Bytes, U256, address},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    sol,
+    sol_types::SolEvent,
+};
+use kazuka_core::error::KazukaError;
+use serde::Deserialize;
+
+sol! {
+    #[sol(rpc)]
+    interface IAaveV3Pool {
+        event Supply(
+            address indexed reserve,
+            address user,
+            address indexed onBehalfOf,
+            uint256 amount,
+            uint16 indexed referralCode
+        );
+        event Borrow(
+            address indexed reserve,
+            address user,
+            address indexed onBehalfOf,
+            uint256 amount,
+            uint8 interestRateMode,
+            uint256 borrowRate,
+            uint16 indexed referralCode
+        );
+
+        function getUserAccountData(address user) external view returns (
+            uint256 totalCollateralBase,
+            uint256 totalDebtBase,
+            uint256 availableBorrowsBase,
+            uint256 currentLiquidationThreshold,
+            uint256 ltv,
+            uint256 healthFactor
+        );
+        function liquidationCall(
+            address collateralAsset,
+            address debtAsset,
+            address user,
+            uint256 debtToCover,
+            bool receiveAToken
+        ) external;
+    }
+
+    #[sol(rpc)]
+    interface IComet {
+        event Withdraw(address indexed src, address indexed to, uint256 amount);
+
+        function isLiquidatable(address account) external view returns (bool);
+        function absorb(address absorber, address[] calldata accounts) external;
+    }
+}
+
+/// Aave V3 pool on Ethereum mainnet.
+pub const AAVE_V3_POOL: Address =
+    address!("0x87870Bca3F3fD6335C3F4ce8392D69350B4fA4E2");
+
+/// Compound V3 USDC market on Ethereum mainnet.
+pub const COMET_USDC: Address =
+    address!("0xc3d688B66703497DAA19211EEdff47f25384cdc3");
+
+/// Health factor, below which a borrower can be liquidated.
+pub const HEALTH_FACTOR_ONE: U256 =
+    U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+/// Gas limit of the liquidation transactions.
+const LIQUIDATION_GAS_LIMIT: u64 = 1_000_000;
+
+/// A lending market.
+///
+/// ```toml
+/// markets = [
+///     { protocol = "aave_v3", address = "0x87870Bca3F3fD6335C3F4ce8392D69350B4fA4E2" },
+///     { protocol = "compound_v3", address = "0xc3d688B66703497DAA19211EEdff47f25384cdc3" },
+/// ]
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(tag = "protocol", content = "address", rename_all = "snake_case")]
+pub enum Market {
+    /// Aave V3 pool.
+    AaveV3(Address),
+    /// Compound V3 market (Comet).
+    CompoundV3(Address),
+}
+
+impl Market {
+    pub fn address(&self) -> Address {
+        match self {
+            Self::AaveV3(address) | Self::CompoundV3(address) => *address,
+        }
+    }
+
+    /// Signatures of the logs revealing the borrowers of the market.
+    pub fn borrower_signatures(&self) -> Vec<B256> {
+        match self {
+            Self::AaveV3(_) => vec![
+                IAaveV3Pool::Supply::SIGNATURE_HASH,
+                IAaveV3Pool::Borrow::SIGNATURE_HASH,
+            ],
+            Self::CompoundV3(_) => vec![IComet::Withdraw::SIGNATURE_HASH],
+        }
+    }
+
+    /// Fetches the health factor of the account, scaled by 1e18.
+    ///
+    /// Compound V3 doesn't expose health factors, so its accounts are
+    /// either liquidatable (zero) or healthy (`U256::MAX`).
+    pub async fn health_factor<P: Provider>(
+        &self,
+        provider: Arc<P>,
+        account: Address,
+    ) -> Result<U256, KazukaError> {
+        match self {
+            Self::AaveV3(pool) => {
+                let data = IAaveV3Pool::new(*pool, provider)
+                    .getUserAccountData(account)
+                    .call()
+                    .await?;
+                Ok(data.healthFactor)
+            }
+            Self::CompoundV3(comet) => {
+                let liquidatable = IComet::new(*comet, provider)
+                    .isLiquidatable(account)
+                    .call()
+                    .await?;
+                Ok(if liquidatable { U256::ZERO } else { U256::MAX })
+            }
+        }
+    }
+}
+
+/// Transaction liquidating the account of an Aave V3 pool, covering as much
+/// debt as allowed. The liquidator must hold and have approved the debt
+/// asset, and receives the collateral.
+pub fn aave_liquidation_call<P: Provider>(
+    provider: Arc<P>,
+    pool: Address,
+    collateral: Address,
+    debt: Address,
+    account: Address,
+) -> TransactionRequest {
+    IAaveV3Pool::new(pool, provider)
+        .liquidationCall(
+            collateral,
+            debt,
+            account,
+            U256::MAX,
+            false,
+        )
+        .into_transaction_request()
+        .with_gas_limit(LIQUIDATION_GAS_LIMIT)
+}
+
+/// Transaction absorbing the accounts of a Compound V3 market, crediting the
+/// absorber.
+pub fn comet_absorb<P: Provider>(
+    provider: Arc<P>,
+    comet: Address,
+    absorber: Address,
+    accounts: Vec<Address>,
+) -> TransactionRequest {
+    IComet::new(comet, provider)
+        .absorb(absorber, accounts)
+        .into_transaction_request()
+        .with_gas_limit(LIQUIDATION_GAS_LIMIT)
+}
+
+/// Signs the transaction with the signer of the provider, at the current
+/// gas price.
+pub async fn sign<P: Provider>(
+    provider: &P,
+    mut tx: TransactionRequest,
+) -> Result<Bytes, KazukaError> {
+    tx.set_gas_price(provider.get_gas_price().await?);
+    Ok(provider.sign_transaction(tx).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Markets {
+        markets: Vec<Market>,
+    }
+
+    #[test]
+    fn test_deserialize_markets() {
+        let markets: Markets = serde_json::from_str(
+            r#"{"markets": [
+                {"protocol": "aave_v3", "address": "0x87870bca3f3fd6335c3f4ce8392d69350b4fa4e2"},
+                {"protocol": "compound_v3", "address": "0xc3d688b66703497daa19211eedff47f25384cdc3"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            markets.markets,
+            [Market::AaveV3(AAVE_V3_POOL), Market::CompoundV3(COMET_USDC)]
+        );
+    }
+}
@@ -0,0 +1,370 @@
+//! Liquidates the borrowers of lending markets:
+//! - borrowers are found from the logs of the markets, and their health factors
+//!   are refreshed every block (only the ones at risk, and all of them every
+//!   [full_refresh_interval](LiquidationConfig::full_refresh_interval)),
+//! - liquidatable borrowers are liquidated with `eth_sendBundle` bundles
+//!   targeting the next block,
+//! - price oracle updates, hinted by MEV-Share or seen in the mempool, are
+//!   backrun with liquidations of the borrowers at risk.
+
+use std::sync::Arc;
+
+use alloy::{
+    consensus::Transaction,
+    eips::Encodable2718,
+    primitives::{Address, B256, BlockNumber, Bytes},
+    providers::Provider,
+    rpc::types::{Filter, TransactionRequest},
+};
+use async_trait::async_trait;
+use kazuka_core::{
+    error::KazukaError, executors::flashbots_bundle_executor::SubmitBundle,
+    types::Strategy,
+};
+use kazuka_mev_share::{rpc::bundle::BundleBuilder, sse};
+
+use crate::{
+    borrowers::{Borrower, BorrowerBook},
+    config::LiquidationConfig,
+    markets::{self, Market},
+    types::{Action, Event},
+};
+
+pub struct LiquidationStrategy<P: Provider> {
+    /// Exposes Ethereum JSON-RPC methods.
+    provider: Arc<P>,
+    /// Markets, oracles and bundle parameters.
+    config: LiquidationConfig,
+    /// Borrowers found so far, with their health factors.
+    borrowers: BorrowerBook,
+    /// Next block to scan the logs of the markets from.
+    next_block: BlockNumber,
+    /// Block every borrower was last refreshed at.
+    last_full_refresh: Option<BlockNumber>,
+    /// Account repaying the debts and receiving the collaterals.
+    liquidator: Address,
+    /// Whether to synthesize sample txs instead of signing liquidations.
+    dry_run: bool,
+}
+
+impl<P: Provider> LiquidationStrategy<P> {
+    pub fn new(provider: Arc<P>, liquidator: Address, dry_run: bool) -> Self {
+        let config = LiquidationConfig::default();
+        Self {
+            provider,
+            next_block: config.from_block,
+            config,
+            borrowers: BorrowerBook::new(),
+            last_full_refresh: None,
+            liquidator,
+            dry_run,
+        }
+    }
+
+    pub fn with_config(mut self, config: LiquidationConfig) -> Self {
+        self.next_block = config.from_block;
+        self.config = config;
+        self
+    }
+
+    /// Finds the borrowers in the logs of the markets up to the latest
+    /// block.
+    async fn scan_logs(&mut self) -> Result<(), KazukaError> {
+        let markets: Vec<Address> =
+            self.config.markets.iter().map(Market::address).collect();
+        let signatures: Vec<B256> = self
+            .config
+            .markets
+            .iter()
+            .flat_map(Market::borrower_signatures)
+            .collect();
+        if markets.is_empty() {
+            return Ok(());
+        }
+        let latest_block = self.provider.get_block_number().await?;
+        while self.next_block <= latest_block {
+            let to_block = latest_block
+                .min(self.next_block + self.config.blocks_per_request - 1);
+            let filter = Filter::new()
+                .address(markets.clone())
+                .event_signature(signatures.clone())
+                .from_block(self.next_block)
+                .to_block(to_block);
+            for log in self.provider.get_logs(&filter).await? {
+                let Some(market) = self
+                    .config
+                    .markets
+                    .iter()
+                    .find(|market| market.address() == log.address())
+                else {
+                    continue;
+                };
+                self.borrowers.add_log(*market, &log);
+            }
+            self.next_block = to_block + 1;
+        }
+        Ok(())
+    }
+
+    /// Fetches the health factors of the borrowers, at risk only unless a
+    /// full refresh is due.
+    async fn refresh_health_factors(&mut self, block_number: BlockNumber) {
+        let full_refresh = self.last_full_refresh.is_none_or(|last| {
+            block_number >= last + self.config.full_refresh_interval
+        });
+        let accounts: Vec<(Market, Address)> = if full_refresh {
+            self.borrowers
+                .borrowers()
+                .map(|borrower| (borrower.market, borrower.account))
+                .collect()
+        } else {
+            self.borrowers
+                .at_risk(self.config.watch_health_factor)
+                .into_iter()
+                .map(|borrower| (borrower.market, borrower.account))
+                .collect()
+        };
+        for (market, account) in accounts {
+            match market.health_factor(self.provider.clone(), account).await {
+                Ok(health_factor) => self.borrowers.set_health_factor(
+                    market,
+                    account,
+                    health_factor,
+                ),
+                Err(e) => tracing::debug!(
+                    ?account,
+                    "Error fetching health factor: {}",
+                    e
+                ),
+            }
+        }
+        if full_refresh {
+            tracing::info!(
+                "Refreshed {} borrowers at block {}",
+                self.borrowers.len(),
+                block_number
+            );
+            self.last_full_refresh = Some(block_number);
+        }
+    }
+
+    /// Transactions liquidating the borrower: one per collateral and debt
+    /// asset for Aave V3, a single absorption for Compound V3.
+    fn liquidation_requests(
+        &self,
+        borrower: &Borrower,
+    ) -> Vec<TransactionRequest> {
+        match borrower.market {
+            Market::AaveV3(pool) => borrower
+                .collaterals
+                .iter()
+                .flat_map(|collateral| {
+                    borrower.debts.iter().map(|debt| {
+                        markets::aave_liquidation_call(
+                            self.provider.clone(),
+                            pool,
+                            *collateral,
+                            *debt,
+                            borrower.account,
+                        )
+                    })
+                })
+                .collect(),
+            Market::CompoundV3(comet) => vec![markets::comet_absorb(
+                self.provider.clone(),
+                comet,
+                self.liquidator,
+                vec![borrower.account],
+            )],
+        }
+    }
+
+    /// Signed liquidation transactions of the borrowers, at most
+    /// [max_bundles_per_event](LiquidationConfig::max_bundles_per_event).
+    /// They share the nonce of the liquidator, so each one goes in its own
+    /// bundle.
+    async fn liquidation_txs(&self, borrowers: Vec<Borrower>) -> Vec<Bytes> {
+        let mut txs = vec![];
+        let requests = borrowers
+            .iter()
+            .flat_map(|borrower| self.liquidation_requests(borrower))
+            .take(self.config.max_bundles_per_event);
+        for request in requests {
+            if self.dry_run {
+                tracing::info!("Liquidation tx: {:?}", request);
+                txs.push(Bytes::from_static(b"sample-tx"));
+                continue;
+            }
+            match markets::sign(&*self.provider, request).await {
+                Ok(tx) => txs.push(tx),
+                Err(e) => {
+                    tracing::error!("Error signing liquidation tx: {:?}", e)
+                }
+            }
+        }
+        txs
+    }
+
+    fn eth_bundle(
+        &self,
+        block_number: BlockNumber,
+        txs: Vec<Bytes>,
+    ) -> Option<SubmitBundle> {
+        let builder = txs.into_iter().fold(
+            BundleBuilder::for_block(block_number + 1),
+            |builder, tx| builder.tx(tx),
+        );
+        match builder.build_eth() {
+            Ok(bundle) => Some(SubmitBundle::Eth(bundle)),
+            Err(err) => {
+                tracing::error!("Skipping invalid bundle: {}", err);
+                None
+            }
+        }
+    }
+
+    fn backrun_bundle(
+        &self,
+        block_number: BlockNumber,
+        tx_hash: B256,
+        tx: Bytes,
+    ) -> Option<SubmitBundle> {
+        let bundle = BundleBuilder::for_block(block_number + 1)
+            .max_block(block_number + self.config.inclusion_window)
+            .backrun_of(tx_hash)
+            .tx(tx)
+            .build();
+        match bundle {
+            Ok(bundle) => Some(SubmitBundle::Mev(bundle)),
+            Err(err) => {
+                tracing::error!("Skipping invalid bundle: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Whether the hinted transaction may update a price oracle.
+    fn is_oracle_update(&self, event: &sse::Event) -> bool {
+        event
+            .logs
+            .iter()
+            .any(|log| self.config.oracles.contains(&log.address))
+            || event.transactions.iter().any(|tx| {
+                tx.to.is_some_and(|to| self.config.oracles.contains(&to))
+            })
+    }
+
+    fn at_risk(&self) -> Vec<Borrower> {
+        self.borrowers
+            .at_risk(self.config.watch_health_factor)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    async fn liquidate(
+        &mut self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<SubmitBundle>, KazukaError> {
+        self.scan_logs().await?;
+        self.refresh_health_factors(block_number).await;
+        let liquidatable: Vec<Borrower> =
+            self.borrowers.liquidatable().into_iter().cloned().collect();
+        if !liquidatable.is_empty() {
+            tracing::info!(
+                "Found {} liquidatable borrowers",
+                liquidatable.len()
+            );
+        }
+        Ok(self
+            .liquidation_txs(liquidatable)
+            .await
+            .into_iter()
+            .filter_map(|tx| self.eth_bundle(block_number, vec![tx]))
+            .collect())
+    }
+
+    async fn backrun_oracle_update(
+        &self,
+        tx_hash: B256,
+    ) -> Result<Vec<SubmitBundle>, KazukaError> {
+        let block_number = self.provider.get_block_number().await?;
+        Ok(self
+            .liquidation_txs(self.at_risk())
+            .await
+            .into_iter()
+            .filter_map(|tx| self.backrun_bundle(block_number, tx_hash, tx))
+            .collect())
+    }
+
+    async fn bundle_oracle_update(
+        &self,
+        oracle_tx: Bytes,
+    ) -> Result<Vec<SubmitBundle>, KazukaError> {
+        let block_number = self.provider.get_block_number().await?;
+        Ok(self
+            .liquidation_txs(self.at_risk())
+            .await
+            .into_iter()
+            .filter_map(|tx| {
+                self.eth_bundle(
+                    block_number,
+                    vec![oracle_tx.clone(), tx],
+                )
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl<P: Provider> Strategy<Event, Action> for LiquidationStrategy<P> {
+    /// Finds the borrowers of the markets, and fetches their health factors.
+    async fn sync_state(&mut self) -> Result<(), KazukaError> {
+        self.scan_logs().await?;
+        let block_number = self.next_block.saturating_sub(1);
+        self.refresh_health_factors(block_number).await;
+        tracing::info!(
+            "Found {} borrowers, {} at risk",
+            self.borrowers.len(),
+            self.borrowers
+                .at_risk(self.config.watch_health_factor)
+                .len()
+        );
+        Ok(())
+    }
+
+    async fn process_event(&mut self, event: Event) -> Vec<Action> {
+        let bundles = match event {
+            Event::NewBlock(block) => self.liquidate(block.number).await,
+            Event::MevShareEvent(event) => {
+                if !self.is_oracle_update(&event) {
+                    return vec![];
+                }
+                tracing::info!(
+                    "Backrunning oracle update {:?}",
+                    event.hash
+                );
+                self.backrun_oracle_update(event.hash).await
+            }
+            Event::PendingTx(tx) => {
+                let Some(oracle) =
+                    tx.to().filter(|to| self.config.oracles.contains(to))
+                else {
+                    return vec![];
+                };
+                tracing::info!("Bundling update of oracle {:?}", oracle);
+                let oracle_tx = tx.inner.inner.inner().encoded_2718();
+                self.bundle_oracle_update(oracle_tx.into()).await
+            }
+        };
+        match bundles {
+            Ok(bundles) => {
+                bundles.into_iter().map(Action::SubmitBundle).collect()
+            }
+            Err(e) => {
+                tracing::error!("Error generating bundles: {:?}", e);
+                vec![]
+            }
+        }
+    }
+}
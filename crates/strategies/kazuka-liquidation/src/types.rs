@@ -0,0 +1,22 @@
+use alloy::network::AnyRpcTransaction;
+use kazuka_core::{
+    event_sources::block_event_source::NewBlock,
+    executors::flashbots_bundle_executor::SubmitBundle,
+};
+use kazuka_mev_share::sse;
+
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// Health factors are refreshed on every block.
+    NewBlock(NewBlock),
+    /// Hint of a MEV-Share transaction, which may update a price oracle.
+    MevShareEvent(sse::Event),
+    /// Public mempool transaction, which may update a price oracle.
+    PendingTx(AnyRpcTransaction),
+}
+
+#[derive(Clone, Debug)]
+pub enum Action {
+    /// Submit a liquidation bundle to the relay.
+    SubmitBundle(SubmitBundle),
+}
@@ -0,0 +1,302 @@
+//! Parameters of the arbitrage strategy, which can be loaded from a TOML or
+//! JSON file.
+//!
+//! ```toml
+//! pools_file = "data/uniswap_v2_uniswap_v3_weth_pools.csv"
+//! graph_pools_file = "data/graph_pools.csv"
+//! sizing = "optimal"
+//! price_impacts_bps = [30, 100, 300]
+//! max_bundles_per_event = 2
+//! inclusion_window = 30
+//! max_hint_age_blocks = 2
+//! payment_percentage = 90
+//! min_expected_value = "1000000000000000"
+//!
+//! [feedback]
+//! min_samples = 20
+//! contested_payment_percentage = 95
+//!
+//! [screening]
+//! blacklist = ["0x0000000000000000000000000000000000000001"]
+//! max_transfer_fee_bps = 0
+//!
+//! [inventory]
+//! min_weth = "500000000000000000"
+//! target_weth = "1000000000000000000"
+//! max_weth = "2000000000000000000"
+//!
+//! [[v2_forks]]
+//! name = "sushiswap"
+//! factory = "0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac"
+//! fee = 3000
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use alloy::primitives::U256;
+use kazuka_core::error::KazukaError;
+use serde::Deserialize;
+
+use crate::{
+    feedback::FeedbackConfig, inventory::InventoryConfig,
+    screening::TokenScreeningConfig, types::V2Fork,
+};
+
+/// Average block time.
+pub(crate) const BLOCK_TIME: Duration = Duration::from_secs(12);
+
+/// How the sizes of the backruns are chosen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sizing {
+    /// Every size of [ArbitrageConfig::sizes].
+    Grid,
+    /// Optimal sizes for the [ArbitrageConfig::price_impacts_bps], computed
+    /// from the reserves of the pools, see [crate::sizing].
+    #[default]
+    Optimal,
+}
+
+/// Parameters of the
+/// [MevShareUniswapV2V3Arbitrage](crate::strategy::MevShareUniswapV2V3Arbitrage)
+/// strategy. Missing fields take their default values.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ArbitrageConfig {
+    /// CSV file with the V2/V3 pool pairs to watch.
+    pub pools_file: PathBuf,
+    /// CSV file with the pools of the triangular cycles, see
+    /// [crate::triangular].
+    pub graph_pools_file: Option<PathBuf>,
+    /// Known forks of the V2 pools in the pool file.
+    pub v2_forks: Vec<V2Fork>,
+    pub sizing: Sizing,
+    /// Sizes (in wei) of the backruns submitted for every event with
+    /// [Sizing::Grid].
+    pub sizes: Vec<U256>,
+    /// Price impacts (in bps) of the target transactions to backrun with
+    /// [Sizing::Optimal], most likely first.
+    pub price_impacts_bps: Vec<u64>,
+    /// Maximum number of bundles submitted for a single event.
+    pub max_bundles_per_event: usize,
+    /// Number of blocks the bundles are valid for.
+    pub inclusion_window: u64,
+    /// Age (in blocks) of the hints, measured from when they were
+    /// received, above which they are skipped instead of backrun.
+    pub max_hint_age_blocks: u32,
+    /// Percentage of the profit paid to the block builder.
+    pub payment_percentage: u8,
+    /// Minimum expected value (in wei) of the simulated bundles, see
+    /// [crate::simulation].
+    pub min_expected_value: U256,
+    /// How the bids adapt to the outcomes of the bundles, see
+    /// [crate::feedback].
+    pub feedback: FeedbackConfig,
+    /// Which tokens are traded, see [crate::screening].
+    pub screening: TokenScreeningConfig,
+    /// WETH balance of the arbitrage contract, see [crate::inventory].
+    pub inventory: InventoryConfig,
+}
+
+impl Default for ArbitrageConfig {
+    /// Optimal backruns of 0.3% to 10% price impacts (or every power of ten
+    /// from 1e5 to 1e18 wei with the grid), valid for 30 blocks, against the
+    /// bundled pool list.
+    fn default() -> Self {
+        Self {
+            pools_file: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("data/uniswap_v2_uniswap_v3_weth_pools.csv"),
+            graph_pools_file: None,
+            v2_forks: vec![V2Fork::uniswap_v2(), V2Fork::sushiswap()],
+            sizing: Sizing::default(),
+            sizes: (5..=18)
+                .map(|exp| U256::from(10).pow(U256::from(exp)))
+                .collect(),
+            price_impacts_bps: vec![30, 100, 300, 1_000],
+            max_bundles_per_event: 14,
+            inclusion_window: 30,
+            max_hint_age_blocks: 2,
+            payment_percentage: 0,
+            min_expected_value: U256::ZERO,
+            feedback: FeedbackConfig::default(),
+            screening: TokenScreeningConfig::default(),
+            inventory: InventoryConfig::default(),
+        }
+    }
+}
+
+impl ArbitrageConfig {
+    /// Loads the config from a `.toml` or `.json` file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, KazukaError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            KazukaError::ConfigError(format!(
+                "failed to read {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let config = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&contents),
+            Some("json") => Self::from_json(&contents),
+            _ => Err(KazukaError::ConfigError(format!(
+                "unsupported config format: {}",
+                path.display()
+            ))),
+        }?;
+        Ok(config.relative_to(path.parent().unwrap_or(Path::new(""))))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, KazukaError> {
+        let config: Self = toml::from_str(contents)
+            .map_err(|e| KazukaError::ConfigError(e.to_string()))?;
+        config.validate()
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, KazukaError> {
+        let config: Self = serde_json::from_str(contents)
+            .map_err(|e| KazukaError::ConfigError(e.to_string()))?;
+        config.validate()
+    }
+
+    /// Age of the hints above which they are skipped, see
+    /// [max_hint_age_blocks](Self::max_hint_age_blocks).
+    pub fn max_hint_age(&self) -> Duration {
+        BLOCK_TIME * self.max_hint_age_blocks
+    }
+
+    /// Resolves relative pool files against the directory of the config.
+    pub fn relative_to(mut self, dir: &Path) -> Self {
+        if self.pools_file.is_relative() {
+            self.pools_file = dir.join(&self.pools_file);
+        }
+        if let Some(graph_pools_file) = &mut self.graph_pools_file
+            && graph_pools_file.is_relative()
+        {
+            *graph_pools_file = dir.join(&*graph_pools_file);
+        }
+        self
+    }
+
+    /// Checks that the parameters are within their bounds.
+    pub fn validate(self) -> Result<Self, KazukaError> {
+        if self.payment_percentage > 100 {
+            return Err(KazukaError::ConfigError(format!(
+                "payment percentage must be at most 100, got {}",
+                self.payment_percentage
+            )));
+        }
+        if let Some(impact) = self
+            .price_impacts_bps
+            .iter()
+            .find(|impact| **impact >= 10_000)
+        {
+            return Err(KazukaError::ConfigError(format!(
+                "price impact must be below 10000 bps, got {}",
+                impact
+            )));
+        }
+        if self.feedback.contested_payment_percentage > 100 {
+            return Err(KazukaError::ConfigError(format!(
+                "contested payment percentage must be at most 100, got {}",
+                self.feedback.contested_payment_percentage
+            )));
+        }
+        if self.screening.round_trip_bps == 0
+            || self.screening.round_trip_bps > 10_000
+        {
+            return Err(KazukaError::ConfigError(format!(
+                "round trip must be between 1 and 10000 bps, got {}",
+                self.screening.round_trip_bps
+            )));
+        }
+        if self.inventory.min_weth > self.inventory.target_weth
+            || self.inventory.target_weth > self.inventory.max_weth
+        {
+            return Err(KazukaError::ConfigError(format!(
+                "inventory must satisfy min_weth <= target_weth <= max_weth, \
+                 got {} / {} / {}",
+                self.inventory.min_weth,
+                self.inventory.target_weth,
+                self.inventory.max_weth
+            )));
+        }
+        if self.max_hint_age_blocks == 0 {
+            return Err(KazukaError::ConfigError(
+                "max hint age must be at least 1 block".to_string(),
+            ));
+        }
+        if self.inclusion_window == 0 {
+            return Err(KazukaError::ConfigError(
+                "inclusion window must be at least 1 block".to_string(),
+            ));
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let config = ArbitrageConfig::from_toml(
+            r#"
+            sizing = "grid"
+            sizes = ["1000", "0x10"]
+            max_bundles_per_event = 1
+            payment_percentage = 90
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.sizes,
+            [U256::from(1000), U256::from(16)]
+        );
+        assert_eq!(config.sizing, Sizing::Grid);
+        assert_eq!(config.max_bundles_per_event, 1);
+        assert_eq!(config.payment_percentage, 90);
+        assert_eq!(config.inclusion_window, 30);
+    }
+
+    #[test]
+    fn test_from_json() {
+        let config =
+            ArbitrageConfig::from_json(r#"{"inclusion_window": 5}"#).unwrap();
+
+        assert_eq!(config.inclusion_window, 5);
+        assert_eq!(config.sizing, Sizing::Optimal);
+        assert_eq!(config.sizes.len(), 14);
+    }
+
+    #[test]
+    fn test_invalid_payment_percentage() {
+        let result =
+            ArbitrageConfig::from_json(r#"{"payment_percentage": 101}"#);
+
+        assert!(matches!(
+            result,
+            Err(KazukaError::ConfigError(_))
+        ));
+    }
+    #[test]
+    fn test_invalid_inventory() {
+        let result = ArbitrageConfig::from_toml(
+            r#"
+            [inventory]
+            min_weth = "2000"
+            target_weth = "1000"
+            "#,
+        );
+
+        assert!(matches!(
+            result,
+            Err(KazukaError::ConfigError(_))
+        ));
+    }
+}
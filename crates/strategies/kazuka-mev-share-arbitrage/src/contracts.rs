@@ -1,13 +1,22 @@
+use std::sync::Arc;
+
 use alloy::{
     network::TransactionBuilder,
     primitives::{Address, Bytes, U256},
     providers::Provider,
     sol,
 };
-use kazuka_core::error::KazukaError;
+use kazuka_core::{error::KazukaError, signer::SignerProvider};
 use kazuka_mev_share_arbitrage_bindings::blind_arb::BlindArb::BlindArbInstance;
 
-use crate::types::UniswapV2PoolInfo;
+use crate::{
+    signing::{self, TxContext},
+    types::V2PoolInfo,
+};
+
+/// Fee the contract quotes V2 swaps with, so only forks with a fee of at most
+/// 0.3% can be arbitraged.
+const MAX_V2_FEE: u32 = 3_000;
 
 sol!(
     BlindArb,
@@ -18,22 +27,57 @@ sol!(
 pub(crate) struct ArbitrageContract<P: Provider> {
     provider: P,
     instance: BlindArbInstance<P>,
+    /// Signs the arbitrage txs offline instead of the node.
+    signers: Option<Arc<dyn SignerProvider>>,
 }
 
 impl<P: Provider> ArbitrageContract<P> {
     pub(crate) fn new(provider: P, instance: BlindArbInstance<P>) -> Self {
-        Self { provider, instance }
+        Self {
+            provider,
+            instance,
+            signers: None,
+        }
+    }
+
+    /// Signs the arbitrage txs with the transactions signer of the
+    /// `signers`, see [crate::signing].
+    pub(crate) fn set_signers(&mut self, signers: Arc<dyn SignerProvider>) {
+        self.signers = Some(signers);
+    }
+
+    pub(crate) fn signers(&self) -> Option<Arc<dyn SignerProvider>> {
+        self.signers.clone()
+    }
+
+    pub(crate) fn address(&self) -> Address {
+        *self.instance.address()
     }
 
+    /// Fields shared by the arbitrage txs of a hint, see [crate::signing].
+    pub(crate) async fn tx_context(&self) -> Result<TxContext, KazukaError> {
+        TxContext::fetch(&self.provider, self.signers.as_deref()).await
+    }
+
+    /// Signed arbitrage tx of the given size, with the fields of the
+    /// `context`.
     pub(crate) async fn generate_arbitrage_tx(
         &self,
+        context: &TxContext,
         v3_address: Address,
-        v2_pool_info: &UniswapV2PoolInfo,
+        v2_pool_info: &V2PoolInfo,
         size: U256,
+        payment_percentage: u8,
     ) -> Result<Bytes, KazukaError> {
+        if v2_pool_info.fork.fee > MAX_V2_FEE {
+            return Err(KazukaError::ConfigError(format!(
+                "fee of {} is not supported by the arbitrage contract",
+                v2_pool_info.fork.name
+            )));
+        }
+
         // Set parameters for backruns.
-        let payment_percentage = U256::ZERO;
-        let bid_gas_price = self.provider.get_gas_price().await?;
+        let payment_percentage = U256::from(payment_percentage);
 
         let mut tx = if v2_pool_info.is_weth_token0 {
             self.instance
@@ -55,14 +99,18 @@ impl<P: Provider> ArbitrageContract<P> {
                 .into_transaction_request()
         };
         tx.set_gas_limit(400000);
-        tx.set_gas_price(bid_gas_price);
+        context.apply(&mut tx);
 
         tracing::info!(
             "Generated arbitrage transaction: {:?}",
             tx
         );
 
-        let tx_bytes = self.provider.sign_transaction(tx).await?;
-        Ok(tx_bytes)
+        signing::sign_tx(
+            &self.provider,
+            self.signers.as_deref(),
+            tx,
+        )
+        .await
     }
 }
@@ -0,0 +1,411 @@
+//! Discovery of Uniswap V2/V3 (and fork) WETH pools from the logs of their
+//! factories.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    primitives::{Address, B256, U256, address},
+    providers::Provider,
+    rpc::types::{Filter, Log},
+    sol,
+    sol_types::SolEvent,
+};
+use kazuka_core::error::KazukaError;
+use kazuka_mev_share_arbitrage_bindings::iweth::IWETH;
+
+use crate::types::{V2Fork, V2PoolInfo, V2V3PoolRecord};
+
+sol! {
+    /// Emitted by Uniswap V2 (and fork) factories.
+    event PairCreated(
+        address indexed token0,
+        address indexed token1,
+        address pair,
+        uint256 allPairsLength
+    );
+
+    /// Emitted by Uniswap V3 (and fork) factories.
+    event PoolCreated(
+        address indexed token0,
+        address indexed token1,
+        uint24 indexed fee,
+        int24 tickSpacing,
+        address pool
+    );
+}
+
+pub const WETH: Address =
+    address!("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
+pub const UNISWAP_V3_FACTORY: Address =
+    address!("0x1F98431c8aD98523631AE4a59f267346ea31F984");
+
+/// Block the Uniswap V2 factory has been deployed at.
+const UNISWAP_V2_DEPLOYMENT_BLOCK: u64 = 10_000_835;
+
+/// Where and how pools are discovered.
+#[derive(Clone, Debug)]
+pub struct PoolDiscoveryConfig {
+    /// Forks whose factories emit [PairCreated].
+    pub v2_forks: Vec<V2Fork>,
+    /// Factories emitting [PoolCreated].
+    pub v3_factories: Vec<Address>,
+    pub weth: Address,
+    /// Minimum WETH balance of both pools of a pair.
+    pub min_weth_liquidity: U256,
+    /// First block to scan factory logs from.
+    pub from_block: u64,
+    /// Maximum number of blocks per `eth_getLogs` request.
+    pub blocks_per_request: u64,
+    /// How often pools are rediscovered at runtime.
+    pub refresh_interval: Duration,
+}
+
+impl Default for PoolDiscoveryConfig {
+    /// Uniswap and Sushiswap pools on mainnet with at least 10 WETH.
+    fn default() -> Self {
+        Self {
+            v2_forks: vec![V2Fork::uniswap_v2(), V2Fork::sushiswap()],
+            v3_factories: vec![UNISWAP_V3_FACTORY],
+            weth: WETH,
+            min_weth_liquidity: U256::from(10_u128.pow(19)),
+            from_block: UNISWAP_V2_DEPLOYMENT_BLOCK,
+            blocks_per_request: 10_000,
+            refresh_interval: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// Pools paired with WETH found so far, by the other token.
+#[derive(Debug, Default)]
+struct WethPools {
+    v2: HashMap<Address, Vec<V2PoolInfo>>,
+    v3: HashMap<Address, Vec<Address>>,
+}
+
+/// Enumerates pools from factory logs, scanning only new blocks on every
+/// refresh, and pairs WETH pools of the same token with enough liquidity.
+pub struct PoolDiscovery<P: Provider> {
+    provider: Arc<P>,
+    config: PoolDiscoveryConfig,
+    pools: WethPools,
+    /// Next block to scan.
+    next_block: u64,
+    last_refresh: Option<Instant>,
+}
+
+impl<P: Provider> PoolDiscovery<P> {
+    pub fn new(provider: Arc<P>, config: PoolDiscoveryConfig) -> Self {
+        Self {
+            provider,
+            next_block: config.from_block,
+            config,
+            pools: WethPools::default(),
+            last_refresh: None,
+        }
+    }
+
+    /// Whether the pools are due to be rediscovered.
+    pub fn is_stale(&self) -> bool {
+        self.last_refresh.is_none_or(|last_refresh| {
+            last_refresh.elapsed() >= self.config.refresh_interval
+        })
+    }
+
+    /// Scans new factory logs and returns the liquid V2/V3 pool pairs,
+    /// keyed by the V3 pool address.
+    pub async fn refresh(
+        &mut self,
+    ) -> Result<HashMap<Address, V2PoolInfo>, KazukaError> {
+        self.scan().await?;
+        let pools = self.liquid_pools().await?;
+        self.last_refresh = Some(Instant::now());
+        tracing::info!(
+            pools = pools.len(),
+            scanned_to = self.next_block,
+            "Discovered V2/V3 pools"
+        );
+        Ok(pools)
+    }
+
+    async fn scan(&mut self) -> Result<(), KazukaError> {
+        let latest_block = self.provider.get_block_number().await?;
+        while self.next_block <= latest_block {
+            let to_block = latest_block
+                .min(self.next_block + self.config.blocks_per_request - 1);
+            let v2_factories: Vec<Address> = self
+                .config
+                .v2_forks
+                .iter()
+                .map(|fork| fork.factory)
+                .collect();
+            let v2_logs = self
+                .logs(
+                    &v2_factories,
+                    PairCreated::SIGNATURE_HASH,
+                    to_block,
+                )
+                .await?;
+            for log in v2_logs {
+                match log.log_decode::<PairCreated>() {
+                    Ok(log) => {
+                        self.add_v2_pair(log.inner.address, &log.inner.data)
+                    }
+                    Err(e) => tracing::debug!("Skipping V2 log: {}", e),
+                }
+            }
+            let v3_logs = self
+                .logs(
+                    &self.config.v3_factories,
+                    PoolCreated::SIGNATURE_HASH,
+                    to_block,
+                )
+                .await?;
+            for log in v3_logs {
+                match log.log_decode::<PoolCreated>() {
+                    Ok(log) => self.add_v3_pool(&log.inner.data),
+                    Err(e) => tracing::debug!("Skipping V3 log: {}", e),
+                }
+            }
+            self.next_block = to_block + 1;
+        }
+        Ok(())
+    }
+
+    async fn logs(
+        &self,
+        factories: &[Address],
+        signature: B256,
+        to_block: u64,
+    ) -> Result<Vec<Log>, KazukaError> {
+        if factories.is_empty() {
+            return Ok(vec![]);
+        }
+        let filter = Filter::new()
+            .address(factories.to_vec())
+            .event_signature(signature)
+            .from_block(self.next_block)
+            .to_block(to_block);
+        Ok(self.provider.get_logs(&filter).await?)
+    }
+
+    fn add_v2_pair(&mut self, factory: Address, event: &PairCreated) {
+        let Some(fork) = self
+            .config
+            .v2_forks
+            .iter()
+            .find(|fork| fork.factory == factory)
+        else {
+            return;
+        };
+        let weth = self.config.weth;
+        let (token, is_weth_token0) = if event.token0 == weth {
+            (event.token1, true)
+        } else if event.token1 == weth {
+            (event.token0, false)
+        } else {
+            return;
+        };
+        self.pools.v2.entry(token).or_default().push(V2PoolInfo {
+            v2_pool: event.pair,
+            token,
+            is_weth_token0,
+            fork: fork.clone(),
+        });
+    }
+
+    fn add_v3_pool(&mut self, event: &PoolCreated) {
+        let weth = self.config.weth;
+        let token = if event.token0 == weth {
+            event.token1
+        } else if event.token1 == weth {
+            event.token0
+        } else {
+            return;
+        };
+        self.pools.v3.entry(token).or_default().push(event.pool);
+    }
+
+    /// Pairs every liquid V3 pool with the most liquid V2 pool of the same
+    /// token.
+    async fn liquid_pools(
+        &self,
+    ) -> Result<HashMap<Address, V2PoolInfo>, KazukaError> {
+        let weth = IWETH::new(self.config.weth, self.provider.clone());
+        let min_liquidity = self.config.min_weth_liquidity;
+        let mut pools = HashMap::new();
+
+        for (token, v3_pools) in &self.pools.v3 {
+            let Some(v2_pools) = self.pools.v2.get(token) else {
+                continue;
+            };
+            let mut best_v2_pool = None;
+            for v2_pool in v2_pools {
+                let balance = weth.balanceOf(v2_pool.v2_pool).call().await?;
+                if balance >= min_liquidity
+                    && best_v2_pool
+                        .as_ref()
+                        .is_none_or(|(_, best)| balance > *best)
+                {
+                    best_v2_pool = Some((v2_pool, balance));
+                }
+            }
+            let Some((v2_pool, _)) = best_v2_pool else {
+                continue;
+            };
+            for v3_pool in v3_pools {
+                let balance = weth.balanceOf(*v3_pool).call().await?;
+                if balance >= min_liquidity {
+                    pools.insert(*v3_pool, v2_pool.clone());
+                }
+            }
+        }
+        Ok(pools)
+    }
+}
+
+/// Writes the pools, keyed by the V3 pool address as returned by
+/// [PoolDiscovery::refresh], to a CSV file in the format of the
+/// [pools_file](crate::config::ArbitrageConfig::pools_file), sorted by token.
+pub fn write_pools_csv(
+    path: impl AsRef<Path>,
+    pools: &HashMap<Address, V2PoolInfo>,
+) -> Result<(), KazukaError> {
+    let path = path.as_ref();
+    let csv_error = |e: csv::Error| {
+        KazukaError::CsvError(
+            path.display().to_string(),
+            e.to_string(),
+        )
+    };
+
+    let mut records: Vec<V2V3PoolRecord> = pools
+        .iter()
+        .map(|(v3_pool, info)| V2V3PoolRecord {
+            token_address: info.token,
+            v2_pool: info.v2_pool,
+            v3_pool: *v3_pool,
+            is_weth_token0: info.is_weth_token0,
+            v2_factory: Some(info.fork.factory),
+        })
+        .collect();
+    records.sort_by_key(|record| (record.token_address, record.v3_pool));
+
+    let mut writer = csv::Writer::from_path(path).map_err(csv_error)?;
+    for record in records {
+        writer.serialize(record).map_err(csv_error)?;
+    }
+    writer.flush().map_err(|e| csv_error(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::Ethereum,
+        primitives::aliases::{I24, U24},
+        providers::{ProviderBuilder, RootProvider},
+        transports::mock::Asserter,
+    };
+
+    use super::*;
+
+    fn discovery() -> PoolDiscovery<RootProvider<Ethereum>> {
+        let provider =
+            ProviderBuilder::default().connect_mocked_client(Asserter::new());
+        PoolDiscovery::new(
+            Arc::new(provider),
+            PoolDiscoveryConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_collect_weth_pools() {
+        let mut discovery = discovery();
+        let token = Address::repeat_byte(1);
+
+        let factory = V2Fork::uniswap_v2().factory;
+        discovery.add_v2_pair(
+            factory,
+            &PairCreated {
+                token0: WETH,
+                token1: token,
+                pair: Address::repeat_byte(2),
+                allPairsLength: U256::from(1),
+            },
+        );
+        discovery.add_v2_pair(
+            factory,
+            &PairCreated {
+                token0: token,
+                token1: Address::repeat_byte(3),
+                pair: Address::repeat_byte(4),
+                allPairsLength: U256::from(2),
+            },
+        );
+        // Pairs of unknown factories are ignored.
+        discovery.add_v2_pair(
+            Address::repeat_byte(6),
+            &PairCreated {
+                token0: WETH,
+                token1: token,
+                pair: Address::repeat_byte(7),
+                allPairsLength: U256::from(1),
+            },
+        );
+        discovery.add_v3_pool(&PoolCreated {
+            token0: token,
+            token1: WETH,
+            fee: U24::from(3000),
+            tickSpacing: I24::try_from(60).unwrap(),
+            pool: Address::repeat_byte(5),
+        });
+
+        let v2_pools = &discovery.pools.v2[&token];
+        assert_eq!(v2_pools.len(), 1);
+        assert!(v2_pools[0].is_weth_token0);
+        assert_eq!(v2_pools[0].fork, V2Fork::uniswap_v2());
+        assert_eq!(
+            discovery.pools.v3[&token],
+            [Address::repeat_byte(5)]
+        );
+        assert!(discovery.is_stale());
+    }
+
+    #[test]
+    fn test_write_pools_csv() {
+        let path = std::env::temp_dir().join("kazuka-discovered-pools.csv");
+        let info = V2PoolInfo {
+            v2_pool: Address::repeat_byte(2),
+            token: Address::repeat_byte(1),
+            is_weth_token0: true,
+            fork: V2Fork::sushiswap(),
+        };
+        let pools = HashMap::from([(Address::repeat_byte(5), info)]);
+
+        write_pools_csv(&path, &pools).unwrap();
+
+        let records: Vec<V2V3PoolRecord> = csv::Reader::from_path(&path)
+            .unwrap()
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].v3_pool,
+            Address::repeat_byte(5)
+        );
+        assert_eq!(
+            records[0].v2_pool,
+            Address::repeat_byte(2)
+        );
+        assert_eq!(
+            records[0].v2_factory,
+            Some(V2Fork::sushiswap().factory)
+        );
+        assert!(records[0].is_weth_token0);
+    }
+}
@@ -1,57 +1,24 @@
-use alloy::{rpc::types::mev::MevSendBundle, signers::Signer};
-use async_trait::async_trait;
-use jsonrpsee::http_client::HttpClientBuilder;
-use kazuka_core::{error::KazukaError, types::Executor};
-use kazuka_mev_share::rpc::{MevApiClient, middleware::AuthLayer};
-use tower::ServiceBuilder;
+use std::sync::Arc;
 
-/// An executor that sends bundles to the MEV-share matchmaker.
-pub struct MevShareExecutor {
-    mev_share_client: Box<dyn MevApiClient + Send + Sync>,
-    /// Whether to actually submit bundles or just log them.
-    dry_run: bool,
-}
-
-impl MevShareExecutor {
-    pub fn new(
-        url: String,
-        dry_run: bool,
-        signer: impl Signer + Clone + Send + Sync + 'static,
-    ) -> Self {
-        let http_middleware =
-            ServiceBuilder::new().layer(AuthLayer::new(signer));
-
-        let client = HttpClientBuilder::default()
-            .set_http_middleware(http_middleware)
-            .build(url)
-            .expect("Failed to build HTTP client");
+use alloy::signers::Signer;
+use kazuka_core::executors::flashbots_bundle_executor::signed_client;
+use kazuka_mev_share::rpc::{FlashbotsApiClient, MevApiClient};
 
-        Self {
-            mev_share_client: Box::new(client),
-            dry_run,
-        }
-    }
+/// Builds a client of the MEV-share matchmaker at the given URL, which signs
+/// requests with the given signer.
+pub fn mev_share_client(
+    url: String,
+    signer: impl Signer + Clone + Send + Sync + 'static,
+) -> Box<dyn MevApiClient + Send + Sync> {
+    Box::new(signed_client(url, signer))
 }
 
-#[async_trait]
-impl Executor<MevSendBundle> for MevShareExecutor {
-    async fn execute(&self, action: MevSendBundle) -> Result<(), KazukaError> {
-        if self.dry_run {
-            tracing::info!(
-                "Submitting bundle [DRY RUN]: {:?}",
-                action
-            );
-            return Ok(());
-        } else {
-            tracing::info!("Submitting bundle: {:?}", action);
-        }
-
-        let body = self.mev_share_client.send_bundle(action).await;
-        match body {
-            Ok(body) => tracing::info!("Bundle response: {:?}", body),
-            Err(err) => tracing::error!("Bundle error: {:?}", err),
-        };
-
-        Ok(())
-    }
+/// Builds a client of the `flashbots_` methods of the relay at the given
+/// URL, e.g. to poll bundle stats, which signs requests with the given
+/// signer.
+pub fn flashbots_client(
+    url: String,
+    signer: impl Signer + Clone + Send + Sync + 'static,
+) -> Arc<dyn FlashbotsApiClient + Send + Sync> {
+    Arc::new(signed_client(url, signer))
 }
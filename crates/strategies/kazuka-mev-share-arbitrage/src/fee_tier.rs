@@ -0,0 +1,718 @@
+//! Arbitrage between Uniswap V3 pools of the same WETH pair at different fee
+//! tiers, backrunning MEV-share hints which moved one of them.
+//!
+//! Both swaps are executed by a single multihop swap of the Uniswap router,
+//! from WETH back to WETH, which reverts unless it breaks even.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use alloy::{
+    network::{Ethereum, TransactionBuilder},
+    primitives::{Address, Bytes, U256, address, aliases::U24},
+    providers::Provider,
+    rpc::types::mev::ProtocolVersion,
+    sol,
+};
+use async_trait::async_trait;
+use kazuka_amm_math::{arbitrage, v2::Reserves};
+use kazuka_core::{
+    clock::{Clock, system_clock},
+    error::KazukaError,
+    signer::SignerProvider,
+    strategies::backrun::{
+        BackrunConfig, BackrunStrategy, Opportunity, OpportunityEvaluator,
+    },
+    types::Strategy,
+};
+
+use crate::{
+    config::{ArbitrageConfig, BLOCK_TIME},
+    discovery::{UNISWAP_V3_FACTORY, WETH},
+    signing::{self, TxContext},
+    sizing::{self, IUniswapV3PoolState},
+    types::{Action, Event},
+};
+
+sol! {
+    #[sol(rpc)]
+    interface IUniswapV3Factory {
+        function getPool(
+            address tokenA,
+            address tokenB,
+            uint24 fee
+        ) external view returns (address pool);
+    }
+
+    /// Multihop swaps of the Uniswap V3 router, which are missing from the
+    /// generated bindings.
+    #[sol(rpc)]
+    interface ISwapRouterMultihop {
+        struct ExactInputParams {
+            bytes path;
+            address recipient;
+            uint256 deadline;
+            uint256 amountIn;
+            uint256 amountOutMinimum;
+        }
+
+        function exactInput(
+            ExactInputParams calldata params
+        ) external payable returns (uint256 amountOut);
+    }
+}
+
+pub const SWAP_ROUTER: Address =
+    address!("0xE592427A0AEce92De3Edee1F18E0157C05861564");
+
+/// Fee tiers of Uniswap V3, in hundredths of a bip.
+pub const FEE_TIERS: [u32; 4] = [100, 500, 3_000, 10_000];
+
+/// Maximum number of pools cached by the [FeeTierEvaluator], whose hints
+/// touch any contract.
+pub const MAX_CACHED_POOLS: usize = 10_000;
+
+/// Name of the strategy its bundles are booked under, see
+/// [BundleOrigin](kazuka_core::pnl::BundleOrigin).
+pub const STRATEGY_NAME: &str = "mev-share-fee-tier-arbitrage";
+
+/// Deadline of swaps, which must be included within the window.
+pub(crate) fn swap_deadline(inclusion_window: u64) -> U256 {
+    let deadline = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        + BLOCK_TIME * inclusion_window as u32;
+    U256::from(deadline.as_secs())
+}
+
+/// A V3 pool paired with WETH.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct V3Pool {
+    pub pool: Address,
+    /// The other token of the pool.
+    pub token: Address,
+    pub is_weth_token0: bool,
+    /// Fee tier in hundredths of a bip.
+    pub fee: u32,
+}
+
+/// A pool with its pools of the other fee tiers.
+#[derive(Clone, Debug)]
+struct PoolGroup {
+    pool: V3Pool,
+    siblings: Vec<V3Pool>,
+}
+
+/// Buys the token with WETH on the first pool, and sells it on the second
+/// one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    pub buy: V3Pool,
+    pub sell: V3Pool,
+    pub amount_in: U256,
+    pub profit: U256,
+}
+
+impl Route {
+    /// Path of the multihop swap from WETH back to WETH.
+    pub fn path(&self) -> Bytes {
+        let mut path = Vec::with_capacity(66);
+        path.extend_from_slice(WETH.as_slice());
+        path.extend_from_slice(&self.buy.fee.to_be_bytes()[1..]);
+        path.extend_from_slice(self.buy.token.as_slice());
+        path.extend_from_slice(&self.sell.fee.to_be_bytes()[1..]);
+        path.extend_from_slice(WETH.as_slice());
+        path.into()
+    }
+}
+
+/// Computes the optimal routes after the target transaction moved the price
+/// of the token on the hinted pool by each of the impacts, in both
+/// directions, keeping the first `max_routes` distinct profitable ones.
+///
+/// All reserves swap WETH to the token.
+pub fn best_routes(
+    hinted: &(V3Pool, Reserves),
+    siblings: &[(V3Pool, Reserves)],
+    price_impacts_bps: &[u64],
+    max_routes: usize,
+) -> Vec<Route> {
+    let (hinted_pool, hinted_reserves) = hinted;
+    let mut routes: Vec<Route> = Vec::new();
+    for &impact in price_impacts_bps {
+        for (pool, reserves) in siblings {
+            // The token got cheaper on the hinted pool, or more expensive.
+            let legs = [
+                (
+                    (
+                        hinted_pool,
+                        hinted_reserves.with_price_impact(impact),
+                    ),
+                    (pool, reserves.reversed()),
+                ),
+                (
+                    (pool, *reserves),
+                    (
+                        hinted_pool,
+                        hinted_reserves.reversed().with_price_impact(impact),
+                    ),
+                ),
+            ];
+            for ((buy, first), (sell, second)) in legs {
+                if routes.len() == max_routes {
+                    return routes;
+                }
+                let amount_in = arbitrage::optimal_amount_in(&first, &second);
+                let profit = arbitrage::profit(&first, &second, amount_in);
+                let route = Route {
+                    buy: buy.clone(),
+                    sell: sell.clone(),
+                    amount_in,
+                    profit,
+                };
+                if !profit.is_zero()
+                    && !routes.iter().any(|r| {
+                        r.buy == route.buy && r.amount_in == route.amount_in
+                    })
+                {
+                    routes.push(route);
+                }
+            }
+        }
+    }
+    routes
+}
+
+/// Routes of a sized opportunity.
+#[derive(Clone, Debug)]
+struct SizedRoutes {
+    routes: Vec<Route>,
+    /// Fields shared by the txs of the routes, `None` on dry runs.
+    context: Option<TxContext>,
+}
+
+/// Backruns hints moving a V3 WETH pool with the best [routes](best_routes)
+/// between its fee tiers.
+pub struct FeeTierEvaluator<P: Provider> {
+    /// Exposes Ethereum JSON-RPC methods.
+    provider: Arc<P>,
+    /// Receives the proceeds of the swaps.
+    recipient: Address,
+    /// Pools by address, `None` if the address isn't a V3 WETH pool. Holds
+    /// at most [MAX_CACHED_POOLS] pools.
+    pools: HashMap<Address, Option<PoolGroup>>,
+    /// Routes of the last sized opportunity of every pool.
+    routes: HashMap<Address, SizedRoutes>,
+    /// Whether to sign real transactions or just synthesize sample txs.
+    dry_run: bool,
+    /// Signs the swap txs offline instead of the node.
+    signers: Option<Arc<dyn SignerProvider>>,
+    /// Backrun parameters, the pool file is unused.
+    config: ArbitrageConfig,
+}
+
+impl<P: Provider> FeeTierEvaluator<P> {
+    pub fn new(provider: Arc<P>, recipient: Address, dry_run: bool) -> Self {
+        Self {
+            provider,
+            recipient,
+            pools: HashMap::new(),
+            routes: HashMap::new(),
+            dry_run,
+            signers: None,
+            config: ArbitrageConfig::default(),
+        }
+    }
+
+    /// Signs the swap txs with the transactions signer of the `signers`,
+    /// see [crate::signing].
+    pub fn set_signers(&mut self, signers: Arc<dyn SignerProvider>) {
+        self.signers = Some(signers);
+    }
+
+    /// Finds the pool and its pools of the other fee tiers, caching them.
+    ///
+    /// Only definitive answers are cached: calls reverting or returning
+    /// nothing tell the address isn't a V3 pool, while transient errors
+    /// are retried on the next hint.
+    async fn pool_group(&mut self, address: Address) -> Option<PoolGroup> {
+        if let Some(group) = self.pools.get(&address) {
+            return group.clone();
+        }
+        let group = match self.fetch_pool_group(address).await {
+            Ok(group) => group,
+            Err(e) if e.classify().is_retryable() => {
+                tracing::warn!(
+                    "Error fetching pool {:?}: {}",
+                    address,
+                    e
+                );
+                return None;
+            }
+            Err(e) => {
+                tracing::debug!("{:?} is not a V3 pool: {}", address, e);
+                None
+            }
+        };
+        // Forgetting the pools is cheaper than tracking their last use, as
+        // the WETH pools are fetched again on their next hint.
+        if self.pools.len() >= MAX_CACHED_POOLS {
+            self.pools.clear();
+        }
+        self.pools.insert(address, group.clone());
+        group
+    }
+
+    async fn fetch_pool_group(
+        &self,
+        address: Address,
+    ) -> Result<Option<PoolGroup>, KazukaError> {
+        let state = IUniswapV3PoolState::new(address, self.provider.clone());
+        let token0 = state.token0().call().await?;
+        let token1 = state.token1().call().await?;
+        let (token, is_weth_token0) = if token0 == WETH {
+            (token1, true)
+        } else if token1 == WETH {
+            (token0, false)
+        } else {
+            return Ok(None);
+        };
+        let fee = state.fee().call().await?.to::<u32>();
+
+        let factory = IUniswapV3Factory::new(
+            UNISWAP_V3_FACTORY,
+            self.provider.clone(),
+        );
+        let mut siblings = vec![];
+        for sibling_fee in FEE_TIERS.into_iter().filter(|f| *f != fee) {
+            let pool = factory
+                .getPool(token0, token1, U24::from(sibling_fee))
+                .call()
+                .await?;
+            if !pool.is_zero() {
+                siblings.push(V3Pool {
+                    pool,
+                    token,
+                    is_weth_token0,
+                    fee: sibling_fee,
+                });
+            }
+        }
+
+        Ok(Some(PoolGroup {
+            pool: V3Pool {
+                pool: address,
+                token,
+                is_weth_token0,
+                fee,
+            },
+            siblings,
+        }))
+    }
+
+    async fn reserves(
+        &self,
+        pool: &V3Pool,
+    ) -> Result<(V3Pool, Reserves), KazukaError> {
+        let reserves = sizing::fetch_v3_reserves(
+            self.provider.clone(),
+            pool.pool,
+            pool.is_weth_token0,
+        )
+        .await?;
+        Ok((pool.clone(), reserves))
+    }
+
+    /// Best routes backrunning a transaction which touched the pool group.
+    async fn routes(
+        &self,
+        group: &PoolGroup,
+    ) -> Result<Vec<Route>, KazukaError> {
+        let hinted = self.reserves(&group.pool).await?;
+        let mut siblings = vec![];
+        for sibling in &group.siblings {
+            siblings.push(self.reserves(sibling).await?);
+        }
+        Ok(best_routes(
+            &hinted,
+            &siblings,
+            &self.config.price_impacts_bps,
+            self.config.max_bundles_per_event,
+        ))
+    }
+
+    /// Signs the swap of the route, with the fields of the `context`.
+    async fn arbitrage_tx(
+        &self,
+        context: &TxContext,
+        route: &Route,
+    ) -> Result<Bytes, KazukaError> {
+        let router =
+            ISwapRouterMultihop::new(SWAP_ROUTER, self.provider.clone());
+
+        let mut tx = router
+            .exactInput(ISwapRouterMultihop::ExactInputParams {
+                path: route.path(),
+                recipient: self.recipient,
+                deadline: swap_deadline(self.config.inclusion_window),
+                amountIn: route.amount_in,
+                amountOutMinimum: route.amount_in,
+            })
+            .into_transaction_request();
+        tx.set_gas_limit(400000);
+        context.apply(&mut tx);
+
+        signing::sign_tx(
+            self.provider.as_ref(),
+            self.signers.as_deref(),
+            tx,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<P: Provider> OpportunityEvaluator for FeeTierEvaluator<P> {
+    /// Pools are discovered lazily from the hints.
+    async fn is_target(&mut self, pool: Address) -> bool {
+        self.pool_group(pool)
+            .await
+            .is_some_and(|group| !group.siblings.is_empty())
+    }
+
+    /// Inputs of the best routes, instead of the given sizes.
+    async fn sizes(
+        &mut self,
+        opportunity: &Opportunity<'_>,
+        _sizes: &[U256],
+    ) -> Result<Vec<U256>, KazukaError> {
+        let Some(group) = self.pool_group(opportunity.pool).await else {
+            return Ok(vec![]);
+        };
+        let routes = self.routes(&group).await?;
+        let sizes = routes.iter().map(|route| route.amount_in).collect();
+        // The routes are alternatives, so their txs share the nonce.
+        let context = if self.dry_run || routes.is_empty() {
+            None
+        } else {
+            Some(
+                TxContext::fetch(
+                    self.provider.as_ref(),
+                    self.signers.as_deref(),
+                )
+                .await?,
+            )
+        };
+        self.routes.insert(
+            opportunity.pool,
+            SizedRoutes { routes, context },
+        );
+        Ok(sizes)
+    }
+
+    async fn backrun_tx(
+        &self,
+        opportunity: &Opportunity<'_>,
+        size: U256,
+    ) -> Result<Option<Bytes>, KazukaError> {
+        let Some(sized) = self.routes.get(&opportunity.pool) else {
+            return Ok(None);
+        };
+        let Some(route) = sized.routes.iter().find(|r| r.amount_in == size)
+        else {
+            return Ok(None);
+        };
+        tracing::info!(
+            "Backrunning {:?} with {:?} -> {:?}, size {}",
+            opportunity.hint.hash,
+            route.buy.pool,
+            route.sell.pool,
+            route.amount_in
+        );
+        match &sized.context {
+            Some(context) => Ok(Some(
+                self.arbitrage_tx(context, route).await?,
+            )),
+            None => Ok(Some(Bytes::from_static(b"sample-tx"))),
+        }
+    }
+
+    fn expected_profit(
+        &self,
+        opportunity: &Opportunity<'_>,
+        size: U256,
+    ) -> U256 {
+        self.routes
+            .get(&opportunity.pool)
+            .and_then(|sized| sized.routes.iter().find(|r| r.amount_in == size))
+            .map_or(U256::ZERO, |route| route.profit)
+    }
+}
+
+/// Arbitrages V3 pools of the same pair at different fee tiers, by running
+/// a [FeeTierEvaluator] in a [BackrunStrategy].
+///
+/// The router pulls WETH from the sender of the txs, see [TxContext], which
+/// must have approved it to the router, and sends the proceeds to the
+/// recipient.
+pub struct MevShareUniswapV3FeeTierArbitrage<P: Provider> {
+    backrun: BackrunStrategy<FeeTierEvaluator<P>, Ethereum>,
+    /// Protocol version of the submitted bundles.
+    protocol_version: ProtocolVersion,
+    /// Backrun parameters, the pool file is unused.
+    config: ArbitrageConfig,
+    /// Tells the age of the hints.
+    clock: Arc<dyn Clock>,
+}
+
+impl<P: Provider + 'static> MevShareUniswapV3FeeTierArbitrage<P> {
+    pub fn new(provider: Arc<P>, recipient: Address, dry_run: bool) -> Self {
+        let evaluator =
+            FeeTierEvaluator::new(provider.clone(), recipient, dry_run);
+        Self {
+            backrun: BackrunStrategy::<_, Ethereum>::new(provider, evaluator),
+            protocol_version: ProtocolVersion::V0_1,
+            config: ArbitrageConfig::default(),
+            clock: system_clock(),
+        }
+        .with_config(ArbitrageConfig::default())
+    }
+
+    /// Sets the backrun parameters.
+    pub fn with_config(mut self, config: ArbitrageConfig) -> Self {
+        self.backrun.evaluator_mut().config = config.clone();
+        self.config = config;
+        let backrun_config = self.backrun_config();
+        self.backrun = self.backrun.with_config(backrun_config);
+        self
+    }
+
+    /// Signs the swap txs with the transactions signer of the `signers`,
+    /// instead of the wallet of the provider.
+    pub fn with_signers(mut self, signers: Arc<dyn SignerProvider>) -> Self {
+        self.backrun.evaluator_mut().set_signers(signers);
+        self
+    }
+
+    /// Sets the clock the age of the hints is measured with.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Sets the protocol version of the submitted bundles, which must be
+    /// accepted by the relay.
+    pub fn with_protocol_version(
+        mut self,
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        self.protocol_version = protocol_version;
+        let backrun_config = self.backrun_config();
+        self.backrun = self.backrun.with_config(backrun_config);
+        self
+    }
+
+    /// Bundle parameters of the backrun config, whose sizes are picked by
+    /// the evaluator.
+    fn backrun_config(&self) -> BackrunConfig {
+        BackrunConfig {
+            max_bundles_per_event: self.config.max_bundles_per_event,
+            inclusion_window: self.config.inclusion_window,
+            protocol_version: self.protocol_version.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait]
+impl<P: Provider> Strategy<Event, Action>
+    for MevShareUniswapV3FeeTierArbitrage<P>
+{
+    async fn sync_state(&mut self) -> Result<(), KazukaError> {
+        self.backrun.sync_state().await
+    }
+
+    async fn process_event(&mut self, event: Event) -> Vec<Action> {
+        match event {
+            Event::MevShareEvent(hint) => {
+                let age = hint.age_on(self.clock.as_ref());
+                if age > self.config.max_hint_age() {
+                    tracing::debug!(
+                        "Skipping stale hint {:?} received {:?} ago",
+                        hint.inner.hash,
+                        age
+                    );
+                    return vec![];
+                }
+                self.backrun
+                    .process_event(hint.inner)
+                    .await
+                    .into_iter()
+                    .map(|bundle| {
+                        Action::SubmitBundle(bundle.attributed(STRATEGY_NAME))
+                    })
+                    .collect()
+            }
+            Event::Log(_) | Event::BundleStatus(_) | Event::NewBlock(_) => {
+                vec![]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{B256, utils::parse_ether},
+        providers::{ProviderBuilder, RootProvider},
+        transports::mock::Asserter,
+    };
+    use kazuka_mev_share::sse;
+
+    use super::*;
+
+    fn pool(byte: u8, fee: u32) -> V3Pool {
+        V3Pool {
+            pool: Address::repeat_byte(byte),
+            token: Address::repeat_byte(0xaa),
+            is_weth_token0: false,
+            fee,
+        }
+    }
+
+    #[test]
+    fn test_path() {
+        let route = Route {
+            buy: pool(1, 500),
+            sell: pool(2, 3_000),
+            amount_in: U256::ZERO,
+            profit: U256::ZERO,
+        };
+
+        let path = route.path();
+
+        assert_eq!(path.len(), 66);
+        assert_eq!(&path[..20], WETH.as_slice());
+        assert_eq!(&path[20..23], &[0x00, 0x01, 0xf4]);
+        assert_eq!(
+            &path[23..43],
+            Address::repeat_byte(0xaa).as_slice()
+        );
+        assert_eq!(&path[43..46], &[0x00, 0x0b, 0xb8]);
+        assert_eq!(&path[46..], WETH.as_slice());
+    }
+
+    #[test]
+    fn test_best_routes() {
+        let reserves = Reserves::new(
+            parse_ether("1000").unwrap(),
+            parse_ether("1000").unwrap(),
+            500,
+        );
+        let hinted = (pool(1, 500), reserves);
+        let siblings = [(
+            pool(2, 100),
+            Reserves {
+                fee: 100,
+                ..reserves
+            },
+        )];
+
+        let routes = best_routes(&hinted, &siblings, &[3, 100], 4);
+
+        // A 0.03% move doesn't cover the fees, a 1% move is arbitraged in
+        // both directions.
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].buy, pool(1, 500));
+        assert_eq!(routes[0].sell, pool(2, 100));
+        assert_eq!(routes[1].buy, pool(2, 100));
+        assert_eq!(routes[1].sell, pool(1, 500));
+        assert!(routes.iter().all(|route| !route.profit.is_zero()));
+    }
+
+    #[tokio::test]
+    async fn test_caches_only_definitive_pools() {
+        let asserter = Asserter::new();
+        let provider: RootProvider<Ethereum> =
+            ProviderBuilder::default().connect_mocked_client(asserter.clone());
+        let mut evaluator = FeeTierEvaluator::new(
+            Arc::new(provider),
+            Address::repeat_byte(0xbb),
+            true,
+        );
+        let address = Address::repeat_byte(1);
+
+        // The node is unreachable, the pool is fetched again on the next
+        // hint.
+        assert!(evaluator.pool_group(address).await.is_none());
+        assert!(evaluator.pools.is_empty());
+
+        // A pool of another pair is never fetched again, the ABI encoded
+        // `token0` and `token1` results.
+        asserter.push_success(&Address::repeat_byte(2).into_word());
+        asserter.push_success(&Address::repeat_byte(3).into_word());
+        assert!(evaluator.pool_group(address).await.is_none());
+        assert!(evaluator.pool_group(address).await.is_none());
+        assert_eq!(
+            evaluator.pools.get(&address).map(Option::is_none),
+            Some(true)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backruns_sized_routes() {
+        let provider: RootProvider<Ethereum> =
+            ProviderBuilder::default().connect_mocked_client(Asserter::new());
+        let mut evaluator = FeeTierEvaluator::new(
+            Arc::new(provider),
+            Address::repeat_byte(0xbb),
+            true,
+        );
+        let route = Route {
+            buy: pool(1, 500),
+            sell: pool(2, 3_000),
+            amount_in: parse_ether("1").unwrap(),
+            profit: U256::ONE,
+        };
+        evaluator.routes.insert(
+            route.buy.pool,
+            SizedRoutes {
+                routes: vec![route.clone()],
+                context: None,
+            },
+        );
+        let hint = sse::Event {
+            hash: B256::repeat_byte(0xaa),
+            logs: vec![],
+            transactions: vec![],
+        };
+        let opportunity = Opportunity {
+            hint: &hint,
+            pool: route.buy.pool,
+            block_number: 100,
+        };
+
+        assert_eq!(
+            evaluator
+                .backrun_tx(&opportunity, parse_ether("1").unwrap())
+                .await
+                .unwrap(),
+            Some(Bytes::from_static(b"sample-tx"))
+        );
+        assert_eq!(
+            evaluator.expected_profit(&opportunity, parse_ether("1").unwrap()),
+            U256::ONE
+        );
+        // Only the sized routes are backrun.
+        assert_eq!(
+            evaluator
+                .backrun_tx(&opportunity, parse_ether("2").unwrap())
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Adapts the bids of the strategy to the outcomes of its bundles, reported
+//! by the
+//! [BundleStatsEventSource](kazuka_core::event_sources::bundle_stats_event_source::BundleStatsEventSource)
+//! as [Event::BundleStatus](crate::types::Event::BundleStatus):
+//! - bids (grid sizes or price impacts) which never land are dropped,
+//! - pools where bundles rarely land are contested, and pay the builder more,
+//! - the inclusion window shrinks to the delays the bundles actually land with.
+//!
+//! Outcomes are halved every
+//! [half_life_blocks](FeedbackConfig::half_life_blocks), so dropped bids are
+//! tried again once their expiries have aged, as the competition changes.
+
+use std::collections::{HashMap, VecDeque};
+
+use alloy::primitives::{Address, BlockNumber, TxHash, U256};
+use kazuka_core::event_sources::bundle_stats_event_source::BundleStatus;
+use serde::Deserialize;
+
+/// Blocks after which submissions without an outcome are forgotten, e.g.
+/// bundles discarded by the simulation.
+const MAX_PENDING_AGE: u64 = 256;
+
+/// Parameters of the [BundleFeedback].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct FeedbackConfig {
+    /// Outcomes of a bid or a pool needed before judging it.
+    pub min_samples: u64,
+    /// Pools landing fewer bundles (in percent) are contested.
+    pub contested_landing_rate: u8,
+    /// Percentage of the profit paid to the builder in contested pools.
+    pub contested_payment_percentage: u8,
+    /// Number of recent landings the inclusion window is derived from.
+    pub recent_landings: usize,
+    /// Blocks after which the outcomes of the bids and pools are halved.
+    pub half_life_blocks: u64,
+}
+
+impl Default for FeedbackConfig {
+    fn default() -> Self {
+        Self {
+            min_samples: 10,
+            contested_landing_rate: 10,
+            contested_payment_percentage: 95,
+            recent_landings: 20,
+            half_life_blocks: 300,
+        }
+    }
+}
+
+/// What a bundle bids for: a size of the grid, or a price impact of the
+/// target transaction with optimal sizing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Bid {
+    Size(U256),
+    PriceImpact(u64),
+}
+
+/// Number of landed and expired bundles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Outcomes {
+    pub landed: u64,
+    pub expired: u64,
+}
+
+impl Outcomes {
+    pub fn total(&self) -> u64 {
+        self.landed + self.expired
+    }
+
+    fn record(&mut self, landed: bool) {
+        if landed {
+            self.landed += 1;
+        } else {
+            self.expired += 1;
+        }
+    }
+
+    /// Halves the outcomes `times` times.
+    fn decay(&mut self, times: u64) {
+        let shift = times.min(u64::BITS as u64 - 1);
+        self.landed >>= shift;
+        self.expired >>= shift;
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Submission {
+    pool: Address,
+    bid: Bid,
+    target_block: BlockNumber,
+}
+
+/// Outcomes of the bundles of the strategy.
+#[derive(Debug, Default)]
+pub struct BundleFeedback {
+    config: FeedbackConfig,
+    /// Submissions by the hash of their backrun transaction.
+    pending: HashMap<TxHash, Submission>,
+    bids: HashMap<Bid, Outcomes>,
+    pools: HashMap<Address, Outcomes>,
+    /// Blocks between the target block and the inclusion of the latest
+    /// landed bundles.
+    delays: VecDeque<u64>,
+    /// Block the outcomes were last halved at.
+    decayed_at: Option<BlockNumber>,
+}
+
+impl BundleFeedback {
+    pub fn new(config: FeedbackConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Remembers a bundle backrunning a hint on the pool with the
+    /// transaction, to learn from its outcome.
+    pub fn record_submission(
+        &mut self,
+        tx_hash: TxHash,
+        pool: Address,
+        bid: Bid,
+        target_block: BlockNumber,
+    ) {
+        self.pending.insert(
+            tx_hash,
+            Submission {
+                pool,
+                bid,
+                target_block,
+            },
+        );
+    }
+
+    /// Forgets submissions without an outcome long after their target
+    /// block, and ages the outcomes.
+    pub fn prune(&mut self, block_number: BlockNumber) {
+        self.pending.retain(|_, submission| {
+            submission.target_block + MAX_PENDING_AGE > block_number
+        });
+        self.decay(block_number);
+    }
+
+    /// Halves the outcomes once per half-life elapsed since they were last
+    /// halved.
+    fn decay(&mut self, block_number: BlockNumber) {
+        let half_life = self.config.half_life_blocks.max(1);
+        let decayed_at = *self.decayed_at.get_or_insert(block_number);
+        let half_lives = block_number.saturating_sub(decayed_at) / half_life;
+        if half_lives == 0 {
+            return;
+        }
+        self.decayed_at = Some(decayed_at + half_lives * half_life);
+        for outcomes in self.bids.values_mut().chain(self.pools.values_mut()) {
+            outcomes.decay(half_lives);
+        }
+        self.bids.retain(|_, outcomes| outcomes.total() > 0);
+        self.pools.retain(|_, outcomes| outcomes.total() > 0);
+    }
+
+    /// Learns from the outcome of a bundle. Returns whether the bundle was
+    /// submitted by the strategy.
+    pub fn record_status(&mut self, status: &BundleStatus) -> bool {
+        let (tx_hashes, landed_block) = match status {
+            BundleStatus::Included {
+                tx_hashes,
+                block_number,
+                ..
+            } => (tx_hashes, Some(*block_number)),
+            BundleStatus::Expired { tx_hashes, .. } => (tx_hashes, None),
+            BundleStatus::Simulated { .. } => return false,
+        };
+        let Some(submission) = tx_hashes
+            .iter()
+            .find_map(|tx_hash| self.pending.remove(tx_hash))
+        else {
+            return false;
+        };
+
+        let landed = landed_block.is_some();
+        self.bids.entry(submission.bid).or_default().record(landed);
+        self.pools
+            .entry(submission.pool)
+            .or_default()
+            .record(landed);
+        if let Some(block_number) = landed_block {
+            self.delays.push_back(
+                block_number.saturating_sub(submission.target_block),
+            );
+            if self.delays.len() > self.config.recent_landings {
+                self.delays.pop_front();
+            }
+        }
+        true
+    }
+
+    pub fn bid_outcomes(&self, bid: Bid) -> Outcomes {
+        self.bids.get(&bid).copied().unwrap_or_default()
+    }
+
+    pub fn pool_outcomes(&self, pool: Address) -> Outcomes {
+        self.pools.get(&pool).copied().unwrap_or_default()
+    }
+
+    /// Whether the bid never landed in enough attempts to stop bidding it.
+    pub fn is_dead(&self, bid: Bid) -> bool {
+        let outcomes = self.bid_outcomes(bid);
+        outcomes.landed == 0 && outcomes.expired >= self.config.min_samples
+    }
+
+    /// Whether bundles rarely land on the pool, so others are competing for
+    /// the same opportunities.
+    pub fn is_contested(&self, pool: Address) -> bool {
+        let outcomes = self.pool_outcomes(pool);
+        outcomes.total() >= self.config.min_samples
+            && outcomes.landed * 100
+                < outcomes.total()
+                    * u64::from(self.config.contested_landing_rate)
+    }
+
+    /// Percentage of the profit paid to the builder for backruns on the
+    /// pool, raised in contested pools.
+    pub fn payment_percentage(&self, pool: Address, default: u8) -> u8 {
+        if self.is_contested(pool) {
+            default.max(self.config.contested_payment_percentage)
+        } else {
+            default
+        }
+    }
+
+    /// Inclusion window covering the delays of the recent landings, at most
+    /// `max_window` blocks. Until enough bundles landed, `max_window`.
+    pub fn inclusion_window(&self, max_window: u64) -> u64 {
+        if (self.delays.len() as u64) < self.config.min_samples {
+            return max_window;
+        }
+        let delay = self.delays.iter().max().copied().unwrap_or_default();
+        (delay + 1).clamp(1, max_window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> FeedbackConfig {
+        FeedbackConfig {
+            min_samples: 2,
+            ..Default::default()
+        }
+    }
+
+    fn included(tx_hash: TxHash, block_number: BlockNumber) -> BundleStatus {
+        BundleStatus::Included {
+            bundle_hash: TxHash::ZERO,
+            tx_hashes: vec![tx_hash],
+            block_number,
+        }
+    }
+
+    fn expired(tx_hash: TxHash) -> BundleStatus {
+        BundleStatus::Expired {
+            bundle_hash: TxHash::ZERO,
+            tx_hashes: vec![tx_hash],
+            max_block: 0,
+            stats: None,
+        }
+    }
+
+    #[test]
+    fn test_dead_bids_and_contested_pools() {
+        let mut feedback = BundleFeedback::new(config());
+        let pool = Address::repeat_byte(1);
+        let small = Bid::PriceImpact(30);
+        let large = Bid::PriceImpact(300);
+
+        for byte in 1..=2 {
+            let tx_hash = TxHash::repeat_byte(byte);
+            feedback.record_submission(tx_hash, pool, small, 100);
+            assert!(feedback.record_status(&expired(tx_hash)));
+        }
+        // Unknown bundles are ignored.
+        assert!(!feedback.record_status(&expired(TxHash::repeat_byte(9))));
+
+        assert!(feedback.is_dead(small));
+        assert!(!feedback.is_dead(large));
+        assert!(feedback.is_contested(pool));
+        assert_eq!(
+            feedback.payment_percentage(pool, 50),
+            95
+        );
+        assert_eq!(
+            feedback.payment_percentage(Address::ZERO, 50),
+            50
+        );
+
+        let tx_hash = TxHash::repeat_byte(3);
+        feedback.record_submission(tx_hash, pool, large, 100);
+        assert!(feedback.record_status(&included(tx_hash, 101)));
+
+        assert!(!feedback.is_dead(large));
+        assert!(!feedback.is_contested(pool));
+    }
+
+    #[test]
+    fn test_inclusion_window() {
+        let mut feedback = BundleFeedback::new(config());
+        let pool = Address::repeat_byte(1);
+
+        for (byte, block_number) in [(1, 100), (2, 103)] {
+            assert_eq!(feedback.inclusion_window(30), 30);
+            let tx_hash = TxHash::repeat_byte(byte);
+            feedback.record_submission(
+                tx_hash,
+                pool,
+                Bid::PriceImpact(30),
+                100,
+            );
+            feedback.record_status(&included(tx_hash, block_number));
+        }
+
+        assert_eq!(feedback.inclusion_window(30), 4);
+        assert_eq!(feedback.inclusion_window(2), 2);
+    }
+
+    #[test]
+    fn test_dead_bids_revive() {
+        let mut feedback = BundleFeedback::new(FeedbackConfig {
+            half_life_blocks: 100,
+            ..config()
+        });
+        let bid = Bid::PriceImpact(30);
+        feedback.prune(100);
+        for byte in 1..=3 {
+            let tx_hash = TxHash::repeat_byte(byte);
+            feedback.record_submission(tx_hash, Address::ZERO, bid, 100);
+            feedback.record_status(&expired(tx_hash));
+        }
+        assert!(feedback.is_dead(bid));
+
+        feedback.prune(199);
+        assert!(feedback.is_dead(bid));
+
+        // Three expiries are halved to one, below the samples needed.
+        feedback.prune(200);
+        assert!(!feedback.is_dead(bid));
+        assert_eq!(
+            feedback.bid_outcomes(bid),
+            Outcomes {
+                landed: 0,
+                expired: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut feedback = BundleFeedback::new(config());
+        let tx_hash = TxHash::repeat_byte(1);
+        feedback.record_submission(
+            tx_hash,
+            Address::ZERO,
+            Bid::Size(U256::ONE),
+            100,
+        );
+
+        feedback.prune(100 + MAX_PENDING_AGE);
+
+        assert!(!feedback.record_status(&expired(tx_hash)));
+    }
+}
@@ -0,0 +1,278 @@
+//! Inventory of the arbitrage contract, which pays the V3 leg of a backrun
+//! from its WETH balance before the V2 leg pays it back:
+//! - the WETH balance (and the balances of the traded tokens, which should stay
+//!   empty) are refreshed once per block,
+//! - backruns larger than the WETH balance are refused,
+//! - the WETH balance is topped up from the owner below
+//!   [min_weth](InventoryConfig::min_weth), and the excess is withdrawn to the
+//!   owner above [max_weth](InventoryConfig::max_weth).
+
+use std::{collections::HashMap, sync::Arc};
+
+use alloy::{
+    primitives::{Address, BlockNumber, Bytes, U256},
+    providers::Provider,
+    rpc::types::{TransactionInput, TransactionRequest},
+    sol_types::SolCall,
+};
+use kazuka_core::error::KazukaError;
+use kazuka_mev_share_arbitrage_bindings::{
+    blind_arb::BlindArb, ierc20::IERC20, iweth::IWETH,
+};
+use serde::Deserialize;
+
+use crate::discovery::WETH;
+
+/// Parameters of the [Inventory].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct InventoryConfig {
+    /// WETH (in wei) below which the contract is topped up.
+    pub min_weth: U256,
+    /// WETH (in wei) the contract is topped up or withdrawn to.
+    pub target_weth: U256,
+    /// WETH (in wei) above which the excess is withdrawn.
+    pub max_weth: U256,
+    /// Blocks to wait for a rebalancing to land before the next one.
+    pub rebalance_cooldown: u64,
+}
+
+impl Default for InventoryConfig {
+    /// Keeps 1 WETH, enough for the largest backrun of the default grid,
+    /// between 0.5 and 2 WETH.
+    fn default() -> Self {
+        let ether = U256::from(10).pow(U256::from(18));
+        Self {
+            min_weth: ether / U256::from(2),
+            target_weth: ether,
+            max_weth: ether * U256::from(2),
+            rebalance_cooldown: 10,
+        }
+    }
+}
+
+/// Transfers bringing the WETH balance of the contract back to the target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rebalance {
+    /// Transfers the amount from the owner to the contract.
+    TopUp(U256),
+    /// Withdraws the whole balance to the owner, and transfers the amount
+    /// back.
+    Withdraw { keep: U256 },
+}
+
+impl Rebalance {
+    /// Rebalancing of the balance, if it is out of the bounds.
+    pub fn of(config: &InventoryConfig, weth: U256) -> Option<Self> {
+        if weth < config.min_weth {
+            Some(Rebalance::TopUp(
+                config.target_weth - weth,
+            ))
+        } else if weth > config.max_weth {
+            Some(Rebalance::Withdraw {
+                keep: config.target_weth,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Balances of the arbitrage contract, see [crate::inventory].
+pub struct Inventory<P: Provider> {
+    provider: Arc<P>,
+    contract: Address,
+    /// Account funding the contract and receiving the withdrawals, which
+    /// must be its owner.
+    owner: Address,
+    /// WETH balance, unknown until refreshed.
+    weth: Option<U256>,
+    /// Balances of the traded tokens.
+    tokens: HashMap<Address, U256>,
+    refreshed_at: Option<BlockNumber>,
+    rebalanced_at: Option<BlockNumber>,
+}
+
+impl<P: Provider> Inventory<P> {
+    pub fn new(provider: Arc<P>, contract: Address, owner: Address) -> Self {
+        Self {
+            provider,
+            contract,
+            owner,
+            weth: None,
+            tokens: HashMap::new(),
+            refreshed_at: None,
+            rebalanced_at: None,
+        }
+    }
+
+    pub fn weth(&self) -> Option<U256> {
+        self.weth
+    }
+
+    pub fn token(&self, token: Address) -> Option<U256> {
+        self.tokens.get(&token).copied()
+    }
+
+    /// Tracks the balance of the token from the next refresh.
+    pub fn watch(&mut self, token: Address) {
+        self.tokens.entry(token).or_default();
+    }
+
+    /// Whether the contract holds enough WETH for a backrun of the size.
+    pub fn can_fund(&self, size: U256) -> bool {
+        self.weth.is_some_and(|weth| size <= weth)
+    }
+
+    /// Fetches the balances of the contract, once per block.
+    pub async fn refresh(
+        &mut self,
+        block_number: BlockNumber,
+    ) -> Result<(), KazukaError> {
+        if self.refreshed_at == Some(block_number) {
+            return Ok(());
+        }
+        let weth = IWETH::new(WETH, self.provider.clone());
+        self.weth = Some(weth.balanceOf(self.contract).call().await?);
+        for (token, balance) in self.tokens.iter_mut() {
+            let erc20 = IERC20::new(*token, self.provider.clone());
+            *balance = erc20.balanceOf(self.contract).call().await?;
+            if !balance.is_zero() {
+                tracing::warn!(
+                    ?token,
+                    "Arbitrage contract holds {} of the token",
+                    balance
+                );
+            }
+        }
+        self.refreshed_at = Some(block_number);
+        Ok(())
+    }
+
+    /// Transactions of the owner rebalancing the WETH balance, unless it is
+    /// within the bounds, or a rebalancing may still be pending.
+    pub fn rebalance(
+        &mut self,
+        config: &InventoryConfig,
+        block_number: BlockNumber,
+    ) -> Vec<TransactionRequest> {
+        if self.rebalanced_at.is_some_and(|rebalanced_at| {
+            block_number < rebalanced_at + config.rebalance_cooldown
+        }) {
+            return vec![];
+        }
+        let Some(rebalance) =
+            self.weth.and_then(|weth| Rebalance::of(config, weth))
+        else {
+            return vec![];
+        };
+        tracing::info!(
+            "Rebalancing {:?} WETH of the arbitrage contract: {:?}",
+            self.weth,
+            rebalance
+        );
+        self.rebalanced_at = Some(block_number);
+        match rebalance {
+            Rebalance::TopUp(amount) => vec![self.top_up(amount)],
+            Rebalance::Withdraw { keep } if keep.is_zero() => {
+                vec![self.withdraw()]
+            }
+            Rebalance::Withdraw { keep } => {
+                vec![self.withdraw(), self.top_up(keep)]
+            }
+        }
+    }
+
+    fn top_up(&self, amount: U256) -> TransactionRequest {
+        self.owner_tx(
+            WETH,
+            IWETH::transferCall {
+                _0: self.contract,
+                _1: amount,
+            },
+        )
+    }
+
+    fn withdraw(&self) -> TransactionRequest {
+        self.owner_tx(
+            self.contract,
+            BlindArb::withdrawWETHCall,
+        )
+    }
+
+    fn owner_tx(&self, to: Address, call: impl SolCall) -> TransactionRequest {
+        TransactionRequest::default().from(self.owner).to(to).input(
+            TransactionInput::new(Bytes::from(call.abi_encode())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::Ethereum,
+        primitives::utils::parse_ether,
+        providers::{ProviderBuilder, RootProvider},
+        transports::mock::Asserter,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_rebalance_of() {
+        let config = InventoryConfig::default();
+
+        assert_eq!(
+            Rebalance::of(&config, parse_ether("1").unwrap()),
+            None
+        );
+        assert_eq!(
+            Rebalance::of(&config, parse_ether("0.25").unwrap()),
+            Some(Rebalance::TopUp(
+                parse_ether("0.75").unwrap()
+            ))
+        );
+        assert_eq!(
+            Rebalance::of(&config, parse_ether("3").unwrap()),
+            Some(Rebalance::Withdraw {
+                keep: parse_ether("1").unwrap()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_and_rebalance() {
+        let asserter = Asserter::new();
+        let provider: RootProvider<Ethereum> =
+            ProviderBuilder::default().connect_mocked_client(asserter.clone());
+        let contract = Address::repeat_byte(1);
+        let owner = Address::repeat_byte(2);
+        let mut inventory = Inventory::new(Arc::new(provider), contract, owner);
+        let config = InventoryConfig::default();
+
+        assert!(!inventory.can_fund(U256::ONE));
+
+        // The ABI encoded `balanceOf` result.
+        asserter.push_success(&Bytes::from(
+            parse_ether("3").unwrap().to_be_bytes::<32>(),
+        ));
+        inventory.refresh(100).await.unwrap();
+        // Refreshed once per block.
+        inventory.refresh(100).await.unwrap();
+
+        assert_eq!(
+            inventory.weth(),
+            Some(parse_ether("3").unwrap())
+        );
+        assert!(inventory.can_fund(parse_ether("3").unwrap()));
+        assert!(!inventory.can_fund(parse_ether("4").unwrap()));
+
+        let txs = inventory.rebalance(&config, 100);
+        assert_eq!(txs.len(), 2);
+        assert!(txs.iter().all(|tx| tx.from == Some(owner)));
+        assert_eq!(txs[0].to, Some(contract.into()));
+        assert_eq!(txs[1].to, Some(WETH.into()));
+        // Waits for the rebalancing to land.
+        assert!(inventory.rebalance(&config, 105).is_empty());
+    }
+}
@@ -1,5 +1,16 @@
+pub mod config;
+pub mod discovery;
 pub mod executor;
+pub mod fee_tier;
+pub mod feedback;
+pub mod inventory;
+pub mod pool_state;
+pub mod screening;
+pub mod signing;
+pub mod simulation;
+pub mod sizing;
 pub mod strategy;
+pub mod triangular;
 pub mod types;
 
 pub(crate) mod contracts;
@@ -0,0 +1,534 @@
+//! In-memory state of the pools, kept up to date from their logs, so the
+//! backruns can be sized without querying the node after receiving a hint.
+//!
+//! Pools are fetched from the node the first time they are needed, and
+//! their state is then updated from the logs matching
+//! [PoolStateCache::log_filter]:
+//! - `Sync` of V2 pools, which carries the new reserves,
+//! - `Swap` of V3 pools, which carries the new price, tick and liquidity,
+//! - `Mint` and `Burn` of V3 pools, which change the liquidity of the current
+//!   tick range.
+//!
+//! Each state records the block it was fetched at, and the logs of that block
+//! or earlier are skipped as the state already includes them: the `Mint` and
+//! `Burn` deltas would otherwise be counted twice.
+
+use std::{collections::HashMap, sync::Arc};
+
+use alloy::{
+    eips::BlockId,
+    primitives::{Address, BlockNumber, U160, U256},
+    providers::Provider,
+    rpc::types::{Filter, Log},
+    sol,
+    sol_types::SolEvent,
+};
+use kazuka_amm_math::v2::Reserves;
+use kazuka_core::error::KazukaError;
+use kazuka_mev_share_arbitrage_bindings::i_uniswap_v2_pair::IUniswapV2Pair;
+
+use crate::sizing::IUniswapV3PoolState;
+
+sol! {
+    interface IUniswapV2PairEvents {
+        event Sync(uint112 reserve0, uint112 reserve1);
+    }
+
+    interface IUniswapV3PoolEvents {
+        event Swap(
+            address indexed sender,
+            address indexed recipient,
+            int256 amount0,
+            int256 amount1,
+            uint160 sqrtPriceX96,
+            uint128 liquidity,
+            int24 tick
+        );
+        event Mint(
+            address sender,
+            address indexed owner,
+            int24 indexed tickLower,
+            int24 indexed tickUpper,
+            uint128 amount,
+            uint256 amount0,
+            uint256 amount1
+        );
+        event Burn(
+            address indexed owner,
+            int24 indexed tickLower,
+            int24 indexed tickUpper,
+            uint128 amount,
+            uint256 amount0,
+            uint256 amount1
+        );
+    }
+}
+
+use IUniswapV2PairEvents::Sync;
+use IUniswapV3PoolEvents::{Burn, Mint, Swap};
+
+/// State of a pool, which determines its reserves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolState {
+    V2 {
+        reserve0: U256,
+        reserve1: U256,
+        /// Fee in hundredths of a bip.
+        fee: u32,
+    },
+    V3 {
+        sqrt_price_x96: U160,
+        tick: i32,
+        /// Liquidity of the current tick range.
+        liquidity: u128,
+        /// Fee in hundredths of a bip.
+        fee: u32,
+    },
+}
+
+impl PoolState {
+    /// Fetches the reserves of a V2 pool at `block`, whose fee depends on its
+    /// fork.
+    pub async fn fetch_v2<P: Provider>(
+        provider: Arc<P>,
+        pool: Address,
+        fee: u32,
+        block: BlockId,
+    ) -> Result<Self, KazukaError> {
+        let reserves = IUniswapV2Pair::new(pool, provider)
+            .getReserves()
+            .block(block)
+            .call()
+            .await?;
+        Ok(Self::V2 {
+            reserve0: U256::from(reserves.reserve0),
+            reserve1: U256::from(reserves.reserve1),
+            fee,
+        })
+    }
+
+    /// Fetches the price and liquidity of a V3 pool at `block`.
+    pub async fn fetch_v3<P: Provider>(
+        provider: Arc<P>,
+        pool: Address,
+        block: BlockId,
+    ) -> Result<Self, KazukaError> {
+        let v3 = IUniswapV3PoolState::new(pool, provider);
+        let slot0 = v3.slot0().block(block).call().await?;
+        let liquidity = v3.liquidity().block(block).call().await?;
+        let fee = v3.fee().block(block).call().await?;
+        Ok(Self::V3 {
+            sqrt_price_x96: slot0.sqrtPriceX96,
+            tick: slot0.tick.as_i32(),
+            liquidity,
+            fee: fee.to::<u32>(),
+        })
+    }
+
+    /// Reserves of the pool swapping token0 for token1 if `zero_for_one`,
+    /// see [Reserves::from_v3] for V3 pools.
+    pub fn reserves(&self, zero_for_one: bool) -> Reserves {
+        match *self {
+            Self::V2 {
+                reserve0,
+                reserve1,
+                fee,
+            } => {
+                let reserves = Reserves::new(reserve0, reserve1, fee);
+                if zero_for_one {
+                    reserves
+                } else {
+                    reserves.reversed()
+                }
+            }
+            Self::V3 {
+                sqrt_price_x96,
+                liquidity,
+                fee,
+                ..
+            } => Reserves::from_v3(
+                sqrt_price_x96,
+                liquidity,
+                fee,
+                zero_for_one,
+            ),
+        }
+    }
+}
+
+/// State of a pool, with the block it was fetched at.
+#[derive(Clone, Copy, Debug)]
+struct CachedState {
+    state: PoolState,
+    /// Block the state was fetched at, whose logs it already includes.
+    fetched_at: Option<BlockNumber>,
+}
+
+impl CachedState {
+    /// Whether the state already includes the changes of the log.
+    fn includes(&self, log: &Log) -> bool {
+        match (self.fetched_at, log.block_number) {
+            (Some(fetched_at), Some(block)) => block <= fetched_at,
+            _ => false,
+        }
+    }
+}
+
+/// States of the pools the strategy has needed so far.
+#[derive(Debug, Default)]
+pub struct PoolStateCache {
+    states: HashMap<Address, CachedState>,
+}
+
+impl PoolStateCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Filter of the logs updating the states of the pools, of every pool as
+    /// the pools can be rediscovered.
+    pub fn log_filter() -> Filter {
+        Filter::new().event_signature(vec![
+            Sync::SIGNATURE_HASH,
+            Swap::SIGNATURE_HASH,
+            Mint::SIGNATURE_HASH,
+            Burn::SIGNATURE_HASH,
+        ])
+    }
+
+    pub fn get(&self, pool: Address) -> Option<&PoolState> {
+        self.states.get(&pool).map(|cached| &cached.state)
+    }
+
+    /// Caches the state of a pool fetched at `fetched_at`, so the logs of
+    /// that block or earlier are skipped. Without a block every log is
+    /// applied.
+    pub fn insert(
+        &mut self,
+        pool: Address,
+        state: PoolState,
+        fetched_at: Option<BlockNumber>,
+    ) {
+        self.states.insert(pool, CachedState { state, fetched_at });
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+
+    /// Updates the state of the pool emitting the log, if it is cached and
+    /// doesn't include the log yet. Pools of logs removed by a reorg are
+    /// evicted, and fetched again when needed.
+    ///
+    /// Returns whether the cache has changed.
+    pub fn apply_log(&mut self, log: &Log) -> bool {
+        if log.removed {
+            return self.states.remove(&log.address()).is_some();
+        }
+        let Some(cached) = self.states.get_mut(&log.address()) else {
+            return false;
+        };
+        if cached.includes(log) {
+            return false;
+        }
+        let state = &mut cached.state;
+        let Some(&topic0) = log.topic0() else {
+            return false;
+        };
+        match state {
+            PoolState::V2 {
+                reserve0, reserve1, ..
+            } if topic0 == Sync::SIGNATURE_HASH => {
+                let Ok(sync) = log.log_decode::<Sync>() else {
+                    return false;
+                };
+                *reserve0 = U256::from(sync.inner.data.reserve0);
+                *reserve1 = U256::from(sync.inner.data.reserve1);
+                true
+            }
+            PoolState::V3 {
+                sqrt_price_x96,
+                tick,
+                liquidity,
+                ..
+            } if topic0 == Swap::SIGNATURE_HASH => {
+                let Ok(swap) = log.log_decode::<Swap>() else {
+                    return false;
+                };
+                *sqrt_price_x96 = swap.inner.data.sqrtPriceX96;
+                *tick = swap.inner.data.tick.as_i32();
+                *liquidity = swap.inner.data.liquidity;
+                true
+            }
+            PoolState::V3 {
+                tick, liquidity, ..
+            } if topic0 == Mint::SIGNATURE_HASH => {
+                let Ok(mint) = log.log_decode::<Mint>() else {
+                    return false;
+                };
+                let mint = mint.inner.data;
+                if !in_range(
+                    *tick,
+                    mint.tickLower.as_i32(),
+                    mint.tickUpper.as_i32(),
+                ) {
+                    return false;
+                }
+                *liquidity = liquidity.saturating_add(mint.amount);
+                true
+            }
+            PoolState::V3 {
+                tick, liquidity, ..
+            } if topic0 == Burn::SIGNATURE_HASH => {
+                let Ok(burn) = log.log_decode::<Burn>() else {
+                    return false;
+                };
+                let burn = burn.inner.data;
+                if !in_range(
+                    *tick,
+                    burn.tickLower.as_i32(),
+                    burn.tickUpper.as_i32(),
+                ) {
+                    return false;
+                }
+                *liquidity = liquidity.saturating_sub(burn.amount);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Reserves of a V2 pool, fetched at the latest block if it isn't cached
+    /// yet.
+    pub async fn v2_reserves<P: Provider>(
+        &mut self,
+        provider: Arc<P>,
+        pool: Address,
+        zero_for_one: bool,
+        fee: u32,
+    ) -> Result<Reserves, KazukaError> {
+        if let Some(cached) = self.states.get(&pool) {
+            return Ok(cached.state.reserves(zero_for_one));
+        }
+        let block = provider.get_block_number().await?;
+        let state =
+            PoolState::fetch_v2(provider, pool, fee, block.into()).await?;
+        self.insert(pool, state, Some(block));
+        Ok(state.reserves(zero_for_one))
+    }
+
+    /// Reserves of a V3 pool, fetched at the latest block if it isn't cached
+    /// yet.
+    pub async fn v3_reserves<P: Provider>(
+        &mut self,
+        provider: Arc<P>,
+        pool: Address,
+        zero_for_one: bool,
+    ) -> Result<Reserves, KazukaError> {
+        if let Some(cached) = self.states.get(&pool) {
+            return Ok(cached.state.reserves(zero_for_one));
+        }
+        let block = provider.get_block_number().await?;
+        let state = PoolState::fetch_v3(provider, pool, block.into()).await?;
+        self.insert(pool, state, Some(block));
+        Ok(state.reserves(zero_for_one))
+    }
+}
+
+/// Whether a position covers the current tick, so that it provides the
+/// liquidity of the pool.
+fn in_range(tick: i32, tick_lower: i32, tick_upper: i32) -> bool {
+    tick_lower <= tick && tick < tick_upper
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{
+        I256,
+        aliases::{I24, U112},
+    };
+
+    use super::*;
+
+    fn log(pool: Address, event: &impl SolEvent) -> Log {
+        Log {
+            inner: alloy::primitives::Log {
+                address: pool,
+                data: event.encode_log_data(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn tick(tick: i32) -> I24 {
+        I24::try_from(tick).unwrap()
+    }
+
+    #[test]
+    fn test_apply_sync() {
+        let pool = Address::repeat_byte(1);
+        let mut cache = PoolStateCache::new();
+        cache.insert(
+            pool,
+            PoolState::V2 {
+                reserve0: U256::from(100),
+                reserve1: U256::from(200),
+                fee: 3_000,
+            },
+            None,
+        );
+
+        let sync = Sync {
+            reserve0: U112::from(110),
+            reserve1: U112::from(190),
+        };
+        assert!(cache.apply_log(&log(pool, &sync)));
+        // Logs of unknown pools are ignored.
+        assert!(!cache.apply_log(&log(Address::repeat_byte(2), &sync)));
+
+        assert_eq!(
+            cache.get(pool).unwrap().reserves(false),
+            Reserves::new(U256::from(190), U256::from(110), 3_000)
+        );
+    }
+
+    #[test]
+    fn test_apply_v3_logs() {
+        let pool = Address::repeat_byte(1);
+        let mut cache = PoolStateCache::new();
+        cache.insert(
+            pool,
+            PoolState::V3 {
+                sqrt_price_x96: U160::from(1) << 96,
+                tick: 0,
+                liquidity: 1_000,
+                fee: 500,
+            },
+            None,
+        );
+
+        let swap = Swap {
+            sender: Address::ZERO,
+            recipient: Address::ZERO,
+            amount0: I256::ZERO,
+            amount1: I256::ZERO,
+            sqrtPriceX96: U160::from(2) << 96,
+            liquidity: 2_000,
+            tick: tick(13_863),
+        };
+        assert!(cache.apply_log(&log(pool, &swap)));
+
+        let mint = |tick_lower, tick_upper| Mint {
+            sender: Address::ZERO,
+            owner: Address::ZERO,
+            tickLower: tick(tick_lower),
+            tickUpper: tick(tick_upper),
+            amount: 500,
+            amount0: U256::ZERO,
+            amount1: U256::ZERO,
+        };
+        // Out of range positions don't change the liquidity.
+        assert!(!cache.apply_log(&log(pool, &mint(-100, 100))));
+        assert!(cache.apply_log(&log(pool, &mint(13_800, 13_900))));
+
+        let burn = Burn {
+            owner: Address::ZERO,
+            tickLower: tick(13_000),
+            tickUpper: tick(14_000),
+            amount: 100,
+            amount0: U256::ZERO,
+            amount1: U256::ZERO,
+        };
+        assert!(cache.apply_log(&log(pool, &burn)));
+
+        assert_eq!(
+            cache.get(pool),
+            Some(&PoolState::V3 {
+                sqrt_price_x96: U160::from(2) << 96,
+                tick: 13_863,
+                liquidity: 2_400,
+                fee: 500,
+            })
+        );
+    }
+
+    #[test]
+    fn test_skip_logs_included_in_fetched_state() {
+        let pool = Address::repeat_byte(1);
+        let mut cache = PoolStateCache::new();
+        cache.insert(
+            pool,
+            PoolState::V3 {
+                sqrt_price_x96: U160::from(1) << 96,
+                tick: 0,
+                liquidity: 1_000,
+                fee: 500,
+            },
+            Some(10),
+        );
+        let mint = |block_number| Log {
+            block_number: Some(block_number),
+            ..log(
+                pool,
+                &Mint {
+                    sender: Address::ZERO,
+                    owner: Address::ZERO,
+                    tickLower: tick(-100),
+                    tickUpper: tick(100),
+                    amount: 500,
+                    amount0: U256::ZERO,
+                    amount1: U256::ZERO,
+                },
+            )
+        };
+
+        // The state fetched at block 10 already includes its mints.
+        assert!(!cache.apply_log(&mint(9)));
+        assert!(!cache.apply_log(&mint(10)));
+        assert!(cache.apply_log(&mint(11)));
+
+        assert_eq!(
+            cache.get(pool),
+            Some(&PoolState::V3 {
+                sqrt_price_x96: U160::from(1) << 96,
+                tick: 0,
+                liquidity: 1_500,
+                fee: 500,
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_removed_log() {
+        let pool = Address::repeat_byte(1);
+        let mut cache = PoolStateCache::new();
+        cache.insert(
+            pool,
+            PoolState::V2 {
+                reserve0: U256::from(100),
+                reserve1: U256::from(200),
+                fee: 3_000,
+            },
+            None,
+        );
+        let mut removed = log(
+            pool,
+            &Sync {
+                reserve0: U112::from(1),
+                reserve1: U112::from(1),
+            },
+        );
+        removed.removed = true;
+
+        assert!(cache.apply_log(&removed));
+        assert!(cache.is_empty());
+    }
+}
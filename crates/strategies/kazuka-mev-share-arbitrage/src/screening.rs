@@ -0,0 +1,469 @@
+//! Screening of the tokens of the pools before they are traded, as a single
+//! malicious token can brick the inventory of the arbitrage contract:
+//! - blacklisted tokens, e.g. known honeypots, are rejected,
+//! - tokens must answer `decimals` and `totalSupply` sensibly,
+//! - a round trip of the token from the V2 pool to the arbitrage contract and
+//!   back is simulated with `eth_simulateV1`, rejecting tokens which can't be
+//!   transferred, or which take a fee on transfers.
+//!
+//! Verdicts are cached for the lifetime of the strategy.
+
+use std::{collections::HashMap, sync::Arc};
+
+use alloy::{
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+    rpc::types::{
+        TransactionInput, TransactionRequest,
+        simulate::{SimBlock, SimCallResult, SimulatePayload},
+    },
+    sol_types::SolCall,
+};
+use kazuka_core::error::KazukaError;
+use kazuka_mev_share_arbitrage_bindings::ierc20::IERC20;
+use serde::Deserialize;
+
+const BPS: u64 = 10_000;
+
+/// Parameters of the [TokenScreener].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct TokenScreeningConfig {
+    /// Tokens which are never traded, e.g. known honeypots.
+    pub blacklist: Vec<Address>,
+    /// Maximum number of decimals of a token.
+    pub max_decimals: u8,
+    /// Maximum fee (in bps) taken on the transfers of a token.
+    pub max_transfer_fee_bps: u64,
+    /// Fraction (in bps) of the balance of the pool transferred in the
+    /// simulated round trip.
+    pub round_trip_bps: u64,
+}
+
+impl Default for TokenScreeningConfig {
+    /// Tokens with up to 24 decimals, without transfer fees, probed with 1%
+    /// of the balance of the pool.
+    fn default() -> Self {
+        Self {
+            blacklist: vec![],
+            max_decimals: 24,
+            max_transfer_fee_bps: 0,
+            round_trip_bps: 100,
+        }
+    }
+}
+
+/// Outcome of the screening of a token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenVerdict {
+    Safe,
+    Blacklisted,
+    /// `decimals` or `totalSupply` reverted.
+    NotErc20,
+    TooManyDecimals(u8),
+    NoSupply,
+    /// A transfer of the round trip reverted or returned `false`.
+    TransferFailed,
+    /// Fee (in bps) taken on the transfers of the round trip.
+    TransferFee(u64),
+}
+
+impl TokenVerdict {
+    pub fn is_safe(&self) -> bool {
+        matches!(self, TokenVerdict::Safe)
+    }
+}
+
+/// Screens the tokens of the pools, see [crate::screening].
+pub struct TokenScreener<P: Provider> {
+    provider: Arc<P>,
+    /// Account receiving the tokens in the round trips, i.e. the arbitrage
+    /// contract.
+    probe: Address,
+    verdicts: HashMap<Address, TokenVerdict>,
+}
+
+impl<P: Provider> TokenScreener<P> {
+    pub fn new(provider: Arc<P>, probe: Address) -> Self {
+        Self {
+            provider,
+            probe,
+            verdicts: HashMap::new(),
+        }
+    }
+
+    /// Cached verdict of the token, if it has been screened.
+    pub fn verdict(&self, token: Address) -> Option<TokenVerdict> {
+        self.verdicts.get(&token).copied()
+    }
+
+    /// Screens the token, which the V2 pool holds, unless it has been
+    /// already. Errors of the node aren't cached.
+    pub async fn screen(
+        &mut self,
+        config: &TokenScreeningConfig,
+        token: Address,
+        v2_pool: Address,
+    ) -> Result<TokenVerdict, KazukaError> {
+        if let Some(verdict) = self.verdict(token) {
+            return Ok(verdict);
+        }
+        let verdict = if config.blacklist.contains(&token) {
+            TokenVerdict::Blacklisted
+        } else {
+            match self.check_metadata(config, token, v2_pool).await? {
+                Ok(amount) => {
+                    self.check_round_trip(config, token, v2_pool, amount)
+                        .await?
+                }
+                Err(verdict) => verdict,
+            }
+        };
+        if !verdict.is_safe() {
+            tracing::warn!(?token, "Token rejected: {:?}", verdict);
+        }
+        self.verdicts.insert(token, verdict);
+        Ok(verdict)
+    }
+
+    async fn simulate(
+        &self,
+        calls: Vec<TransactionRequest>,
+    ) -> Result<Vec<SimCallResult>, KazukaError> {
+        let payload = SimulatePayload::default()
+            .extend(SimBlock::default().extend_calls(calls));
+        let blocks = self.provider.simulate(&payload).await?;
+        Ok(blocks
+            .into_iter()
+            .next()
+            .map(|block| block.calls)
+            .unwrap_or_default())
+    }
+
+    /// Checks the metadata of the token, returning the amount of the round
+    /// trip if it is sensible.
+    async fn check_metadata(
+        &self,
+        config: &TokenScreeningConfig,
+        token: Address,
+        v2_pool: Address,
+    ) -> Result<Result<U256, TokenVerdict>, KazukaError> {
+        let results = self
+            .simulate(vec![
+                call(self.probe, token, IERC20::decimalsCall),
+                call(
+                    self.probe,
+                    token,
+                    IERC20::totalSupplyCall,
+                ),
+                call(
+                    self.probe,
+                    token,
+                    IERC20::balanceOfCall { account: v2_pool },
+                ),
+            ])
+            .await?;
+        let [decimals, total_supply, pool_balance] =
+            [0, 1, 2].map(|i| results.get(i).filter(|result| result.status));
+        let (Some(decimals), Some(total_supply), Some(pool_balance)) = (
+            decode::<IERC20::decimalsCall>(decimals),
+            decode::<IERC20::totalSupplyCall>(total_supply),
+            decode::<IERC20::balanceOfCall>(pool_balance),
+        ) else {
+            return Ok(Err(TokenVerdict::NotErc20));
+        };
+        if decimals > config.max_decimals {
+            return Ok(Err(TokenVerdict::TooManyDecimals(
+                decimals,
+            )));
+        }
+        let amount =
+            pool_balance * U256::from(config.round_trip_bps) / U256::from(BPS);
+        if total_supply.is_zero() || amount.is_zero() {
+            return Ok(Err(TokenVerdict::NoSupply));
+        }
+        Ok(Ok(amount))
+    }
+
+    /// Simulates a transfer of the amount from the pool to the probe, and
+    /// back.
+    async fn check_round_trip(
+        &self,
+        config: &TokenScreeningConfig,
+        token: Address,
+        v2_pool: Address,
+        amount: U256,
+    ) -> Result<TokenVerdict, KazukaError> {
+        let probe_balance = || IERC20::balanceOfCall {
+            account: self.probe,
+        };
+        // The amount received by the probe is only known once simulated,
+        // and the probe sends it back in a second simulation.
+        let results = self
+            .simulate(vec![
+                call(self.probe, token, probe_balance()),
+                call(
+                    v2_pool,
+                    token,
+                    IERC20::transferCall {
+                        to: self.probe,
+                        amount,
+                    },
+                ),
+                call(self.probe, token, probe_balance()),
+            ])
+            .await?;
+        let Some(received) = amount_received(&results, amount) else {
+            return Ok(TokenVerdict::TransferFailed);
+        };
+        let results = self
+            .simulate(vec![
+                call(
+                    v2_pool,
+                    token,
+                    IERC20::transferCall {
+                        to: self.probe,
+                        amount,
+                    },
+                ),
+                call(
+                    v2_pool,
+                    token,
+                    IERC20::balanceOfCall { account: v2_pool },
+                ),
+                call(
+                    self.probe,
+                    token,
+                    IERC20::transferCall {
+                        to: v2_pool,
+                        amount: received,
+                    },
+                ),
+                call(
+                    v2_pool,
+                    token,
+                    IERC20::balanceOfCall { account: v2_pool },
+                ),
+            ])
+            .await?;
+        Ok(round_trip_verdict(
+            config, &results, amount, received,
+        ))
+    }
+}
+
+fn call(from: Address, to: Address, call: impl SolCall) -> TransactionRequest {
+    TransactionRequest::default().from(from).to(to).input(
+        TransactionInput::new(Bytes::from(call.abi_encode())),
+    )
+}
+
+fn decode<C: SolCall>(result: Option<&SimCallResult>) -> Option<C::Return> {
+    C::abi_decode_returns(&result?.return_data).ok()
+}
+
+/// Whether the transfer succeeded. Tokens which don't return a value, like
+/// USDT, succeed unless they revert.
+fn transferred(result: Option<&SimCallResult>) -> bool {
+    result.is_some_and(|result| {
+        result.status
+            && (result.return_data.is_empty()
+                || decode::<IERC20::transferCall>(Some(result))
+                    .unwrap_or(false))
+    })
+}
+
+/// Amount received by the probe from the results of the balance before, the
+/// transfer and the balance after. `None` if the transfer failed.
+fn amount_received(results: &[SimCallResult], amount: U256) -> Option<U256> {
+    let before = decode::<IERC20::balanceOfCall>(results.first())?;
+    if !transferred(results.get(1)) {
+        return None;
+    }
+    let after = decode::<IERC20::balanceOfCall>(results.get(2))?;
+    Some(after.saturating_sub(before).min(amount))
+}
+
+/// Fee (in bps) taken on a transfer of `sent`, of which `received` arrived.
+fn transfer_fee_bps(sent: U256, received: U256) -> u64 {
+    if sent.is_zero() {
+        return 0;
+    }
+    (sent.saturating_sub(received) * U256::from(BPS) / sent).saturating_to()
+}
+
+/// Verdict of the round trip from the results of the transfer to the probe,
+/// the balance of the pool, the transfer back and the balance of the pool.
+fn round_trip_verdict(
+    config: &TokenScreeningConfig,
+    results: &[SimCallResult],
+    amount: U256,
+    received: U256,
+) -> TokenVerdict {
+    if !transferred(results.first()) || !transferred(results.get(2)) {
+        return TokenVerdict::TransferFailed;
+    }
+    let (Some(before), Some(after)) = (
+        decode::<IERC20::balanceOfCall>(results.get(1)),
+        decode::<IERC20::balanceOfCall>(results.get(3)),
+    ) else {
+        return TokenVerdict::TransferFailed;
+    };
+    let fee_bps = transfer_fee_bps(amount, received).max(transfer_fee_bps(
+        received,
+        after.saturating_sub(before),
+    ));
+    if fee_bps > config.max_transfer_fee_bps {
+        TokenVerdict::TransferFee(fee_bps)
+    } else {
+        TokenVerdict::Safe
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::Ethereum,
+        providers::{ProviderBuilder, RootProvider},
+        sol_types::SolValue,
+        transports::mock::Asserter,
+    };
+
+    use super::*;
+
+    fn success(value: impl SolValue) -> SimCallResult {
+        SimCallResult {
+            return_data: Bytes::from(value.abi_encode()),
+            status: true,
+            ..Default::default()
+        }
+    }
+
+    /// Results of a round trip, where the pool got back `returned` of the
+    /// 1000 tokens it had sent.
+    fn round_trip(returned: u64) -> Vec<SimCallResult> {
+        vec![
+            success(true),
+            success(U256::from(0)),
+            // Like USDT, which doesn't return a value.
+            SimCallResult {
+                status: true,
+                ..Default::default()
+            },
+            success(U256::from(returned)),
+        ]
+    }
+
+    #[test]
+    fn test_round_trip_verdict() {
+        let config = TokenScreeningConfig::default();
+        let amount = U256::from(1_000);
+
+        assert_eq!(
+            round_trip_verdict(
+                &config,
+                &round_trip(1_000),
+                amount,
+                amount
+            ),
+            TokenVerdict::Safe
+        );
+        // A 1% fee on the sells.
+        assert_eq!(
+            round_trip_verdict(
+                &config,
+                &round_trip(990),
+                amount,
+                amount
+            ),
+            TokenVerdict::TransferFee(100)
+        );
+        // A 2% fee on the buys.
+        assert_eq!(
+            round_trip_verdict(
+                &config,
+                &round_trip(980),
+                amount,
+                U256::from(980)
+            ),
+            TokenVerdict::TransferFee(200)
+        );
+        assert_eq!(
+            round_trip_verdict(&config, &[], amount, amount),
+            TokenVerdict::TransferFailed
+        );
+
+        let mut reverted = round_trip(1_000);
+        reverted[2].status = false;
+        assert_eq!(
+            round_trip_verdict(&config, &reverted, amount, amount),
+            TokenVerdict::TransferFailed
+        );
+    }
+
+    #[test]
+    fn test_amount_received() {
+        let amount = U256::from(100);
+
+        assert_eq!(
+            amount_received(
+                &[
+                    success(U256::from(5)),
+                    success(true),
+                    success(U256::from(103))
+                ],
+                amount
+            ),
+            Some(U256::from(98))
+        );
+        assert_eq!(
+            amount_received(
+                &[
+                    success(U256::from(5)),
+                    success(false),
+                    success(U256::from(5))
+                ],
+                amount
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blacklist() {
+        let provider: RootProvider<Ethereum> =
+            ProviderBuilder::default().connect_mocked_client(Asserter::new());
+        let mut screener = TokenScreener::new(
+            Arc::new(provider),
+            Address::repeat_byte(1),
+        );
+        let token = Address::repeat_byte(2);
+        let config = TokenScreeningConfig {
+            blacklist: vec![token],
+            ..Default::default()
+        };
+
+        // Blacklisted tokens are rejected without querying the node.
+        assert_eq!(
+            screener
+                .screen(&config, token, Address::repeat_byte(3))
+                .await
+                .unwrap(),
+            TokenVerdict::Blacklisted
+        );
+        assert_eq!(
+            screener.verdict(token),
+            Some(TokenVerdict::Blacklisted)
+        );
+        assert!(
+            screener
+                .screen(
+                    &TokenScreeningConfig::default(),
+                    Address::repeat_byte(4),
+                    Address::repeat_byte(3)
+                )
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Signing of the backrun txs of a hint. The candidate backruns of a hint
+//! are alternatives, at most one of which lands, so:
+//! - the sender, nonce, chain ID and gas price are fetched once per hint, see
+//!   [TxContext], and shared by the txs of every candidate,
+//! - txs are signed offline with the transactions signer of a [SignerProvider],
+//!   if any, and by the node (`eth_signTransaction`) otherwise.
+
+use alloy::{
+    eips::Encodable2718,
+    network::{EthereumWallet, TransactionBuilder},
+    primitives::{Address, Bytes, ChainId},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+};
+use kazuka_core::{
+    error::KazukaError,
+    signer::{KeyPurpose, SignerProvider},
+};
+
+/// Fields shared by the txs of the candidate backruns of a hint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TxContext {
+    pub from: Address,
+    pub nonce: u64,
+    pub chain_id: ChainId,
+    pub gas_price: u128,
+}
+
+impl TxContext {
+    /// Fetches the next nonce of the transactions signer, or of the first
+    /// account of the node without signers, along with the chain ID and
+    /// the current gas price.
+    pub async fn fetch<P: Provider>(
+        provider: &P,
+        signers: Option<&dyn SignerProvider>,
+    ) -> Result<Self, KazukaError> {
+        let from = match signers {
+            Some(signers) => {
+                signers.signer(KeyPurpose::Transactions)?.address()
+            }
+            None => {
+                provider.get_accounts().await?.first().copied().ok_or_else(
+                    || {
+                        KazukaError::ConfigError(
+                            "node has no account to sign txs with".to_string(),
+                        )
+                    },
+                )?
+            }
+        };
+        let (nonce, chain_id, gas_price) = futures_util::try_join!(
+            async { provider.get_transaction_count(from).pending().await },
+            provider.get_chain_id(),
+            provider.get_gas_price(),
+        )?;
+        Ok(Self {
+            from,
+            nonce,
+            chain_id,
+            gas_price,
+        })
+    }
+
+    /// Sets the sender, nonce, chain ID and gas price of the tx.
+    pub fn apply(&self, tx: &mut TransactionRequest) {
+        tx.set_from(self.from);
+        tx.set_nonce(self.nonce);
+        tx.set_chain_id(self.chain_id);
+        tx.set_gas_price(self.gas_price);
+    }
+}
+
+/// Signs the tx, whose fields must all be set, e.g. by [TxContext::apply],
+/// offline with the transactions signer of the `signers`, or by the node
+/// without signers.
+pub async fn sign_tx<P: Provider>(
+    provider: &P,
+    signers: Option<&dyn SignerProvider>,
+    tx: TransactionRequest,
+) -> Result<Bytes, KazukaError> {
+    let Some(signers) = signers else {
+        return Ok(provider.sign_transaction(tx).await?);
+    };
+    let signer = signers.signer(KeyPurpose::Transactions)?;
+    if tx.from != Some(signer.address()) {
+        return Err(KazukaError::ConfigError(format!(
+            "tx from {:?} can't be signed by {}",
+            tx.from,
+            signer.address()
+        )));
+    }
+    let envelope = tx
+        .build(&EthereumWallet::new(signer))
+        .await
+        .map_err(alloy::signers::Error::other)?;
+    Ok(envelope.encoded_2718().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{Transaction, TxEnvelope, transaction::SignerRecoverable},
+        eips::Decodable2718,
+        network::Ethereum,
+        primitives::U256,
+        providers::{ProviderBuilder, RootProvider},
+        signers::local::PrivateKeySigner,
+        transports::mock::Asserter,
+    };
+    use kazuka_core::signer::KazukaSigner;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sign_tx_offline() {
+        let provider: RootProvider<Ethereum> =
+            ProviderBuilder::default().connect_mocked_client(Asserter::new());
+        let signer = KazukaSigner::new(PrivateKeySigner::random());
+        let context = TxContext {
+            from: signer.address(),
+            nonce: 7,
+            chain_id: 1,
+            gas_price: 1_000_000_000,
+        };
+
+        let mut txs = vec![];
+        for value in [1, 2] {
+            let mut tx = TransactionRequest::default()
+                .to(Address::repeat_byte(1))
+                .value(U256::from(value))
+                .gas_limit(21_000);
+            context.apply(&mut tx);
+            let bytes = sign_tx(&provider, Some(&signer), tx).await.unwrap();
+            txs.push(TxEnvelope::decode_2718(&mut bytes.as_ref()).unwrap());
+        }
+
+        // Candidates are alternatives, so they share the nonce.
+        for tx in &txs {
+            assert_eq!(tx.nonce(), 7);
+            assert_eq!(tx.chain_id(), Some(1));
+            assert_eq!(tx.gas_price(), Some(1_000_000_000));
+            assert_eq!(
+                tx.recover_signer().unwrap(),
+                signer.address()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_tx_of_other_sender() {
+        let provider: RootProvider<Ethereum> =
+            ProviderBuilder::default().connect_mocked_client(Asserter::new());
+        let signer = KazukaSigner::new(PrivateKeySigner::random());
+        let tx = TransactionRequest::default().from(Address::repeat_byte(2));
+
+        assert!(matches!(
+            sign_tx(&provider, Some(&signer), tx).await,
+            Err(KazukaError::ConfigError(_))
+        ));
+    }
+}
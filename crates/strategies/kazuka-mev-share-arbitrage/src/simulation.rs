@@ -0,0 +1,102 @@
+//! Profitability filter of the bundles, simulated with `mev_simBundle`
+//! before they are submitted.
+//!
+//! Any [MevApiClient] can simulate bundles, e.g. the relay itself, or the
+//! simulation server of the backend on a local fork.
+
+use std::borrow::Borrow;
+
+use alloy::{
+    primitives::U256,
+    rpc::types::mev::{MevSendBundle, SimBundleOverrides, SimBundleResponse},
+};
+use futures_util::future::join_all;
+use kazuka_mev_share::rpc::MevApiClient;
+
+/// Expected value of a simulated bundle: its profit minus the gas it pays
+/// and the value refundable to the users.
+pub fn expected_value(response: &SimBundleResponse, gas_price: u128) -> U256 {
+    let gas_cost = U256::from(response.gas_used) * U256::from(gas_price);
+    response
+        .profit
+        .saturating_sub(gas_cost)
+        .saturating_sub(response.refundable_value)
+}
+
+/// Simulates the bundles, and keeps the successful ones with an expected
+/// value of at least `min_expected_value`.
+pub async fn filter_profitable<B: Borrow<MevSendBundle>>(
+    simulator: &(dyn MevApiClient + Send + Sync),
+    bundles: Vec<B>,
+    gas_price: u128,
+    min_expected_value: U256,
+) -> Vec<B> {
+    let simulations = join_all(bundles.iter().map(|bundle| {
+        simulator.sim_bundle(
+            bundle.borrow().clone(),
+            SimBundleOverrides::default(),
+        )
+    }))
+    .await;
+
+    bundles
+        .into_iter()
+        .zip(simulations)
+        .filter_map(
+            |(bundle, simulation)| match simulation {
+                Ok(response) if response.success => {
+                    let value = expected_value(&response, gas_price);
+                    if value < min_expected_value {
+                        tracing::debug!(
+                            "Discarding bundle with expected value {}",
+                            value
+                        );
+                        return None;
+                    }
+                    Some(bundle)
+                }
+                Ok(response) => {
+                    tracing::debug!(
+                        "Discarding failed bundle: {:?}",
+                        response.error
+                    );
+                    None
+                }
+                Err(err) => {
+                    tracing::error!("Error simulating bundle: {:?}", err);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_value() {
+        let response = SimBundleResponse {
+            success: true,
+            error: None,
+            state_block: 1,
+            mev_gas_price: U256::from(10),
+            profit: U256::from(1_000_000),
+            refundable_value: U256::from(200_000),
+            gas_used: 21_000,
+            logs: None,
+            exec_error: None,
+            revert: None,
+        };
+
+        assert_eq!(
+            expected_value(&response, 10),
+            U256::from(590_000)
+        );
+        assert_eq!(
+            expected_value(&response, 100),
+            U256::ZERO
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Sizing of the backruns from the reserves of the pools.
+//!
+//! The V3 pool is approximated by its virtual reserves within the current
+//! tick range, see [Reserves::from_v3], so all legs of the arbitrage are
+//! constant product swaps, whose optimal input is computed in closed form by
+//! [arbitrage::optimal_path_amount_in].
+
+use std::sync::Arc;
+
+use alloy::{
+    eips::BlockId,
+    primitives::{Address, U256},
+    providers::Provider,
+    sol,
+};
+use kazuka_amm_math::{arbitrage, v2::Reserves};
+use kazuka_core::error::KazukaError;
+
+use crate::{
+    pool_state::{PoolState, PoolStateCache},
+    types::V2PoolInfo,
+};
+
+sol! {
+    /// State of a Uniswap V3 pool, which is missing from the generated
+    /// bindings.
+    #[sol(rpc)]
+    interface IUniswapV3PoolState {
+        function slot0() external view returns (
+            uint160 sqrtPriceX96,
+            int24 tick,
+            uint16 observationIndex,
+            uint16 observationCardinality,
+            uint16 observationCardinalityNext,
+            uint8 feeProtocol,
+            bool unlocked
+        );
+        function liquidity() external view returns (uint128);
+        function fee() external view returns (uint24);
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+    }
+}
+
+/// Optimal backrun for a price impact of the target transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub price_impact_bps: u64,
+    pub amount_in: U256,
+    pub profit: U256,
+}
+
+/// Computes the optimal backruns after the target transaction lowered the
+/// price of the token on the V3 pool by each of the impacts, keeping the
+/// first `max_candidates` distinct profitable ones.
+pub fn candidate_sizes(
+    v3: &Reserves,
+    v2: &Reserves,
+    price_impacts_bps: &[u64],
+    max_candidates: usize,
+) -> Vec<Candidate> {
+    path_candidates(
+        &[*v3, *v2],
+        0,
+        price_impacts_bps,
+        max_candidates,
+    )
+}
+
+/// Computes the optimal backruns of the path after the target transaction
+/// lowered the price of the output token of the hinted pool by each of the
+/// impacts, keeping the first `max_candidates` distinct profitable ones.
+pub fn path_candidates(
+    path: &[Reserves],
+    hinted: usize,
+    price_impacts_bps: &[u64],
+    max_candidates: usize,
+) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for &price_impact_bps in price_impacts_bps {
+        if candidates.len() == max_candidates {
+            break;
+        }
+        let mut path = path.to_vec();
+        path[hinted] = path[hinted].with_price_impact(price_impact_bps);
+        let amount_in = arbitrage::optimal_path_amount_in(&path);
+        let profit = arbitrage::path_profit(&path, amount_in);
+        if profit.is_zero()
+            || candidates.iter().any(|c| c.amount_in == amount_in)
+        {
+            continue;
+        }
+        candidates.push(Candidate {
+            price_impact_bps,
+            amount_in,
+            profit,
+        });
+    }
+    candidates
+}
+
+/// Fetches the virtual reserves of a V3 pool, see [Reserves::from_v3].
+pub async fn fetch_v3_reserves<P: Provider>(
+    provider: Arc<P>,
+    v3_pool: Address,
+    zero_for_one: bool,
+) -> Result<Reserves, KazukaError> {
+    Ok(
+        PoolState::fetch_v3(provider, v3_pool, BlockId::latest())
+            .await?
+            .reserves(zero_for_one),
+    )
+}
+
+/// Reserves of the V3 pool (WETH to token) and the V2 pool (token to WETH)
+/// of the arbitrage, fetched unless they are cached.
+pub async fn fetch_reserves<P: Provider>(
+    provider: Arc<P>,
+    cache: &mut PoolStateCache,
+    v3_pool: Address,
+    v2_pool_info: &V2PoolInfo,
+) -> Result<(Reserves, Reserves), KazukaError> {
+    let v3 = cache
+        .v3_reserves(
+            provider.clone(),
+            v3_pool,
+            v2_pool_info.is_weth_token0,
+        )
+        .await?;
+
+    // The V2 pool swaps the token back to WETH.
+    let v2 = cache
+        .v2_reserves(
+            provider,
+            v2_pool_info.v2_pool,
+            !v2_pool_info.is_weth_token0,
+            v2_pool_info.fork.fee,
+        )
+        .await?;
+
+    Ok((v3, v2))
+}
+
+/// Fetches the reserves of a V2 pool, which swaps token0 for token1 if
+/// `zero_for_one`.
+pub async fn fetch_v2_reserves<P: Provider>(
+    provider: Arc<P>,
+    v2_pool: Address,
+    zero_for_one: bool,
+    fee: u32,
+) -> Result<Reserves, KazukaError> {
+    Ok(PoolState::fetch_v2(
+        provider,
+        v2_pool,
+        fee,
+        BlockId::latest(),
+    )
+    .await?
+    .reserves(zero_for_one))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::utils::parse_ether;
+
+    use super::*;
+
+    /// Fee of Uniswap V2.
+    const UNISWAP_V2_FEE: u32 = 3_000;
+
+    #[test]
+    fn test_no_arbitrage() {
+        let first = Reserves::new(
+            parse_ether("1000").unwrap(),
+            parse_ether("1000").unwrap(),
+            UNISWAP_V2_FEE,
+        );
+        let second = Reserves::new(
+            parse_ether("1000").unwrap(),
+            parse_ether("1000").unwrap(),
+            UNISWAP_V2_FEE,
+        );
+
+        assert!(candidate_sizes(&first, &second, &[0], 1).is_empty());
+    }
+
+    #[test]
+    fn test_candidate_sizes() {
+        let v3 = Reserves::new(
+            parse_ether("1000").unwrap(),
+            parse_ether("1000").unwrap(),
+            500,
+        );
+        let v2 = Reserves::new(
+            parse_ether("1000").unwrap(),
+            parse_ether("1000").unwrap(),
+            UNISWAP_V2_FEE,
+        );
+
+        let candidates = candidate_sizes(&v3, &v2, &[10, 100, 300, 1_000], 2);
+
+        // A 0.1% move doesn't cover the fees.
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].price_impact_bps, 100);
+        assert_eq!(candidates[1].price_impact_bps, 300);
+        assert!(candidates[0].amount_in < candidates[1].amount_in);
+    }
+}
@@ -1,32 +1,121 @@
-use std::{collections::HashMap, ops::Add, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use alloy::{
-    primitives::{Address, B256, Bytes, U256},
+    network::Ethereum,
+    primitives::{Address, Bytes, U256, keccak256},
     providers::Provider,
-    rpc::types::mev::{BundleItem, Inclusion, MevSendBundle, ProtocolVersion},
+    rpc::types::mev::ProtocolVersion,
+    serde::WithOtherFields,
 };
 use async_trait::async_trait;
-use kazuka_core::{error::KazukaError, types::Strategy};
+use kazuka_core::{
+    clock::{Clock, system_clock},
+    error::KazukaError,
+    executors::mempool_executor::SubmitTxToMempool,
+    signer::SignerProvider,
+    strategies::backrun::{
+        BackrunBundle, BackrunConfig, BackrunStrategy, Opportunity,
+        OpportunityEvaluator,
+    },
+    types::Strategy,
+};
+use kazuka_mev_share::rpc::MevApiClient;
 use kazuka_mev_share_arbitrage_bindings::blind_arb::BlindArb::BlindArbInstance;
 
 use crate::{
+    config::{ArbitrageConfig, Sizing},
     contracts::ArbitrageContract,
-    types::{Action, Event, UniswapV2PoolInfo, V2V3PoolRecord},
+    discovery::{PoolDiscovery, PoolDiscoveryConfig, WETH},
+    feedback::{Bid, BundleFeedback},
+    inventory::Inventory,
+    pool_state::PoolStateCache,
+    screening::TokenScreener,
+    signing::TxContext,
+    simulation,
+    sizing::{self, Candidate},
+    triangular::{Cycle, PoolGraph, TriangularArbitrage},
+    types::{Action, Event, V2Fork, V2PoolInfo, V2V3PoolRecord},
 };
 
-pub struct MevShareUniswapV2V3Arbitrage<P: Provider> {
+/// Name of the strategy its bundles are booked under, see
+/// [BundleOrigin](kazuka_core::pnl::BundleOrigin).
+pub const STRATEGY_NAME: &str = "mev-share-v2-v3-arbitrage";
+
+/// Size of a backrun, with the bid its outcome is tracked by.
+#[derive(Clone, Copy, Debug)]
+struct SizedBid {
+    bid: Bid,
+    size: U256,
+    /// Expected profit (in wei), zero with grid sizing.
+    profit: U256,
+}
+
+/// Backrun of a hinted pool, by the arbitrage contract or through a
+/// triangular cycle.
+#[derive(Clone, Debug)]
+enum Backrun {
+    /// Arbitrage of the V3 pool against its V2 pool.
+    Direct {
+        v2_pool_info: V2PoolInfo,
+    },
+    Triangular {
+        cycle: Cycle,
+    },
+}
+
+/// Backruns of a sized opportunity.
+#[derive(Clone, Debug)]
+struct SizedBackruns {
+    backruns: Vec<(SizedBid, Backrun)>,
+    /// Share of the profit of direct backruns paid to the builder.
+    payment_percentage: u8,
+    /// Fields shared by the txs of the backruns, `None` on dry runs.
+    context: Option<TxContext>,
+}
+
+impl SizedBackruns {
+    fn get(&self, size: U256) -> Option<&(SizedBid, Backrun)> {
+        self.backruns.iter().find(|(sized, _)| sized.size == size)
+    }
+}
+
+/// Backruns hints moving a V3 pool with the arbitrage contract against its
+/// V2 pool, and with the most promising triangular cycles through WETH.
+pub struct V2V3Evaluator<P: Provider> {
     /// Exposes Ethereum JSON-RPC methods.
     provider: Arc<P>,
     /// Maps Uniswap V3 pool address to Uniswap V2 pool info.
-    v3_address_to_v2_pool_info: HashMap<Address, UniswapV2PoolInfo>,
+    v3_address_to_v2_pool_info: HashMap<Address, V2PoolInfo>,
     /// Arbitrage contract.
     contract: ArbitrageContract<Arc<P>>,
     /// Whether to want to interact with a real arbitrage contract or just
     /// synthesize sample txs and log traces.
     dry_run: bool,
+    /// Discovers pools on-chain instead of loading them from the CSV.
+    discovery: Option<PoolDiscovery<P>>,
+    /// Pool file and backrun parameters.
+    config: ArbitrageConfig,
+    /// Backruns hints with triangular cycles through WETH.
+    triangular: Option<TriangularArbitrage<P>>,
+    /// States of the pools, which the backruns are sized from.
+    pool_states: PoolStateCache,
+    /// Whether the pool states are kept up to date from log events, and can
+    /// be reused across hints.
+    live_pool_states: bool,
+    /// Outcomes of the submitted bundles, which the bids adapt to.
+    feedback: BundleFeedback,
+    /// Screens the tokens of the pools before they are traded.
+    screener: Option<TokenScreener<P>>,
+    /// Balances of the arbitrage contract, which cap the backruns.
+    inventory: Option<Inventory<P>>,
+    /// Backruns of the last sized opportunity of every pool.
+    sized: HashMap<Address, SizedBackruns>,
 }
 
-impl<P: Provider> MevShareUniswapV2V3Arbitrage<P> {
+impl<P: Provider> V2V3Evaluator<P> {
     pub fn new(
         provider: Arc<P>,
         arbitrage_contract_address: Address,
@@ -38,103 +127,165 @@ impl<P: Provider> MevShareUniswapV2V3Arbitrage<P> {
         );
         let contract = ArbitrageContract::new(provider.clone(), instance);
         Self {
-            provider: provider.clone(),
+            provider,
             v3_address_to_v2_pool_info: HashMap::new(),
             contract,
             dry_run,
+            discovery: None,
+            config: ArbitrageConfig::default(),
+            triangular: None,
+            pool_states: PoolStateCache::new(),
+            live_pool_states: false,
+            feedback: BundleFeedback::default(),
+            screener: None,
+            inventory: None,
+            sized: HashMap::new(),
         }
     }
 
-    /// Generates bundles of varying sizes to submit to the matchmaker.
-    pub async fn generate_bundles(
-        &self,
-        v3_address: Address,
-        tx_hash: B256,
-    ) -> Result<Vec<MevSendBundle>, KazukaError> {
-        let mut bundles = Vec::new();
-
-        // The sizes of the backruns we want to submit.
-        // TODO: Run some analysis to figure out likely sizes.
-        let sizes = vec![
-            U256::from(100000_u128),
-            U256::from(1000000_u128),
-            U256::from(10000000_u128),
-            U256::from(100000000_u128),
-            U256::from(1000000000_u128),
-            U256::from(10000000000_u128),
-            U256::from(100000000000_u128),
-            U256::from(1000000000000_u128),
-            U256::from(10000000000000_u128),
-            U256::from(100000000000000_u128),
-            U256::from(1000000000000000_u128),
-            U256::from(10000000000000000_u128),
-            U256::from(100000000000000000_u128),
-            U256::from(1000000000000000000_u128),
-        ];
-
-        let v2_pool_info = self
-            .v3_address_to_v2_pool_info
-            .get(&v3_address)
-            .expect("Failed to get V3 pool info");
+    /// Signs the arbitrage txs with the transactions signer of the
+    /// `signers`, see [crate::signing].
+    pub fn set_signers(&mut self, signers: Arc<dyn SignerProvider>) {
+        if let Some(triangular) = &mut self.triangular {
+            triangular.set_signers(signers.clone());
+        }
+        self.contract.set_signers(signers);
+    }
 
+    fn load_pool_graph(&mut self) -> Result<(), KazukaError> {
+        let Some(triangular) = &mut self.triangular else {
+            return Ok(());
+        };
+        let Some(path) = &self.config.graph_pools_file else {
+            return Err(KazukaError::ConfigError(
+                "triangular arbitrage requires a graph pools file".to_string(),
+            ));
+        };
+        let graph = PoolGraph::from_csv(path, WETH)?;
         tracing::info!(
-            "Generating bundles to exploit arbitrage opportunity on Uniswap V3 pool at {:?} versus Uniswap V2 pool at {:?}",
-            v3_address,
-            v2_pool_info.v2_pool
+            "Loaded {} triangular cycles",
+            graph.len()
         );
+        triangular.set_graph(graph);
+        Ok(())
+    }
 
-        let block_num = self.provider.get_block_number().await?;
-
-        for size in sizes {
-            let tx_bytes = if self.dry_run {
-                Bytes::from_static(b"sample-tx")
-            } else {
-                self.contract
-                    .generate_arbitrage_tx(v3_address, v2_pool_info, size)
-                    .await?
-            };
+    /// Whether the token of the V2 pool passed the screening, if enabled.
+    /// Tokens which couldn't be screened aren't traded.
+    async fn is_token_safe(&mut self, v2_pool_info: &V2PoolInfo) -> bool {
+        let Some(screener) = &mut self.screener else {
+            return true;
+        };
+        match screener
+            .screen(
+                &self.config.screening,
+                v2_pool_info.token,
+                v2_pool_info.v2_pool,
+            )
+            .await
+        {
+            Ok(verdict) => verdict.is_safe(),
+            Err(e) => {
+                tracing::error!(
+                    token = ?v2_pool_info.token,
+                    "Error screening token: {}",
+                    e
+                );
+                false
+            }
+        }
+    }
 
-            let bundle_body = vec![
-                BundleItem::Hash { hash: tx_hash },
-                BundleItem::Tx {
-                    tx: tx_bytes,
-                    can_revert: false,
-                },
-            ];
-
-            let bundle = MevSendBundle {
-                protocol_version: ProtocolVersion::V0_1,
-                inclusion: Inclusion {
-                    block: block_num.add(1),
-                    // Set a large validity window to ensure builder gets a
-                    // chance to include bundle.
-                    max_block: Some(block_num.add(30)),
-                },
-                bundle_body,
-                validity: None,
-                privacy: None,
-            };
+    /// V2 pool info of the V3 pool, if it is known and its token is safe.
+    async fn tradable_pool(
+        &mut self,
+        v3_address: Address,
+    ) -> Option<V2PoolInfo> {
+        let v2_pool_info =
+            self.v3_address_to_v2_pool_info.get(&v3_address).cloned()?;
+        self.is_token_safe(&v2_pool_info)
+            .await
+            .then_some(v2_pool_info)
+    }
 
-            tracing::info!("Constructed bundle: {:?}", bundle);
+    /// Keeps the sizes the arbitrage contract can fund, if its inventory
+    /// is managed.
+    async fn fundable_sizes(
+        &mut self,
+        block_num: u64,
+        token: Address,
+        sizes: Vec<SizedBid>,
+    ) -> Result<Vec<SizedBid>, KazukaError> {
+        let Some(inventory) = &mut self.inventory else {
+            return Ok(sizes);
+        };
+        inventory.watch(token);
+        inventory.refresh(block_num).await?;
+        let (fundable, unfundable): (Vec<_>, Vec<_>) = sizes
+            .into_iter()
+            .partition(|sized| inventory.can_fund(sized.size));
+        if !unfundable.is_empty() {
+            tracing::debug!(
+                "Skipping {} backruns above the WETH balance {:?}",
+                unfundable.len(),
+                inventory.weth()
+            );
+        }
+        Ok(fundable)
+    }
 
-            bundles.push(bundle);
+    /// Transactions rebalancing the inventory of the arbitrage contract at
+    /// the block, if managed. They are only logged on dry runs.
+    async fn rebalance_inventory(
+        &mut self,
+        block_num: u64,
+    ) -> Result<Vec<Action>, KazukaError> {
+        let Some(inventory) = &mut self.inventory else {
+            return Ok(vec![]);
+        };
+        inventory.refresh(block_num).await?;
+        let txs = inventory.rebalance(&self.config.inventory, block_num);
+        if self.dry_run {
+            for tx in txs {
+                tracing::info!(
+                    "Skipping rebalancing tx on dry run: {:?}",
+                    tx
+                );
+            }
+            return Ok(vec![]);
         }
+        Ok(txs
+            .into_iter()
+            .map(|tx| {
+                Action::SubmitTx(SubmitTxToMempool {
+                    tx: WithOtherFields::new(tx),
+                    gas_bid_info: None,
+                    blob_sidecar: None,
+                })
+            })
+            .collect())
+    }
 
-        Ok(bundles)
+    /// Rediscovers pools if they are stale, keeping the known ones on
+    /// errors.
+    async fn refresh_pools(&mut self) {
+        let Some(discovery) = &mut self.discovery else {
+            return;
+        };
+        if !discovery.is_stale() {
+            return;
+        }
+        match discovery.refresh().await {
+            Ok(pools) => self.v3_address_to_v2_pool_info = pools,
+            Err(e) => tracing::error!("Error discovering pools: {}", e),
+        }
     }
-}
 
-#[async_trait]
-impl<P: Provider> Strategy<Event, Action> for MevShareUniswapV2V3Arbitrage<P> {
-    /// Syncs the initial state of the strategy.
-    /// This is called once at startup, and loads pool information into memory.
-    async fn sync_state(&mut self) -> Result<(), KazukaError> {
-        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        let file_name =
-            String::from("data/uniswap_v2_uniswap_v3_weth_pools.csv");
-        path.push(file_name.clone());
+    fn load_pools_from_csv(&mut self) -> Result<(), KazukaError> {
+        let path = &self.config.pools_file;
+        let file_name = path.display().to_string();
 
-        let mut reader = csv::Reader::from_path(path.clone()).map_err(|e| {
+        let mut reader = csv::Reader::from_path(path).map_err(|e| {
             KazukaError::CsvError(file_name.clone(), e.to_string())
         })?;
 
@@ -142,11 +293,40 @@ impl<P: Provider> Strategy<Event, Action> for MevShareUniswapV2V3Arbitrage<P> {
             let record: V2V3PoolRecord = record.map_err(|e| {
                 KazukaError::CsvError(file_name.clone(), e.to_string())
             })?;
+            let factory = record
+                .v2_factory
+                .unwrap_or_else(|| V2Fork::uniswap_v2().factory);
+            let fork = self
+                .config
+                .v2_forks
+                .iter()
+                .find(|fork| fork.factory == factory)
+                .ok_or_else(|| {
+                    KazukaError::CsvError(
+                        file_name.clone(),
+                        format!("unknown V2 factory {}", factory),
+                    )
+                })?;
+            if !fork.is_pair(
+                record.v2_pool,
+                record.token_address,
+                WETH,
+            ) {
+                return Err(KazukaError::CsvError(
+                    file_name.clone(),
+                    format!(
+                        "{} is not a {} pair of {}",
+                        record.v2_pool, fork.name, record.token_address
+                    ),
+                ));
+            }
             self.v3_address_to_v2_pool_info.insert(
                 record.v3_pool,
-                UniswapV2PoolInfo {
+                V2PoolInfo {
                     v2_pool: record.v2_pool,
+                    token: record.token_address,
                     is_weth_token0: record.is_weth_token0,
+                    fork: fork.clone(),
                 },
             );
         }
@@ -154,32 +334,561 @@ impl<P: Provider> Strategy<Event, Action> for MevShareUniswapV2V3Arbitrage<P> {
         Ok(())
     }
 
+    /// Computes the optimal backrun sizes from the current reserves of the
+    /// pools.
+    async fn optimal_sizes(
+        &mut self,
+        v3_address: Address,
+        v2_pool_info: &V2PoolInfo,
+    ) -> Result<Vec<Candidate>, KazukaError> {
+        let (v3, v2) = sizing::fetch_reserves(
+            self.provider.clone(),
+            &mut self.pool_states,
+            v3_address,
+            v2_pool_info,
+        )
+        .await?;
+        let candidates = sizing::candidate_sizes(
+            &v3,
+            &v2,
+            &self.config.price_impacts_bps,
+            self.config.max_bundles_per_event,
+        );
+        tracing::debug!("Backrun candidates: {:?}", candidates);
+        Ok(candidates)
+    }
+
+    /// Bids of the candidate sizes, skipping the ones that never land.
+    fn candidate_bids(&self, candidates: Vec<Candidate>) -> Vec<SizedBid> {
+        candidates
+            .into_iter()
+            .map(|candidate| SizedBid {
+                bid: Bid::PriceImpact(candidate.price_impact_bps),
+                size: candidate.amount_in,
+                profit: candidate.profit,
+            })
+            .filter(|sized| !self.feedback.is_dead(sized.bid))
+            .collect()
+    }
+
+    /// Number of blocks the bundles are valid for, adapted to the delays
+    /// the bundles land with.
+    fn inclusion_window(&self) -> u64 {
+        self.feedback.inclusion_window(self.config.inclusion_window)
+    }
+
+    /// Backruns of the V3 pool against its V2 pool, which the arbitrage
+    /// contract can fund.
+    async fn direct_backruns(
+        &mut self,
+        opportunity: &Opportunity<'_>,
+    ) -> Result<Vec<(SizedBid, Backrun)>, KazukaError> {
+        let v3_address = opportunity.pool;
+        let Some(v2_pool_info) = self.tradable_pool(v3_address).await else {
+            return Ok(vec![]);
+        };
+
+        // The sizes of the backruns we want to submit.
+        let sizes = match self.config.sizing {
+            Sizing::Grid => self
+                .config
+                .sizes
+                .iter()
+                .map(|size| SizedBid {
+                    bid: Bid::Size(*size),
+                    size: *size,
+                    profit: U256::ZERO,
+                })
+                .filter(|sized| !self.feedback.is_dead(sized.bid))
+                .take(self.config.max_bundles_per_event)
+                .collect(),
+            Sizing::Optimal => {
+                let candidates =
+                    self.optimal_sizes(v3_address, &v2_pool_info).await?;
+                self.candidate_bids(candidates)
+            }
+        };
+
+        tracing::info!(
+            "Generating bundles to exploit arbitrage opportunity on Uniswap V3 pool at {:?} versus Uniswap V2 pool at {:?}",
+            v3_address,
+            v2_pool_info.v2_pool
+        );
+
+        let sizes = self
+            .fundable_sizes(
+                opportunity.block_number,
+                v2_pool_info.token,
+                sizes,
+            )
+            .await?;
+        Ok(sizes
+            .into_iter()
+            .map(|sized| {
+                (
+                    sized,
+                    Backrun::Direct {
+                        v2_pool_info: v2_pool_info.clone(),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// Backruns of the most promising triangular cycle through the pool.
+    async fn triangular_backruns(
+        &mut self,
+        pool: Address,
+    ) -> Result<Vec<(SizedBid, Backrun)>, KazukaError> {
+        let Some(triangular) = &self.triangular else {
+            return Ok(vec![]);
+        };
+        let Some((cycle, candidates)) = triangular
+            .best_cycle(
+                &mut self.pool_states,
+                pool,
+                &self.config.price_impacts_bps,
+                self.config.max_bundles_per_event,
+            )
+            .await?
+        else {
+            return Ok(vec![]);
+        };
+        tracing::info!(
+            "Generating bundles to exploit triangular arbitrage opportunity through {:?}",
+            cycle.tokens()
+        );
+        Ok(self
+            .candidate_bids(candidates)
+            .into_iter()
+            .map(|sized| {
+                (
+                    sized,
+                    Backrun::Triangular {
+                        cycle: cycle.clone(),
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl<P: Provider> OpportunityEvaluator for V2V3Evaluator<P> {
+    async fn sync_state(&mut self) -> Result<(), KazukaError> {
+        self.load_pool_graph()?;
+        match &mut self.discovery {
+            Some(discovery) => {
+                self.v3_address_to_v2_pool_info = discovery.refresh().await?;
+                Ok(())
+            }
+            None => self.load_pools_from_csv(),
+        }
+    }
+
+    /// Known V3 pools with a safe token, and pools of triangular cycles.
+    async fn is_target(&mut self, pool: Address) -> bool {
+        if self.tradable_pool(pool).await.is_some() {
+            return true;
+        }
+        self.triangular
+            .as_ref()
+            .is_some_and(|triangular| triangular.has_cycles(pool))
+    }
+
+    /// Direct backruns first, then the triangular ones, instead of the
+    /// given sizes.
+    async fn sizes(
+        &mut self,
+        opportunity: &Opportunity<'_>,
+        _sizes: &[U256],
+    ) -> Result<Vec<U256>, KazukaError> {
+        self.feedback.prune(opportunity.block_number);
+        let mut backruns = vec![];
+        match self.direct_backruns(opportunity).await {
+            Ok(direct) => backruns.extend(direct),
+            Err(e) => tracing::error!("Error generating bundles: {:?}", e),
+        }
+        match self.triangular_backruns(opportunity.pool).await {
+            Ok(triangular) => backruns.extend(triangular),
+            Err(e) => {
+                tracing::error!(
+                    "Error generating triangular bundles: {:?}",
+                    e
+                )
+            }
+        }
+        // Backruns are told apart by their size.
+        let mut sizes = HashSet::new();
+        backruns.retain(|(sized, _)| sizes.insert(sized.size));
+
+        // The backruns are alternatives, so their txs share the nonce.
+        let context = if self.dry_run || backruns.is_empty() {
+            None
+        } else {
+            Some(self.contract.tx_context().await?)
+        };
+        let payment_percentage = self.feedback.payment_percentage(
+            opportunity.pool,
+            self.config.payment_percentage,
+        );
+        let sizes = backruns.iter().map(|(sized, _)| sized.size).collect();
+        self.sized.insert(
+            opportunity.pool,
+            SizedBackruns {
+                backruns,
+                payment_percentage,
+                context,
+            },
+        );
+        Ok(sizes)
+    }
+
+    async fn backrun_tx(
+        &self,
+        opportunity: &Opportunity<'_>,
+        size: U256,
+    ) -> Result<Option<Bytes>, KazukaError> {
+        let Some(sized) = self.sized.get(&opportunity.pool) else {
+            return Ok(None);
+        };
+        let Some((_, backrun)) = sized.get(size) else {
+            return Ok(None);
+        };
+        let Some(context) = &sized.context else {
+            return Ok(Some(Bytes::from_static(b"sample-tx")));
+        };
+        let tx = match backrun {
+            Backrun::Direct { v2_pool_info } => {
+                self.contract
+                    .generate_arbitrage_tx(
+                        context,
+                        opportunity.pool,
+                        v2_pool_info,
+                        size,
+                        sized.payment_percentage,
+                    )
+                    .await?
+            }
+            Backrun::Triangular { cycle } => {
+                let Some(triangular) = &self.triangular else {
+                    return Ok(None);
+                };
+                triangular
+                    .arbitrage_tx(
+                        context,
+                        cycle,
+                        size,
+                        self.inclusion_window(),
+                    )
+                    .await?
+            }
+        };
+        Ok(Some(tx))
+    }
+
+    fn expected_profit(
+        &self,
+        opportunity: &Opportunity<'_>,
+        size: U256,
+    ) -> U256 {
+        self.sized
+            .get(&opportunity.pool)
+            .and_then(|sized| sized.get(size))
+            .map_or(U256::ZERO, |(sized, _)| sized.profit)
+    }
+
+    /// Tracks the outcome of the bundle, which the bids adapt to.
+    fn on_backrun(
+        &mut self,
+        opportunity: &Opportunity<'_>,
+        size: U256,
+        tx: &Bytes,
+    ) {
+        if self.dry_run {
+            return;
+        }
+        let Some(bid) = self
+            .sized
+            .get(&opportunity.pool)
+            .and_then(|sized| sized.get(size))
+            .map(|(sized, _)| sized.bid)
+        else {
+            return;
+        };
+        self.feedback.record_submission(
+            keccak256(tx),
+            opportunity.pool,
+            bid,
+            opportunity.block_number + 1,
+        );
+    }
+}
+
+/// Arbitrages V3 pools against their V2 pools, and through triangular
+/// cycles, by running a [V2V3Evaluator] in a [BackrunStrategy].
+pub struct MevShareUniswapV2V3Arbitrage<P: Provider> {
+    backrun: BackrunStrategy<V2V3Evaluator<P>, Ethereum>,
+    /// Exposes Ethereum JSON-RPC methods.
+    provider: Arc<P>,
+    /// Protocol version of the submitted bundles.
+    protocol_version: ProtocolVersion,
+    /// Pool file and backrun parameters.
+    config: ArbitrageConfig,
+    /// Simulates bundles to discard unprofitable ones before submission.
+    simulator: Option<Box<dyn MevApiClient + Send + Sync>>,
+    /// Tells the age of the hints.
+    clock: Arc<dyn Clock>,
+}
+
+impl<P: Provider + 'static> MevShareUniswapV2V3Arbitrage<P> {
+    pub fn new(
+        provider: Arc<P>,
+        arbitrage_contract_address: Address,
+        dry_run: bool,
+    ) -> Self {
+        let evaluator = V2V3Evaluator::new(
+            provider.clone(),
+            arbitrage_contract_address,
+            dry_run,
+        );
+        Self {
+            backrun: BackrunStrategy::<_, Ethereum>::new(
+                provider.clone(),
+                evaluator,
+            ),
+            provider,
+            protocol_version: ProtocolVersion::V0_1,
+            config: ArbitrageConfig::default(),
+            simulator: None,
+            clock: system_clock(),
+        }
+        .with_config(ArbitrageConfig::default())
+    }
+
+    /// Signs the arbitrage txs with the transactions signer of the
+    /// `signers`, instead of the wallet of the provider.
+    pub fn with_signers(mut self, signers: Arc<dyn SignerProvider>) -> Self {
+        self.backrun.evaluator_mut().set_signers(signers);
+        self
+    }
+
+    /// Keeps the states of the pools in memory across hints, updated from
+    /// [Event::Log]s matching [PoolStateCache::log_filter], which the engine
+    /// must be fed with. Otherwise, the states are fetched for every hint.
+    pub fn with_live_pool_states(mut self) -> Self {
+        self.backrun.evaluator_mut().live_pool_states = true;
+        self
+    }
+
+    /// Also backruns hints with the most promising triangular cycle through
+    /// the hinted pools, sending the proceeds to the recipient. The pools of
+    /// the cycles are loaded from
+    /// [graph_pools_file](ArbitrageConfig::graph_pools_file).
+    pub fn with_triangular_arbitrage(mut self, recipient: Address) -> Self {
+        let evaluator = self.backrun.evaluator_mut();
+        let mut triangular =
+            TriangularArbitrage::new(self.provider.clone(), recipient);
+        if let Some(signers) = evaluator.contract.signers() {
+            triangular.set_signers(signers);
+        }
+        evaluator.triangular = Some(triangular);
+        self
+    }
+
+    /// Simulates every bundle before submitting it, and discards the ones
+    /// with an expected value below
+    /// [min_expected_value](ArbitrageConfig::min_expected_value).
+    pub fn with_simulator(
+        mut self,
+        simulator: Box<dyn MevApiClient + Send + Sync>,
+    ) -> Self {
+        self.simulator = Some(simulator);
+        self
+    }
+
+    /// Only trades pools whose token passes the screening of
+    /// [screening](ArbitrageConfig::screening), with round trips through
+    /// the arbitrage contract.
+    pub fn with_token_screening(mut self) -> Self {
+        let evaluator = self.backrun.evaluator_mut();
+        evaluator.screener = Some(TokenScreener::new(
+            self.provider.clone(),
+            evaluator.contract.address(),
+        ));
+        self
+    }
+
+    /// Only submits backruns the arbitrage contract holds enough WETH for,
+    /// and keeps its balance within the bounds of
+    /// [inventory](ArbitrageConfig::inventory) with transfers from (and
+    /// to) the owner, which must be the owner of the contract. The balance
+    /// is rebalanced on [Event::NewBlock]s, which the engine must be fed
+    /// with.
+    pub fn with_inventory_management(mut self, owner: Address) -> Self {
+        let evaluator = self.backrun.evaluator_mut();
+        evaluator.inventory = Some(Inventory::new(
+            self.provider.clone(),
+            evaluator.contract.address(),
+            owner,
+        ));
+        self
+    }
+
+    /// Sets the pool file and backrun parameters.
+    pub fn with_config(mut self, config: ArbitrageConfig) -> Self {
+        let evaluator = self.backrun.evaluator_mut();
+        evaluator.feedback = BundleFeedback::new(config.feedback.clone());
+        evaluator.config = config.clone();
+        self.config = config;
+        let backrun_config = self.backrun_config();
+        self.backrun = self.backrun.with_config(backrun_config);
+        self
+    }
+
+    /// Discovers pools from factory logs at startup, and rediscovers them
+    /// periodically while processing events.
+    pub fn with_pool_discovery(mut self, config: PoolDiscoveryConfig) -> Self {
+        self.backrun.evaluator_mut().discovery = Some(PoolDiscovery::new(
+            self.provider.clone(),
+            config,
+        ));
+        self
+    }
+
+    /// Sets the protocol version of the submitted bundles, which must be
+    /// accepted by the relay.
+    pub fn with_protocol_version(
+        mut self,
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        self.protocol_version = protocol_version;
+        let backrun_config = self.backrun_config();
+        self.backrun = self.backrun.with_config(backrun_config);
+        self
+    }
+
+    /// Sets the clock the age of the hints is measured with.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Bundle parameters of the backrun config, whose sizes are picked by
+    /// the evaluator.
+    fn backrun_config(&self) -> BackrunConfig {
+        BackrunConfig {
+            max_bundles_per_event: self.config.max_bundles_per_event,
+            inclusion_window: self.config.inclusion_window,
+            protocol_version: self.protocol_version.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl<P: Provider> MevShareUniswapV2V3Arbitrage<P> {
+    /// Keeps the bundles worth submitting, if a simulator is set. Bundles
+    /// of dry runs can't be simulated, so they are all kept.
+    async fn filter_profitable(
+        &self,
+        bundles: Vec<BackrunBundle>,
+    ) -> Result<Vec<BackrunBundle>, KazukaError> {
+        let Some(simulator) = self
+            .simulator
+            .as_ref()
+            .filter(|_| !self.backrun.evaluator().dry_run)
+        else {
+            return Ok(bundles);
+        };
+        if bundles.is_empty() {
+            return Ok(bundles);
+        }
+        let gas_price = self.provider.get_gas_price().await?;
+        Ok(simulation::filter_profitable(
+            simulator.as_ref(),
+            bundles,
+            gas_price,
+            self.config.min_expected_value,
+        )
+        .await)
+    }
+}
+
+#[async_trait]
+impl<P: Provider> Strategy<Event, Action> for MevShareUniswapV2V3Arbitrage<P> {
+    /// Syncs the initial state of the strategy.
+    /// This is called once at startup, and loads pool information into memory.
+    async fn sync_state(&mut self) -> Result<(), KazukaError> {
+        self.backrun.sync_state().await
+    }
+
     /// Processes a MEV-share event, and return an action if needed.
     async fn process_event(&mut self, event: Event) -> Vec<Action> {
         match event {
-            Event::MevShareEvent(event) => {
-                tracing::trace!("Received MEV-share event: {:?}", event);
-                // Skip if event has no logs.
-                if event.logs.is_empty() {
+            Event::MevShareEvent(hint) => {
+                tracing::trace!(
+                    "Received MEV-share event: {:?}",
+                    hint.inner
+                );
+                // Backruns of stale hints would most likely miss the target
+                // tx, and waste the reputation of the searcher.
+                let age = hint.age_on(self.clock.as_ref());
+                if age > self.config.max_hint_age() {
+                    tracing::debug!(
+                        "Skipping stale hint {:?} received {:?} ago",
+                        hint.inner.hash,
+                        age
+                    );
                     return vec![];
                 }
-                let v3_address = event.logs[0].address;
-                // Skip if address is not a V3 pool.
-                if !self.v3_address_to_v2_pool_info.contains_key(&v3_address) {
-                    return vec![];
+                let evaluator = self.backrun.evaluator_mut();
+                evaluator.refresh_pools().await;
+                if !evaluator.live_pool_states {
+                    evaluator.pool_states.clear();
                 }
+                let inclusion_window = evaluator.inclusion_window();
+                self.backrun.config_mut().inclusion_window = inclusion_window;
 
-                tracing::info!(
-                    "Found a V3 pool match at address {:?}, generating bundles",
-                    v3_address
-                );
-
-                match self.generate_bundles(v3_address, event.hash).await {
-                    Ok(bundles) => {
-                        bundles.into_iter().map(Action::SubmitBundle).collect()
+                let bundles = self.backrun.process_event(hint.inner).await;
+                match self.filter_profitable(bundles).await {
+                    Ok(bundles) => bundles
+                        .into_iter()
+                        .map(|bundle| {
+                            Action::SubmitBundle(
+                                bundle.attributed(STRATEGY_NAME),
+                            )
+                        })
+                        .collect(),
+                    Err(e) => {
+                        tracing::error!("Error simulating bundles: {:?}", e);
+                        vec![]
                     }
+                }
+            }
+            Event::Log(log) => {
+                let evaluator = self.backrun.evaluator_mut();
+                if evaluator.live_pool_states {
+                    evaluator.pool_states.apply_log(&log);
+                }
+                vec![]
+            }
+            Event::BundleStatus(status) => {
+                let evaluator = self.backrun.evaluator_mut();
+                if evaluator.feedback.record_status(&status) {
+                    tracing::debug!("Recorded bundle outcome: {:?}", status);
+                }
+                vec![]
+            }
+            Event::NewBlock(block) => {
+                match self
+                    .backrun
+                    .evaluator_mut()
+                    .rebalance_inventory(block.number)
+                    .await
+                {
+                    Ok(actions) => actions,
                     Err(e) => {
-                        tracing::error!("Error generating bundles: {:?}", e);
+                        tracing::error!("Error rebalancing inventory: {:?}", e);
                         vec![]
                     }
                 }
@@ -0,0 +1,589 @@
+//! Triangular arbitrage (WETH → A → B → WETH) over a graph of pools built at
+//! sync time from a pool file:
+//!
+//! ```csv
+//! pool,kind,token0,token1,fee
+//! 0xb4e16d0168e52d35cacd2c6185b44281ec28c9dc,v2,0xa0b8...,0xc02a...,3000
+//! 0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640,v3,0xa0b8...,0xc02a...,500
+//! ```
+//!
+//! Cycles are executed by the Uniswap router of their protocol, which swaps
+//! through the canonical pool of each pair, so:
+//! - the pool file only lists Uniswap V2 and V3 pools, pools of forks are
+//!   rejected,
+//! - all pools of a cycle are either V2 or V3 pools, mixed V2/V3 cycles are not
+//!   arbitraged as neither router can execute them.
+
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{Address, B256, Bytes, U256, address, b256, keccak256},
+    providers::Provider,
+    sol,
+};
+use kazuka_amm_math::v2::Reserves;
+use kazuka_core::{error::KazukaError, signer::SignerProvider};
+use serde::Deserialize;
+
+use crate::{
+    discovery::UNISWAP_V3_FACTORY,
+    fee_tier::{ISwapRouterMultihop, SWAP_ROUTER, swap_deadline},
+    pool_state::PoolStateCache,
+    signing::{self, TxContext},
+    sizing::{self, Candidate},
+    types::V2Fork,
+};
+
+sol! {
+    #[sol(rpc)]
+    interface IUniswapV2Router {
+        function swapExactTokensForTokens(
+            uint256 amountIn,
+            uint256 amountOutMin,
+            address[] calldata path,
+            address to,
+            uint256 deadline
+        ) external returns (uint256[] memory amounts);
+    }
+}
+
+pub const UNISWAP_V2_ROUTER: Address =
+    address!("0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D");
+
+/// Hash of the init code of the Uniswap V3 pools, from which the router
+/// derives their addresses.
+const UNISWAP_V3_POOL_INIT_CODE_HASH: B256 =
+    b256!("0xe34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54");
+
+/// Address of the Uniswap V3 pool of the tokens at the fee tier.
+fn uniswap_v3_pool_address(
+    token_a: Address,
+    token_b: Address,
+    fee: u32,
+) -> Address {
+    let (token0, token1) = if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    };
+    let salt = keccak256(
+        [
+            token0.into_word(),
+            token1.into_word(),
+            B256::from(U256::from(fee)),
+        ]
+        .concat(),
+    );
+    UNISWAP_V3_FACTORY.create2(salt, UNISWAP_V3_POOL_INIT_CODE_HASH)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolKind {
+    V2,
+    V3,
+}
+
+/// A pool of the graph, as listed in the pool file.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct GraphPool {
+    pub pool: Address,
+    pub kind: PoolKind,
+    pub token0: Address,
+    pub token1: Address,
+    /// Fee in hundredths of a bip.
+    pub fee: u32,
+}
+
+impl GraphPool {
+    /// Whether the router of its kind swaps through the pool, which must be
+    /// the Uniswap pool of its tokens (and fee).
+    pub fn is_routable(&self) -> bool {
+        match self.kind {
+            PoolKind::V2 => {
+                V2Fork::uniswap_v2().pair_address(self.token0, self.token1)
+                    == Some(self.pool)
+            }
+            PoolKind::V3 => {
+                uniswap_v3_pool_address(self.token0, self.token1, self.fee)
+                    == self.pool
+            }
+        }
+    }
+}
+
+/// A swap through a pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hop {
+    pub pool: Address,
+    pub kind: PoolKind,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub fee: u32,
+}
+
+impl Hop {
+    pub fn zero_for_one(&self) -> bool {
+        self.token_in < self.token_out
+    }
+}
+
+/// Swaps WETH to A, A to B, and B back to WETH.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub hops: [Hop; 3],
+}
+
+impl Cycle {
+    pub fn kind(&self) -> PoolKind {
+        self.hops[0].kind
+    }
+
+    /// Tokens of the cycle, starting and ending with WETH.
+    pub fn tokens(&self) -> Vec<Address> {
+        let mut tokens = vec![self.hops[0].token_in];
+        tokens.extend(self.hops.iter().map(|hop| hop.token_out));
+        tokens
+    }
+
+    /// Path of the multihop swap of the V3 router.
+    pub fn v3_path(&self) -> Bytes {
+        let mut path = self.hops[0].token_in.to_vec();
+        for hop in &self.hops {
+            path.extend_from_slice(&hop.fee.to_be_bytes()[1..]);
+            path.extend_from_slice(hop.token_out.as_slice());
+        }
+        path.into()
+    }
+}
+
+/// Cycles through WETH of pools of the same protocol, mixed V2/V3 cycles
+/// are skipped.
+#[derive(Debug, Default)]
+pub struct PoolGraph {
+    cycles: Vec<Cycle>,
+    /// Indices of the cycles by the pools they swap through.
+    by_pool: HashMap<Address, Vec<usize>>,
+}
+
+impl PoolGraph {
+    pub fn new(pools: &[GraphPool], weth: Address) -> Self {
+        let mut hops: HashMap<Address, Vec<Hop>> = HashMap::new();
+        for pool in pools {
+            for (token_in, token_out) in
+                [(pool.token0, pool.token1), (pool.token1, pool.token0)]
+            {
+                hops.entry(token_in).or_default().push(Hop {
+                    pool: pool.pool,
+                    kind: pool.kind,
+                    token_in,
+                    token_out,
+                    fee: pool.fee,
+                });
+            }
+        }
+
+        let hops_from = |token: Address| {
+            hops.get(&token).map(Vec::as_slice).unwrap_or_default()
+        };
+        let mut graph = Self::default();
+        for first in hops_from(weth) {
+            for second in hops_from(first.token_out) {
+                if second.token_out == weth || second.kind != first.kind {
+                    continue;
+                }
+                for third in hops_from(second.token_out) {
+                    if third.token_out != weth || third.kind != first.kind {
+                        continue;
+                    }
+                    graph.add(Cycle {
+                        hops: [first.clone(), second.clone(), third.clone()],
+                    });
+                }
+            }
+        }
+        graph
+    }
+
+    /// Loads the pools of the graph from a CSV file, rejecting the pools the
+    /// routers can't swap through, see [GraphPool::is_routable].
+    pub fn from_csv(
+        path: impl AsRef<Path>,
+        weth: Address,
+    ) -> Result<Self, KazukaError> {
+        let file_name = path.as_ref().display().to_string();
+        let csv_error = |e: csv::Error| {
+            KazukaError::CsvError(file_name.clone(), e.to_string())
+        };
+        let mut reader = csv::Reader::from_path(path).map_err(csv_error)?;
+        let pools = reader
+            .deserialize()
+            .collect::<Result<Vec<GraphPool>, _>>()
+            .map_err(csv_error)?;
+        if let Some(pool) = pools.iter().find(|pool| !pool.is_routable()) {
+            return Err(KazukaError::CsvError(
+                file_name,
+                format!(
+                    "{:?} is not the Uniswap {:?} pool of its tokens",
+                    pool.pool, pool.kind
+                ),
+            ));
+        }
+        Ok(Self::new(&pools, weth))
+    }
+
+    fn add(&mut self, cycle: Cycle) {
+        let index = self.cycles.len();
+        for hop in &cycle.hops {
+            self.by_pool.entry(hop.pool).or_default().push(index);
+        }
+        self.cycles.push(cycle);
+    }
+
+    pub fn len(&self) -> usize {
+        self.cycles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cycles.is_empty()
+    }
+
+    /// Cycles swapping through the pool.
+    pub fn cycles(&self, pool: Address) -> impl Iterator<Item = &Cycle> {
+        self.by_pool
+            .get(&pool)
+            .into_iter()
+            .flatten()
+            .map(|index| &self.cycles[*index])
+    }
+}
+
+/// Picks the most promising cycle: the one profitable at the earliest of
+/// the price impacts of the hinted pool, with the highest profit.
+///
+/// Returns the index of the cycle, and its candidate sizes.
+pub fn best_cycle(
+    cycles: &[(Cycle, Vec<Reserves>)],
+    hinted_pool: Address,
+    price_impacts_bps: &[u64],
+    max_candidates: usize,
+) -> Option<(usize, Vec<Candidate>)> {
+    cycles
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (cycle, reserves))| {
+            let hinted =
+                cycle.hops.iter().position(|hop| hop.pool == hinted_pool)?;
+            let candidates = sizing::path_candidates(
+                reserves,
+                hinted,
+                price_impacts_bps,
+                max_candidates,
+            );
+            let first = *candidates.first()?;
+            let impact_index = price_impacts_bps
+                .iter()
+                .position(|impact| *impact == first.price_impact_bps);
+            Some((
+                (impact_index, first.profit),
+                index,
+                candidates,
+            ))
+        })
+        .min_by(|(a, ..), (b, ..)| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+        .map(|(_, index, candidates)| (index, candidates))
+}
+
+/// Backruns hints touching the pools of a [PoolGraph] with the most
+/// promising cycle.
+///
+/// The routers pull WETH from the sender of the txs, see [TxContext], which
+/// must have approved it to them, and send the proceeds to the recipient.
+pub struct TriangularArbitrage<P: Provider> {
+    provider: Arc<P>,
+    graph: PoolGraph,
+    /// Receives the proceeds of the swaps.
+    recipient: Address,
+    /// Signs the swap txs offline instead of the node.
+    signers: Option<Arc<dyn SignerProvider>>,
+}
+
+impl<P: Provider> TriangularArbitrage<P> {
+    pub fn new(provider: Arc<P>, recipient: Address) -> Self {
+        Self {
+            provider,
+            graph: PoolGraph::default(),
+            recipient,
+            signers: None,
+        }
+    }
+
+    /// Signs the swap txs with the transactions signer of the `signers`,
+    /// see [crate::signing].
+    pub fn set_signers(&mut self, signers: Arc<dyn SignerProvider>) {
+        self.signers = Some(signers);
+    }
+
+    /// Fields shared by the swap txs of a hint, see [crate::signing].
+    pub async fn tx_context(&self) -> Result<TxContext, KazukaError> {
+        TxContext::fetch(
+            self.provider.as_ref(),
+            self.signers.as_deref(),
+        )
+        .await
+    }
+
+    pub fn set_graph(&mut self, graph: PoolGraph) {
+        self.graph = graph;
+    }
+
+    /// Whether cycles of the graph swap through the pool.
+    pub fn has_cycles(&self, pool: Address) -> bool {
+        self.graph.cycles(pool).next().is_some()
+    }
+
+    async fn reserves(
+        &self,
+        cache: &mut PoolStateCache,
+        cycle: &Cycle,
+    ) -> Result<Vec<Reserves>, KazukaError> {
+        let mut reserves = Vec::with_capacity(cycle.hops.len());
+        for hop in &cycle.hops {
+            let provider = self.provider.clone();
+            reserves.push(match hop.kind {
+                PoolKind::V2 => {
+                    cache
+                        .v2_reserves(
+                            provider,
+                            hop.pool,
+                            hop.zero_for_one(),
+                            hop.fee,
+                        )
+                        .await?
+                }
+                PoolKind::V3 => {
+                    cache
+                        .v3_reserves(provider, hop.pool, hop.zero_for_one())
+                        .await?
+                }
+            });
+        }
+        Ok(reserves)
+    }
+
+    /// Finds the most promising cycle through the hinted pool, and its
+    /// candidate sizes, from the cached reserves of the pools.
+    pub async fn best_cycle(
+        &self,
+        cache: &mut PoolStateCache,
+        hinted_pool: Address,
+        price_impacts_bps: &[u64],
+        max_candidates: usize,
+    ) -> Result<Option<(Cycle, Vec<Candidate>)>, KazukaError> {
+        let mut cycles = vec![];
+        for cycle in self.graph.cycles(hinted_pool) {
+            cycles.push((
+                cycle.clone(),
+                self.reserves(cache, cycle).await?,
+            ));
+        }
+        Ok(best_cycle(
+            &cycles,
+            hinted_pool,
+            price_impacts_bps,
+            max_candidates,
+        )
+        .map(|(index, candidates)| (cycles.swap_remove(index).0, candidates)))
+    }
+
+    /// Signs the swap of the input through the cycle, with the fields of the
+    /// `context`, which reverts unless it breaks even.
+    pub async fn arbitrage_tx(
+        &self,
+        context: &TxContext,
+        cycle: &Cycle,
+        amount_in: U256,
+        inclusion_window: u64,
+    ) -> Result<Bytes, KazukaError> {
+        let deadline = swap_deadline(inclusion_window);
+        let mut tx = match cycle.kind() {
+            PoolKind::V2 => {
+                IUniswapV2Router::new(UNISWAP_V2_ROUTER, self.provider.clone())
+                    .swapExactTokensForTokens(
+                        amount_in,
+                        amount_in,
+                        cycle.tokens(),
+                        self.recipient,
+                        deadline,
+                    )
+                    .into_transaction_request()
+            }
+            PoolKind::V3 => {
+                ISwapRouterMultihop::new(SWAP_ROUTER, self.provider.clone())
+                    .exactInput(ISwapRouterMultihop::ExactInputParams {
+                        path: cycle.v3_path(),
+                        recipient: self.recipient,
+                        deadline,
+                        amountIn: amount_in,
+                        amountOutMinimum: amount_in,
+                    })
+                    .into_transaction_request()
+            }
+        };
+        tx.set_gas_limit(500000);
+        context.apply(&mut tx);
+
+        signing::sign_tx(
+            self.provider.as_ref(),
+            self.signers.as_deref(),
+            tx,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::utils::parse_ether;
+
+    use super::*;
+    use crate::discovery::WETH;
+
+    fn pool(
+        byte: u8,
+        kind: PoolKind,
+        token_a: Address,
+        token_b: Address,
+    ) -> GraphPool {
+        GraphPool {
+            pool: Address::repeat_byte(byte),
+            kind,
+            token0: token_a.min(token_b),
+            token1: token_a.max(token_b),
+            fee: 3_000,
+        }
+    }
+
+    #[test]
+    fn test_pool_graph() {
+        let a = Address::repeat_byte(0xaa);
+        let b = Address::repeat_byte(0xbb);
+        let graph = PoolGraph::new(
+            &[
+                pool(1, PoolKind::V2, WETH, a),
+                pool(2, PoolKind::V2, a, b),
+                pool(3, PoolKind::V2, b, WETH),
+                // Mixed cycles are skipped.
+                pool(4, PoolKind::V3, b, WETH),
+            ],
+            WETH,
+        );
+
+        // Both directions of the cycle.
+        assert_eq!(graph.len(), 2);
+        let cycles: Vec<_> = graph.cycles(Address::repeat_byte(2)).collect();
+        assert_eq!(cycles.len(), 2);
+        assert_eq!(cycles[0].tokens(), [WETH, a, b, WETH]);
+        assert_eq!(cycles[1].tokens(), [WETH, b, a, WETH]);
+        assert_eq!(
+            graph.cycles(Address::repeat_byte(4)).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_routable_pools() {
+        let usdc = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        let pool = |pool, kind, fee| GraphPool {
+            pool,
+            kind,
+            token0: usdc,
+            token1: WETH,
+            fee,
+        };
+        let v2 = address!("0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc");
+        let v3 = address!("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640");
+        // Pools of forks aren't traded by the Uniswap routers.
+        let fork = Address::repeat_byte(1);
+
+        assert!(pool(v2, PoolKind::V2, 3_000).is_routable());
+        assert!(pool(v3, PoolKind::V3, 500).is_routable());
+        assert!(!pool(fork, PoolKind::V2, 3_000).is_routable());
+        assert!(!pool(v3, PoolKind::V3, 3_000).is_routable());
+    }
+
+    #[test]
+    fn test_v3_path() {
+        let a = Address::repeat_byte(0xaa);
+        let b = Address::repeat_byte(0xbb);
+        let graph = PoolGraph::new(
+            &[
+                pool(1, PoolKind::V3, WETH, a),
+                pool(2, PoolKind::V3, a, b),
+                pool(3, PoolKind::V3, b, WETH),
+            ],
+            WETH,
+        );
+        let cycle = graph.cycles(Address::repeat_byte(1)).next().unwrap();
+
+        let path = cycle.v3_path();
+
+        assert_eq!(path.len(), 20 * 4 + 3 * 3);
+        assert_eq!(&path[..20], WETH.as_slice());
+        assert_eq!(&path[20..23], &[0x00, 0x0b, 0xb8]);
+        assert_eq!(
+            &path[path.len() - 20..],
+            WETH.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_best_cycle() {
+        let hop = |byte: u8| Hop {
+            pool: Address::repeat_byte(byte),
+            kind: PoolKind::V2,
+            token_in: Address::ZERO,
+            token_out: Address::ZERO,
+            fee: 3_000,
+        };
+        let reserves = Reserves::new(
+            parse_ether("1000").unwrap(),
+            parse_ether("1000").unwrap(),
+            3_000,
+        );
+        let cycle = |byte: u8| Cycle {
+            hops: [hop(1), hop(byte), hop(byte + 1)],
+        };
+        // The second cycle has deeper pools after the hinted one.
+        let deep = Reserves::new(
+            parse_ether("10000").unwrap(),
+            parse_ether("10000").unwrap(),
+            3_000,
+        );
+        let cycles = [
+            (cycle(2), vec![reserves; 3]),
+            (cycle(4), vec![reserves, deep, deep]),
+        ];
+
+        let (index, candidates) = best_cycle(
+            &cycles,
+            Address::repeat_byte(1),
+            &[100, 300],
+            2,
+        )
+        .unwrap();
+
+        assert_eq!(index, 1);
+        assert_eq!(candidates.len(), 2);
+        assert!(
+            best_cycle(
+                &cycles,
+                Address::repeat_byte(9),
+                &[100],
+                2
+            )
+            .is_none()
+        );
+    }
+}
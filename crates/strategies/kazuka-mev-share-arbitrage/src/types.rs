@@ -1,15 +1,42 @@
-use alloy::{primitives::Address, rpc::types::mev::MevSendBundle};
+use alloy::{
+    primitives::{Address, B256, address, b256, keccak256},
+    rpc::types::Log,
+};
+use kazuka_core::{
+    event_sources::{
+        block_event_source::NewBlock, bundle_stats_event_source::BundleStatus,
+    },
+    executors::{
+        flashbots_bundle_executor::AttributedBundle,
+        mempool_executor::SubmitTxToMempool,
+    },
+    types::Timestamped,
+};
 use kazuka_mev_share::sse;
 
-#[derive(Clone, Debug)]
-pub enum Event {
-    MevShareEvent(sse::Event),
+kazuka_core::variants! {
+    #[derive(Clone, Debug)]
+    pub enum Event {
+        /// Hint, stamped with the time it was received at.
+        MevShareEvent(Timestamped<sse::Event>),
+        /// Log of a pool, see [crate::pool_state].
+        Log(Log),
+        /// Outcome of a submitted bundle, see [crate::feedback].
+        BundleStatus(BundleStatus),
+        /// New block, on which the inventory is rebalanced, see
+        /// [crate::inventory].
+        NewBlock(NewBlock),
+    }
 }
 
-#[derive(Clone, Debug)]
-pub enum Action {
-    // Submit a bundle of transactions to the matchmaker.
-    SubmitBundle(MevSendBundle),
+kazuka_core::variants! {
+    #[derive(Clone, Debug)]
+    pub enum Action {
+        // Submit a bundle of transactions to the matchmaker.
+        SubmitBundle(AttributedBundle),
+        /// Submit a transaction to the mempool, see [crate::inventory].
+        SubmitTx(SubmitTxToMempool),
+    }
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -19,18 +46,112 @@ pub struct PoolRecord {
     pub sushi_pool_address: Address,
 }
 
-#[derive(Debug, serde::Deserialize)]
+/// Row of the [pools_file](crate::config::ArbitrageConfig::pools_file).
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct V2V3PoolRecord {
     pub token_address: Address,
     pub v2_pool: Address,
     pub v3_pool: Address,
     pub is_weth_token0: bool,
+    /// Factory of the V2 pool, Uniswap V2 if missing.
+    #[serde(default)]
+    pub v2_factory: Option<Address>,
+}
+
+/// A Uniswap V2 fork, whose pairs are deployed by its factory with CREATE2.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+pub struct V2Fork {
+    pub name: String,
+    pub factory: Address,
+    /// Hash of the init code of the pairs, if known, which identifies the
+    /// pairs of the fork without querying them.
+    pub init_code_hash: Option<B256>,
+    /// Swap fee in hundredths of a bip.
+    pub fee: u32,
+}
+
+impl V2Fork {
+    pub fn uniswap_v2() -> Self {
+        Self {
+            name: "uniswap-v2".to_string(),
+            factory: address!("0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"),
+            init_code_hash: Some(b256!(
+                "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f"
+            )),
+            fee: 3_000,
+        }
+    }
+
+    pub fn sushiswap() -> Self {
+        Self {
+            name: "sushiswap".to_string(),
+            factory: address!("0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac"),
+            init_code_hash: None,
+            fee: 3_000,
+        }
+    }
+
+    /// Address of the pair of the tokens, if the init code hash is known.
+    pub fn pair_address(
+        &self,
+        token_a: Address,
+        token_b: Address,
+    ) -> Option<Address> {
+        let init_code_hash = self.init_code_hash?;
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        let salt = keccak256([token0.as_slice(), token1.as_slice()].concat());
+        Some(self.factory.create2(salt, init_code_hash))
+    }
+
+    /// Whether the pool is the pair of the tokens. Always true if the init
+    /// code hash is unknown.
+    pub fn is_pair(
+        &self,
+        pool: Address,
+        token_a: Address,
+        token_b: Address,
+    ) -> bool {
+        self.pair_address(token_a, token_b)
+            .is_none_or(|pair| pair == pool)
+    }
 }
 
 #[derive(Clone, Debug)]
-pub struct UniswapV2PoolInfo {
-    /// Address of the Uniswap V2 pool.
+pub struct V2PoolInfo {
+    /// Address of the V2 pool.
     pub v2_pool: Address,
+    /// Token of the pool paired with WETH.
+    pub token: Address,
     /// Whether the pool has weth as token0.
     pub is_weth_token0: bool,
+    /// Fork of Uniswap V2 the pool belongs to.
+    pub fork: V2Fork,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pair_address() {
+        let fork = V2Fork::uniswap_v2();
+        let weth = crate::discovery::WETH;
+        let token = address!("0x106552c11272420aad5d7e94f8acab9095a6c952");
+        let pair = address!("0xfee4800067bfc9dff564d116cba4d4b16ca7b7b3");
+
+        assert_eq!(
+            fork.pair_address(weth, token),
+            Some(pair)
+        );
+        assert_eq!(
+            fork.pair_address(token, weth),
+            Some(pair)
+        );
+        assert!(!fork.is_pair(Address::ZERO, token, weth));
+        assert!(V2Fork::sushiswap().is_pair(Address::ZERO, token, weth));
+    }
 }